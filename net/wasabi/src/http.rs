@@ -1,7 +1,5 @@
 extern crate alloc;
 
-use crate::http::alloc::string::ToString;
-use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::fmt::Write;
@@ -20,7 +18,14 @@ impl HttpClient {
         Self {}
     }
 
-    pub fn get(&self, host: String, port: u16, path: String) -> Result<HttpResponse, Error> {
+    /// Sends a GET request for `path` on `host`, which accepts the media types in `accept`.
+    pub fn get(
+        &self,
+        host: String,
+        port: u16,
+        path: String,
+        accept: &str,
+    ) -> Result<HttpResponse, Error> {
         let ips = match lookup_host(&host) {
             Ok(ips) => ips,
             Err(e) => {
@@ -54,7 +59,9 @@ impl HttpClient {
         request.push_str("Host: ");
         request.push_str(&host);
         request.push_str("\r\n");
-        request.push_str("Accept: text/html\r\n");
+        request.push_str("Accept: ");
+        request.push_str(accept);
+        request.push_str("\r\n");
         request.push_str("Connection: close\r\n");
         request.push_str("\r\n");
 
@@ -81,9 +88,6 @@ impl HttpClient {
             recieved.extend_from_slice(&buffer[..bytes_read]);
         }
 
-        match core::str::from_utf8(&recieved) {
-            Ok(response) => HttpResponse::new(response.to_string()),
-            Err(e) => Err(Error::Network(format!("Invalid recieved response: {}", e))),
-        }
+        HttpResponse::from_bytes(recieved)
     }
 }
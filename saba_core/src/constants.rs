@@ -1,28 +1,2 @@
-pub static WINDOW_WIDTH: i64 = 600;
-pub static WINDOW_HEIGHT: i64 = 400;
-pub static WINDOW_PADDING: i64 = 5;
-pub static WINDOW_INIT_X_POS: i64 = 30;
-pub static WINDOW_INIT_Y_POS: i64 = 50;
-
-pub static TITLE_BAR_HEIGHT: i64 = 24;
-pub static TOOLBAR_HEIGHT: i64 = 26;
-
-pub static CONTENT_AREA_WIDTH: i64 = WINDOW_WIDTH - WINDOW_PADDING * 2;
-pub static CONTENT_AREA_HEIGHT: i64 =
-    WINDOW_HEIGHT - WINDOW_PADDING * 2 - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT;
-
 pub static CHAR_WIDTH: i64 = 8;
 pub static CHAR_HEIGHT: i64 = 16;
-pub static CHAR_HEIGHT_WITH_PADDING: i64 = CHAR_HEIGHT + 4;
-
-pub static WHITE: u32 = 0xFFFFFF;
-pub static BLACK: u32 = 0x000000;
-pub static GRAY: u32 = 0x808080;
-pub static DARK_GRAY: u32 = 0x404040;
-pub static LIGHT_GRAY: u32 = 0xC0C0C0;
-
-pub static ADDRESS_BAR_HEIGHT: i64 = 24;
-pub static ADDRESS_BAR_PADDING: i64 = 5;
-pub static ADDRESS_BAR_WIDTH: i64 = WINDOW_WIDTH - ADDRESS_BAR_PADDING * 2;
-pub static ADDRESS_BAR_X_POS: i64 = ADDRESS_BAR_PADDING;
-pub static ADDRESS_BAR_Y_POS: i64 = WINDOW_PADDING + TITLE_BAR_HEIGHT + TOOLBAR_HEIGHT;
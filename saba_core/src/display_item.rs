@@ -1,47 +1,584 @@
-use crate::renderer::layout::computed_style::ComputedStyle;
+//! The display list, which is the output of painting. Each item is an explicit drawing primitive
+//! with resolved geometry and colors, so a backend draws the list by interpreting the items in
+//! order without looking at styles.
+
+use crate::renderer::css::value::round;
+use crate::renderer::image::Image;
+use crate::renderer::layout::computed_style::BorderStyle;
+use crate::renderer::layout::computed_style::Color;
+use crate::renderer::layout::computed_style::Font;
+use crate::renderer::layout::computed_style::Side;
+use crate::renderer::layout::computed_style::TextDecoration;
+use crate::renderer::layout::font_metrics::FontMetrics;
 use crate::renderer::layout::layout_object::LayoutPoint;
 use crate::renderer::layout::layout_object::LayoutSize;
+use crate::renderer::layout::transform::Transform;
+use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum DisplayItem {
+    /// A rectangle filled with a solid color.
     Rect {
-        style: ComputedStyle,
+        color: Color,
         layout_point: LayoutPoint,
         layout_size: LayoutSize,
     },
-    Text {
-        style: ComputedStyle,
+    /// https://www.w3.org/TR/css-backgrounds-3/#corner-shaping
+    /// A rectangle with rounded corners filled with a solid color.
+    RoundedRect {
+        color: Color,
+        radii: CornerRadii,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    },
+    /// https://www.w3.org/TR/css-backgrounds-3/#borders
+    /// Borders drawn inside the edges of the border box, whose outer corners are rounded by
+    /// `radii`.
+    Border {
+        sides: BorderSides,
+        radii: CornerRadii,
         layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    },
+    /// A run of text on a line. `layout_point` is the top-left corner of the first glyph.
+    Text {
         text: String,
+        font: Font,
+        color: Color,
+        decoration: TextDecoration,
+        layout_point: LayoutPoint,
+    },
+    /// https://www.w3.org/TR/css-counter-styles-3/#simple-symbolic
+    /// The symbol of a disc, circle or square list marker, drawn as a shape in the box instead of
+    /// as a glyph.
+    Bullet {
+        color: Color,
+        shape: BulletShape,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
     },
+    /// https://www.w3.org/TR/css-images-3/#default-sizing
+    /// A decoded image scaled to fill the rectangle.
     Img {
-        src: String,
-        style: ComputedStyle,
+        image: Rc<Image>,
         layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    },
+    /// https://www.w3.org/TR/css-backgrounds-3/#background-repeat
+    /// A background image repeated in both directions over the rectangle at its intrinsic size.
+    /// One of the tiles has its top-left corner at `origin`.
+    BackgroundImage {
+        image: Rc<Image>,
+        origin: LayoutPoint,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
     },
+    /// https://www.w3.org/TR/css-overflow-3/#overflow-properties
+    /// Clips the following items to the rectangle until the matching `PopClip`. Clips are nested,
+    /// and an item is clipped to the intersection of all clips around it.
+    PushClip {
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    },
+    PopClip,
+    /// https://www.w3.org/TR/css-color-4/#transparency
+    /// "Opacity can be thought of conceptually as a postprocessing operation. Conceptually, after
+    /// the element (including its descendants) is rendered into an RGBA offscreen image, the
+    /// opacity setting specifies how to blend the offscreen rendering into the current composite
+    /// rendering." The following items until the matching `PopOpacity` are such a group, and
+    /// nested groups multiply their opacities.
+    PushOpacity {
+        opacity: f64,
+    },
+    PopOpacity,
+    /// https://www.w3.org/TR/css-transforms-1/#transform-rendering
+    /// Transforms the following items until the matching `PopTransform` by `transform` about
+    /// `origin`. Nested transforms are applied from the innermost one.
+    PushTransform {
+        transform: Transform,
+        origin: LayoutPoint,
+    },
+    PopTransform,
+    /// A scrollbar of a scroll container. The thumb is drawn over the track.
+    Scrollbar {
+        track_point: LayoutPoint,
+        track_size: LayoutSize,
+        thumb_point: LayoutPoint,
+        thumb_size: LayoutSize,
+    },
+}
+
+/// https://www.w3.org/TR/css-counter-styles-3/#disc
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BulletShape {
+    /// "A filled circle".
+    Disc,
+    /// "A hollow circle".
+    Circle,
+    /// "A filled square".
+    Square,
+}
+
+/// One side of a border with its used width in px.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorderSide {
+    pub width: i64,
+    pub style: BorderStyle,
+    pub color: Color,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorderSides {
+    pub top: BorderSide,
+    pub right: BorderSide,
+    pub bottom: BorderSide,
+    pub left: BorderSide,
+}
+
+impl BorderSides {
+    pub fn side(&self, side: Side) -> &BorderSide {
+        match side {
+            Side::Top => &self.top,
+            Side::Right => &self.right,
+            Side::Bottom => &self.bottom,
+            Side::Left => &self.left,
+        }
+    }
+
+    pub fn side_mut(&mut self, side: Side) -> &mut BorderSide {
+        match side {
+            Side::Top => &mut self.top,
+            Side::Right => &mut self.right,
+            Side::Bottom => &mut self.bottom,
+            Side::Left => &mut self.left,
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-backgrounds-3/#corner-shaping
+/// The used horizontal and vertical radii of the corners of a box in px. A corner with a zero
+/// radius is square.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CornerRadii {
+    pub top_left: (i64, i64),
+    pub top_right: (i64, i64),
+    pub bottom_right: (i64, i64),
+    pub bottom_left: (i64, i64),
+}
+
+impl CornerRadii {
+    pub fn zero() -> Self {
+        Self {
+            top_left: (0, 0),
+            top_right: (0, 0),
+            bottom_right: (0, 0),
+            bottom_left: (0, 0),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        [
+            self.top_left,
+            self.top_right,
+            self.bottom_right,
+            self.bottom_left,
+        ]
+        .iter()
+        .all(|(h, v)| *h == 0 || *v == 0)
+    }
+
+    /// https://www.w3.org/TR/css-backgrounds-3/#corner-overlap
+    /// "Let f = min(Li/Si), where i ∈ {top, right, bottom, left}, Si is the sum of the two
+    /// corresponding radii of the corners on side i, and Ltop = Lbottom = the width of the box, and
+    /// Lleft = Lright = the height of the box. If f < 1, then all corner radii are reduced by
+    /// multiplying them by f."
+    pub fn fit(&self, size: LayoutSize) -> Self {
+        let sums = [
+            (size.width(), self.top_left.0 + self.top_right.0),
+            (size.height(), self.top_right.1 + self.bottom_right.1),
+            (size.width(), self.bottom_right.0 + self.bottom_left.0),
+            (size.height(), self.bottom_left.1 + self.top_left.1),
+        ];
+        let f = sums
+            .iter()
+            .filter(|(_, sum)| *sum > 0)
+            .map(|(length, sum)| *length as f64 / *sum as f64)
+            .fold(1.0, f64::min);
+        if f >= 1.0 {
+            return *self;
+        }
+        let scale = |(h, v): (i64, i64)| (round(h as f64 * f), round(v as f64 * f));
+        Self {
+            top_left: scale(self.top_left),
+            top_right: scale(self.top_right),
+            bottom_right: scale(self.bottom_right),
+            bottom_left: scale(self.bottom_left),
+        }
+    }
+
+    /// Returns the horizontal inset of the curved edge from each side of a box of `size` in the
+    /// row at `y` px from the top. It's 0 outside the corners.
+    pub fn insets_at(&self, y: i64, size: LayoutSize) -> (i64, i64) {
+        let inset = |(rx, ry): (i64, i64), dy: i64| {
+            if rx <= 0 || ry <= 0 || dy >= ry {
+                return 0;
+            }
+            // The distance from the center of the ellipse to the middle of the row, in doubled
+            // coordinates so that it can be between pixels.
+            let d = 2 * (ry - dy) - 1;
+            // Find the largest half chord x with (x / rx)^2 + (d / 2ry)^2 <= 1.
+            let mut x = rx;
+            while x > 0 && 4 * x * x * ry * ry + d * d * rx * rx > 4 * rx * rx * ry * ry {
+                x -= 1;
+            }
+            rx - x
+        };
+        let bottom = size.height() - 1 - y;
+        (
+            inset(self.top_left, y).max(inset(self.bottom_left, bottom)),
+            inset(self.top_right, y).max(inset(self.bottom_right, bottom)),
+        )
+    }
 }
 
 impl DisplayItem {
     pub fn is_rect(&self) -> bool {
         matches!(
             self,
-            DisplayItem::Rect {
-                style: _,
-                layout_point: _,
-                layout_size: _,
-            }
+            DisplayItem::Rect { .. } | DisplayItem::RoundedRect { .. }
         )
     }
 
+    /// Returns the item moved by (`dx`, `dy`).
+    pub fn translated(&self, dx: i64, dy: i64) -> Self {
+        let translate = |point: &LayoutPoint| LayoutPoint::new(point.x() + dx, point.y() + dy);
+        let mut item = self.clone();
+        match &mut item {
+            DisplayItem::Rect { layout_point, .. }
+            | DisplayItem::RoundedRect { layout_point, .. }
+            | DisplayItem::Border { layout_point, .. }
+            | DisplayItem::Text { layout_point, .. }
+            | DisplayItem::Bullet { layout_point, .. }
+            | DisplayItem::Img { layout_point, .. }
+            | DisplayItem::PushClip { layout_point, .. } => *layout_point = translate(layout_point),
+            DisplayItem::BackgroundImage {
+                origin,
+                layout_point,
+                ..
+            } => {
+                *origin = translate(origin);
+                *layout_point = translate(layout_point);
+            }
+            DisplayItem::PushTransform { origin, .. } => *origin = translate(origin),
+            DisplayItem::PopClip | DisplayItem::PushOpacity { .. } => {}
+            DisplayItem::PopOpacity | DisplayItem::PopTransform => {}
+            DisplayItem::Scrollbar {
+                track_point,
+                thumb_point,
+                ..
+            } => {
+                *track_point = translate(track_point);
+                *thumb_point = translate(thumb_point);
+            }
+        }
+        item
+    }
+
     pub fn is_text(&self) -> bool {
-        matches!(
-            self,
+        matches!(self, DisplayItem::Text { .. })
+    }
+}
+
+/// https://source.chromium.org/chromium/chromium/src/+/main:third_party/blink/renderer/platform/graphics/paint/raster_invalidator.h
+/// Returns the rectangles where the display lists `old` and `new` look different, which have to
+/// be drawn again when `old` is replaced by `new`. The lists are compared item by item after
+/// their common beginning and end, and the visual bounds of the different items in both lists
+/// are damaged. A push item covers its group, so a changed clip, opacity or transform damages
+/// all items in it. Text can't be drawn partially, so the rectangles are grown to contain the
+/// text that they cut, and the overlapping ones are merged.
+pub fn damage(
+    old: &[DisplayItem],
+    new: &[DisplayItem],
+    font_metrics: &dyn FontMetrics,
+) -> Vec<(LayoutPoint, LayoutSize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    let old_bounds = visual_bounds(old, font_metrics);
+    let new_bounds = visual_bounds(new, font_metrics);
+
+    let mut damage = Vec::new();
+    for i in prefix..old_end.max(new_end) {
+        let (a, b) = (old[..old_end].get(i), new[..new_end].get(i));
+        if a == b {
+            continue;
+        }
+        if a.is_some() {
+            damage.extend(old_bounds[i]);
+        }
+        if b.is_some() {
+            damage.extend(new_bounds[i]);
+        }
+    }
+
+    let texts: Vec<(LayoutPoint, LayoutSize)> = new
+        .iter()
+        .zip(&new_bounds)
+        .filter_map(|(item, bounds)| bounds.filter(|_| item.is_text()))
+        .collect();
+    loop {
+        let mut grown = false;
+        for rect in damage.iter_mut() {
+            for text in &texts {
+                if intersection(rect, text).is_some() && union(rect, text) != *rect {
+                    *rect = union(rect, text);
+                    grown = true;
+                }
+            }
+        }
+        let merged = merge(&damage);
+        if !grown && merged.len() == damage.len() {
+            return damage;
+        }
+        damage = merged;
+    }
+}
+
+/// Returns the bounds of each item in `items` that are painted, after the transforms and the
+/// clips around it. The bounds of a push item are the ones of its group, and a pop item has no
+/// bounds.
+fn visual_bounds(
+    items: &[DisplayItem],
+    font_metrics: &dyn FontMetrics,
+) -> Vec<Option<(LayoutPoint, LayoutSize)>> {
+    let mut bounds = vec![None; items.len()];
+    let mut clips: Vec<Option<(LayoutPoint, LayoutSize)>> = Vec::new();
+    let mut transforms: Vec<Transform> = Vec::new();
+    // The indices of the push items whose groups are open.
+    let mut groups: Vec<usize> = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let transform = transforms
+            .last()
+            .copied()
+            .unwrap_or_else(Transform::identity);
+        let clip = |rect: (LayoutPoint, LayoutSize)| match clips.last() {
+            Some(Some(c)) => intersection(c, &rect),
+            Some(None) => None,
+            None => Some(rect),
+        };
+        let (point, size) = match item {
+            DisplayItem::Rect {
+                layout_point,
+                layout_size,
+                ..
+            }
+            | DisplayItem::RoundedRect {
+                layout_point,
+                layout_size,
+                ..
+            }
+            | DisplayItem::Border {
+                layout_point,
+                layout_size,
+                ..
+            }
+            | DisplayItem::Bullet {
+                layout_point,
+                layout_size,
+                ..
+            }
+            | DisplayItem::Img {
+                layout_point,
+                layout_size,
+                ..
+            }
+            | DisplayItem::BackgroundImage {
+                layout_point,
+                layout_size,
+                ..
+            } => (*layout_point, *layout_size),
+            DisplayItem::Scrollbar {
+                track_point,
+                track_size,
+                ..
+            } => (*track_point, *track_size),
+            // Bold text is drawn twice with an offset of 1px.
             DisplayItem::Text {
-                text: _,
-                style: _,
-                layout_point: _,
+                text,
+                font,
+                layout_point,
+                ..
+            } => (
+                *layout_point,
+                LayoutSize::new(
+                    font_metrics.text_width(text, font) + 1,
+                    font_metrics.ascent(font) + font_metrics.descent(font),
+                ),
+            ),
+            DisplayItem::PushClip {
+                layout_point,
+                layout_size,
+            } => {
+                let rect = clip(transform.bounding_box(*layout_point, *layout_size));
+                clips.push(rect);
+                groups.push(i);
+                continue;
+            }
+            DisplayItem::PushOpacity { .. } => {
+                groups.push(i);
+                continue;
+            }
+            DisplayItem::PushTransform {
+                transform: t,
+                origin,
+            } => {
+                transforms.push(transform.multiply(&t.about(*origin)));
+                groups.push(i);
+                continue;
             }
+            DisplayItem::PopClip | DisplayItem::PopOpacity | DisplayItem::PopTransform => {
+                match item {
+                    DisplayItem::PopClip => {
+                        clips.pop();
+                    }
+                    DisplayItem::PopTransform => {
+                        transforms.pop();
+                    }
+                    _ => {}
+                }
+                groups.pop();
+                continue;
+            }
+        };
+        let rect = clip(transform.bounding_box(point, size));
+        bounds[i] = rect;
+        if let Some(rect) = rect {
+            for &g in &groups {
+                bounds[g] = Some(match &bounds[g] {
+                    Some(b) => union(b, &rect),
+                    None => rect,
+                });
+            }
+        }
+    }
+    bounds
+}
+
+/// Merges the rectangles that overlap into the rectangles around them.
+fn merge(rects: &[(LayoutPoint, LayoutSize)]) -> Vec<(LayoutPoint, LayoutSize)> {
+    let mut merged: Vec<(LayoutPoint, LayoutSize)> = Vec::new();
+    for rect in rects {
+        let mut rect = *rect;
+        while let Some(i) = merged.iter().position(|m| intersection(m, &rect).is_some()) {
+            rect = union(&merged.swap_remove(i), &rect);
+        }
+        merged.push(rect);
+    }
+    merged
+}
+
+fn intersection(
+    a: &(LayoutPoint, LayoutSize),
+    b: &(LayoutPoint, LayoutSize),
+) -> Option<(LayoutPoint, LayoutSize)> {
+    let left = a.0.x().max(b.0.x());
+    let top = a.0.y().max(b.0.y());
+    let right = (a.0.x() + a.1.width()).min(b.0.x() + b.1.width());
+    let bottom = (a.0.y() + a.1.height()).min(b.0.y() + b.1.height());
+    (left < right && top < bottom).then(|| {
+        (
+            LayoutPoint::new(left, top),
+            LayoutSize::new(right - left, bottom - top),
         )
+    })
+}
+
+fn union(
+    a: &(LayoutPoint, LayoutSize),
+    b: &(LayoutPoint, LayoutSize),
+) -> (LayoutPoint, LayoutSize) {
+    let left = a.0.x().min(b.0.x());
+    let top = a.0.y().min(b.0.y());
+    let right = (a.0.x() + a.1.width()).max(b.0.x() + b.1.width());
+    let bottom = (a.0.y() + a.1.height()).max(b.0.y() + b.1.height());
+    (
+        LayoutPoint::new(left, top),
+        LayoutSize::new(right - left, bottom - top),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::layout::computed_style::FontStyle;
+    use crate::renderer::layout::font_metrics::MonospaceFontMetrics;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_corner_radii() {
+        let radii = CornerRadii {
+            top_left: (30, 10),
+            top_right: (30, 10),
+            bottom_right: (0, 0),
+            bottom_left: (4, 4),
+        };
+        // The top corners overlap on a 40px wide box, so all radii are scaled by 2/3.
+        let fitted = radii.fit(LayoutSize::new(40, 100));
+        assert_eq!((20, 7), fitted.top_left);
+        assert_eq!((3, 3), fitted.bottom_left);
+
+        let size = LayoutSize::new(40, 100);
+        assert_eq!((13, 13), fitted.insets_at(0, size));
+        assert_eq!((0, 0), fitted.insets_at(50, size));
+        assert_eq!((2, 0), fitted.insets_at(99, size));
+    }
+
+    #[test]
+    fn test_damage() {
+        let rect = |color: &str, x, y| DisplayItem::Rect {
+            color: Color::from_name(color).unwrap(),
+            layout_point: LayoutPoint::new(x, y),
+            layout_size: LayoutSize::new(20, 10),
+        };
+        let display_list = |red: DisplayItem, blue: DisplayItem| {
+            vec![
+                DisplayItem::PushClip {
+                    layout_point: LayoutPoint::new(0, 0),
+                    layout_size: LayoutSize::new(100, 100),
+                },
+                red,
+                DisplayItem::Text {
+                    text: "ab".to_string(),
+                    font: Font::new(Vec::new(), 16, 400, FontStyle::Normal),
+                    color: Color::from_name("black").unwrap(),
+                    decoration: TextDecoration::None,
+                    layout_point: LayoutPoint::new(0, 50),
+                },
+                blue,
+                DisplayItem::PopClip,
+            ]
+        };
+        let old = display_list(rect("red", 10, 10), rect("blue", 60, 50));
+        assert!(damage(&old, &old, &MonospaceFontMetrics).is_empty());
+
+        // The old and new places of the moved rectangles are damaged. The overlapping ones are
+        // merged, and the one on the text is grown to contain it.
+        let new = display_list(rect("green", 15, 10), rect("blue", 10, 52));
+        assert_eq!(
+            vec![
+                (LayoutPoint::new(10, 10), LayoutSize::new(25, 10)),
+                (LayoutPoint::new(60, 50), LayoutSize::new(20, 10)),
+                (LayoutPoint::new(0, 50), LayoutSize::new(30, 16)),
+            ],
+            damage(&old, &new, &MonospaceFontMetrics)
+        );
     }
 }
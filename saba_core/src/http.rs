@@ -4,6 +4,13 @@ use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 
+/// https://fetch.spec.whatwg.org/#http-accept
+/// The Accept header of a request for a document.
+pub const DOCUMENT_ACCEPT: &str = "text/html";
+
+/// The Accept header of a request for an image, which lists the formats that can be decoded.
+pub const IMAGE_ACCEPT: &str = "image/png,image/gif,image/bmp,image/jpeg,*/*;q=0.8";
+
 #[derive(Debug, Clone)]
 pub struct HttpResponse {
     pub version: String,
@@ -11,6 +18,7 @@ pub struct HttpResponse {
     pub reason: String,
     pub headers: Vec<Header>,
     pub body: String,
+    pub body_bytes: Vec<u8>,
 }
 
 impl HttpResponse {
@@ -34,6 +42,11 @@ impl HttpResponse {
         self.body.clone()
     }
 
+    /// The body as it's received, which may not be text, e.g. an image.
+    pub fn body_bytes(&self) -> Vec<u8> {
+        self.body_bytes.clone()
+    }
+
     pub fn header_value(&self, name: &str) -> Result<String, Error> {
         for h in &self.headers {
             if h.name == name {
@@ -77,8 +90,41 @@ impl HttpResponse {
             reason: statuses[2].to_string(),
             headers,
             body: body.to_string(),
+            body_bytes: body.as_bytes().to_vec(),
         })
     }
+
+    /// Parses a response whose body may be binary. The status line and the headers are text, and
+    /// the body is kept as it is after the empty line that ends the headers.
+    pub fn from_bytes(raw_response: Vec<u8>) -> Result<Self, Error> {
+        let header_end = raw_response
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .map(|i| (i, i + 4))
+            .or_else(|| {
+                raw_response
+                    .windows(2)
+                    .position(|w| w == b"\n\n")
+                    .map(|i| (i, i + 2))
+            });
+        let (head, body) = match header_end {
+            Some((end, body_start)) => (&raw_response[..end], &raw_response[body_start..]),
+            None => (&raw_response[..], &[][..]),
+        };
+        let head = match core::str::from_utf8(head) {
+            Ok(head) => head.replace("\r\n", "\n"),
+            Err(e) => {
+                return Err(Error::UnexpectedInput(format!(
+                    "Invalid http response header: {}",
+                    e
+                )))
+            }
+        };
+        let mut response = Self::new(format!("{}\n\n", head))?;
+        response.body = String::from_utf8_lossy(body).to_string();
+        response.body_bytes = body.to_vec();
+        Ok(response)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -97,6 +143,7 @@ impl Header {
 
 mod tests {
     use super::*;
+    use alloc::vec;
     #[test]
     fn test_status_line_only() {
         let raw = "HTTP/1.1 200 OK\n\n".to_string();
@@ -139,6 +186,21 @@ mod tests {
         assert_eq!(response.body(), "body message".to_string());
     }
     #[test]
+    fn test_binary_body() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\r\n".to_vec();
+        raw.extend_from_slice(&[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0xff, 0x00]);
+        let response = HttpResponse::from_bytes(raw).expect("failed to parse http response");
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(
+            response.header_value("Content-Type"),
+            Ok("image/png".to_string())
+        );
+        assert_eq!(
+            response.body_bytes(),
+            vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0xff, 0x00]
+        );
+    }
+    #[test]
     fn test_invalid() {
         let raw = "HTTP/1.1 200 OK".to_string();
         assert!(HttpResponse::new(raw).is_err());
@@ -1,5 +1,9 @@
+use crate::renderer::css::media::Device;
+use crate::renderer::css::media::MediaQueryList;
+use crate::renderer::css::media::MediaQueryParser;
 use crate::renderer::css::token::CssToken;
 use crate::renderer::css::token::CssTokenizer;
+use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
@@ -31,7 +35,16 @@ use core::iter::Peekable;
 #[derive(Debug, Clone, PartialEq)]
 pub struct StyleSheet {
     /// https://drafts.csswg.org/cssom/#dom-cssstylesheet-cssrules
-    pub rules: Vec<QualifiedRule>,
+    pub rules: Vec<Rule>,
+    pub origin: CascadeOrigin,
+}
+
+/// https://www.w3.org/TR/css-cascade-4/#cascading-origins
+/// User style sheets are not supported.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CascadeOrigin {
+    UserAgent,
+    Author,
 }
 
 impl Default for StyleSheet {
@@ -42,21 +55,69 @@ impl Default for StyleSheet {
 
 impl StyleSheet {
     pub fn new() -> Self {
-        Self { rules: Vec::new() }
+        Self {
+            rules: Vec::new(),
+            origin: CascadeOrigin::Author,
+        }
     }
 
-    pub fn set_rules(&mut self, rules: Vec<QualifiedRule>) {
+    pub fn set_rules(&mut self, rules: Vec<Rule>) {
         self.rules = rules;
     }
+
+    pub fn set_origin(&mut self, origin: CascadeOrigin) {
+        self.origin = origin;
+    }
+
+    /// Returns the style rules that apply to `device` in the order they appear in the style sheet.
+    /// Rules nested in @media are included only when the media query list matches, and rules in
+    /// other at-rules are never included.
+    pub fn style_rules(&self, device: &Device) -> Vec<&QualifiedRule> {
+        let mut result = Vec::new();
+        collect_style_rules(&self.rules, device, &mut result);
+        result
+    }
+}
+
+fn collect_style_rules<'a>(
+    rules: &'a [Rule],
+    device: &Device,
+    result: &mut Vec<&'a QualifiedRule>,
+) {
+    for rule in rules {
+        match rule {
+            Rule::QualifiedRule(qualified_rule) => result.push(qualified_rule),
+            Rule::AtRule(at_rule) => {
+                if let Some(media_query_list) = at_rule.media_query_list() {
+                    if media_query_list.matches(device) {
+                        collect_style_rules(&at_rule.rules, device, result);
+                    }
+                }
+            }
+        }
+    }
 }
 
+/// https://www.w3.org/TR/css-syntax-3/#css-rule
 #[derive(Debug, Clone, PartialEq)]
-// TODO: implement it properly
+pub enum Rule {
+    QualifiedRule(QualifiedRule),
+    AtRule(AtRule),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// https://www.w3.org/TR/css-syntax-3/#at-rule
+/// https://www.w3.org/TR/cssom-1/#cssgroupingrule
 pub struct AtRule {
-    // TODO: support list of media query
+    /// The name of the at-rule without "@", e.g. "media".
+    pub name: String,
     /// https://www.w3.org/TR/mediaqueries-5/#typedef-media-query-list
-    pub prelude: String,
-    pub rule: QualifiedRule,
+    /// For @media, the prelude is parsed as a <media-query-list>.
+    pub prelude: Vec<ComponentValue>,
+    /// https://www.w3.org/TR/css-conditional-3/#contents-of
+    /// The block of @media is parsed as a list of rules. The block of an unsupported at-rule is
+    /// discarded and this list stays empty.
+    pub rules: Vec<Rule>,
 }
 
 impl Default for AtRule {
@@ -65,13 +126,33 @@ impl Default for AtRule {
     }
 }
 
-// TODO: support list of media query
 impl AtRule {
     pub fn new() -> Self {
         Self {
-            prelude: String::new(),
-            rule: QualifiedRule::new(),
+            name: String::new(),
+            prelude: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    pub fn set_prelude(&mut self, prelude: Vec<ComponentValue>) {
+        self.prelude = prelude;
+    }
+
+    pub fn set_rules(&mut self, rules: Vec<Rule>) {
+        self.rules = rules;
+    }
+
+    /// Returns the media query list of a @media rule, or None for other at-rules.
+    pub fn media_query_list(&self) -> Option<MediaQueryList> {
+        if !self.name.eq_ignore_ascii_case("media") {
+            return None;
         }
+        Some(MediaQueryParser::new(self.prelude.clone()).parse_media_query_list())
     }
 }
 
@@ -122,17 +203,61 @@ pub enum Selector {
     ClassSelector(String),
     /// https://www.w3.org/TR/selectors-4/#id-selectors
     IdSelector(String),
+    /// https://www.w3.org/TR/selectors-4/#pseudo-elements
+    /// A pseudo-element of the elements that the selector matches. It doesn't match the elements
+    /// themselves.
+    PseudoElement(Box<Selector>, PseudoElement),
     /// This is an unofficial selector.
     UnknownSelector,
 }
 
+/// https://www.w3.org/TR/css-pseudo-4/
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PseudoElement {
+    /// https://www.w3.org/TR/css-pseudo-4/#marker-pseudo
+    Marker,
+    /// https://www.w3.org/TR/css-pseudo-4/#generated-content
+    Before,
+    After,
+    /// https://www.w3.org/TR/css-pseudo-4/#first-line-pseudo
+    FirstLine,
+    /// https://www.w3.org/TR/css-pseudo-4/#first-letter-pseudo
+    FirstLetter,
+}
+
+impl PseudoElement {
+    pub fn from_str_pseudo_element(s: &str) -> Option<Self> {
+        match s {
+            "marker" => Some(Self::Marker),
+            "before" => Some(Self::Before),
+            "after" => Some(Self::After),
+            "first-line" => Some(Self::FirstLine),
+            "first-letter" => Some(Self::FirstLetter),
+            _ => None,
+        }
+    }
+
+    /// https://www.w3.org/TR/selectors-4/#pseudo-element-syntax
+    /// "For compatibility with existing style sheets, user agents must also accept the previous
+    /// one-colon notation for pseudo-elements introduced in CSS levels 1 and 2 (namely, :first-line,
+    /// :first-letter, :before and :after)."
+    fn is_legacy(&self) -> bool {
+        matches!(
+            self,
+            Self::Before | Self::After | Self::FirstLine | Self::FirstLetter
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 /// https://www.w3.org/TR/css-syntax-3/#declaration
 /// https://www.w3.org/TR/cssom-1/#the-cssstyledeclaration-interface
 /// https://developer.mozilla.org/en-US/docs/Web/API/CSSStyleDeclaration
 pub struct Declaration {
     pub property: String,
-    pub value: ComponentValue,
+    /// https://www.w3.org/TR/css-syntax-3/#declaration
+    /// "a value consisting of a list of component values"
+    pub value: Vec<ComponentValue>,
 }
 
 impl Default for Declaration {
@@ -145,7 +270,7 @@ impl Declaration {
     pub fn new() -> Self {
         Self {
             property: String::new(),
-            value: ComponentValue::Ident(String::new()),
+            value: Vec::new(),
         }
     }
 
@@ -153,7 +278,7 @@ impl Declaration {
         self.property = property;
     }
 
-    pub fn set_value(&mut self, value: ComponentValue) {
+    pub fn set_value(&mut self, value: Vec<ComponentValue>) {
         self.value = value;
     }
 }
@@ -204,21 +329,25 @@ impl CssParser {
 
         match token {
             // TODO: support tag.class and tag#id
-            CssToken::HashToken(value) => Selector::IdSelector(value[1..].to_string()),
+            CssToken::HashToken(value) => {
+                self.consume_pseudo_element(Selector::IdSelector(value[1..].to_string()))
+            }
             CssToken::Delim(delim) => {
                 if delim == '.' {
-                    return Selector::ClassSelector(self.consume_ident());
+                    let selector = Selector::ClassSelector(self.consume_ident());
+                    return self.consume_pseudo_element(selector);
                 }
                 panic!("Parse error: {:?} is an unexpected token.", token);
             }
             CssToken::Ident(ident) => {
+                let selector = self.consume_pseudo_element(Selector::TypeSelector(ident));
                 // TODO: fix this. Skip pseudo-classes such as :link and :visited
                 if self.t.peek() == Some(&CssToken::Colon) {
                     while self.t.peek() != Some(&CssToken::OpenCurly) {
                         self.t.next();
                     }
                 }
-                Selector::TypeSelector(ident.to_string())
+                selector
             }
             CssToken::AtKeyword(_keyword) => {
                 // skip until "{" comes
@@ -234,6 +363,40 @@ impl CssParser {
         }
     }
 
+    /// https://www.w3.org/TR/selectors-4/#pseudo-elements
+    /// "A pseudo-element is represented by two colons (::) followed by the name of the
+    /// pseudo-element". An unknown pseudo-element makes the selector invalid, so it matches
+    /// nothing. The legacy one-colon notation is accepted for the CSS 2 pseudo-elements.
+    fn consume_pseudo_element(&mut self, selector: Selector) -> Selector {
+        let mut lookahead = self.t.clone();
+        if lookahead.next() != Some(CssToken::Colon) {
+            return selector;
+        }
+        match lookahead.next() {
+            Some(CssToken::Colon) => {}
+            Some(CssToken::Ident(name)) => {
+                return match PseudoElement::from_str_pseudo_element(&name) {
+                    Some(pseudo_element) if pseudo_element.is_legacy() => {
+                        self.t = lookahead;
+                        Selector::PseudoElement(Box::new(selector), pseudo_element)
+                    }
+                    // A pseudo-class, which is left to the caller.
+                    _ => selector,
+                };
+            }
+            _ => return selector,
+        }
+        let name = match lookahead.next() {
+            Some(CssToken::Ident(name)) => name,
+            _ => return selector,
+        };
+        self.t = lookahead;
+        match PseudoElement::from_str_pseudo_element(&name) {
+            Some(pseudo_element) => Selector::PseudoElement(Box::new(selector), pseudo_element),
+            None => Selector::UnknownSelector,
+        }
+    }
+
     /// https://www.w3.org/TR/css-syntax-3/#consume-a-declaration
     fn consume_declaration(&mut self) -> Option<Declaration> {
         // Create a new declaration with its name set to the value of the current input token.
@@ -251,8 +414,16 @@ impl CssParser {
 
         // "4. As long as the next input token is anything other than an <EOF-token>, consume a
         // component value and append it to the declaration’s value."
-        // TODO: support multiple values in one declaration.
-        declaration.set_value(self.consume_component_value());
+        // The end of a declaration is ";" or "}" of the enclosing block. "}" is consumed by the
+        // caller.
+        let mut value = Vec::new();
+        while let Some(token) = self.t.peek() {
+            if *token == CssToken::SemiColon || *token == CssToken::CloseCurly {
+                break;
+            }
+            value.push(self.consume_component_value());
+        }
+        declaration.set_value(value);
 
         Some(declaration)
     }
@@ -292,24 +463,58 @@ impl CssParser {
         }
     }
 
+    /// https://www.w3.org/TR/css-syntax-3/#consume-simple-block
+    /// Consumes tokens until the matching "}" to discard the block of an unsupported at-rule.
+    fn skip_simple_block(&mut self) {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.t.next() {
+                Some(CssToken::OpenCurly) => depth += 1,
+                Some(CssToken::CloseCurly) => depth -= 1,
+                Some(_) => {}
+                None => return,
+            }
+        }
+    }
+
     /// https://www.w3.org/TR/css-syntax-3/#consume-at-rule
     fn consume_at_rule(&mut self) -> Option<AtRule> {
-        let rule = AtRule::new();
+        let mut rule = AtRule::new();
+
+        // "Consume the next input token. Create a new at-rule with its name set to the value of
+        // the current input token"
+        match self.t.next()? {
+            CssToken::AtKeyword(name) => rule.set_name(name),
+            _ => return None,
+        }
 
+        let mut prelude = Vec::new();
         loop {
             let token = self.t.next()?;
 
             match token {
-                CssToken::OpenCurly => {
-                    //TODO: set rule to AtRule.
-                    let _qualified_rule = self.consume_qualified_rule();
-                    // consume the close curly for a AtRule block
-                    assert_eq!(self.t.next(), Some(CssToken::CloseCurly));
+                // <semicolon-token>
+                // "Return the at-rule."
+                CssToken::SemiColon => {
+                    rule.set_prelude(prelude);
                     return Some(rule);
                 }
-                _ => {
-                    // TODO: set prelude to AtRule
+                // <{-token>
+                // "Consume a simple block and assign it to the at-rule’s block. Return the at-rule."
+                CssToken::OpenCurly => {
+                    rule.set_prelude(prelude);
+                    if rule.name.eq_ignore_ascii_case("media") {
+                        // https://www.w3.org/TR/css-conditional-3/#contents-of
+                        rule.set_rules(self.consume_list_of_rules(false));
+                    } else {
+                        self.skip_simple_block();
+                    }
+                    return Some(rule);
                 }
+                // anything else
+                // "Reconsume the current input token. Consume a component value. Append the
+                // returned value to the at-rule’s prelude."
+                _ => prelude.push(token),
             }
         }
     }
@@ -347,7 +552,8 @@ impl CssParser {
     }
 
     /// https://www.w3.org/TR/css-syntax-3/#consume-a-list-of-rules
-    fn consume_list_of_rules(&mut self) -> Vec<QualifiedRule> {
+    /// When `top_level` is false, the list is the content of a block and it ends with "}".
+    fn consume_list_of_rules(&mut self, top_level: bool) -> Vec<Rule> {
         // "Create an initially empty list of rules."
         let mut rules = Vec::new();
 
@@ -357,12 +563,17 @@ impl CssParser {
                 None => return rules,
             };
             match token {
+                CssToken::CloseCurly if !top_level => {
+                    assert_eq!(self.t.next(), Some(CssToken::CloseCurly));
+                    return rules;
+                }
                 // <at-keyword-token>
                 // "Reconsume the current input token. Consume an at-rule, and append the returned value
                 // to the list of rules."
                 CssToken::AtKeyword(_keyword) => {
-                    let _rule = self.consume_at_rule();
-                    // TODO: we ignore media query for now. implement it properly.
+                    if let Some(rule) = self.consume_at_rule() {
+                        rules.push(Rule::AtRule(rule));
+                    }
                 }
                 _ => {
                     // anything else
@@ -370,7 +581,7 @@ impl CssParser {
                     // returned, append it to the list of rules."
                     let rule = self.consume_qualified_rule();
                     match rule {
-                        Some(r) => rules.push(r),
+                        Some(r) => rules.push(Rule::QualifiedRule(r)),
                         None => return rules,
                     }
                 }
@@ -386,7 +597,7 @@ impl CssParser {
         // 2. Consume a list of rules from the stream of tokens, with the top-level flag set. Let
         // the return value be rules.
         // 3. Assign rules to the stylesheet’s value.
-        sheet.set_rules(self.consume_list_of_rules());
+        sheet.set_rules(self.consume_list_of_rules(true));
 
         // 4. Return the stylesheet.
         sheet
@@ -417,10 +628,10 @@ mod tests {
         rule.set_selector(Selector::TypeSelector("p".to_string()));
         let mut declaration = Declaration::default();
         declaration.set_property("color".to_string());
-        declaration.set_value(ComponentValue::Ident("red".to_string()));
+        declaration.set_value(vec![ComponentValue::Ident("red".to_string())]);
         rule.set_declarations(vec![declaration]);
 
-        let expected = [rule];
+        let expected = [Rule::QualifiedRule(rule)];
         assert_eq!(cssom.rules.len(), expected.len());
 
         let mut i = 0;
@@ -440,10 +651,10 @@ mod tests {
         rule.set_selector(Selector::IdSelector("id".to_string()));
         let mut declaration = Declaration::default();
         declaration.set_property("color".to_string());
-        declaration.set_value(ComponentValue::Ident("red".to_string()));
+        declaration.set_value(vec![ComponentValue::Ident("red".to_string())]);
         rule.set_declarations(vec![declaration]);
 
-        let expected = [rule];
+        let expected = [Rule::QualifiedRule(rule)];
         assert_eq!(cssom.rules.len(), expected.len());
 
         let mut i = 0;
@@ -463,10 +674,10 @@ mod tests {
         rule.set_selector(Selector::ClassSelector("class".to_string()));
         let mut declaration = Declaration::default();
         declaration.set_property("color".to_string());
-        declaration.set_value(ComponentValue::Ident("red".to_string()));
+        declaration.set_value(vec![ComponentValue::Ident("red".to_string())]);
         rule.set_declarations(vec![declaration]);
 
-        let expected = [rule];
+        let expected = [Rule::QualifiedRule(rule)];
         assert_eq!(cssom.rules.len(), expected.len());
 
         let mut i = 0;
@@ -486,20 +697,20 @@ mod tests {
         rule1.set_selector(Selector::TypeSelector("p".to_string()));
         let mut declaration1 = Declaration::default();
         declaration1.set_property("content".to_string());
-        declaration1.set_value(ComponentValue::StringToken("Hey".to_string()));
+        declaration1.set_value(vec![ComponentValue::StringToken("Hey".to_string())]);
         rule1.set_declarations(vec![declaration1]);
 
         let mut rule2 = QualifiedRule::default();
         rule2.set_selector(Selector::TypeSelector("h1".to_string()));
         let mut declaration2 = Declaration::default();
         declaration2.set_property("font-size".to_string());
-        declaration2.set_value(ComponentValue::Number(40.0));
+        declaration2.set_value(vec![ComponentValue::Number(40.0)]);
         let mut declaration3 = Declaration::default();
         declaration3.set_property("color".to_string());
-        declaration3.set_value(ComponentValue::Ident("blue".to_string()));
+        declaration3.set_value(vec![ComponentValue::Ident("blue".to_string())]);
         rule2.set_declarations(vec![declaration2, declaration3]);
 
-        let expected = [rule1, rule2];
+        let expected = [Rule::QualifiedRule(rule1), Rule::QualifiedRule(rule2)];
         assert_eq!(cssom.rules.len(), expected.len());
 
         let mut i = 0;
@@ -508,4 +719,142 @@ mod tests {
             i += 1;
         }
     }
+
+    #[test]
+    fn test_media_rule() {
+        let style =
+            "@media screen and (max-width: 600px) { p { color: red; } } h1 { color: blue; }"
+                .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let mut rule1 = QualifiedRule::default();
+        rule1.set_selector(Selector::TypeSelector("p".to_string()));
+        let mut declaration1 = Declaration::default();
+        declaration1.set_property("color".to_string());
+        declaration1.set_value(vec![ComponentValue::Ident("red".to_string())]);
+        rule1.set_declarations(vec![declaration1]);
+
+        let mut at_rule = AtRule::default();
+        at_rule.set_name("media".to_string());
+        at_rule.set_prelude(vec![
+            ComponentValue::Ident("screen".to_string()),
+            ComponentValue::Ident("and".to_string()),
+            ComponentValue::OpenParenthesis,
+            ComponentValue::Ident("max-width".to_string()),
+            ComponentValue::Colon,
+            ComponentValue::Dimension(600.0, "px".to_string()),
+            ComponentValue::CloseParenthesis,
+        ]);
+        at_rule.set_rules(vec![Rule::QualifiedRule(rule1.clone())]);
+
+        let mut rule2 = QualifiedRule::default();
+        rule2.set_selector(Selector::TypeSelector("h1".to_string()));
+        let mut declaration2 = Declaration::default();
+        declaration2.set_property("color".to_string());
+        declaration2.set_value(vec![ComponentValue::Ident("blue".to_string())]);
+        rule2.set_declarations(vec![declaration2]);
+
+        let expected = vec![Rule::AtRule(at_rule), Rule::QualifiedRule(rule2.clone())];
+        assert_eq!(expected, cssom.rules);
+
+        assert_eq!(
+            vec![&rule1, &rule2],
+            cssom.style_rules(&Device::new(500, 400))
+        );
+        assert_eq!(vec![&rule2], cssom.style_rules(&Device::new(800, 400)));
+    }
+
+    #[test]
+    fn test_unsupported_at_rules() {
+        let style = "@import url(\"a.css\"); @font-face { font-family: foo; } p { color: red; }"
+            .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        assert_eq!(cssom.rules.len(), 3);
+        let style_rules = cssom.style_rules(&Device::default());
+        assert_eq!(1, style_rules.len());
+        assert_eq!(
+            Selector::TypeSelector("p".to_string()),
+            style_rules[0].selector
+        );
+    }
+
+    #[test]
+    fn test_multiple_values() {
+        let style = "p { margin: 0 auto; border: 1px solid red }".to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let mut rule = QualifiedRule::default();
+        rule.set_selector(Selector::TypeSelector("p".to_string()));
+        let mut declaration1 = Declaration::default();
+        declaration1.set_property("margin".to_string());
+        declaration1.set_value(vec![
+            ComponentValue::Number(0.0),
+            ComponentValue::Ident("auto".to_string()),
+        ]);
+        let mut declaration2 = Declaration::default();
+        declaration2.set_property("border".to_string());
+        declaration2.set_value(vec![
+            ComponentValue::Dimension(1.0, "px".to_string()),
+            ComponentValue::Ident("solid".to_string()),
+            ComponentValue::Ident("red".to_string()),
+        ]);
+        rule.set_declarations(vec![declaration1, declaration2]);
+
+        assert_eq!(vec![Rule::QualifiedRule(rule)], cssom.rules);
+    }
+
+    #[test]
+    fn test_pseudo_element_selector() {
+        let style =
+            "li::marker { color: red; } .a::marker { color: red; } p::unknown { color: red; } \
+             p::before { color: red; } p:after { color: red; } #b::first-line { color: red; } \
+             .c:first-letter { color: red; } a:hover { color: red; }"
+                .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let selectors: Vec<Selector> = cssom
+            .rules
+            .iter()
+            .map(|rule| match rule {
+                Rule::QualifiedRule(rule) => rule.selector.clone(),
+                _ => panic!("expected a qualified rule"),
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                Selector::PseudoElement(
+                    Box::new(Selector::TypeSelector("li".to_string())),
+                    PseudoElement::Marker
+                ),
+                Selector::PseudoElement(
+                    Box::new(Selector::ClassSelector("a".to_string())),
+                    PseudoElement::Marker
+                ),
+                Selector::UnknownSelector,
+                Selector::PseudoElement(
+                    Box::new(Selector::TypeSelector("p".to_string())),
+                    PseudoElement::Before
+                ),
+                Selector::PseudoElement(
+                    Box::new(Selector::TypeSelector("p".to_string())),
+                    PseudoElement::After
+                ),
+                Selector::PseudoElement(
+                    Box::new(Selector::IdSelector("b".to_string())),
+                    PseudoElement::FirstLine
+                ),
+                Selector::PseudoElement(
+                    Box::new(Selector::ClassSelector("c".to_string())),
+                    PseudoElement::FirstLetter
+                ),
+                Selector::TypeSelector("a".to_string()),
+            ],
+            selectors
+        );
+    }
 }
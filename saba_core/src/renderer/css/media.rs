@@ -0,0 +1,471 @@
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::token::CssToken;
+use alloc::vec::Vec;
+
+/// The number of pixels in 1em when a media query is evaluated. Relative units in media queries
+/// are resolved against the initial value of font-size, not against any element.
+/// https://www.w3.org/TR/mediaqueries-5/#units
+const MEDIA_QUERY_EM_IN_PX: f64 = 16.0;
+
+/// The viewport size used when an embedder doesn't specify one.
+const DEFAULT_VIEWPORT_WIDTH: i64 = 800;
+const DEFAULT_VIEWPORT_HEIGHT: i64 = 600;
+
+/// https://www.w3.org/TR/mediaqueries-5/#prefers-color-scheme
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+/// The output device that media queries are evaluated against.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Device {
+    width: i64,
+    height: i64,
+    device_pixel_ratio: f64,
+    color_scheme: ColorScheme,
+}
+
+impl Default for Device {
+    fn default() -> Self {
+        Self::new(DEFAULT_VIEWPORT_WIDTH, DEFAULT_VIEWPORT_HEIGHT)
+    }
+}
+
+impl Device {
+    /// Creates a device whose viewport is `width` x `height` CSS pixels.
+    pub fn new(width: i64, height: i64) -> Self {
+        Self {
+            width,
+            height,
+            device_pixel_ratio: 1.0,
+            color_scheme: ColorScheme::Light,
+        }
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    /// https://drafts.csswg.org/cssom-view/#dom-window-devicepixelratio
+    /// "the ratio of the vertical size of a device pixel to the size of a CSS pixel"
+    pub fn device_pixel_ratio(&self) -> f64 {
+        self.device_pixel_ratio
+    }
+
+    pub fn set_device_pixel_ratio(&mut self, device_pixel_ratio: f64) {
+        self.device_pixel_ratio = device_pixel_ratio;
+    }
+
+    pub fn set_size(&mut self, width: i64, height: i64) {
+        self.width = width;
+        self.height = height;
+    }
+
+    pub fn color_scheme(&self) -> ColorScheme {
+        self.color_scheme
+    }
+
+    pub fn set_color_scheme(&mut self, color_scheme: ColorScheme) {
+        self.color_scheme = color_scheme;
+    }
+}
+
+/// https://www.w3.org/TR/mediaqueries-5/#media-types
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MediaType {
+    All,
+    Screen,
+    Print,
+    /// Unknown media types never match.
+    Unknown,
+}
+
+impl MediaType {
+    fn from_str_media_type(s: &str) -> Self {
+        match s {
+            "all" => Self::All,
+            "screen" => Self::Screen,
+            "print" => Self::Print,
+            _ => Self::Unknown,
+        }
+    }
+
+    fn matches(&self) -> bool {
+        // This browser always renders to a screen.
+        matches!(self, Self::All | Self::Screen)
+    }
+}
+
+/// https://www.w3.org/TR/mediaqueries-5/#mq-range-context
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RangePrefix {
+    Min,
+    Max,
+    Exact,
+}
+
+impl RangePrefix {
+    fn compare(&self, actual: f64, expected: f64) -> bool {
+        match self {
+            RangePrefix::Min => actual >= expected,
+            RangePrefix::Max => actual <= expected,
+            RangePrefix::Exact => actual == expected,
+        }
+    }
+}
+
+/// https://www.w3.org/TR/mediaqueries-5/#media-feature
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaFeature {
+    /// https://www.w3.org/TR/mediaqueries-5/#width
+    Width(RangePrefix, f64),
+    /// https://www.w3.org/TR/mediaqueries-5/#height
+    Height(RangePrefix, f64),
+    /// https://www.w3.org/TR/mediaqueries-5/#resolution
+    /// The value is in dppx.
+    Resolution(RangePrefix, f64),
+    /// https://www.w3.org/TR/mediaqueries-5/#prefers-color-scheme
+    PrefersColorScheme(ColorScheme),
+    /// A feature this browser doesn't know. It evaluates to false.
+    Unknown,
+}
+
+impl MediaFeature {
+    fn matches(&self, device: &Device) -> bool {
+        match self {
+            MediaFeature::Width(prefix, value) => prefix.compare(device.width() as f64, *value),
+            MediaFeature::Height(prefix, value) => prefix.compare(device.height() as f64, *value),
+            MediaFeature::Resolution(prefix, value) => {
+                prefix.compare(device.device_pixel_ratio(), *value)
+            }
+            MediaFeature::PrefersColorScheme(scheme) => device.color_scheme() == *scheme,
+            MediaFeature::Unknown => false,
+        }
+    }
+}
+
+/// https://www.w3.org/TR/mediaqueries-5/#media-query
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQuery {
+    negated: bool,
+    media_type: MediaType,
+    features: Vec<MediaFeature>,
+}
+
+impl MediaQuery {
+    /// "A media query that does not match the grammar in the previous section must be replaced by
+    /// not all during parsing."
+    /// https://www.w3.org/TR/mediaqueries-5/#error-handling
+    fn not_all() -> Self {
+        Self {
+            negated: true,
+            media_type: MediaType::All,
+            features: Vec::new(),
+        }
+    }
+
+    pub fn matches(&self, device: &Device) -> bool {
+        let result = self.media_type.matches() && self.features.iter().all(|f| f.matches(device));
+        if self.negated {
+            !result
+        } else {
+            result
+        }
+    }
+}
+
+/// https://www.w3.org/TR/mediaqueries-5/#typedef-media-query-list
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQueryList {
+    queries: Vec<MediaQuery>,
+}
+
+impl MediaQueryList {
+    /// https://www.w3.org/TR/mediaqueries-5/#mq-list
+    /// "A media query list is true if any of its component media queries are true, and false only
+    /// if all of its component media queries are false."
+    /// An empty media query list evaluates to true.
+    pub fn matches(&self, device: &Device) -> bool {
+        self.queries.is_empty() || self.queries.iter().any(|q| q.matches(device))
+    }
+
+    pub fn queries(&self) -> &[MediaQuery] {
+        &self.queries
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MediaQueryParser {
+    tokens: Vec<ComponentValue>,
+    pos: usize,
+}
+
+impl MediaQueryParser {
+    pub fn new(tokens: Vec<ComponentValue>) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&CssToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<CssToken> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn is_ident(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(CssToken::Ident(ident)) if ident.eq_ignore_ascii_case(name))
+    }
+
+    /// https://www.w3.org/TR/mediaqueries-5/#typedef-media-feature
+    fn consume_media_feature(&mut self) -> Option<MediaFeature> {
+        if self.peek() != Some(&CssToken::OpenParenthesis) {
+            return None;
+        }
+        self.next();
+        let name = match self.next()? {
+            CssToken::Ident(name) => name.to_ascii_lowercase(),
+            _ => return None,
+        };
+
+        // Boolean context such as `(color)` is not supported, so a feature must have a value.
+        if self.next()? != CssToken::Colon {
+            return None;
+        }
+        let value = self.next()?;
+        if self.next()? != CssToken::CloseParenthesis {
+            return None;
+        }
+
+        let (prefix, feature_name) = if let Some(n) = name.strip_prefix("min-") {
+            (RangePrefix::Min, n)
+        } else if let Some(n) = name.strip_prefix("max-") {
+            (RangePrefix::Max, n)
+        } else {
+            (RangePrefix::Exact, name.as_str())
+        };
+
+        let feature = match (prefix, feature_name) {
+            (_, "width") => MediaFeature::Width(prefix, length_in_px(&value)?),
+            (_, "height") => MediaFeature::Height(prefix, length_in_px(&value)?),
+            (_, "resolution") => MediaFeature::Resolution(prefix, resolution_in_dppx(&value)?),
+            (RangePrefix::Exact, "prefers-color-scheme") => match value {
+                CssToken::Ident(ref scheme) if scheme == "light" => {
+                    MediaFeature::PrefersColorScheme(ColorScheme::Light)
+                }
+                CssToken::Ident(ref scheme) if scheme == "dark" => {
+                    MediaFeature::PrefersColorScheme(ColorScheme::Dark)
+                }
+                _ => return None,
+            },
+            _ => MediaFeature::Unknown,
+        };
+        Some(feature)
+    }
+
+    /// https://www.w3.org/TR/mediaqueries-5/#typedef-media-query
+    /// <media-query> = <media-condition>
+    ///              | [ not | only ]? <media-type> [ and <media-condition-without-or> ]?
+    fn consume_media_query(&mut self) -> Option<MediaQuery> {
+        let mut query = MediaQuery {
+            negated: false,
+            media_type: MediaType::All,
+            features: Vec::new(),
+        };
+
+        if self.is_ident("not") {
+            self.next();
+            query.negated = true;
+        } else if self.is_ident("only") {
+            self.next();
+        }
+
+        match self.peek()? {
+            CssToken::Ident(media_type) => {
+                query.media_type = MediaType::from_str_media_type(&media_type.to_ascii_lowercase());
+                self.next();
+                if self.peek().is_none() || self.peek() == Some(&CssToken::Delim(',')) {
+                    return Some(query);
+                }
+                if !self.is_ident("and") {
+                    return None;
+                }
+                self.next();
+            }
+            CssToken::OpenParenthesis => {}
+            _ => return None,
+        }
+
+        loop {
+            query.features.push(self.consume_media_feature()?);
+            if !self.is_ident("and") {
+                break;
+            }
+            self.next();
+        }
+
+        Some(query)
+    }
+
+    /// https://www.w3.org/TR/mediaqueries-5/#mq-syntax
+    pub fn parse_media_query_list(&mut self) -> MediaQueryList {
+        let mut queries = Vec::new();
+
+        while self.peek().is_some() {
+            let query = match self.consume_media_query() {
+                Some(q) if self.peek().is_none() || self.peek() == Some(&CssToken::Delim(',')) => q,
+                _ => {
+                    // Skip the rest of the broken media query.
+                    while self.peek().is_some() && self.peek() != Some(&CssToken::Delim(',')) {
+                        self.next();
+                    }
+                    MediaQuery::not_all()
+                }
+            };
+            queries.push(query);
+
+            // Consume a comma between media queries.
+            self.next();
+        }
+
+        MediaQueryList { queries }
+    }
+}
+
+fn length_in_px(value: &ComponentValue) -> Option<f64> {
+    match value {
+        CssToken::Number(n) if *n == 0.0 => Some(0.0),
+        CssToken::Dimension(n, unit) => match unit.to_ascii_lowercase().as_str() {
+            "px" => Some(*n),
+            "em" | "rem" => Some(*n * MEDIA_QUERY_EM_IN_PX),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// https://www.w3.org/TR/css-values-4/#resolution
+fn resolution_in_dppx(value: &ComponentValue) -> Option<f64> {
+    match value {
+        CssToken::Dimension(n, unit) => match unit.to_ascii_lowercase().as_str() {
+            "dppx" | "x" => Some(*n),
+            // "1dppx is equivalent to 96dpi"
+            "dpi" => Some(*n / 96.0),
+            // 1in is 2.54cm.
+            "dpcm" => Some(*n * 2.54 / 96.0),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::CssTokenizer;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn parse(query: &str) -> MediaQueryList {
+        let tokens = CssTokenizer::new(query.to_string()).collect();
+        MediaQueryParser::new(tokens).parse_media_query_list()
+    }
+
+    #[test]
+    fn test_media_type() {
+        let list = parse("screen");
+        assert_eq!(
+            vec![MediaQuery {
+                negated: false,
+                media_type: MediaType::Screen,
+                features: Vec::new(),
+            }],
+            list.queries()
+        );
+        assert!(list.matches(&Device::new(600, 400)));
+        assert!(!parse("print").matches(&Device::new(600, 400)));
+        assert!(parse("not print").matches(&Device::new(600, 400)));
+    }
+
+    #[test]
+    fn test_width() {
+        let list = parse("screen and (min-width: 600px)");
+        assert_eq!(
+            vec![MediaQuery {
+                negated: false,
+                media_type: MediaType::Screen,
+                features: vec![MediaFeature::Width(RangePrefix::Min, 600.0)],
+            }],
+            list.queries()
+        );
+        assert!(list.matches(&Device::new(800, 400)));
+        assert!(list.matches(&Device::new(600, 400)));
+        assert!(!list.matches(&Device::new(599, 400)));
+
+        let list = parse("(max-width: 30em)");
+        assert!(list.matches(&Device::new(480, 400)));
+        assert!(!list.matches(&Device::new(481, 400)));
+    }
+
+    #[test]
+    fn test_and() {
+        let list = parse("(min-width: 300px) and (max-width: 500px)");
+        assert!(!list.matches(&Device::new(200, 400)));
+        assert!(list.matches(&Device::new(400, 400)));
+        assert!(!list.matches(&Device::new(600, 400)));
+    }
+
+    #[test]
+    fn test_not() {
+        let list = parse("not screen and (max-width: 500px)");
+        assert!(!list.matches(&Device::new(400, 400)));
+        assert!(list.matches(&Device::new(600, 400)));
+    }
+
+    #[test]
+    fn test_comma_separated_list() {
+        let list = parse("print, (max-height: 300px)");
+        assert_eq!(2, list.queries().len());
+        assert!(list.matches(&Device::new(600, 300)));
+        assert!(!list.matches(&Device::new(600, 400)));
+    }
+
+    #[test]
+    fn test_prefers_color_scheme() {
+        let list = parse("(prefers-color-scheme: dark)");
+        let mut device = Device::new(600, 400);
+        assert!(!list.matches(&device));
+        device.set_color_scheme(ColorScheme::Dark);
+        assert!(list.matches(&device));
+    }
+
+    #[test]
+    fn test_resolution() {
+        let list = parse("(min-resolution: 2dppx)");
+        let mut device = Device::new(600, 400);
+        assert!(!list.matches(&device));
+        device.set_device_pixel_ratio(2.0);
+        assert!(list.matches(&device));
+        assert!(parse("(resolution: 192dpi)").matches(&device));
+        assert!(parse("(max-resolution: 2x)").matches(&device));
+        assert!(!parse("(max-resolution: 1.5x)").matches(&device));
+    }
+
+    #[test]
+    fn test_invalid_query() {
+        // A broken query is replaced with `not all` and doesn't affect other queries.
+        let list = parse("screen and, (min-width: 100px)");
+        assert_eq!(MediaQuery::not_all(), list.queries()[0]);
+        assert!(list.matches(&Device::new(600, 400)));
+
+        assert!(!parse("(unknown-feature: 1px)").matches(&Device::new(600, 400)));
+    }
+}
@@ -1,2 +1,8 @@
 pub mod cssom;
+pub mod media;
+pub mod property;
+pub mod shorthand;
 pub mod token;
+pub mod user_agent;
+pub mod value;
+pub mod variable;
@@ -0,0 +1,380 @@
+//! https://www.w3.org/TR/css-cascade-4/#defaulting
+//! The registry of supported CSS properties. Each longhand property declares its initial value and
+//! whether it is inherited, which is all the cascade needs to know to default it.
+
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::token::CssTokenizer;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// https://www.w3.org/TR/css-values-4/#common-keywords
+/// https://www.w3.org/TR/css-cascade-4/#defaulting-keywords
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CssWideKeyword {
+    Initial,
+    Inherit,
+    Unset,
+    Revert,
+}
+
+impl CssWideKeyword {
+    /// Returns the keyword if `values` consists of a CSS-wide keyword only.
+    pub fn from_component_values(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::Ident(ident)] => match ident.to_ascii_lowercase().as_str() {
+                "initial" => Some(Self::Initial),
+                "inherit" => Some(Self::Inherit),
+                "unset" => Some(Self::Unset),
+                "revert" => Some(Self::Revert),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-cascade-4/#initial-values
+/// https://www.w3.org/TR/css-cascade-4/#inherited-property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PropertyDefinition {
+    pub name: &'static str,
+    /// The initial value written in CSS. It's parsed in the same way as declared values.
+    pub initial: &'static str,
+    pub inherited: bool,
+}
+
+impl PropertyDefinition {
+    const fn new(name: &'static str, initial: &'static str, inherited: bool) -> Self {
+        Self {
+            name,
+            initial,
+            inherited,
+        }
+    }
+
+    pub fn initial_value(&self) -> Vec<ComponentValue> {
+        CssTokenizer::new(self.initial.to_string()).collect()
+    }
+}
+
+/// All supported longhand properties. Properties are resolved in this order, so a property comes
+/// after the properties its computed value depends on: color before properties that can be
+/// currentColor, and font-size before properties that can be font-relative lengths.
+pub static PROPERTIES: &[PropertyDefinition] = &[
+    PropertyDefinition::new("color", "black", true),
+    PropertyDefinition::new("font-size", "medium", true),
+    PropertyDefinition::new("font-weight", "normal", true),
+    PropertyDefinition::new("font-style", "normal", true),
+    PropertyDefinition::new("font-family", "serif", true),
+    PropertyDefinition::new("line-height", "normal", true),
+    // Text decorations are not inherited in the spec but are propagated to the text inside the
+    // element, which we model as inheritance.
+    PropertyDefinition::new("text-decoration-line", "none", true),
+    PropertyDefinition::new("text-align", "start", true),
+    PropertyDefinition::new("white-space", "normal", true),
+    PropertyDefinition::new("text-indent", "0", true),
+    PropertyDefinition::new("letter-spacing", "normal", true),
+    PropertyDefinition::new("word-spacing", "normal", true),
+    PropertyDefinition::new("text-transform", "none", true),
+    PropertyDefinition::new("word-break", "normal", true),
+    PropertyDefinition::new("overflow-wrap", "normal", true),
+    PropertyDefinition::new("line-break", "auto", true),
+    PropertyDefinition::new("display", "inline", false),
+    PropertyDefinition::new("vertical-align", "baseline", false),
+    PropertyDefinition::new("background-color", "transparent", false),
+    PropertyDefinition::new("background-image", "none", false),
+    PropertyDefinition::new("width", "auto", false),
+    PropertyDefinition::new("height", "auto", false),
+    PropertyDefinition::new("box-sizing", "content-box", false),
+    PropertyDefinition::new("overflow-x", "visible", false),
+    PropertyDefinition::new("overflow-y", "visible", false),
+    PropertyDefinition::new("position", "static", false),
+    PropertyDefinition::new("top", "auto", false),
+    PropertyDefinition::new("right", "auto", false),
+    PropertyDefinition::new("bottom", "auto", false),
+    PropertyDefinition::new("left", "auto", false),
+    PropertyDefinition::new("z-index", "auto", false),
+    PropertyDefinition::new("float", "none", false),
+    PropertyDefinition::new("clear", "none", false),
+    PropertyDefinition::new("flex-direction", "row", false),
+    PropertyDefinition::new("flex-wrap", "nowrap", false),
+    PropertyDefinition::new("justify-content", "normal", false),
+    PropertyDefinition::new("align-items", "normal", false),
+    PropertyDefinition::new("align-self", "auto", false),
+    PropertyDefinition::new("flex-grow", "0", false),
+    PropertyDefinition::new("flex-shrink", "1", false),
+    PropertyDefinition::new("flex-basis", "auto", false),
+    PropertyDefinition::new("row-gap", "normal", false),
+    PropertyDefinition::new("column-gap", "normal", false),
+    PropertyDefinition::new("order", "0", false),
+    PropertyDefinition::new("grid-template-columns", "none", false),
+    PropertyDefinition::new("grid-template-rows", "none", false),
+    PropertyDefinition::new("grid-template-areas", "none", false),
+    PropertyDefinition::new("grid-auto-columns", "auto", false),
+    PropertyDefinition::new("grid-auto-rows", "auto", false),
+    PropertyDefinition::new("grid-auto-flow", "row", false),
+    PropertyDefinition::new("grid-row-start", "auto", false),
+    PropertyDefinition::new("grid-column-start", "auto", false),
+    PropertyDefinition::new("grid-row-end", "auto", false),
+    PropertyDefinition::new("grid-column-end", "auto", false),
+    PropertyDefinition::new("margin-top", "0", false),
+    PropertyDefinition::new("margin-right", "0", false),
+    PropertyDefinition::new("margin-bottom", "0", false),
+    PropertyDefinition::new("margin-left", "0", false),
+    PropertyDefinition::new("padding-top", "0", false),
+    PropertyDefinition::new("padding-right", "0", false),
+    PropertyDefinition::new("padding-bottom", "0", false),
+    PropertyDefinition::new("padding-left", "0", false),
+    PropertyDefinition::new("border-top-width", "medium", false),
+    PropertyDefinition::new("border-right-width", "medium", false),
+    PropertyDefinition::new("border-bottom-width", "medium", false),
+    PropertyDefinition::new("border-left-width", "medium", false),
+    PropertyDefinition::new("border-top-style", "none", false),
+    PropertyDefinition::new("border-right-style", "none", false),
+    PropertyDefinition::new("border-bottom-style", "none", false),
+    PropertyDefinition::new("border-left-style", "none", false),
+    PropertyDefinition::new("border-top-color", "currentcolor", false),
+    PropertyDefinition::new("border-right-color", "currentcolor", false),
+    PropertyDefinition::new("border-bottom-color", "currentcolor", false),
+    PropertyDefinition::new("border-left-color", "currentcolor", false),
+    PropertyDefinition::new("border-top-left-radius", "0", false),
+    PropertyDefinition::new("border-top-right-radius", "0", false),
+    PropertyDefinition::new("border-bottom-right-radius", "0", false),
+    PropertyDefinition::new("border-bottom-left-radius", "0", false),
+    PropertyDefinition::new("opacity", "1", false),
+    PropertyDefinition::new("transform", "none", false),
+    PropertyDefinition::new("list-style-type", "disc", true),
+    PropertyDefinition::new("list-style-position", "outside", true),
+    PropertyDefinition::new("counter-reset", "none", false),
+    PropertyDefinition::new("counter-increment", "none", false),
+    PropertyDefinition::new("counter-set", "none", false),
+    PropertyDefinition::new("content", "normal", false),
+    PropertyDefinition::new("quotes", "auto", true),
+];
+
+/// https://www.w3.org/TR/css-cascade-4/#shorthand-property
+/// Shorthand properties and the longhand properties they set. Longhand properties that are not in
+/// PROPERTIES are not supported yet, and declarations of them are ignored.
+pub static SHORTHANDS: &[(&str, &[&str])] = &[
+    (
+        "margin",
+        &["margin-top", "margin-right", "margin-bottom", "margin-left"],
+    ),
+    (
+        "padding",
+        &[
+            "padding-top",
+            "padding-right",
+            "padding-bottom",
+            "padding-left",
+        ],
+    ),
+    (
+        "border-width",
+        &[
+            "border-top-width",
+            "border-right-width",
+            "border-bottom-width",
+            "border-left-width",
+        ],
+    ),
+    (
+        "border-style",
+        &[
+            "border-top-style",
+            "border-right-style",
+            "border-bottom-style",
+            "border-left-style",
+        ],
+    ),
+    (
+        "border-color",
+        &[
+            "border-top-color",
+            "border-right-color",
+            "border-bottom-color",
+            "border-left-color",
+        ],
+    ),
+    (
+        "border",
+        &[
+            "border-top-width",
+            "border-right-width",
+            "border-bottom-width",
+            "border-left-width",
+            "border-top-style",
+            "border-right-style",
+            "border-bottom-style",
+            "border-left-style",
+            "border-top-color",
+            "border-right-color",
+            "border-bottom-color",
+            "border-left-color",
+        ],
+    ),
+    (
+        "border-radius",
+        &[
+            "border-top-left-radius",
+            "border-top-right-radius",
+            "border-bottom-right-radius",
+            "border-bottom-left-radius",
+        ],
+    ),
+    (
+        "border-top",
+        &["border-top-width", "border-top-style", "border-top-color"],
+    ),
+    (
+        "border-right",
+        &[
+            "border-right-width",
+            "border-right-style",
+            "border-right-color",
+        ],
+    ),
+    (
+        "border-bottom",
+        &[
+            "border-bottom-width",
+            "border-bottom-style",
+            "border-bottom-color",
+        ],
+    ),
+    (
+        "border-left",
+        &[
+            "border-left-width",
+            "border-left-style",
+            "border-left-color",
+        ],
+    ),
+    (
+        "font",
+        &[
+            "font-style",
+            "font-variant-caps",
+            "font-weight",
+            "font-stretch",
+            "font-size",
+            "line-height",
+            "font-family",
+        ],
+    ),
+    (
+        "background",
+        &[
+            "background-image",
+            "background-position",
+            "background-size",
+            "background-repeat",
+            "background-attachment",
+            "background-origin",
+            "background-clip",
+            "background-color",
+        ],
+    ),
+    (
+        "list-style",
+        &["list-style-position", "list-style-image", "list-style-type"],
+    ),
+    (
+        "text-decoration",
+        &[
+            "text-decoration-line",
+            "text-decoration-thickness",
+            "text-decoration-style",
+            "text-decoration-color",
+        ],
+    ),
+    ("overflow", &["overflow-x", "overflow-y"]),
+    ("inset", &["top", "right", "bottom", "left"]),
+    ("flex", &["flex-grow", "flex-shrink", "flex-basis"]),
+    ("flex-flow", &["flex-direction", "flex-wrap"]),
+    ("gap", &["row-gap", "column-gap"]),
+    (
+        "grid-template",
+        &[
+            "grid-template-rows",
+            "grid-template-columns",
+            "grid-template-areas",
+        ],
+    ),
+    ("grid-row", &["grid-row-start", "grid-row-end"]),
+    ("grid-column", &["grid-column-start", "grid-column-end"]),
+    (
+        "grid-area",
+        &[
+            "grid-row-start",
+            "grid-column-start",
+            "grid-row-end",
+            "grid-column-end",
+        ],
+    ),
+];
+
+/// https://www.w3.org/TR/css-cascade-4/#legacy-name-alias
+/// Legacy names of properties and the properties they are aliases of. A declaration of a legacy
+/// name is treated as a declaration of the property.
+pub static LEGACY_NAME_ALIASES: &[(&str, &str)] = &[("word-wrap", "overflow-wrap")];
+
+/// Returns the property that `name` is a legacy name alias of, or `name` itself.
+pub fn resolve_alias(name: &str) -> &str {
+    match LEGACY_NAME_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        Some((_, property)) => property,
+        None => name,
+    }
+}
+
+pub fn is_shorthand(name: &str) -> bool {
+    SHORTHANDS.iter().any(|(shorthand, _)| *shorthand == name)
+}
+
+pub fn property_definition(name: &str) -> Option<&'static PropertyDefinition> {
+    PROPERTIES.iter().find(|definition| definition.name == name)
+}
+
+/// Returns the longhand properties that a declaration of `name` sets. A longhand property sets
+/// itself, and an unsupported property sets nothing.
+pub fn longhands(name: &str) -> Vec<&'static str> {
+    if let Some((_, longhands)) = SHORTHANDS.iter().find(|(shorthand, _)| *shorthand == name) {
+        return longhands.to_vec();
+    }
+    match property_definition(name) {
+        Some(definition) => vec![definition.name],
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_css_wide_keywords() {
+        let values: Vec<ComponentValue> = CssTokenizer::new("INHERIT".to_string()).collect();
+        assert_eq!(
+            Some(CssWideKeyword::Inherit),
+            CssWideKeyword::from_component_values(&values)
+        );
+        let values: Vec<ComponentValue> = CssTokenizer::new("inherit red".to_string()).collect();
+        assert_eq!(None, CssWideKeyword::from_component_values(&values));
+    }
+
+    #[test]
+    fn test_longhands() {
+        assert_eq!(vec!["color"], longhands("color"));
+        assert_eq!(
+            vec!["border-top-width", "border-top-style", "border-top-color"],
+            longhands("border-top")
+        );
+        assert!(longhands("unknown").is_empty());
+        for shorthand in ["margin", "padding", "border"] {
+            for longhand in longhands(shorthand) {
+                assert!(property_definition(longhand).is_some(), "{}", longhand);
+            }
+        }
+    }
+}
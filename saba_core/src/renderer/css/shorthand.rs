@@ -0,0 +1,1276 @@
+//! https://www.w3.org/TR/css-cascade-4/#shorthand
+//! Expands declarations of shorthand properties into declarations of their longhand properties,
+//! so the cascade only deals with longhand properties.
+//!
+//! The expander only sorts out which part of a value belongs to which longhand. Each longhand value
+//! is parsed later by the property that consumes it, and a longhand that is not supported yet is
+//! dropped there.
+
+use crate::error::Error;
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::cssom::Declaration;
+use crate::renderer::css::property::is_shorthand;
+use crate::renderer::css::property::longhands;
+use crate::renderer::css::property::resolve_alias;
+use crate::renderer::css::property::CssWideKeyword;
+use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::css::value::split_component_values;
+use crate::renderer::css::value::LengthPercentage;
+use crate::renderer::layout::computed_style::border_width_from_component_value;
+use crate::renderer::layout::computed_style::BorderStyle;
+use crate::renderer::layout::computed_style::ColorValue;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Returns the declarations of the longhand properties that `declaration` sets. A declaration of
+/// a longhand property is returned as is, with a legacy name replaced by the property it's an
+/// alias of. Returns an error if the value doesn't match the grammar of the shorthand property,
+/// and then the whole declaration is invalid.
+pub fn expand_declaration(declaration: &Declaration) -> Result<Vec<Declaration>, Error> {
+    let property = resolve_alias(declaration.property.as_str());
+    let values = declaration.value.as_slice();
+
+    if !is_shorthand(property) {
+        return Ok(vec![longhand(property, values.to_vec())]);
+    }
+    if CssWideKeyword::from_component_values(values).is_some() {
+        return Ok(longhands(property)
+            .into_iter()
+            .map(|name| longhand(name, values.to_vec()))
+            .collect());
+    }
+
+    match property {
+        "margin" | "padding" | "border-width" | "border-style" | "border-color" | "inset" => {
+            expand_four_sides(property, values)
+        }
+        "border" => {
+            let [width, style, color] = parse_border(values)?;
+            let mut expanded = Vec::new();
+            for side in SIDES {
+                expanded.push(longhand(&format!("border-{}-width", side), width.clone()));
+                expanded.push(longhand(&format!("border-{}-style", side), style.clone()));
+                expanded.push(longhand(&format!("border-{}-color", side), color.clone()));
+            }
+            Ok(expanded)
+        }
+        "border-top" | "border-right" | "border-bottom" | "border-left" => {
+            let [width, style, color] = parse_border(values)?;
+            Ok(vec![
+                longhand(&format!("{}-width", property), width),
+                longhand(&format!("{}-style", property), style),
+                longhand(&format!("{}-color", property), color),
+            ])
+        }
+        "border-radius" => expand_border_radius(values),
+        "font" => expand_font(values),
+        "background" => expand_background(values),
+        "list-style" => expand_list_style(values),
+        "text-decoration" => expand_text_decoration(values),
+        "overflow" => expand_overflow(values),
+        "flex" => expand_flex(values),
+        "flex-flow" => expand_flex_flow(values),
+        "gap" => expand_gap(values),
+        "grid-row" | "grid-column" => expand_grid_line(property, values),
+        "grid-area" => expand_grid_area(values),
+        "grid-template" => expand_grid_template(values),
+        _ => invalid(property, values),
+    }
+}
+
+static SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+
+fn longhand(property: &str, value: Vec<ComponentValue>) -> Declaration {
+    let mut declaration = Declaration::new();
+    declaration.set_property(property.to_string());
+    declaration.set_value(value);
+    declaration
+}
+
+/// Tokenizes the initial value of a longhand that is omitted in a shorthand.
+/// https://www.w3.org/TR/css-cascade-4/#shorthand
+/// "When values are omitted from a shorthand form, unless otherwise defined, each 'missing'
+/// sub-property is assigned its initial value."
+fn initial(css: &str) -> Vec<ComponentValue> {
+    CssTokenizer::new(css.to_string()).collect()
+}
+
+fn invalid<T>(property: &str, values: &[ComponentValue]) -> Result<T, Error> {
+    Err(Error::UnexpectedInput(format!(
+        "invalid value for {}: {:?}",
+        property, values
+    )))
+}
+
+fn is_keyword(value: &[ComponentValue], keywords: &[&str]) -> bool {
+    match value {
+        [ComponentValue::Ident(ident)] => keywords.iter().any(|k| ident.eq_ignore_ascii_case(k)),
+        _ => false,
+    }
+}
+
+fn is_color(value: &[ComponentValue]) -> bool {
+    ColorValue::from_component_values(value).is_ok()
+}
+
+fn is_non_negative_length_percentage(value: &[ComponentValue]) -> bool {
+    matches!(LengthPercentage::from_component_values(value), Ok(l) if !l.is_negative())
+}
+
+/// https://www.w3.org/TR/css-images-3/#typedef-image
+fn is_image(value: &[ComponentValue]) -> bool {
+    match value.first() {
+        Some(ComponentValue::Function(name)) => [
+            "url",
+            "linear-gradient",
+            "radial-gradient",
+            "repeating-linear-gradient",
+            "repeating-radial-gradient",
+        ]
+        .iter()
+        .any(|f| name.eq_ignore_ascii_case(f)),
+        Some(ComponentValue::Url(_)) => true,
+        _ => false,
+    }
+}
+
+/// Joins component values back into a value of a longhand property.
+fn concat(components: &[&[ComponentValue]]) -> Vec<ComponentValue> {
+    components.concat()
+}
+
+/// https://www.w3.org/TR/css-box-4/#margin-shorthand
+/// "If there is only one component value, it applies to all sides. If there are two values, the
+/// top and bottom are set to the first value and the right and left are set to the second. If
+/// there are three values, the top is set to the first value, the left and right are set to the
+/// second, and the bottom is set to the third. If there are four values they apply to the top,
+/// right, bottom, and left, respectively."
+fn expand_four_sides(property: &str, values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let components = split_component_values(values);
+    let sides = match components.as_slice() {
+        [all] => [*all, *all, *all, *all],
+        [vertical, horizontal] => [*vertical, *horizontal, *vertical, *horizontal],
+        [top, horizontal, bottom] => [*top, *horizontal, *bottom, *horizontal],
+        [top, right, bottom, left] => [*top, *right, *bottom, *left],
+        _ => return invalid(property, values),
+    };
+
+    // border-width expands to border-top-width and so on, and inset expands to top and so on.
+    let name = |side: &str| match property.split_once('-') {
+        Some((prefix, suffix)) => format!("{}-{}-{}", prefix, side, suffix),
+        None if property == "inset" => side.to_string(),
+        None => format!("{}-{}", property, side),
+    };
+    Ok(SIDES
+        .iter()
+        .zip(sides)
+        .map(|(side, value)| longhand(&name(side), value.to_vec()))
+        .collect())
+}
+
+/// https://www.w3.org/TR/css-backgrounds-3/#border-radius
+/// `<length-percentage [0,∞]>{1,4} [ / <length-percentage [0,∞]>{1,4} ]?`. "If values are given
+/// before and after the slash, then the values before the slash set the horizontal radius and the
+/// values after the slash set the vertical radius. If there is no slash, then the values set both
+/// radii equally. The four values for each radii are given in the order top-left, top-right,
+/// bottom-right, bottom-left. If bottom-left is omitted it is the same as top-right. If
+/// bottom-right is omitted it is the same as top-left. If top-right is omitted it is the same as
+/// top-left."
+fn expand_border_radius(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let corners = |section: &[ComponentValue]| -> Result<[Vec<ComponentValue>; 4], Error> {
+        let radii = split_component_values(section);
+        if !radii.iter().all(|r| is_non_negative_length_percentage(r)) {
+            return invalid("border-radius", values);
+        }
+        let [top_left, top_right, bottom_right, bottom_left] = match radii.as_slice() {
+            [all] => [*all, *all, *all, *all],
+            [a, b] => [*a, *b, *a, *b],
+            [a, b, c] => [*a, *b, *c, *b],
+            [a, b, c, d] => [*a, *b, *c, *d],
+            _ => return invalid("border-radius", values),
+        };
+        Ok([
+            top_left.to_vec(),
+            top_right.to_vec(),
+            bottom_right.to_vec(),
+            bottom_left.to_vec(),
+        ])
+    };
+    let sections = split_at_slashes(values);
+    let (horizontal, vertical) = match sections.as_slice() {
+        [both] => (corners(both)?, corners(both)?),
+        [horizontal, vertical] => (corners(horizontal)?, corners(vertical)?),
+        _ => return invalid("border-radius", values),
+    };
+    Ok(["top-left", "top-right", "bottom-right", "bottom-left"]
+        .iter()
+        .zip(horizontal.iter().zip(vertical.iter()))
+        .map(|(corner, (h, v))| {
+            let value = if h == v { h.clone() } else { concat(&[h, v]) };
+            longhand(&format!("border-{}-radius", corner), value)
+        })
+        .collect())
+}
+
+/// https://www.w3.org/TR/css-backgrounds-3/#border-shorthands
+/// `<line-width> || <line-style> || <color>`. Returns the values of width, style and color.
+fn parse_border(values: &[ComponentValue]) -> Result<[Vec<ComponentValue>; 3], Error> {
+    let mut width = None;
+    let mut style = None;
+    let mut color = None;
+    for value in split_component_values(values) {
+        if width.is_none() && matches!(value, [v] if border_width_from_component_value(v).is_ok()) {
+            width = Some(value.to_vec());
+        } else if style.is_none()
+            && matches!(value, [ComponentValue::Ident(s)] if BorderStyle::from_str_border_style(s).is_ok())
+        {
+            style = Some(value.to_vec());
+        } else if color.is_none() && is_color(value) {
+            color = Some(value.to_vec());
+        } else {
+            return invalid("border", values);
+        }
+    }
+    if values.is_empty() {
+        return invalid("border", values);
+    }
+
+    Ok([
+        width.unwrap_or_else(|| initial("medium")),
+        style.unwrap_or_else(|| initial("none")),
+        color.unwrap_or_else(|| initial("currentcolor")),
+    ])
+}
+
+/// https://www.w3.org/TR/css-fonts-4/#font-size-prop
+fn is_font_size(value: &[ComponentValue]) -> bool {
+    is_keyword(
+        value,
+        &[
+            "xx-small",
+            "x-small",
+            "small",
+            "medium",
+            "large",
+            "x-large",
+            "xx-large",
+            "xxx-large",
+            "larger",
+            "smaller",
+        ],
+    ) || is_non_negative_length_percentage(value)
+}
+
+/// https://www.w3.org/TR/css-fonts-4/#font-weight-prop
+fn is_font_weight(value: &[ComponentValue]) -> bool {
+    match value {
+        [ComponentValue::Number(n)] => (1.0..=1000.0).contains(n),
+        _ => is_keyword(value, &["bold", "bolder", "lighter"]),
+    }
+}
+
+/// https://www.w3.org/TR/css-fonts-4/#font-family-prop
+/// `[ <family-name> | <generic-family> ]#` where a family name is a string or a sequence of
+/// identifiers.
+fn is_font_family(values: &[&[ComponentValue]]) -> bool {
+    !values.is_empty()
+        && values
+            .split(|value| matches!(value, [ComponentValue::Delim(',')]))
+            .all(|family| match family {
+                [[ComponentValue::StringToken(_)]] => true,
+                [] => false,
+                names => names
+                    .iter()
+                    .all(|name| matches!(name, [ComponentValue::Ident(_)])),
+            })
+}
+
+/// https://www.w3.org/TR/css-fonts-4/#font-prop
+/// `[ <'font-style'> || <font-variant-css2> || <'font-weight'> || <font-width-css3> ]?
+/// <'font-size'> [ / <'line-height'> ]? <'font-family'>#`
+fn expand_font(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let components = split_component_values(values);
+    let mut style = None;
+    let mut variant = None;
+    let mut weight = None;
+    let mut stretch = None;
+
+    let mut i = 0;
+    while let Some(value) = components.get(i) {
+        if is_keyword(value, &["normal"]) {
+            // "normal" is valid for any of the four properties and leaves it at the initial
+            // value.
+        } else if style.is_none() && is_keyword(value, &["italic", "oblique"]) {
+            style = Some(value.to_vec());
+        } else if variant.is_none() && is_keyword(value, &["small-caps"]) {
+            variant = Some(value.to_vec());
+        } else if weight.is_none() && is_font_weight(value) {
+            weight = Some(value.to_vec());
+        } else if stretch.is_none()
+            && is_keyword(
+                value,
+                &[
+                    "ultra-condensed",
+                    "extra-condensed",
+                    "condensed",
+                    "semi-condensed",
+                    "semi-expanded",
+                    "expanded",
+                    "extra-expanded",
+                    "ultra-expanded",
+                ],
+            )
+        {
+            stretch = Some(value.to_vec());
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    if i > 4 {
+        return invalid("font", values);
+    }
+
+    let size = match components.get(i) {
+        Some(size) if is_font_size(size) => size.to_vec(),
+        _ => return invalid("font", values),
+    };
+    i += 1;
+
+    let mut line_height = initial("normal");
+    if components.get(i) == Some(&[ComponentValue::Delim('/')].as_slice()) {
+        match components.get(i + 1) {
+            Some(value)
+                if is_keyword(value, &["normal"])
+                    || matches!(value, [ComponentValue::Number(n)] if *n >= 0.0)
+                    || is_non_negative_length_percentage(value) =>
+            {
+                line_height = value.to_vec();
+            }
+            _ => return invalid("font", values),
+        }
+        i += 2;
+    }
+
+    let family = &components[i..];
+    if !is_font_family(family) {
+        return invalid("font", values);
+    }
+
+    Ok(vec![
+        longhand("font-style", style.unwrap_or_else(|| initial("normal"))),
+        longhand(
+            "font-variant-caps",
+            variant.unwrap_or_else(|| initial("normal")),
+        ),
+        longhand("font-weight", weight.unwrap_or_else(|| initial("normal"))),
+        longhand("font-stretch", stretch.unwrap_or_else(|| initial("normal"))),
+        longhand("font-size", size),
+        longhand("line-height", line_height),
+        longhand("font-family", concat(family)),
+    ])
+}
+
+/// The longhands of one layer of the background shorthand.
+#[derive(Default)]
+struct BackgroundLayer {
+    image: Option<Vec<ComponentValue>>,
+    position: Option<Vec<ComponentValue>>,
+    size: Option<Vec<ComponentValue>>,
+    repeat: Option<Vec<ComponentValue>>,
+    attachment: Option<Vec<ComponentValue>>,
+    origin: Option<Vec<ComponentValue>>,
+    clip: Option<Vec<ComponentValue>>,
+    color: Option<Vec<ComponentValue>>,
+}
+
+fn is_position_component(value: &[ComponentValue]) -> bool {
+    is_keyword(value, &["left", "right", "top", "bottom", "center"])
+        || LengthPercentage::from_component_values(value).is_ok()
+}
+
+fn is_background_size_component(value: &[ComponentValue]) -> bool {
+    is_keyword(value, &["auto", "cover", "contain"]) || is_non_negative_length_percentage(value)
+}
+
+fn is_repeat_component(value: &[ComponentValue]) -> bool {
+    is_keyword(value, &["repeat", "space", "round", "no-repeat"])
+}
+
+/// https://www.w3.org/TR/css-backgrounds-3/#typedef-final-bg-layer
+/// `<bg-image> || <bg-position> [ / <bg-size> ]? || <repeat-style> || <attachment> ||
+/// <visual-box> || <visual-box> || <'background-color'>`, where only the final layer may have a
+/// color.
+fn parse_background_layer(
+    components: &[&[ComponentValue]],
+    is_final: bool,
+) -> Option<BackgroundLayer> {
+    let mut layer = BackgroundLayer::default();
+    let mut i = 0;
+    while let Some(value) = components.get(i) {
+        let start = i;
+        i += 1;
+        if layer.image.is_none() && (is_image(value) || is_keyword(value, &["none"])) {
+            layer.image = Some(value.to_vec());
+        } else if layer.position.is_none() && is_position_component(value) {
+            while i < components.len() && i - start < 4 && is_position_component(components[i]) {
+                i += 1;
+            }
+            layer.position = Some(concat(&components[start..i]));
+            if components.get(i) == Some(&[ComponentValue::Delim('/')].as_slice()) {
+                let size_start = i + 1;
+                i = size_start;
+                while i < components.len()
+                    && i - size_start < 2
+                    && is_background_size_component(components[i])
+                {
+                    i += 1;
+                }
+                if i == size_start {
+                    return None;
+                }
+                layer.size = Some(concat(&components[size_start..i]));
+            }
+        } else if layer.repeat.is_none() && is_keyword(value, &["repeat-x", "repeat-y"]) {
+            layer.repeat = Some(value.to_vec());
+        } else if layer.repeat.is_none() && is_repeat_component(value) {
+            if components.get(i).is_some_and(|v| is_repeat_component(v)) {
+                i += 1;
+            }
+            layer.repeat = Some(concat(&components[start..i]));
+        } else if layer.attachment.is_none() && is_keyword(value, &["scroll", "fixed", "local"]) {
+            layer.attachment = Some(value.to_vec());
+        } else if is_keyword(value, &["border-box", "padding-box", "content-box"]) {
+            // "If one <visual-box> value is present then it sets both background-origin and
+            // background-clip to that value. If two values are present, then the first sets
+            // background-origin and the second background-clip."
+            if layer.origin.is_none() {
+                layer.origin = Some(value.to_vec());
+            } else if layer.clip.is_none() {
+                layer.clip = Some(value.to_vec());
+            } else {
+                return None;
+            }
+        } else if is_final && layer.color.is_none() && is_color(value) {
+            layer.color = Some(value.to_vec());
+        } else {
+            return None;
+        }
+    }
+    if components.is_empty() {
+        return None;
+    }
+    if layer.clip.is_none() {
+        layer.clip = layer.origin.clone();
+    }
+    Some(layer)
+}
+
+/// https://www.w3.org/TR/css-backgrounds-3/#background
+/// `<bg-layer>#? , <final-bg-layer>`. Each longhand takes a comma-separated list with a value for
+/// each layer, except background-color, which only the final layer sets.
+fn expand_background(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let components = split_component_values(values);
+    let layers: Vec<&[&[ComponentValue]]> = components
+        .split(|value| matches!(value, [ComponentValue::Delim(',')]))
+        .collect();
+
+    let mut parsed = Vec::new();
+    for (i, layer) in layers.iter().enumerate() {
+        match parse_background_layer(layer, i == layers.len() - 1) {
+            Some(layer) => parsed.push(layer),
+            None => return invalid("background", values),
+        }
+    }
+
+    let list = |get: fn(&BackgroundLayer) -> &Option<Vec<ComponentValue>>, initial_value: &str| {
+        let mut list = Vec::new();
+        for (i, layer) in parsed.iter().enumerate() {
+            if i > 0 {
+                list.push(ComponentValue::Delim(','));
+            }
+            match get(layer) {
+                Some(value) => list.extend(value.iter().cloned()),
+                None => list.extend(initial(initial_value)),
+            }
+        }
+        list
+    };
+
+    let color = parsed
+        .last()
+        .and_then(|layer| layer.color.clone())
+        .unwrap_or_else(|| initial("transparent"));
+    Ok(vec![
+        longhand("background-image", list(|l| &l.image, "none")),
+        longhand("background-position", list(|l| &l.position, "0% 0%")),
+        longhand("background-size", list(|l| &l.size, "auto")),
+        longhand("background-repeat", list(|l| &l.repeat, "repeat")),
+        longhand("background-attachment", list(|l| &l.attachment, "scroll")),
+        longhand("background-origin", list(|l| &l.origin, "padding-box")),
+        longhand("background-clip", list(|l| &l.clip, "border-box")),
+        longhand("background-color", color),
+    ])
+}
+
+/// https://www.w3.org/TR/css-counter-styles-3/#typedef-counter-style
+/// `<counter-style-name> | <symbols()>`, and a string is also valid for list-style-type.
+fn is_counter_style(value: &[ComponentValue]) -> bool {
+    match value {
+        [ComponentValue::Ident(_)] | [ComponentValue::StringToken(_)] => true,
+        [ComponentValue::Function(name), ..] => name.eq_ignore_ascii_case("symbols"),
+        _ => false,
+    }
+}
+
+/// https://www.w3.org/TR/css-lists-3/#list-style-property
+/// `<'list-style-position'> || <'list-style-image'> || <'list-style-type'>`
+fn expand_list_style(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let mut position = None;
+    let mut image = None;
+    let mut list_type = None;
+    let mut nones = 0;
+    for value in split_component_values(values) {
+        if is_keyword(value, &["none"]) {
+            // Both list-style-image and list-style-type accept none, so it's assigned after
+            // the others are known.
+            nones += 1;
+        } else if position.is_none() && is_keyword(value, &["inside", "outside"]) {
+            position = Some(value.to_vec());
+        } else if image.is_none() && is_image(value) {
+            image = Some(value.to_vec());
+        } else if list_type.is_none() && is_counter_style(value) {
+            list_type = Some(value.to_vec());
+        } else {
+            return invalid("list-style", values);
+        }
+    }
+    if values.is_empty() {
+        return invalid("list-style", values);
+    }
+
+    // "Using a value of none in the shorthand is potentially ambiguous, as none is a valid value
+    // for both list-style-image and list-style-type. To resolve this ambiguity, a value of none
+    // in the shorthand must be applied to whichever of the two properties aren't otherwise set by
+    // the shorthand."
+    let none = || Some(initial("none"));
+    match nones {
+        0 => {}
+        1 if image.is_none() && list_type.is_none() => (image, list_type) = (none(), none()),
+        1 if image.is_none() => image = none(),
+        1 if list_type.is_none() => list_type = none(),
+        2 if image.is_none() && list_type.is_none() => (image, list_type) = (none(), none()),
+        _ => return invalid("list-style", values),
+    }
+
+    Ok(vec![
+        longhand(
+            "list-style-position",
+            position.unwrap_or_else(|| initial("outside")),
+        ),
+        longhand("list-style-image", image.unwrap_or_else(|| initial("none"))),
+        longhand(
+            "list-style-type",
+            list_type.unwrap_or_else(|| initial("disc")),
+        ),
+    ])
+}
+
+/// https://www.w3.org/TR/css-text-decor-4/#text-decoration-property
+/// `<'text-decoration-line'> || <'text-decoration-thickness'> || <'text-decoration-style'> ||
+/// <'text-decoration-color'>`
+fn expand_text_decoration(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let mut line: Option<Vec<ComponentValue>> = None;
+    let mut line_keywords: Vec<String> = Vec::new();
+    let mut thickness = None;
+    let mut style = None;
+    let mut color = None;
+    for value in split_component_values(values) {
+        if line.is_none() && is_keyword(value, &["none"]) {
+            line = Some(value.to_vec());
+        } else if let [ComponentValue::Ident(ident)] = value {
+            let ident = ident.to_ascii_lowercase();
+            let is_line =
+                ["underline", "overline", "line-through", "blink"].contains(&ident.as_str());
+            if is_line && !line_keywords.contains(&ident) && line.is_none() {
+                line_keywords.push(ident);
+            } else if style.is_none()
+                && ["solid", "double", "dotted", "dashed", "wavy"].contains(&ident.as_str())
+            {
+                style = Some(value.to_vec());
+            } else if thickness.is_none() && ["auto", "from-font"].contains(&ident.as_str()) {
+                thickness = Some(value.to_vec());
+            } else if color.is_none() && is_color(value) {
+                color = Some(value.to_vec());
+            } else {
+                return invalid("text-decoration", values);
+            }
+        } else if thickness.is_none() && LengthPercentage::from_component_values(value).is_ok() {
+            thickness = Some(value.to_vec());
+        } else if color.is_none() && is_color(value) {
+            color = Some(value.to_vec());
+        } else {
+            return invalid("text-decoration", values);
+        }
+    }
+    if values.is_empty() {
+        return invalid("text-decoration", values);
+    }
+    if !line_keywords.is_empty() {
+        line = Some(
+            line_keywords
+                .into_iter()
+                .map(ComponentValue::Ident)
+                .collect(),
+        );
+    }
+
+    Ok(vec![
+        longhand(
+            "text-decoration-line",
+            line.unwrap_or_else(|| initial("none")),
+        ),
+        longhand(
+            "text-decoration-thickness",
+            thickness.unwrap_or_else(|| initial("auto")),
+        ),
+        longhand(
+            "text-decoration-style",
+            style.unwrap_or_else(|| initial("solid")),
+        ),
+        longhand(
+            "text-decoration-color",
+            color.unwrap_or_else(|| initial("currentcolor")),
+        ),
+    ])
+}
+
+/// https://www.w3.org/TR/css-overflow-3/#propdef-overflow
+/// `[ visible | hidden | clip | scroll | auto ]{1,2}`. "If the second value is omitted, it is
+/// copied from the first."
+fn expand_overflow(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let keywords = ["visible", "hidden", "clip", "scroll", "auto"];
+    let parts = split_component_values(values);
+    if !parts.iter().all(|part| is_keyword(part, &keywords)) {
+        return invalid("overflow", values);
+    }
+    let (x, y) = match parts.as_slice() {
+        [both] => (both.to_vec(), both.to_vec()),
+        [x, y] => (x.to_vec(), y.to_vec()),
+        _ => return invalid("overflow", values),
+    };
+    Ok(vec![longhand("overflow-x", x), longhand("overflow-y", y)])
+}
+
+fn is_flex_factor(value: &[ComponentValue]) -> bool {
+    matches!(value, [ComponentValue::Number(n)] if *n >= 0.0)
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#flex-property
+/// `none | [ <'flex-grow'> <'flex-shrink'>? || <'flex-basis'> ]`. "When omitted from the flex
+/// shorthand, its specified value is 1" for the flex factors, and 0 for the flex basis. "A
+/// unitless zero that is not already preceded by two flex factors must be interpreted as a flex
+/// factor."
+fn expand_flex(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let parts = split_component_values(values);
+    let flex = |grow: &str, shrink: &str, basis: Vec<ComponentValue>| {
+        vec![
+            longhand("flex-grow", initial(grow)),
+            longhand("flex-shrink", initial(shrink)),
+            longhand("flex-basis", basis),
+        ]
+    };
+    match parts.as_slice() {
+        [keyword] if is_keyword(keyword, &["none"]) => return Ok(flex("0", "0", initial("auto"))),
+        [keyword] if is_keyword(keyword, &["auto"]) => return Ok(flex("1", "1", initial("auto"))),
+        _ => {}
+    }
+
+    let mut factors: Option<Vec<&[ComponentValue]>> = None;
+    let mut basis = None;
+    let mut i = 0;
+    while i < parts.len() {
+        let part = parts[i];
+        if factors.is_none() && is_flex_factor(part) {
+            let mut f = vec![part];
+            if parts.get(i + 1).is_some_and(|next| is_flex_factor(next)) {
+                f.push(parts[i + 1]);
+                i += 1;
+            }
+            factors = Some(f);
+        } else if basis.is_none()
+            && (is_keyword(part, &["auto", "content"]) || is_non_negative_length_percentage(part))
+        {
+            basis = Some(part.to_vec());
+        } else {
+            return invalid("flex", values);
+        }
+        i += 1;
+    }
+    if factors.is_none() && basis.is_none() {
+        return invalid("flex", values);
+    }
+
+    let factors = factors.unwrap_or_default();
+    Ok(vec![
+        longhand(
+            "flex-grow",
+            factors.first().map_or_else(|| initial("1"), |f| f.to_vec()),
+        ),
+        longhand(
+            "flex-shrink",
+            factors.get(1).map_or_else(|| initial("1"), |f| f.to_vec()),
+        ),
+        longhand("flex-basis", basis.unwrap_or_else(|| initial("0%"))),
+    ])
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#flex-flow-property
+/// `<'flex-direction'> || <'flex-wrap'>`
+fn expand_flex_flow(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let mut direction = None;
+    let mut wrap = None;
+    for part in split_component_values(values) {
+        if direction.is_none()
+            && is_keyword(part, &["row", "row-reverse", "column", "column-reverse"])
+        {
+            direction = Some(part.to_vec());
+        } else if wrap.is_none() && is_keyword(part, &["nowrap", "wrap", "wrap-reverse"]) {
+            wrap = Some(part.to_vec());
+        } else {
+            return invalid("flex-flow", values);
+        }
+    }
+    if direction.is_none() && wrap.is_none() {
+        return invalid("flex-flow", values);
+    }
+    Ok(vec![
+        longhand(
+            "flex-direction",
+            direction.unwrap_or_else(|| initial("row")),
+        ),
+        longhand("flex-wrap", wrap.unwrap_or_else(|| initial("nowrap"))),
+    ])
+}
+
+/// https://www.w3.org/TR/css-align-3/#gap-shorthand
+/// `<'row-gap'> <'column-gap'>?`. "If <'column-gap'> is omitted, it's set to the same value as
+/// <'row-gap'>."
+fn expand_gap(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let parts = split_component_values(values);
+    if !parts
+        .iter()
+        .all(|part| is_keyword(part, &["normal"]) || is_non_negative_length_percentage(part))
+    {
+        return invalid("gap", values);
+    }
+    let (row, column) = match parts.as_slice() {
+        [both] => (both.to_vec(), both.to_vec()),
+        [row, column] => (row.to_vec(), column.to_vec()),
+        _ => return invalid("gap", values),
+    };
+    Ok(vec![
+        longhand("row-gap", row),
+        longhand("column-gap", column),
+    ])
+}
+
+/// Splits `values` at the slashes that are not in a function.
+fn split_at_slashes(values: &[ComponentValue]) -> Vec<Vec<ComponentValue>> {
+    let mut sections = vec![Vec::new()];
+    for part in split_component_values(values) {
+        if part == [ComponentValue::Delim('/')] {
+            sections.push(Vec::new());
+        } else if let Some(section) = sections.last_mut() {
+            section.extend_from_slice(part);
+        }
+    }
+    sections
+}
+
+/// https://www.w3.org/TR/css-grid-1/#typedef-grid-row-start-grid-line
+/// "When the second value is omitted, if the first value is a <custom-ident>, the
+/// grid-row-end/grid-column-end longhand is also set to that <custom-ident>; otherwise, it is
+/// set to auto."
+fn omitted_grid_line(line: &[ComponentValue]) -> Vec<ComponentValue> {
+    match line {
+        [ComponentValue::Ident(ident)]
+            if !ident.eq_ignore_ascii_case("auto") && !ident.eq_ignore_ascii_case("span") =>
+        {
+            line.to_vec()
+        }
+        _ => initial("auto"),
+    }
+}
+
+/// https://www.w3.org/TR/css-grid-1/#placement-shorthands
+/// `<grid-line> [ / <grid-line> ]?` for grid-row and grid-column.
+fn expand_grid_line(property: &str, values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let sections = split_at_slashes(values);
+    if sections.len() > 2 || sections.iter().any(|section| section.is_empty()) {
+        return invalid(property, values);
+    }
+    let end = match sections.get(1) {
+        Some(end) => end.clone(),
+        None => omitted_grid_line(&sections[0]),
+    };
+    Ok(vec![
+        longhand(&format!("{}-start", property), sections[0].clone()),
+        longhand(&format!("{}-end", property), end),
+    ])
+}
+
+/// https://www.w3.org/TR/css-grid-1/#propdef-grid-area
+/// `<grid-line> [ / <grid-line> ]{0,3}`, which are grid-row-start, grid-column-start,
+/// grid-row-end and grid-column-end. An omitted grid-column-start and grid-row-end copy
+/// grid-row-start, and an omitted grid-column-end copies grid-column-start, if it's a
+/// <custom-ident>.
+fn expand_grid_area(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    let sections = split_at_slashes(values);
+    if sections.len() > 4 || sections.iter().any(|section| section.is_empty()) {
+        return invalid("grid-area", values);
+    }
+    let row_start = sections[0].clone();
+    let column_start = sections
+        .get(1)
+        .cloned()
+        .unwrap_or_else(|| omitted_grid_line(&row_start));
+    let row_end = sections
+        .get(2)
+        .cloned()
+        .unwrap_or_else(|| omitted_grid_line(&row_start));
+    let column_end = sections
+        .get(3)
+        .cloned()
+        .unwrap_or_else(|| omitted_grid_line(&column_start));
+    Ok(vec![
+        longhand("grid-row-start", row_start),
+        longhand("grid-column-start", column_start),
+        longhand("grid-row-end", row_end),
+        longhand("grid-column-end", column_end),
+    ])
+}
+
+/// https://www.w3.org/TR/css-grid-1/#explicit-grid-shorthand
+/// `none | [ <'grid-template-rows'> / <'grid-template-columns'> ] |
+/// [ <line-names>? <string> <track-size>? <line-names>? ]+ [ / <explicit-track-list> ]?`
+/// In the last form, the strings are the areas, and each of them is followed by the size of its
+/// row, which is auto if it's omitted.
+fn expand_grid_template(values: &[ComponentValue]) -> Result<Vec<Declaration>, Error> {
+    if is_keyword(values, &["none"]) {
+        return Ok(vec![
+            longhand("grid-template-rows", initial("none")),
+            longhand("grid-template-columns", initial("none")),
+            longhand("grid-template-areas", initial("none")),
+        ]);
+    }
+    let sections = split_at_slashes(values);
+    if sections.len() > 2 || sections.iter().any(|section| section.is_empty()) {
+        return invalid("grid-template", values);
+    }
+    let is_string = |part: &[ComponentValue]| matches!(part, [ComponentValue::StringToken(_)]);
+    let parts = split_component_values(&sections[0]);
+    if !parts.iter().any(|part| is_string(part)) {
+        if sections.len() != 2 {
+            return invalid("grid-template", values);
+        }
+        return Ok(vec![
+            longhand("grid-template-rows", sections[0].clone()),
+            longhand("grid-template-columns", sections[1].clone()),
+            longhand("grid-template-areas", initial("none")),
+        ]);
+    }
+
+    let mut rows = Vec::new();
+    let mut areas = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        if is_string(part) {
+            areas.extend_from_slice(part);
+            let has_size = parts
+                .get(i + 1)
+                .is_some_and(|next| !is_string(next) && *next != [ComponentValue::Delim('[')]);
+            if !has_size {
+                rows.extend(initial("auto"));
+            }
+        } else {
+            rows.extend_from_slice(part);
+        }
+    }
+    Ok(vec![
+        longhand("grid-template-rows", rows),
+        longhand(
+            "grid-template-columns",
+            sections.get(1).cloned().unwrap_or_else(|| initial("none")),
+        ),
+        longhand("grid-template-areas", areas),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(property: &str, css: &str) -> Result<Vec<(String, Vec<ComponentValue>)>, Error> {
+        let declaration = longhand(property, initial(css));
+        Ok(expand_declaration(&declaration)?
+            .into_iter()
+            .map(|declaration| (declaration.property, declaration.value))
+            .collect())
+    }
+
+    fn expanded(declarations: &[(&str, &str)]) -> Vec<(String, Vec<ComponentValue>)> {
+        declarations
+            .iter()
+            .map(|(property, css)| (property.to_string(), initial(css)))
+            .collect()
+    }
+
+    #[test]
+    fn test_longhand() {
+        assert_eq!(Ok(expanded(&[("color", "red")])), expand("color", "red"));
+        assert_eq!(
+            Ok(expanded(&[("overflow-wrap", "anywhere")])),
+            expand("word-wrap", "anywhere")
+        );
+    }
+
+    #[test]
+    fn test_four_sides() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("margin-top", "1px"),
+                ("margin-right", "calc(2px + 1%)"),
+                ("margin-bottom", "3px"),
+                ("margin-left", "calc(2px + 1%)"),
+            ])),
+            expand("margin", "1px calc(2px + 1%) 3px")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("border-top-style", "solid"),
+                ("border-right-style", "none"),
+                ("border-bottom-style", "solid"),
+                ("border-left-style", "none"),
+            ])),
+            expand("border-style", "solid none")
+        );
+        assert!(expand("padding", "1px 2px 3px 4px 5px").is_err());
+    }
+
+    #[test]
+    fn test_border() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("border-left-width", "medium"),
+                ("border-left-style", "dashed"),
+                ("border-left-color", "rgb(1, 2, 3)"),
+            ])),
+            expand("border-left", "rgb(1, 2, 3) dashed")
+        );
+        assert_eq!(12, expand("border", "1px solid").unwrap().len());
+        assert!(expand("border", "1px 2px").is_err());
+    }
+
+    #[test]
+    fn test_border_radius() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("border-top-left-radius", "1px"),
+                ("border-top-right-radius", "2px"),
+                ("border-bottom-right-radius", "3px"),
+                ("border-bottom-left-radius", "2px"),
+            ])),
+            expand("border-radius", "1px 2px 3px")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("border-top-left-radius", "10% 5px"),
+                ("border-top-right-radius", "10% 6px"),
+                ("border-bottom-right-radius", "10% 5px"),
+                ("border-bottom-left-radius", "10% 6px"),
+            ])),
+            expand("border-radius", "10% / 5px 6px")
+        );
+        assert!(expand("border-radius", "-1px").is_err());
+        assert!(expand("border-radius", "1px / 2px / 3px").is_err());
+    }
+
+    #[test]
+    fn test_css_wide_keyword() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("list-style-position", "inherit"),
+                ("list-style-image", "inherit"),
+                ("list-style-type", "inherit"),
+            ])),
+            expand("list-style", "inherit")
+        );
+    }
+
+    #[test]
+    fn test_font() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("font-style", "italic"),
+                ("font-variant-caps", "normal"),
+                ("font-weight", "bold"),
+                ("font-stretch", "normal"),
+                ("font-size", "12px"),
+                ("line-height", "1.5"),
+                ("font-family", r#""Helvetica Neue", Arial, sans-serif"#),
+            ])),
+            expand(
+                "font",
+                r#"normal bold italic 12px/1.5 "Helvetica Neue", Arial, sans-serif"#
+            )
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("font-style", "normal"),
+                ("font-variant-caps", "normal"),
+                ("font-weight", "normal"),
+                ("font-stretch", "normal"),
+                ("font-size", "x-large"),
+                ("line-height", "normal"),
+                ("font-family", "monospace"),
+            ])),
+            expand("font", "x-large monospace")
+        );
+        // font-size and font-family are required.
+        assert!(expand("font", "bold 12px").is_err());
+        assert!(expand("font", "bold serif").is_err());
+        assert!(expand("font", "12px/ serif").is_err());
+    }
+
+    #[test]
+    fn test_background() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("background-image", "none"),
+                ("background-position", "0% 0%"),
+                ("background-size", "auto"),
+                ("background-repeat", "repeat"),
+                ("background-attachment", "scroll"),
+                ("background-origin", "padding-box"),
+                ("background-clip", "border-box"),
+                ("background-color", "#ff0"),
+            ])),
+            expand("background", "#ff0")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("background-image", r#"url("a.png"), none"#),
+                ("background-position", "center, left 10px top 5%"),
+                ("background-size", "auto, cover"),
+                ("background-repeat", "no-repeat, repeat-x"),
+                ("background-attachment", "scroll, fixed"),
+                ("background-origin", "padding-box, content-box"),
+                ("background-clip", "border-box, content-box"),
+                ("background-color", "red"),
+            ])),
+            expand(
+                "background",
+                r#"url("a.png") center no-repeat, red left 10px top 5% / cover repeat-x fixed content-box"#
+            )
+        );
+        // Only the final layer can have a color.
+        assert!(expand("background", "red, blue").is_err());
+    }
+
+    #[test]
+    fn test_list_style() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("list-style-position", "inside"),
+                ("list-style-image", "none"),
+                ("list-style-type", "square"),
+            ])),
+            expand("list-style", "square inside")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("list-style-position", "outside"),
+                ("list-style-image", "none"),
+                ("list-style-type", "none"),
+            ])),
+            expand("list-style", "none")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("list-style-position", "outside"),
+                ("list-style-image", "none"),
+                ("list-style-type", "decimal"),
+            ])),
+            expand("list-style", "none decimal")
+        );
+        assert!(expand("list-style", "none none none").is_err());
+    }
+
+    #[test]
+    fn test_text_decoration() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("text-decoration-line", "underline overline"),
+                ("text-decoration-thickness", "2px"),
+                ("text-decoration-style", "wavy"),
+                ("text-decoration-color", "blue"),
+            ])),
+            expand("text-decoration", "underline blue wavy overline 2px")
+        );
+        assert!(expand("text-decoration", "none underline").is_err());
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("overflow-x", "hidden"),
+                ("overflow-y", "hidden")
+            ])),
+            expand("overflow", "hidden")
+        );
+        assert_eq!(
+            Ok(expanded(&[("overflow-x", "clip"), ("overflow-y", "auto")])),
+            expand("overflow", "clip auto")
+        );
+        assert!(expand("overflow", "scroll scroll scroll").is_err());
+        assert!(expand("overflow", "10px").is_err());
+    }
+
+    #[test]
+    fn test_flex() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("flex-grow", "0"),
+                ("flex-shrink", "0"),
+                ("flex-basis", "auto")
+            ])),
+            expand("flex", "none")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("flex-grow", "2"),
+                ("flex-shrink", "1"),
+                ("flex-basis", "0%")
+            ])),
+            expand("flex", "2")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("flex-grow", "1"),
+                ("flex-shrink", "1"),
+                ("flex-basis", "100px")
+            ])),
+            expand("flex", "100px")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("flex-grow", "0"),
+                ("flex-shrink", "3"),
+                ("flex-basis", "content")
+            ])),
+            expand("flex", "content 0 3")
+        );
+        assert!(expand("flex", "1 2 3").is_err());
+        assert!(expand("flex", "-1").is_err());
+        assert!(expand("flex", "auto auto").is_err());
+    }
+
+    #[test]
+    fn test_flex_flow_and_gap() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("flex-direction", "column"),
+                ("flex-wrap", "wrap")
+            ])),
+            expand("flex-flow", "wrap column")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("flex-direction", "row"),
+                ("flex-wrap", "wrap-reverse")
+            ])),
+            expand("flex-flow", "wrap-reverse")
+        );
+        assert!(expand("flex-flow", "row column").is_err());
+
+        assert_eq!(
+            Ok(expanded(&[("row-gap", "10px"), ("column-gap", "10px")])),
+            expand("gap", "10px")
+        );
+        assert_eq!(
+            Ok(expanded(&[("row-gap", "normal"), ("column-gap", "5%")])),
+            expand("gap", "normal 5%")
+        );
+        assert!(expand("gap", "-1px").is_err());
+    }
+
+    #[test]
+    fn test_grid() {
+        assert_eq!(
+            Ok(expanded(&[
+                ("grid-row-start", "1"),
+                ("grid-row-end", "span 2")
+            ])),
+            expand("grid-row", "1 / span 2")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("grid-column-start", "main"),
+                ("grid-column-end", "main")
+            ])),
+            expand("grid-column", "main")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("grid-column-start", "2"),
+                ("grid-column-end", "auto")
+            ])),
+            expand("grid-column", "2")
+        );
+        assert!(expand("grid-row", "1 / 2 / 3").is_err());
+
+        assert_eq!(
+            Ok(expanded(&[
+                ("grid-row-start", "header"),
+                ("grid-column-start", "header"),
+                ("grid-row-end", "header"),
+                ("grid-column-end", "header"),
+            ])),
+            expand("grid-area", "header")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("grid-row-start", "1"),
+                ("grid-column-start", "2"),
+                ("grid-row-end", "auto"),
+                ("grid-column-end", "auto"),
+            ])),
+            expand("grid-area", "1 / 2")
+        );
+
+        assert_eq!(
+            Ok(expanded(&[
+                ("grid-template-rows", "100px 1fr"),
+                ("grid-template-columns", "repeat(2, 1fr)"),
+                ("grid-template-areas", "none"),
+            ])),
+            expand("grid-template", "100px 1fr / repeat(2, 1fr)")
+        );
+        assert_eq!(
+            Ok(expanded(&[
+                ("grid-template-rows", "[top] 40px auto"),
+                ("grid-template-columns", "200px 1fr"),
+                ("grid-template-areas", "\"head head\" \"nav main\""),
+            ])),
+            expand(
+                "grid-template",
+                "[top] \"head head\" 40px \"nav main\" / 200px 1fr"
+            )
+        );
+        assert!(expand("grid-template", "100px").is_err());
+    }
+}
@@ -6,6 +6,17 @@ pub enum CssToken {
     HashToken(String),
     Delim(char),
     Number(f64),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-dimension-token
+    Dimension(f64, String),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-percentage-token
+    Percentage(f64),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-function-token
+    /// The name of a function such as `calc(`. The arguments follow as separate tokens and end with
+    /// a CloseParenthesis.
+    Function(String),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-url-token
+    /// An unquoted `url(...)`. A quoted URL is a Function followed by a StringToken.
+    Url(String),
     Colon,
     SemiColon,
     OpenParenthesis,
@@ -30,22 +41,65 @@ impl CssTokenizer {
             input: css.chars().collect(),
         }
     }
+    /// https://www.w3.org/TR/css-syntax-3/#consume-string-token
+    /// "This algorithm may be called with an ending code point, which denotes the code point that
+    /// ends the string."
     fn consume_string_token(&mut self) -> String {
+        let ending = self.input[self.pos];
         let mut s = String::new();
         loop {
+            self.pos += 1;
             if self.pos >= self.input.len() {
                 return s;
             }
-            self.pos += 1;
             let c = self.input[self.pos];
             match c {
-                '"' | '\'' => break,
+                c if c == ending => break,
+                '\\' => {
+                    match self.input.get(self.pos + 1) {
+                        // An escaped newline continues the string on the next line.
+                        Some('\n') => self.pos += 1,
+                        Some(_) => s.push(self.consume_escaped_code_point()),
+                        None => {}
+                    }
+                }
                 _ => s.push(c),
             }
         }
         s
     }
 
+    /// https://www.w3.org/TR/css-syntax-3/#consume-escaped-code-point
+    /// "Consume as many hex digits as possible, but no more than 5. Note that this means 1-6 hex
+    /// digits have been consumed in total. If the next input code point is whitespace, consume it
+    /// as well."
+    fn consume_escaped_code_point(&mut self) -> char {
+        self.pos += 1;
+        let c = self.input[self.pos];
+        if !c.is_ascii_hexdigit() {
+            return c;
+        }
+        let mut value = 0u32;
+        let mut digits = 0;
+        while digits < 6 {
+            match self.input.get(self.pos).and_then(|c| c.to_digit(16)) {
+                Some(d) => {
+                    value = value * 16 + d;
+                    digits += 1;
+                    self.pos += 1;
+                }
+                None => break,
+            }
+        }
+        if !matches!(self.input.get(self.pos), Some(' ' | '\t' | '\n')) {
+            self.pos -= 1;
+        }
+        match char::from_u32(value) {
+            Some(c) if value != 0 => c,
+            _ => '\u{FFFD}',
+        }
+    }
+
     fn consume_numeric_token(&mut self) -> f64 {
         let mut num = 0f64;
         let mut floating = false;
@@ -76,12 +130,127 @@ impl CssTokenizer {
         }
         num
     }
+    /// https://www.w3.org/TR/css-syntax-3/#consume-numeric-token
+    fn consume_numeric_token_with_unit(&mut self) -> CssToken {
+        let num = self.consume_numeric_token();
+        match self.input.get(self.pos) {
+            Some('%') => {
+                self.pos += 1;
+                CssToken::Percentage(num)
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let unit = self.consume_ident_token();
+                CssToken::Dimension(num, unit)
+            }
+            _ => CssToken::Number(num),
+        }
+    }
+
+    /// https://www.w3.org/TR/css-syntax-3/#consume-ident-like-token
+    fn consume_ident_like_token(&mut self) -> CssToken {
+        let name = self.consume_ident_token();
+        if self.input.get(self.pos) == Some(&'(') {
+            self.pos += 1;
+            // "If string's value is an ASCII case-insensitive match for "url", and the next input
+            // code point is U+0028 LEFT PARENTHESIS ((), consume it. While the next two input code
+            // points are whitespace, consume the next input code point. If the next one or two
+            // input code points are U+0022 QUOTATION MARK ("), U+0027 APOSTROPHE ('), or whitespace
+            // followed by U+0022 QUOTATION MARK (") or U+0027 APOSTROPHE ('), then create a
+            // <function-token> with its value set to string and return it. Otherwise, consume a url
+            // token, and return it."
+            if name.eq_ignore_ascii_case("url") {
+                let mut next = self.pos;
+                while matches!(self.input.get(next), Some(' ' | '\t' | '\n')) {
+                    next += 1;
+                }
+                if !matches!(self.input.get(next), Some('"' | '\'')) {
+                    self.pos = next;
+                    return self.consume_url_token();
+                }
+            }
+            CssToken::Function(name)
+        } else {
+            CssToken::Ident(name)
+        }
+    }
+
+    /// https://www.w3.org/TR/css-syntax-3/#consume-url-token
+    /// Consumes the URL up to the closing parenthesis. White space around the URL is dropped, and
+    /// white space in it makes the token a bad url, which is returned as an empty URL.
+    fn consume_url_token(&mut self) -> CssToken {
+        let mut url = String::new();
+        let mut bad = false;
+        while let Some(&c) = self.input.get(self.pos) {
+            self.pos += 1;
+            match c {
+                ')' => break,
+                ' ' | '\t' | '\n' => {
+                    while matches!(self.input.get(self.pos), Some(' ' | '\t' | '\n')) {
+                        self.pos += 1;
+                    }
+                    if !matches!(self.input.get(self.pos), Some(')') | None) {
+                        bad = true;
+                    }
+                }
+                '"' | '\'' | '(' => bad = true,
+                '\\' if self.pos < self.input.len() => {
+                    self.pos -= 1;
+                    url.push(self.consume_escaped_code_point());
+                    self.pos += 1;
+                }
+                _ => url.push(c),
+            }
+        }
+        if bad {
+            url = String::new();
+        }
+        CssToken::Url(url)
+    }
+
+    /// https://www.w3.org/TR/css-syntax-3/#consume-comment
+    fn consume_comment(&mut self) {
+        self.pos += 2;
+        while self.pos < self.input.len() {
+            if self.input[self.pos] == '*' && self.input.get(self.pos + 1) == Some(&'/') {
+                self.pos += 2;
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// https://www.w3.org/TR/css-syntax-3/#starts-with-a-number
+    fn starts_with_number(&self, offset: usize) -> bool {
+        match self.input.get(self.pos + offset) {
+            Some(c) if c.is_ascii_digit() => true,
+            Some('.') => {
+                matches!(self.input.get(self.pos + offset + 1), Some(c) if c.is_ascii_digit())
+            }
+            _ => false,
+        }
+    }
+
+    fn consume_signed_numeric_token(&mut self) -> CssToken {
+        let negative = self.input[self.pos] == '-';
+        self.pos += 1;
+        let sign = if negative { -1.0 } else { 1.0 };
+        match self.consume_numeric_token_with_unit() {
+            CssToken::Number(n) => CssToken::Number(sign * n),
+            CssToken::Percentage(n) => CssToken::Percentage(sign * n),
+            CssToken::Dimension(n, unit) => CssToken::Dimension(sign * n, unit),
+            t => t,
+        }
+    }
+
     fn consume_ident_token(&mut self) -> String {
         let mut s = String::new();
         s.push(self.input[self.pos]);
         loop {
             self.pos += 1;
-            let c = self.input[self.pos];
+            let c = match self.input.get(self.pos) {
+                Some(c) => *c,
+                None => break,
+            };
             match c {
                 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => {
                     s.push(c);
@@ -107,7 +276,23 @@ impl Iterator for CssTokenizer {
                     CssToken::StringToken(value)
                 }
                 '0'..='9' => {
-                    let t = CssToken::Number(self.consume_numeric_token());
+                    let t = self.consume_numeric_token_with_unit();
+                    self.pos -= 1;
+                    t
+                }
+                '.' if self.starts_with_number(0) => {
+                    let t = self.consume_numeric_token_with_unit();
+                    self.pos -= 1;
+                    t
+                }
+                '+' | '-' if self.starts_with_number(1) => {
+                    let t = self.consume_signed_numeric_token();
+                    self.pos -= 1;
+                    t
+                }
+                '-' if matches!(self.input.get(self.pos + 1), Some(c) if c.is_ascii_alphabetic() || *c == '-' || *c == '_') =>
+                {
+                    let t = self.consume_ident_like_token();
                     self.pos -= 1;
                     t
                 }
@@ -117,7 +302,7 @@ impl Iterator for CssTokenizer {
                     CssToken::HashToken(value)
                 }
                 '_' => {
-                    let t = CssToken::Ident(self.consume_ident_token());
+                    let t = self.consume_ident_like_token();
                     self.pos -= 1;
                     t
                 }
@@ -135,7 +320,7 @@ impl Iterator for CssTokenizer {
                     }
                 }
                 'a'..='z' | 'A'..='Z' => {
-                    let t = CssToken::Ident(self.consume_ident_token());
+                    let t = self.consume_ident_like_token();
                     self.pos -= 1;
                     t
                 }
@@ -147,13 +332,15 @@ impl Iterator for CssTokenizer {
                 ';' => CssToken::SemiColon,
                 '{' => CssToken::OpenCurly,
                 '}' => CssToken::CloseCurly,
-                ' ' | '\n' => {
-                    self.pos += 1;
+                '/' if self.input.get(self.pos + 1) == Some(&'*') => {
+                    self.consume_comment();
                     continue;
                 }
-                _ => {
-                    unimplemented!("char {} is not supported yes", c);
+                ' ' | '\n' | '\t' | '\r' => {
+                    self.pos += 1;
+                    continue;
                 }
+                _ => CssToken::Delim(c),
             };
             self.pos += 1;
             return Some(token);
@@ -165,6 +352,7 @@ impl Iterator for CssTokenizer {
 mod tests {
     use super::*;
     use alloc::string::ToString;
+    use alloc::vec;
     #[test]
     fn test_empty() {
         let style = "".to_string();
@@ -255,4 +443,71 @@ mod tests {
         }
         assert!(t.next().is_none());
     }
+    #[test]
+    fn test_dimension_and_percentage() {
+        let style = "(min-width: 600px) { width: 50%; }".to_string();
+        let mut t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::OpenParenthesis,
+            CssToken::Ident("min-width".to_string()),
+            CssToken::Colon,
+            CssToken::Dimension(600.0, "px".to_string()),
+            CssToken::CloseParenthesis,
+            CssToken::OpenCurly,
+            CssToken::Ident("width".to_string()),
+            CssToken::Colon,
+            CssToken::Percentage(50.0),
+            CssToken::SemiColon,
+            CssToken::CloseCurly,
+        ];
+        for e in expected {
+            assert_eq!(Some(e.clone()), t.next());
+        }
+        assert!(t.next().is_none());
+    }
+    #[test]
+    fn test_function_and_signed_numbers() {
+        let style = "calc(100% - -.5em) /* comment */ * -webkit-box +2px".to_string();
+        let t = CssTokenizer::new(style);
+        let expected = vec![
+            CssToken::Function("calc".to_string()),
+            CssToken::Percentage(100.0),
+            CssToken::Delim('-'),
+            CssToken::Dimension(-0.5, "em".to_string()),
+            CssToken::CloseParenthesis,
+            CssToken::Delim('*'),
+            CssToken::Ident("-webkit-box".to_string()),
+            CssToken::Dimension(2.0, "px".to_string()),
+        ];
+        assert_eq!(expected, t.collect::<Vec<CssToken>>());
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let style = r#""it's" '"q"' "\f101 x" "\2014" "a\"b""#.to_string();
+        let t = CssTokenizer::new(style);
+        let expected = vec![
+            CssToken::StringToken("it's".to_string()),
+            CssToken::StringToken("\"q\"".to_string()),
+            CssToken::StringToken("\u{f101}x".to_string()),
+            CssToken::StringToken("\u{2014}".to_string()),
+            CssToken::StringToken("a\"b".to_string()),
+        ];
+        assert_eq!(expected, t.collect::<Vec<CssToken>>());
+    }
+
+    #[test]
+    fn test_url() {
+        let style = "url(img/a.png) url( b.gif ) url(\"c.bmp\") url(d e)".to_string();
+        let t = CssTokenizer::new(style);
+        let expected = vec![
+            CssToken::Url("img/a.png".to_string()),
+            CssToken::Url("b.gif".to_string()),
+            CssToken::Function("url".to_string()),
+            CssToken::StringToken("c.bmp".to_string()),
+            CssToken::CloseParenthesis,
+            CssToken::Url("".to_string()),
+        ];
+        assert_eq!(expected, t.collect::<Vec<CssToken>>());
+    }
 }
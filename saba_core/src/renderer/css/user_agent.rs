@@ -0,0 +1,276 @@
+use crate::renderer::css::cssom::CascadeOrigin;
+use crate::renderer::css::cssom::CssParser;
+use crate::renderer::css::cssom::Declaration;
+use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// https://html.spec.whatwg.org/multipage/rendering.html#rendering
+/// The default styles of HTML elements. Properties that are not listed here take their initial
+/// values, so an element that is not in this style sheet is rendered as an inline box.
+/// Rules are written with one selector each because selector lists are not supported yet, and
+/// only for the elements that the HTML parser creates.
+pub static USER_AGENT_STYLE: &str = r#"
+html { display: block; }
+head { display: none; }
+style { display: none; }
+script { display: none; }
+
+body { display: block; margin: 8px; }
+
+p { display: block; margin-top: 1em; margin-bottom: 1em; }
+pre { display: block; margin-top: 1em; margin-bottom: 1em; white-space: pre; font-family: monospace; }
+
+h1 { display: block; font-size: xx-large; margin-top: 0.67em; margin-bottom: 0.67em; }
+h2 { display: block; font-size: x-large; margin-top: 0.83em; margin-bottom: 0.83em; }
+
+ul { display: block; list-style-type: disc; counter-reset: list-item; margin-top: 1em; margin-bottom: 1em; padding-left: 40px; }
+ol { display: block; list-style-type: decimal; counter-reset: list-item; margin-top: 1em; margin-bottom: 1em; padding-left: 40px; }
+li { display: list-item; }
+
+a { text-decoration: underline; }
+"#;
+
+/// https://www.w3.org/TR/css-cascade-4/#cascade-origin-ua
+pub fn user_agent_style_sheet() -> StyleSheet {
+    let t = CssTokenizer::new(USER_AGENT_STYLE.to_string());
+    let mut sheet = CssParser::new(t).parse_stylesheet();
+    sheet.set_origin(CascadeOrigin::UserAgent);
+    sheet
+}
+
+fn hint(property: &str, value: &str) -> Declaration {
+    let mut declaration = Declaration::new();
+    declaration.set_property(property.to_string());
+    declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+    declaration
+}
+
+/// https://html.spec.whatwg.org/multipage/common-microsyntaxes.html#rules-for-parsing-dimension-values
+/// The length or percentage that a dimension attribute maps to. The number is the leading digits
+/// with an optional fraction, and it's a percentage if it's followed by "%".
+fn dimension_hint(value: &str) -> Option<String> {
+    let value = value.trim_start();
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let number = value[..digits].parse::<f64>().ok()?;
+    if value[digits..].starts_with('%') {
+        Some(format!("{}%", number))
+    } else {
+        Some(format!("{}px", number))
+    }
+}
+
+/// https://html.spec.whatwg.org/multipage/rendering.html#lists
+/// The list-style-type that the type attribute of ol, ul and li maps to.
+fn list_style_type_hint(value: &str) -> Option<&'static str> {
+    match value {
+        "1" => Some("decimal"),
+        "a" => Some("lower-alpha"),
+        "A" => Some("upper-alpha"),
+        "i" => Some("lower-roman"),
+        "I" => Some("upper-roman"),
+        _ => match value.to_ascii_lowercase().as_str() {
+            "none" => Some("none"),
+            "disc" => Some("disc"),
+            "circle" => Some("circle"),
+            "square" => Some("square"),
+            _ => None,
+        },
+    }
+}
+
+/// https://html.spec.whatwg.org/multipage/grouping-content.html#list-owner
+/// The number of the child li elements of a list.
+fn count_list_items(node: &Rc<RefCell<Node>>) -> i32 {
+    let mut count = 0;
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        if c.borrow().element_kind() == Some(ElementKind::Li) {
+            count += 1;
+        }
+        child = c.borrow().next_sibling();
+    }
+    count
+}
+
+/// https://html.spec.whatwg.org/multipage/rendering.html#presentational-hints
+/// The declarations that the attributes of `node` map to. They're in the author origin and are
+/// treated as if they were before all the author style sheets.
+pub fn presentational_hints(node: &Rc<RefCell<Node>>) -> Vec<Declaration> {
+    let element = match node.borrow().get_element() {
+        Some(element) => element,
+        None => return Vec::new(),
+    };
+    let integer = |name: &str| {
+        element
+            .get_attribute(name)
+            .and_then(|value| value.trim().parse::<i32>().ok())
+    };
+
+    let mut hints = Vec::new();
+    match element.kind() {
+        ElementKind::Ol => {
+            // https://html.spec.whatwg.org/multipage/grouping-content.html#dom-ol-start
+            // "If the reversed attribute is present, the default value is the number of child
+            // li elements." The list-item counter is changed by each li before it's shown, so
+            // the counter starts one step before the start.
+            if element.get_attribute("reversed").is_some() {
+                let start = integer("start").unwrap_or_else(|| count_list_items(node));
+                hints.push(hint(
+                    "counter-reset",
+                    &format!("reversed(list-item) {}", start.saturating_add(1)),
+                ));
+            } else if let Some(start) = integer("start") {
+                hints.push(hint(
+                    "counter-reset",
+                    &format!("list-item {}", start.saturating_sub(1)),
+                ));
+            }
+        }
+        // https://html.spec.whatwg.org/multipage/grouping-content.html#ordinal-value
+        ElementKind::Li => {
+            if let Some(value) = integer("value") {
+                hints.push(hint("counter-set", &format!("list-item {}", value)));
+            }
+        }
+        // https://html.spec.whatwg.org/multipage/rendering.html#images-3
+        // "The width and height attributes map to the dimension properties width and height on
+        // the element respectively."
+        ElementKind::Img => {
+            for name in ["width", "height"] {
+                if let Some(value) = element
+                    .get_attribute(name)
+                    .and_then(|value| dimension_hint(&value))
+                {
+                    hints.push(hint(name, &value));
+                }
+            }
+        }
+        _ => {}
+    }
+    if matches!(
+        element.kind(),
+        ElementKind::Ol | ElementKind::Ul | ElementKind::Li
+    ) {
+        if let Some(list_style_type) = element
+            .get_attribute("type")
+            .and_then(|value| list_style_type_hint(&value))
+        {
+            hints.push(hint("list-style-type", list_style_type));
+        }
+    }
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::cssom::ComponentValue;
+    use crate::renderer::css::cssom::Declaration;
+    use crate::renderer::css::cssom::Selector;
+    use crate::renderer::css::media::Device;
+    use crate::renderer::dom::node::Element;
+    use crate::renderer::dom::node::NodeKind;
+    use crate::renderer::html::attribute::Attribute;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn declarations_for(sheet: &StyleSheet, element: &str) -> Vec<Declaration> {
+        sheet
+            .style_rules(&Device::default())
+            .into_iter()
+            .filter(|rule| rule.selector == Selector::TypeSelector(element.to_string()))
+            .flat_map(|rule| rule.declarations.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_user_agent_style_sheet() {
+        let sheet = user_agent_style_sheet();
+        assert!(!sheet.rules.is_empty());
+
+        for element in ["head", "style", "script"] {
+            let declarations = declarations_for(&sheet, element);
+            assert_eq!(1, declarations.len());
+            assert_eq!("display", declarations[0].property);
+            assert_eq!(
+                vec![ComponentValue::Ident("none".to_string())],
+                declarations[0].value
+            );
+        }
+    }
+
+    #[test]
+    fn test_heading_font_size() {
+        let sheet = user_agent_style_sheet();
+        let declarations = declarations_for(&sheet, "h1");
+        assert!(declarations.iter().any(|d| d.property == "font-size"
+            && d.value == vec![ComponentValue::Ident("xx-large".to_string())]));
+    }
+
+    #[test]
+    fn test_list_presentational_hints() {
+        let hints = |element: &str, attributes: &[(&str, &str)], children: usize| {
+            let attributes = attributes
+                .iter()
+                .map(|(name, value)| {
+                    let mut attribute = Attribute::new();
+                    name.chars().for_each(|c| attribute.add_char(c, true));
+                    value.chars().for_each(|c| attribute.add_char(c, false));
+                    attribute
+                })
+                .collect();
+            let node = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+                element, attributes,
+            )))));
+            for _ in 0..children {
+                let child = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+                    "li",
+                    Vec::new(),
+                )))));
+                let last = node.borrow().last_child().upgrade();
+                match last {
+                    Some(last) => last.borrow_mut().set_next_sibling(Some(child.clone())),
+                    None => node.borrow_mut().set_first_child(Some(child.clone())),
+                }
+                node.borrow_mut().set_last_child(Rc::downgrade(&child));
+            }
+            presentational_hints(&node)
+                .into_iter()
+                .map(|d| (d.property, d.value))
+                .collect::<Vec<_>>()
+        };
+        let tokens = |css: &str| CssTokenizer::new(css.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("counter-reset".to_string(), tokens("list-item 4")),
+                ("list-style-type".to_string(), tokens("upper-roman")),
+            ],
+            hints("ol", &[("start", "5"), ("type", "I")], 0)
+        );
+        // A reversed list counts down from the number of its items.
+        assert_eq!(
+            vec![("counter-reset".to_string(), tokens("reversed(list-item) 4"))],
+            hints("ol", &[("reversed", "")], 3)
+        );
+        assert_eq!(
+            vec![("counter-set".to_string(), tokens("list-item -2"))],
+            hints("li", &[("value", "-2"), ("type", "unknown")], 0)
+        );
+        assert_eq!(
+            vec![("list-style-type".to_string(), tokens("square"))],
+            hints("ul", &[("type", "SQUARE")], 0)
+        );
+        assert!(hints("p", &[("type", "a")], 0).is_empty());
+    }
+}
@@ -0,0 +1,625 @@
+//! https://www.w3.org/TR/css-values-4/
+//! Typed CSS values. Declarations are parsed into specified values, which are turned into
+//! computed values once the font size and the viewport are known. Percentages are kept in the
+//! computed value and resolved against the containing block at layout time.
+
+use crate::error::Error;
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::media::Device;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+
+/// Rounds a CSS pixel value to the nearest integer device pixel.
+pub fn round(value: f64) -> i64 {
+    if value >= 0.0 {
+        (value + 0.5) as i64
+    } else {
+        (value - 0.5) as i64
+    }
+}
+
+/// Splits a declaration value into component values. A function such as `calc(...)` and a
+/// parenthesized block are kept together as one component value.
+/// https://www.w3.org/TR/css-syntax-3/#consume-component-value
+pub fn split_component_values(values: &[ComponentValue]) -> Vec<&[ComponentValue]> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut depth = 0;
+    for (i, value) in values.iter().enumerate() {
+        match value {
+            ComponentValue::Function(_) | ComponentValue::OpenParenthesis => depth += 1,
+            ComponentValue::CloseParenthesis => depth -= 1,
+            _ => {}
+        }
+        if depth <= 0 {
+            result.push(&values[start..=i]);
+            start = i + 1;
+            depth = 0;
+        }
+    }
+    if start < values.len() {
+        // An unclosed function is closed at the end of the declaration.
+        result.push(&values[start..]);
+    }
+    result
+}
+
+/// https://www.w3.org/TR/css-values-4/#lengths
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LengthUnit {
+    Px,
+    /// https://www.w3.org/TR/css-values-4/#font-relative-lengths
+    Em,
+    Rem,
+    /// https://www.w3.org/TR/css-values-4/#viewport-relative-lengths
+    Vw,
+    Vh,
+    Vmin,
+    Vmax,
+    /// https://www.w3.org/TR/css-values-4/#absolute-lengths
+    Cm,
+    Mm,
+    Q,
+    In,
+    Pt,
+    Pc,
+}
+
+impl LengthUnit {
+    pub fn from_str_unit(s: &str) -> Result<Self, Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "px" => Ok(Self::Px),
+            "em" => Ok(Self::Em),
+            "rem" => Ok(Self::Rem),
+            "vw" => Ok(Self::Vw),
+            "vh" => Ok(Self::Vh),
+            "vmin" => Ok(Self::Vmin),
+            "vmax" => Ok(Self::Vmax),
+            "cm" => Ok(Self::Cm),
+            "mm" => Ok(Self::Mm),
+            "q" => Ok(Self::Q),
+            "in" => Ok(Self::In),
+            "pt" => Ok(Self::Pt),
+            "pc" => Ok(Self::Pc),
+            _ => Err(Error::UnexpectedInput(format!(
+                "length unit {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// The values that relative lengths are computed against.
+/// https://www.w3.org/TR/css-values-4/#relative-lengths
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ComputeContext {
+    /// The computed font size of the element, in px.
+    font_size: f64,
+    /// The computed font size of the root element, in px.
+    root_font_size: f64,
+    viewport_width: f64,
+    viewport_height: f64,
+}
+
+impl ComputeContext {
+    pub fn new(font_size: f64, root_font_size: f64, device: &Device) -> Self {
+        Self {
+            font_size,
+            root_font_size,
+            viewport_width: device.width() as f64,
+            viewport_height: device.height() as f64,
+        }
+    }
+
+    pub fn font_size(&self) -> f64 {
+        self.font_size
+    }
+}
+
+impl Default for ComputeContext {
+    fn default() -> Self {
+        Self::new(16.0, 16.0, &Device::default())
+    }
+}
+
+/// https://www.w3.org/TR/css-values-4/#length-value
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Length {
+    value: f64,
+    unit: LengthUnit,
+}
+
+impl Length {
+    pub fn new(value: f64, unit: LengthUnit) -> Self {
+        Self { value, unit }
+    }
+
+    pub fn px(value: f64) -> Self {
+        Self::new(value, LengthUnit::Px)
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn unit(&self) -> LengthUnit {
+        self.unit
+    }
+
+    /// "For zero lengths the unit identifier is optional (i.e. can be syntactically represented as
+    /// the <number> 0)."
+    pub fn from_component_value(value: &ComponentValue) -> Result<Self, Error> {
+        match value {
+            ComponentValue::Number(n) if *n == 0.0 => Ok(Self::px(0.0)),
+            ComponentValue::Dimension(n, unit) => {
+                Ok(Self::new(*n, LengthUnit::from_str_unit(unit)?))
+            }
+            _ => Err(Error::UnexpectedInput(format!(
+                "length {:?} is not supported yet",
+                value
+            ))),
+        }
+    }
+
+    /// Converts the length to an absolute length in px.
+    pub fn compute(&self, context: &ComputeContext) -> Self {
+        let px_per_unit = match self.unit {
+            LengthUnit::Px => 1.0,
+            LengthUnit::Em => context.font_size,
+            LengthUnit::Rem => context.root_font_size,
+            LengthUnit::Vw => context.viewport_width / 100.0,
+            LengthUnit::Vh => context.viewport_height / 100.0,
+            LengthUnit::Vmin => context.viewport_width.min(context.viewport_height) / 100.0,
+            LengthUnit::Vmax => context.viewport_width.max(context.viewport_height) / 100.0,
+            // "1in = 2.54cm = 96px"
+            LengthUnit::Cm => 96.0 / 2.54,
+            LengthUnit::Mm => 96.0 / 25.4,
+            LengthUnit::Q => 96.0 / 101.6,
+            LengthUnit::In => 96.0,
+            LengthUnit::Pt => 96.0 / 72.0,
+            LengthUnit::Pc => 96.0 / 6.0,
+        };
+        Self::px(self.value * px_per_unit)
+    }
+
+    /// Returns the length in integer px. The length should be computed beforehand.
+    pub fn to_px(&self) -> i64 {
+        round(self.compute(&ComputeContext::default()).value)
+    }
+}
+
+/// https://www.w3.org/TR/css-values-4/#calc-syntax
+/// A node of a calculation tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcNode {
+    Number(f64),
+    Length(Length),
+    Percentage(f64),
+    Sum(Box<CalcNode>, Box<CalcNode>),
+    Difference(Box<CalcNode>, Box<CalcNode>),
+    Product(Box<CalcNode>, Box<CalcNode>),
+    Quotient(Box<CalcNode>, Box<CalcNode>),
+}
+
+/// The result of a calculation. A length-percentage is a linear combination of px and a percentage
+/// of the basis, e.g. `calc(50% - 10px)` is `{ px: -10, percent: 50 }`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum CalcValue {
+    Number(f64),
+    LengthPercentage { px: f64, percent: f64 },
+}
+
+impl CalcNode {
+    /// https://www.w3.org/TR/css-values-4/#calc-type-checking
+    /// Returns an error if the types of the operands don't match, e.g. `1px * 2px` or `1px + 2`.
+    fn evaluate(&self, context: &ComputeContext) -> Result<CalcValue, Error> {
+        let invalid = || {
+            Err(Error::UnexpectedInput(format!(
+                "invalid calculation {:?}",
+                self
+            )))
+        };
+        match self {
+            Self::Number(n) => Ok(CalcValue::Number(*n)),
+            Self::Length(l) => Ok(CalcValue::LengthPercentage {
+                px: l.compute(context).value(),
+                percent: 0.0,
+            }),
+            Self::Percentage(p) => Ok(CalcValue::LengthPercentage {
+                px: 0.0,
+                percent: *p,
+            }),
+            Self::Sum(a, b) | Self::Difference(a, b) => {
+                let sign = if let Self::Sum(_, _) = self {
+                    1.0
+                } else {
+                    -1.0
+                };
+                match (a.evaluate(context)?, b.evaluate(context)?) {
+                    (CalcValue::Number(a), CalcValue::Number(b)) => {
+                        Ok(CalcValue::Number(a + sign * b))
+                    }
+                    (
+                        CalcValue::LengthPercentage {
+                            px: a_px,
+                            percent: a_percent,
+                        },
+                        CalcValue::LengthPercentage {
+                            px: b_px,
+                            percent: b_percent,
+                        },
+                    ) => Ok(CalcValue::LengthPercentage {
+                        px: a_px + sign * b_px,
+                        percent: a_percent + sign * b_percent,
+                    }),
+                    _ => invalid(),
+                }
+            }
+            Self::Product(a, b) => match (a.evaluate(context)?, b.evaluate(context)?) {
+                (CalcValue::Number(a), CalcValue::Number(b)) => Ok(CalcValue::Number(a * b)),
+                (CalcValue::Number(n), CalcValue::LengthPercentage { px, percent })
+                | (CalcValue::LengthPercentage { px, percent }, CalcValue::Number(n)) => {
+                    Ok(CalcValue::LengthPercentage {
+                        px: px * n,
+                        percent: percent * n,
+                    })
+                }
+                _ => invalid(),
+            },
+            Self::Quotient(a, b) => match (a.evaluate(context)?, b.evaluate(context)?) {
+                (_, CalcValue::Number(0.0)) => invalid(),
+                (CalcValue::Number(a), CalcValue::Number(b)) => Ok(CalcValue::Number(a / b)),
+                (CalcValue::LengthPercentage { px, percent }, CalcValue::Number(n)) => {
+                    Ok(CalcValue::LengthPercentage {
+                        px: px / n,
+                        percent: percent / n,
+                    })
+                }
+                _ => invalid(),
+            },
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-values-4/#calc-syntax
+/// ```text
+/// <calc-sum> = <calc-product> [ [ '+' | '-' ] <calc-product> ]*
+/// <calc-product> = <calc-value> [ [ '*' | '/' ] <calc-value> ]*
+/// <calc-value> = <number> | <dimension> | <percentage> | ( <calc-sum> )
+/// ```
+struct CalcParser<'a> {
+    values: &'a [ComponentValue],
+    pos: usize,
+}
+
+impl<'a> CalcParser<'a> {
+    fn new(values: &'a [ComponentValue]) -> Self {
+        Self { values, pos: 0 }
+    }
+
+    fn consume(&mut self) -> Result<&'a ComponentValue, Error> {
+        let value = self
+            .values
+            .get(self.pos)
+            .ok_or_else(|| Error::UnexpectedInput("unexpected end of calc()".into()))?;
+        self.pos += 1;
+        Ok(value)
+    }
+
+    fn consume_close_parenthesis(&mut self) -> Result<(), Error> {
+        match self.consume()? {
+            ComponentValue::CloseParenthesis => Ok(()),
+            value => Err(Error::UnexpectedInput(format!(
+                "expected ')' in calc() but got {:?}",
+                value
+            ))),
+        }
+    }
+
+    fn parse_sum(&mut self) -> Result<CalcNode, Error> {
+        let mut node = self.parse_product()?;
+        loop {
+            match self.values.get(self.pos) {
+                Some(ComponentValue::Delim('+')) => {
+                    self.pos += 1;
+                    node = CalcNode::Sum(Box::new(node), Box::new(self.parse_product()?));
+                }
+                Some(ComponentValue::Delim('-')) => {
+                    self.pos += 1;
+                    node = CalcNode::Difference(Box::new(node), Box::new(self.parse_product()?));
+                }
+                _ => return Ok(node),
+            }
+        }
+    }
+
+    fn parse_product(&mut self) -> Result<CalcNode, Error> {
+        let mut node = self.parse_value()?;
+        loop {
+            match self.values.get(self.pos) {
+                Some(ComponentValue::Delim('*')) => {
+                    self.pos += 1;
+                    node = CalcNode::Product(Box::new(node), Box::new(self.parse_value()?));
+                }
+                Some(ComponentValue::Delim('/')) => {
+                    self.pos += 1;
+                    node = CalcNode::Quotient(Box::new(node), Box::new(self.parse_value()?));
+                }
+                _ => return Ok(node),
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<CalcNode, Error> {
+        match self.consume()? {
+            ComponentValue::Number(n) => Ok(CalcNode::Number(*n)),
+            ComponentValue::Percentage(p) => Ok(CalcNode::Percentage(*p)),
+            value @ ComponentValue::Dimension(_, _) => {
+                Ok(CalcNode::Length(Length::from_component_value(value)?))
+            }
+            ComponentValue::OpenParenthesis => {
+                let node = self.parse_sum()?;
+                self.consume_close_parenthesis()?;
+                Ok(node)
+            }
+            ComponentValue::Function(name) if name.eq_ignore_ascii_case("calc") => {
+                let node = self.parse_sum()?;
+                self.consume_close_parenthesis()?;
+                Ok(node)
+            }
+            value => Err(Error::UnexpectedInput(format!(
+                "{:?} is not supported in calc()",
+                value
+            ))),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-values-4/#typedef-length-percentage
+#[derive(Debug, Clone, PartialEq)]
+pub enum LengthPercentage {
+    Length(Length),
+    Percentage(f64),
+    /// https://www.w3.org/TR/css-values-4/#calc-func
+    Calc(Box<CalcNode>),
+}
+
+impl LengthPercentage {
+    pub fn zero() -> Self {
+        Self::Length(Length::px(0.0))
+    }
+
+    /// Parses one component value: a length, a percentage or a `calc()` function.
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Percentage(p)] => Ok(Self::Percentage(*p)),
+            [value] => Ok(Self::Length(Length::from_component_value(value)?)),
+            [ComponentValue::Function(name), arguments @ ..]
+                if name.eq_ignore_ascii_case("calc") =>
+            {
+                let mut parser = CalcParser::new(arguments);
+                let node = parser.parse_sum()?;
+                // The closing parenthesis may be omitted at the end of the declaration.
+                if parser.pos < arguments.len() {
+                    parser.consume_close_parenthesis()?;
+                }
+                if parser.pos != arguments.len() {
+                    return Err(Error::UnexpectedInput(format!(
+                        "unexpected values after calc(): {:?}",
+                        &arguments[parser.pos..]
+                    )));
+                }
+                match node.evaluate(&ComputeContext::default())? {
+                    CalcValue::LengthPercentage { .. } => Ok(Self::Calc(Box::new(node))),
+                    CalcValue::Number(_) => Err(Error::UnexpectedInput(format!(
+                        "calc() should be a length or a percentage: {:?}",
+                        values
+                    ))),
+                }
+            }
+            _ => Err(Error::UnexpectedInput(format!(
+                "length or percentage {:?} is not supported yet",
+                values
+            ))),
+        }
+    }
+
+    /// Returns true if the value is a negative length or percentage. A negative `calc()` is
+    /// clamped at used-value time instead.
+    /// https://www.w3.org/TR/css-values-4/#calc-range
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Self::Length(l) => l.value() < 0.0,
+            Self::Percentage(p) => *p < 0.0,
+            Self::Calc(_) => false,
+        }
+    }
+
+    fn linear(&self, context: &ComputeContext) -> (f64, f64) {
+        match self {
+            Self::Length(l) => (l.compute(context).value(), 0.0),
+            Self::Percentage(p) => (0.0, *p),
+            Self::Calc(node) => match node.evaluate(context) {
+                Ok(CalcValue::LengthPercentage { px, percent }) => (px, percent),
+                // Type checking is done when parsing.
+                _ => (0.0, 0.0),
+            },
+        }
+    }
+
+    /// "Computed value: as specified, with lengths made absolute"
+    /// A `calc()` is simplified to the sum of a length and a percentage.
+    pub fn compute(&self, context: &ComputeContext) -> Self {
+        let (px, percent) = self.linear(context);
+        match self {
+            Self::Length(_) => Self::Length(Length::px(px)),
+            Self::Percentage(_) => self.clone(),
+            Self::Calc(_) => Self::Calc(Box::new(CalcNode::Sum(
+                Box::new(CalcNode::Length(Length::px(px))),
+                Box::new(CalcNode::Percentage(percent)),
+            ))),
+        }
+    }
+
+    pub fn has_percentage(&self) -> bool {
+        let (_, percent) = self.linear(&ComputeContext::default());
+        match self {
+            Self::Length(_) => false,
+            Self::Percentage(_) => true,
+            Self::Calc(_) => percent != 0.0,
+        }
+    }
+
+    /// Returns the value in px with lengths made absolute and percentages resolved against
+    /// `basis`. This is for properties whose percentages are resolved at computed-value time,
+    /// such as font-size.
+    pub fn compute_with_basis(&self, context: &ComputeContext, basis: f64) -> f64 {
+        let (px, percent) = self.linear(context);
+        px + basis * percent / 100.0
+    }
+
+    /// https://www.w3.org/TR/css-values-4/#percentages
+    /// Returns the used value in px. Percentages are relative to `basis`, e.g. the width of the
+    /// containing block.
+    pub fn resolve(&self, basis: i64) -> i64 {
+        let (px, percent) = self.linear(&ComputeContext::default());
+        round(px + basis as f64 * percent / 100.0)
+    }
+}
+
+/// `<length-percentage> | auto`
+/// https://www.w3.org/TR/css-sizing-3/#preferred-size-properties
+/// https://www.w3.org/TR/css-box-4/#margin-physical
+#[derive(Debug, Clone, PartialEq)]
+pub enum LengthPercentageOrAuto {
+    LengthPercentage(LengthPercentage),
+    Auto,
+}
+
+impl LengthPercentageOrAuto {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(ident)] if ident.eq_ignore_ascii_case("auto") => Ok(Self::Auto),
+            _ => Ok(Self::LengthPercentage(
+                LengthPercentage::from_component_values(values)?,
+            )),
+        }
+    }
+
+    pub fn is_auto(&self) -> bool {
+        *self == Self::Auto
+    }
+
+    pub fn compute(&self, context: &ComputeContext) -> Self {
+        match self {
+            Self::LengthPercentage(l) => Self::LengthPercentage(l.compute(context)),
+            Self::Auto => Self::Auto,
+        }
+    }
+
+    /// Returns the used value in px, or `0` for `auto`.
+    pub fn resolve_or_zero(&self, basis: i64) -> i64 {
+        match self {
+            Self::LengthPercentage(l) => l.resolve(basis),
+            Self::Auto => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::CssTokenizer;
+    use alloc::string::ToString;
+
+    fn tokenize(s: &str) -> Vec<ComponentValue> {
+        CssTokenizer::new(s.to_string()).collect()
+    }
+
+    fn context() -> ComputeContext {
+        ComputeContext::new(20.0, 16.0, &Device::new(800, 600))
+    }
+
+    #[test]
+    fn test_split_component_values() {
+        let values = tokenize("1px calc(2px + (3px * 2)) auto");
+        let split = split_component_values(&values);
+        assert_eq!(3, split.len());
+        assert_eq!(&values[0..1], split[0]);
+        assert_eq!(&values[1..10], split[1]);
+        assert_eq!(&values[10..], split[2]);
+    }
+
+    #[test]
+    fn test_compute_lengths() {
+        let expected = [
+            ("10px", 10),
+            ("2em", 40),
+            ("2rem", 32),
+            ("10vw", 80),
+            ("10vh", 60),
+            ("10vmin", 60),
+            ("1in", 96),
+            ("12pt", 16),
+            ("0", 0),
+        ];
+        for (css, px) in expected {
+            let value = LengthPercentage::from_component_values(&tokenize(css)).unwrap();
+            assert_eq!(px, value.compute(&context()).resolve(0), "{}", css);
+        }
+    }
+
+    #[test]
+    fn test_percentage() {
+        let value = LengthPercentage::from_component_values(&tokenize("25%")).unwrap();
+        assert!(value.has_percentage());
+        assert_eq!(
+            LengthPercentage::Percentage(25.0),
+            value.compute(&context())
+        );
+        assert_eq!(50, value.resolve(200));
+    }
+
+    #[test]
+    fn test_calc() {
+        let value =
+            LengthPercentage::from_component_values(&tokenize("calc(50% - 2em + 10px * 2)"))
+                .unwrap();
+        let computed = value.compute(&context());
+        assert!(computed.has_percentage());
+        assert_eq!(100 - 40 + 20, computed.resolve(200));
+
+        let value =
+            LengthPercentage::from_component_values(&tokenize("calc((1px + 2px) / 3)")).unwrap();
+        assert_eq!(1, value.compute(&context()).resolve(0));
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        for css in [
+            "calc(1px * 2px)",
+            "calc(1px + 2)",
+            "calc(1px / 0)",
+            "calc(2 * 3)",
+            "calc(1px 2px)",
+            "10",
+            "10foo",
+            "auto",
+        ] {
+            assert!(
+                LengthPercentage::from_component_values(&tokenize(css)).is_err(),
+                "{}",
+                css
+            );
+        }
+        assert_eq!(
+            Ok(LengthPercentageOrAuto::Auto),
+            LengthPercentageOrAuto::from_component_values(&tokenize("auto"))
+        );
+        assert_eq!(
+            Ok(LengthPercentage::zero()),
+            LengthPercentage::from_component_values(&tokenize("0"))
+        );
+    }
+}
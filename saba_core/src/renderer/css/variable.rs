@@ -0,0 +1,227 @@
+//! https://www.w3.org/TR/css-variables-1/
+//! Custom properties hold arbitrary token sequences that other properties refer to with var().
+
+use crate::error::Error;
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::token::CssToken;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Custom properties by name. A custom property that is not in the map has the guaranteed-invalid
+/// value, which is its initial value.
+/// https://www.w3.org/TR/css-variables-1/#guaranteed-invalid
+pub type CustomProperties = BTreeMap<String, Vec<ComponentValue>>;
+
+/// https://www.w3.org/TR/css-variables-1/#custom-property
+/// "A custom property is any property whose name starts with two dashes (U+002D HYPHEN-MINUS),
+/// like --foo."
+pub fn is_custom_property_name(name: &str) -> bool {
+    name.len() > 2 && name.starts_with("--")
+}
+
+fn is_var_function(value: &ComponentValue) -> bool {
+    matches!(value, CssToken::Function(name) if name.eq_ignore_ascii_case("var"))
+}
+
+/// Returns true if `values` contains a var() function anywhere, including in nested functions.
+pub fn contains_var(values: &[ComponentValue]) -> bool {
+    values.iter().any(is_var_function)
+}
+
+/// Returns the index of the CloseParenthesis that closes the function or the block opened just
+/// before `start`.
+fn find_closing_parenthesis(values: &[ComponentValue], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, value) in values.iter().enumerate().skip(start) {
+        match value {
+            CssToken::Function(_) | CssToken::OpenParenthesis => depth += 1,
+            CssToken::CloseParenthesis if depth == 0 => return Some(i),
+            CssToken::CloseParenthesis => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// https://www.w3.org/TR/css-variables-1/#substitute-a-var
+/// Replaces every var() function in `values` with the value of the custom property it refers to,
+/// which `lookup` returns. If the custom property has the guaranteed-invalid value, the fallback
+/// is used instead. Returns an error if a var() function has neither, and then the declaration
+/// is invalid at computed-value time.
+pub fn substitute_var(
+    values: &[ComponentValue],
+    lookup: &mut dyn FnMut(&str) -> Option<Vec<ComponentValue>>,
+) -> Result<Vec<ComponentValue>, Error> {
+    let mut substituted = Vec::new();
+    let mut i = 0;
+    while i < values.len() {
+        if !is_var_function(&values[i]) {
+            substituted.push(values[i].clone());
+            i += 1;
+            continue;
+        }
+
+        let end = find_closing_parenthesis(values, i + 1).ok_or(Error::UnexpectedInput(
+            format!("var() is not closed in {:?}", values),
+        ))?;
+        // https://www.w3.org/TR/css-variables-1/#funcdef-var
+        // var() = var( <custom-property-name> , <declaration-value>? )
+        let (name, fallback) = match &values[i + 1..end] {
+            [CssToken::Ident(name)] if is_custom_property_name(name) => (name, None),
+            [CssToken::Ident(name), CssToken::Delim(','), fallback @ ..]
+                if is_custom_property_name(name) =>
+            {
+                (name, Some(fallback))
+            }
+            arguments => {
+                return Err(Error::UnexpectedInput(format!(
+                    "invalid arguments of var(): {:?}",
+                    arguments
+                )))
+            }
+        };
+
+        match (lookup(name), fallback) {
+            (Some(value), _) => substituted.extend(value),
+            (None, Some(fallback)) => substituted.extend(substitute_var(fallback, lookup)?),
+            (None, None) => {
+                return Err(Error::UnexpectedInput(format!(
+                    "custom property {} is not defined",
+                    name
+                )))
+            }
+        }
+        i = end + 1;
+    }
+    Ok(substituted)
+}
+
+/// https://www.w3.org/TR/css-variables-1/#cycles
+/// Substitutes var() functions in the values of custom properties. "If there is a cycle in the
+/// dependency graph, all the custom properties in the cycle are invalid at computed-value time",
+/// so they are removed and have the guaranteed-invalid value.
+pub fn compute_custom_properties(specified: CustomProperties) -> CustomProperties {
+    let mut resolver = CustomPropertyResolver {
+        specified,
+        computed: BTreeMap::new(),
+        stack: Vec::new(),
+        in_cycle: Vec::new(),
+    };
+    let names: Vec<String> = resolver.specified.keys().cloned().collect();
+    for name in names {
+        resolver.resolve(&name);
+    }
+    resolver
+        .computed
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .collect()
+}
+
+struct CustomPropertyResolver {
+    specified: CustomProperties,
+    /// The computed values of custom properties that have been resolved. None means the
+    /// guaranteed-invalid value.
+    computed: BTreeMap<String, Option<Vec<ComponentValue>>>,
+    /// Custom properties that are being resolved, in the order of references.
+    stack: Vec<String>,
+    in_cycle: Vec<String>,
+}
+
+impl CustomPropertyResolver {
+    fn resolve(&mut self, name: &str) -> Option<Vec<ComponentValue>> {
+        if let Some(value) = self.computed.get(name) {
+            return value.clone();
+        }
+        if let Some(position) = self.stack.iter().position(|n| n == name) {
+            // Every custom property from `name` to the top of the stack is in the cycle.
+            let cycle = self.stack[position..].to_vec();
+            self.in_cycle.extend(cycle);
+            return None;
+        }
+        let specified = self.specified.get(name)?.clone();
+
+        self.stack.push(String::from(name));
+        let value = substitute_var(&specified, &mut |n| self.resolve(n)).ok();
+        self.stack.pop();
+
+        let value = if self.in_cycle.iter().any(|n| n == name) {
+            None
+        } else {
+            value
+        };
+        self.computed.insert(String::from(name), value.clone());
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::CssTokenizer;
+    use alloc::string::ToString;
+
+    fn tokens(css: &str) -> Vec<ComponentValue> {
+        CssTokenizer::new(css.to_string()).collect()
+    }
+
+    fn custom_properties(declarations: &[(&str, &str)]) -> CustomProperties {
+        declarations
+            .iter()
+            .map(|(name, value)| (name.to_string(), tokens(value)))
+            .collect()
+    }
+
+    #[test]
+    fn test_substitute_var() {
+        let properties = custom_properties(&[("--gap", "4px"), ("--color", "rgb(0, 0, 255)")]);
+        let mut lookup = |name: &str| properties.get(name).cloned();
+
+        assert_eq!(
+            Ok(tokens("4px 2px")),
+            substitute_var(&tokens("var(--gap) 2px"), &mut lookup)
+        );
+        assert_eq!(
+            Ok(tokens("1px solid rgb(0, 0, 255)")),
+            substitute_var(&tokens("1px solid var(--color)"), &mut lookup)
+        );
+        // The fallback may contain commas and other var() functions.
+        assert_eq!(
+            Ok(tokens("rgb(1, 2, 3)")),
+            substitute_var(&tokens("var(--undefined, rgb(1, 2, 3))"), &mut lookup)
+        );
+        assert_eq!(
+            Ok(tokens("calc(4px * 2)")),
+            substitute_var(
+                &tokens("calc(var(--undefined, var(--gap)) * 2)"),
+                &mut lookup
+            )
+        );
+        assert!(substitute_var(&tokens("var(--undefined)"), &mut lookup).is_err());
+        assert!(substitute_var(&tokens("var(gap)"), &mut lookup).is_err());
+    }
+
+    #[test]
+    fn test_cycles() {
+        let computed = compute_custom_properties(custom_properties(&[
+            ("--a", "var(--b)"),
+            ("--b", "var(--c, 1px)"),
+            ("--c", "var(--a)"),
+            ("--d", "var(--a, 2px)"),
+            ("--e", "var(--e)"),
+            ("--f", "var(--g) var(--g)"),
+            ("--g", "3px"),
+        ]));
+
+        // Custom properties in a cycle are invalid even if they have a fallback.
+        assert_eq!(None, computed.get("--a"));
+        assert_eq!(None, computed.get("--b"));
+        assert_eq!(None, computed.get("--c"));
+        assert_eq!(None, computed.get("--e"));
+        // A custom property that refers to a cycle but is not part of it uses its fallback.
+        assert_eq!(Some(&tokens("2px")), computed.get("--d"));
+        assert_eq!(Some(&tokens("3px 3px")), computed.get("--f"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::renderer::dom::node::DirtyFlags;
 use crate::renderer::dom::node::Element;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
@@ -89,3 +90,67 @@ pub fn get_js_content(root: Rc<RefCell<Node>>) -> String {
     };
     content
 }
+
+/// https://dom.spec.whatwg.org/#dom-element-setattribute
+/// Sets an attribute of an element. Selectors may match the element differently, so its style
+/// has to be computed again. The image or the alternative text of an img element is attached when
+/// its box is built, so the box is built again when its src or alt is changed.
+pub fn set_attribute(node: &Rc<RefCell<Node>>, name: &str, value: String) {
+    let mut n = node.borrow_mut();
+    if let NodeKind::Element(ref mut e) = n.kind {
+        if e.get_attribute(name).as_ref() == Some(&value) {
+            return;
+        }
+        let replaced = e.kind() == ElementKind::Img && (name == "src" || name == "alt");
+        e.set_attribute(name, value);
+        n.set_dirty(DirtyFlags {
+            tree: replaced,
+            style: true,
+            ..Default::default()
+        });
+    }
+}
+
+/// https://dom.spec.whatwg.org/#dom-node-textcontent
+/// Replaces the text of a text node, or the children of an element with a text node of `text`.
+/// An element whose only child is a text node keeps the node, so that only the boxes of the text
+/// have to be laid out again instead of being built.
+pub fn set_text_content(node: &Rc<RefCell<Node>>, text: String) {
+    let only_text_child = match node.borrow().first_child() {
+        Some(child)
+            if child.borrow().next_sibling().is_none()
+                && matches!(child.borrow().kind, NodeKind::Text(_)) =>
+        {
+            Some(child)
+        }
+        _ => None,
+    };
+    if let Some(child) = only_text_child {
+        return set_text_content(&child, text);
+    }
+
+    let mut n = node.borrow_mut();
+    match n.kind {
+        NodeKind::Text(ref mut s) => {
+            if *s == text {
+                return;
+            }
+            *s = text;
+            n.set_dirty(DirtyFlags {
+                layout: true,
+                ..Default::default()
+            });
+        }
+        NodeKind::Element(_) => {
+            let child = Rc::new(RefCell::new(Node::new(NodeKind::Text(text))));
+            child.borrow_mut().set_parent(Rc::downgrade(node));
+            n.set_last_child(Rc::downgrade(&child));
+            n.set_first_child(Some(child));
+            n.set_dirty(DirtyFlags {
+                tree: true,
+                ..Default::default()
+            });
+        }
+        NodeKind::Document => {}
+    }
+}
@@ -19,6 +19,43 @@ pub struct Node {
     last_child: Weak<RefCell<Node>>,
     previous_sibling: Weak<RefCell<Node>>,
     next_sibling: Option<Rc<RefCell<Node>>>,
+    /// What has to be updated for this node since the rendering was last updated.
+    dirty: DirtyFlags,
+}
+
+/// https://source.chromium.org/chromium/chromium/src/+/main:third_party/blink/renderer/core/dom/node.h
+/// The parts of the rendering of a node that are out of date after the DOM tree is changed. The
+/// flags are set by the changes and cleared when the rendering is updated.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DirtyFlags {
+    /// The children of the node are changed, so its boxes have to be built again.
+    pub tree: bool,
+    /// The node matches other rules, so the style of it and its descendants has to be computed
+    /// again.
+    pub style: bool,
+    /// The content of the node is changed, so its boxes have to be laid out again.
+    pub layout: bool,
+    /// Only the look of the node is changed, so its boxes have to be painted again.
+    pub paint: bool,
+    /// A descendant of the node has some flag set.
+    pub descendants: bool,
+}
+
+impl DirtyFlags {
+    /// Returns true if the node itself has to be updated.
+    pub fn is_dirty(&self) -> bool {
+        self.tree || self.style || self.layout || self.paint
+    }
+
+    fn union(&self, other: &DirtyFlags) -> Self {
+        Self {
+            tree: self.tree || other.tree,
+            style: self.style || other.style,
+            layout: self.layout || other.layout,
+            paint: self.paint || other.paint,
+            descendants: self.descendants || other.descendants,
+        }
+    }
 }
 
 impl PartialEq for Node {
@@ -37,6 +74,7 @@ impl Node {
             last_child: Weak::new(),
             previous_sibling: Weak::new(),
             next_sibling: None,
+            dirty: DirtyFlags::default(),
         }
     }
     pub fn kind(&self) -> NodeKind {
@@ -87,6 +125,25 @@ impl Node {
     pub fn next_sibling(&self) -> Option<Rc<RefCell<Node>>> {
         self.next_sibling.clone()
     }
+    pub fn dirty(&self) -> DirtyFlags {
+        self.dirty
+    }
+    /// Sets `dirty` on this node, and marks the ancestors so that the node is found from the
+    /// document when the rendering is updated.
+    pub fn set_dirty(&mut self, dirty: DirtyFlags) {
+        self.dirty = self.dirty.union(&dirty);
+        let mut parent = self.parent.upgrade();
+        while let Some(p) = parent {
+            if p.borrow().dirty.descendants {
+                break;
+            }
+            p.borrow_mut().dirty.descendants = true;
+            parent = p.borrow().parent.upgrade();
+        }
+    }
+    pub fn clear_dirty(&mut self) {
+        self.dirty = DirtyFlags::default();
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -158,13 +215,6 @@ impl Element {
         self.kind
     }
 
-    pub fn is_block_element(&self) -> bool {
-        matches!(
-            self.kind,
-            ElementKind::Body | ElementKind::H1 | ElementKind::H2 | ElementKind::P
-        )
-    }
-
     pub fn attributes(&self) -> Vec<Attribute> {
         self.attributes.clone()
     }
@@ -176,6 +226,19 @@ impl Element {
         }
         None
     }
+    /// https://dom.spec.whatwg.org/#dom-element-setattribute
+    pub fn set_attribute(&mut self, name: &str, value: String) {
+        if let Some(attr) = self.attributes.iter_mut().find(|attr| attr.name() == name) {
+            attr.set_value(value);
+            return;
+        }
+        let mut attr = Attribute::new();
+        for c in name.chars() {
+            attr.add_char(c, true);
+        }
+        attr.set_value(value);
+        self.attributes.push(attr);
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -188,7 +251,12 @@ pub enum ElementKind {
     P,
     H1,
     H2,
+    Pre,
     A,
+    Ul,
+    Ol,
+    Li,
+    Img,
 }
 
 impl FromStr for ElementKind {
@@ -203,7 +271,12 @@ impl FromStr for ElementKind {
             "h1" => Ok(ElementKind::H1),
             "h2" => Ok(ElementKind::H2),
             "p" => Ok(ElementKind::P),
+            "pre" => Ok(ElementKind::Pre),
             "a" => Ok(ElementKind::A),
+            "ul" => Ok(ElementKind::Ul),
+            "ol" => Ok(ElementKind::Ol),
+            "li" => Ok(ElementKind::Li),
+            "img" => Ok(ElementKind::Img),
             _ => Err(format!("unimplement element name {:?}", s)),
         }
     }
@@ -220,7 +293,12 @@ impl Display for ElementKind {
             ElementKind::P => "p",
             ElementKind::H1 => "h1",
             ElementKind::H2 => "h2",
+            ElementKind::Pre => "pre",
             ElementKind::A => "a",
+            ElementKind::Ul => "ul",
+            ElementKind::Ol => "ol",
+            ElementKind::Li => "li",
+            ElementKind::Img => "img",
         };
         write!(f, "{}", s)
     }
@@ -36,4 +36,8 @@ impl Attribute {
     pub fn value(&self) -> String {
         self.value.clone()
     }
+
+    pub fn set_value(&mut self, value: String) {
+        self.value = value;
+    }
 }
@@ -77,6 +77,20 @@ impl HtmlParser {
         }
     }
 
+    /// https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-list-item-scope
+    /// Whether an element of `element_kind` is open inside the innermost list. The lists are the
+    /// boundaries of the scope.
+    fn has_in_list_item_scope(&self, element_kind: ElementKind) -> bool {
+        for node in self.stack_of_open_elements.iter().rev() {
+            match node.borrow().element_kind() {
+                Some(kind) if kind == element_kind => return true,
+                Some(ElementKind::Ul | ElementKind::Ol) => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+
     fn pop_current_node(&mut self, element_kind: ElementKind) -> bool {
         let current = match self.stack_of_open_elements.last() {
             Some(n) => n,
@@ -97,86 +111,59 @@ impl HtmlParser {
         Node::new(NodeKind::Text(s))
     }
 
+    /// https://html.spec.whatwg.org/multipage/parsing.html#insert-a-character
     fn insert_char(&mut self, c: char) {
         let current = match self.stack_of_open_elements.last() {
             Some(n) => n.clone(),
             None => return,
         };
 
-        // 現在参照しているノードがテキストノードの場合、そのノードに文字を追加する。
-        if let NodeKind::Text(ref mut s) = current.borrow_mut().kind {
-            s.push(c);
-            return;
-        }
-
-        // 改行文字や空白文字のときはテキストノードを追加しない。
-        if c == '\n' || c == ' ' {
-            return;
+        // 直前のノードがテキストノードの場合、そのノードに文字を追加する。
+        // "If there is a Text node immediately before the adjusted insertion location, then
+        // append data to that Text node's data."
+        if let Some(last_child) = current.borrow().last_child().upgrade() {
+            if let NodeKind::Text(ref mut s) = last_child.borrow_mut().kind {
+                s.push(c);
+                return;
+            }
         }
 
+        // テキストノードはスタック・オブ・オープン・エレメンツには積まない。
         let node = Rc::new(RefCell::new(self.create_char(c)));
-
-        if current.borrow().first_child().is_some() {
-            current
-                .borrow()
-                .first_child()
-                .unwrap()
-                .borrow_mut()
-                .set_next_sibling(Some(node.clone()));
-        } else {
-            current.borrow_mut().set_first_child(Some(node.clone()));
-        }
-
-        current.borrow_mut().set_last_child(Rc::downgrade(&node));
-        node.borrow_mut().set_parent(Rc::downgrade(&current));
-
-        self.stack_of_open_elements.push(node);
+        Self::append_child(&current, node);
     }
 
     fn create_element(&self, tag: &str, attributes: Vec<Attribute>) -> Node {
         Node::new(NodeKind::Element(Element::new(tag, attributes)))
     }
 
+    /// https://html.spec.whatwg.org/multipage/parsing.html#insert-an-html-element
     fn insert_element(&mut self, tag: &str, attributes: Vec<Attribute>) {
-        let window = self.window.borrow();
         let current = match self.stack_of_open_elements.last() {
             Some(n) => n.clone(),
-            None => window.document(),
+            None => self.window.borrow().document(),
         };
 
         let node = Rc::new(RefCell::new(self.create_element(tag, attributes)));
+        Self::append_child(&current, node.clone());
 
-        if current.borrow().first_child().is_some() {
-            let mut last_sibling = current.borrow().first_child();
-            loop {
-                last_sibling = match last_sibling {
-                    Some(ref node) => {
-                        if node.borrow().next_sibling().is_some() {
-                            node.borrow().next_sibling()
-                        } else {
-                            break;
-                        }
-                    }
-                    None => unimplemented!("last_sibling should be Some"),
-                };
-            }
+        self.stack_of_open_elements.push(node);
+    }
 
-            last_sibling
-                .as_ref()
-                .unwrap()
-                .borrow_mut()
-                .set_next_sibling(Some(node.clone()));
-            node.borrow_mut().set_previous_sibling(Rc::downgrade(
-                &last_sibling.expect("last_sibling should be Some"),
-            ))
-        } else {
-            current.borrow_mut().set_first_child(Some(node.clone()));
+    /// `node`を`parent`の最後の子として追加する。
+    fn append_child(parent: &Rc<RefCell<Node>>, node: Rc<RefCell<Node>>) {
+        let last_child = parent.borrow().last_child().upgrade();
+        match last_child {
+            Some(last_child) => {
+                last_child.borrow_mut().set_next_sibling(Some(node.clone()));
+                node.borrow_mut()
+                    .set_previous_sibling(Rc::downgrade(&last_child));
+            }
+            None => parent.borrow_mut().set_first_child(Some(node.clone())),
         }
 
-        current.borrow_mut().set_last_child(Rc::downgrade(&node));
-        node.borrow_mut().set_parent(Rc::downgrade(&current));
-
-        self.stack_of_open_elements.push(node);
+        parent.borrow_mut().set_last_child(Rc::downgrade(&node));
+        node.borrow_mut().set_parent(Rc::downgrade(parent));
     }
 
     pub fn construct_tree(&mut self) -> Rc<RefCell<Window>> {
@@ -357,11 +344,50 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
+                            "pre" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody
+                                // "If the next token is a U+000A LINE FEED (LF) character token,
+                                // then ignore that token and move on to the next one. (Newlines at
+                                // the start of pre blocks are ignored as an authoring
+                                // convenience.)"
+                                if token == Some(HtmlToken::Char('\n')) {
+                                    token = self.t.next();
+                                }
+                                continue;
+                            }
                             "a" => {
                                 self.insert_element(tag, attributes.to_vec());
                                 token = self.t.next();
                                 continue;
                             }
+                            "ul" | "ol" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "li" => {
+                                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody
+                                // An open li element is closed by the next li element in the
+                                // same list.
+                                if self.has_in_list_item_scope(ElementKind::Li) {
+                                    self.pop_until(ElementKind::Li);
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "img" => {
+                                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody
+                                // img is a void element. "Insert an HTML element for the token.
+                                // Immediately pop the current node off the stack of open
+                                // elements."
+                                self.insert_element(tag, attributes.to_vec());
+                                self.pop_current_node(ElementKind::Img);
+                                token = self.t.next();
+                                continue;
+                            }
                             _ => {
                                 token = self.t.next();
                             }
@@ -394,7 +420,7 @@ impl HtmlParser {
                                     self.pop_until(element_kind);
                                     continue;
                                 }
-                                "h1" | "h2" => {
+                                "h1" | "h2" | "pre" => {
                                     let element_kind = ElementKind::from_str(tag)
                                         .expect("failed to convert string to ElementKind");
                                     token = self.t.next();
@@ -408,6 +434,17 @@ impl HtmlParser {
                                     self.pop_until(element_kind);
                                     continue;
                                 }
+                                "ul" | "ol" | "li" => {
+                                    let element_kind = ElementKind::from_str(tag)
+                                        .expect("failed to convert string to ElementKind");
+                                    token = self.t.next();
+                                    // An end tag without a matching open element is a parse
+                                    // error, and it's ignored.
+                                    if self.contain_in_stack(element_kind) {
+                                        self.pop_until(element_kind);
+                                    }
+                                    continue;
+                                }
                                 _ => {
                                     token = self.t.next();
                                 }
@@ -499,6 +536,7 @@ impl HtmlParser {
 mod tests {
     use super::*;
     use crate::alloc::string::ToString;
+    use crate::renderer::dom::api::get_target_element_node;
     use alloc::vec;
 
     #[test]
@@ -672,4 +710,131 @@ mod tests {
             text
         );
     }
+
+    #[test]
+    fn test_pre() {
+        let html =
+            "<html><head></head><body><pre>\n  fn main() {\n  }\n</pre></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let pre = get_target_element_node(Some(document), ElementKind::Pre)
+            .expect("failed to get a pre element");
+        // The newline just after the start tag is ignored, and the other white space is kept.
+        let text = pre
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of pre");
+        let kind = text.borrow().kind();
+        match kind {
+            NodeKind::Text(text) => assert_eq!("  fn main() {\n  }\n", text),
+            kind => panic!("expected a text node but got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_text_and_element_siblings() {
+        let html = "<html><head></head><body><p>foo <a>bar</a> baz</p><p>qux</p></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let p = get_target_element_node(Some(document), ElementKind::P)
+            .expect("failed to get a p element");
+        let text = |node: &Rc<RefCell<Node>>| match node.borrow().kind() {
+            NodeKind::Text(text) => text,
+            kind => panic!("expected a text node but got {:?}", kind),
+        };
+
+        // Text nodes and elements are siblings, and the element after a text node is not nested
+        // in it.
+        let foo = p.borrow().first_child().expect("failed to get foo");
+        assert_eq!("foo ", text(&foo));
+        let a = foo.borrow().next_sibling().expect("failed to get a");
+        assert_eq!(Some(ElementKind::A), a.borrow().element_kind());
+        let bar = a.borrow().first_child().expect("failed to get bar");
+        assert_eq!("bar", text(&bar));
+        let baz = a.borrow().next_sibling().expect("failed to get baz");
+        assert_eq!(" baz", text(&baz));
+        assert!(baz.borrow().next_sibling().is_none());
+        assert_eq!(
+            a,
+            baz.borrow()
+                .previous_sibling()
+                .upgrade()
+                .expect("failed to get the previous sibling of baz")
+        );
+
+        let next_p = p.borrow().next_sibling().expect("failed to get the next p");
+        assert_eq!(Some(ElementKind::P), next_p.borrow().element_kind());
+    }
+
+    #[test]
+    fn test_lists() {
+        let html =
+            "<html><head></head><body><ul><li>a<li>b<ol><li>c</ol></ul></li><p>d</p></body></html>"
+                .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        let kind = |node: &Option<Rc<RefCell<Node>>>| {
+            node.as_ref()
+                .expect("node should exist")
+                .borrow()
+                .element_kind()
+        };
+
+        let ul = get_target_element_node(Some(document), ElementKind::Ul);
+        // A li element closes the open li element before it in the same list, but not the one
+        // that a nested list is in.
+        let a = ul.as_ref().expect("ul should exist").borrow().first_child();
+        assert_eq!(Some(ElementKind::Li), kind(&a));
+        let b = a.as_ref().expect("li should exist").borrow().next_sibling();
+        assert_eq!(Some(ElementKind::Li), kind(&b));
+        let ol = b
+            .as_ref()
+            .expect("li should exist")
+            .borrow()
+            .first_child()
+            .expect("text should exist")
+            .borrow()
+            .next_sibling();
+        assert_eq!(Some(ElementKind::Ol), kind(&ol));
+        let c = ol.as_ref().expect("ol should exist").borrow().first_child();
+        assert_eq!(Some(ElementKind::Li), kind(&c));
+        // The stray end tag is ignored, and the p element is after the list.
+        let p = ul
+            .as_ref()
+            .expect("ul should exist")
+            .borrow()
+            .next_sibling();
+        assert_eq!(Some(ElementKind::P), kind(&p));
+    }
+
+    #[test]
+    fn test_img() {
+        let html = "<html><head></head><body><p><img src=\"a.png\" alt=\"A\">text<img src=b.gif /></img></p></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        let p = get_target_element_node(Some(document), ElementKind::P).expect("p should exist");
+
+        // img has no children, and the text after it is its sibling.
+        let img = p.borrow().first_child().expect("img should exist");
+        assert_eq!(Some(ElementKind::Img), img.borrow().element_kind());
+        assert!(img.borrow().first_child().is_none());
+        let element = img
+            .borrow()
+            .get_element()
+            .expect("img should be an element");
+        assert_eq!(Some("a.png".to_string()), element.get_attribute("src"));
+        assert_eq!(Some("A".to_string()), element.get_attribute("alt"));
+        let text = img.borrow().next_sibling().expect("text should exist");
+        assert_eq!(NodeKind::Text("text".to_string()), text.borrow().kind());
+        let second = text.borrow().next_sibling().expect("img should exist");
+        assert_eq!(Some(ElementKind::Img), second.borrow().element_kind());
+        assert!(second.borrow().next_sibling().is_none());
+    }
 }
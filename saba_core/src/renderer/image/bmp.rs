@@ -0,0 +1,172 @@
+use crate::error::Error;
+use crate::renderer::image::argb;
+use crate::renderer::image::pixel_count;
+use crate::renderer::image::read_u16_le;
+use crate::renderer::image::truncated;
+use crate::renderer::image::Image;
+use alloc::format;
+use alloc::vec::Vec;
+
+/// "An uncompressed format."
+const BI_RGB: u32 = 0;
+/// Uncompressed with color masks. Only 32-bit pixels of blue, green, red and alpha are supported.
+const BI_BITFIELDS: u32 = 3;
+
+/// https://learn.microsoft.com/en-us/windows/win32/gdi/bitmap-storage
+/// Decodes an uncompressed BMP file of 1, 4, 8, 24 or 32 bits per pixel. A BMP file is a
+/// BITMAPFILEHEADER, a BITMAPINFOHEADER or a later version of it, a color table for up to 8 bits
+/// per pixel, and the pixels. Rows are padded to 4 bytes and stored from the bottom up unless the
+/// height is negative.
+pub fn decode(bytes: &[u8]) -> Result<Image, Error> {
+    let read_u32 = |pos: usize| -> Result<u32, Error> {
+        match bytes.get(pos..pos + 4) {
+            Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            None => Err(truncated()),
+        }
+    };
+    // BITMAPFILEHEADER: "bfOffBits" is the offset of the pixels.
+    let offset = read_u32(10)? as usize;
+    // BITMAPINFOHEADER.
+    let header_size = read_u32(14)? as usize;
+    let width = read_u32(18)? as i32;
+    let height = read_u32(22)? as i32;
+    let bit_count = read_u16_le(bytes, 28)?;
+    let compression = read_u32(30)?;
+    let colors_used = read_u32(46)? as usize;
+    if width <= 0 || height == 0 {
+        return Err(Error::UnexpectedInput(format!(
+            "invalid BMP size {}x{}",
+            width, height
+        )));
+    }
+    if !(compression == BI_RGB || (compression == BI_BITFIELDS && bit_count == 32)) {
+        return Err(Error::UnexpectedInput(format!(
+            "BMP compression {} is not supported",
+            compression
+        )));
+    }
+    if !matches!(bit_count, 1 | 4 | 8 | 24 | 32) {
+        return Err(Error::UnexpectedInput(format!(
+            "BMP of {} bits per pixel is not supported",
+            bit_count
+        )));
+    }
+
+    // The color table is RGBQUADs of blue, green, red and a reserved byte.
+    let palette: Vec<[u8; 3]> = if bit_count <= 8 {
+        let len = if colors_used == 0 {
+            1 << bit_count
+        } else {
+            colors_used
+        };
+        let start = 14 + header_size;
+        bytes
+            .get(start..start + len * 4)
+            .ok_or_else(truncated)?
+            .as_chunks::<4>()
+            .0
+            .iter()
+            .map(|c| [c[2], c[1], c[0]])
+            .collect()
+    } else {
+        Vec::new()
+    };
+    // The alpha of 32-bit pixels is only used if the header has an alpha mask.
+    let has_alpha = bit_count == 32 && header_size >= 56 && read_u32(14 + 52)? != 0;
+
+    let (width, top_down) = (width as usize, height < 0);
+    let height = height.unsigned_abs() as usize;
+    pixel_count(width, height)?;
+    let stride = (width * bit_count as usize).div_ceil(32) * 4;
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = if top_down { y } else { height - 1 - y };
+        let start = offset + row * stride;
+        let line = bytes.get(start..start + stride).ok_or_else(truncated)?;
+        for x in 0..width {
+            let pixel = match bit_count {
+                1 | 4 | 8 => {
+                    let bits = bit_count as usize;
+                    let bit = x * bits;
+                    let index = (line[bit / 8] >> (8 - bits - bit % 8)) & ((1 << bits) - 1) as u8;
+                    let [r, g, b] = palette.get(index as usize).copied().unwrap_or([0, 0, 0]);
+                    argb(r, g, b, 255)
+                }
+                24 => argb(line[x * 3 + 2], line[x * 3 + 1], line[x * 3], 255),
+                _ => {
+                    let a = if has_alpha { line[x * 4 + 3] } else { 255 };
+                    argb(line[x * 4 + 2], line[x * 4 + 1], line[x * 4], a)
+                }
+            };
+            pixels.push(pixel);
+        }
+    }
+    Image::new(width as i64, height as i64, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a BMP file with a BITMAPINFOHEADER.
+    fn bmp(width: i32, height: i32, bit_count: u16, palette: &[u8], data: &[u8]) -> Vec<u8> {
+        let offset = 14 + 40 + palette.len() as u32;
+        let mut bytes = b"BM".to_vec();
+        bytes.extend_from_slice(&(offset + data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend_from_slice(&offset.to_le_bytes());
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&bit_count.to_le_bytes());
+        bytes.extend_from_slice(&[0; 24]);
+        bytes.extend_from_slice(palette);
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_24_bits_bottom_up() {
+        // Rows of 2 pixels are 6 bytes padded to 8, and the bottom row comes first.
+        let data = [
+            0, 0, 255, 0, 255, 0, 0, 0, //
+            255, 0, 0, 255, 255, 255, 0, 0,
+        ];
+        let image = decode(&bmp(2, 2, 24, &[], &data)).expect("failed to decode BMP");
+        assert_eq!((2, 2), (image.width(), image.height()));
+        assert_eq!(
+            &[0xff0000ff, 0xffffffff, 0xffff0000, 0xff00ff00],
+            image.pixels()
+        );
+    }
+
+    #[test]
+    fn test_palette_top_down() {
+        // 1 bit per pixel with a palette of black and white, stored from the top.
+        let palette = [0, 0, 0, 0, 255, 255, 255, 0];
+        let data = [0b1010_0000, 0, 0, 0, 0b0100_0000, 0, 0, 0];
+        let image = decode(&bmp(3, -2, 1, &palette, &data)).expect("failed to decode BMP");
+        assert_eq!(
+            &[
+                0xffffffff, 0xff000000, 0xffffffff, //
+                0xff000000, 0xffffffff, 0xff000000,
+            ],
+            image.pixels()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(decode(b"BM").is_err());
+        assert!(decode(&bmp(1, 1, 16, &[], &[0, 0, 0, 0])).is_err());
+        assert!(decode(&bmp(2, 2, 24, &[], &[0; 8])).is_err());
+        assert!(decode(&bmp(0, 1, 24, &[], &[0; 4])).is_err());
+    }
+
+    #[test]
+    fn test_too_large() {
+        assert!(decode(&bmp(60000, -60000, 24, &[], &[])).is_err());
+        assert!(decode(&bmp(i32::MAX, i32::MIN, 32, &[], &[])).is_err());
+    }
+}
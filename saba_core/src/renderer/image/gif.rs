@@ -0,0 +1,305 @@
+use crate::error::Error;
+use crate::renderer::image::argb;
+use crate::renderer::image::pixel_count;
+use crate::renderer::image::read_u16_le;
+use crate::renderer::image::truncated;
+use crate::renderer::image::Image;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The maximum number of bits of an LZW code. "The code size may not exceed 12 bits".
+const MAX_CODE_BITS: u32 = 12;
+
+/// https://www.w3.org/Graphics/GIF/spec-gif89a.txt
+/// Decodes the first image of a GIF file. Later images of an animation aren't shown. Pixels of
+/// the logical screen outside of the image, and pixels of the transparent color index of the
+/// Graphic Control Extension, are transparent.
+pub fn decode(bytes: &[u8]) -> Result<Image, Error> {
+    // "18. Logical Screen Descriptor."
+    let screen_width = read_u16_le(bytes, 6)? as usize;
+    let screen_height = read_u16_le(bytes, 8)? as usize;
+    let flags = *bytes.get(10).ok_or_else(truncated)?;
+    let mut pos = 13;
+    let mut global_colors = Vec::new();
+    if flags & 0x80 != 0 {
+        global_colors = read_color_table(bytes, &mut pos, flags)?;
+    }
+
+    let mut transparent_index = None;
+    loop {
+        match bytes.get(pos) {
+            // "23. Graphic Control Extension."
+            Some(0x21) if bytes.get(pos + 1) == Some(&0xf9) => {
+                let packed = *bytes.get(pos + 3).ok_or_else(truncated)?;
+                if packed & 0x01 != 0 {
+                    transparent_index = Some(*bytes.get(pos + 6).ok_or_else(truncated)?);
+                }
+                pos += 2;
+                skip_sub_blocks(bytes, &mut pos)?;
+            }
+            // Other extensions such as comments and application extensions are skipped.
+            Some(0x21) => {
+                pos += 2;
+                skip_sub_blocks(bytes, &mut pos)?;
+            }
+            // "20. Image Descriptor."
+            Some(0x2c) => break,
+            Some(0x3b) => return Err(Error::UnexpectedInput("GIF file has no image".to_string())),
+            Some(b) => {
+                return Err(Error::UnexpectedInput(format!(
+                    "invalid GIF block {:#04x}",
+                    b
+                )))
+            }
+            None => return Err(truncated()),
+        }
+    }
+
+    let left = read_u16_le(bytes, pos + 1)? as usize;
+    let top = read_u16_le(bytes, pos + 3)? as usize;
+    let width = read_u16_le(bytes, pos + 5)? as usize;
+    let height = read_u16_le(bytes, pos + 7)? as usize;
+    let image_flags = *bytes.get(pos + 9).ok_or_else(truncated)?;
+    pos += 10;
+    let colors = if image_flags & 0x80 != 0 {
+        read_color_table(bytes, &mut pos, image_flags)?
+    } else {
+        global_colors
+    };
+    let interlaced = image_flags & 0x40 != 0;
+
+    // "22. Table Based Image Data." The LZW data is split into sub-blocks.
+    let min_code_size = *bytes.get(pos).ok_or_else(truncated)? as u32;
+    pos += 1;
+    let mut data = Vec::new();
+    loop {
+        let size = *bytes.get(pos).ok_or_else(truncated)? as usize;
+        pos += 1;
+        if size == 0 {
+            break;
+        }
+        data.extend_from_slice(bytes.get(pos..pos + size).ok_or_else(truncated)?);
+        pos += size;
+    }
+    let indices = decompress_lzw(&data, min_code_size, pixel_count(width, height)?)?;
+
+    let mut pixels = vec![0u32; pixel_count(screen_width, screen_height)?];
+    for (i, &index) in indices.iter().enumerate() {
+        let row = if interlaced {
+            interlaced_row(i / width.max(1), height)
+        } else {
+            i / width.max(1)
+        };
+        let (x, y) = (left + i % width.max(1), top + row);
+        if x >= screen_width || y >= screen_height || transparent_index == Some(index) {
+            continue;
+        }
+        if let Some(&[r, g, b]) = colors.get(index as usize) {
+            pixels[y * screen_width + x] = argb(r, g, b, 255);
+        }
+    }
+    Image::new(screen_width as i64, screen_height as i64, pixels)
+}
+
+/// "19. Global Color Table." A table of 2^(N+1) RGB triplets, where N is the size in the low 3
+/// bits of `flags`.
+fn read_color_table(bytes: &[u8], pos: &mut usize, flags: u8) -> Result<Vec<[u8; 3]>, Error> {
+    let len = 3 << ((flags & 0x07) + 1);
+    let table = bytes.get(*pos..*pos + len).ok_or_else(truncated)?;
+    *pos += len;
+    Ok(table.as_chunks::<3>().0.to_vec())
+}
+
+/// "15. Data Sub-blocks." Skips sub-blocks up to the block terminator.
+fn skip_sub_blocks(bytes: &[u8], pos: &mut usize) -> Result<(), Error> {
+    loop {
+        let size = *bytes.get(*pos).ok_or_else(truncated)? as usize;
+        *pos += 1 + size;
+        if size == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// "Appendix E. Interlaced Images." Returns the row of the `n`th row in the data. Rows are
+/// stored every 8th from 0, every 8th from 4, every 4th from 2 and every 2nd from 1.
+fn interlaced_row(n: usize, height: usize) -> usize {
+    let mut n = n;
+    for (start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+        let rows = height.saturating_sub(start).div_ceil(step);
+        if n < rows {
+            return start + n * step;
+        }
+        n -= rows;
+    }
+    n
+}
+
+/// "Appendix F. Variable-Length-Code LZW Compression." Decompresses color indices. Codes are
+/// read from the least significant bit, start at `min_code_size + 1` bits and grow as the table
+/// fills up to 12 bits.
+fn decompress_lzw(data: &[u8], min_code_size: u32, len: usize) -> Result<Vec<u8>, Error> {
+    if !(1..MAX_CODE_BITS).contains(&min_code_size) {
+        return Err(Error::UnexpectedInput(format!(
+            "invalid LZW minimum code size {}",
+            min_code_size
+        )));
+    }
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    // Each entry is (the prefix code, the last index), so that a string is followed back to its
+    // first index.
+    let mut table: Vec<(u16, u8)> = Vec::with_capacity(1 << MAX_CODE_BITS);
+    let reset = |table: &mut Vec<(u16, u8)>| {
+        table.clear();
+        for i in 0..clear {
+            table.push((u16::MAX, i as u8));
+        }
+        // The clear code and the end of information code.
+        table.push((u16::MAX, 0));
+        table.push((u16::MAX, 0));
+    };
+    reset(&mut table);
+
+    let mut out = Vec::with_capacity(len);
+    let mut code_bits = min_code_size + 1;
+    let mut previous: Option<u16> = None;
+    let (mut bit_buf, mut bit_count, mut pos) = (0u32, 0u32, 0usize);
+    let mut string = Vec::new();
+    while out.len() < len {
+        while bit_count < code_bits {
+            match data.get(pos) {
+                Some(&b) => bit_buf |= (b as u32) << bit_count,
+                None => return Ok(pad(out, len)),
+            }
+            pos += 1;
+            bit_count += 8;
+        }
+        let code = (bit_buf & ((1 << code_bits) - 1)) as u16;
+        bit_buf >>= code_bits;
+        bit_count -= code_bits;
+
+        if code == clear {
+            reset(&mut table);
+            code_bits = min_code_size + 1;
+            previous = None;
+            continue;
+        }
+        if code == end {
+            break;
+        }
+        // The string of the code, or for a code that isn't in the table yet, the string of the
+        // previous code followed by its own first index.
+        let known = (code as usize) < table.len();
+        let start = match (known, previous) {
+            (true, _) => code,
+            (false, Some(p)) if code as usize == table.len() => p,
+            _ => return Err(Error::UnexpectedInput(format!("invalid LZW code {}", code))),
+        };
+        string.clear();
+        let mut c = start;
+        while c != u16::MAX {
+            let (prefix, index) = table[c as usize];
+            string.push(index);
+            c = prefix;
+        }
+        string.reverse();
+        let first = string[0];
+        if !known {
+            string.push(first);
+        }
+        out.extend_from_slice(&string);
+
+        if let Some(p) = previous {
+            if table.len() < 1 << MAX_CODE_BITS {
+                table.push((p, first));
+                if table.len() == 1 << code_bits && code_bits < MAX_CODE_BITS {
+                    code_bits += 1;
+                }
+            }
+        }
+        previous = Some(code);
+    }
+    Ok(pad(out, len))
+}
+
+/// Makes `out` `len` long. Pixels missing from truncated data are the index 0.
+fn pad(mut out: Vec<u8>, len: usize) -> Vec<u8> {
+    out.resize(len, 0);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transparent_pixel() {
+        // The well-known 1x1 transparent GIF.
+        let bytes = [
+            0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0xff,
+            0xff, 0xff, 0x00, 0x00, 0x00, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c,
+            0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00,
+            0x3b,
+        ];
+        let image = decode(&bytes).expect("failed to decode GIF");
+        assert_eq!((1, 1), (image.width(), image.height()));
+        assert_eq!(0, image.pixel(0, 0) >> 24);
+    }
+
+    #[test]
+    fn test_lzw() {
+        // The 10x10 sample image of 4 colors from "What's in a GIF".
+        let data = [
+            0x8c, 0x2d, 0x99, 0x87, 0x2a, 0x1c, 0xdc, 0x33, 0xa0, 0x02, 0x75, 0xec, 0x95, 0xfa,
+            0xa8, 0xde, 0x60, 0x8c, 0x04, 0x91, 0x4c, 0x01,
+        ];
+        let indices = decompress_lzw(&data, 2, 100).expect("failed to decompress LZW");
+        let rows = [
+            [1, 1, 1, 1, 1, 2, 2, 2, 2, 2],
+            [1, 1, 1, 1, 1, 2, 2, 2, 2, 2],
+            [1, 1, 1, 1, 1, 2, 2, 2, 2, 2],
+            [1, 1, 1, 0, 0, 0, 0, 2, 2, 2],
+            [1, 1, 1, 0, 0, 0, 0, 2, 2, 2],
+            [2, 2, 2, 0, 0, 0, 0, 1, 1, 1],
+            [2, 2, 2, 0, 0, 0, 0, 1, 1, 1],
+            [2, 2, 2, 2, 2, 1, 1, 1, 1, 1],
+            [2, 2, 2, 2, 2, 1, 1, 1, 1, 1],
+            [2, 2, 2, 2, 2, 1, 1, 1, 1, 1],
+        ];
+        assert_eq!(rows.concat(), indices);
+    }
+
+    #[test]
+    fn test_interlaced_rows() {
+        let rows: Vec<usize> = (0..10).map(|n| interlaced_row(n, 10)).collect();
+        assert_eq!(vec![0, 8, 4, 2, 6, 1, 3, 5, 7, 9], rows);
+        assert_eq!(0, interlaced_row(0, 1));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(decode(b"GIF89a").is_err());
+        let no_image = [
+            0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x3b,
+        ];
+        assert!(decode(&no_image).is_err());
+    }
+
+    #[test]
+    fn test_too_large() {
+        // A logical screen and an image of 65535x65535 in an otherwise valid 1x1 GIF.
+        let mut bytes = vec![
+            0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0xff, 0xff, 0xff, 0xff, 0x80, 0x00, 0x00, 0xff,
+            0xff, 0xff, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00,
+            0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+        ];
+        assert!(decode(&bytes).is_err());
+        bytes[6..10].copy_from_slice(&[1, 0, 1, 0]);
+        assert!(decode(&bytes).is_ok());
+        bytes[24..28].copy_from_slice(&[0xff; 4]);
+        assert!(decode(&bytes).is_err());
+    }
+}
@@ -0,0 +1,361 @@
+use crate::error::Error;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.5
+/// The base lengths of the length codes 257..285, and their numbers of extra bits.
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// The base distances of the distance codes 0..29, and their numbers of extra bits.
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.7
+/// The order in which "code lengths for the code length alphabet" are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn truncated() -> Error {
+    Error::UnexpectedInput("deflate data is truncated".to_string())
+}
+
+/// Reads bits from the least significant bit of each byte, as "data elements are packed into
+/// bytes in order of increasing bit number within the byte".
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bit_buf: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn bits(&mut self, n: u32) -> Result<u32, Error> {
+        while self.bit_count < n {
+            let byte = *self.data.get(self.pos).ok_or_else(truncated)?;
+            self.pos += 1;
+            self.bit_buf |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buf & ((1u32 << n) - 1);
+        self.bit_buf >>= n;
+        self.bit_count -= n;
+        Ok(value)
+    }
+
+    /// Drops the rest of the current byte. Fewer than 8 bits are buffered after each read, so
+    /// they are all in the current byte.
+    fn align_to_byte(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+}
+
+/// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.2
+/// A canonical Huffman code given by the code length of each symbol. `counts[len]` is the number
+/// of codes of `len` bits, and `symbols` are sorted by their codes.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    /// Reads a code bit by bit. "Huffman codes are packed starting with the most-significant bit
+    /// of the code."
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, Error> {
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+        for len in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return self
+                    .symbols
+                    .get((index + code - first) as usize)
+                    .copied()
+                    .ok_or_else(|| Error::UnexpectedInput("invalid Huffman code".to_string()));
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(Error::UnexpectedInput("invalid Huffman code".to_string()))
+    }
+}
+
+/// https://www.rfc-editor.org/rfc/rfc1950
+/// Decompresses a zlib stream: "CMF" and "FLG" bytes, deflate data and an Adler-32 checksum,
+/// which isn't verified.
+pub fn decompress_zlib(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let (cmf, flg) = match data {
+        [cmf, flg, ..] => (*cmf, *flg),
+        _ => return Err(truncated()),
+    };
+    // "CM = 8 denotes the "deflate" compression method", and "the FCHECK value must be such that
+    // CMF and FLG, when viewed as a 16-bit unsigned integer stored in MSB order (CMF*256 + FLG),
+    // is a multiple of 31."
+    if cmf & 0x0f != 8 || !((cmf as u16) << 8 | flg as u16).is_multiple_of(31) {
+        return Err(Error::UnexpectedInput(format!(
+            "invalid zlib header {:#04x} {:#04x}",
+            cmf, flg
+        )));
+    }
+    if flg & 0x20 != 0 {
+        return Err(Error::UnexpectedInput(
+            "zlib preset dictionaries are not supported".to_string(),
+        ));
+    }
+    inflate(&data[2..])
+}
+
+/// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.3
+/// Decompresses deflate data, which is a series of stored, fixed Huffman or dynamic Huffman
+/// blocks. The last block has the BFINAL bit set.
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut reader = BitReader::new(data);
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => inflate_stored(&mut reader, &mut out)?,
+            1 => {
+                let (literals, distances) = fixed_codes();
+                inflate_codes(&mut reader, &mut out, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut reader)?;
+                inflate_codes(&mut reader, &mut out, &literals, &distances)?;
+            }
+            _ => {
+                return Err(Error::UnexpectedInput(
+                    "invalid deflate block type".to_string(),
+                ))
+            }
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.4
+/// "Any bits of input up to the next byte boundary are ignored. The rest of the block consists of
+/// the following information: LEN, NLEN, LEN bytes of literal data".
+fn inflate_stored(reader: &mut BitReader, out: &mut Vec<u8>) -> Result<(), Error> {
+    reader.align_to_byte();
+    let pos = reader.pos;
+    let header = reader.data.get(pos..pos + 4).ok_or_else(truncated)?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+        return Err(Error::UnexpectedInput(
+            "stored block length doesn't match its complement".to_string(),
+        ));
+    }
+    let start = pos + 4;
+    let bytes = reader
+        .data
+        .get(start..start + len as usize)
+        .ok_or_else(truncated)?;
+    out.extend_from_slice(bytes);
+    reader.pos = start + len as usize;
+    Ok(())
+}
+
+/// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.6
+/// The fixed Huffman codes of literal/length and distance codes.
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    for (symbol, len) in lengths.iter_mut().enumerate() {
+        *len = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    (Huffman::new(&lengths), Huffman::new(&[5u8; 30]))
+}
+
+/// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.7
+/// Reads the Huffman codes of a block compressed with dynamic Huffman codes. The code lengths of
+/// both codes are themselves Huffman coded with run lengths.
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), Error> {
+    let hlit = reader.bits(5)? as usize + 257;
+    let hdist = reader.bits(5)? as usize + 1;
+    let hclen = reader.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for &i in CODE_LENGTH_ORDER.iter().take(hclen) {
+        code_lengths[i] = reader.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(hlit + hdist);
+    while lengths.len() < hlit + hdist {
+        let (len, repeat) = match code_length_code.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            // "Copy the previous code length 3 - 6 times."
+            16 => match lengths.last() {
+                Some(&previous) => (previous, 3 + reader.bits(2)?),
+                None => {
+                    return Err(Error::UnexpectedInput(
+                        "code length repeat without a previous length".to_string(),
+                    ))
+                }
+            },
+            // "Repeat a code length of 0 for 3 - 10 times" and "for 11 - 138 times".
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        for _ in 0..repeat {
+            lengths.push(len);
+        }
+    }
+    if lengths.len() > hlit + hdist {
+        return Err(Error::UnexpectedInput(
+            "code lengths overflow the alphabets".to_string(),
+        ));
+    }
+    Ok((
+        Huffman::new(&lengths[..hlit]),
+        Huffman::new(&lengths[hlit..]),
+    ))
+}
+
+/// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.5
+/// Decodes literals and <length, backward distance> pairs until the end-of-block code 256.
+fn inflate_codes(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), Error> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let i = symbol - 257;
+                let length =
+                    LENGTH_BASES[i] as usize + reader.bits(LENGTH_EXTRA_BITS[i] as u32)? as usize;
+                let d = distances.decode(reader)? as usize;
+                if d >= DISTANCE_BASES.len() {
+                    return Err(Error::UnexpectedInput(format!(
+                        "invalid distance code {}",
+                        d
+                    )));
+                }
+                let distance = DISTANCE_BASES[d] as usize
+                    + reader.bits(DISTANCE_EXTRA_BITS[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(Error::UnexpectedInput(format!(
+                        "distance {} is before the start of the output",
+                        distance
+                    )));
+                }
+                // The copy may overlap the bytes it produces.
+                let start = out.len() - distance;
+                for j in 0..length {
+                    out.push(out[start + j]);
+                }
+            }
+            _ => {
+                return Err(Error::UnexpectedInput(format!(
+                    "invalid literal/length code {}",
+                    symbol
+                )))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_block() {
+        let data = [0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c'];
+        assert_eq!(Ok(b"abc".to_vec()), inflate(&data));
+        assert!(inflate(&[0x01, 0x03, 0x00, 0x00, 0x00, b'a']).is_err());
+    }
+
+    #[test]
+    fn test_fixed_huffman() {
+        // zlib.compress(b"hello hello hello")
+        let data = [
+            120, 218, 203, 72, 205, 201, 201, 87, 200, 64, 144, 0, 58, 46, 6, 125,
+        ];
+        assert_eq!(Ok(b"hello hello hello".to_vec()), decompress_zlib(&data));
+    }
+
+    #[test]
+    fn test_dynamic_huffman() {
+        // zlib.compress(data, 9) of 200 letters picked by a linear congruential generator, which
+        // are skewed enough to be compressed with dynamic Huffman codes.
+        let data = [
+            120, 218, 53, 141, 201, 13, 192, 64, 8, 3, 107, 245, 65, 255, 45, 196, 134, 44, 15,
+            132, 124, 12, 48, 72, 130, 128, 70, 2, 48, 112, 87, 38, 186, 98, 213, 3, 117, 78, 163,
+            185, 9, 15, 57, 179, 146, 68, 55, 100, 81, 190, 230, 188, 82, 0, 69, 64, 24, 30, 179,
+            185, 184, 125, 181, 127, 74, 227, 101, 187, 188, 213, 0, 212, 82, 208, 144, 61, 203,
+            63, 192, 49, 240, 243, 222, 240, 3, 23, 197, 76, 144,
+        ];
+        assert_eq!((data[2] >> 1) & 3, 2);
+        let mut x: u32 = 1;
+        let mut expected = Vec::new();
+        for _ in 0..200 {
+            x = x.wrapping_mul(1103515245).wrapping_add(12345) & 0x7fffffff;
+            expected.push(b"aaaaaaaabbbbccde"[((x >> 16) % 16) as usize]);
+        }
+        assert_eq!(Ok(expected), decompress_zlib(&data));
+    }
+
+    #[test]
+    fn test_invalid_zlib_header() {
+        assert!(decompress_zlib(&[0x78, 0x00, 0x03, 0x00]).is_err());
+        assert!(decompress_zlib(&[0x78]).is_err());
+    }
+}
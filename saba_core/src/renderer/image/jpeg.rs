@@ -0,0 +1,692 @@
+use crate::error::Error;
+use crate::renderer::image::argb;
+use crate::renderer::image::pixel_count;
+use crate::renderer::image::read_u16_be;
+use crate::renderer::image::truncated;
+use crate::renderer::image::Image;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// https://www.w3.org/Graphics/JPEG/itu-t81.pdf
+/// "Figure A.6 – Zig-zag sequence of quantized DCT coefficients". The index in the 8x8 block of
+/// each coefficient in the order of the data.
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// "B.2.2 Frame header syntax". A component of the frame and the samples decoded for it.
+struct Component {
+    id: u8,
+    /// The horizontal and vertical sampling factors.
+    h: usize,
+    v: usize,
+    /// The quantization table, the DC Huffman table and the AC Huffman table.
+    tq: usize,
+    td: usize,
+    ta: usize,
+    /// The DC coefficient of the previous block, from which the next one is coded as a difference.
+    pred: i32,
+    samples: Vec<u8>,
+    samples_width: usize,
+}
+
+/// "Annex C – Huffman table specification" and "F.2.2.3 The DECODE procedure". `maxcode[l]` is
+/// the largest code of `l` bits, `valptr[l]` is the index in `values` of the smallest one, and
+/// `mincode[l]` is the smallest one.
+#[derive(Clone)]
+struct HuffmanTable {
+    maxcode: [i32; 17],
+    mincode: [i32; 17],
+    valptr: [i32; 17],
+    values: Vec<u8>,
+}
+
+impl HuffmanTable {
+    fn new(counts: &[u8], values: Vec<u8>) -> Self {
+        let mut table = Self {
+            maxcode: [-1; 17],
+            mincode: [0; 17],
+            valptr: [0; 17],
+            values,
+        };
+        let (mut code, mut k) = (0i32, 0i32);
+        for l in 1..=16 {
+            let count = counts[l - 1] as i32;
+            table.valptr[l] = k;
+            table.mincode[l] = code;
+            code += count;
+            k += count;
+            if count > 0 {
+                table.maxcode[l] = code - 1;
+            }
+            code <<= 1;
+        }
+        table
+    }
+
+    fn decode(&self, reader: &mut EntropyReader) -> Result<u8, Error> {
+        let mut code = reader.bit() as i32;
+        for l in 1..=16 {
+            if code <= self.maxcode[l] {
+                let j = self.valptr[l] + code - self.mincode[l];
+                return self.values.get(j as usize).copied().ok_or_else(|| {
+                    Error::UnexpectedInput("invalid JPEG Huffman code".to_string())
+                });
+            }
+            code = (code << 1) | reader.bit() as i32;
+        }
+        Err(Error::UnexpectedInput(
+            "invalid JPEG Huffman code".to_string(),
+        ))
+    }
+}
+
+/// Reads the entropy-coded data of a scan from the most significant bit. "F.1.2.3 Byte
+/// stuffing": a 0xFF byte in the data is followed by a 0x00 byte, which is dropped. At a marker,
+/// zeros are read so that a truncated scan doesn't fail.
+struct EntropyReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    byte: u8,
+    bit_count: u32,
+}
+
+impl EntropyReader<'_> {
+    fn bit(&mut self) -> u32 {
+        if self.bit_count == 0 {
+            self.byte = match self.data.get(self.pos) {
+                Some(0xff) if self.data.get(self.pos + 1) == Some(&0x00) => {
+                    self.pos += 2;
+                    0xff
+                }
+                Some(0xff) | None => 0,
+                Some(&b) => {
+                    self.pos += 1;
+                    b
+                }
+            };
+            self.bit_count = 8;
+        }
+        self.bit_count -= 1;
+        ((self.byte >> self.bit_count) & 1) as u32
+    }
+
+    /// "F.2.2.4 The RECEIVE procedure" followed by "F.2.2.1 The EXTEND procedure". Reads a value
+    /// of `size` bits, where values whose first bit is 0 are negative.
+    fn receive_extend(&mut self, size: u8) -> i32 {
+        let mut value = 0i32;
+        for _ in 0..size {
+            value = (value << 1) | self.bit() as i32;
+        }
+        if size > 0 && value < 1 << (size - 1) {
+            value - (1 << size) + 1
+        } else {
+            value
+        }
+    }
+
+    /// "F.1.2.3 Restart interval". Drops the remaining bits and skips the RSTn marker.
+    fn restart(&mut self) {
+        self.bit_count = 0;
+        if self.data.get(self.pos) == Some(&0xff)
+            && matches!(self.data.get(self.pos + 1), Some(0xd0..=0xd7))
+        {
+            self.pos += 2;
+        }
+    }
+}
+
+/// https://www.w3.org/Graphics/JPEG/itu-t81.pdf
+/// Decodes a baseline sequential JPEG image with Huffman coding. "Annex B – Compressed data
+/// formats": the image is a sequence of marker segments with the tables, the frame header and
+/// scans of entropy-coded data. Images of 1 component are greyscale and images of 3 components
+/// are YCbCr as in JFIF. Progressive and arithmetic-coded images are not supported.
+pub fn decode(bytes: &[u8]) -> Result<Image, Error> {
+    let mut quantization = [[0u16; 64]; 4];
+    let mut dc_tables: [Option<HuffmanTable>; 4] = [None, None, None, None];
+    let mut ac_tables: [Option<HuffmanTable>; 4] = [None, None, None, None];
+    let mut components: Vec<Component> = Vec::new();
+    let (mut width, mut height) = (0, 0);
+    let mut restart_interval = 0;
+
+    let mut pos = 2;
+    loop {
+        if bytes.get(pos) != Some(&0xff) {
+            return Err(truncated());
+        }
+        let marker = *bytes.get(pos + 1).ok_or_else(truncated)?;
+        pos += 2;
+        match marker {
+            // A fill byte before a marker.
+            0xff => {
+                pos -= 1;
+                continue;
+            }
+            // EOI.
+            0xd9 => break,
+            0xd0..=0xd8 | 0x01 => continue,
+            _ => {}
+        }
+        let len = read_u16_be(bytes, pos)? as usize;
+        let segment = bytes.get(pos + 2..pos + len).ok_or_else(truncated)?;
+        pos += len;
+        match marker {
+            // "B.2.4.1 Quantization table-specification syntax".
+            0xdb => {
+                let mut s = segment;
+                while let [pq_tq, rest @ ..] = s {
+                    let (precision, tq) = ((pq_tq >> 4) as usize, (pq_tq & 0x0f) as usize & 3);
+                    let size = 64 * (precision + 1);
+                    let values = rest.get(..size).ok_or_else(truncated)?;
+                    for (k, q) in quantization[tq].iter_mut().enumerate() {
+                        *q = if precision == 0 {
+                            values[k] as u16
+                        } else {
+                            u16::from_be_bytes([values[2 * k], values[2 * k + 1]])
+                        };
+                    }
+                    s = &rest[size..];
+                }
+            }
+            // "B.2.4.2 Huffman table-specification syntax".
+            0xc4 => {
+                let mut s = segment;
+                while let [tc_th, rest @ ..] = s {
+                    let counts = rest.get(..16).ok_or_else(truncated)?;
+                    let total = counts.iter().map(|&c| c as usize).sum::<usize>();
+                    let values = rest.get(16..16 + total).ok_or_else(truncated)?;
+                    let table = Some(HuffmanTable::new(counts, values.to_vec()));
+                    if tc_th >> 4 == 0 {
+                        dc_tables[(tc_th & 3) as usize] = table;
+                    } else {
+                        ac_tables[(tc_th & 3) as usize] = table;
+                    }
+                    s = &rest[16 + total..];
+                }
+            }
+            // "B.2.2 Frame header syntax" of baseline and extended sequential DCT.
+            0xc0 | 0xc1 => {
+                if segment.len() < 6 || segment[0] != 8 {
+                    return Err(Error::UnexpectedInput(
+                        "JPEG sample precision must be 8 bits".to_string(),
+                    ));
+                }
+                height = u16::from_be_bytes([segment[1], segment[2]]) as usize;
+                width = u16::from_be_bytes([segment[3], segment[4]]) as usize;
+                let count = segment[5] as usize;
+                let specs = segment.get(6..6 + count * 3).ok_or_else(truncated)?;
+                components = specs
+                    .as_chunks::<3>()
+                    .0
+                    .iter()
+                    .map(|c| Component {
+                        id: c[0],
+                        h: (c[1] >> 4).clamp(1, 4) as usize,
+                        v: (c[1] & 0x0f).clamp(1, 4) as usize,
+                        tq: (c[2] & 3) as usize,
+                        td: 0,
+                        ta: 0,
+                        pred: 0,
+                        samples: Vec::new(),
+                        samples_width: 0,
+                    })
+                    .collect();
+                if width == 0 || height == 0 || !matches!(count, 1 | 3) {
+                    return Err(Error::UnexpectedInput(format!(
+                        "JPEG image of {}x{} with {} components is not supported",
+                        width, height, count
+                    )));
+                }
+                pixel_count(width, height)?;
+                let (h_max, v_max) = max_sampling(&components);
+                let (mcus_x, mcus_y) = (width.div_ceil(8 * h_max), height.div_ceil(8 * v_max));
+                for c in components.iter_mut() {
+                    c.samples_width = mcus_x * c.h * 8;
+                    c.samples = vec![0; c.samples_width * mcus_y * c.v * 8];
+                }
+            }
+            0xc2 | 0xc3 | 0xc5..=0xc7 | 0xc9..=0xcb | 0xcd..=0xcf => {
+                return Err(Error::UnexpectedInput(format!(
+                    "JPEG process of SOF{} is not supported",
+                    marker - 0xc0
+                )))
+            }
+            // "B.2.4.4 Restart interval definition syntax".
+            0xdd => restart_interval = read_u16_be(segment, 0)? as usize,
+            // "B.2.3 Scan header syntax".
+            0xda => {
+                let count = *segment.first().ok_or_else(truncated)? as usize;
+                let specs = segment.get(1..1 + count * 2).ok_or_else(truncated)?;
+                let mut scan = Vec::new();
+                for spec in specs.as_chunks::<2>().0 {
+                    let i = components
+                        .iter()
+                        .position(|c| c.id == spec[0])
+                        .ok_or_else(|| {
+                            Error::UnexpectedInput(format!(
+                                "JPEG scan has an unknown component {}",
+                                spec[0]
+                            ))
+                        })?;
+                    components[i].td = (spec[1] >> 4) as usize & 3;
+                    components[i].ta = (spec[1] & 0x0f) as usize & 3;
+                    scan.push(i);
+                }
+                let mut reader = EntropyReader {
+                    data: bytes,
+                    pos,
+                    byte: 0,
+                    bit_count: 0,
+                };
+                let tables = Tables {
+                    quantization: &quantization,
+                    dc: &dc_tables,
+                    ac: &ac_tables,
+                };
+                decode_scan(
+                    &mut reader,
+                    &tables,
+                    &mut components,
+                    &scan,
+                    (width, height),
+                    restart_interval,
+                )?;
+                // The scan ends at the next marker.
+                pos = reader.pos;
+                while pos < bytes.len()
+                    && (bytes[pos] != 0xff
+                        || matches!(bytes.get(pos + 1), Some(0x00 | 0xd0..=0xd7)))
+                {
+                    pos += 1;
+                }
+            }
+            // APPn, COM and the other segments are skipped.
+            _ => {}
+        }
+    }
+
+    if components.is_empty() {
+        return Err(Error::UnexpectedInput(
+            "JPEG image has no frame".to_string(),
+        ));
+    }
+    let (h_max, v_max) = max_sampling(&components);
+    // The samples of a component at a pixel. Subsampled components are upsampled by repeating
+    // samples.
+    let sample = |c: &Component, x: usize, y: usize| -> i32 {
+        c.samples[(y * c.v / v_max) * c.samples_width + x * c.h / h_max] as i32
+    };
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let luma = sample(&components[0], x, y);
+            if components.len() == 1 {
+                pixels.push(argb(luma as u8, luma as u8, luma as u8, 255));
+            } else {
+                let cb = sample(&components[1], x, y);
+                let cr = sample(&components[2], x, y);
+                let (r, g, b) = ycbcr_to_rgb(luma, cb, cr);
+                pixels.push(argb(r, g, b, 255));
+            }
+        }
+    }
+    Image::new(width as i64, height as i64, pixels)
+}
+
+/// The tables in effect for a scan.
+struct Tables<'a> {
+    quantization: &'a [[u16; 64]; 4],
+    dc: &'a [Option<HuffmanTable>; 4],
+    ac: &'a [Option<HuffmanTable>; 4],
+}
+
+fn max_sampling(components: &[Component]) -> (usize, usize) {
+    (
+        components.iter().map(|c| c.h).max().unwrap_or(1),
+        components.iter().map(|c| c.v).max().unwrap_or(1),
+    )
+}
+
+/// "A.2 Order of source image data encoding". A scan of one component is a sequence of its
+/// blocks from left to right and top to bottom. A scan of several components is a sequence of
+/// MCUs, each of which has h x v blocks of each component.
+fn decode_scan(
+    reader: &mut EntropyReader,
+    tables: &Tables,
+    components: &mut [Component],
+    scan: &[usize],
+    (width, height): (usize, usize),
+    restart_interval: usize,
+) -> Result<(), Error> {
+    let (h_max, v_max) = max_sampling(components);
+    // Each unit is a list of (component, block x, block y).
+    let units: Vec<Vec<(usize, usize, usize)>> = if let [i] = scan {
+        let c = &components[*i];
+        let blocks_x = (width * c.h).div_ceil(h_max).div_ceil(8);
+        let blocks_y = (height * c.v).div_ceil(v_max).div_ceil(8);
+        (0..blocks_y)
+            .flat_map(|y| (0..blocks_x).map(move |x| vec![(*i, x, y)]))
+            .collect()
+    } else {
+        let (mcus_x, mcus_y) = (width.div_ceil(8 * h_max), height.div_ceil(8 * v_max));
+        let mut units = Vec::with_capacity(mcus_x * mcus_y);
+        for my in 0..mcus_y {
+            for mx in 0..mcus_x {
+                let mut unit = Vec::new();
+                for &i in scan {
+                    let c = &components[i];
+                    for v in 0..c.v {
+                        for h in 0..c.h {
+                            unit.push((i, mx * c.h + h, my * c.v + v));
+                        }
+                    }
+                }
+                units.push(unit);
+            }
+        }
+        units
+    };
+
+    for &i in scan {
+        components[i].pred = 0;
+    }
+    for (n, unit) in units.iter().enumerate() {
+        if restart_interval > 0 && n > 0 && n % restart_interval == 0 {
+            reader.restart();
+            for &i in scan {
+                components[i].pred = 0;
+            }
+        }
+        for &(i, bx, by) in unit {
+            let c = &mut components[i];
+            let missing = || Error::UnexpectedInput("JPEG Huffman table is missing".to_string());
+            let dc = tables.dc[c.td].as_ref().ok_or_else(missing)?;
+            let ac = tables.ac[c.ta].as_ref().ok_or_else(missing)?;
+            let block = decode_block(reader, dc, ac, &tables.quantization[c.tq], &mut c.pred)?;
+            let pixels = idct(&block);
+            for y in 0..8 {
+                let start = (by * 8 + y) * c.samples_width + bx * 8;
+                c.samples[start..start + 8].copy_from_slice(&pixels[y * 8..y * 8 + 8]);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// "F.2.2 Baseline Huffman decoding procedures". Decodes the DC difference and the AC
+/// coefficients of a block, and dequantizes them into the natural order.
+fn decode_block(
+    reader: &mut EntropyReader,
+    dc: &HuffmanTable,
+    ac: &HuffmanTable,
+    quantization: &[u16; 64],
+    pred: &mut i32,
+) -> Result<[i32; 64], Error> {
+    let mut block = [0i32; 64];
+    let size = dc.decode(reader)?;
+    if size > 11 {
+        return Err(Error::UnexpectedInput(format!(
+            "invalid JPEG DC difference size {}",
+            size
+        )));
+    }
+    *pred += reader.receive_extend(size);
+    block[0] = *pred * quantization[0] as i32;
+    let mut k = 1;
+    while k < 64 {
+        let rs = ac.decode(reader)?;
+        let (run, size) = ((rs >> 4) as usize, rs & 0x0f);
+        if size == 0 {
+            // "ZRL" skips 16 zero coefficients, and the others are "EOB".
+            if run == 15 {
+                k += 16;
+                continue;
+            }
+            break;
+        }
+        k += run;
+        if k > 63 {
+            return Err(Error::UnexpectedInput(
+                "JPEG AC coefficients overflow the block".to_string(),
+            ));
+        }
+        block[ZIGZAG[k]] = reader.receive_extend(size) * quantization[k] as i32;
+        k += 1;
+    }
+    Ok(block)
+}
+
+/// "A.3.3 FDCT and IDCT". The inverse DCT in integer arithmetic, as the "islow" method of the
+/// Independent JPEG Group: a pass over the columns and a pass over the rows, each of which is a
+/// 1-D IDCT with constants scaled by 2^13. Returns the samples of the block shifted by +128.
+fn idct(block: &[i32; 64]) -> [u8; 64] {
+    const CONST_BITS: i32 = 13;
+    const PASS1_BITS: i32 = 2;
+    const FIX_0_298631336: i32 = 2446;
+    const FIX_0_390180644: i32 = 3196;
+    const FIX_0_541196100: i32 = 4433;
+    const FIX_0_765366865: i32 = 6270;
+    const FIX_0_899976223: i32 = 7373;
+    const FIX_1_175875602: i32 = 9633;
+    const FIX_1_501321110: i32 = 12299;
+    const FIX_1_847759065: i32 = 15137;
+    const FIX_1_961570560: i32 = 16069;
+    const FIX_2_053119869: i32 = 16819;
+    const FIX_2_562915447: i32 = 20995;
+    const FIX_3_072711026: i32 = 25172;
+
+    let descale = |x: i32, n: i32| (x + (1 << (n - 1))) >> n;
+    // The 1-D IDCT of 8 values, which are `get(0)`..`get(7)`, unscaled.
+    let idct_1d = |get: &dyn Fn(usize) -> i32| -> [i32; 8] {
+        let z2 = get(2);
+        let z3 = get(6);
+        let z1 = (z2 + z3) * FIX_0_541196100;
+        let tmp2 = z1 - z3 * FIX_1_847759065;
+        let tmp3 = z1 + z2 * FIX_0_765366865;
+        let tmp0 = (get(0) + get(4)) << CONST_BITS;
+        let tmp1 = (get(0) - get(4)) << CONST_BITS;
+        let (tmp10, tmp13) = (tmp0 + tmp3, tmp0 - tmp3);
+        let (tmp11, tmp12) = (tmp1 + tmp2, tmp1 - tmp2);
+
+        let (mut t0, mut t1, mut t2, mut t3) = (get(7), get(5), get(3), get(1));
+        let z1 = t0 + t3;
+        let z2 = t1 + t2;
+        let z3 = t0 + t2;
+        let z4 = t1 + t3;
+        let z5 = (z3 + z4) * FIX_1_175875602;
+        t0 *= FIX_0_298631336;
+        t1 *= FIX_2_053119869;
+        t2 *= FIX_3_072711026;
+        t3 *= FIX_1_501321110;
+        let z1 = -z1 * FIX_0_899976223;
+        let z2 = -z2 * FIX_2_562915447;
+        let z3 = -z3 * FIX_1_961570560 + z5;
+        let z4 = -z4 * FIX_0_390180644 + z5;
+        t0 += z1 + z3;
+        t1 += z2 + z4;
+        t2 += z2 + z3;
+        t3 += z1 + z4;
+        [
+            tmp10 + t3,
+            tmp11 + t2,
+            tmp12 + t1,
+            tmp13 + t0,
+            tmp13 - t0,
+            tmp12 - t1,
+            tmp11 - t2,
+            tmp10 - t3,
+        ]
+    };
+
+    let mut workspace = [0i32; 64];
+    for x in 0..8 {
+        let column = idct_1d(&|i| block[i * 8 + x]);
+        for (y, value) in column.iter().enumerate() {
+            workspace[y * 8 + x] = descale(*value, CONST_BITS - PASS1_BITS);
+        }
+    }
+    let mut out = [0u8; 64];
+    for y in 0..8 {
+        let row = idct_1d(&|i| workspace[y * 8 + i]);
+        for (x, value) in row.iter().enumerate() {
+            let sample = descale(*value, CONST_BITS + PASS1_BITS + 3) + 128;
+            out[y * 8 + x] = sample.clamp(0, 255) as u8;
+        }
+    }
+    out
+}
+
+/// https://www.w3.org/Graphics/JPEG/jfif3.pdf
+/// "Conversion to and from RGB": R = Y + 1.402 (Cr-128), G = Y - 0.34414 (Cb-128) - 0.71414
+/// (Cr-128), B = Y + 1.772 (Cb-128). The coefficients are scaled by 2^16.
+fn ycbcr_to_rgb(y: i32, cb: i32, cr: i32) -> (u8, u8, u8) {
+    let (cb, cr) = (cb - 128, cr - 128);
+    let y = y << 16;
+    let round = 1 << 15;
+    let r = (y + 91881 * cr + round) >> 16;
+    let g = (y - 22554 * cb - 46802 * cr + round) >> 16;
+    let b = (y + 116130 * cb + round) >> 16;
+    (
+        r.clamp(0, 255) as u8,
+        g.clamp(0, 255) as u8,
+        b.clamp(0, 255) as u8,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a baseline JPEG file around the entropy-coded data `scan`. All coefficients are
+    /// quantized by `q`. The DC Huffman table has codes of 4 bits for the sizes 0 to 11, and the
+    /// AC table has "0" for EOB and "10" for a coefficient of 1 bit after no zeros. All
+    /// components use table 0 and have the sampling factors in `sampling`.
+    fn jpeg(
+        width: u16,
+        height: u16,
+        sampling: &[u8],
+        q: u8,
+        restart_interval: u16,
+        scan: &[u8],
+    ) -> Vec<u8> {
+        let mut bytes = vec![0xff, 0xd8];
+        let mut segment = |marker: u8, data: &[u8]| {
+            bytes.extend_from_slice(&[0xff, marker]);
+            bytes.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+            bytes.extend_from_slice(data);
+        };
+        let mut dqt = vec![0];
+        dqt.extend_from_slice(&[q; 64]);
+        segment(0xdb, &dqt);
+        let mut dc = vec![0x00, 0, 0, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        dc.extend(0..12);
+        segment(0xc4, &dc);
+        let ac = [
+            0x10, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x00, 0x01,
+        ];
+        segment(0xc4, &ac);
+        let mut sof = vec![8];
+        sof.extend_from_slice(&height.to_be_bytes());
+        sof.extend_from_slice(&width.to_be_bytes());
+        sof.push(sampling.len() as u8);
+        let mut sos = vec![sampling.len() as u8];
+        for (i, hv) in sampling.iter().enumerate() {
+            sof.extend_from_slice(&[i as u8 + 1, *hv, 0]);
+            sos.extend_from_slice(&[i as u8 + 1, 0x00]);
+        }
+        sos.extend_from_slice(&[0, 63, 0]);
+        segment(0xc0, &sof);
+        if restart_interval > 0 {
+            segment(0xdd, &restart_interval.to_be_bytes());
+        }
+        segment(0xda, &sos);
+        bytes.extend_from_slice(scan);
+        bytes.extend_from_slice(&[0xff, 0xd9]);
+        bytes
+    }
+
+    #[test]
+    fn test_greyscale_dc() {
+        // Two blocks whose DC coefficients are -512 and 512, which are 64 and 192 after the IDCT.
+        let bytes = jpeg(16, 8, &[0x11], 1, 0, &[0xa7, 0xfd, 0x70, 0x01]);
+        let image = decode(&bytes).expect("failed to decode JPEG");
+        assert_eq!((16, 8), (image.width(), image.height()));
+        assert_eq!(0xff404040, image.pixel(0, 0));
+        assert_eq!(0xff404040, image.pixel(7, 7));
+        assert_eq!(0xffc0c0c0, image.pixel(8, 0));
+        assert_eq!(0xffc0c0c0, image.pixel(15, 7));
+    }
+
+    #[test]
+    fn test_ycbcr() {
+        // Y = 100, Cb = 128 and Cr = 200.
+        let bytes = jpeg(
+            8,
+            8,
+            &[0x11, 0x11, 0x11],
+            1,
+            0,
+            &[0x81, 0xf0, 0x2a, 0x40, 0x7f],
+        );
+        let image = decode(&bytes).expect("failed to decode JPEG");
+        assert_eq!(0xffc93164, image.pixel(3, 3));
+        assert_eq!((201, 49, 100), ycbcr_to_rgb(100, 128, 200));
+        assert_eq!((255, 255, 255), ycbcr_to_rgb(255, 128, 128));
+    }
+
+    #[test]
+    fn test_subsampling_and_restart() {
+        // Two MCUs of 16x16 with a restart marker between them. The first MCU has 4 luma blocks
+        // of 128, 144, 160 and 176, and the second has 4 blocks of 64. The chroma is neutral and
+        // upsampled to 16x16.
+        let scan = [
+            0x04, 0x40, 0x22, 0x01, 0x10, 0x00, 0x03, 0xff, 0xd0, 0xa7, 0xfc, 0x00, 0x00, 0x00,
+        ];
+        let bytes = jpeg(32, 16, &[0x22, 0x11, 0x11], 1, 1, &scan);
+        let image = decode(&bytes).expect("failed to decode JPEG");
+        assert_eq!(0xff808080, image.pixel(0, 0));
+        assert_eq!(0xff909090, image.pixel(8, 0));
+        assert_eq!(0xffa0a0a0, image.pixel(0, 8));
+        assert_eq!(0xffb0b0b0, image.pixel(15, 15));
+        assert_eq!(0xff404040, image.pixel(16, 0));
+        assert_eq!(0xff404040, image.pixel(31, 15));
+    }
+
+    #[test]
+    fn test_idct_of_ac_coefficient() {
+        // Only the first horizontal frequency is 80. The samples are
+        // 128 + 80 / (4 * sqrt(2)) * cos((2x + 1) * pi / 16) in every row.
+        let image = decode(&jpeg(8, 8, &[0x11], 80, 0, &[0x0a])).expect("failed to decode JPEG");
+        let expected = [
+            141.87, 139.76, 135.86, 130.76, 125.24, 120.14, 116.24, 114.13,
+        ];
+        for (x, e) in expected.iter().enumerate() {
+            for y in 0..8 {
+                let v = (image.pixel(x as i64, y) & 0xff) as f64;
+                assert!((v - e).abs() <= 1.0, "({}, {}): {} != {}", x, y, v, e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let mut progressive = jpeg(8, 8, &[0x11], 1, 0, &[0x0a]);
+        let sof = progressive
+            .windows(2)
+            .position(|w| w == [0xff, 0xc0])
+            .expect("no SOF0");
+        progressive[sof + 1] = 0xc2;
+        assert!(decode(&progressive).is_err());
+        assert!(decode(&[0xff, 0xd8, 0xff, 0xd9]).is_err());
+        assert!(decode(&[0xff, 0xd8, 0xff, 0xdb, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_too_large() {
+        assert!(decode(&jpeg(u16::MAX, u16::MAX, &[0x11], 1, 0, &[])).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+pub mod bmp;
+pub mod gif;
+pub mod inflate;
+pub mod jpeg;
+pub mod png;
+
+use crate::error::Error;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// The largest number of pixels that an image may have. The size of an image is read from its
+/// header, so a larger image is refused before its pixels are allocated.
+pub const MAX_PIXELS: usize = 1 << 24;
+
+/// A decoded image. `pixels` are stored row by row from the top left, and each pixel is
+/// 0xAARRGGBB, where an alpha of 0 is fully transparent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: i64,
+    height: i64,
+    pixels: Vec<u32>,
+}
+
+impl Image {
+    /// Creates an image of `width` x `height` pixels. Fails if the number of `pixels` doesn't
+    /// match the size.
+    pub fn new(width: i64, height: i64, pixels: Vec<u32>) -> Result<Self, Error> {
+        let len = usize::try_from(width)
+            .ok()
+            .zip(usize::try_from(height).ok())
+            .and_then(|(w, h)| w.checked_mul(h));
+        if len != Some(pixels.len()) {
+            return Err(Error::UnexpectedInput(format!(
+                "{} pixels don't make an image of {}x{}",
+                pixels.len(),
+                width,
+                height
+            )));
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    /// Returns the pixel at (`x`, `y`). A position outside of the image is clamped to its edges,
+    /// and an empty image is transparent.
+    pub fn pixel(&self, x: i64, y: i64) -> u32 {
+        if self.pixels.is_empty() {
+            return 0;
+        }
+        let x = x.clamp(0, self.width - 1);
+        let y = y.clamp(0, self.height - 1);
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+/// https://mimesniff.spec.whatwg.org/#matching-an-image-type-pattern
+/// Decodes `bytes` in the format determined by its signature, regardless of the Content-Type of
+/// the response.
+pub fn decode(bytes: &[u8]) -> Result<Image, Error> {
+    if bytes.starts_with(&png::SIGNATURE) {
+        png::decode(bytes)
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        gif::decode(bytes)
+    } else if bytes.starts_with(b"BM") {
+        bmp::decode(bytes)
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        jpeg::decode(bytes)
+    } else {
+        Err(Error::UnexpectedInput("unknown image format".to_string()))
+    }
+}
+
+/// The images of a page by the URL written in the document. An image that failed to load or to
+/// decode is kept as broken, so that it's shown as broken instead of being loaded again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageStore {
+    images: BTreeMap<String, Option<Rc<Image>>>,
+}
+
+impl ImageStore {
+    pub fn new() -> Self {
+        Self {
+            images: BTreeMap::new(),
+        }
+    }
+
+    /// Stores the image of `url`, which is None if it's broken.
+    pub fn insert(&mut self, url: String, image: Option<Image>) {
+        self.images.insert(url, image.map(Rc::new));
+    }
+
+    /// Whether `url` has been loaded, successfully or not.
+    pub fn contains(&self, url: &str) -> bool {
+        self.images.contains_key(url)
+    }
+
+    /// Returns the image of `url`, or None if it's broken or not loaded.
+    pub fn get(&self, url: &str) -> Option<Rc<Image>> {
+        self.images.get(url).cloned().flatten()
+    }
+}
+
+/// Converts a pixel of 8-bit channels to 0xAARRGGBB.
+pub(crate) fn argb(r: u8, g: u8, b: u8, a: u8) -> u32 {
+    (a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32
+}
+
+/// Reads a little-endian u16 at `pos`.
+pub(crate) fn read_u16_le(bytes: &[u8], pos: usize) -> Result<u16, Error> {
+    match bytes.get(pos..pos + 2) {
+        Some(b) => Ok(u16::from_le_bytes([b[0], b[1]])),
+        None => Err(truncated()),
+    }
+}
+
+/// Reads a big-endian u16 at `pos`.
+pub(crate) fn read_u16_be(bytes: &[u8], pos: usize) -> Result<u16, Error> {
+    match bytes.get(pos..pos + 2) {
+        Some(b) => Ok(u16::from_be_bytes([b[0], b[1]])),
+        None => Err(truncated()),
+    }
+}
+
+/// Returns the number of pixels of an image of `width` x `height`, or an error if it's larger
+/// than MAX_PIXELS.
+pub(crate) fn pixel_count(width: usize, height: usize) -> Result<usize, Error> {
+    match width.checked_mul(height) {
+        Some(count) if count <= MAX_PIXELS => Ok(count),
+        _ => Err(Error::UnexpectedInput(format!(
+            "image of {}x{} is too large",
+            width, height
+        ))),
+    }
+}
+
+pub(crate) fn truncated() -> Error {
+    Error::UnexpectedInput("image data is truncated".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_decode_by_signature() {
+        assert!(decode(b"not an image").is_err());
+        assert!(decode(&[0xff, 0xd8, 0xff]).is_err());
+
+        let mut store = ImageStore::new();
+        assert!(!store.contains("a.png"));
+        store.insert(
+            "a.png".to_string(),
+            Some(Image::new(1, 1, vec![0xffff0000]).unwrap()),
+        );
+        store.insert("broken.png".to_string(), None);
+        assert_eq!(Some(0xffff0000), store.get("a.png").map(|i| i.pixel(0, 0)));
+        assert!(store.contains("broken.png"));
+        assert_eq!(None, store.get("broken.png"));
+    }
+
+    #[test]
+    fn test_image() {
+        assert!(Image::new(2, 2, vec![0; 3]).is_err());
+        assert!(Image::new(-1, -1, vec![0]).is_err());
+
+        // Pixels outside of the image are the ones at its edges.
+        let image = Image::new(2, 1, vec![0xffff0000, 0xff0000ff]).unwrap();
+        assert_eq!(0xffff0000, image.pixel(-1, 0));
+        assert_eq!(0xff0000ff, image.pixel(5, 5));
+        assert_eq!(0, Image::new(0, 0, Vec::new()).unwrap().pixel(0, 0));
+    }
+}
@@ -0,0 +1,457 @@
+use crate::error::Error;
+use crate::renderer::image::argb;
+use crate::renderer::image::inflate::decompress_zlib;
+use crate::renderer::image::pixel_count;
+use crate::renderer::image::truncated;
+use crate::renderer::image::Image;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// https://www.w3.org/TR/png-3/#3PNGsignature
+pub const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// https://www.w3.org/TR/png-3/#8Interlace
+/// The (x, y) offsets and the (x, y) intervals of the pixels in each pass of Adam7.
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// https://www.w3.org/TR/png-3/#6Colour-values
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ColorType {
+    Greyscale,
+    Truecolor,
+    IndexedColor,
+    GreyscaleWithAlpha,
+    TruecolorWithAlpha,
+}
+
+impl ColorType {
+    fn from_u8(value: u8) -> Result<Self, Error> {
+        match value {
+            0 => Ok(Self::Greyscale),
+            2 => Ok(Self::Truecolor),
+            3 => Ok(Self::IndexedColor),
+            4 => Ok(Self::GreyscaleWithAlpha),
+            6 => Ok(Self::TruecolorWithAlpha),
+            _ => Err(Error::UnexpectedInput(format!(
+                "invalid PNG color type {}",
+                value
+            ))),
+        }
+    }
+
+    fn channels(&self) -> usize {
+        match self {
+            Self::Greyscale | Self::IndexedColor => 1,
+            Self::GreyscaleWithAlpha => 2,
+            Self::Truecolor => 3,
+            Self::TruecolorWithAlpha => 4,
+        }
+    }
+}
+
+/// The fields of IHDR, and PLTE and tRNS that are needed to convert samples to pixels.
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: ColorType,
+    interlaced: bool,
+    palette: Vec<[u8; 3]>,
+    /// The alpha of each palette entry for an indexed-color image, or the samples of the color
+    /// that is fully transparent for the other color types.
+    transparency: Vec<u16>,
+}
+
+/// https://www.w3.org/TR/png-3/#5DataRep
+/// Decodes a PNG image. It's a sequence of chunks, and the image data is the concatenation of
+/// the IDAT chunks, compressed with zlib.
+pub fn decode(bytes: &[u8]) -> Result<Image, Error> {
+    if !bytes.starts_with(&SIGNATURE) {
+        return Err(Error::UnexpectedInput(
+            "PNG signature is missing".to_string(),
+        ));
+    }
+    let mut header = None;
+    let mut data = Vec::new();
+    let mut pos = SIGNATURE.len();
+    // "A chunk consists of three or four fields": Length, Chunk Type, Chunk Data and CRC. The CRC
+    // isn't verified.
+    loop {
+        let length = match bytes.get(pos..pos + 4) {
+            Some(b) => u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize,
+            None => return Err(truncated()),
+        };
+        let chunk_type = bytes.get(pos + 4..pos + 8).ok_or_else(truncated)?;
+        let chunk = bytes.get(pos + 8..pos + 8 + length).ok_or_else(truncated)?;
+        match chunk_type {
+            b"IHDR" => header = Some(parse_header(chunk)?),
+            b"PLTE" => {
+                if let Some(h) = header.as_mut() {
+                    h.palette = chunk.as_chunks::<3>().0.to_vec();
+                }
+            }
+            b"tRNS" => {
+                if let Some(h) = header.as_mut() {
+                    h.transparency = if h.color_type == ColorType::IndexedColor {
+                        chunk.iter().map(|&a| a as u16).collect()
+                    } else {
+                        chunk
+                            .as_chunks::<2>()
+                            .0
+                            .iter()
+                            .map(|&c| u16::from_be_bytes(c))
+                            .collect()
+                    };
+                }
+            }
+            b"IDAT" => data.extend_from_slice(chunk),
+            b"IEND" => break,
+            // Ancillary chunks such as gAMA and tEXt are ignored.
+            _ => {}
+        }
+        pos += 12 + length;
+    }
+
+    let header = header
+        .ok_or_else(|| Error::UnexpectedInput("PNG image doesn't start with IHDR".to_string()))?;
+    if header.color_type == ColorType::IndexedColor && header.palette.is_empty() {
+        return Err(Error::UnexpectedInput(
+            "indexed-color PNG image has no palette".to_string(),
+        ));
+    }
+    let data = decompress_zlib(&data)?;
+    let mut pixels = vec![0u32; header.width * header.height];
+    if header.interlaced {
+        let mut offset = 0;
+        for (x0, y0, dx, dy) in ADAM7 {
+            let width = (header.width + dx - 1 - x0) / dx;
+            let height = (header.height + dy - 1 - y0) / dy;
+            if width == 0 || height == 0 {
+                continue;
+            }
+            let pass = data.get(offset..).ok_or_else(truncated)?;
+            let (pass_pixels, used) = decode_pass(&header, pass, width, height)?;
+            offset += used;
+            for y in 0..height {
+                for x in 0..width {
+                    pixels[(y0 + y * dy) * header.width + x0 + x * dx] = pass_pixels[y * width + x];
+                }
+            }
+        }
+    } else {
+        pixels = decode_pass(&header, &data, header.width, header.height)?.0;
+    }
+    Image::new(header.width as i64, header.height as i64, pixels)
+}
+
+/// https://www.w3.org/TR/png-3/#11IHDR
+fn parse_header(chunk: &[u8]) -> Result<Header, Error> {
+    if chunk.len() < 13 {
+        return Err(truncated());
+    }
+    let width = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as usize;
+    let height = u32::from_be_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as usize;
+    pixel_count(width, height)?;
+    let bit_depth = chunk[8];
+    let color_type = ColorType::from_u8(chunk[9])?;
+    let valid_depth = match color_type {
+        ColorType::Greyscale => matches!(bit_depth, 1 | 2 | 4 | 8 | 16),
+        ColorType::IndexedColor => matches!(bit_depth, 1 | 2 | 4 | 8),
+        _ => matches!(bit_depth, 8 | 16),
+    };
+    if !valid_depth {
+        return Err(Error::UnexpectedInput(format!(
+            "invalid PNG bit depth {} for {:?}",
+            bit_depth, color_type
+        )));
+    }
+    if chunk[12] > 1 {
+        return Err(Error::UnexpectedInput(format!(
+            "invalid PNG interlace method {}",
+            chunk[12]
+        )));
+    }
+    Ok(Header {
+        width,
+        height,
+        bit_depth,
+        color_type,
+        interlaced: chunk[12] == 1,
+        palette: Vec::new(),
+        transparency: Vec::new(),
+    })
+}
+
+/// https://www.w3.org/TR/png-3/#9Filters
+/// Reverses the filters of `height` scanlines of `width` pixels at the start of `data`, and
+/// converts them to pixels. Returns the pixels and the number of bytes used.
+fn decode_pass(
+    header: &Header,
+    data: &[u8],
+    width: usize,
+    height: usize,
+) -> Result<(Vec<u32>, usize), Error> {
+    let bits_per_pixel = header.color_type.channels() * header.bit_depth as usize;
+    let stride = (width * bits_per_pixel).div_ceil(8);
+    // "bpp is defined as the number of bytes per complete pixel, rounding up to one."
+    let bpp = bits_per_pixel.div_ceil(8);
+    let mut previous = vec![0u8; stride];
+    let mut line = vec![0u8; stride];
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let start = y * (stride + 1);
+        let filter = *data.get(start).ok_or_else(truncated)?;
+        let raw = data
+            .get(start + 1..start + 1 + stride)
+            .ok_or_else(truncated)?;
+        for i in 0..stride {
+            let a = if i >= bpp { line[i - bpp] } else { 0 };
+            let b = previous[i];
+            let c = if i >= bpp { previous[i - bpp] } else { 0 };
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => {
+                    return Err(Error::UnexpectedInput(format!(
+                        "invalid PNG filter type {}",
+                        filter
+                    )))
+                }
+            };
+            line[i] = raw[i].wrapping_add(predictor);
+        }
+        for x in 0..width {
+            pixels.push(pixel(header, &line, x));
+        }
+        core::mem::swap(&mut previous, &mut line);
+    }
+    Ok((pixels, height * (stride + 1)))
+}
+
+/// https://www.w3.org/TR/png-3/#9Filter-type-4-Paeth
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Converts the `x`th pixel of an unfiltered scanline to 0xAARRGGBB. Samples of 16 bits are
+/// reduced to their high byte, and samples of fewer than 8 bits are scaled up to 8 bits.
+fn pixel(header: &Header, line: &[u8], x: usize) -> u32 {
+    let depth = header.bit_depth as usize;
+    let channels = header.color_type.channels();
+    // The raw value of the `i`th sample of the pixel.
+    let sample = |i: usize| -> u16 {
+        let bit = (x * channels + i) * depth;
+        match depth {
+            16 => u16::from_be_bytes([line[bit / 8], line[bit / 8 + 1]]),
+            8 => line[bit / 8] as u16,
+            _ => ((line[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1)) as u16,
+        }
+    };
+    let to_u8 = |value: u16| -> u8 {
+        match depth {
+            16 => (value >> 8) as u8,
+            8 => value as u8,
+            _ => (value * 255 / ((1 << depth) - 1)) as u8,
+        }
+    };
+    let samples: Vec<u16> = (0..channels).map(sample).collect();
+    match header.color_type {
+        ColorType::Greyscale => {
+            let alpha = if header.transparency.first() == Some(&samples[0]) {
+                0
+            } else {
+                255
+            };
+            let v = to_u8(samples[0]);
+            argb(v, v, v, alpha)
+        }
+        ColorType::Truecolor => {
+            let alpha = if header.transparency.len() == 3 && header.transparency == samples {
+                0
+            } else {
+                255
+            };
+            argb(
+                to_u8(samples[0]),
+                to_u8(samples[1]),
+                to_u8(samples[2]),
+                alpha,
+            )
+        }
+        ColorType::IndexedColor => {
+            let index = samples[0] as usize;
+            let [r, g, b] = header.palette.get(index).copied().unwrap_or([0, 0, 0]);
+            let alpha = header.transparency.get(index).map_or(255, |&a| a as u8);
+            argb(r, g, b, alpha)
+        }
+        ColorType::GreyscaleWithAlpha => {
+            let v = to_u8(samples[0]);
+            argb(v, v, v, to_u8(samples[1]))
+        }
+        ColorType::TruecolorWithAlpha => argb(
+            to_u8(samples[0]),
+            to_u8(samples[1]),
+            to_u8(samples[2]),
+            to_u8(samples[3]),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a PNG file of an IHDR chunk, the chunks in `chunks` and an IEND chunk. CRCs are
+    /// left as zero since they aren't verified.
+    fn png(
+        width: u32,
+        height: u32,
+        depth: u8,
+        color: u8,
+        interlace: u8,
+        chunks: &[(&[u8; 4], Vec<u8>)],
+    ) -> Vec<u8> {
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&width.to_be_bytes());
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[depth, color, 0, 0, interlace]);
+        let mut bytes = SIGNATURE.to_vec();
+        let mut push = |chunk_type: &[u8; 4], data: &[u8]| {
+            bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(chunk_type);
+            bytes.extend_from_slice(data);
+            bytes.extend_from_slice(&[0, 0, 0, 0]);
+        };
+        push(b"IHDR", &ihdr);
+        for (chunk_type, data) in chunks {
+            push(chunk_type, data);
+        }
+        push(b"IEND", &[]);
+        bytes
+    }
+
+    /// Compresses `data` with zlib in a single stored block.
+    fn zlib_stored(data: &[u8]) -> Vec<u8> {
+        let len = data.len() as u16;
+        let mut bytes = vec![0x78, 0x01, 0x01];
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(&(!len).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn test_truecolor_with_filters() {
+        // 2x3 RGB pixels, one scanline per filter type: None, Sub, Up.
+        let data = [
+            0, 255, 0, 0, 0, 255, 0, //
+            1, 0, 0, 255, 255, 0, 0, //
+            2, 0, 0, 0, 0, 0, 2,
+        ];
+        let image = decode(&png(2, 3, 8, 2, 0, &[(b"IDAT", zlib_stored(&data))]))
+            .expect("failed to decode PNG");
+        assert_eq!((2, 3), (image.width(), image.height()));
+        assert_eq!(
+            &[0xffff0000, 0xff00ff00, 0xff0000ff, 0xffff00ff, 0xff0000ff, 0xffff0001],
+            image.pixels()
+        );
+    }
+
+    #[test]
+    fn test_average_and_paeth_filters() {
+        // A 2x2 greyscale image with alpha filtered with Average and Paeth.
+        let data = [3, 100, 200, 10, 20, 4, 50, 50, 5, 5];
+        let image = decode(&png(2, 2, 8, 4, 0, &[(b"IDAT", zlib_stored(&data))]))
+            .expect("failed to decode PNG");
+        // Average: (100, 200), then (10 + 100/2, 20 + 200/2).
+        // Paeth: the predictor is b, the byte above, for the first pixel and c, the byte above
+        // and to the left, for the second.
+        assert_eq!(
+            &[0xc8646464, 0x783c3c3c, 0xfa969696, 0xcd696969],
+            image.pixels()
+        );
+    }
+
+    #[test]
+    fn test_indexed_color_with_transparency() {
+        // 4 pixels of 2 bits: indices 0, 1, 2, 1.
+        let data = [0, 0b0001_1001];
+        let image = decode(&png(
+            4,
+            1,
+            2,
+            3,
+            0,
+            &[
+                (b"PLTE", vec![255, 0, 0, 0, 255, 0, 0, 0, 255]),
+                (b"tRNS", vec![0, 128]),
+                (b"IDAT", zlib_stored(&data)),
+            ],
+        ))
+        .expect("failed to decode PNG");
+        assert_eq!(
+            &[0x00ff0000, 0x8000ff00, 0xff0000ff, 0x8000ff00],
+            image.pixels()
+        );
+    }
+
+    #[test]
+    fn test_low_bit_depth_greyscale() {
+        // 1-bit greyscale samples are scaled to 0 and 255.
+        let data = [0, 0b1010_0000];
+        let image = decode(&png(3, 1, 1, 0, 0, &[(b"IDAT", zlib_stored(&data))]))
+            .expect("failed to decode PNG");
+        assert_eq!(&[0xffffffff, 0xff000000, 0xffffffff], image.pixels());
+    }
+
+    #[test]
+    fn test_adam7_interlace() {
+        // A 2x2 greyscale image has pixels in passes 1, 6 and 7.
+        let data = [0, 10, 0, 20, 0, 30, 40];
+        let image = decode(&png(2, 2, 8, 0, 1, &[(b"IDAT", zlib_stored(&data))]))
+            .expect("failed to decode PNG");
+        assert_eq!(
+            &[0xff0a0a0a, 0xff141414, 0xff1e1e1e, 0xff282828],
+            image.pixels()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(decode(&png(1, 1, 3, 2, 0, &[])).is_err());
+        assert!(decode(&png(1, 1, 8, 3, 0, &[(b"IDAT", zlib_stored(&[0, 0]))])).is_err());
+        assert!(decode(&png(2, 2, 8, 0, 0, &[(b"IDAT", zlib_stored(&[0, 1, 2]))])).is_err());
+        assert!(decode(&SIGNATURE).is_err());
+    }
+
+    #[test]
+    fn test_too_large() {
+        let idat = [(b"IDAT", zlib_stored(&[]))];
+        assert!(decode(&png(u32::MAX, u32::MAX, 8, 2, 0, &idat)).is_err());
+        assert!(decode(&png(60000, 60000, 8, 2, 1, &idat)).is_err());
+    }
+}
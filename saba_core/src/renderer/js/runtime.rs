@@ -1,4 +1,5 @@
 use crate::renderer::dom::api::get_element_by_id;
+use crate::renderer::dom::api::set_text_content;
 use crate::renderer::dom::node::Node as DomNode;
 use crate::renderer::dom::node::NodeKind as DomNodeKind;
 use crate::renderer::js::ast::Node;
@@ -240,11 +241,7 @@ impl JsRuntime {
                     if let Some(p) = property {
                         // target.textContent = "foobar"; のようにノードのテキストを変更する
                         if p == "textContent" {
-                            object
-                                .borrow_mut()
-                                .set_first_child(Some(Rc::new(RefCell::new(DomNode::new(
-                                    DomNodeKind::Text(right_value.to_string()),
-                                )))));
+                            set_text_content(&object, right_value.to_string());
                         }
                     }
                 }
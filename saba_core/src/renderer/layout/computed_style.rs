@@ -1,38 +1,66 @@
 use crate::error::Error;
-use crate::renderer::dom::node::ElementKind;
-use crate::renderer::dom::node::Node;
-use crate::renderer::dom::node::NodeKind;
+use crate::renderer::css::cssom::CascadeOrigin;
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::cssom::Declaration;
+use crate::renderer::css::media::Device;
+use crate::renderer::css::property::property_definition;
+use crate::renderer::css::property::CssWideKeyword;
+use crate::renderer::css::property::PROPERTIES;
+use crate::renderer::css::shorthand::expand_declaration;
+use crate::renderer::css::value::round;
+use crate::renderer::css::value::split_component_values;
+use crate::renderer::css::value::ComputeContext;
+use crate::renderer::css::value::Length;
+use crate::renderer::css::value::LengthPercentage;
+use crate::renderer::css::value::LengthPercentageOrAuto;
+use crate::renderer::css::variable::compute_custom_properties;
+use crate::renderer::css::variable::contains_var;
+use crate::renderer::css::variable::is_custom_property_name;
+use crate::renderer::css::variable::substitute_var;
+use crate::renderer::css::variable::CustomProperties;
+use crate::renderer::layout::counter::CounterStyle;
+use alloc::collections::BTreeMap;
 use alloc::format;
-use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::string::ToString;
-use core::cell::RefCell;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum DisplayType {
     Block,
     Inline,
+    /// https://www.w3.org/TR/css-display-3/#valdef-display-flow-root
+    /// A block box that establishes a new block formatting context.
+    FlowRoot,
+    /// https://www.w3.org/TR/css-flexbox-1/#flex-containers
+    /// A block-level flex container.
+    Flex,
+    /// An inline-level flex container, which is laid out as an atomic inline.
+    InlineFlex,
+    /// https://www.w3.org/TR/css-grid-1/#grid-containers
+    /// A block-level grid container.
+    Grid,
+    /// An inline-level grid container, which is laid out as an atomic inline.
+    InlineGrid,
+    /// https://www.w3.org/TR/css-display-3/#list-items
+    /// A block box that also generates a ::marker pseudo-element.
+    ListItem,
     DisplayNone,
 }
 
 impl DisplayType {
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
-        match &node.borrow().kind() {
-            NodeKind::Document => DisplayType::Block,
-            NodeKind::Element(e) => {
-                if e.is_block_element() {
-                    DisplayType::Block
-                } else {
-                    DisplayType::Inline
-                }
-            }
-            NodeKind::Text(_) => DisplayType::Inline,
-        }
-    }
     pub fn from_str_display(s: &str) -> Result<Self, Error> {
         match s {
             "block" => Ok(Self::Block),
             "inline" => Ok(Self::Inline),
+            "flow-root" => Ok(Self::FlowRoot),
+            "flex" => Ok(Self::Flex),
+            "inline-flex" => Ok(Self::InlineFlex),
+            "grid" => Ok(Self::Grid),
+            "inline-grid" => Ok(Self::InlineGrid),
+            "list-item" => Ok(Self::ListItem),
             "none" => Ok(Self::DisplayNone),
             _ => Err(Error::UnexpectedInput(format!(
                 "display type {:?} is not supported yet",
@@ -40,6 +68,32 @@ impl DisplayType {
             ))),
         }
     }
+
+    pub fn is_flex_container(&self) -> bool {
+        matches!(self, Self::Flex | Self::InlineFlex)
+    }
+
+    pub fn is_grid_container(&self) -> bool {
+        matches!(self, Self::Grid | Self::InlineGrid)
+    }
+
+    /// Whether the box is an inline-level box that is laid out as a unit on a line.
+    pub fn is_atomic_inline(&self) -> bool {
+        matches!(self, Self::InlineFlex | Self::InlineGrid)
+    }
+
+    /// https://www.w3.org/TR/css-display-3/#blockify
+    /// "If a layout-internal or inline-level box is blockified, its inner display type converts
+    /// to flow so that it becomes a block container." An inline flex or grid container becomes a
+    /// block-level one.
+    pub fn blockify(&self) -> Self {
+        match self {
+            Self::Inline => Self::Block,
+            Self::InlineFlex => Self::Flex,
+            Self::InlineGrid => Self::Grid,
+            display => *display,
+        }
+    }
 }
 
 // 2. TextDecorationの定義を追加
@@ -48,272 +102,5165 @@ pub enum TextDecoration {
     None,
     Underline,
 }
+
 impl TextDecoration {
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
-        match &node.borrow().kind() {
-            NodeKind::Element(element) => match element.kind() {
-                ElementKind::A => TextDecoration::Underline,
-                _ => TextDecoration::None,
-            },
-            _ => TextDecoration::None,
+    pub fn from_str_text_decoration(s: &str) -> Result<Self, Error> {
+        match s {
+            "none" => Ok(Self::None),
+            "underline" => Ok(Self::Underline),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text decoration {:?} is not supported yet",
+                s
+            ))),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub enum FontSize {
-    Medium,
-    XLarge,
-    XXLarge,
+/// https://www.w3.org/TR/css-text-3/#text-align-property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextAlign {
+    Start,
+    End,
+    Left,
+    Right,
+    Center,
+    Justify,
 }
-impl FontSize {
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
-        match &node.borrow().kind() {
-            NodeKind::Element(element) => match element.kind() {
-                ElementKind::H1 => FontSize::XXLarge,
-                ElementKind::H2 => FontSize::XLarge,
-                _ => FontSize::Medium,
-            },
-            _ => FontSize::Medium,
+
+impl TextAlign {
+    pub fn from_str_text_align(s: &str) -> Result<Self, Error> {
+        match s {
+            "start" => Ok(Self::Start),
+            "end" => Ok(Self::End),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "center" => Ok(Self::Center),
+            "justify" => Ok(Self::Justify),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text align {:?} is not supported yet",
+                s
+            ))),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct ComputedStyle {
-    background_color: Option<Color>,
-    color: Option<Color>,
-    display: Option<DisplayType>,
-    font_size: Option<FontSize>,
-    text_decoration: Option<TextDecoration>,
-    height: Option<i64>,
-    width: Option<i64>,
+/// https://www.w3.org/TR/css-text-3/#white-space-property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WhiteSpace {
+    Normal,
+    Pre,
+    Nowrap,
+    PreWrap,
+    PreLine,
 }
 
-impl Default for ComputedStyle {
-    fn default() -> Self {
-        Self::new()
+impl WhiteSpace {
+    pub fn from_str_white_space(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "pre" => Ok(Self::Pre),
+            "nowrap" => Ok(Self::Nowrap),
+            "pre-wrap" => Ok(Self::PreWrap),
+            "pre-line" => Ok(Self::PreLine),
+            _ => Err(Error::UnexpectedInput(format!(
+                "white space {:?} is not supported yet",
+                s
+            ))),
+        }
     }
-}
 
-impl ComputedStyle {
-    pub fn new() -> Self {
-        Self {
-            background_color: None,
-            color: None,
-            display: None,
-            font_size: None,
-            text_decoration: None,
-            height: None,
-            width: None,
-        }
+    /// Returns true if sequences of spaces and tabs collapse into a single space.
+    pub fn collapses_spaces(&self) -> bool {
+        matches!(self, Self::Normal | Self::Nowrap | Self::PreLine)
     }
 
-    pub fn set_background_color(&mut self, color: Color) {
-        self.background_color = Some(color);
+    /// Returns true if segment breaks (newlines) are preserved as forced line breaks.
+    pub fn preserves_newlines(&self) -> bool {
+        matches!(self, Self::Pre | Self::PreWrap | Self::PreLine)
     }
 
-    pub fn background_color(&self) -> Color {
-        self.background_color
-            .clone()
-            .expect("failed to access Css property: background_color")
+    /// Returns true if lines may wrap at soft wrap opportunities.
+    pub fn wraps(&self) -> bool {
+        matches!(self, Self::Normal | Self::PreWrap | Self::PreLine)
     }
+}
 
-    pub fn set_color(&mut self, color: Color) {
-        self.color = Some(color);
+/// https://www.w3.org/TR/css-text-3/#word-break-property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WordBreak {
+    Normal,
+    BreakAll,
+    KeepAll,
+    /// "For compatibility with legacy content, the word-break property also supports a
+    /// deprecated break-word keyword. When specified, this has the same effect as word-break:
+    /// normal and overflow-wrap: anywhere, regardless of the actual value of the overflow-wrap
+    /// property."
+    BreakWord,
+}
+
+impl WordBreak {
+    pub fn from_str_word_break(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "break-all" => Ok(Self::BreakAll),
+            "keep-all" => Ok(Self::KeepAll),
+            "break-word" => Ok(Self::BreakWord),
+            _ => Err(Error::UnexpectedInput(format!(
+                "word break {:?} is not supported yet",
+                s
+            ))),
+        }
     }
+}
 
-    pub fn color(&self) -> Color {
-        self.color
-            .clone()
-            .expect("failed to access Css property: color")
+/// https://www.w3.org/TR/css-text-3/#overflow-wrap-property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OverflowWrap {
+    Normal,
+    Anywhere,
+    BreakWord,
+}
+
+impl OverflowWrap {
+    pub fn from_str_overflow_wrap(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "anywhere" => Ok(Self::Anywhere),
+            "break-word" => Ok(Self::BreakWord),
+            _ => Err(Error::UnexpectedInput(format!(
+                "overflow wrap {:?} is not supported yet",
+                s
+            ))),
+        }
     }
+}
 
-    pub fn set_display(&mut self, display: DisplayType) {
-        self.display = Some(display);
+/// https://www.w3.org/TR/css-text-3/#line-break-property
+/// How strictly line breaking rules are applied, mostly to CJK punctuation and small kana.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineBreak {
+    Auto,
+    Loose,
+    Normal,
+    Strict,
+    Anywhere,
+}
+
+impl LineBreak {
+    pub fn from_str_line_break(s: &str) -> Result<Self, Error> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "loose" => Ok(Self::Loose),
+            "normal" => Ok(Self::Normal),
+            "strict" => Ok(Self::Strict),
+            "anywhere" => Ok(Self::Anywhere),
+            _ => Err(Error::UnexpectedInput(format!(
+                "line break {:?} is not supported yet",
+                s
+            ))),
+        }
     }
+}
 
-    pub fn defaulting(&mut self, node: &Rc<RefCell<Node>>, parent_style: Option<ComputedStyle>) {
-        if let Some(parent_style) = parent_style {
-            if self.background_color.is_none() && parent_style.background_color() != Color::white()
-            {
-                self.background_color = Some(parent_style.background_color());
-            }
-            if self.color.is_none() && parent_style.color() != Color::black() {
-                self.color = Some(parent_style.color());
-            }
-            if self.font_size.is_none() && parent_style.font_size() != FontSize::Medium {
-                self.font_size = Some(parent_style.font_size());
-            }
-            if self.text_decoration.is_none()
-                && parent_style.text_decoration() != TextDecoration::None
-            {
-                self.text_decoration = Some(parent_style.text_decoration());
-            }
+/// https://www.w3.org/TR/css-text-3/#text-transform-property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextTransform {
+    None,
+    Capitalize,
+    Uppercase,
+    Lowercase,
+}
+
+impl TextTransform {
+    pub fn from_str_text_transform(s: &str) -> Result<Self, Error> {
+        match s {
+            "none" => Ok(Self::None),
+            "capitalize" => Ok(Self::Capitalize),
+            "uppercase" => Ok(Self::Uppercase),
+            "lowercase" => Ok(Self::Lowercase),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text transform {:?} is not supported yet",
+                s
+            ))),
         }
-        if self.background_color.is_none() {
-            self.set_background_color(Color::white());
+    }
+
+    /// Transforms the case of `text`. "capitalize" puts the first letter of each word in
+    /// titlecase, where a word is a sequence of characters that follows whitespace.
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            Self::None => text.to_string(),
+            Self::Uppercase => text.to_uppercase(),
+            Self::Lowercase => text.to_lowercase(),
+            Self::Capitalize => {
+                let mut result = String::with_capacity(text.len());
+                let mut at_word_start = true;
+                for c in text.chars() {
+                    if at_word_start && c.is_alphanumeric() {
+                        result.extend(c.to_uppercase());
+                        at_word_start = false;
+                    } else {
+                        result.push(c);
+                        if c.is_whitespace() {
+                            at_word_start = true;
+                        }
+                    }
+                }
+                result
+            }
         }
-        if self.color.is_none() {
-            self.set_color(Color::black());
+    }
+}
+
+/// https://www.w3.org/TR/css-inline-3/#propdef-vertical-align
+/// https://www.w3.org/TR/CSS2/visudet.html#propdef-vertical-align
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerticalAlign {
+    Baseline,
+    Sub,
+    Super,
+    TextTop,
+    TextBottom,
+    Middle,
+    Top,
+    Bottom,
+    /// Raises the box by the length. Percentages refer to the line-height of the element itself.
+    LengthPercentage(LengthPercentage),
+}
+
+impl VerticalAlign {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        if let [ComponentValue::Ident(ident)] = values {
+            return match ident.as_str() {
+                "baseline" => Ok(Self::Baseline),
+                "sub" => Ok(Self::Sub),
+                "super" => Ok(Self::Super),
+                "text-top" => Ok(Self::TextTop),
+                "text-bottom" => Ok(Self::TextBottom),
+                "middle" => Ok(Self::Middle),
+                "top" => Ok(Self::Top),
+                "bottom" => Ok(Self::Bottom),
+                _ => Err(Error::UnexpectedInput(format!(
+                    "vertical align {:?} is not supported yet",
+                    ident
+                ))),
+            };
         }
-        if self.display.is_none() {
-            self.set_display_type_default(node);
+        Ok(Self::LengthPercentage(
+            LengthPercentage::from_component_values(values)?,
+        ))
+    }
+}
+
+/// https://www.w3.org/TR/css-text-3/#letter-spacing-property
+/// https://www.w3.org/TR/css-text-3/#word-spacing-property
+/// "normal" is the same as 0, and the spacing is a length that can be negative.
+fn spacing_from_component_values(values: &[ComponentValue]) -> Result<Length, Error> {
+    match values {
+        [ComponentValue::Ident(ident)] if ident == "normal" => Ok(Length::px(0.0)),
+        _ => single_token(values, Length::from_component_value),
+    }
+}
+
+/// https://www.w3.org/TR/css-fonts-4/#font-size-prop
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontSize {
+    /// https://www.w3.org/TR/css-fonts-4/#valdef-font-size-absolute-size
+    XXSmall,
+    XSmall,
+    Small,
+    Medium,
+    Large,
+    XLarge,
+    XXLarge,
+    XXXLarge,
+    /// https://www.w3.org/TR/css-fonts-4/#valdef-font-size-relative-size
+    Larger,
+    Smaller,
+    /// A length, or a percentage of the font size of the parent element. The computed value is
+    /// always this variant with an absolute length.
+    LengthPercentage(LengthPercentage),
+}
+
+/// The initial font size. It's also the size that rem is resolved against because the root
+/// element is not part of the layout tree.
+pub static MEDIUM_FONT_SIZE: f64 = 16.0;
+
+/// https://www.w3.org/TR/css-fonts-4/#relative-size-value
+/// The ratio between adjacent absolute sizes, which larger and smaller scale the parent size by.
+static FONT_SIZE_SCALING_FACTOR: f64 = 1.2;
+
+impl FontSize {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        if let [ComponentValue::Ident(ident)] = values {
+            return match ident.as_str() {
+                "xx-small" => Ok(Self::XXSmall),
+                "x-small" => Ok(Self::XSmall),
+                "small" => Ok(Self::Small),
+                "medium" => Ok(Self::Medium),
+                "large" => Ok(Self::Large),
+                "x-large" => Ok(Self::XLarge),
+                "xx-large" => Ok(Self::XXLarge),
+                "xxx-large" => Ok(Self::XXXLarge),
+                "larger" => Ok(Self::Larger),
+                "smaller" => Ok(Self::Smaller),
+                _ => Err(Error::UnexpectedInput(format!(
+                    "font size {:?} is not supported yet",
+                    ident
+                ))),
+            };
         }
-        if self.font_size.is_none() {
-            self.set_font_size_default(node);
+
+        // "Negative values are not allowed."
+        let size = LengthPercentage::from_component_values(values)?;
+        if size.is_negative() {
+            return Err(Error::UnexpectedInput(format!(
+                "font size {:?} should not be negative",
+                values
+            )));
         }
-        if self.text_decoration.is_none() {
-            self.set_text_decoration_default(node);
+        Ok(Self::LengthPercentage(size))
+    }
+
+    /// Returns the computed font size in px. Font-relative lengths and percentages refer to the
+    /// font size of the parent element.
+    fn compute(&self, parent_font_size: f64, device: &Device) -> f64 {
+        // https://www.w3.org/TR/css-fonts-4/#absolute-size-mapping
+        let absolute = |scale: f64| MEDIUM_FONT_SIZE * scale;
+        match self {
+            Self::XXSmall => absolute(3.0 / 5.0),
+            Self::XSmall => absolute(3.0 / 4.0),
+            Self::Small => absolute(8.0 / 9.0),
+            Self::Medium => absolute(1.0),
+            Self::Large => absolute(6.0 / 5.0),
+            Self::XLarge => absolute(3.0 / 2.0),
+            Self::XXLarge => absolute(2.0),
+            Self::XXXLarge => absolute(3.0),
+            Self::Larger => parent_font_size * FONT_SIZE_SCALING_FACTOR,
+            Self::Smaller => parent_font_size / FONT_SIZE_SCALING_FACTOR,
+            Self::LengthPercentage(size) => {
+                let context = ComputeContext::new(parent_font_size, MEDIUM_FONT_SIZE, device);
+                size.compute_with_basis(&context, parent_font_size).max(0.0)
+            }
         }
-        if self.height.is_none() {
-            self.set_height(0);
+    }
+}
+
+/// https://www.w3.org/TR/css-fonts-4/#font-weight-prop
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontWeight {
+    /// A number between 1 and 1000. normal is 400 and bold is 700.
+    Absolute(f64),
+    Bolder,
+    Lighter,
+}
+
+impl FontWeight {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Number(n)] if (1.0..=1000.0).contains(n) => Ok(Self::Absolute(*n)),
+            [ComponentValue::Ident(ident)] => match ident.as_str() {
+                "normal" => Ok(Self::Absolute(400.0)),
+                "bold" => Ok(Self::Absolute(700.0)),
+                "bolder" => Ok(Self::Bolder),
+                "lighter" => Ok(Self::Lighter),
+                _ => Err(Error::UnexpectedInput(format!(
+                    "font weight {:?} is not supported",
+                    ident
+                ))),
+            },
+            _ => Err(Error::UnexpectedInput(format!(
+                "font weight {:?} is not supported",
+                values
+            ))),
         }
-        if self.width.is_none() {
-            self.set_width(0);
+    }
+
+    /// https://www.w3.org/TR/css-fonts-4/#relative-weights
+    /// Returns the computed weight. bolder and lighter are relative to the weight of the parent.
+    fn compute(&self, parent_weight: f64) -> f64 {
+        match self {
+            Self::Absolute(weight) => *weight,
+            Self::Bolder => match parent_weight {
+                w if w < 350.0 => 400.0,
+                w if w < 550.0 => 700.0,
+                w if w < 900.0 => 900.0,
+                w => w,
+            },
+            Self::Lighter => match parent_weight {
+                w if w < 100.0 => w,
+                w if w < 550.0 => 100.0,
+                w if w < 750.0 => 400.0,
+                _ => 700.0,
+            },
         }
     }
+}
 
-    pub fn display(&self) -> DisplayType {
-        self.display
-            .expect("failed to access Css property: display")
+/// https://www.w3.org/TR/css-fonts-4/#font-style-prop
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl FontStyle {
+    pub fn from_str_font_style(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "italic" => Ok(Self::Italic),
+            "oblique" => Ok(Self::Oblique),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font style {:?} is not supported",
+                s
+            ))),
+        }
     }
+}
 
-    pub fn set_display_type_default(&mut self, node: &Rc<RefCell<Node>>) {
-        let display_type = DisplayType::default(node);
-        self.display = Some(display_type);
+/// https://www.w3.org/TR/css-fonts-4/#generic-font-families
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GenericFontFamily {
+    Serif,
+    SansSerif,
+    Monospace,
+    Cursive,
+    Fantasy,
+    SystemUi,
+}
+
+impl GenericFontFamily {
+    fn from_str_generic_family(s: &str) -> Option<Self> {
+        match s {
+            "serif" => Some(Self::Serif),
+            "sans-serif" => Some(Self::SansSerif),
+            "monospace" => Some(Self::Monospace),
+            "cursive" => Some(Self::Cursive),
+            "fantasy" => Some(Self::Fantasy),
+            "system-ui" => Some(Self::SystemUi),
+            _ => None,
+        }
     }
+}
+
+/// https://www.w3.org/TR/css-fonts-4/#font-family-prop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontFamily {
+    Named(String),
+    Generic(GenericFontFamily),
+}
 
-    pub fn font_size(&self) -> FontSize {
-        self.font_size
-            .expect("failed to access CSS property: font_size")
+impl FontFamily {
+    /// Parses `[ <family-name> | <generic-family> ]#`.
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Vec<Self>, Error> {
+        let mut families = Vec::new();
+        for family in values.split(|v| *v == ComponentValue::Delim(',')) {
+            match family {
+                [ComponentValue::StringToken(name)] => families.push(Self::Named(name.clone())),
+                // "Font family names that happen to be the same as a keyword value must be
+                // quoted", so an unquoted generic name is the generic family.
+                [ComponentValue::Ident(ident)] => {
+                    match GenericFontFamily::from_str_generic_family(ident) {
+                        Some(generic) => families.push(Self::Generic(generic)),
+                        None => families.push(Self::Named(ident.clone())),
+                    }
+                }
+                // "a sequence of identifiers ... is converted to a string by joining all the
+                // identifiers in the sequence by single spaces."
+                idents if !idents.is_empty() => {
+                    let mut names = Vec::new();
+                    for ident in idents {
+                        match ident {
+                            ComponentValue::Ident(name) => names.push(name.clone()),
+                            _ => {
+                                return Err(Error::UnexpectedInput(format!(
+                                    "invalid font family {:?}",
+                                    values
+                                )))
+                            }
+                        }
+                    }
+                    families.push(Self::Named(names.join(" ")));
+                }
+                _ => {
+                    return Err(Error::UnexpectedInput(format!(
+                        "invalid font family {:?}",
+                        values
+                    )))
+                }
+            }
+        }
+        Ok(families)
     }
-    pub fn set_font_size_default(&mut self, node: &Rc<RefCell<Node>>) {
-        let fs = FontSize::default(node);
-        self.font_size = Some(fs);
+}
+
+/// https://www.w3.org/TR/css-inline-3/#line-height-property
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineHeight {
+    Normal,
+    /// A multiple of the font size of the element. It's inherited as a number, not as the
+    /// resulting length.
+    Number(f64),
+    /// A length, or a percentage of the font size of the element. The computed value is an
+    /// absolute length.
+    LengthPercentage(LengthPercentage),
+}
+
+/// The used line height of normal relative to the font size. The glyphs of the built-in font are
+/// 16px high on a 20px line at the medium size.
+pub static NORMAL_LINE_HEIGHT: f64 = 1.25;
+
+impl LineHeight {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        let line_height = match values {
+            [ComponentValue::Ident(ident)] if ident == "normal" => return Ok(Self::Normal),
+            [ComponentValue::Number(n)] if *n != 0.0 => Self::Number(*n),
+            _ => Self::LengthPercentage(LengthPercentage::from_component_values(values)?),
+        };
+        // "Negative values are invalid."
+        let negative = match &line_height {
+            Self::Number(n) => *n < 0.0,
+            Self::LengthPercentage(l) => l.is_negative(),
+            Self::Normal => false,
+        };
+        if negative {
+            return Err(Error::UnexpectedInput(format!(
+                "line height {:?} should not be negative",
+                values
+            )));
+        }
+        Ok(line_height)
     }
-    pub fn text_decoration(&self) -> TextDecoration {
-        self.text_decoration
-            .expect("failed to access Css property: text_decoration")
+}
+
+/// The font that a run of text is drawn with. It's resolved from the font properties so that a
+/// backend can draw text without knowing about CSS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Font {
+    family: Vec<FontFamily>,
+    size: i64,
+    weight: u16,
+    style: FontStyle,
+}
+
+impl Font {
+    pub fn new(family: Vec<FontFamily>, size: i64, weight: u16, style: FontStyle) -> Self {
+        Self {
+            family,
+            size,
+            weight,
+            style,
+        }
     }
 
-    pub fn set_text_decoration_default(&mut self, node: &Rc<RefCell<Node>>) -> TextDecoration {
-        let text_decoration = TextDecoration::default(node);
-        self.text_decoration = Some(text_decoration);
-        text_decoration
+    /// The preferred font families, in order.
+    pub fn family(&self) -> &[FontFamily] {
+        &self.family
     }
 
-    pub fn set_height(&mut self, height: i64) {
-        self.height = Some(height);
+    /// The font size in px.
+    pub fn size(&self) -> i64 {
+        self.size
     }
 
-    pub fn height(&self) -> i64 {
-        self.height.expect("failed to access Css property: height")
+    pub fn weight(&self) -> u16 {
+        self.weight
     }
 
-    pub fn set_width(&mut self, width: i64) {
-        self.width = Some(width);
+    pub fn style(&self) -> FontStyle {
+        self.style
     }
 
-    pub fn width(&self) -> i64 {
-        self.width.expect("failed to access Css property: width")
+    /// https://www.w3.org/TR/css-fonts-4/#font-weight-numeric-values
+    /// Weights of 600 and above are bold faces.
+    pub fn is_bold(&self) -> bool {
+        self.weight >= 600
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Color {
-    name: Option<String>,
-    code: String,
+/// https://www.w3.org/TR/css-box-4/#margins
+/// The four sides of a box, in the order used by shorthand properties.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
 }
 
-impl Color {
-    pub fn from_name(name: &str) -> Result<Self, Error> {
-        let code = match name {
-            "black" => "#000000".to_string(),
-            "silver" => "#C0C0C0".to_string(),
-            "gray" => "#808080".to_string(),
-            "white" => "#FFFFFF".to_string(),
-            "maroon" => "#800000".to_string(),
-            "red" => "#FF0000".to_string(),
-            "purple" => "#800080".to_string(),
-            "fuchsia" => "#FF00FF".to_string(),
-            "green" => "#00FF00".to_string(),
-            "lime" => "#00FF00".to_string(),
-            "olive" => "#808000".to_string(),
-            "yellow" => "#FFFF00".to_string(),
-            "navy" => "#000080".to_string(),
-            "blue" => "#0000FF".to_string(),
-            "teal" => "#008080".to_string(),
-            "aqua" => "#00FFFF".to_string(),
-            "orange" => "#FFA500".to_string(),
-            "lightgray" => "#D3D3D3".to_string(),
-            _ => {
-                return Err(Error::UnexpectedInput(format!(
-                    "color name {:?} is not supported yet",
-                    name
-                )));
-            }
-        };
-        Ok(Self {
-            name: Some(name.to_string()),
-            code: code.to_string(),
-        })
+impl Side {
+    pub const ALL: [Side; 4] = [Side::Top, Side::Right, Side::Bottom, Side::Left];
+
+    fn index(&self) -> usize {
+        match self {
+            Side::Top => 0,
+            Side::Right => 1,
+            Side::Bottom => 2,
+            Side::Left => 3,
+        }
     }
 
-    pub fn from_code(code: &str) -> Result<Self, Error> {
-        if code.chars().nth(0) != Some('#') || code.len() != 7 {
-            return Err(Error::UnexpectedInput(format!(
-                "invalid color code {}",
-                code
-            )));
+    pub fn from_str_side(s: &str) -> Result<Self, Error> {
+        match s {
+            "top" => Ok(Self::Top),
+            "right" => Ok(Self::Right),
+            "bottom" => Ok(Self::Bottom),
+            "left" => Ok(Self::Left),
+            _ => Err(Error::UnexpectedInput(format!(
+                "side {:?} is not supported",
+                s
+            ))),
         }
-        let name = match code {
-            "#000000" => "black".to_string(),
-            "#C0C0C0" => "silver".to_string(),
-            "#808080" => "gray".to_string(),
-            "#FFFFFF" => "white".to_string(),
-            "#800000" => "maroon".to_string(),
-            "#FF0000" => "red".to_string(),
-            "#800080" => "purple".to_string(),
-            "#FF00FF" => "fuchsia".to_string(),
-            "#008000" => "green".to_string(),
-            "#00FF00" => "lime".to_string(),
-            "#808000" => "olive".to_string(),
-            "#FFFF00" => "yellow".to_string(),
-            "#000080" => "navy".to_string(),
-            "#0000FF" => "blue".to_string(),
-            "#008080" => "teal".to_string(),
-            "#00FFFF" => "aqua".to_string(),
-            "#FFA500" => "orange".to_string(),
-            "#D3D3D3" => "lightgray".to_string(),
-            _ => {
-                return Err(Error::UnexpectedInput(format!(
-                    "color code {:?} is not supported yet",
-                    code
-                )));
-            }
-        };
-        Ok(Self {
-            name: Some(name),
-            code: code.to_string(),
-        })
     }
+}
 
-    pub fn white() -> Self {
-        Self {
-            name: Some("white".to_string()),
-            code: "#FFFFFF".to_string(),
+/// https://www.w3.org/TR/css-backgrounds-3/#border-style
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BorderStyle {
+    None,
+    Hidden,
+    Solid,
+    Dashed,
+    Dotted,
+    Double,
+}
+
+impl BorderStyle {
+    pub fn from_str_border_style(s: &str) -> Result<Self, Error> {
+        match s {
+            "none" => Ok(Self::None),
+            "hidden" => Ok(Self::Hidden),
+            "solid" => Ok(Self::Solid),
+            "dashed" => Ok(Self::Dashed),
+            "dotted" => Ok(Self::Dotted),
+            "double" => Ok(Self::Double),
+            _ => Err(Error::UnexpectedInput(format!(
+                "border style {:?} is not supported yet",
+                s
+            ))),
         }
     }
 
-    pub fn black() -> Self {
-        Self {
-            name: Some("black".to_string()),
-            code: "#000000".to_string(),
+    pub fn is_visible(&self) -> bool {
+        !matches!(self, Self::None | Self::Hidden)
+    }
+}
+
+/// https://www.w3.org/TR/css-backgrounds-3/#corners
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomLeft,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomRight,
+        Corner::BottomLeft,
+    ];
+
+    fn index(&self) -> usize {
+        match self {
+            Corner::TopLeft => 0,
+            Corner::TopRight => 1,
+            Corner::BottomRight => 2,
+            Corner::BottomLeft => 3,
         }
     }
 
-    pub fn code_u32(&self) -> u32 {
-        u32::from_str_radix(self.code.trim_start_matches('#'), 16).unwrap()
+    pub fn from_str_corner(s: &str) -> Result<Self, Error> {
+        match s {
+            "top-left" => Ok(Self::TopLeft),
+            "top-right" => Ok(Self::TopRight),
+            "bottom-right" => Ok(Self::BottomRight),
+            "bottom-left" => Ok(Self::BottomLeft),
+            _ => Err(Error::UnexpectedInput(format!(
+                "corner {:?} is not supported",
+                s
+            ))),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-backgrounds-3/#border-radius
+/// `<length-percentage [0,∞]>{1,2}`. "The two length or percentage values of the
+/// 'border-*-radius' properties define the radii of a quarter ellipse that defines the shape of the
+/// corner of the outer border edge. The first value is the horizontal radius, the second the
+/// vertical radius. If the second value is omitted it is copied from the first."
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorderRadius {
+    horizontal: LengthPercentage,
+    vertical: LengthPercentage,
+}
+
+impl BorderRadius {
+    pub fn new(horizontal: LengthPercentage, vertical: LengthPercentage) -> Self {
+        Self {
+            horizontal,
+            vertical,
+        }
+    }
+
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        let radii = split_component_values(values)
+            .into_iter()
+            .map(|radius| {
+                let radius = LengthPercentage::from_component_values(radius)?;
+                if radius.is_negative() {
+                    return Err(Error::UnexpectedInput(format!(
+                        "border radius {:?} should not be negative",
+                        values
+                    )));
+                }
+                Ok(radius)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        match radii.as_slice() {
+            [radius] => Ok(Self::new(radius.clone(), radius.clone())),
+            [horizontal, vertical] => Ok(Self::new(horizontal.clone(), vertical.clone())),
+            _ => Err(Error::UnexpectedInput(format!(
+                "border radius {:?} should have one or two values",
+                values
+            ))),
+        }
+    }
+
+    fn compute(&self, context: &ComputeContext) -> Self {
+        Self::new(
+            self.horizontal.compute(context),
+            self.vertical.compute(context),
+        )
+    }
+
+    /// "Percentages: Refer to corresponding dimension of the border box."
+    /// Returns the used horizontal and vertical radii in px.
+    pub fn resolve(&self, width: i64, height: i64) -> (i64, i64) {
+        (
+            self.horizontal.resolve(width).max(0),
+            self.vertical.resolve(height).max(0),
+        )
+    }
+}
+
+/// https://www.w3.org/TR/css-backgrounds-3/#border-width
+/// The width of `medium` is not defined by the spec. We use the same value as major browsers.
+pub static BORDER_WIDTH_THIN: f64 = 1.0;
+pub static BORDER_WIDTH_MEDIUM: f64 = 3.0;
+pub static BORDER_WIDTH_THICK: f64 = 5.0;
+
+/// `<line-width> = <length [0,∞]> | thin | medium | thick`
+pub fn border_width_from_component_value(value: &ComponentValue) -> Result<Length, Error> {
+    match value {
+        ComponentValue::Ident(ident) => match ident.as_str() {
+            "thin" => Ok(Length::px(BORDER_WIDTH_THIN)),
+            "medium" => Ok(Length::px(BORDER_WIDTH_MEDIUM)),
+            "thick" => Ok(Length::px(BORDER_WIDTH_THICK)),
+            _ => Err(Error::UnexpectedInput(format!(
+                "border width {:?} is not supported",
+                ident
+            ))),
+        },
+        _ => match Length::from_component_value(value)? {
+            length if length.value() < 0.0 => Err(Error::UnexpectedInput(format!(
+                "border width {:?} should not be negative",
+                value
+            ))),
+            length => Ok(length),
+        },
+    }
+}
+
+/// https://www.w3.org/TR/css-sizing-3/#box-sizing
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BoxSizing {
+    ContentBox,
+    BorderBox,
+}
+
+impl BoxSizing {
+    pub fn from_str_box_sizing(s: &str) -> Result<Self, Error> {
+        match s {
+            "content-box" => Ok(Self::ContentBox),
+            "border-box" => Ok(Self::BorderBox),
+            _ => Err(Error::UnexpectedInput(format!(
+                "box sizing {:?} is not supported",
+                s
+            ))),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-overflow-3/#overflow-control
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Overflow {
+    Visible,
+    Hidden,
+    Clip,
+    Scroll,
+    Auto,
+}
+
+impl Overflow {
+    pub fn from_str_overflow(s: &str) -> Result<Self, Error> {
+        match s {
+            "visible" => Ok(Self::Visible),
+            "hidden" => Ok(Self::Hidden),
+            "clip" => Ok(Self::Clip),
+            "scroll" => Ok(Self::Scroll),
+            "auto" => Ok(Self::Auto),
+            _ => Err(Error::UnexpectedInput(format!(
+                "overflow {:?} is not supported",
+                s
+            ))),
+        }
+    }
+
+    /// https://www.w3.org/TR/css-overflow-3/#scroll-container
+    /// "hidden, scroll and auto" make the box a scroll container, "visible and clip" don't.
+    pub fn is_scrollable(&self) -> bool {
+        matches!(self, Overflow::Hidden | Overflow::Scroll | Overflow::Auto)
+    }
+
+    /// Whether the user can scroll the box. A hidden box can only be scrolled by scripts.
+    pub fn is_user_scrollable(&self) -> bool {
+        matches!(self, Overflow::Scroll | Overflow::Auto)
+    }
+}
+
+/// https://www.w3.org/TR/css-position-3/#position-property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Position {
+    Static,
+    Relative,
+    Absolute,
+    Fixed,
+    Sticky,
+}
+
+impl Position {
+    pub fn from_str_position(s: &str) -> Result<Self, Error> {
+        match s {
+            "static" => Ok(Self::Static),
+            "relative" => Ok(Self::Relative),
+            "absolute" => Ok(Self::Absolute),
+            "fixed" => Ok(Self::Fixed),
+            "sticky" => Ok(Self::Sticky),
+            _ => Err(Error::UnexpectedInput(format!(
+                "position {:?} is not supported",
+                s
+            ))),
+        }
+    }
+
+    /// https://www.w3.org/TR/css-position-3/#positioned-box
+    pub fn is_positioned(&self) -> bool {
+        *self != Position::Static
+    }
+
+    /// https://www.w3.org/TR/css-position-3/#absolute-positioning-scheme
+    /// Absolutely positioned boxes are taken out of flow and don't affect the layout of others.
+    pub fn is_out_of_flow(&self) -> bool {
+        matches!(self, Position::Absolute | Position::Fixed)
+    }
+}
+
+/// https://www.w3.org/TR/CSS2/visuren.html#z-index
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ZIndex {
+    Auto,
+    Integer(i32),
+}
+
+impl ZIndex {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(ident)] if ident.eq_ignore_ascii_case("auto") => Ok(Self::Auto),
+            [ComponentValue::Number(n)] if *n == (*n as i32) as f64 => Ok(Self::Integer(*n as i32)),
+            _ => Err(Error::UnexpectedInput(format!(
+                "z-index {:?} should be auto or an integer",
+                values
+            ))),
+        }
+    }
+
+    /// The stack level of a box. `auto` is at the same level as 0.
+    pub fn level(&self) -> i32 {
+        match self {
+            ZIndex::Auto => 0,
+            ZIndex::Integer(level) => *level,
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-color-4/#transparency
+/// `<alpha-value>`, which is a number or a percentage. "Values outside the range [0,1] are not
+/// invalid, but are clamped to that range when computed."
+fn opacity_from_component_values(values: &[ComponentValue]) -> Result<f64, Error> {
+    match values {
+        [ComponentValue::Number(n)] => Ok(n.clamp(0.0, 1.0)),
+        [ComponentValue::Percentage(p)] => Ok((p / 100.0).clamp(0.0, 1.0)),
+        _ => Err(Error::UnexpectedInput(format!(
+            "opacity {:?} should be a number or a percentage",
+            values
+        ))),
+    }
+}
+
+/// https://www.w3.org/TR/css-transforms-1/#two-d-transform-functions
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformFunction {
+    /// translate(), translateX() and translateY(). Percentages refer to the size of the border
+    /// box.
+    Translate(LengthPercentage, LengthPercentage),
+    /// scale(), scaleX() and scaleY().
+    Scale(f64, f64),
+    /// rotate(), in degrees clockwise.
+    Rotate(f64),
+    /// matrix(a, b, c, d, e, f).
+    Matrix([f64; 6]),
+}
+
+impl TransformFunction {
+    /// https://www.w3.org/TR/css-transforms-1/#transform-property
+    /// `none | <transform-list>`, where `<transform-list> = <transform-function>+`. `none` is an
+    /// empty list.
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Vec<Self>, Error> {
+        match values {
+            [ComponentValue::Ident(ident)] if ident.eq_ignore_ascii_case("none") => Ok(Vec::new()),
+            [] => Err(Error::UnexpectedInput(
+                "transform should not be empty".to_string(),
+            )),
+            _ => split_component_values(values)
+                .into_iter()
+                .map(Self::from_function)
+                .collect(),
+        }
+    }
+
+    fn from_function(values: &[ComponentValue]) -> Result<Self, Error> {
+        let invalid = || {
+            Error::UnexpectedInput(format!(
+                "transform function {:?} is not supported yet",
+                values
+            ))
+        };
+        let (name, args) = match values {
+            [ComponentValue::Function(name), args @ .., ComponentValue::CloseParenthesis] => {
+                (name.to_ascii_lowercase(), args)
+            }
+            _ => return Err(invalid()),
+        };
+        let parts = split_component_values(args);
+        let args: Vec<Vec<ComponentValue>> = parts
+            .split(|v| matches!(v, [ComponentValue::Delim(',')]))
+            .map(|arg| arg.concat())
+            .collect();
+        let length = |arg: &[ComponentValue]| LengthPercentage::from_component_values(arg);
+        let number = |arg: &[ComponentValue]| match arg {
+            [ComponentValue::Number(n)] => Ok(*n),
+            _ => Err(invalid()),
+        };
+        match (name.as_str(), args.as_slice()) {
+            ("translate", [x]) => Ok(Self::Translate(length(x)?, LengthPercentage::zero())),
+            ("translate", [x, y]) => Ok(Self::Translate(length(x)?, length(y)?)),
+            ("translatex", [x]) => Ok(Self::Translate(length(x)?, LengthPercentage::zero())),
+            ("translatey", [y]) => Ok(Self::Translate(LengthPercentage::zero(), length(y)?)),
+            // "If the second parameter is not provided, it takes a value equal to the first."
+            ("scale", [s]) => Ok(Self::Scale(number(s)?, number(s)?)),
+            ("scale", [sx, sy]) => Ok(Self::Scale(number(sx)?, number(sy)?)),
+            ("scalex", [sx]) => Ok(Self::Scale(number(sx)?, 1.0)),
+            ("scaley", [sy]) => Ok(Self::Scale(1.0, number(sy)?)),
+            ("rotate", [angle]) => Ok(Self::Rotate(angle_from_component_values(angle)?)),
+            ("matrix", [a, b, c, d, e, f]) => Ok(Self::Matrix([
+                number(a)?,
+                number(b)?,
+                number(c)?,
+                number(d)?,
+                number(e)?,
+                number(f)?,
+            ])),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn compute(&self, context: &ComputeContext) -> Self {
+        match self {
+            Self::Translate(x, y) => Self::Translate(x.compute(context), y.compute(context)),
+            function => function.clone(),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-values-4/#angles
+/// Returns the angle in degrees. A unitless zero is accepted as in transform functions.
+fn angle_from_component_values(values: &[ComponentValue]) -> Result<f64, Error> {
+    match values {
+        [ComponentValue::Number(n)] if *n == 0.0 => Ok(0.0),
+        [ComponentValue::Dimension(value, unit)] => match unit.to_ascii_lowercase().as_str() {
+            "deg" => Ok(*value),
+            "grad" => Ok(value * 0.9),
+            "rad" => Ok(value * 180.0 / core::f64::consts::PI),
+            "turn" => Ok(value * 360.0),
+            _ => Err(Error::UnexpectedInput(format!(
+                "angle unit {:?} is not supported",
+                unit
+            ))),
+        },
+        _ => Err(Error::UnexpectedInput(format!(
+            "{:?} should be an angle",
+            values
+        ))),
+    }
+}
+
+/// https://www.w3.org/TR/CSS2/visuren.html#float-position
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Float {
+    None,
+    Left,
+    Right,
+}
+
+impl Float {
+    pub fn from_str_float(s: &str) -> Result<Self, Error> {
+        match s {
+            "none" => Ok(Self::None),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            _ => Err(Error::UnexpectedInput(format!(
+                "float {:?} is not supported",
+                s
+            ))),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/CSS2/visuren.html#flow-control
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Clear {
+    None,
+    Left,
+    Right,
+    Both,
+}
+
+impl Clear {
+    pub fn from_str_clear(s: &str) -> Result<Self, Error> {
+        match s {
+            "none" => Ok(Self::None),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "both" => Ok(Self::Both),
+            _ => Err(Error::UnexpectedInput(format!(
+                "clear {:?} is not supported",
+                s
+            ))),
+        }
+    }
+
+    /// Whether a box has to be placed below the floats on `side`.
+    pub fn clears(&self, side: Float) -> bool {
+        matches!(
+            (self, side),
+            (Clear::Both, _) | (Clear::Left, Float::Left) | (Clear::Right, Float::Right)
+        )
+    }
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#flex-direction-property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlexDirection {
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse,
+}
+
+impl FlexDirection {
+    pub fn from_str_flex_direction(s: &str) -> Result<Self, Error> {
+        match s {
+            "row" => Ok(Self::Row),
+            "row-reverse" => Ok(Self::RowReverse),
+            "column" => Ok(Self::Column),
+            "column-reverse" => Ok(Self::ColumnReverse),
+            _ => Err(Error::UnexpectedInput(format!(
+                "flex direction {:?} is not supported",
+                s
+            ))),
+        }
+    }
+
+    /// Whether the main axis is horizontal.
+    pub fn is_row(&self) -> bool {
+        matches!(self, Self::Row | Self::RowReverse)
+    }
+
+    /// Whether the main-start and main-end directions are swapped.
+    pub fn is_reverse(&self) -> bool {
+        matches!(self, Self::RowReverse | Self::ColumnReverse)
+    }
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#flex-wrap-property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlexWrap {
+    Nowrap,
+    Wrap,
+    WrapReverse,
+}
+
+impl FlexWrap {
+    pub fn from_str_flex_wrap(s: &str) -> Result<Self, Error> {
+        match s {
+            "nowrap" => Ok(Self::Nowrap),
+            "wrap" => Ok(Self::Wrap),
+            "wrap-reverse" => Ok(Self::WrapReverse),
+            _ => Err(Error::UnexpectedInput(format!(
+                "flex wrap {:?} is not supported",
+                s
+            ))),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-align-3/#propdef-justify-content
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JustifyContent {
+    Normal,
+    FlexStart,
+    FlexEnd,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+    Start,
+    End,
+}
+
+impl JustifyContent {
+    pub fn from_str_justify_content(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "flex-start" => Ok(Self::FlexStart),
+            "flex-end" => Ok(Self::FlexEnd),
+            "center" => Ok(Self::Center),
+            "space-between" => Ok(Self::SpaceBetween),
+            "space-around" => Ok(Self::SpaceAround),
+            "space-evenly" => Ok(Self::SpaceEvenly),
+            "start" | "left" => Ok(Self::Start),
+            "end" | "right" => Ok(Self::End),
+            _ => Err(Error::UnexpectedInput(format!(
+                "justify content {:?} is not supported",
+                s
+            ))),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-align-3/#propdef-align-items
+/// The values of align-items and align-self. Auto is only a value of align-self.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AlignItems {
+    Auto,
+    Normal,
+    Stretch,
+    FlexStart,
+    FlexEnd,
+    Center,
+    Start,
+    End,
+}
+
+impl AlignItems {
+    pub fn from_str_align_items(s: &str) -> Result<Self, Error> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "stretch" => Ok(Self::Stretch),
+            "flex-start" => Ok(Self::FlexStart),
+            "flex-end" => Ok(Self::FlexEnd),
+            "center" => Ok(Self::Center),
+            "start" | "self-start" => Ok(Self::Start),
+            "end" | "self-end" => Ok(Self::End),
+            _ => Err(Error::UnexpectedInput(format!(
+                "align items {:?} is not supported",
+                s
+            ))),
+        }
+    }
+
+    pub fn from_str_align_self(s: &str) -> Result<Self, Error> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            s => Self::from_str_align_items(s),
+        }
+    }
+
+    /// https://www.w3.org/TR/css-align-3/#valdef-align-self-auto
+    /// "Behaves as normal if the box has no parent, or when determining the actual position of
+    /// an absolutely positioned box. It behaves as the computed align-items value of the parent
+    /// box otherwise."
+    pub fn resolve(&self, align_items: AlignItems) -> AlignItems {
+        match self {
+            Self::Auto => align_items,
+            align => *align,
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#flex-basis-property
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlexBasis {
+    /// Uses the value of the main size property, width or height.
+    Auto,
+    /// Sizes the item based on its content.
+    Content,
+    LengthPercentage(LengthPercentage),
+}
+
+impl FlexBasis {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(ident)] if ident == "auto" => Ok(Self::Auto),
+            [ComponentValue::Ident(ident)] if ident == "content" => Ok(Self::Content),
+            _ => {
+                let basis = LengthPercentage::from_component_values(values)?;
+                if basis.is_negative() {
+                    return Err(Error::UnexpectedInput(format!(
+                        "flex basis {:?} should not be negative",
+                        values
+                    )));
+                }
+                Ok(Self::LengthPercentage(basis))
+            }
+        }
+    }
+
+    fn compute(&self, context: &ComputeContext) -> Self {
+        match self {
+            Self::LengthPercentage(l) => Self::LengthPercentage(l.compute(context)),
+            basis => basis.clone(),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#flex-grow-property
+/// flex-grow and flex-shrink take a `<number>`. "Negative numbers are invalid."
+fn flex_factor_from_component_values(values: &[ComponentValue]) -> Result<f64, Error> {
+    match values {
+        [ComponentValue::Number(n)] if *n >= 0.0 => Ok(*n),
+        _ => Err(Error::UnexpectedInput(format!(
+            "flex factor {:?} should be a non-negative number",
+            values
+        ))),
+    }
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#order-property
+fn order_from_component_values(values: &[ComponentValue]) -> Result<i32, Error> {
+    match values {
+        [ComponentValue::Number(n)] if *n == (*n as i32) as f64 => Ok(*n as i32),
+        _ => Err(Error::UnexpectedInput(format!(
+            "order {:?} should be an integer",
+            values
+        ))),
+    }
+}
+
+/// https://www.w3.org/TR/css-align-3/#column-row-gap
+/// `normal | <length-percentage [0,∞]>`. normal is 0 in flex containers.
+fn gap_from_component_values(values: &[ComponentValue]) -> Result<LengthPercentage, Error> {
+    match values {
+        [ComponentValue::Ident(ident)] if ident == "normal" => Ok(LengthPercentage::zero()),
+        _ => {
+            let gap = LengthPercentage::from_component_values(values)?;
+            if gap.is_negative() {
+                return Err(Error::UnexpectedInput(format!(
+                    "gap {:?} should not be negative",
+                    values
+                )));
+            }
+            Ok(gap)
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-grid-1/#typedef-track-breadth
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackBreadth {
+    LengthPercentage(LengthPercentage),
+    /// https://www.w3.org/TR/css-grid-1/#fr-unit
+    /// "A flexible length or <flex> is a dimension with the fr unit, which represents a fraction
+    /// of the leftover space in the grid container."
+    Flex(f64),
+    MinContent,
+    MaxContent,
+    Auto,
+}
+
+impl TrackBreadth {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(ident)] if ident == "min-content" => Ok(Self::MinContent),
+            [ComponentValue::Ident(ident)] if ident == "max-content" => Ok(Self::MaxContent),
+            [ComponentValue::Ident(ident)] if ident == "auto" => Ok(Self::Auto),
+            [ComponentValue::Dimension(n, unit)] if unit == "fr" && *n >= 0.0 => Ok(Self::Flex(*n)),
+            _ => {
+                let breadth = LengthPercentage::from_component_values(values)?;
+                if breadth.is_negative() {
+                    return Err(Error::UnexpectedInput(format!(
+                        "track breadth {:?} should not be negative",
+                        values
+                    )));
+                }
+                Ok(Self::LengthPercentage(breadth))
+            }
+        }
+    }
+
+    fn compute(&self, context: &ComputeContext) -> Self {
+        match self {
+            Self::LengthPercentage(l) => Self::LengthPercentage(l.compute(context)),
+            breadth => breadth.clone(),
+        }
+    }
+
+    /// Resolves a fixed breadth against the size of the grid container. A percentage of an
+    /// indefinite size behaves as auto.
+    pub fn resolve(&self, basis: Option<i64>) -> Option<i64> {
+        match (self, basis) {
+            (Self::LengthPercentage(l), Some(basis)) => Some(l.resolve(basis)),
+            (Self::LengthPercentage(l), None) if !l.has_percentage() => Some(l.resolve(0)),
+            _ => None,
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-grid-1/#typedef-track-size
+/// The min and max track sizing functions of a track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackSize {
+    min: TrackBreadth,
+    max: TrackBreadth,
+}
+
+impl TrackSize {
+    pub fn new(min: TrackBreadth, max: TrackBreadth) -> Self {
+        Self { min, max }
+    }
+
+    /// `<track-breadth> | minmax( <inflexible-breadth> , <track-breadth> )`. A single flexible
+    /// breadth is `minmax(auto, <flex>)`.
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Function(name), args @ .., ComponentValue::CloseParenthesis]
+                if name == "minmax" =>
+            {
+                let parts = split_component_values(args);
+                let args: Vec<_> = parts
+                    .split(|v| matches!(v, [ComponentValue::Delim(',')]))
+                    .collect();
+                match args.as_slice() {
+                    [min, max] => {
+                        let min = TrackBreadth::from_component_values(&min.concat())?;
+                        if let TrackBreadth::Flex(_) = min {
+                            return Err(Error::UnexpectedInput(format!(
+                                "the min track sizing function {:?} should not be flexible",
+                                values
+                            )));
+                        }
+                        let max = TrackBreadth::from_component_values(&max.concat())?;
+                        Ok(Self { min, max })
+                    }
+                    _ => Err(Error::UnexpectedInput(format!(
+                        "minmax() {:?} should have two arguments",
+                        values
+                    ))),
+                }
+            }
+            _ => match TrackBreadth::from_component_values(values)? {
+                TrackBreadth::Flex(flex) => Ok(Self {
+                    min: TrackBreadth::Auto,
+                    max: TrackBreadth::Flex(flex),
+                }),
+                breadth => Ok(Self {
+                    min: breadth.clone(),
+                    max: breadth,
+                }),
+            },
+        }
+    }
+
+    pub fn min(&self) -> &TrackBreadth {
+        &self.min
+    }
+
+    pub fn max(&self) -> &TrackBreadth {
+        &self.max
+    }
+
+    fn compute(&self, context: &ComputeContext) -> Self {
+        Self {
+            min: self.min.compute(context),
+            max: self.max.compute(context),
+        }
+    }
+}
+
+fn track_sizes_from_component_values(values: &[ComponentValue]) -> Result<Vec<TrackSize>, Error> {
+    let sizes = split_component_values(values)
+        .into_iter()
+        .map(TrackSize::from_component_values)
+        .collect::<Result<Vec<_>, _>>()?;
+    if sizes.is_empty() {
+        return Err(Error::UnexpectedInput(
+            "a list of track sizes should not be empty".to_string(),
+        ));
+    }
+    Ok(sizes)
+}
+
+/// https://www.w3.org/TR/css-grid-1/#auto-repeat
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AutoRepeat {
+    /// "the number of repetitions is the largest possible positive integer that does not cause
+    /// the grid to overflow the content box of its grid container"
+    AutoFill,
+    /// The same as auto-fill, except that the empty repeated tracks are collapsed.
+    AutoFit,
+}
+
+/// https://www.w3.org/TR/css-grid-1/#track-sizing
+/// The value of grid-template-columns or grid-template-rows, which are the sizes of the tracks
+/// of the explicit grid and the names of the lines between them. none is an empty list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackList {
+    sizes: Vec<TrackSize>,
+    /// The names of the lines before each track, and the ones after the last track.
+    line_names: Vec<Vec<String>>,
+    /// The tracks repeated by `repeat(auto-fill | auto-fit, ...)`, which are inserted before
+    /// the track at `auto_repeat_index`.
+    auto_repeat: Option<(AutoRepeat, TrackListPart)>,
+    auto_repeat_index: usize,
+}
+
+/// A list of track sizes with the names of the lines around them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackListPart {
+    sizes: Vec<TrackSize>,
+    line_names: Vec<Vec<String>>,
+}
+
+impl TrackListPart {
+    /// Parses `[ <line-names>? <track-size> ]+ <line-names>?`, where `repeat()` with an integer
+    /// is expanded.
+    fn from_component_values(
+        values: &[ComponentValue],
+        mut auto_repeat: Option<&mut Option<(AutoRepeat, TrackListPart, usize)>>,
+    ) -> Result<Self, Error> {
+        let mut part = Self {
+            sizes: Vec::new(),
+            line_names: vec![Vec::new()],
+        };
+        let parts = split_component_values(values);
+        let mut i = 0;
+        while i < parts.len() {
+            match parts[i] {
+                // https://www.w3.org/TR/css-grid-1/#named-lines
+                [ComponentValue::Delim('[')] => {
+                    i += 1;
+                    while let Some([ComponentValue::Ident(name)]) = parts.get(i) {
+                        part.line_names
+                            .last_mut()
+                            .expect("line names should exist")
+                            .push(name.clone());
+                        i += 1;
+                    }
+                    if parts.get(i) != Some(&[ComponentValue::Delim(']')].as_slice()) {
+                        return Err(Error::UnexpectedInput(format!(
+                            "line names in {:?} should be closed by ]",
+                            values
+                        )));
+                    }
+                }
+                // https://www.w3.org/TR/css-grid-1/#repeat-notation
+                [ComponentValue::Function(name), args @ .., ComponentValue::CloseParenthesis]
+                    if name == "repeat" =>
+                {
+                    let args = split_component_values(args);
+                    let (count, tracks) = match args.as_slice() {
+                        [count, [ComponentValue::Delim(',')], tracks @ ..] => {
+                            (*count, tracks.concat())
+                        }
+                        _ => {
+                            return Err(Error::UnexpectedInput(format!(
+                                "repeat() {:?} should have two arguments",
+                                parts[i]
+                            )))
+                        }
+                    };
+                    let repeated = Self::from_component_values(&tracks, None)?;
+                    match count {
+                        [ComponentValue::Number(n)] if *n >= 1.0 && *n == (*n as usize) as f64 => {
+                            for _ in 0..(*n as usize) {
+                                part.append(&repeated);
+                            }
+                        }
+                        [ComponentValue::Ident(ident)]
+                            if ident == "auto-fill" || ident == "auto-fit" =>
+                        {
+                            let kind = if ident == "auto-fill" {
+                                AutoRepeat::AutoFill
+                            } else {
+                                AutoRepeat::AutoFit
+                            };
+                            // "The <auto-repeat> variant can repeat automatically to fill a
+                            // space, but requires definite track sizes". Only one of them is
+                            // allowed in a track list.
+                            let fixed = repeated.sizes.iter().all(|s| {
+                                matches!(s.min, TrackBreadth::LengthPercentage(_))
+                                    || matches!(s.max, TrackBreadth::LengthPercentage(_))
+                            });
+                            match auto_repeat.as_mut() {
+                                Some(slot) if slot.is_none() && fixed => {
+                                    **slot = Some((kind, repeated, part.sizes.len()));
+                                }
+                                _ => {
+                                    return Err(Error::UnexpectedInput(format!(
+                                        "repeat() {:?} is not allowed here",
+                                        parts[i]
+                                    )))
+                                }
+                            }
+                        }
+                        _ => {
+                            return Err(Error::UnexpectedInput(format!(
+                                "the repetitions of repeat() {:?} should be a positive integer",
+                                parts[i]
+                            )))
+                        }
+                    }
+                }
+                size => {
+                    part.sizes.push(TrackSize::from_component_values(size)?);
+                    part.line_names.push(Vec::new());
+                }
+            }
+            i += 1;
+        }
+        Ok(part)
+    }
+
+    /// Appends the tracks of `other`. The names of the lines where they meet are merged.
+    fn append(&mut self, other: &TrackListPart) {
+        let (first, rest) = other
+            .line_names
+            .split_first()
+            .expect("line names should exist");
+        self.line_names
+            .last_mut()
+            .expect("line names should exist")
+            .extend(first.iter().cloned());
+        self.sizes.extend(other.sizes.iter().cloned());
+        self.line_names.extend(rest.iter().cloned());
+    }
+
+    fn compute(&self, context: &ComputeContext) -> Self {
+        Self {
+            sizes: self.sizes.iter().map(|s| s.compute(context)).collect(),
+            line_names: self.line_names.clone(),
+        }
+    }
+}
+
+impl TrackList {
+    /// `none | <track-list> | <auto-track-list>`
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        if let [ComponentValue::Ident(ident)] = values {
+            if ident == "none" {
+                return Ok(Self::default());
+            }
+        }
+        let mut auto_repeat = None;
+        let part = TrackListPart::from_component_values(values, Some(&mut auto_repeat))?;
+        if part.sizes.is_empty() && auto_repeat.is_none() {
+            return Err(Error::UnexpectedInput(format!(
+                "track list {:?} should have a track",
+                values
+            )));
+        }
+        let (auto_repeat, auto_repeat_index) = match auto_repeat {
+            Some((kind, tracks, index)) => (Some((kind, tracks)), index),
+            None => (None, 0),
+        };
+        Ok(Self {
+            sizes: part.sizes,
+            line_names: part.line_names,
+            auto_repeat,
+            auto_repeat_index,
+        })
+    }
+
+    /// Returns the kind of the auto-repeated tracks and where they are when they are repeated
+    /// `repetitions` times.
+    pub fn auto_repeat(&self, repetitions: usize) -> Option<(AutoRepeat, Range<usize>)> {
+        self.auto_repeat.as_ref().map(|(kind, repeated)| {
+            let start = self.auto_repeat_index;
+            (*kind, start..start + repeated.sizes.len() * repetitions)
+        })
+    }
+
+    /// https://www.w3.org/TR/css-grid-1/#auto-repeat
+    /// Returns how many times the auto-repeated tracks are repeated in a grid container whose
+    /// content box is `available` in this axis. "If the grid container has a definite size or
+    /// max size in the relevant axis, then the number of repetitions is the largest possible
+    /// positive integer that does not cause the grid to overflow the content box of its grid
+    /// container [...]. Otherwise, [...] the number of repetitions is 1."
+    pub fn repetitions(&self, available: Option<i64>, gap: i64) -> usize {
+        let (repeated, available) = match (&self.auto_repeat, available) {
+            (Some((_, repeated)), Some(available)) => (repeated, available),
+            _ => return 1,
+        };
+        // "treating each track as its max track sizing function if that is definite or as its
+        // minimum track sizing function otherwise"
+        let size = |s: &TrackSize| {
+            s.max
+                .resolve(Some(available))
+                .or_else(|| s.min.resolve(Some(available)))
+                .unwrap_or(0)
+        };
+        let fixed: i64 = self.sizes.iter().map(size).sum::<i64>() + gap * self.sizes.len() as i64;
+        let repeated_size: i64 =
+            repeated.sizes.iter().map(size).sum::<i64>() + gap * repeated.sizes.len() as i64;
+        if repeated_size <= 0 {
+            return 1;
+        }
+        // The gap after the last track isn't in the grid.
+        ((available - fixed + gap) / repeated_size).max(1) as usize
+    }
+
+    /// Returns the sizes of the tracks and the names of the lines with the auto-repeated tracks
+    /// repeated `repetitions` times.
+    pub fn expand(&self, repetitions: usize) -> (Vec<TrackSize>, Vec<Vec<String>>) {
+        let repeated = match &self.auto_repeat {
+            Some((_, repeated)) => repeated,
+            None => return (self.sizes.clone(), self.line_names.clone()),
+        };
+        let index = self.auto_repeat_index;
+        let mut part = TrackListPart {
+            sizes: self.sizes[..index].to_vec(),
+            line_names: self.line_names[..=index].to_vec(),
+        };
+        for _ in 0..repetitions {
+            part.append(repeated);
+        }
+        part.append(&TrackListPart {
+            sizes: self.sizes[index..].to_vec(),
+            line_names: self.line_names[index..].to_vec(),
+        });
+        (part.sizes, part.line_names)
+    }
+
+    fn compute(&self, context: &ComputeContext) -> Self {
+        Self {
+            sizes: self.sizes.iter().map(|s| s.compute(context)).collect(),
+            line_names: self.line_names.clone(),
+            auto_repeat: self
+                .auto_repeat
+                .as_ref()
+                .map(|(kind, tracks)| (*kind, tracks.compute(context))),
+            auto_repeat_index: self.auto_repeat_index,
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-grid-1/#grid-template-areas-property
+/// The named grid areas. Each area is a rectangle of cells given as the ranges of the rows and
+/// the columns that it covers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GridTemplateAreas {
+    rows: usize,
+    columns: usize,
+    areas: Vec<(String, Range<usize>, Range<usize>)>,
+}
+
+impl GridTemplateAreas {
+    /// `none | <string>+`. "A sequence of one or more '.' (U+002E FULL STOP) characters" is a
+    /// null cell token that doesn't belong to an area.
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        if let [ComponentValue::Ident(ident)] = values {
+            if ident == "none" {
+                return Ok(Self::default());
+            }
+        }
+        let invalid = || {
+            Err(Error::UnexpectedInput(format!(
+                "grid template areas {:?} are invalid",
+                values
+            )))
+        };
+        let mut cells: Vec<Vec<Option<&str>>> = Vec::new();
+        for value in values {
+            match value {
+                ComponentValue::StringToken(row) => cells.push(
+                    row.split_whitespace()
+                        .map(|cell| Some(cell).filter(|c| !c.chars().all(|c| c == '.')))
+                        .collect(),
+                ),
+                _ => return invalid(),
+            }
+        }
+        // "All strings must have the same number of columns, or else the declaration is
+        // invalid."
+        let columns = cells.first().map_or(0, |row| row.len());
+        if columns == 0 || cells.iter().any(|row| row.len() != columns) {
+            return invalid();
+        }
+
+        let mut areas: Vec<(String, Range<usize>, Range<usize>)> = Vec::new();
+        for (r, row) in cells.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                let name = match cell {
+                    Some(name) => *name,
+                    None => continue,
+                };
+                match areas.iter_mut().find(|(n, _, _)| n == name) {
+                    Some((_, rows, columns)) => {
+                        rows.start = rows.start.min(r);
+                        rows.end = rows.end.max(r + 1);
+                        columns.start = columns.start.min(c);
+                        columns.end = columns.end.max(c + 1);
+                    }
+                    None => areas.push((name.to_string(), r..r + 1, c..c + 1)),
+                }
+            }
+        }
+        // "If a named grid area spans multiple grid cells, but those cells do not form a
+        // single filled-in rectangle, the declaration is invalid."
+        for (name, rows, columns) in &areas {
+            for row in &cells[rows.clone()] {
+                if row[columns.clone()]
+                    .iter()
+                    .any(|cell| *cell != Some(name.as_str()))
+                {
+                    return invalid();
+                }
+            }
+            let count = cells
+                .iter()
+                .flatten()
+                .filter(|cell| **cell == Some(name.as_str()))
+                .count();
+            if count != rows.len() * columns.len() {
+                return invalid();
+            }
+        }
+        Ok(Self {
+            rows: cells.len(),
+            columns,
+            areas,
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Returns the rows and the columns of the area named `name`.
+    pub fn area(&self, name: &str) -> Option<(Range<usize>, Range<usize>)> {
+        self.areas
+            .iter()
+            .find(|(n, _, _)| n == name)
+            .map(|(_, rows, columns)| (rows.clone(), columns.clone()))
+    }
+
+    /// The names of the areas with their rows and columns.
+    pub fn areas(&self) -> impl Iterator<Item = (&str, Range<usize>, Range<usize>)> {
+        self.areas
+            .iter()
+            .map(|(name, rows, columns)| (name.as_str(), rows.clone(), columns.clone()))
+    }
+}
+
+/// https://www.w3.org/TR/css-grid-1/#line-placement
+/// The value of grid-row-start, grid-column-start, grid-row-end and grid-column-end.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridLine {
+    Auto,
+    /// The nth line. A negative number counts from the end of the explicit grid.
+    Line(i32),
+    /// "Contributes a grid span to the grid item's placement such that the corresponding edge
+    /// of the grid item's grid area is N lines from its opposite edge"
+    Span(usize),
+    /// A named line, or the line at the start or the end of a named area.
+    Name(String),
+}
+
+impl GridLine {
+    /// `auto | <custom-ident> | <integer> | span <integer>`
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        let integer = |n: f64| n == (n as i32) as f64 && n != 0.0;
+        match values {
+            [ComponentValue::Ident(ident)] if ident == "auto" => Ok(Self::Auto),
+            [ComponentValue::Ident(ident)] if ident != "span" => Ok(Self::Name(ident.clone())),
+            [ComponentValue::Number(n)] if integer(*n) => Ok(Self::Line(*n as i32)),
+            [ComponentValue::Ident(span), ComponentValue::Number(n)]
+            | [ComponentValue::Number(n), ComponentValue::Ident(span)]
+                if span == "span" && integer(*n) && *n > 0.0 =>
+            {
+                Ok(Self::Span(*n as usize))
+            }
+            _ => Err(Error::UnexpectedInput(format!(
+                "grid line {:?} is not supported",
+                values
+            ))),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-grid-1/#grid-auto-flow-property
+/// `[ row | column ] || dense`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GridAutoFlow {
+    column: bool,
+    dense: bool,
+}
+
+impl GridAutoFlow {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        let (mut direction, mut dense) = (None, false);
+        for value in values {
+            match value {
+                ComponentValue::Ident(ident) if ident == "row" && direction.is_none() => {
+                    direction = Some(false)
+                }
+                ComponentValue::Ident(ident) if ident == "column" && direction.is_none() => {
+                    direction = Some(true)
+                }
+                ComponentValue::Ident(ident) if ident == "dense" && !dense => dense = true,
+                _ => {
+                    return Err(Error::UnexpectedInput(format!(
+                        "grid auto flow {:?} is not supported",
+                        values
+                    )))
+                }
+            }
+        }
+        if direction.is_none() && !dense {
+            return Err(Error::UnexpectedInput(
+                "grid auto flow should not be empty".to_string(),
+            ));
+        }
+        Ok(Self {
+            column: direction.unwrap_or(false),
+            dense,
+        })
+    }
+
+    /// Whether the auto-placed items fill each column in turn instead of each row.
+    pub fn is_column(&self) -> bool {
+        self.column
+    }
+
+    /// "If specified, the auto-placement algorithm uses a "dense" packing algorithm, which
+    /// attempts to fill in holes earlier in the grid if smaller items come up later."
+    pub fn is_dense(&self) -> bool {
+        self.dense
+    }
+}
+
+/// https://www.w3.org/TR/css-lists-3/#text-markers
+/// `<counter-style> | <string> | none`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListStyleType {
+    None,
+    CounterStyle(CounterStyle),
+    String(String),
+}
+
+impl ListStyleType {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(ident)] if ident == "none" => Ok(Self::None),
+            [ComponentValue::Ident(ident)] => Ok(Self::CounterStyle(
+                CounterStyle::from_str_counter_style(ident)?,
+            )),
+            [ComponentValue::StringToken(s)] => Ok(Self::String(s.clone())),
+            _ => Err(Error::UnexpectedInput(format!(
+                "list style type {:?} is not supported yet",
+                values
+            ))),
+        }
+    }
+
+    /// https://www.w3.org/TR/css-lists-3/#content-property
+    /// "If the list-style-type value is a <counter-style>, the ::marker's contents are the marker
+    /// string of the list-item counter", and a <string> is used as it is. Returns None if there is
+    /// no marker.
+    pub fn marker(&self, value: i32) -> Option<String> {
+        match self {
+            Self::None => None,
+            Self::CounterStyle(style) => Some(style.marker(value)),
+            Self::String(s) => Some(s.clone()),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-lists-3/#list-style-position-property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ListStylePosition {
+    /// "The ::marker pseudo-element is an inline element placed immediately before the
+    /// ::before pseudo-element in the list item's principal box".
+    Inside,
+    /// The marker box is placed outside the principal block box, before the first line.
+    Outside,
+}
+
+impl ListStylePosition {
+    pub fn from_str_list_style_position(s: &str) -> Result<Self, Error> {
+        match s {
+            "inside" => Ok(Self::Inside),
+            "outside" => Ok(Self::Outside),
+            _ => Err(Error::UnexpectedInput(format!(
+                "list style position {:?} is not supported",
+                s
+            ))),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-lists-3/#counter-properties
+/// A counter name and the integer of counter-reset, counter-increment or counter-set. The integer
+/// is None when it's omitted, which is 0 for counter-reset and counter-set and 1 for
+/// counter-increment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterChange {
+    name: String,
+    value: Option<i32>,
+    /// `reversed(<counter-name>)` in counter-reset creates a counter that counts down.
+    reversed: bool,
+}
+
+impl CounterChange {
+    pub fn new(name: &str, value: Option<i32>, reversed: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            value,
+            reversed,
+        }
+    }
+
+    /// `[ <counter-name> <integer>? ]+ | none`, where a counter name of counter-reset can also be
+    /// `reversed(<counter-name>)`.
+    pub fn from_component_values(
+        values: &[ComponentValue],
+        allow_reversed: bool,
+    ) -> Result<Vec<Self>, Error> {
+        let invalid =
+            || Error::UnexpectedInput(format!("counter list {:?} is not supported", values));
+        if let [ComponentValue::Ident(ident)] = values {
+            if ident == "none" {
+                return Ok(Vec::new());
+            }
+        }
+
+        let mut changes: Vec<Self> = Vec::new();
+        for value in split_component_values(values) {
+            match value {
+                [ComponentValue::Ident(name)] if is_counter_name(name) => {
+                    changes.push(Self::new(name, None, false))
+                }
+                [ComponentValue::Function(function), ComponentValue::Ident(name), ComponentValue::CloseParenthesis]
+                    if allow_reversed && function == "reversed" && is_counter_name(name) =>
+                {
+                    changes.push(Self::new(name, None, true))
+                }
+                [ComponentValue::Number(n)] if *n == (*n as i32) as f64 => {
+                    match changes.last_mut() {
+                        Some(change) if change.value.is_none() => change.value = Some(*n as i32),
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        if changes.is_empty() {
+            return Err(invalid());
+        }
+        Ok(changes)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> Option<i32> {
+        self.value
+    }
+
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+}
+
+/// https://www.w3.org/TR/css-lists-3/#typedef-counter-name
+/// "The <counter-name> can be any <custom-ident> other than none".
+fn is_counter_name(name: &str) -> bool {
+    !matches!(
+        name,
+        "none" | "initial" | "inherit" | "unset" | "revert" | "default"
+    )
+}
+
+/// https://www.w3.org/TR/css-content-3/#content-property
+/// `normal | none | [ <string> | <counter()> | <counters()> | <attr()> | <quote> ]+`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Normal,
+    None,
+    Items(Vec<ContentItem>),
+}
+
+/// https://www.w3.org/TR/css-lists-3/#counter-functions
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentItem {
+    String(String),
+    /// `counter(<counter-name>, <counter-style>?)`. The style is None for `none`, which shows
+    /// nothing.
+    Counter(String, Option<CounterStyle>),
+    /// `counters(<counter-name>, <string>, <counter-style>?)`
+    Counters(String, String, Option<CounterStyle>),
+    /// https://www.w3.org/TR/css-content-3/#attr
+    /// `attr(<attr-name>)` is the value of the attribute of the originating element, or the empty
+    /// string when it is missing.
+    Attr(String),
+    /// https://www.w3.org/TR/css-content-3/#quote-values
+    OpenQuote,
+    CloseQuote,
+    NoOpenQuote,
+    NoCloseQuote,
+}
+
+impl Content {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(ident)] if ident == "normal" => return Ok(Self::Normal),
+            [ComponentValue::Ident(ident)] if ident == "none" => return Ok(Self::None),
+            [] => {
+                return Err(Error::UnexpectedInput(
+                    "content should not be empty".to_string(),
+                ))
+            }
+            _ => {}
+        }
+        split_component_values(values)
+            .into_iter()
+            .map(ContentItem::from_component_values)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self::Items)
+    }
+}
+
+impl ContentItem {
+    fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        let invalid =
+            || Error::UnexpectedInput(format!("content {:?} is not supported yet", values));
+        let (name, arguments) = match values {
+            [ComponentValue::StringToken(s)] => return Ok(Self::String(s.clone())),
+            [ComponentValue::Ident(ident)] => {
+                return match ident.as_str() {
+                    "open-quote" => Ok(Self::OpenQuote),
+                    "close-quote" => Ok(Self::CloseQuote),
+                    "no-open-quote" => Ok(Self::NoOpenQuote),
+                    "no-close-quote" => Ok(Self::NoCloseQuote),
+                    _ => Err(invalid()),
+                }
+            }
+            [ComponentValue::Function(name), arguments @ .., ComponentValue::CloseParenthesis] => {
+                (name.as_str(), arguments)
+            }
+            _ => return Err(invalid()),
+        };
+        let arguments: Vec<&[ComponentValue]> = arguments
+            .split(|v| *v == ComponentValue::Delim(','))
+            .collect();
+        let counter_style = |argument: Option<&&[ComponentValue]>| match argument {
+            None => Ok(Some(CounterStyle::Decimal)),
+            Some([ComponentValue::Ident(ident)]) if ident == "none" => Ok(None),
+            Some([ComponentValue::Ident(ident)]) => {
+                CounterStyle::from_str_counter_style(ident).map(Some)
+            }
+            _ => Err(invalid()),
+        };
+        match (name, arguments.as_slice()) {
+            ("counter", [[ComponentValue::Ident(counter)], rest @ ..])
+                if rest.len() <= 1 && is_counter_name(counter) =>
+            {
+                Ok(Self::Counter(counter.clone(), counter_style(rest.first())?))
+            }
+            (
+                "counters",
+                [[ComponentValue::Ident(counter)], [ComponentValue::StringToken(separator)], rest @ ..],
+            ) if rest.len() <= 1 && is_counter_name(counter) => Ok(Self::Counters(
+                counter.clone(),
+                separator.clone(),
+                counter_style(rest.first())?,
+            )),
+            ("attr", [[ComponentValue::Ident(attribute)]]) => Ok(Self::Attr(attribute.clone())),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-backgrounds-3/#background-image
+/// A layer of `<bg-image>#`, where `<bg-image> = <image> | none`. Only url() images are supported,
+/// and the other images such as gradients are painted as none.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackgroundImage {
+    None,
+    Url(String),
+}
+
+impl BackgroundImage {
+    /// Parses the comma-separated layers of background-image. The first layer is painted on top.
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Vec<Self>, Error> {
+        let mut layers = vec![Vec::new()];
+        for part in split_component_values(values) {
+            match part {
+                [ComponentValue::Delim(',')] => layers.push(Vec::new()),
+                _ => layers.last_mut().expect("layers aren't empty").push(part),
+            }
+        }
+        layers
+            .iter()
+            .map(|layer| match layer[..] {
+                [[ComponentValue::Ident(ident)]] if ident.eq_ignore_ascii_case("none") => {
+                    Ok(Self::None)
+                }
+                [[ComponentValue::Url(url)]] => Ok(Self::Url(url.clone())),
+                [[ComponentValue::Function(name), ComponentValue::StringToken(url), ComponentValue::CloseParenthesis]]
+                    if name.eq_ignore_ascii_case("url") =>
+                {
+                    Ok(Self::Url(url.clone()))
+                }
+                [[ComponentValue::Function(_), ..]] => Ok(Self::None),
+                _ => Err(Error::UnexpectedInput(format!(
+                    "background-image {:?} should be a list of images or none",
+                    values
+                ))),
+            })
+            .collect()
+    }
+
+    /// Returns the URL of the image, which is empty for an invalid url().
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Self::Url(url) if !url.is_empty() => Some(url),
+            _ => None,
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-content-3/#quotes-property
+/// `auto | none | [ <string> <string> ]+`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Quotes {
+    /// "A typographically appropriate used value for quotes is automatically chosen by the UA
+    /// based on the content language". English curly quotes are used for every language here.
+    Auto,
+    None,
+    Pairs(Vec<(String, String)>),
+}
+
+impl Quotes {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(ident)] if ident == "auto" => return Ok(Self::Auto),
+            [ComponentValue::Ident(ident)] if ident == "none" => return Ok(Self::None),
+            _ => {}
+        }
+        if values.is_empty() || !values.len().is_multiple_of(2) {
+            return Err(Error::UnexpectedInput(format!(
+                "quotes {:?} should be pairs of strings",
+                values
+            )));
+        }
+        values
+            .chunks(2)
+            .map(|pair| match pair {
+                [ComponentValue::StringToken(open), ComponentValue::StringToken(close)] => {
+                    Ok((open.clone(), close.clone()))
+                }
+                _ => Err(Error::UnexpectedInput(format!(
+                    "quotes {:?} should be pairs of strings",
+                    values
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self::Pairs)
+    }
+
+    /// Returns the open quote or the close quote for the nesting `depth`. "If the depth is greater
+    /// than the number of pairs, the last pair is repeated."
+    pub fn quote(&self, depth: usize, open: bool) -> String {
+        let auto = [
+            ("\u{201C}".to_string(), "\u{201D}".to_string()),
+            ("\u{2018}".to_string(), "\u{2019}".to_string()),
+        ];
+        let pairs = match self {
+            Self::Auto => &auto[..],
+            Self::None => return String::new(),
+            Self::Pairs(pairs) => pairs.as_slice(),
+        };
+        let (open_quote, close_quote) = &pairs[depth.min(pairs.len() - 1)];
+        if open {
+            open_quote.clone()
+        } else {
+            close_quote.clone()
+        }
+    }
+}
+
+/// Expands to a match over the names of longhand properties. For each property, `$callback` is
+/// called with `$args` and the path of the field that stores the property.
+macro_rules! match_longhand {
+    ($name:expr, $callback:ident!($($args:tt)*), $default:expr) => {
+        match $name {
+            "color" => $callback!($($args)*; color),
+            "font-size" => $callback!($($args)*; font_size),
+            "font-weight" => $callback!($($args)*; font_weight),
+            "font-style" => $callback!($($args)*; font_style),
+            "font-family" => $callback!($($args)*; font_family),
+            "line-height" => $callback!($($args)*; line_height),
+            "text-decoration-line" => $callback!($($args)*; text_decoration),
+            "text-align" => $callback!($($args)*; text_align),
+            "white-space" => $callback!($($args)*; white_space),
+            "text-indent" => $callback!($($args)*; text_indent),
+            "letter-spacing" => $callback!($($args)*; letter_spacing),
+            "word-spacing" => $callback!($($args)*; word_spacing),
+            "text-transform" => $callback!($($args)*; text_transform),
+            "word-break" => $callback!($($args)*; word_break),
+            "overflow-wrap" => $callback!($($args)*; overflow_wrap),
+            "line-break" => $callback!($($args)*; line_break),
+            "vertical-align" => $callback!($($args)*; vertical_align),
+            "display" => $callback!($($args)*; display),
+            "background-color" => $callback!($($args)*; background_color),
+            "background-image" => $callback!($($args)*; background_image),
+            "width" => $callback!($($args)*; width),
+            "height" => $callback!($($args)*; height),
+            "box-sizing" => $callback!($($args)*; box_sizing),
+            "overflow-x" => $callback!($($args)*; overflow[0]),
+            "overflow-y" => $callback!($($args)*; overflow[1]),
+            "position" => $callback!($($args)*; position),
+            "top" => $callback!($($args)*; inset[0]),
+            "right" => $callback!($($args)*; inset[1]),
+            "bottom" => $callback!($($args)*; inset[2]),
+            "left" => $callback!($($args)*; inset[3]),
+            "z-index" => $callback!($($args)*; z_index),
+            "float" => $callback!($($args)*; float),
+            "clear" => $callback!($($args)*; clear),
+            "flex-direction" => $callback!($($args)*; flex_direction),
+            "flex-wrap" => $callback!($($args)*; flex_wrap),
+            "justify-content" => $callback!($($args)*; justify_content),
+            "align-items" => $callback!($($args)*; align_items),
+            "align-self" => $callback!($($args)*; align_self),
+            "flex-grow" => $callback!($($args)*; flex_grow),
+            "flex-shrink" => $callback!($($args)*; flex_shrink),
+            "flex-basis" => $callback!($($args)*; flex_basis),
+            "row-gap" => $callback!($($args)*; gap[0]),
+            "column-gap" => $callback!($($args)*; gap[1]),
+            "order" => $callback!($($args)*; order),
+            "grid-template-columns" => $callback!($($args)*; grid_template_columns),
+            "grid-template-rows" => $callback!($($args)*; grid_template_rows),
+            "grid-template-areas" => $callback!($($args)*; grid_template_areas),
+            "grid-auto-columns" => $callback!($($args)*; grid_auto_columns),
+            "grid-auto-rows" => $callback!($($args)*; grid_auto_rows),
+            "grid-auto-flow" => $callback!($($args)*; grid_auto_flow),
+            "grid-row-start" => $callback!($($args)*; grid_line[0]),
+            "grid-column-start" => $callback!($($args)*; grid_line[1]),
+            "grid-row-end" => $callback!($($args)*; grid_line[2]),
+            "grid-column-end" => $callback!($($args)*; grid_line[3]),
+            "margin-top" => $callback!($($args)*; margin[0]),
+            "margin-right" => $callback!($($args)*; margin[1]),
+            "margin-bottom" => $callback!($($args)*; margin[2]),
+            "margin-left" => $callback!($($args)*; margin[3]),
+            "padding-top" => $callback!($($args)*; padding[0]),
+            "padding-right" => $callback!($($args)*; padding[1]),
+            "padding-bottom" => $callback!($($args)*; padding[2]),
+            "padding-left" => $callback!($($args)*; padding[3]),
+            "border-top-width" => $callback!($($args)*; border_width[0]),
+            "border-right-width" => $callback!($($args)*; border_width[1]),
+            "border-bottom-width" => $callback!($($args)*; border_width[2]),
+            "border-left-width" => $callback!($($args)*; border_width[3]),
+            "border-top-style" => $callback!($($args)*; border_style[0]),
+            "border-right-style" => $callback!($($args)*; border_style[1]),
+            "border-bottom-style" => $callback!($($args)*; border_style[2]),
+            "border-left-style" => $callback!($($args)*; border_style[3]),
+            "border-top-color" => $callback!($($args)*; border_color[0]),
+            "border-right-color" => $callback!($($args)*; border_color[1]),
+            "border-bottom-color" => $callback!($($args)*; border_color[2]),
+            "border-left-color" => $callback!($($args)*; border_color[3]),
+            "border-top-left-radius" => $callback!($($args)*; border_radius[0]),
+            "border-top-right-radius" => $callback!($($args)*; border_radius[1]),
+            "border-bottom-right-radius" => $callback!($($args)*; border_radius[2]),
+            "border-bottom-left-radius" => $callback!($($args)*; border_radius[3]),
+            "opacity" => $callback!($($args)*; opacity),
+            "transform" => $callback!($($args)*; transform),
+            "list-style-type" => $callback!($($args)*; list_style_type),
+            "list-style-position" => $callback!($($args)*; list_style_position),
+            "counter-reset" => $callback!($($args)*; counters[0]),
+            "counter-increment" => $callback!($($args)*; counters[1]),
+            "counter-set" => $callback!($($args)*; counters[2]),
+            "content" => $callback!($($args)*; content),
+            "quotes" => $callback!($($args)*; quotes),
+            _ => $default,
+        }
+    };
+}
+
+macro_rules! has_field {
+    ($style:expr; $($field:tt)+) => {
+        $style.$($field)+.is_some()
+    };
+}
+
+macro_rules! clear_field {
+    ($style:expr; $($field:tt)+) => {
+        $style.$($field)+ = None
+    };
+}
+
+macro_rules! copy_field {
+    ($style:expr, $from:expr; $($field:tt)+) => {
+        $style.$($field)+ = $from.$($field)+.clone()
+    };
+}
+
+/// Parses a component value that consists of a single token.
+fn single_token<T>(
+    values: &[ComponentValue],
+    parse: fn(&ComponentValue) -> Result<T, Error>,
+) -> Result<T, Error> {
+    match values {
+        [value] => parse(value),
+        _ => Err(Error::UnexpectedInput(format!(
+            "expected a single value but got {:?}",
+            values
+        ))),
+    }
+}
+
+/// Parses a value that consists of a single keyword.
+fn keyword<T>(values: &[ComponentValue], parse: fn(&str) -> Result<T, Error>) -> Result<T, Error> {
+    match values {
+        [ComponentValue::Ident(ident)] => parse(ident),
+        _ => Err(Error::UnexpectedInput(format!(
+            "expected a keyword but got {:?}",
+            values
+        ))),
+    }
+}
+
+/// https://www.w3.org/TR/css-box-4/#padding-physical
+/// "Negative values for padding properties are invalid."
+fn padding_from_component_values(values: &[ComponentValue]) -> Result<LengthPercentage, Error> {
+    let padding = LengthPercentage::from_component_values(values)?;
+    if padding.is_negative() {
+        return Err(Error::UnexpectedInput(format!(
+            "padding {:?} should not be negative",
+            values
+        )));
+    }
+    Ok(padding)
+}
+
+/// https://www.w3.org/TR/css-sizing-3/#preferred-size-properties
+/// "Negative values are invalid."
+fn size_from_component_values(values: &[ComponentValue]) -> Result<LengthPercentageOrAuto, Error> {
+    let size = LengthPercentageOrAuto::from_component_values(values)?;
+    if let LengthPercentageOrAuto::LengthPercentage(l) = &size {
+        if l.is_negative() {
+            return Err(Error::UnexpectedInput(format!(
+                "size {:?} should not be negative",
+                values
+            )));
+        }
+    }
+    Ok(size)
+}
+
+/// https://source.chromium.org/chromium/chromium/src/+/main:third_party/blink/renderer/core/style/style_difference.h
+/// How much of the rendering of a box is out of date when its style changes, from the least to
+/// the most.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StyleDifference {
+    Equal,
+    /// Only properties that don't change the sizes or positions of boxes are changed, so the box
+    /// only has to be painted again.
+    Paint,
+    /// The box has to be laid out again.
+    Layout,
+    /// Properties that decide which boxes are generated are changed, so the boxes have to be
+    /// built again.
+    Rebuild,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedStyle {
+    background_color: Option<ColorValue>,
+    background_image: Option<Vec<BackgroundImage>>,
+    color: Option<ColorValue>,
+    display: Option<DisplayType>,
+    font_size: Option<FontSize>,
+    font_weight: Option<FontWeight>,
+    font_style: Option<FontStyle>,
+    font_family: Option<Vec<FontFamily>>,
+    line_height: Option<LineHeight>,
+    text_decoration: Option<TextDecoration>,
+    text_align: Option<TextAlign>,
+    white_space: Option<WhiteSpace>,
+    text_indent: Option<LengthPercentage>,
+    letter_spacing: Option<Length>,
+    word_spacing: Option<Length>,
+    text_transform: Option<TextTransform>,
+    word_break: Option<WordBreak>,
+    overflow_wrap: Option<OverflowWrap>,
+    line_break: Option<LineBreak>,
+    vertical_align: Option<VerticalAlign>,
+    height: Option<LengthPercentageOrAuto>,
+    width: Option<LengthPercentageOrAuto>,
+    box_sizing: Option<BoxSizing>,
+    /// overflow-x and overflow-y.
+    overflow: [Option<Overflow>; 2],
+    position: Option<Position>,
+    /// top, right, bottom and left.
+    inset: [Option<LengthPercentageOrAuto>; 4],
+    z_index: Option<ZIndex>,
+    float: Option<Float>,
+    clear: Option<Clear>,
+    flex_direction: Option<FlexDirection>,
+    flex_wrap: Option<FlexWrap>,
+    justify_content: Option<JustifyContent>,
+    align_items: Option<AlignItems>,
+    align_self: Option<AlignItems>,
+    flex_grow: Option<f64>,
+    flex_shrink: Option<f64>,
+    flex_basis: Option<FlexBasis>,
+    /// row-gap and column-gap.
+    gap: [Option<LengthPercentage>; 2],
+    order: Option<i32>,
+    grid_template_columns: Option<TrackList>,
+    grid_template_rows: Option<TrackList>,
+    grid_template_areas: Option<GridTemplateAreas>,
+    grid_auto_columns: Option<Vec<TrackSize>>,
+    grid_auto_rows: Option<Vec<TrackSize>>,
+    grid_auto_flow: Option<GridAutoFlow>,
+    /// grid-row-start, grid-column-start, grid-row-end and grid-column-end.
+    grid_line: [Option<GridLine>; 4],
+    margin: [Option<LengthPercentageOrAuto>; 4],
+    padding: [Option<LengthPercentage>; 4],
+    border_width: [Option<Length>; 4],
+    border_style: [Option<BorderStyle>; 4],
+    border_color: [Option<ColorValue>; 4],
+    /// border-top-left-radius, border-top-right-radius, border-bottom-right-radius and
+    /// border-bottom-left-radius.
+    border_radius: [Option<BorderRadius>; 4],
+    opacity: Option<f64>,
+    transform: Option<Vec<TransformFunction>>,
+    list_style_type: Option<ListStyleType>,
+    list_style_position: Option<ListStylePosition>,
+    /// counter-reset, counter-increment and counter-set.
+    counters: [Option<Vec<CounterChange>>; 3],
+    content: Option<Content>,
+    quotes: Option<Quotes>,
+    /// Custom properties are always inherited, and their computed values have no var().
+    custom_properties: CustomProperties,
+}
+
+impl Default for ComputedStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ComputedStyle {
+    pub fn new() -> Self {
+        Self {
+            background_color: None,
+            background_image: None,
+            color: None,
+            display: None,
+            font_size: None,
+            font_weight: None,
+            font_style: None,
+            font_family: None,
+            line_height: None,
+            text_decoration: None,
+            text_align: None,
+            white_space: None,
+            text_indent: None,
+            letter_spacing: None,
+            word_spacing: None,
+            text_transform: None,
+            word_break: None,
+            overflow_wrap: None,
+            line_break: None,
+            vertical_align: None,
+            height: None,
+            width: None,
+            box_sizing: None,
+            overflow: [None; 2],
+            position: None,
+            inset: [None, None, None, None],
+            z_index: None,
+            float: None,
+            clear: None,
+            flex_direction: None,
+            flex_wrap: None,
+            justify_content: None,
+            align_items: None,
+            align_self: None,
+            flex_grow: None,
+            flex_shrink: None,
+            flex_basis: None,
+            gap: [None, None],
+            order: None,
+            grid_template_columns: None,
+            grid_template_rows: None,
+            grid_template_areas: None,
+            grid_auto_columns: None,
+            grid_auto_rows: None,
+            grid_auto_flow: None,
+            grid_line: [None, None, None, None],
+            margin: [None, None, None, None],
+            padding: [None, None, None, None],
+            border_width: [None; 4],
+            border_style: [None; 4],
+            border_color: [None, None, None, None],
+            border_radius: [None, None, None, None],
+            opacity: None,
+            transform: None,
+            list_style_type: None,
+            list_style_position: None,
+            counters: [None, None, None],
+            content: None,
+            quotes: None,
+            custom_properties: CustomProperties::new(),
+        }
+    }
+
+    pub fn set_background_color(&mut self, color: ColorValue) {
+        self.background_color = Some(color);
+    }
+
+    pub fn background_color(&self) -> Color {
+        self.background_color
+            .as_ref()
+            .and_then(ColorValue::computed)
+            .expect("failed to access Css property: background_color")
+    }
+
+    pub fn set_background_image(&mut self, layers: Vec<BackgroundImage>) {
+        self.background_image = Some(layers);
+    }
+
+    pub fn background_image(&self) -> Vec<BackgroundImage> {
+        self.background_image
+            .clone()
+            .expect("failed to access Css property: background_image")
+    }
+
+    pub fn set_color(&mut self, color: ColorValue) {
+        self.color = Some(color);
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+            .as_ref()
+            .and_then(ColorValue::computed)
+            .expect("failed to access Css property: color")
+    }
+
+    pub fn set_display(&mut self, display: DisplayType) {
+        self.display = Some(display);
+    }
+
+    pub fn set_font_size(&mut self, font_size: FontSize) {
+        self.font_size = Some(font_size);
+    }
+
+    pub fn set_font_weight(&mut self, font_weight: FontWeight) {
+        self.font_weight = Some(font_weight);
+    }
+
+    pub fn set_font_style(&mut self, font_style: FontStyle) {
+        self.font_style = Some(font_style);
+    }
+
+    pub fn set_font_family(&mut self, font_family: Vec<FontFamily>) {
+        self.font_family = Some(font_family);
+    }
+
+    pub fn set_line_height(&mut self, line_height: LineHeight) {
+        self.line_height = Some(line_height);
+    }
+
+    pub fn set_text_decoration(&mut self, text_decoration: TextDecoration) {
+        self.text_decoration = Some(text_decoration);
+    }
+
+    pub fn set_text_align(&mut self, text_align: TextAlign) {
+        self.text_align = Some(text_align);
+    }
+
+    pub fn set_white_space(&mut self, white_space: WhiteSpace) {
+        self.white_space = Some(white_space);
+    }
+
+    pub fn set_word_break(&mut self, word_break: WordBreak) {
+        self.word_break = Some(word_break);
+    }
+
+    pub fn set_overflow_wrap(&mut self, overflow_wrap: OverflowWrap) {
+        self.overflow_wrap = Some(overflow_wrap);
+    }
+
+    pub fn set_line_break(&mut self, line_break: LineBreak) {
+        self.line_break = Some(line_break);
+    }
+
+    pub fn set_text_indent(&mut self, text_indent: LengthPercentage) {
+        self.text_indent = Some(text_indent);
+    }
+
+    pub fn set_letter_spacing(&mut self, letter_spacing: Length) {
+        self.letter_spacing = Some(letter_spacing);
+    }
+
+    pub fn set_word_spacing(&mut self, word_spacing: Length) {
+        self.word_spacing = Some(word_spacing);
+    }
+
+    pub fn set_text_transform(&mut self, text_transform: TextTransform) {
+        self.text_transform = Some(text_transform);
+    }
+
+    pub fn set_vertical_align(&mut self, vertical_align: VerticalAlign) {
+        self.vertical_align = Some(vertical_align);
+    }
+
+    /// https://www.w3.org/TR/css-cascade-4/#value-stages
+    /// Computes the style of an element from the declarations that apply to it, in cascade order,
+    /// and the computed style of its parent.
+    pub fn compute(
+        declarations: &[(CascadeOrigin, Declaration)],
+        parent_style: Option<&ComputedStyle>,
+        device: &Device,
+    ) -> Self {
+        let mut style = Self::new();
+        style.custom_properties = Self::cascade_custom_properties(declarations, parent_style);
+
+        // CSS-wide keywords that won the cascade, by longhand property.
+        let mut keywords: BTreeMap<&'static str, CssWideKeyword> = BTreeMap::new();
+        // The result of the user-agent origin, which `revert` in the author origin rolls back to.
+        let mut user_agent_cascade = None;
+
+        for (origin, declaration) in declarations {
+            if is_custom_property_name(&declaration.property) {
+                continue;
+            }
+            if *origin == CascadeOrigin::Author && user_agent_cascade.is_none() {
+                user_agent_cascade = Some((style.clone(), keywords.clone()));
+            }
+            let has_var = contains_var(&declaration.value);
+            let value = if has_var {
+                substitute_var(&declaration.value, &mut |name| {
+                    style.custom_properties.get(name).cloned()
+                })
+            } else {
+                Ok(declaration.value.clone())
+            };
+
+            // Shorthands are expanded after var() is substituted because a custom property can
+            // hold any part of the value of a shorthand.
+            let expanded = value.and_then(|value| {
+                let mut declaration = declaration.clone();
+                declaration.set_value(value);
+                expand_declaration(&declaration)
+            });
+            let expanded = match expanded {
+                Ok(expanded) if Self::is_valid(&expanded) => expanded,
+                // https://www.w3.org/TR/css-variables-1/#invalid-at-computed-value-time
+                // "If a declaration is invalid at computed-value time, ... the declaration's
+                // value becomes ... unset."
+                _ if has_var => {
+                    let mut unset = declaration.clone();
+                    unset.set_value(Vec::from([ComponentValue::Ident("unset".to_string())]));
+                    expand_declaration(&unset).unwrap_or_default()
+                }
+                // Invalid declarations are ignored.
+                _ => continue,
+            };
+
+            for longhand in expanded {
+                let name = match property_definition(&longhand.property) {
+                    Some(definition) => definition.name,
+                    // The longhand is not supported yet.
+                    None => continue,
+                };
+                match CssWideKeyword::from_component_values(&longhand.value) {
+                    Some(CssWideKeyword::Revert) if *origin == CascadeOrigin::Author => {
+                        if let Some((user_agent_style, user_agent_keywords)) = &user_agent_cascade {
+                            style.copy_property(name, user_agent_style);
+                            match user_agent_keywords.get(name) {
+                                Some(keyword) => keywords.insert(name, *keyword),
+                                None => keywords.remove(name),
+                            };
+                        }
+                    }
+                    Some(keyword) => {
+                        style.clear_property(name);
+                        keywords.insert(name, keyword);
+                    }
+                    None => {
+                        if style.apply_declaration(name, &longhand.value).is_ok() {
+                            keywords.remove(name);
+                        }
+                    }
+                }
+            }
+        }
+
+        // "If currentColor is the specified value of the color property, it's treated as if the
+        // specified value was inherit."
+        if style.color == Some(ColorValue::CurrentColor) {
+            style.color = None;
+            keywords.insert("color", CssWideKeyword::Inherit);
+        }
+
+        style.defaulting(&keywords, parent_style);
+        style.compute_values(parent_style, device);
+        style
+    }
+
+    /// Returns true if all the supported longhands in `declarations` have valid values. A shorthand
+    /// declaration is invalid as a whole if any of its longhands is invalid.
+    fn is_valid(declarations: &[Declaration]) -> bool {
+        declarations.iter().all(|declaration| {
+            property_definition(&declaration.property).is_none()
+                || CssWideKeyword::from_component_values(&declaration.value).is_some()
+                || Self::new()
+                    .apply_declaration(&declaration.property, &declaration.value)
+                    .is_ok()
+        })
+    }
+
+    /// https://www.w3.org/TR/css-variables-1/#defining-variables
+    /// Cascades the custom properties declared in `declarations` over the ones inherited from the
+    /// parent and substitutes var() in their values.
+    fn cascade_custom_properties(
+        declarations: &[(CascadeOrigin, Declaration)],
+        parent_style: Option<&ComputedStyle>,
+    ) -> CustomProperties {
+        let inherited = parent_style
+            .map(|parent_style| parent_style.custom_properties.clone())
+            .unwrap_or_default();
+        let mut specified = inherited.clone();
+        let mut user_agent_cascade = None;
+
+        for (origin, declaration) in declarations {
+            let name = &declaration.property;
+            if !is_custom_property_name(name) {
+                continue;
+            }
+            if *origin == CascadeOrigin::Author && user_agent_cascade.is_none() {
+                user_agent_cascade = Some(specified.clone());
+            }
+
+            let value = match CssWideKeyword::from_component_values(&declaration.value) {
+                // "The initial value of a custom property is a guaranteed-invalid value."
+                Some(CssWideKeyword::Initial) => None,
+                // Custom properties are inherited, so unset behaves as inherit.
+                Some(CssWideKeyword::Inherit) | Some(CssWideKeyword::Unset) => {
+                    inherited.get(name).cloned()
+                }
+                Some(CssWideKeyword::Revert) => match &user_agent_cascade {
+                    Some(user_agent_cascade) => user_agent_cascade.get(name).cloned(),
+                    None => inherited.get(name).cloned(),
+                },
+                None => Some(declaration.value.clone()),
+            };
+            match value {
+                Some(value) => specified.insert(name.clone(), value),
+                None => specified.remove(name),
+            };
+        }
+
+        compute_custom_properties(specified)
+    }
+
+    /// Returns the computed value of a custom property, or None if it has the guaranteed-invalid
+    /// value.
+    pub fn custom_property(&self, name: &str) -> Option<&[ComponentValue]> {
+        self.custom_properties
+            .get(name)
+            .map(|value| value.as_slice())
+    }
+
+    /// https://www.w3.org/TR/css-cascade-4/#defaulting
+    /// Gives a value to every property that has no declared value or that is set to a CSS-wide
+    /// keyword. Inherited properties take the computed value of the parent and the others take
+    /// their initial values.
+    fn defaulting(
+        &mut self,
+        keywords: &BTreeMap<&'static str, CssWideKeyword>,
+        parent_style: Option<&ComputedStyle>,
+    ) {
+        for definition in PROPERTIES {
+            let inherit = match keywords.get(definition.name) {
+                Some(CssWideKeyword::Inherit) => true,
+                Some(CssWideKeyword::Initial) => false,
+                // "revert" in the user-agent origin rolls back to the user origin, which doesn't
+                // exist here, so it behaves as unset.
+                Some(CssWideKeyword::Unset) | Some(CssWideKeyword::Revert) => definition.inherited,
+                None if self.has_property(definition.name) => continue,
+                None => definition.inherited,
+            };
+            match parent_style {
+                Some(parent_style) if inherit => self.copy_property(definition.name, parent_style),
+                // The root element inherits initial values.
+                _ => self
+                    .apply_declaration(definition.name, &definition.initial_value())
+                    .expect("initial value should be valid"),
+            }
+        }
+    }
+
+    fn has_property(&self, name: &str) -> bool {
+        match_longhand!(name, has_field!(self), false)
+    }
+
+    fn clear_property(&mut self, name: &str) {
+        match_longhand!(name, clear_field!(self), ())
+    }
+
+    fn copy_property(&mut self, name: &str, from: &ComputedStyle) {
+        match_longhand!(name, copy_field!(self, from), ())
+    }
+
+    /// Parses a declared value and sets it as the specified value of the property. Returns an
+    /// error if the property is not supported or the value is invalid, and then the declaration
+    /// is ignored.
+    /// https://www.w3.org/TR/css-syntax-3/#error-handling
+    fn apply_declaration(&mut self, property: &str, value: &[ComponentValue]) -> Result<(), Error> {
+        match property {
+            "background-color" => {
+                self.set_background_color(ColorValue::from_component_values(value)?)
+            }
+            "background-image" => {
+                self.set_background_image(BackgroundImage::from_component_values(value)?)
+            }
+            "color" => self.set_color(ColorValue::from_component_values(value)?),
+            "display" => self.set_display(keyword(value, DisplayType::from_str_display)?),
+            "font-size" => self.set_font_size(FontSize::from_component_values(value)?),
+            "font-weight" => self.set_font_weight(FontWeight::from_component_values(value)?),
+            "font-style" => self.set_font_style(keyword(value, FontStyle::from_str_font_style)?),
+            "font-family" => self.set_font_family(FontFamily::from_component_values(value)?),
+            "line-height" => self.set_line_height(LineHeight::from_component_values(value)?),
+            "text-decoration-line" => {
+                self.set_text_decoration(keyword(value, TextDecoration::from_str_text_decoration)?)
+            }
+            "text-align" => self.set_text_align(keyword(value, TextAlign::from_str_text_align)?),
+            "white-space" => {
+                self.set_white_space(keyword(value, WhiteSpace::from_str_white_space)?)
+            }
+            "text-indent" => self.set_text_indent(LengthPercentage::from_component_values(value)?),
+            "letter-spacing" => self.set_letter_spacing(spacing_from_component_values(value)?),
+            "word-spacing" => self.set_word_spacing(spacing_from_component_values(value)?),
+            "text-transform" => {
+                self.set_text_transform(keyword(value, TextTransform::from_str_text_transform)?)
+            }
+            "word-break" => self.set_word_break(keyword(value, WordBreak::from_str_word_break)?),
+            "overflow-wrap" => {
+                self.set_overflow_wrap(keyword(value, OverflowWrap::from_str_overflow_wrap)?)
+            }
+            "line-break" => self.set_line_break(keyword(value, LineBreak::from_str_line_break)?),
+            "vertical-align" => {
+                self.set_vertical_align(VerticalAlign::from_component_values(value)?)
+            }
+            "width" => self.set_width(size_from_component_values(value)?),
+            "height" => self.set_height(size_from_component_values(value)?),
+            "box-sizing" => self.set_box_sizing(keyword(value, BoxSizing::from_str_box_sizing)?),
+            "overflow-x" => self.set_overflow_x(keyword(value, Overflow::from_str_overflow)?),
+            "overflow-y" => self.set_overflow_y(keyword(value, Overflow::from_str_overflow)?),
+            "position" => self.set_position(keyword(value, Position::from_str_position)?),
+            "top" | "right" | "bottom" | "left" => self.set_inset(
+                Side::from_str_side(property)?,
+                LengthPercentageOrAuto::from_component_values(value)?,
+            ),
+            "z-index" => self.set_z_index(ZIndex::from_component_values(value)?),
+            "float" => self.set_float(keyword(value, Float::from_str_float)?),
+            "clear" => self.set_clear(keyword(value, Clear::from_str_clear)?),
+            "flex-direction" => {
+                self.set_flex_direction(keyword(value, FlexDirection::from_str_flex_direction)?)
+            }
+            "flex-wrap" => self.set_flex_wrap(keyword(value, FlexWrap::from_str_flex_wrap)?),
+            "justify-content" => {
+                self.set_justify_content(keyword(value, JustifyContent::from_str_justify_content)?)
+            }
+            "align-items" => {
+                self.set_align_items(keyword(value, AlignItems::from_str_align_items)?)
+            }
+            "align-self" => self.set_align_self(keyword(value, AlignItems::from_str_align_self)?),
+            "flex-grow" => self.set_flex_grow(flex_factor_from_component_values(value)?),
+            "flex-shrink" => self.set_flex_shrink(flex_factor_from_component_values(value)?),
+            "flex-basis" => self.set_flex_basis(FlexBasis::from_component_values(value)?),
+            "row-gap" => self.gap[0] = Some(gap_from_component_values(value)?),
+            "column-gap" => self.gap[1] = Some(gap_from_component_values(value)?),
+            "order" => self.set_order(order_from_component_values(value)?),
+            "grid-template-columns" => {
+                self.set_grid_template_columns(TrackList::from_component_values(value)?)
+            }
+            "grid-template-rows" => {
+                self.set_grid_template_rows(TrackList::from_component_values(value)?)
+            }
+            "grid-template-areas" => {
+                self.set_grid_template_areas(GridTemplateAreas::from_component_values(value)?)
+            }
+            "grid-auto-columns" => {
+                self.set_grid_auto_columns(track_sizes_from_component_values(value)?)
+            }
+            "grid-auto-rows" => self.set_grid_auto_rows(track_sizes_from_component_values(value)?),
+            "grid-auto-flow" => {
+                self.set_grid_auto_flow(GridAutoFlow::from_component_values(value)?)
+            }
+            "grid-row-start" => self.grid_line[0] = Some(GridLine::from_component_values(value)?),
+            "grid-column-start" => {
+                self.grid_line[1] = Some(GridLine::from_component_values(value)?)
+            }
+            "grid-row-end" => self.grid_line[2] = Some(GridLine::from_component_values(value)?),
+            "grid-column-end" => self.grid_line[3] = Some(GridLine::from_component_values(value)?),
+            "list-style-type" => {
+                self.set_list_style_type(ListStyleType::from_component_values(value)?)
+            }
+            "list-style-position" => self.set_list_style_position(keyword(
+                value,
+                ListStylePosition::from_str_list_style_position,
+            )?),
+            "counter-reset" => {
+                self.counters[0] = Some(CounterChange::from_component_values(value, true)?)
+            }
+            "counter-increment" => {
+                self.counters[1] = Some(CounterChange::from_component_values(value, false)?)
+            }
+            "counter-set" => {
+                self.counters[2] = Some(CounterChange::from_component_values(value, false)?)
+            }
+            "content" => self.set_content(Content::from_component_values(value)?),
+            "quotes" => self.set_quotes(Quotes::from_component_values(value)?),
+            "opacity" => self.set_opacity(opacity_from_component_values(value)?),
+            "transform" => self.set_transform(TransformFunction::from_component_values(value)?),
+            property => {
+                if let Some(side) = property.strip_prefix("margin-") {
+                    let side = Side::from_str_side(side)?;
+                    self.set_margin(side, LengthPercentageOrAuto::from_component_values(value)?);
+                } else if let Some(side) = property.strip_prefix("padding-") {
+                    let side = Side::from_str_side(side)?;
+                    self.set_padding(side, padding_from_component_values(value)?);
+                } else if let Some(corner) = property
+                    .strip_prefix("border-")
+                    .and_then(|p| p.strip_suffix("-radius"))
+                {
+                    let corner = Corner::from_str_corner(corner)?;
+                    self.set_border_radius(corner, BorderRadius::from_component_values(value)?);
+                } else if let Some(rest) = property.strip_prefix("border-") {
+                    self.apply_border_side(rest, value)?;
+                } else {
+                    return Err(Error::UnexpectedInput(format!(
+                        "property {:?} is not supported yet",
+                        property
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles `border-<side>-<width|style|color>`.
+    fn apply_border_side(&mut self, property: &str, value: &[ComponentValue]) -> Result<(), Error> {
+        let (side, longhand) = property
+            .split_once('-')
+            .ok_or(Error::UnexpectedInput(format!(
+                "property border-{} is not supported",
+                property
+            )))?;
+        let side = Side::from_str_side(side)?;
+
+        match longhand {
+            "width" => self.set_border_width(
+                side,
+                single_token(value, border_width_from_component_value)?,
+            ),
+            "style" => {
+                self.set_border_style(side, keyword(value, BorderStyle::from_str_border_style)?)
+            }
+            "color" => self.set_border_color(side, ColorValue::from_component_values(value)?),
+            _ => {
+                return Err(Error::UnexpectedInput(format!(
+                    "property border-{} is not supported",
+                    property
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// https://www.w3.org/TR/css-cascade-4/#computed
+    /// Replaces currentColor with the color property and makes lengths absolute. font-size and
+    /// font-weight are resolved first because they can be relative to the parent, and the other
+    /// font-relative lengths are resolved against the font size of this element. The root element
+    /// is not part of the layout tree, so rem is resolved against the initial font size.
+    fn compute_values(&mut self, parent_style: Option<&ComputedStyle>, device: &Device) {
+        let parent_font_size = parent_style.map_or(MEDIUM_FONT_SIZE, |p| p.font_size());
+        let font_size = self
+            .font_size
+            .as_ref()
+            .map_or(MEDIUM_FONT_SIZE, |f| f.compute(parent_font_size, device));
+        self.font_size = Some(FontSize::LengthPercentage(LengthPercentage::Length(
+            Length::px(font_size),
+        )));
+        let parent_weight = parent_style.map_or(400.0, |p| p.font_weight() as f64);
+        self.font_weight = self
+            .font_weight
+            .map(|w| FontWeight::Absolute(w.compute(parent_weight)));
+
+        let context = ComputeContext::new(font_size, MEDIUM_FONT_SIZE, device);
+        self.line_height = self.line_height.as_ref().map(|l| match l {
+            LineHeight::LengthPercentage(l) => LineHeight::LengthPercentage(
+                LengthPercentage::Length(Length::px(l.compute_with_basis(&context, font_size))),
+            ),
+            l => l.clone(),
+        });
+        let current_color = self.color();
+        self.background_color = self
+            .background_color
+            .as_ref()
+            .map(|c| ColorValue::Color(c.resolve(&current_color)));
+        self.text_indent = self.text_indent.as_ref().map(|t| t.compute(&context));
+        self.vertical_align = self.vertical_align.as_ref().map(|v| match v {
+            VerticalAlign::LengthPercentage(l) => {
+                VerticalAlign::LengthPercentage(l.compute(&context))
+            }
+            v => v.clone(),
+        });
+        self.letter_spacing = self.letter_spacing.map(|l| l.compute(&context));
+        self.word_spacing = self.word_spacing.map(|w| w.compute(&context));
+        self.height = self.height.as_ref().map(|h| h.compute(&context));
+        self.width = self.width.as_ref().map(|w| w.compute(&context));
+
+        // https://www.w3.org/TR/CSS2/visuren.html#dis-pos-flo
+        // "Otherwise, if 'position' has the value 'absolute' or 'fixed', the box is absolutely
+        // positioned, the computed value of 'float' is set to 'none', and display is set
+        // according to the table below. Otherwise, if 'float' has a value other than 'none',
+        // the box is floated and 'display' is set according to the table below." An inline box
+        // becomes a block box.
+        // https://www.w3.org/TR/css-flexbox-1/#flex-items
+        // "The display value of a flex item is blockified", and "float and clear do not create
+        // floating or clearance of flex item". So are grid items.
+        // https://www.w3.org/TR/css-grid-1/#grid-item-display
+        let flex_item = parent_style
+            .is_some_and(|p| p.display().is_flex_container() || p.display().is_grid_container());
+        if self.position.is_some_and(|p| p.is_out_of_flow()) || flex_item {
+            self.float = self.float.map(|_| Float::None);
+        }
+        if self.position.is_some_and(|p| p.is_out_of_flow())
+            || self.float.is_some_and(|f| f != Float::None)
+            || flex_item
+        {
+            self.display = self.display.map(|d| d.blockify());
+        }
+        self.flex_basis = self.flex_basis.as_ref().map(|b| b.compute(&context));
+        self.grid_template_columns = self
+            .grid_template_columns
+            .as_ref()
+            .map(|t| t.compute(&context));
+        self.grid_template_rows = self
+            .grid_template_rows
+            .as_ref()
+            .map(|t| t.compute(&context));
+        self.grid_auto_columns = self
+            .grid_auto_columns
+            .as_ref()
+            .map(|t| t.iter().map(|s| s.compute(&context)).collect());
+        self.grid_auto_rows = self
+            .grid_auto_rows
+            .as_ref()
+            .map(|t| t.iter().map(|s| s.compute(&context)).collect());
+
+        for i in 0..4 {
+            self.inset[i] = self.inset[i].as_ref().map(|inset| inset.compute(&context));
+            if i < 2 {
+                self.gap[i] = self.gap[i].as_ref().map(|gap| gap.compute(&context));
+            }
+            self.margin[i] = self.margin[i].as_ref().map(|m| m.compute(&context));
+            self.padding[i] = self.padding[i].as_ref().map(|p| p.compute(&context));
+            self.border_width[i] = self.border_width[i].map(|w| w.compute(&context));
+            self.border_color[i] = self.border_color[i]
+                .as_ref()
+                .map(|c| ColorValue::Color(c.resolve(&current_color)));
+            self.border_radius[i] = self.border_radius[i].as_ref().map(|r| r.compute(&context));
+        }
+        self.transform = self
+            .transform
+            .as_ref()
+            .map(|t| t.iter().map(|f| f.compute(&context)).collect());
+    }
+
+    pub fn display(&self) -> DisplayType {
+        self.display
+            .expect("failed to access Css property: display")
+    }
+
+    /// The computed font size in px.
+    pub fn font_size(&self) -> f64 {
+        match &self.font_size {
+            Some(FontSize::LengthPercentage(LengthPercentage::Length(size))) => size.value(),
+            Some(size) => size.compute(MEDIUM_FONT_SIZE, &Device::default()),
+            None => panic!("failed to access CSS property: font_size"),
+        }
+    }
+
+    /// The computed font weight, rounded to an integer.
+    pub fn font_weight(&self) -> u16 {
+        match self.font_weight {
+            Some(weight) => round(weight.compute(400.0)) as u16,
+            None => panic!("failed to access CSS property: font_weight"),
+        }
+    }
+
+    pub fn font_style(&self) -> FontStyle {
+        self.font_style
+            .expect("failed to access CSS property: font_style")
+    }
+
+    pub fn font_family(&self) -> Vec<FontFamily> {
+        self.font_family
+            .clone()
+            .expect("failed to access CSS property: font_family")
+    }
+
+    /// https://www.w3.org/TR/css-inline-3/#line-height-property
+    /// The used line height in px.
+    pub fn line_height(&self) -> i64 {
+        let font_size = self.font_size();
+        match &self.line_height {
+            Some(LineHeight::Normal) => round(font_size * NORMAL_LINE_HEIGHT),
+            Some(LineHeight::Number(n)) => round(font_size * n),
+            Some(LineHeight::LengthPercentage(l)) => {
+                round(l.compute_with_basis(&ComputeContext::default(), font_size))
+            }
+            None => panic!("failed to access CSS property: line_height"),
+        }
+    }
+
+    /// The font that text in this element is drawn with.
+    pub fn font(&self) -> Font {
+        Font::new(
+            self.font_family(),
+            round(self.font_size()),
+            self.font_weight(),
+            self.font_style(),
+        )
+    }
+
+    pub fn text_decoration(&self) -> TextDecoration {
+        self.text_decoration
+            .expect("failed to access Css property: text_decoration")
+    }
+
+    pub fn text_align(&self) -> TextAlign {
+        self.text_align
+            .expect("failed to access CSS property: text_align")
+    }
+
+    pub fn white_space(&self) -> WhiteSpace {
+        self.white_space
+            .expect("failed to access CSS property: white_space")
+    }
+
+    pub fn word_break(&self) -> WordBreak {
+        self.word_break
+            .expect("failed to access CSS property: word_break")
+    }
+
+    pub fn overflow_wrap(&self) -> OverflowWrap {
+        self.overflow_wrap
+            .expect("failed to access CSS property: overflow_wrap")
+    }
+
+    pub fn line_break(&self) -> LineBreak {
+        self.line_break
+            .expect("failed to access CSS property: line_break")
+    }
+
+    /// https://www.w3.org/TR/css-text-3/#text-indent-property
+    /// "Percentages are relative to the block container's own inline size."
+    pub fn text_indent(&self) -> LengthPercentage {
+        self.text_indent
+            .clone()
+            .expect("failed to access CSS property: text_indent")
+    }
+
+    /// The used letter spacing in px.
+    pub fn letter_spacing(&self) -> i64 {
+        self.letter_spacing
+            .expect("failed to access CSS property: letter_spacing")
+            .to_px()
+    }
+
+    /// The used word spacing in px.
+    pub fn word_spacing(&self) -> i64 {
+        self.word_spacing
+            .expect("failed to access CSS property: word_spacing")
+            .to_px()
+    }
+
+    pub fn text_transform(&self) -> TextTransform {
+        self.text_transform
+            .expect("failed to access CSS property: text_transform")
+    }
+
+    pub fn vertical_align(&self) -> VerticalAlign {
+        self.vertical_align
+            .clone()
+            .expect("failed to access CSS property: vertical_align")
+    }
+
+    pub fn set_height(&mut self, height: LengthPercentageOrAuto) {
+        self.height = Some(height);
+    }
+
+    pub fn height(&self) -> LengthPercentageOrAuto {
+        self.height
+            .clone()
+            .expect("failed to access Css property: height")
+    }
+
+    pub fn set_width(&mut self, width: LengthPercentageOrAuto) {
+        self.width = Some(width);
+    }
+
+    pub fn width(&self) -> LengthPercentageOrAuto {
+        self.width
+            .clone()
+            .expect("failed to access Css property: width")
+    }
+
+    pub fn set_box_sizing(&mut self, box_sizing: BoxSizing) {
+        self.box_sizing = Some(box_sizing);
+    }
+
+    pub fn box_sizing(&self) -> BoxSizing {
+        self.box_sizing
+            .expect("failed to access Css property: box_sizing")
+    }
+
+    pub fn set_overflow_x(&mut self, overflow: Overflow) {
+        self.overflow[0] = Some(overflow);
+    }
+
+    pub fn set_overflow_y(&mut self, overflow: Overflow) {
+        self.overflow[1] = Some(overflow);
+    }
+
+    pub fn set_position(&mut self, position: Position) {
+        self.position = Some(position);
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+            .expect("failed to access Css property: position")
+    }
+
+    pub fn set_inset(&mut self, side: Side, inset: LengthPercentageOrAuto) {
+        self.inset[side.index()] = Some(inset);
+    }
+
+    /// The value of top, right, bottom or left.
+    pub fn inset(&self, side: Side) -> LengthPercentageOrAuto {
+        self.inset[side.index()]
+            .clone()
+            .expect("failed to access Css property: inset")
+    }
+
+    pub fn set_z_index(&mut self, z_index: ZIndex) {
+        self.z_index = Some(z_index);
+    }
+
+    pub fn z_index(&self) -> ZIndex {
+        self.z_index
+            .expect("failed to access Css property: z_index")
+    }
+
+    pub fn set_float(&mut self, float: Float) {
+        self.float = Some(float);
+    }
+
+    pub fn float(&self) -> Float {
+        self.float.expect("failed to access Css property: float")
+    }
+
+    pub fn set_clear(&mut self, clear: Clear) {
+        self.clear = Some(clear);
+    }
+
+    pub fn clear(&self) -> Clear {
+        self.clear.expect("failed to access Css property: clear")
+    }
+
+    pub fn set_flex_direction(&mut self, flex_direction: FlexDirection) {
+        self.flex_direction = Some(flex_direction);
+    }
+
+    pub fn flex_direction(&self) -> FlexDirection {
+        self.flex_direction
+            .expect("failed to access Css property: flex_direction")
+    }
+
+    pub fn set_flex_wrap(&mut self, flex_wrap: FlexWrap) {
+        self.flex_wrap = Some(flex_wrap);
+    }
+
+    pub fn flex_wrap(&self) -> FlexWrap {
+        self.flex_wrap
+            .expect("failed to access Css property: flex_wrap")
+    }
+
+    pub fn set_justify_content(&mut self, justify_content: JustifyContent) {
+        self.justify_content = Some(justify_content);
+    }
+
+    pub fn justify_content(&self) -> JustifyContent {
+        self.justify_content
+            .expect("failed to access Css property: justify_content")
+    }
+
+    pub fn set_align_items(&mut self, align_items: AlignItems) {
+        self.align_items = Some(align_items);
+    }
+
+    pub fn align_items(&self) -> AlignItems {
+        self.align_items
+            .expect("failed to access Css property: align_items")
+    }
+
+    pub fn set_align_self(&mut self, align_self: AlignItems) {
+        self.align_self = Some(align_self);
+    }
+
+    pub fn align_self(&self) -> AlignItems {
+        self.align_self
+            .expect("failed to access Css property: align_self")
+    }
+
+    pub fn set_flex_grow(&mut self, flex_grow: f64) {
+        self.flex_grow = Some(flex_grow);
+    }
+
+    pub fn flex_grow(&self) -> f64 {
+        self.flex_grow
+            .expect("failed to access Css property: flex_grow")
+    }
+
+    pub fn set_flex_shrink(&mut self, flex_shrink: f64) {
+        self.flex_shrink = Some(flex_shrink);
+    }
+
+    pub fn flex_shrink(&self) -> f64 {
+        self.flex_shrink
+            .expect("failed to access Css property: flex_shrink")
+    }
+
+    pub fn set_flex_basis(&mut self, flex_basis: FlexBasis) {
+        self.flex_basis = Some(flex_basis);
+    }
+
+    pub fn flex_basis(&self) -> FlexBasis {
+        self.flex_basis
+            .clone()
+            .expect("failed to access Css property: flex_basis")
+    }
+
+    /// The value of row-gap.
+    pub fn row_gap(&self) -> LengthPercentage {
+        self.gap[0]
+            .clone()
+            .expect("failed to access Css property: row_gap")
+    }
+
+    /// The value of column-gap.
+    pub fn column_gap(&self) -> LengthPercentage {
+        self.gap[1]
+            .clone()
+            .expect("failed to access Css property: column_gap")
+    }
+
+    pub fn set_order(&mut self, order: i32) {
+        self.order = Some(order);
+    }
+
+    pub fn order(&self) -> i32 {
+        self.order.expect("failed to access Css property: order")
+    }
+
+    pub fn set_grid_template_columns(&mut self, grid_template_columns: TrackList) {
+        self.grid_template_columns = Some(grid_template_columns);
+    }
+
+    pub fn grid_template_columns(&self) -> TrackList {
+        self.grid_template_columns
+            .clone()
+            .expect("failed to access Css property: grid_template_columns")
+    }
+
+    pub fn set_grid_template_rows(&mut self, grid_template_rows: TrackList) {
+        self.grid_template_rows = Some(grid_template_rows);
+    }
+
+    pub fn grid_template_rows(&self) -> TrackList {
+        self.grid_template_rows
+            .clone()
+            .expect("failed to access Css property: grid_template_rows")
+    }
+
+    pub fn set_grid_template_areas(&mut self, grid_template_areas: GridTemplateAreas) {
+        self.grid_template_areas = Some(grid_template_areas);
+    }
+
+    pub fn grid_template_areas(&self) -> GridTemplateAreas {
+        self.grid_template_areas
+            .clone()
+            .expect("failed to access Css property: grid_template_areas")
+    }
+
+    pub fn set_grid_auto_columns(&mut self, grid_auto_columns: Vec<TrackSize>) {
+        self.grid_auto_columns = Some(grid_auto_columns);
+    }
+
+    pub fn grid_auto_columns(&self) -> Vec<TrackSize> {
+        self.grid_auto_columns
+            .clone()
+            .expect("failed to access Css property: grid_auto_columns")
+    }
+
+    pub fn set_grid_auto_rows(&mut self, grid_auto_rows: Vec<TrackSize>) {
+        self.grid_auto_rows = Some(grid_auto_rows);
+    }
+
+    pub fn grid_auto_rows(&self) -> Vec<TrackSize> {
+        self.grid_auto_rows
+            .clone()
+            .expect("failed to access Css property: grid_auto_rows")
+    }
+
+    pub fn set_grid_auto_flow(&mut self, grid_auto_flow: GridAutoFlow) {
+        self.grid_auto_flow = Some(grid_auto_flow);
+    }
+
+    pub fn grid_auto_flow(&self) -> GridAutoFlow {
+        self.grid_auto_flow
+            .expect("failed to access Css property: grid_auto_flow")
+    }
+
+    /// The values of grid-row-start and grid-row-end.
+    pub fn grid_row(&self) -> (GridLine, GridLine) {
+        (
+            self.grid_line[0]
+                .clone()
+                .expect("failed to access Css property: grid_row_start"),
+            self.grid_line[2]
+                .clone()
+                .expect("failed to access Css property: grid_row_end"),
+        )
+    }
+
+    /// The values of grid-column-start and grid-column-end.
+    pub fn grid_column(&self) -> (GridLine, GridLine) {
+        (
+            self.grid_line[1]
+                .clone()
+                .expect("failed to access Css property: grid_column_start"),
+            self.grid_line[3]
+                .clone()
+                .expect("failed to access Css property: grid_column_end"),
+        )
+    }
+
+    pub fn set_list_style_type(&mut self, list_style_type: ListStyleType) {
+        self.list_style_type = Some(list_style_type);
+    }
+
+    pub fn list_style_type(&self) -> ListStyleType {
+        self.list_style_type
+            .clone()
+            .expect("failed to access Css property: list_style_type")
+    }
+
+    pub fn set_list_style_position(&mut self, list_style_position: ListStylePosition) {
+        self.list_style_position = Some(list_style_position);
+    }
+
+    pub fn list_style_position(&self) -> ListStylePosition {
+        self.list_style_position
+            .expect("failed to access Css property: list_style_position")
+    }
+
+    pub fn counter_reset(&self) -> Vec<CounterChange> {
+        self.counters[0]
+            .clone()
+            .expect("failed to access Css property: counter_reset")
+    }
+
+    pub fn counter_increment(&self) -> Vec<CounterChange> {
+        self.counters[1]
+            .clone()
+            .expect("failed to access Css property: counter_increment")
+    }
+
+    pub fn counter_set(&self) -> Vec<CounterChange> {
+        self.counters[2]
+            .clone()
+            .expect("failed to access Css property: counter_set")
+    }
+
+    pub fn set_content(&mut self, content: Content) {
+        self.content = Some(content);
+    }
+
+    pub fn content(&self) -> Content {
+        self.content
+            .clone()
+            .expect("failed to access Css property: content")
+    }
+
+    pub fn set_quotes(&mut self, quotes: Quotes) {
+        self.quotes = Some(quotes);
+    }
+
+    pub fn quotes(&self) -> Quotes {
+        self.quotes
+            .clone()
+            .expect("failed to access Css property: quotes")
+    }
+
+    /// https://www.w3.org/TR/CSS2/visuren.html#block-formatting
+    /// "Floats, absolutely positioned elements, block containers (such as inline-blocks,
+    /// table-cells, and table-captions) that are not block boxes, and block boxes with 'overflow'
+    /// other than 'visible' (except when that value has been propagated to the viewport)
+    /// establish new block formatting contexts for their contents." So does display: flow-root.
+    /// Flex and grid containers establish flex and grid formatting contexts, which are independent
+    /// of the outside in the same way.
+    pub fn establishes_block_formatting_context(&self) -> bool {
+        self.float() != Float::None
+            || self.position().is_out_of_flow()
+            || self.display() == DisplayType::FlowRoot
+            || self.display().is_flex_container()
+            || self.display().is_grid_container()
+            || self.is_scroll_container()
+    }
+
+    /// https://www.w3.org/TR/css-position-3/#position-property
+    /// https://www.w3.org/TR/CSS2/visuren.html#z-index
+    /// A positioned box with an integer z-index creates a stacking context, and so does a fixed or
+    /// sticky box whatever its z-index is, and a box that is translucent or transformed.
+    pub fn creates_stacking_context(&self) -> bool {
+        // https://www.w3.org/TR/css-color-4/#transparency
+        // "If an element with opacity less than 1 is not positioned, then it is painted on the
+        // same layer, within its parent stacking context, as positioned elements with stack level
+        // 0."
+        // https://www.w3.org/TR/css-transforms-1/#transform-rendering
+        // "Any computed value other than none for the transform property results in the creation
+        // of a stacking context."
+        if self.opacity() < 1.0 || !self.transform().is_empty() {
+            return true;
+        }
+        match self.position() {
+            Position::Static => false,
+            Position::Fixed | Position::Sticky => true,
+            Position::Relative | Position::Absolute => self.z_index() != ZIndex::Auto,
+        }
+    }
+
+    /// https://www.w3.org/TR/CSS2/visuren.html#layers
+    /// The stack level of a box in its stacking context. z-index only applies to positioned
+    /// boxes, so the other boxes that form a layer are at level 0.
+    pub fn stack_level(&self) -> i32 {
+        match self.position() {
+            Position::Static => 0,
+            _ => self.z_index().level(),
+        }
+    }
+
+    /// Returns true if the box is painted as a layer of its stacking context instead of in flow.
+    pub fn is_layer(&self) -> bool {
+        self.position().is_positioned() || self.creates_stacking_context()
+    }
+
+    /// Returns how much of the rendering of a box in this style is out of date in `other`. The
+    /// display, the generated content, the counters and the background images decide the boxes,
+    /// and the colors and the visual effects are only painted. Custom properties are only used
+    /// through the other properties.
+    pub fn difference(&self, other: &ComputedStyle) -> StyleDifference {
+        if self == other {
+            return StyleDifference::Equal;
+        }
+        if self.display != other.display
+            || self.content != other.content
+            || self.quotes != other.quotes
+            || self.counters != other.counters
+            || self.list_style_type != other.list_style_type
+            || self.list_style_position != other.list_style_position
+            || self.background_image != other.background_image
+        {
+            return StyleDifference::Rebuild;
+        }
+        if self.without_paint_properties() == other.without_paint_properties() {
+            StyleDifference::Paint
+        } else {
+            StyleDifference::Layout
+        }
+    }
+
+    fn without_paint_properties(&self) -> Self {
+        Self {
+            background_color: None,
+            color: None,
+            text_decoration: None,
+            border_color: [None, None, None, None],
+            border_radius: [None, None, None, None],
+            opacity: None,
+            transform: None,
+            z_index: None,
+            custom_properties: CustomProperties::default(),
+            ..self.clone()
+        }
+    }
+
+    /// https://www.w3.org/TR/css-overflow-3/#overflow-control
+    /// "The visible/clip values of overflow compute to auto/hidden (respectively) if one of
+    /// overflow-x or overflow-y is neither visible nor clip."
+    fn computed_overflow(&self, index: usize) -> Overflow {
+        let overflow = self.overflow[index].expect("failed to access Css property: overflow");
+        let other = self.overflow[1 - index].expect("failed to access Css property: overflow");
+        match overflow {
+            Overflow::Visible if other.is_scrollable() => Overflow::Auto,
+            Overflow::Clip if other.is_scrollable() => Overflow::Hidden,
+            overflow => overflow,
+        }
+    }
+
+    pub fn overflow_x(&self) -> Overflow {
+        self.computed_overflow(0)
+    }
+
+    pub fn overflow_y(&self) -> Overflow {
+        self.computed_overflow(1)
+    }
+
+    /// https://www.w3.org/TR/css-overflow-3/#scroll-container
+    pub fn is_scroll_container(&self) -> bool {
+        self.overflow_x().is_scrollable() || self.overflow_y().is_scrollable()
+    }
+
+    /// Whether the content is clipped to the padding box in either axis.
+    pub fn clips_content(&self) -> bool {
+        self.overflow_x() != Overflow::Visible || self.overflow_y() != Overflow::Visible
+    }
+
+    pub fn set_margin(&mut self, side: Side, margin: LengthPercentageOrAuto) {
+        self.margin[side.index()] = Some(margin);
+    }
+
+    pub fn margin(&self, side: Side) -> LengthPercentageOrAuto {
+        self.margin[side.index()]
+            .clone()
+            .expect("failed to access Css property: margin")
+    }
+
+    pub fn set_padding(&mut self, side: Side, padding: LengthPercentage) {
+        self.padding[side.index()] = Some(padding);
+    }
+
+    pub fn padding(&self, side: Side) -> LengthPercentage {
+        self.padding[side.index()]
+            .clone()
+            .expect("failed to access Css property: padding")
+    }
+
+    pub fn set_border_width(&mut self, side: Side, width: Length) {
+        self.border_width[side.index()] = Some(width);
+    }
+
+    /// https://www.w3.org/TR/css-backgrounds-3/#border-width
+    /// "Computed value: absolute length, snapped as a border width; zero if the border style is
+    /// none or hidden"
+    pub fn border_width(&self, side: Side) -> i64 {
+        if !self.border_style(side).is_visible() {
+            return 0;
+        }
+        self.border_width[side.index()]
+            .expect("failed to access Css property: border_width")
+            .to_px()
+    }
+
+    pub fn set_border_style(&mut self, side: Side, style: BorderStyle) {
+        self.border_style[side.index()] = Some(style);
+    }
+
+    pub fn border_style(&self, side: Side) -> BorderStyle {
+        self.border_style[side.index()].expect("failed to access Css property: border_style")
+    }
+
+    pub fn set_border_color(&mut self, side: Side, color: ColorValue) {
+        self.border_color[side.index()] = Some(color);
+    }
+
+    pub fn border_color(&self, side: Side) -> Color {
+        self.border_color[side.index()]
+            .as_ref()
+            .and_then(ColorValue::computed)
+            .expect("failed to access Css property: border_color")
+    }
+
+    pub fn has_border(&self) -> bool {
+        Side::ALL.iter().any(|side| self.border_width(*side) > 0)
+    }
+
+    pub fn set_border_radius(&mut self, corner: Corner, radius: BorderRadius) {
+        self.border_radius[corner.index()] = Some(radius);
+    }
+
+    pub fn border_radius(&self, corner: Corner) -> BorderRadius {
+        self.border_radius[corner.index()]
+            .clone()
+            .expect("failed to access Css property: border_radius")
+    }
+
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.opacity = Some(opacity);
+    }
+
+    pub fn opacity(&self) -> f64 {
+        self.opacity
+            .expect("failed to access Css property: opacity")
+    }
+
+    pub fn set_transform(&mut self, transform: Vec<TransformFunction>) {
+        self.transform = Some(transform);
+    }
+
+    pub fn transform(&self) -> Vec<TransformFunction> {
+        self.transform
+            .clone()
+            .expect("failed to access Css property: transform")
+    }
+}
+
+/// https://www.w3.org/TR/css-color-4/#named-colors
+static NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xF0F8FF),
+    ("antiquewhite", 0xFAEBD7),
+    ("aqua", 0x00FFFF),
+    ("aquamarine", 0x7FFFD4),
+    ("azure", 0xF0FFFF),
+    ("beige", 0xF5F5DC),
+    ("bisque", 0xFFE4C4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xFFEBCD),
+    ("blue", 0x0000FF),
+    ("blueviolet", 0x8A2BE2),
+    ("brown", 0xA52A2A),
+    ("burlywood", 0xDEB887),
+    ("cadetblue", 0x5F9EA0),
+    ("chartreuse", 0x7FFF00),
+    ("chocolate", 0xD2691E),
+    ("coral", 0xFF7F50),
+    ("cornflowerblue", 0x6495ED),
+    ("cornsilk", 0xFFF8DC),
+    ("crimson", 0xDC143C),
+    ("cyan", 0x00FFFF),
+    ("darkblue", 0x00008B),
+    ("darkcyan", 0x008B8B),
+    ("darkgoldenrod", 0xB8860B),
+    ("darkgray", 0xA9A9A9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xA9A9A9),
+    ("darkkhaki", 0xBDB76B),
+    ("darkmagenta", 0x8B008B),
+    ("darkolivegreen", 0x556B2F),
+    ("darkorange", 0xFF8C00),
+    ("darkorchid", 0x9932CC),
+    ("darkred", 0x8B0000),
+    ("darksalmon", 0xE9967A),
+    ("darkseagreen", 0x8FBC8F),
+    ("darkslateblue", 0x483D8B),
+    ("darkslategray", 0x2F4F4F),
+    ("darkslategrey", 0x2F4F4F),
+    ("darkturquoise", 0x00CED1),
+    ("darkviolet", 0x9400D3),
+    ("deeppink", 0xFF1493),
+    ("deepskyblue", 0x00BFFF),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1E90FF),
+    ("firebrick", 0xB22222),
+    ("floralwhite", 0xFFFAF0),
+    ("forestgreen", 0x228B22),
+    ("fuchsia", 0xFF00FF),
+    ("gainsboro", 0xDCDCDC),
+    ("ghostwhite", 0xF8F8FF),
+    ("gold", 0xFFD700),
+    ("goldenrod", 0xDAA520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xADFF2F),
+    ("grey", 0x808080),
+    ("honeydew", 0xF0FFF0),
+    ("hotpink", 0xFF69B4),
+    ("indianred", 0xCD5C5C),
+    ("indigo", 0x4B0082),
+    ("ivory", 0xFFFFF0),
+    ("khaki", 0xF0E68C),
+    ("lavender", 0xE6E6FA),
+    ("lavenderblush", 0xFFF0F5),
+    ("lawngreen", 0x7CFC00),
+    ("lemonchiffon", 0xFFFACD),
+    ("lightblue", 0xADD8E6),
+    ("lightcoral", 0xF08080),
+    ("lightcyan", 0xE0FFFF),
+    ("lightgoldenrodyellow", 0xFAFAD2),
+    ("lightgray", 0xD3D3D3),
+    ("lightgreen", 0x90EE90),
+    ("lightgrey", 0xD3D3D3),
+    ("lightpink", 0xFFB6C1),
+    ("lightsalmon", 0xFFA07A),
+    ("lightseagreen", 0x20B2AA),
+    ("lightskyblue", 0x87CEFA),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xB0C4DE),
+    ("lightyellow", 0xFFFFE0),
+    ("lime", 0x00FF00),
+    ("limegreen", 0x32CD32),
+    ("linen", 0xFAF0E6),
+    ("magenta", 0xFF00FF),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66CDAA),
+    ("mediumblue", 0x0000CD),
+    ("mediumorchid", 0xBA55D3),
+    ("mediumpurple", 0x9370DB),
+    ("mediumseagreen", 0x3CB371),
+    ("mediumslateblue", 0x7B68EE),
+    ("mediumspringgreen", 0x00FA9A),
+    ("mediumturquoise", 0x48D1CC),
+    ("mediumvioletred", 0xC71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xF5FFFA),
+    ("mistyrose", 0xFFE4E1),
+    ("moccasin", 0xFFE4B5),
+    ("navajowhite", 0xFFDEAD),
+    ("navy", 0x000080),
+    ("oldlace", 0xFDF5E6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6B8E23),
+    ("orange", 0xFFA500),
+    ("orangered", 0xFF4500),
+    ("orchid", 0xDA70D6),
+    ("palegoldenrod", 0xEEE8AA),
+    ("palegreen", 0x98FB98),
+    ("paleturquoise", 0xAFEEEE),
+    ("palevioletred", 0xDB7093),
+    ("papayawhip", 0xFFEFD5),
+    ("peachpuff", 0xFFDAB9),
+    ("peru", 0xCD853F),
+    ("pink", 0xFFC0CB),
+    ("plum", 0xDDA0DD),
+    ("powderblue", 0xB0E0E6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xFF0000),
+    ("rosybrown", 0xBC8F8F),
+    ("royalblue", 0x4169E1),
+    ("saddlebrown", 0x8B4513),
+    ("salmon", 0xFA8072),
+    ("sandybrown", 0xF4A460),
+    ("seagreen", 0x2E8B57),
+    ("seashell", 0xFFF5EE),
+    ("sienna", 0xA0522D),
+    ("silver", 0xC0C0C0),
+    ("skyblue", 0x87CEEB),
+    ("slateblue", 0x6A5ACD),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xFFFAFA),
+    ("springgreen", 0x00FF7F),
+    ("steelblue", 0x4682B4),
+    ("tan", 0xD2B48C),
+    ("teal", 0x008080),
+    ("thistle", 0xD8BFD8),
+    ("tomato", 0xFF6347),
+    ("turquoise", 0x40E0D0),
+    ("violet", 0xEE82EE),
+    ("wheat", 0xF5DEB3),
+    ("white", 0xFFFFFF),
+    ("whitesmoke", 0xF5F5F5),
+    ("yellow", 0xFFFF00),
+    ("yellowgreen", 0x9ACD32),
+];
+
+/// https://www.w3.org/TR/css-color-4/#color-type
+/// An sRGB color with an alpha channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+}
+
+/// Converts a value in [0, 1] to [0, 255].
+fn unit_to_u8(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+}
+
+impl Color {
+    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba(r, g, b, 255)
+    }
+
+    fn from_u32(rgb: u32) -> Self {
+        Self::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+
+    /// https://www.w3.org/TR/css-color-4/#named-colors
+    /// https://www.w3.org/TR/css-color-4/#transparent-color
+    pub fn from_name(name: &str) -> Result<Self, Error> {
+        let name = name.to_ascii_lowercase();
+        if name == "transparent" {
+            return Ok(Self::transparent());
+        }
+        match NAMED_COLORS.iter().find(|(n, _)| *n == name) {
+            Some((_, rgb)) => Ok(Self::from_u32(*rgb)),
+            None => Err(Error::UnexpectedInput(format!(
+                "color name {:?} is not supported",
+                name
+            ))),
+        }
+    }
+
+    /// https://www.w3.org/TR/css-color-4/#hex-notation
+    /// Accepts `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa`.
+    pub fn from_code(code: &str) -> Result<Self, Error> {
+        let invalid = || {
+            Err(Error::UnexpectedInput(format!(
+                "invalid color code {}",
+                code
+            )))
+        };
+        let hex = match code.strip_prefix('#') {
+            Some(hex) if hex.chars().all(|c| c.is_ascii_hexdigit()) => hex,
+            _ => return invalid(),
+        };
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|c| c.to_digit(16).unwrap_or(0) as u8)
+            .collect();
+        match digits.as_slice() {
+            [r, g, b] => Ok(Self::rgb(r * 17, g * 17, b * 17)),
+            [r, g, b, a] => Ok(Self::rgba(r * 17, g * 17, b * 17, a * 17)),
+            [r1, r2, g1, g2, b1, b2] => Ok(Self::rgb(r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2)),
+            [r1, r2, g1, g2, b1, b2, a1, a2] => Ok(Self::rgba(
+                r1 * 16 + r2,
+                g1 * 16 + g2,
+                b1 * 16 + b2,
+                a1 * 16 + a2,
+            )),
+            _ => invalid(),
+        }
+    }
+
+    /// Parses the arguments of a color function. Both the legacy comma-separated syntax
+    /// `rgba(255, 0, 0, 0.5)` and the modern syntax `rgb(255 0 0 / 50%)` are accepted.
+    /// Returns three channels and the alpha value in [0, 1].
+    fn parse_function_arguments(
+        arguments: &[ComponentValue],
+    ) -> Result<([ComponentValue; 3], f64), Error> {
+        let mut channels = Vec::new();
+        let mut alpha = None;
+        let mut after_slash = false;
+        for argument in arguments {
+            match argument {
+                ComponentValue::Delim(',') => {}
+                ComponentValue::Delim('/') => after_slash = true,
+                ComponentValue::CloseParenthesis => break,
+                value if channels.len() < 3 && !after_slash => channels.push(value.clone()),
+                ComponentValue::Number(n) if alpha.is_none() => alpha = Some(*n),
+                ComponentValue::Percentage(p) if alpha.is_none() => alpha = Some(p / 100.0),
+                _ => {
+                    return Err(Error::UnexpectedInput(format!(
+                        "invalid color function arguments {:?}",
+                        arguments
+                    )))
+                }
+            }
+        }
+        match <[ComponentValue; 3]>::try_from(channels) {
+            Ok(channels) => Ok((channels, alpha.unwrap_or(1.0))),
+            Err(_) => Err(Error::UnexpectedInput(format!(
+                "color functions need three channels: {:?}",
+                arguments
+            ))),
+        }
+    }
+
+    /// https://www.w3.org/TR/css-color-4/#rgb-functions
+    fn from_rgb_function(arguments: &[ComponentValue]) -> Result<Self, Error> {
+        let (channels, alpha) = Self::parse_function_arguments(arguments)?;
+        let mut rgb = [0u8; 3];
+        for (i, channel) in channels.iter().enumerate() {
+            rgb[i] = match channel {
+                ComponentValue::Number(n) => unit_to_u8(n / 255.0),
+                ComponentValue::Percentage(p) => unit_to_u8(p / 100.0),
+                _ => {
+                    return Err(Error::UnexpectedInput(format!(
+                        "invalid rgb() channel {:?}",
+                        channel
+                    )))
+                }
+            };
+        }
+        Ok(Self::rgba(rgb[0], rgb[1], rgb[2], unit_to_u8(alpha)))
+    }
+
+    /// https://www.w3.org/TR/css-color-4/#the-hsl-notation
+    fn from_hsl_function(arguments: &[ComponentValue]) -> Result<Self, Error> {
+        let (channels, alpha) = Self::parse_function_arguments(arguments)?;
+        let invalid = || {
+            Err(Error::UnexpectedInput(format!(
+                "invalid hsl() arguments {:?}",
+                arguments
+            )))
+        };
+        // https://www.w3.org/TR/css-values-4/#angles
+        let hue = match &channels[0] {
+            ComponentValue::Number(n) => *n,
+            ComponentValue::Dimension(n, unit) => match unit.to_ascii_lowercase().as_str() {
+                "deg" => *n,
+                "grad" => n * 360.0 / 400.0,
+                "rad" => n * 180.0 / core::f64::consts::PI,
+                "turn" => n * 360.0,
+                _ => return invalid(),
+            },
+            _ => return invalid(),
+        };
+        let mut percentages = [0f64; 2];
+        for (i, channel) in channels[1..].iter().enumerate() {
+            percentages[i] = match channel {
+                ComponentValue::Percentage(p) | ComponentValue::Number(p) => p / 100.0,
+                _ => return invalid(),
+            };
+        }
+        let [r, g, b] = hsl_to_rgb(hue, percentages[0], percentages[1]);
+        Ok(Self::rgba(
+            unit_to_u8(r),
+            unit_to_u8(g),
+            unit_to_u8(b),
+            unit_to_u8(alpha),
+        ))
+    }
+
+    /// Parses a `<color>` except for `currentColor`, which is handled by [`ColorValue`].
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(name)] => Self::from_name(name),
+            [ComponentValue::HashToken(code)] => Self::from_code(code),
+            [ComponentValue::Function(name), arguments @ ..] => {
+                match name.to_ascii_lowercase().as_str() {
+                    "rgb" | "rgba" => Self::from_rgb_function(arguments),
+                    "hsl" | "hsla" => Self::from_hsl_function(arguments),
+                    _ => Err(Error::UnexpectedInput(format!(
+                        "color function {:?} is not supported",
+                        name
+                    ))),
+                }
+            }
+            _ => Err(Error::UnexpectedInput(format!(
+                "color {:?} is not supported yet",
+                values
+            ))),
+        }
+    }
+
+    pub fn white() -> Self {
+        Self::rgb(255, 255, 255)
+    }
+
+    pub fn black() -> Self {
+        Self::rgb(0, 0, 0)
+    }
+
+    pub fn transparent() -> Self {
+        Self::rgba(0, 0, 0, 0)
+    }
+
+    pub fn r(&self) -> u8 {
+        self.r
+    }
+
+    pub fn g(&self) -> u8 {
+        self.g
+    }
+
+    pub fn b(&self) -> u8 {
+        self.b
+    }
+
+    pub fn alpha(&self) -> u8 {
+        self.a
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.a == 0
+    }
+
+    /// https://www.w3.org/TR/compositing-1/#porterduffcompositingoperators_srcover
+    /// Composites this color over `backdrop` and returns the result.
+    pub fn blend_over(&self, backdrop: &Color) -> Color {
+        let source_alpha = self.a as f64 / 255.0;
+        let backdrop_alpha = backdrop.a as f64 / 255.0 * (1.0 - source_alpha);
+        let alpha = source_alpha + backdrop_alpha;
+        if alpha == 0.0 {
+            return Self::transparent();
+        }
+        let blend = |s: u8, b: u8| {
+            unit_to_u8((s as f64 * source_alpha + b as f64 * backdrop_alpha) / alpha / 255.0)
+        };
+        Self::rgba(
+            blend(self.r, backdrop.r),
+            blend(self.g, backdrop.g),
+            blend(self.b, backdrop.b),
+            unit_to_u8(alpha),
+        )
+    }
+
+    /// Returns the color as 0xRRGGBB. The alpha channel is ignored, so translucent colors should
+    /// be blended with [`Color::blend_over`] first.
+    pub fn code_u32(&self) -> u32 {
+        ((self.r as u32) << 16) | ((self.g as u32) << 8) | self.b as u32
+    }
+}
+
+/// https://www.w3.org/TR/css-color-4/#hsl-to-rgb
+/// `hue` is in degrees, `saturation` and `lightness` are in [0, 1].
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [f64; 3] {
+    let mut hue = hue - 360.0 * ((hue / 360.0) as i64 as f64);
+    if hue < 0.0 {
+        hue += 360.0;
+    }
+    let saturation = saturation.clamp(0.0, 1.0);
+    let lightness = lightness.clamp(0.0, 1.0);
+
+    let f = |n: f64| {
+        let mut k = (n + hue / 30.0) - 12.0 * (((n + hue / 30.0) / 12.0) as i64 as f64);
+        if k < 0.0 {
+            k += 12.0;
+        }
+        let a = saturation * lightness.min(1.0 - lightness);
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    [f(0.0), f(8.0), f(4.0)]
+}
+
+/// https://www.w3.org/TR/css-color-4/#typedef-color
+/// A specified color. `currentColor` is kept until the style is computed, and then replaced with
+/// the value of the color property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColorValue {
+    Color(Color),
+    /// https://www.w3.org/TR/css-color-4/#currentcolor-color
+    CurrentColor,
+}
+
+impl ColorValue {
+    pub fn from_component_values(values: &[ComponentValue]) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(name)] if name.eq_ignore_ascii_case("currentcolor") => {
+                Ok(Self::CurrentColor)
+            }
+            _ => Ok(Self::Color(Color::from_component_values(values)?)),
+        }
+    }
+
+    /// Returns the color if `currentColor` has already been resolved.
+    fn computed(&self) -> Option<Color> {
+        match self {
+            Self::Color(color) => Some(color.clone()),
+            Self::CurrentColor => None,
+        }
+    }
+
+    fn resolve(&self, current_color: &Color) -> Color {
+        match self {
+            Self::Color(color) => color.clone(),
+            Self::CurrentColor => current_color.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::CssTokenizer;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn parse_color(css: &str) -> Result<ColorValue, Error> {
+        let values: Vec<ComponentValue> = CssTokenizer::new(css.to_string()).collect();
+        ColorValue::from_component_values(&values)
+    }
+
+    #[test]
+    fn test_named_colors() {
+        assert_eq!(Ok(Color::rgb(0, 128, 0)), Color::from_name("green"));
+        assert_eq!(
+            Ok(Color::rgb(102, 51, 153)),
+            Color::from_name("RebeccaPurple")
+        );
+        assert_eq!(Ok(Color::transparent()), Color::from_name("transparent"));
+        assert!(Color::from_name("unknown").is_err());
+    }
+
+    #[test]
+    fn test_hex_colors() {
+        assert_eq!(Ok(Color::rgb(0, 0, 255)), Color::from_code("#0000ff"));
+        assert_eq!(Ok(Color::rgb(255, 170, 0)), Color::from_code("#FA0"));
+        assert_eq!(Ok(Color::rgba(255, 170, 0, 136)), Color::from_code("#fa08"));
+        assert_eq!(
+            Ok(Color::rgba(18, 52, 86, 128)),
+            Color::from_code("#12345680")
+        );
+        assert!(Color::from_code("#12345").is_err());
+        assert!(Color::from_code("#ggg").is_err());
+    }
+
+    #[test]
+    fn test_color_functions() {
+        let expected = [
+            ("rgb(255, 0, 0)", Color::rgb(255, 0, 0)),
+            ("rgba(0, 0, 255, 0.5)", Color::rgba(0, 0, 255, 128)),
+            ("rgb(100% 50% 0% / 25%)", Color::rgba(255, 128, 0, 64)),
+            ("hsl(120, 100%, 50%)", Color::rgb(0, 255, 0)),
+            ("hsl(0.5turn 100% 25%)", Color::rgb(0, 128, 128)),
+            ("hsla(240deg, 100%, 50%, 0)", Color::rgba(0, 0, 255, 0)),
+            ("hsl(-120 100% 50%)", Color::rgb(0, 0, 255)),
+        ];
+        for (css, color) in expected {
+            assert_eq!(Ok(ColorValue::Color(color)), parse_color(css), "{}", css);
+        }
+        assert_eq!(Ok(ColorValue::CurrentColor), parse_color("currentColor"));
+        assert!(parse_color("rgb(1, 2)").is_err());
+        assert!(parse_color("hsl(1px, 2%, 3%)").is_err());
+    }
+
+    #[test]
+    fn test_blend_over() {
+        let half_red = Color::rgba(255, 0, 0, 128);
+        assert_eq!(
+            Color::rgb(255, 127, 127),
+            half_red.blend_over(&Color::white())
+        );
+        assert_eq!(
+            Color::white(),
+            Color::transparent().blend_over(&Color::white())
+        );
+        assert_eq!(Color::black(), Color::black().blend_over(&half_red));
+    }
+
+    #[test]
+    fn test_initial_values() {
+        let mut style = ComputedStyle::new();
+        for definition in PROPERTIES {
+            assert!(
+                style
+                    .apply_declaration(definition.name, &definition.initial_value())
+                    .is_ok(),
+                "{}",
+                definition.name
+            );
+            assert!(style.has_property(definition.name), "{}", definition.name);
+        }
+    }
+
+    #[test]
+    fn test_css_wide_keywords() {
+        let declaration = |origin, property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (origin, declaration)
+        };
+        let mut parent = ComputedStyle::compute(
+            &[
+                declaration(CascadeOrigin::Author, "color", "red"),
+                declaration(CascadeOrigin::Author, "padding", "4px"),
+            ],
+            None,
+            &Device::default(),
+        );
+        parent.set_font_size(FontSize::XLarge);
+
+        let style = ComputedStyle::compute(
+            &[
+                declaration(CascadeOrigin::UserAgent, "margin-top", "8px"),
+                declaration(CascadeOrigin::Author, "margin", "2px"),
+                declaration(CascadeOrigin::Author, "margin-top", "revert"),
+                declaration(CascadeOrigin::Author, "padding", "inherit"),
+                declaration(CascadeOrigin::Author, "padding-left", "initial"),
+                declaration(CascadeOrigin::Author, "color", "unset"),
+                declaration(CascadeOrigin::Author, "font-size", "initial"),
+                declaration(CascadeOrigin::Author, "display", "invalid"),
+            ],
+            Some(&parent),
+            &Device::default(),
+        );
+        assert_eq!(Color::rgb(255, 0, 0), style.color());
+        assert_eq!(16.0, style.font_size());
+        assert_eq!(DisplayType::Inline, style.display());
+        assert_eq!(
+            LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Length(Length::px(8.0))),
+            style.margin(Side::Top)
+        );
+        assert_eq!(
+            LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Length(Length::px(2.0))),
+            style.margin(Side::Left)
+        );
+        assert_eq!(
+            LengthPercentage::Length(Length::px(4.0)),
+            style.padding(Side::Top)
+        );
+        assert_eq!(LengthPercentage::zero(), style.padding(Side::Left));
+    }
+
+    #[test]
+    fn test_font_properties() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let parent = ComputedStyle::compute(
+            &[declaration(
+                "font",
+                "italic bold 20px/1.5 \"Noto Sans\", Helvetica Neue, serif",
+            )],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(20.0, parent.font_size());
+        assert_eq!(700, parent.font_weight());
+        assert_eq!(FontStyle::Italic, parent.font_style());
+        assert_eq!(
+            vec![
+                FontFamily::Named("Noto Sans".to_string()),
+                FontFamily::Named("Helvetica Neue".to_string()),
+                FontFamily::Generic(GenericFontFamily::Serif),
+            ],
+            parent.font_family()
+        );
+        assert_eq!(30, parent.line_height());
+
+        // Relative sizes refer to the parent, and a number line height is inherited as a number.
+        let style = ComputedStyle::compute(
+            &[
+                declaration("font-size", "150%"),
+                declaration("font-weight", "bolder"),
+            ],
+            Some(&parent),
+            &Device::default(),
+        );
+        assert_eq!(30.0, style.font_size());
+        assert_eq!(900, style.font_weight());
+        assert_eq!(45, style.line_height());
+        assert_eq!(
+            Font::new(parent.font_family(), 30, 900, FontStyle::Italic),
+            style.font()
+        );
+
+        let style = ComputedStyle::compute(
+            &[
+                declaration("font-size", "larger"),
+                declaration("line-height", "200%"),
+                declaration("font-weight", "lighter"),
+            ],
+            Some(&parent),
+            &Device::default(),
+        );
+        assert_eq!(24.0, style.font_size());
+        assert_eq!(48, style.line_height());
+        assert_eq!(400, style.font_weight());
+
+        // A percentage line height is inherited as a length.
+        let child = ComputedStyle::compute(
+            &[declaration("font-size", "x-small")],
+            Some(&style),
+            &Device::default(),
+        );
+        assert_eq!(12.0, child.font_size());
+        assert_eq!(48, child.line_height());
+
+        for invalid in ["-1px", "big"] {
+            let style = ComputedStyle::compute(
+                &[declaration("font-size", invalid)],
+                None,
+                &Device::default(),
+            );
+            assert_eq!(MEDIUM_FONT_SIZE, style.font_size());
+        }
+    }
+
+    #[test]
+    fn test_text_properties() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let parent = ComputedStyle::compute(
+            &[
+                declaration("font-size", "20px"),
+                declaration("text-align", "center"),
+                declaration("white-space", "pre-wrap"),
+                declaration("text-indent", "10%"),
+                declaration("letter-spacing", "0.1em"),
+                declaration("word-spacing", "-2px"),
+                declaration("text-transform", "uppercase"),
+                declaration("word-break", "keep-all"),
+                declaration("overflow-wrap", "anywhere"),
+                declaration("line-break", "strict"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(TextAlign::Center, parent.text_align());
+        assert_eq!(WhiteSpace::PreWrap, parent.white_space());
+        assert_eq!(40, parent.text_indent().resolve(400));
+        assert_eq!(2, parent.letter_spacing());
+        assert_eq!(-2, parent.word_spacing());
+        assert_eq!(TextTransform::Uppercase, parent.text_transform());
+        assert_eq!(WordBreak::KeepAll, parent.word_break());
+        assert_eq!(OverflowWrap::Anywhere, parent.overflow_wrap());
+        assert_eq!(LineBreak::Strict, parent.line_break());
+
+        // Text properties are inherited, and em lengths are inherited as absolute lengths.
+        let child = ComputedStyle::compute(
+            &[
+                declaration("font-size", "10px"),
+                declaration("letter-spacing", "10%"),
+                declaration("word-spacing", "normal"),
+            ],
+            Some(&parent),
+            &Device::default(),
+        );
+        assert_eq!(TextAlign::Center, child.text_align());
+        assert_eq!(WhiteSpace::PreWrap, child.white_space());
+        assert_eq!(2, child.letter_spacing());
+        assert_eq!(0, child.word_spacing());
+        assert_eq!(WordBreak::KeepAll, child.word_break());
+        assert_eq!(LineBreak::Strict, child.line_break());
+
+        assert_eq!(
+            "HELLO, WORLD",
+            TextTransform::Uppercase.apply("Hello, world")
+        );
+        assert_eq!(
+            "hello, world",
+            TextTransform::Lowercase.apply("Hello, World")
+        );
+        assert_eq!(
+            "Hello (World) 2nd",
+            TextTransform::Capitalize.apply("hello (world) 2nd")
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let style = ComputedStyle::compute(&[], None, &Device::default());
+        assert_eq!(Overflow::Visible, style.overflow_x());
+        assert!(!style.is_scroll_container());
+        assert!(!style.clips_content());
+
+        // visible and clip compute to auto and hidden next to a scrollable value.
+        let style = ComputedStyle::compute(
+            &[declaration("overflow", "visible scroll")],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(Overflow::Auto, style.overflow_x());
+        assert_eq!(Overflow::Scroll, style.overflow_y());
+        assert!(style.is_scroll_container());
+
+        let style = ComputedStyle::compute(
+            &[declaration("overflow-x", "clip")],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(Overflow::Clip, style.overflow_x());
+        assert_eq!(Overflow::Visible, style.overflow_y());
+        assert!(!style.is_scroll_container());
+        assert!(style.clips_content());
+
+        let style = ComputedStyle::compute(
+            &[declaration("overflow", "clip hidden")],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(Overflow::Hidden, style.overflow_x());
+        assert!(!style.overflow_y().is_user_scrollable());
+    }
+
+    #[test]
+    fn test_position() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let style = ComputedStyle::compute(&[], None, &Device::default());
+        assert_eq!(Position::Static, style.position());
+        assert_eq!(LengthPercentageOrAuto::Auto, style.inset(Side::Top));
+        assert_eq!(ZIndex::Auto, style.z_index());
+        assert!(!style.creates_stacking_context());
+
+        // An absolutely positioned inline box is blockified.
+        let style = ComputedStyle::compute(
+            &[
+                declaration("display", "inline"),
+                declaration("position", "absolute"),
+                declaration("inset", "1em auto"),
+                declaration("z-index", "-2"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(DisplayType::Block, style.display());
+        assert_eq!(
+            LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Length(Length::px(16.0))),
+            style.inset(Side::Bottom)
+        );
+        assert_eq!(LengthPercentageOrAuto::Auto, style.inset(Side::Left));
+        assert_eq!(-2, style.z_index().level());
+        assert!(style.creates_stacking_context());
+
+        let style = ComputedStyle::compute(
+            &[
+                declaration("position", "sticky"),
+                declaration("z-index", "1.5"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(ZIndex::Auto, style.z_index());
+        assert!(style.creates_stacking_context());
+    }
+
+    #[test]
+    fn test_style_difference() {
+        let style = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            ComputedStyle::compute(
+                &[(CascadeOrigin::Author, declaration)],
+                None,
+                &Device::default(),
+            )
+        };
+        let initial = ComputedStyle::compute(&[], None, &Device::default());
+        assert_eq!(StyleDifference::Equal, initial.difference(&initial.clone()));
+        assert_eq!(
+            StyleDifference::Paint,
+            initial.difference(&style("color", "red"))
+        );
+        assert_eq!(
+            StyleDifference::Paint,
+            initial.difference(&style("transform", "rotate(45deg)"))
+        );
+        assert_eq!(
+            StyleDifference::Layout,
+            initial.difference(&style("width", "10px"))
+        );
+        assert_eq!(
+            StyleDifference::Rebuild,
+            initial.difference(&style("display", "none"))
+        );
+    }
+
+    #[test]
+    fn test_opacity_and_transform() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let style = ComputedStyle::compute(&[], None, &Device::default());
+        assert_eq!(1.0, style.opacity());
+        assert!(style.transform().is_empty());
+        assert_eq!(0, style.stack_level());
+
+        // Opacity is clamped, and a translucent box creates a stacking context.
+        let style = ComputedStyle::compute(
+            &[declaration("opacity", "150%"), declaration("z-index", "3")],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(1.0, style.opacity());
+        assert!(!style.creates_stacking_context());
+        let style = ComputedStyle::compute(
+            &[declaration("opacity", "0.25"), declaration("z-index", "3")],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(0.25, style.opacity());
+        assert!(style.creates_stacking_context());
+        // z-index doesn't apply to a box that isn't positioned.
+        assert_eq!(0, style.stack_level());
+
+        let style = ComputedStyle::compute(
+            &[
+                declaration("font-size", "10px"),
+                declaration("transform", "translate(2em, 50%) scale(2) rotate(0.25turn)"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(
+            vec![
+                TransformFunction::Translate(
+                    LengthPercentage::Length(Length::px(20.0)),
+                    LengthPercentage::Percentage(50.0)
+                ),
+                TransformFunction::Scale(2.0, 2.0),
+                TransformFunction::Rotate(90.0),
+            ],
+            style.transform()
+        );
+        assert!(style.creates_stacking_context());
+
+        // An invalid transform list is ignored as a whole.
+        let style = ComputedStyle::compute(
+            &[declaration("transform", "scale(2) skew(10deg)")],
+            None,
+            &Device::default(),
+        );
+        assert!(style.transform().is_empty());
+    }
+
+    #[test]
+    fn test_border_radius() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let style = ComputedStyle::compute(
+            &[
+                declaration("font-size", "10px"),
+                declaration("border-radius", "1em 10% / 4px"),
+                declaration("border-bottom-left-radius", "-1px"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(
+            BorderRadius::new(
+                LengthPercentage::Length(Length::px(10.0)),
+                LengthPercentage::Length(Length::px(4.0))
+            ),
+            style.border_radius(Corner::TopLeft)
+        );
+        assert_eq!(
+            (20, 4),
+            style.border_radius(Corner::TopRight).resolve(200, 100)
+        );
+        // The negative radius is invalid, so the one from the shorthand is kept.
+        assert_eq!(
+            (20, 4),
+            style.border_radius(Corner::BottomLeft).resolve(200, 100)
+        );
+    }
+
+    #[test]
+    fn test_float() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let style = ComputedStyle::compute(&[], None, &Device::default());
+        assert_eq!(Float::None, style.float());
+        assert_eq!(Clear::None, style.clear());
+        assert!(!style.establishes_block_formatting_context());
+
+        // A floated inline box is blockified, and establishes a block formatting context.
+        let style = ComputedStyle::compute(
+            &[
+                declaration("display", "inline"),
+                declaration("float", "right"),
+                declaration("clear", "left"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(DisplayType::Block, style.display());
+        assert_eq!(Float::Right, style.float());
+        assert!(style.clear().clears(Float::Left));
+        assert!(!style.clear().clears(Float::Right));
+        assert!(style.establishes_block_formatting_context());
+
+        // An absolutely positioned box doesn't float.
+        let style = ComputedStyle::compute(
+            &[
+                declaration("float", "left"),
+                declaration("position", "absolute"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(Float::None, style.float());
+
+        for (property, value) in [("display", "flow-root"), ("overflow", "hidden")] {
+            let style =
+                ComputedStyle::compute(&[declaration(property, value)], None, &Device::default());
+            assert!(style.establishes_block_formatting_context());
+        }
+        let style =
+            ComputedStyle::compute(&[declaration("overflow", "clip")], None, &Device::default());
+        assert!(!style.establishes_block_formatting_context());
+    }
+
+    #[test]
+    fn test_flex() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let style = ComputedStyle::compute(&[], None, &Device::default());
+        assert_eq!(FlexDirection::Row, style.flex_direction());
+        assert_eq!(FlexWrap::Nowrap, style.flex_wrap());
+        assert_eq!(JustifyContent::Normal, style.justify_content());
+        assert_eq!(AlignItems::Normal, style.align_items());
+        assert_eq!(AlignItems::Auto, style.align_self());
+        assert_eq!(0.0, style.flex_grow());
+        assert_eq!(1.0, style.flex_shrink());
+        assert_eq!(FlexBasis::Auto, style.flex_basis());
+        assert_eq!(LengthPercentage::zero(), style.row_gap());
+        assert_eq!(0, style.order());
+
+        let container = ComputedStyle::compute(
+            &[
+                declaration("display", "inline-flex"),
+                declaration("flex-flow", "column-reverse wrap"),
+                declaration("align-items", "center"),
+                declaration("gap", "1em 5px"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert!(container.display().is_flex_container());
+        assert!(container.establishes_block_formatting_context());
+        assert!(!container.flex_direction().is_row());
+        assert!(container.flex_direction().is_reverse());
+        assert_eq!(FlexWrap::Wrap, container.flex_wrap());
+        assert_eq!(
+            LengthPercentage::Length(Length::px(16.0)),
+            container.row_gap()
+        );
+        assert_eq!(
+            LengthPercentage::Length(Length::px(5.0)),
+            container.column_gap()
+        );
+
+        // Flex items are blockified, and they don't float.
+        let item = ComputedStyle::compute(
+            &[
+                declaration("display", "inline-flex"),
+                declaration("float", "left"),
+                declaration("flex", "2 0 10px"),
+                declaration("order", "-1"),
+            ],
+            Some(&container),
+            &Device::default(),
+        );
+        assert_eq!(DisplayType::Flex, item.display());
+        assert_eq!(Float::None, item.float());
+        assert_eq!(2.0, item.flex_grow());
+        assert_eq!(0.0, item.flex_shrink());
+        assert_eq!(
+            FlexBasis::LengthPercentage(LengthPercentage::Length(Length::px(10.0))),
+            item.flex_basis()
+        );
+        assert_eq!(-1, item.order());
+        assert_eq!(
+            AlignItems::Center,
+            item.align_self().resolve(container.align_items())
+        );
+
+        // Negative flex factors and fractional orders are invalid.
+        let item = ComputedStyle::compute(
+            &[
+                declaration("flex-grow", "-1"),
+                declaration("order", "1.5"),
+                declaration("align-items", "auto"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(0.0, item.flex_grow());
+        assert_eq!(0, item.order());
+        assert_eq!(AlignItems::Normal, item.align_items());
+    }
+
+    #[test]
+    fn test_grid() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let px = |px: f64| TrackBreadth::LengthPercentage(LengthPercentage::Length(Length::px(px)));
+        let style = ComputedStyle::compute(&[], None, &Device::default());
+        assert_eq!(TrackList::default(), style.grid_template_columns());
+        assert_eq!(GridTemplateAreas::default(), style.grid_template_areas());
+        assert_eq!(
+            vec![TrackSize::new(TrackBreadth::Auto, TrackBreadth::Auto)],
+            style.grid_auto_rows()
+        );
+        assert!(!style.grid_auto_flow().is_column());
+        assert_eq!((GridLine::Auto, GridLine::Auto), style.grid_row());
+
+        let container = ComputedStyle::compute(
+            &[
+                declaration("display", "inline-grid"),
+                declaration(
+                    "grid-template-columns",
+                    "[full-start] 1em repeat(2, minmax(10px, 1fr)) [full-end]",
+                ),
+                declaration("grid-template-rows", "repeat(auto-fill, 50px)"),
+                declaration("grid-template-areas", "\"a a b\" \". c b\""),
+                declaration("grid-auto-flow", "column dense"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert!(container.display().is_grid_container());
+        assert!(container.display().is_atomic_inline());
+        assert!(container.establishes_block_formatting_context());
+        let columns = container.grid_template_columns();
+        let minmax = TrackSize::new(px(10.0), TrackBreadth::Flex(1.0));
+        // Lengths in the track sizes are computed.
+        assert_eq!(
+            (
+                vec![TrackSize::new(px(16.0), px(16.0)), minmax.clone(), minmax],
+                vec![
+                    vec!["full-start".to_string()],
+                    Vec::new(),
+                    Vec::new(),
+                    vec!["full-end".to_string()]
+                ]
+            ),
+            columns.expand(1)
+        );
+        // The auto-repeated rows fill the container.
+        let rows = container.grid_template_rows();
+        assert_eq!(3, rows.repetitions(Some(170), 10));
+        assert_eq!(1, rows.repetitions(None, 10));
+        assert_eq!(Some((AutoRepeat::AutoFill, 0..3)), rows.auto_repeat(3));
+        let areas = container.grid_template_areas();
+        assert_eq!((2, 3), (areas.rows(), areas.columns()));
+        assert_eq!(Some((0..1, 0..2)), areas.area("a"));
+        assert_eq!(Some((0..2, 2..3)), areas.area("b"));
+        assert_eq!(Some((1..2, 1..2)), areas.area("c"));
+        assert!(container.grid_auto_flow().is_column());
+        assert!(container.grid_auto_flow().is_dense());
+
+        // The items are blockified.
+        let item = ComputedStyle::compute(
+            &[
+                declaration("display", "inline-grid"),
+                declaration("grid-row", "span 2 / -1"),
+                declaration("grid-area", "auto / main"),
+            ],
+            Some(&container),
+            &Device::default(),
+        );
+        assert_eq!(DisplayType::Grid, item.display());
+        assert_eq!((GridLine::Auto, GridLine::Auto), item.grid_row());
+        assert_eq!(
+            (
+                GridLine::Name("main".to_string()),
+                GridLine::Name("main".to_string())
+            ),
+            item.grid_column()
+        );
+        let item = ComputedStyle::compute(
+            &[declaration("grid-row", "span 2 / -1")],
+            Some(&container),
+            &Device::default(),
+        );
+        assert_eq!((GridLine::Span(2), GridLine::Line(-1)), item.grid_row());
+
+        // Areas that are not rectangles, rows of different lengths, flexible minimums and zero
+        // lines are invalid.
+        let invalid = ComputedStyle::compute(
+            &[
+                declaration("grid-template-areas", "\"a b\" \"b b\""),
+                declaration("grid-template-rows", "minmax(1fr, 10px)"),
+                declaration("grid-template-columns", "repeat(0, 10px)"),
+                declaration("grid-row-start", "0"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(GridTemplateAreas::default(), invalid.grid_template_areas());
+        assert_eq!(TrackList::default(), invalid.grid_template_rows());
+        assert_eq!(TrackList::default(), invalid.grid_template_columns());
+        assert_eq!(GridLine::Auto, invalid.grid_row().0);
+        let invalid = ComputedStyle::compute(
+            &[declaration("grid-template-areas", "\"a b\" \"c\"")],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(GridTemplateAreas::default(), invalid.grid_template_areas());
+    }
+
+    #[test]
+    fn test_lists_and_counters() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let style = ComputedStyle::compute(&[], None, &Device::default());
+        assert_eq!(
+            ListStyleType::CounterStyle(CounterStyle::Disc),
+            style.list_style_type()
+        );
+        assert_eq!(ListStylePosition::Outside, style.list_style_position());
+        assert_eq!(Vec::<CounterChange>::new(), style.counter_reset());
+        assert_eq!(Content::Normal, style.content());
+
+        let list = ComputedStyle::compute(
+            &[
+                declaration("display", "list-item"),
+                declaration("list-style", "inside lower-roman"),
+                declaration("counter-reset", "reversed(list-item) 4 section"),
+                declaration("counter-increment", "section -2"),
+                declaration(
+                    "content",
+                    "\"(\" counter(section, upper-alpha) counters(list-item, \".\") \")\"",
+                ),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(DisplayType::ListItem, list.display());
+        assert_eq!(
+            ListStyleType::CounterStyle(CounterStyle::LowerRoman),
+            list.list_style_type()
+        );
+        assert_eq!(ListStylePosition::Inside, list.list_style_position());
+        assert_eq!(
+            vec![
+                CounterChange::new("list-item", Some(4), true),
+                CounterChange::new("section", None, false),
+            ],
+            list.counter_reset()
+        );
+        assert_eq!(
+            vec![CounterChange::new("section", Some(-2), false)],
+            list.counter_increment()
+        );
+        assert_eq!(
+            Content::Items(vec![
+                ContentItem::String("(".to_string()),
+                ContentItem::Counter("section".to_string(), Some(CounterStyle::UpperAlpha)),
+                ContentItem::Counters(
+                    "list-item".to_string(),
+                    ".".to_string(),
+                    Some(CounterStyle::Decimal)
+                ),
+                ContentItem::String(")".to_string()),
+            ]),
+            list.content()
+        );
+
+        // The list style is inherited and the counters are not.
+        let child = ComputedStyle::compute(&[], Some(&list), &Device::default());
+        assert_eq!(ListStylePosition::Inside, child.list_style_position());
+        assert_eq!(Vec::<CounterChange>::new(), child.counter_reset());
+
+        // Invalid values are ignored.
+        let invalid = ComputedStyle::compute(
+            &[
+                declaration("list-style-type", "unknown"),
+                declaration("counter-increment", "reversed(a)"),
+                declaration("counter-set", "a 1 2"),
+                declaration("content", "counter()"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(
+            ListStyleType::CounterStyle(CounterStyle::Disc),
+            invalid.list_style_type()
+        );
+        assert_eq!(Vec::<CounterChange>::new(), invalid.counter_increment());
+        assert_eq!(Vec::<CounterChange>::new(), invalid.counter_set());
+        assert_eq!(Content::Normal, invalid.content());
+    }
+
+    #[test]
+    fn test_generated_content() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let style = ComputedStyle::compute(&[], None, &Device::default());
+        assert_eq!(Quotes::Auto, style.quotes());
+        assert_eq!("\u{201C}", style.quotes().quote(0, true));
+        assert_eq!("\u{2019}", style.quotes().quote(1, false));
+
+        let quoted = ComputedStyle::compute(
+            &[
+                declaration(
+                    "content",
+                    "open-quote attr(title) no-close-quote \"\\f101\"",
+                ),
+                declaration("quotes", "\"<\" \">\" \"(\" \")\""),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(
+            Content::Items(vec![
+                ContentItem::OpenQuote,
+                ContentItem::Attr("title".to_string()),
+                ContentItem::NoCloseQuote,
+                ContentItem::String("\u{f101}".to_string()),
+            ]),
+            quoted.content()
+        );
+        let quotes = quoted.quotes();
+        assert_eq!(
+            Quotes::Pairs(vec![
+                ("<".to_string(), ">".to_string()),
+                ("(".to_string(), ")".to_string()),
+            ]),
+            quotes
+        );
+        // The last pair is repeated for deeper quotes.
+        assert_eq!(")", quotes.quote(5, false));
+
+        // Quotes are inherited.
+        let child = ComputedStyle::compute(&[], Some(&quoted), &Device::default());
+        assert_eq!(quotes, child.quotes());
+
+        let none = ComputedStyle::compute(
+            &[
+                declaration("quotes", "none"),
+                declaration("content", "close-quote"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!("", none.quotes().quote(0, true));
+        assert_eq!(
+            Content::Items(vec![ContentItem::CloseQuote]),
+            none.content()
+        );
+
+        // Invalid values are ignored.
+        let invalid = ComputedStyle::compute(
+            &[
+                declaration("quotes", "\"<\""),
+                declaration("content", "attr()"),
+            ],
+            None,
+            &Device::default(),
+        );
+        assert_eq!(Quotes::Auto, invalid.quotes());
+        assert_eq!(Content::Normal, invalid.content());
+    }
+
+    #[test]
+    fn test_background_image() {
+        let declaration = |property: &str, value: &str| {
+            let mut declaration = Declaration::new();
+            declaration.set_property(property.to_string());
+            declaration.set_value(CssTokenizer::new(value.to_string()).collect());
+            (CascadeOrigin::Author, declaration)
+        };
+        let style = ComputedStyle::compute(&[], None, &Device::default());
+        assert_eq!(vec![BackgroundImage::None], style.background_image());
+
+        let layered = ComputedStyle::compute(
+            &[declaration(
+                "background-image",
+                "url(a.png), linear-gradient(red, blue), url(\"b.gif\")",
+            )],
+            Some(&style),
+            &Device::default(),
+        );
+        assert_eq!(
+            vec![
+                BackgroundImage::Url("a.png".to_string()),
+                BackgroundImage::None,
+                BackgroundImage::Url("b.gif".to_string()),
+            ],
+            layered.background_image()
+        );
+        // background-image isn't inherited.
+        let child = ComputedStyle::compute(&[], Some(&layered), &Device::default());
+        assert_eq!(vec![BackgroundImage::None], child.background_image());
     }
 }
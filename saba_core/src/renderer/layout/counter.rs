@@ -0,0 +1,449 @@
+//! https://www.w3.org/TR/css-lists-3/#auto-numbering
+//! CSS counters and the predefined counter styles that format them. Counters are created by
+//! counter-reset, changed by counter-increment and counter-set, and are visible to the element that
+//! creates them, its descendants and its following siblings with their descendants.
+
+use crate::error::Error;
+use crate::renderer::dom::node::Element;
+use crate::renderer::layout::computed_style::ComputedStyle;
+use crate::renderer::layout::computed_style::ContentItem;
+use crate::renderer::layout::computed_style::CounterChange;
+use crate::renderer::layout::computed_style::DisplayType;
+use crate::renderer::layout::computed_style::Quotes;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// https://www.w3.org/TR/css-counter-styles-3/#predefined-counters
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CounterStyle {
+    Disc,
+    Circle,
+    Square,
+    Decimal,
+    DecimalLeadingZero,
+    LowerRoman,
+    UpperRoman,
+    LowerAlpha,
+    UpperAlpha,
+    LowerGreek,
+    /// https://www.w3.org/TR/css-counter-styles-3/#simple-numeric
+    CjkDecimal,
+    /// https://www.w3.org/TR/css-counter-styles-3/#japanese-informal
+    JapaneseInformal,
+    Hiragana,
+    Katakana,
+}
+
+/// https://www.w3.org/TR/css-counter-styles-3/#counter-style-symbols
+/// The symbols and weights of the additive roman numerals.
+static ROMAN: [(i32, &str); 13] = [
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+static LOWER_GREEK: &str = "αβγδεζηθικλμνξοπρστυφχψω";
+
+static HIRAGANA: &str =
+    "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめもやゆよらりるれろわゐゑをん";
+
+static KATAKANA: &str =
+    "アイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワヰヱヲン";
+
+static CJK_DIGITS: [char; 10] = ['〇', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+impl CounterStyle {
+    pub fn from_str_counter_style(s: &str) -> Result<Self, Error> {
+        match s {
+            "disc" => Ok(Self::Disc),
+            "circle" => Ok(Self::Circle),
+            "square" => Ok(Self::Square),
+            "decimal" => Ok(Self::Decimal),
+            "decimal-leading-zero" => Ok(Self::DecimalLeadingZero),
+            "lower-roman" => Ok(Self::LowerRoman),
+            "upper-roman" => Ok(Self::UpperRoman),
+            // "lower-latin" and "upper-latin" are aliases of the alpha styles.
+            "lower-alpha" | "lower-latin" => Ok(Self::LowerAlpha),
+            "upper-alpha" | "upper-latin" => Ok(Self::UpperAlpha),
+            "lower-greek" => Ok(Self::LowerGreek),
+            "cjk-decimal" => Ok(Self::CjkDecimal),
+            "japanese-informal" => Ok(Self::JapaneseInformal),
+            "hiragana" => Ok(Self::Hiragana),
+            "katakana" => Ok(Self::Katakana),
+            _ => Err(Error::UnexpectedInput(format!(
+                "counter style {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+
+    /// https://www.w3.org/TR/css-counter-styles-3/#generate-a-counter
+    /// Returns the representation of `value`. "If the counter value is outside the range of the
+    /// counter style, the representation is constructed using the fallback style", which is
+    /// decimal for all the predefined styles.
+    pub fn format(&self, value: i32) -> String {
+        self.representation(value)
+            .unwrap_or_else(|| Self::Decimal.representation(value).unwrap_or_default())
+    }
+
+    /// https://www.w3.org/TR/css-lists-3/#content-property
+    /// The text of a marker: the representation of the value followed by the suffix.
+    pub fn marker(&self, value: i32) -> String {
+        format!("{}{}", self.format(value), self.suffix())
+    }
+
+    /// https://www.w3.org/TR/css-counter-styles-3/#counter-style-suffix
+    fn suffix(&self) -> &'static str {
+        match self {
+            Self::Disc | Self::Circle | Self::Square => " ",
+            Self::CjkDecimal | Self::JapaneseInformal | Self::Hiragana | Self::Katakana => "、",
+            _ => ". ",
+        }
+    }
+
+    fn representation(&self, value: i32) -> Option<String> {
+        match self {
+            Self::Disc => Some("•".to_string()),
+            Self::Circle => Some("◦".to_string()),
+            Self::Square => Some("▪".to_string()),
+            Self::Decimal => Some(value.to_string()),
+            Self::DecimalLeadingZero if value < 0 => Some(format!("-{:02}", value.unsigned_abs())),
+            Self::DecimalLeadingZero => Some(format!("{:02}", value)),
+            Self::LowerRoman => roman(value).map(|r| r.to_ascii_lowercase()),
+            Self::UpperRoman => roman(value),
+            Self::LowerAlpha => alphabetic(value, "abcdefghijklmnopqrstuvwxyz"),
+            Self::UpperAlpha => alphabetic(value, "ABCDEFGHIJKLMNOPQRSTUVWXYZ"),
+            Self::LowerGreek => alphabetic(value, LOWER_GREEK),
+            Self::CjkDecimal if value < 0 => None,
+            Self::CjkDecimal => Some(
+                value
+                    .to_string()
+                    .chars()
+                    .map(|d| CJK_DIGITS[d as usize - '0' as usize])
+                    .collect(),
+            ),
+            Self::JapaneseInformal => japanese_informal(value),
+            Self::Hiragana => alphabetic(value, HIRAGANA),
+            Self::Katakana => alphabetic(value, KATAKANA),
+        }
+    }
+}
+
+/// https://www.w3.org/TR/css-counter-styles-3/#additive-system
+/// Roman numerals are defined for 1 to 3999.
+fn roman(value: i32) -> Option<String> {
+    if !(1..=3999).contains(&value) {
+        return None;
+    }
+    let mut rest = value;
+    let mut result = String::new();
+    for (weight, symbol) in ROMAN {
+        while rest >= weight {
+            result.push_str(symbol);
+            rest -= weight;
+        }
+    }
+    Some(result)
+}
+
+/// https://www.w3.org/TR/css-counter-styles-3/#alphabetic-system
+/// "interprets the list of counter symbols as digits to an alphabetic numbering system", so the
+/// symbol after the last one is the first symbol repeated twice. Defined for 1 and above.
+fn alphabetic(value: i32, symbols: &str) -> Option<String> {
+    if value < 1 {
+        return None;
+    }
+    let symbols: Vec<char> = symbols.chars().collect();
+    let mut rest = value as usize;
+    let mut result = Vec::new();
+    while rest > 0 {
+        rest -= 1;
+        result.push(symbols[rest % symbols.len()]);
+        rest /= symbols.len();
+    }
+    Some(result.iter().rev().collect())
+}
+
+/// https://www.w3.org/TR/css-counter-styles-3/#japanese-informal
+/// Defined for -9999 to 9999. A digit of one is omitted before 十, 百 and 千, and zero digits are
+/// skipped.
+fn japanese_informal(value: i32) -> Option<String> {
+    if !(-9999..=9999).contains(&value) {
+        return None;
+    }
+    if value == 0 {
+        return Some(CJK_DIGITS[0].to_string());
+    }
+    let mut result = String::new();
+    if value < 0 {
+        result.push_str("マイナス");
+    }
+    let mut rest = value.unsigned_abs() as usize;
+    for (weight, unit) in [(1000, '千'), (100, '百'), (10, '十')] {
+        let digit = rest / weight;
+        if digit > 1 {
+            result.push(CJK_DIGITS[digit]);
+        }
+        if digit > 0 {
+            result.push(unit);
+        }
+        rest %= weight;
+    }
+    if rest > 0 {
+        result.push(CJK_DIGITS[rest]);
+    }
+    Some(result)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Counter {
+    name: String,
+    value: i32,
+    reversed: bool,
+    /// The depth of the element that created the counter. The counter goes out of scope when the
+    /// parent of that element is left.
+    depth: usize,
+}
+
+/// https://www.w3.org/TR/css-lists-3/#creating-a-counter
+/// The counters in scope while the layout tree is built in tree order. `enter` and `leave` are
+/// called around the children of an element.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CounterScopes {
+    /// Counters from the outermost to the innermost. A name may appear more than once when a
+    /// descendant resets a counter that is already in scope, which nests the counters.
+    counters: Vec<Counter>,
+    depth: usize,
+    /// https://www.w3.org/TR/css-content-3/#quote-values
+    /// The nesting level of quotes, which open-quote and no-open-quote increment and close-quote
+    /// and no-close-quote decrement in tree order.
+    quote_depth: usize,
+}
+
+impl CounterScopes {
+    pub fn new() -> Self {
+        Self {
+            counters: Vec::new(),
+            depth: 0,
+            quote_depth: 0,
+        }
+    }
+
+    pub fn enter(&mut self) {
+        self.depth += 1;
+    }
+
+    /// Leaves the children of an element. The counters created by the children go out of scope.
+    pub fn leave(&mut self) {
+        let depth = self.depth;
+        self.counters.retain(|c| c.depth < depth);
+        self.depth -= 1;
+    }
+
+    /// https://www.w3.org/TR/css-lists-3/#counter-properties
+    /// "Resetting the counter on the element is done first, then incrementing it, then setting
+    /// it." A list item also increments the list-item counter unless counter-increment names it,
+    /// and a reversed list-item counter counts down.
+    pub fn update(&mut self, style: &ComputedStyle) {
+        for change in style.counter_reset() {
+            self.reset(&change);
+        }
+
+        let mut increments = style.counter_increment();
+        if style.display() == DisplayType::ListItem
+            && !increments.iter().any(|c| c.name() == "list-item")
+        {
+            let step = match self.find("list-item") {
+                Some(counter) if counter.reversed => -1,
+                _ => 1,
+            };
+            increments.push(CounterChange::new("list-item", Some(step), false));
+        }
+        for change in increments {
+            let counter = self.find_or_instantiate(change.name());
+            counter.value = counter.value.saturating_add(change.value().unwrap_or(1));
+        }
+
+        for change in style.counter_set() {
+            self.find_or_instantiate(change.name()).value = change.value().unwrap_or(0);
+        }
+    }
+
+    /// https://www.w3.org/TR/css-lists-3/#counter-functions
+    /// The value of the innermost counter, which is what counter() shows. A counter that is not
+    /// in scope is 0.
+    pub fn value(&self, name: &str) -> i32 {
+        self.find(name).map_or(0, |c| c.value)
+    }
+
+    /// The values of all the counters with the name from the outermost, which is what counters()
+    /// shows.
+    pub fn values(&self, name: &str) -> Vec<i32> {
+        let values: Vec<i32> = self
+            .counters
+            .iter()
+            .filter(|c| c.name == name)
+            .map(|c| c.value)
+            .collect();
+        if values.is_empty() {
+            Vec::from([0])
+        } else {
+            values
+        }
+    }
+
+    /// Returns the text of `items` of the content property, where counter() and counters() show
+    /// the counters in scope, attr() reads the attributes of `element` and quotes are chosen from
+    /// `quotes` by their nesting level.
+    pub fn resolve(
+        &mut self,
+        items: &[ContentItem],
+        quotes: &Quotes,
+        element: Option<&Element>,
+    ) -> String {
+        items
+            .iter()
+            .map(|item| match item {
+                ContentItem::String(s) => s.clone(),
+                ContentItem::Attr(name) => element
+                    .and_then(|e| e.get_attribute(name))
+                    .unwrap_or_default(),
+                ContentItem::OpenQuote => {
+                    self.quote_depth += 1;
+                    quotes.quote(self.quote_depth - 1, true)
+                }
+                // "A close-quote or no-close-quote that would make the depth negative is in error
+                // and is ignored".
+                ContentItem::CloseQuote if self.quote_depth == 0 => String::new(),
+                ContentItem::CloseQuote => {
+                    self.quote_depth -= 1;
+                    quotes.quote(self.quote_depth, false)
+                }
+                ContentItem::NoOpenQuote => {
+                    self.quote_depth += 1;
+                    String::new()
+                }
+                ContentItem::NoCloseQuote => {
+                    self.quote_depth = self.quote_depth.saturating_sub(1);
+                    String::new()
+                }
+                ContentItem::Counter(name, style) => {
+                    style.map_or(String::new(), |style| style.format(self.value(name)))
+                }
+                ContentItem::Counters(name, separator, style) => {
+                    style.map_or(String::new(), |style| {
+                        self.values(name)
+                            .iter()
+                            .map(|value| style.format(*value))
+                            .collect::<Vec<_>>()
+                            .join(separator)
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// "If element already has a counter of the given name created by a counter-reset, ... it
+    /// replaces it", otherwise a new counter is created which nests inside the counters of the
+    /// ancestors. A reversed counter without a value starts from 0 here because its items are
+    /// not known before they are laid out.
+    fn reset(&mut self, change: &CounterChange) {
+        let depth = self.depth;
+        self.counters
+            .retain(|c| !(c.name == change.name() && c.depth == depth));
+        self.counters.push(Counter {
+            name: change.name().to_string(),
+            value: change.value().unwrap_or(0),
+            reversed: change.is_reversed(),
+            depth,
+        });
+    }
+
+    fn find(&self, name: &str) -> Option<&Counter> {
+        self.counters.iter().rev().find(|c| c.name == name)
+    }
+
+    /// https://www.w3.org/TR/css-lists-3/#instantiating-counters
+    /// "If there is not currently a counter of the given name on the element, the element
+    /// instantiates a new counter of the given name with a starting value of 0".
+    fn find_or_instantiate(&mut self, name: &str) -> &mut Counter {
+        match self.counters.iter().rposition(|c| c.name == name) {
+            Some(i) => &mut self.counters[i],
+            None => {
+                self.counters.push(Counter {
+                    name: name.to_string(),
+                    value: 0,
+                    reversed: false,
+                    depth: self.depth,
+                });
+                self.counters.last_mut().expect("a counter was pushed")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_styles() {
+        let format = |style: &str, value: i32| {
+            CounterStyle::from_str_counter_style(style)
+                .expect("failed to parse counter style")
+                .format(value)
+        };
+        assert_eq!("•", format("disc", 3));
+        assert_eq!("-7", format("decimal", -7));
+        assert_eq!("05", format("decimal-leading-zero", 5));
+        assert_eq!("-05", format("decimal-leading-zero", -5));
+        assert_eq!("xiv", format("lower-roman", 14));
+        assert_eq!("MCMXCIV", format("upper-roman", 1994));
+        // Out of range values fall back to decimal.
+        assert_eq!("4000", format("upper-roman", 4000));
+        assert_eq!("0", format("lower-alpha", 0));
+        assert_eq!("z", format("lower-alpha", 26));
+        assert_eq!("AA", format("upper-latin", 27));
+        assert_eq!("γ", format("lower-greek", 3));
+        assert_eq!("一〇二", format("cjk-decimal", 102));
+        assert_eq!("十二", format("japanese-informal", 12));
+        assert_eq!("二千百五", format("japanese-informal", 2105));
+        assert_eq!("い", format("hiragana", 2));
+        assert_eq!("ア", format("katakana", 1));
+        assert!(CounterStyle::from_str_counter_style("unknown").is_err());
+
+        assert_eq!("• ", CounterStyle::Disc.marker(1));
+        assert_eq!("3. ", CounterStyle::Decimal.marker(3));
+        assert_eq!("三、", CounterStyle::CjkDecimal.marker(3));
+    }
+
+    #[test]
+    fn test_counter_scopes() {
+        let mut counters = CounterScopes::new();
+        counters.reset(&CounterChange::new("c", Some(1), false));
+        counters.enter();
+        assert_eq!(1, counters.value("c"));
+        // A nested reset creates a new counter inside the outer one.
+        counters.reset(&CounterChange::new("c", None, false));
+        counters.find_or_instantiate("c").value += 2;
+        assert_eq!(Vec::from([1, 2]), counters.values("c"));
+        counters.leave();
+        assert_eq!(Vec::from([1]), counters.values("c"));
+        // An unknown counter is instantiated with 0.
+        assert_eq!(0, counters.value("d"));
+        counters.find_or_instantiate("d").value += 1;
+        assert_eq!(1, counters.value("d"));
+    }
+}
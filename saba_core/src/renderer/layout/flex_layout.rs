@@ -0,0 +1,842 @@
+//! https://www.w3.org/TR/css-flexbox-1/
+//! Flex layout. The in-flow children of a flex container are flex items, which are put on flex
+//! lines along the main axis, sized by their flex factors, and aligned in the cross axis. The
+//! main axis is horizontal for rows and vertical for columns.
+
+use crate::renderer::css::value::round;
+use crate::renderer::css::value::LengthPercentage;
+use crate::renderer::css::value::LengthPercentageOrAuto;
+use crate::renderer::dom::node::NodeKind;
+use crate::renderer::layout::computed_style::AlignItems;
+use crate::renderer::layout::computed_style::BoxSizing;
+use crate::renderer::layout::computed_style::ComputedStyle;
+use crate::renderer::layout::computed_style::FlexBasis;
+use crate::renderer::layout::computed_style::FlexWrap;
+use crate::renderer::layout::computed_style::JustifyContent;
+use crate::renderer::layout::computed_style::Side;
+use crate::renderer::layout::float_layout::FloatManager;
+use crate::renderer::layout::font_metrics::FontMetrics;
+use crate::renderer::layout::inline_layout::layout_inline_content;
+use crate::renderer::layout::layout_object::LayoutObject;
+use crate::renderer::layout::layout_object::LayoutObjectKind;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::ops::Range;
+
+/// The box that a flex or grid item is laid out in.
+#[derive(Debug, Clone)]
+pub enum ItemContent {
+    Box(Rc<RefCell<LayoutObject>>),
+    /// https://www.w3.org/TR/css-flexbox-1/#flex-items
+    /// "each contiguous sequence of child text runs is wrapped in an anonymous block container
+    /// flex item." So is it in a grid container.
+    Text(Vec<Rc<RefCell<LayoutObject>>>),
+}
+
+impl ItemContent {
+    /// Collects the contents of the items of a flex or grid container in document order. An
+    /// absolutely positioned child is not an item, and it's put at the top-left corner of the
+    /// content box, which is its static position. A run of collapsible white space isn't
+    /// rendered, and doesn't make an item.
+    pub fn collect(container: &LayoutObject, font_metrics: &dyn FontMetrics) -> Vec<Self> {
+        let mut contents = Vec::new();
+        let mut text_run = Vec::new();
+        let mut child = container.first_child();
+        while let Some(c) = child {
+            if c.borrow().kind() == LayoutObjectKind::Text {
+                text_run.push(c.clone());
+            } else {
+                if !text_run.is_empty() {
+                    contents.push(Self::Text(core::mem::take(&mut text_run)));
+                }
+                if c.borrow().is_out_of_flow() {
+                    c.borrow_mut().set_offset(LayoutPoint::new(0, 0));
+                } else {
+                    contents.push(Self::Box(c.clone()));
+                }
+            }
+            child = c.borrow().next_sibling();
+        }
+        if !text_run.is_empty() {
+            contents.push(Self::Text(text_run));
+        }
+
+        let style = container.style();
+        contents.retain(|content| match content {
+            Self::Box(_) => true,
+            Self::Text(run) => {
+                let rendered = run.iter().any(|t| match t.borrow().node_kind() {
+                    NodeKind::Text(text) => !text.trim().is_empty(),
+                    _ => false,
+                });
+                if !rendered {
+                    layout_text(&style, run, 0, font_metrics);
+                }
+                rendered
+            }
+        });
+        contents
+    }
+
+    /// The style of the box, which is None for an anonymous item.
+    pub fn style(&self) -> Option<ComputedStyle> {
+        match self {
+            Self::Box(object) => Some(object.borrow().style()),
+            Self::Text(_) => None,
+        }
+    }
+
+    /// Lays out the item in a containing block of `containing_block` so that its content box
+    /// is `width` wide and `height` tall when they are given, and returns the size of the
+    /// content box. The text of an anonymous item is laid out in the style of the container.
+    #[allow(clippy::too_many_arguments)]
+    pub fn layout(
+        &self,
+        container_style: &ComputedStyle,
+        containing_block: LayoutSize,
+        definite_height: bool,
+        font_metrics: &dyn FontMetrics,
+        width: Option<i64>,
+        height: Option<i64>,
+    ) -> LayoutSize {
+        match self {
+            Self::Box(object) => {
+                let mut o = object.borrow_mut();
+                o.layout_with_content_size(
+                    containing_block,
+                    definite_height,
+                    font_metrics,
+                    width,
+                    height,
+                );
+                o.content_size()
+            }
+            Self::Text(run) => {
+                let width = width.unwrap_or_else(|| self.max_content_width(font_metrics));
+                let text_height = layout_text(container_style, run, width, font_metrics);
+                LayoutSize::new(width, height.unwrap_or(text_height))
+            }
+        }
+    }
+
+    /// The max-content width of the content box.
+    pub fn max_content_width(&self, font_metrics: &dyn FontMetrics) -> i64 {
+        match self {
+            Self::Box(object) => object.borrow().max_content_width(font_metrics),
+            Self::Text(run) => run
+                .iter()
+                .map(|t| t.borrow().max_content_width(font_metrics))
+                .sum(),
+        }
+    }
+
+    /// The min-content width of the content box.
+    pub fn min_content_width(&self, font_metrics: &dyn FontMetrics) -> i64 {
+        match self {
+            Self::Box(object) => object.borrow().min_content_width(font_metrics),
+            Self::Text(run) => run
+                .iter()
+                .map(|t| t.borrow().min_content_width(font_metrics))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Moves the item so that the top-left corner of its border box is at `offset` in the
+    /// content box of the container. The text of an anonymous item was laid out in the content
+    /// box of the item.
+    pub fn set_offset(&self, offset: LayoutPoint) {
+        match self {
+            Self::Box(object) => object.borrow_mut().set_offset(offset),
+            Self::Text(run) => {
+                for t in run {
+                    let o = t.borrow().offset();
+                    t.borrow_mut()
+                        .set_offset(LayoutPoint::new(o.x() + offset.x(), o.y() + offset.y()));
+                }
+            }
+        }
+    }
+}
+
+/// Lays out a run of text in an anonymous block of `width`, and returns its height.
+fn layout_text(
+    container_style: &ComputedStyle,
+    run: &[Rc<RefCell<LayoutObject>>],
+    width: i64,
+    font_metrics: &dyn FontMetrics,
+) -> i64 {
+    let mut floats = FloatManager::new();
+    layout_inline_content(
+        font_metrics,
+        container_style,
+        None,
+        run,
+        width,
+        0,
+        &mut floats,
+        LayoutPoint::new(0, 0),
+    )
+}
+
+#[derive(Debug, Clone)]
+struct FlexItem {
+    content: ItemContent,
+    order: i32,
+    flex_grow: f64,
+    flex_shrink: f64,
+    align: AlignItems,
+    /// The margins on the low (left or top) and high (right or bottom) sides of the main and the
+    /// cross axes, where None is auto.
+    main_margin: [Option<i64>; 2],
+    cross_margin: [Option<i64>; 2],
+    /// The sum of the paddings and borders in the main and the cross axes.
+    main_edges: i64,
+    cross_edges: i64,
+    /// Whether the size property in the cross axis is auto, so that the item can be stretched.
+    auto_cross_size: bool,
+    /// https://www.w3.org/TR/css-flexbox-1/#flex-base-size
+    /// The flex base size of the content box.
+    base: f64,
+    /// The size of the content box in the main axis while the flexible lengths are resolved.
+    target: f64,
+    frozen: bool,
+    /// The used size of the content box in the main and the cross axes.
+    main: i64,
+    cross: i64,
+    /// The cross size that the content box is laid out at, if it's decided before the layout.
+    forced_cross: Option<i64>,
+}
+
+impl FlexItem {
+    fn outer_main_edges(&self) -> i64 {
+        self.main_edges + self.main_margin.iter().map(|m| m.unwrap_or(0)).sum::<i64>()
+    }
+
+    fn outer_cross(&self) -> i64 {
+        self.cross
+            + self.cross_edges
+            + self
+                .cross_margin
+                .iter()
+                .map(|m| m.unwrap_or(0))
+                .sum::<i64>()
+    }
+
+    fn outer_base(&self) -> f64 {
+        self.base + self.outer_main_edges() as f64
+    }
+
+    fn flex_factor(&self, grow: bool) -> f64 {
+        if grow {
+            self.flex_grow
+        } else {
+            self.flex_shrink
+        }
+    }
+}
+
+/// The state of the layout of a flex container.
+struct FlexLayout<'a> {
+    font_metrics: &'a dyn FontMetrics,
+    style: ComputedStyle,
+    /// The size of the content box of the flex container. The height is 0 if it's not definite.
+    content_size: LayoutSize,
+    definite_height: Option<i64>,
+    /// Whether the main axis is horizontal.
+    row: bool,
+}
+
+impl FlexLayout<'_> {
+    /// The size of the content box in the main axis if it's definite.
+    fn main_size(&self) -> Option<i64> {
+        if self.row {
+            Some(self.content_size.width())
+        } else {
+            self.definite_height
+        }
+    }
+
+    /// The size of the content box in the cross axis if it's definite.
+    fn cross_size(&self) -> Option<i64> {
+        if self.row {
+            self.definite_height
+        } else {
+            Some(self.content_size.width())
+        }
+    }
+
+    /// https://www.w3.org/TR/css-align-3/#gaps
+    /// The gaps between items on a line and between lines. Percentages refer to the content box,
+    /// and they are 0 when it's not definite.
+    fn gaps(&self) -> (i64, i64) {
+        let resolve = |gap: LengthPercentage, basis: Option<i64>| match basis {
+            Some(basis) => gap.resolve(basis),
+            None if gap.has_percentage() => 0,
+            None => gap.resolve(0),
+        };
+        let column_gap = resolve(self.style.column_gap(), Some(self.content_size.width()));
+        let row_gap = resolve(self.style.row_gap(), self.definite_height);
+        if self.row {
+            (column_gap, row_gap)
+        } else {
+            (row_gap, column_gap)
+        }
+    }
+
+    fn item(&self, content: ItemContent) -> FlexItem {
+        let style = match content.style() {
+            Some(style) => style,
+            None => {
+                return FlexItem {
+                    content,
+                    order: 0,
+                    flex_grow: 0.0,
+                    flex_shrink: 1.0,
+                    align: self.style.align_items(),
+                    main_margin: [Some(0), Some(0)],
+                    cross_margin: [Some(0), Some(0)],
+                    main_edges: 0,
+                    cross_edges: 0,
+                    auto_cross_size: true,
+                    base: 0.0,
+                    target: 0.0,
+                    frozen: false,
+                    main: 0,
+                    cross: 0,
+                    forced_cross: None,
+                }
+            }
+        };
+        let width = self.content_size.width();
+        // Percentages of margins and paddings refer to the width of the containing block, and
+        // auto margins are resolved when the items are aligned.
+        let margin = |side: Side| match style.margin(side) {
+            LengthPercentageOrAuto::Auto => None,
+            margin => Some(margin.resolve_or_zero(width)),
+        };
+        let edges = |sides: [Side; 2]| -> i64 {
+            sides
+                .iter()
+                .map(|side| style.padding(*side).resolve(width) + style.border_width(*side))
+                .sum()
+        };
+        let (main_sides, cross_sides) = if self.row {
+            ([Side::Left, Side::Right], [Side::Top, Side::Bottom])
+        } else {
+            ([Side::Top, Side::Bottom], [Side::Left, Side::Right])
+        };
+        let cross_size = if self.row {
+            style.height()
+        } else {
+            style.width()
+        };
+        FlexItem {
+            content,
+            order: style.order(),
+            flex_grow: style.flex_grow(),
+            flex_shrink: style.flex_shrink(),
+            align: style.align_self().resolve(self.style.align_items()),
+            main_margin: [margin(main_sides[0]), margin(main_sides[1])],
+            cross_margin: [margin(cross_sides[0]), margin(cross_sides[1])],
+            main_edges: edges(main_sides),
+            cross_edges: edges(cross_sides),
+            auto_cross_size: cross_size.is_auto(),
+            base: 0.0,
+            target: 0.0,
+            frozen: false,
+            main: 0,
+            cross: 0,
+            forced_cross: None,
+        }
+    }
+
+    /// Collects the flex items in `order`.
+    fn collect_items(&self, container: &LayoutObject) -> Vec<FlexItem> {
+        let mut items: Vec<_> = ItemContent::collect(container, self.font_metrics)
+            .into_iter()
+            .map(|content| self.item(content))
+            .collect();
+        // https://www.w3.org/TR/css-flexbox-1/#order-property
+        // "Flex items are displayed and laid out starting from the lowest numbered ordinal
+        // group and going up. Items with the same ordinal group are laid out in the order they
+        // appear in the source document."
+        items.sort_by_key(|item| item.order);
+        items
+    }
+
+    /// Lays out an item whose content box has `main` and `cross` sizes when they are given, and
+    /// returns the sizes of the content box in the main and the cross axes.
+    fn layout_item(&self, item: &FlexItem, main: Option<i64>, cross: Option<i64>) -> (i64, i64) {
+        let (width, height) = if self.row {
+            (main, cross)
+        } else {
+            (cross, main)
+        };
+        let size = item.content.layout(
+            &self.style,
+            self.content_size,
+            self.definite_height.is_some(),
+            self.font_metrics,
+            width,
+            height,
+        );
+        if self.row {
+            (size.width(), size.height())
+        } else {
+            (size.height(), size.width())
+        }
+    }
+
+    /// https://www.w3.org/TR/css-flexbox-1/#algo-cross-item
+    /// The width of a column item before it's stretched. An auto width is fit-content, which is
+    /// the max-content width limited to the content box of the container.
+    fn hypothetical_column_width(&self, item: &FlexItem) -> Option<i64> {
+        if !item.auto_cross_size {
+            return None;
+        }
+        let margins = item
+            .cross_margin
+            .iter()
+            .map(|m| m.unwrap_or(0))
+            .sum::<i64>();
+        let available = (self.content_size.width() - margins - item.cross_edges).max(0);
+        Some(
+            item.content
+                .max_content_width(self.font_metrics)
+                .min(available),
+        )
+    }
+
+    /// https://www.w3.org/TR/css-flexbox-1/#algo-main-item
+    /// Determines the flex base size of an item.
+    fn flex_base_size(&self, item: &FlexItem) -> i64 {
+        let style = match item.content.style() {
+            Some(style) => style,
+            None => return self.content_base_size(item),
+        };
+        // "When specified on a flex item, the auto keyword retrieves the value of the main size
+        // property as the used flex-basis. If that value is itself auto, then the used value is
+        // content."
+        let basis = match style.flex_basis() {
+            FlexBasis::Auto => {
+                let size = if self.row {
+                    style.width()
+                } else {
+                    style.height()
+                };
+                match size {
+                    LengthPercentageOrAuto::LengthPercentage(size) => {
+                        FlexBasis::LengthPercentage(size)
+                    }
+                    LengthPercentageOrAuto::Auto => FlexBasis::Content,
+                }
+            }
+            basis => basis,
+        };
+        match basis {
+            // A percentage of an indefinite size is treated as content.
+            FlexBasis::LengthPercentage(basis)
+                if !basis.has_percentage() || self.main_size().is_some() =>
+            {
+                let basis = basis.resolve(self.main_size().unwrap_or(0));
+                match style.box_sizing() {
+                    BoxSizing::ContentBox => basis.max(0),
+                    BoxSizing::BorderBox => (basis - item.main_edges).max(0),
+                }
+            }
+            _ => self.content_base_size(item),
+        }
+    }
+
+    /// The max-content size of an item in the main axis.
+    fn content_base_size(&self, item: &FlexItem) -> i64 {
+        if self.row {
+            item.content.max_content_width(self.font_metrics)
+        } else {
+            self.layout_item(item, None, item.forced_cross).0
+        }
+    }
+
+    /// https://www.w3.org/TR/css-flexbox-1/#algo-line-break
+    /// Collects the items into flex lines. A single-line container puts all of them on one
+    /// line, and a multi-line one starts a new line when the next item doesn't fit.
+    fn collect_lines(&self, items: &[FlexItem], main_gap: i64) -> Vec<Range<usize>> {
+        let available = match self.style.flex_wrap() {
+            FlexWrap::Nowrap => None,
+            FlexWrap::Wrap | FlexWrap::WrapReverse => self.main_size(),
+        };
+        let mut lines = Vec::new();
+        let (mut start, mut used) = (0, 0.0);
+        for (i, item) in items.iter().enumerate() {
+            let size = item.outer_base();
+            if i > start && available.is_some_and(|a| used + main_gap as f64 + size > a as f64) {
+                lines.push(start..i);
+                start = i;
+                used = size;
+            } else if i == start {
+                used = size;
+            } else {
+                used += main_gap as f64 + size;
+            }
+        }
+        if start < items.len() {
+            lines.push(start..items.len());
+        }
+        lines
+    }
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#resolve-flexible-lengths
+/// Resolves the main sizes of the items on a line of `main_size`, and sets their targets.
+fn resolve_flexible_lengths(items: &mut [FlexItem], main_size: i64, main_gap: i64) {
+    let gaps = main_gap * (items.len() as i64 - 1).max(0);
+    let outer_edges: i64 = items.iter().map(|i| i.outer_main_edges()).sum();
+    let available = (main_size - gaps - outer_edges) as f64;
+    // "If the sum of the outer hypothetical main sizes of all items on the line is less than
+    // the flex container's inner main size, use the flex grow factor for the rest of this
+    // algorithm; otherwise, use the flex shrink factor."
+    let grow = items.iter().map(|i| i.base).sum::<f64>() < available;
+    // "Size inflexible items."
+    for item in items.iter_mut() {
+        item.target = item.base;
+        item.frozen = item.flex_factor(grow) == 0.0;
+    }
+    let free_space = |items: &[FlexItem]| available - items.iter().map(|i| i.target).sum::<f64>();
+    let initial_free_space = free_space(items);
+
+    while items.iter().any(|i| !i.frozen) {
+        for item in items.iter_mut().filter(|i| !i.frozen) {
+            item.target = item.base;
+        }
+        let mut remaining = free_space(items);
+        // "If the sum of the unfrozen flex items' flex factors is less than one, multiply the
+        // initial free space by this sum. If the magnitude of this value is less than the
+        // magnitude of the remaining free space, use this as the remaining free space."
+        let factors: f64 = items
+            .iter()
+            .filter(|i| !i.frozen)
+            .map(|i| i.flex_factor(grow))
+            .sum();
+        if factors < 1.0 && (initial_free_space * factors).abs() < remaining.abs() {
+            remaining = initial_free_space * factors;
+        }
+        // "Distribute free space proportional to the flex factors."
+        if remaining != 0.0 {
+            if grow {
+                for item in items.iter_mut().filter(|i| !i.frozen) {
+                    item.target = item.base + remaining * item.flex_grow / factors;
+                }
+            } else {
+                // "For every unfrozen item on the line, multiply its flex shrink factor by its
+                // inner flex base size, and note this as its scaled flex shrink factor."
+                let scaled: f64 = items
+                    .iter()
+                    .filter(|i| !i.frozen)
+                    .map(|i| i.flex_shrink * i.base)
+                    .sum();
+                if scaled > 0.0 {
+                    for item in items.iter_mut().filter(|i| !i.frozen) {
+                        item.target =
+                            item.base - remaining.abs() * item.flex_shrink * item.base / scaled;
+                    }
+                }
+            }
+        }
+        // "Fix min/max violations." The content box can't be negative. The min violations are
+        // frozen at 0, or all the items are frozen if there is no violation.
+        let mut violation = false;
+        for item in items.iter_mut().filter(|i| !i.frozen) {
+            if item.target < 0.0 {
+                item.target = 0.0;
+                item.frozen = true;
+                violation = true;
+            }
+        }
+        if !violation {
+            for item in items.iter_mut() {
+                item.frozen = true;
+            }
+        }
+    }
+
+    // The sizes are rounded so that the outer sizes add up to the rounded sum of them.
+    let mut sum = 0.0;
+    for item in items.iter_mut() {
+        let start = round(sum);
+        sum += item.target + item.outer_main_edges() as f64;
+        item.main = (round(sum) - start - item.outer_main_edges()).max(0);
+    }
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#main-alignment
+/// Resolves the auto margins in the main axis, and returns the position of the outer edge of
+/// the first item from the main-start edge and the space added between the items.
+fn justify_line(
+    items: &mut [FlexItem],
+    main_size: i64,
+    main_gap: i64,
+    reverse: bool,
+    justify: JustifyContent,
+) -> (f64, f64) {
+    let gaps = main_gap * (items.len() as i64 - 1).max(0);
+    let used: i64 = items.iter().map(|i| i.main + i.outer_main_edges()).sum();
+    let free = main_size - gaps - used;
+    // "If the remaining free space is positive and at least one main-axis margin on this line
+    // is auto, distribute the free space equally among these margins."
+    let auto_margins = items
+        .iter()
+        .flat_map(|i| i.main_margin.iter())
+        .filter(|m| m.is_none())
+        .count() as i64;
+    let share = if auto_margins > 0 {
+        free.max(0) / auto_margins
+    } else {
+        0
+    };
+    for item in items.iter_mut() {
+        for margin in item.main_margin.iter_mut() {
+            margin.get_or_insert(share);
+        }
+    }
+    if auto_margins > 0 {
+        return (0.0, 0.0);
+    }
+
+    let (free, n) = (free as f64, items.len() as f64);
+    // start and end are the edges in the writing mode, which are the main-end and main-start
+    // edges when the direction is reversed.
+    let justify = match justify {
+        JustifyContent::Start if reverse => JustifyContent::FlexEnd,
+        JustifyContent::End if !reverse => JustifyContent::FlexEnd,
+        justify => justify,
+    };
+    match justify {
+        JustifyContent::FlexEnd => (free, 0.0),
+        JustifyContent::Center => (free / 2.0, 0.0),
+        JustifyContent::SpaceBetween if free > 0.0 && n > 1.0 => (0.0, free / (n - 1.0)),
+        JustifyContent::SpaceAround | JustifyContent::SpaceEvenly if free < 0.0 => {
+            (free / 2.0, 0.0)
+        }
+        JustifyContent::SpaceAround => (free / n / 2.0, free / n),
+        JustifyContent::SpaceEvenly => (free / (n + 1.0), free / (n + 1.0)),
+        _ => (0.0, 0.0),
+    }
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#cross-alignment
+/// Resolves the auto margins of an item in the cross axis, and returns the position of its
+/// outer edge from the low edge of a line of `line_cross`.
+fn align_item(item: &mut FlexItem, line_cross: i64, wrap_reverse: bool) -> i64 {
+    let free = line_cross - item.outer_cross();
+    match item.cross_margin {
+        [None, None] => {
+            let low = free.max(0) / 2;
+            item.cross_margin = [Some(low), Some(free.max(0) - low)];
+            return 0;
+        }
+        [None, Some(_)] => {
+            item.cross_margin[0] = Some(free.max(0));
+            return 0;
+        }
+        [Some(_), None] => {
+            item.cross_margin[1] = Some(free.max(0));
+            return 0;
+        }
+        _ => {}
+    }
+    // "flex-start" and "flex-end" are swapped in a wrap-reverse container, where the cross-start
+    // edge is the high edge.
+    match item.align {
+        AlignItems::FlexStart if wrap_reverse => free,
+        AlignItems::FlexEnd if wrap_reverse => 0,
+        AlignItems::FlexEnd | AlignItems::End => free,
+        AlignItems::Center => free / 2,
+        _ => 0,
+    }
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#layout-algorithm
+/// Lays out the flex items of `container`, whose content box is `content_size` and whose
+/// height is specified if `definite_height` is given. The offsets of the items are set, and the
+/// height of the content is returned.
+pub fn layout_flex_items(
+    container: &LayoutObject,
+    content_size: LayoutSize,
+    definite_height: Option<i64>,
+    font_metrics: &dyn FontMetrics,
+) -> i64 {
+    let style = container.style();
+    let direction = style.flex_direction();
+    let flex = FlexLayout {
+        font_metrics,
+        style,
+        content_size,
+        definite_height,
+        row: direction.is_row(),
+    };
+    let (main_gap, cross_gap) = flex.gaps();
+    let mut items = flex.collect_items(container);
+
+    for item in items.iter_mut() {
+        if !flex.row {
+            item.forced_cross = flex.hypothetical_column_width(item);
+        }
+        item.base = flex.flex_base_size(item) as f64;
+    }
+    let lines = flex.collect_lines(&items, main_gap);
+
+    // The main size of the container is the sum of the items when it's not definite.
+    let main_size = flex.main_size().unwrap_or_else(|| {
+        let sizes: f64 = items.iter().map(|i| i.outer_base()).sum();
+        round(sizes) + main_gap * (items.len() as i64 - 1).max(0)
+    });
+    for line in &lines {
+        resolve_flexible_lengths(&mut items[line.clone()], main_size, main_gap);
+    }
+
+    // https://www.w3.org/TR/css-flexbox-1/#algo-cross-line
+    // "If the flex container is single-line and has a definite cross size, the cross size of
+    // the flex line is the flex container's inner cross size." Otherwise it's the largest outer
+    // hypothetical cross size of the items.
+    for item in items.iter_mut() {
+        item.cross = flex.layout_item(item, Some(item.main), item.forced_cross).1;
+    }
+    let mut line_crosses: Vec<i64> = lines
+        .iter()
+        .map(|line| {
+            items[line.clone()]
+                .iter()
+                .map(|i| i.outer_cross())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let single_line = flex.style.flex_wrap() == FlexWrap::Nowrap;
+    match flex.cross_size() {
+        Some(cross_size) if single_line => line_crosses = vec![cross_size],
+        // The initial value of align-content, normal, stretches the lines to fill the
+        // container.
+        Some(cross_size) => {
+            let used: i64 = line_crosses.iter().sum::<i64>()
+                + cross_gap * (line_crosses.len() as i64 - 1).max(0);
+            let free = cross_size - used;
+            if free > 0 {
+                let n = line_crosses.len() as i64;
+                for (i, cross) in line_crosses.iter_mut().enumerate() {
+                    let i = i as i64;
+                    *cross += free * (i + 1) / n - free * i / n;
+                }
+            }
+        }
+        None => {}
+    }
+    let cross_size = flex.cross_size().unwrap_or_else(|| {
+        line_crosses.iter().sum::<i64>() + cross_gap * (line_crosses.len() as i64 - 1).max(0)
+    });
+
+    // https://www.w3.org/TR/css-flexbox-1/#algo-stretch
+    // "If a flex item has align-self: stretch, its computed cross size property is auto, and
+    // neither of its cross-axis margins are auto, the used outer cross size is the used cross
+    // size of its flex line."
+    for (line, line_cross) in lines.iter().zip(&line_crosses) {
+        for item in items[line.clone()].iter_mut() {
+            let stretch = matches!(item.align, AlignItems::Normal | AlignItems::Stretch)
+                && item.auto_cross_size
+                && item.cross_margin.iter().all(|m| m.is_some());
+            let cross = line_cross - item.outer_cross() + item.cross;
+            if stretch && cross.max(0) != item.cross {
+                item.cross = cross.max(0);
+                flex.layout_item(item, Some(item.main), Some(item.cross));
+            }
+        }
+    }
+
+    let reverse = direction.is_reverse();
+    let wrap_reverse = flex.style.flex_wrap() == FlexWrap::WrapReverse;
+    let mut line_position = 0;
+    for (line, line_cross) in lines.iter().zip(&line_crosses) {
+        let line_items = &mut items[line.clone()];
+        let (start, between) = justify_line(
+            line_items,
+            main_size,
+            main_gap,
+            reverse,
+            flex.style.justify_content(),
+        );
+        // The lines are stacked from the cross-end edge in a wrap-reverse container.
+        let line_low = if wrap_reverse {
+            cross_size - line_position - line_cross
+        } else {
+            line_position
+        };
+        let mut position = start;
+        for item in line_items.iter_mut() {
+            let outer_main = item.main + item.outer_main_edges();
+            let main_low = if reverse {
+                main_size - round(position) - outer_main
+            } else {
+                round(position)
+            };
+            let cross_low = line_low + align_item(item, *line_cross, wrap_reverse);
+            let main_offset = main_low + item.main_margin[0].unwrap_or(0);
+            let cross_offset = cross_low + item.cross_margin[0].unwrap_or(0);
+            let offset = if flex.row {
+                LayoutPoint::new(main_offset, cross_offset)
+            } else {
+                LayoutPoint::new(cross_offset, main_offset)
+            };
+            item.content.set_offset(offset);
+            position += (outer_main + main_gap) as f64 + between;
+        }
+        line_position += line_cross + cross_gap;
+    }
+
+    if flex.row {
+        cross_size
+    } else {
+        main_size
+    }
+}
+
+/// https://www.w3.org/TR/css-flexbox-1/#intrinsic-main-sizes
+/// The max-content width of a flex container. The items of a row are put side by side, and a
+/// column is as wide as its widest item.
+pub fn max_content_width(container: &LayoutObject, font_metrics: &dyn FontMetrics) -> i64 {
+    let style = container.style();
+    let row = style.flex_direction().is_row();
+    let mut widths = Vec::new();
+    let mut text = 0;
+    let mut child = container.first_child();
+    while let Some(c) = child {
+        let c_ref = c.borrow();
+        if c_ref.kind() == LayoutObjectKind::Text {
+            text += c_ref.max_content_width(font_metrics);
+        } else if !c_ref.is_out_of_flow() {
+            if text > 0 {
+                widths.push(text);
+                text = 0;
+            }
+            widths.push(c_ref.outer_max_content_width(font_metrics));
+        }
+        child = c_ref.next_sibling();
+    }
+    if text > 0 {
+        widths.push(text);
+    }
+    if row {
+        let gap = style.column_gap();
+        let gap = if gap.has_percentage() {
+            0
+        } else {
+            gap.resolve(0)
+        };
+        widths.iter().sum::<i64>() + gap * (widths.len() as i64 - 1).max(0)
+    } else {
+        widths.into_iter().max().unwrap_or(0)
+    }
+}
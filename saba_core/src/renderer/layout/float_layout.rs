@@ -0,0 +1,173 @@
+//! https://www.w3.org/TR/CSS2/visuren.html#floats
+//! Floats in a block formatting context. A float is shifted to the left or the right as far as
+//! it can go, and the line boxes next to it are shortened to make room for it. The positions are
+//! in the coordinate space of the content box of the box that establishes the block formatting
+//! context.
+
+use crate::renderer::layout::computed_style::Clear;
+use crate::renderer::layout::computed_style::Float;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
+use alloc::vec::Vec;
+
+/// The margin box of a placed float.
+#[derive(Debug, Clone, PartialEq)]
+struct FloatBox {
+    side: Float,
+    point: LayoutPoint,
+    size: LayoutSize,
+}
+
+impl FloatBox {
+    fn top(&self) -> i64 {
+        self.point.y()
+    }
+
+    fn bottom(&self) -> i64 {
+        self.point.y() + self.size.height()
+    }
+
+    /// Whether the float is next to something from `top` to `top + height`. An empty range
+    /// is treated as a line of height 1.
+    fn intersects(&self, top: i64, height: i64) -> bool {
+        self.top() < top + height.max(1) && top < self.bottom()
+    }
+}
+
+/// Keeps the floats placed in a block formatting context.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FloatManager {
+    floats: Vec<FloatBox>,
+}
+
+impl FloatManager {
+    pub fn new() -> Self {
+        Self { floats: Vec::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.floats.is_empty()
+    }
+
+    /// Returns the left and right edges of the space between `left` and `right` that isn't taken
+    /// by the floats next to a box from `top` to `top + height`.
+    pub fn available_space(&self, top: i64, height: i64, left: i64, right: i64) -> (i64, i64) {
+        let (mut left, mut right) = (left, right);
+        for float in self.floats.iter().filter(|f| f.intersects(top, height)) {
+            match float.side {
+                Float::Left => left = left.max(float.point.x() + float.size.width()),
+                Float::Right => right = right.min(float.point.x()),
+                Float::None => {}
+            }
+        }
+        (left, right)
+    }
+
+    /// Returns the nearest bottom edge of a float below `top`, where the available space may
+    /// become wider.
+    pub fn next_band(&self, top: i64) -> Option<i64> {
+        self.floats
+            .iter()
+            .map(|f| f.bottom())
+            .filter(|bottom| *bottom > top)
+            .min()
+    }
+
+    /// https://www.w3.org/TR/CSS2/visuren.html#float-rules
+    /// Places the margin box of `size` floated to `side` between `left` and `right`, and returns
+    /// its top-left corner. It's put as high as possible but not above `top`, and as far to the
+    /// side as possible. It's moved down until it fits next to the other floats.
+    pub fn place(
+        &mut self,
+        side: Float,
+        size: LayoutSize,
+        top: i64,
+        left: i64,
+        right: i64,
+    ) -> LayoutPoint {
+        // "The outer top of a floating box may not be higher than the outer top of any block or
+        // floated box generated by an element earlier in the source document."
+        let mut top = self.floats.iter().map(|f| f.top()).fold(top, i64::max);
+        loop {
+            let (l, r) = self.available_space(top, size.height(), left, right);
+            let next = self.next_band(top);
+            // A float that is wider than the containing block overflows it.
+            if size.width() <= r - l || next.is_none() {
+                let x = match side {
+                    Float::Right => r - size.width(),
+                    Float::Left | Float::None => l,
+                };
+                let point = LayoutPoint::new(x, top);
+                self.floats.push(FloatBox { side, point, size });
+                return point;
+            }
+            top = next.unwrap_or(top);
+        }
+    }
+
+    /// https://www.w3.org/TR/CSS2/visuren.html#flow-control
+    /// Returns the bottom edge of the floats that a box with `clear` has to be placed below.
+    pub fn clearance(&self, clear: Clear) -> Option<i64> {
+        self.floats
+            .iter()
+            .filter(|f| clear.clears(f.side))
+            .map(|f| f.bottom())
+            .max()
+    }
+
+    /// The bottom edge of the lowest float. A box that establishes a block formatting context is
+    /// tall enough to contain its floats.
+    /// https://www.w3.org/TR/CSS2/visudet.html#root-height
+    pub fn bottom(&self) -> Option<i64> {
+        self.floats.iter().map(|f| f.bottom()).max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_place() {
+        let mut floats = FloatManager::new();
+        assert!(floats.is_empty());
+        assert_eq!((0, 100), floats.available_space(0, 20, 0, 100));
+
+        assert_eq!(
+            LayoutPoint::new(0, 0),
+            floats.place(Float::Left, LayoutSize::new(30, 50), 0, 0, 100)
+        );
+        assert_eq!(
+            LayoutPoint::new(80, 10),
+            floats.place(Float::Right, LayoutSize::new(20, 20), 10, 0, 100)
+        );
+        assert_eq!((30, 80), floats.available_space(10, 20, 0, 100));
+        assert_eq!((30, 100), floats.available_space(30, 20, 0, 100));
+        assert_eq!((0, 100), floats.available_space(50, 20, 0, 100));
+
+        // A float that doesn't fit next to the others is moved down, but not above the others.
+        assert_eq!(
+            LayoutPoint::new(30, 30),
+            floats.place(Float::Left, LayoutSize::new(60, 10), 0, 0, 100)
+        );
+        assert_eq!(Some(40), floats.next_band(35));
+        assert_eq!(None, floats.next_band(50));
+
+        assert_eq!(Some(50), floats.clearance(Clear::Left));
+        assert_eq!(Some(30), floats.clearance(Clear::Right));
+        assert_eq!(Some(50), floats.clearance(Clear::Both));
+        assert_eq!(None, floats.clearance(Clear::None));
+        assert_eq!(Some(50), floats.bottom());
+    }
+
+    #[test]
+    fn test_place_too_wide() {
+        let mut floats = FloatManager::new();
+        floats.place(Float::Left, LayoutSize::new(50, 50), 0, 0, 100);
+        // A float wider than the space goes below the others, where it overflows.
+        assert_eq!(
+            LayoutPoint::new(-20, 50),
+            floats.place(Float::Right, LayoutSize::new(120, 10), 0, 0, 100)
+        );
+    }
+}
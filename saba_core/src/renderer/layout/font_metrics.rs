@@ -0,0 +1,418 @@
+//! https://www.w3.org/TR/css-fonts-4/
+//! Text measurement. Layout asks a FontMetrics how wide each grapheme cluster of a text is and
+//! how far glyphs extend above and below the baseline, so that the fonts used to paint text can be
+//! swapped without touching the layout code.
+
+use crate::constants::CHAR_HEIGHT;
+use crate::constants::CHAR_WIDTH;
+use crate::error::Error;
+use crate::renderer::layout::computed_style::Font;
+use crate::renderer::layout::unicode::graphemes;
+use crate::renderer::layout::unicode::is_wide_cluster;
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+/// Measures text set in a font. Lengths are in px for the size of `font`.
+pub trait FontMetrics: Debug {
+    /// The advance width of a grapheme cluster.
+    fn advance(&self, cluster: &str, font: &Font) -> i64;
+
+    /// The distance from the top of the content area to the baseline.
+    fn ascent(&self, font: &Font) -> i64;
+
+    /// The distance from the baseline to the bottom of the content area.
+    fn descent(&self, font: &Font) -> i64;
+
+    /// https://www.w3.org/TR/css-values-4/#ex
+    /// The height of lowercase letters, which vertical-align: middle uses.
+    fn x_height(&self, font: &Font) -> i64 {
+        font.size() / 2
+    }
+
+    /// The width of `text`, which is the sum of the advances of its grapheme clusters.
+    fn text_width(&self, text: &str, font: &Font) -> i64 {
+        graphemes(text).map(|g| self.advance(g, font)).sum()
+    }
+}
+
+/// The metrics of the built-in bitmap font of the UI. Its glyphs are CHAR_WIDTH wide at
+/// CHAR_HEIGHT px and scale with the font size. East Asian wide characters take up two columns,
+/// as they do in a terminal.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct MonospaceFontMetrics;
+
+impl MonospaceFontMetrics {
+    /// The width of a narrow glyph at `font_size` px.
+    pub fn column_width(font_size: i64) -> i64 {
+        (CHAR_WIDTH * font_size / CHAR_HEIGHT).max(1)
+    }
+}
+
+impl FontMetrics for MonospaceFontMetrics {
+    fn advance(&self, cluster: &str, font: &Font) -> i64 {
+        let columns = if is_wide_cluster(cluster) { 2 } else { 1 };
+        Self::column_width(font.size()) * columns
+    }
+
+    /// The glyphs have their baseline at 4/5 of their height.
+    fn ascent(&self, font: &Font) -> i64 {
+        font.size() * 4 / 5
+    }
+
+    fn descent(&self, font: &Font) -> i64 {
+        font.size() - self.ascent(font)
+    }
+}
+
+/// https://learn.microsoft.com/en-us/typography/opentype/spec/otff
+/// The metrics of a TrueType or OpenType font file, read from its head, hhea, hmtx and cmap
+/// tables. This lets an embedder bundle a font with include_bytes! and lay text out with the
+/// advances of the glyphs it paints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrueTypeFontMetrics {
+    data: Vec<u8>,
+    units_per_em: i64,
+    ascender: i64,
+    descender: i64,
+    x_height: Option<i64>,
+    /// The advance widths of the glyphs from the hmtx table. Glyphs after the last one share its
+    /// advance.
+    advances: Vec<u16>,
+    /// The offset of the cmap subtable that maps Unicode code points to glyphs.
+    cmap: usize,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, Error> {
+    match data.get(offset..offset + 2) {
+        Some(b) => Ok(u16::from_be_bytes([b[0], b[1]])),
+        None => Err(Error::UnexpectedInput(format!(
+            "font data ends at {} while reading offset {}",
+            data.len(),
+            offset
+        ))),
+    }
+}
+
+fn read_i16(data: &[u8], offset: usize) -> Result<i16, Error> {
+    read_u16(data, offset).map(|v| v as i16)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    Ok(((read_u16(data, offset)? as u32) << 16) | read_u16(data, offset + 2)? as u32)
+}
+
+impl TrueTypeFontMetrics {
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Error> {
+        // https://learn.microsoft.com/en-us/typography/opentype/spec/otff#table-directory
+        let version = read_u32(&data, 0)?;
+        if version != 0x0001_0000 && &data[0..4] != b"true" && &data[0..4] != b"OTTO" {
+            return Err(Error::UnexpectedInput(format!(
+                "unsupported sfnt version {:#x}",
+                version
+            )));
+        }
+        let num_tables = read_u16(&data, 4)? as usize;
+        let table = |tag: &[u8; 4]| -> Result<usize, Error> {
+            for i in 0..num_tables {
+                let record = 12 + i * 16;
+                if data.get(record..record + 4) == Some(tag) {
+                    return Ok(read_u32(&data, record + 8)? as usize);
+                }
+            }
+            Err(Error::UnexpectedInput(format!(
+                "font has no {} table",
+                core::str::from_utf8(tag).unwrap_or("?")
+            )))
+        };
+
+        // https://learn.microsoft.com/en-us/typography/opentype/spec/head
+        let head = table(b"head")?;
+        let units_per_em = read_u16(&data, head + 18)? as i64;
+        if units_per_em == 0 {
+            return Err(Error::UnexpectedInput("unitsPerEm is 0".into()));
+        }
+
+        // https://learn.microsoft.com/en-us/typography/opentype/spec/hhea
+        let hhea = table(b"hhea")?;
+        let ascender = read_i16(&data, hhea + 4)? as i64;
+        let descender = read_i16(&data, hhea + 6)? as i64;
+        let number_of_h_metrics = read_u16(&data, hhea + 34)? as usize;
+
+        // https://learn.microsoft.com/en-us/typography/opentype/spec/hmtx
+        let hmtx = table(b"hmtx")?;
+        let advances = (0..number_of_h_metrics)
+            .map(|i| read_u16(&data, hmtx + i * 4))
+            .collect::<Result<Vec<u16>, Error>>()?;
+        if advances.is_empty() {
+            return Err(Error::UnexpectedInput("numberOfHMetrics is 0".into()));
+        }
+
+        // https://learn.microsoft.com/en-us/typography/opentype/spec/os2#sxheight
+        // sxHeight is only in version 2 and later of the OS/2 table.
+        let x_height = match table(b"OS/2") {
+            Ok(os2) if read_u16(&data, os2)? >= 2 => Some(read_i16(&data, os2 + 86)? as i64),
+            _ => None,
+        };
+
+        // https://learn.microsoft.com/en-us/typography/opentype/spec/cmap
+        // The subtables for the full Unicode repertoire are preferred to the ones for the BMP.
+        let cmap = table(b"cmap")?;
+        let mut subtable = None;
+        for i in 0..read_u16(&data, cmap + 2)? as usize {
+            let record = cmap + 4 + i * 8;
+            let platform = read_u16(&data, record)?;
+            let encoding = read_u16(&data, record + 2)?;
+            let offset = cmap + read_u32(&data, record + 4)? as usize;
+            let format = read_u16(&data, offset)?;
+            let unicode = platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10));
+            match format {
+                12 if unicode => subtable = Some(offset),
+                4 if unicode && subtable.is_none() => subtable = Some(offset),
+                _ => {}
+            }
+        }
+        let cmap = match subtable {
+            Some(offset) => offset,
+            None => {
+                return Err(Error::UnexpectedInput(
+                    "font has no Unicode cmap subtable of format 4 or 12".into(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            data,
+            units_per_em,
+            ascender,
+            descender,
+            x_height,
+            advances,
+            cmap,
+        })
+    }
+
+    /// Returns the glyph of `c`, which is 0, the missing glyph, if the font doesn't have it.
+    fn glyph_id(&self, c: char) -> usize {
+        self.lookup(c as u32).unwrap_or(0)
+    }
+
+    fn lookup(&self, c: u32) -> Result<usize, Error> {
+        let data = &self.data;
+        let subtable = self.cmap;
+        match read_u16(data, subtable)? {
+            // https://learn.microsoft.com/en-us/typography/opentype/spec/cmap#format-4-segment-mapping-to-delta-values
+            4 => {
+                if c > 0xFFFF {
+                    return Ok(0);
+                }
+                let seg_count = read_u16(data, subtable + 6)? as usize / 2;
+                let end_codes = subtable + 14;
+                let start_codes = end_codes + seg_count * 2 + 2;
+                let id_deltas = start_codes + seg_count * 2;
+                let id_range_offsets = id_deltas + seg_count * 2;
+                for i in 0..seg_count {
+                    if (read_u16(data, end_codes + i * 2)? as u32) < c {
+                        continue;
+                    }
+                    let start = read_u16(data, start_codes + i * 2)? as u32;
+                    if start > c {
+                        return Ok(0);
+                    }
+                    let delta = read_u16(data, id_deltas + i * 2)?;
+                    let range_offset = read_u16(data, id_range_offsets + i * 2)? as usize;
+                    if range_offset == 0 {
+                        return Ok(delta.wrapping_add(c as u16) as usize);
+                    }
+                    let address =
+                        id_range_offsets + i * 2 + range_offset + (c - start) as usize * 2;
+                    let glyph = read_u16(data, address)?;
+                    return Ok(if glyph == 0 {
+                        0
+                    } else {
+                        glyph.wrapping_add(delta) as usize
+                    });
+                }
+                Ok(0)
+            }
+            // https://learn.microsoft.com/en-us/typography/opentype/spec/cmap#format-12-segmented-coverage
+            12 => {
+                let groups = read_u32(data, subtable + 12)? as usize;
+                for i in 0..groups {
+                    let group = subtable + 16 + i * 12;
+                    let start = read_u32(data, group)?;
+                    let end = read_u32(data, group + 4)?;
+                    if (start..=end).contains(&c) {
+                        return Ok((read_u32(data, group + 8)? + (c - start)) as usize);
+                    }
+                }
+                Ok(0)
+            }
+            format => Err(Error::UnexpectedInput(format!(
+                "unsupported cmap format {}",
+                format
+            ))),
+        }
+    }
+
+    /// Converts font units to px at the size of `font`.
+    fn scale(&self, units: i64, font: &Font) -> i64 {
+        units * font.size() / self.units_per_em
+    }
+}
+
+impl FontMetrics for TrueTypeFontMetrics {
+    /// The advance of the base character of the cluster. Combining marks are drawn over it, and
+    /// emoji sequences are drawn as one glyph, so they don't add to the width.
+    fn advance(&self, cluster: &str, font: &Font) -> i64 {
+        let c = match cluster.chars().next() {
+            Some(c) => c,
+            None => return 0,
+        };
+        let glyph = self.glyph_id(c);
+        let advance = self
+            .advances
+            .get(glyph)
+            .or(self.advances.last())
+            .copied()
+            .unwrap_or(0);
+        self.scale(advance as i64, font)
+    }
+
+    fn ascent(&self, font: &Font) -> i64 {
+        self.scale(self.ascender, font)
+    }
+
+    fn descent(&self, font: &Font) -> i64 {
+        self.scale(-self.descender, font)
+    }
+
+    fn x_height(&self, font: &Font) -> i64 {
+        match self.x_height {
+            Some(x_height) => self.scale(x_height, font),
+            None => font.size() / 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::layout::computed_style::FontStyle;
+    use alloc::vec;
+
+    fn font(size: i64) -> Font {
+        Font::new(Vec::new(), size, 400, FontStyle::Normal)
+    }
+
+    /// Builds a font with 1000 units per em whose cmap maps 'A'..='C' to glyphs 1..=3 with a
+    /// format 4 subtable, and also U+3042 to glyph 4 if it has a format 12 subtable.
+    fn true_type_font(format12: bool) -> Vec<u8> {
+        fn u16s(values: &[u16]) -> Vec<u8> {
+            values.iter().flat_map(|v| v.to_be_bytes()).collect()
+        }
+
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+        let mut hhea = vec![0; 36];
+        hhea[4..6].copy_from_slice(&800i16.to_be_bytes());
+        hhea[6..8].copy_from_slice(&(-200i16).to_be_bytes());
+        hhea[34..36].copy_from_slice(&4u16.to_be_bytes());
+        // Glyph 4 and later share the advance of glyph 3.
+        let hmtx = u16s(&[500, 0, 600, 0, 700, 0, 1000, 0]);
+
+        // Segments 'A'..='C' with a delta and the required final segment 0xFFFF.
+        let header = [4, 32, 0, 4, 4, 1, 0];
+        let end_codes = [0x43, 0xFFFF, 0];
+        let start_codes = [0x41, 0xFFFF];
+        let id_deltas = [1u16.wrapping_sub(0x41), 1];
+        let id_range_offsets = [0, 0];
+        let format4 = u16s(
+            &[
+                &header[..],
+                &end_codes,
+                &start_codes,
+                &id_deltas,
+                &id_range_offsets,
+            ]
+            .concat(),
+        );
+        let mut cmap = if format12 {
+            u16s(&[0, 2, 3, 1, 0, 20, 3, 10, 0, 20 + format4.len() as u16])
+        } else {
+            u16s(&[0, 1, 3, 1, 0, 12])
+        };
+        cmap.extend(format4);
+        if format12 {
+            // The groups 'A'..='C' and U+3042.
+            cmap.extend(u16s(&[12, 0, 0, 40, 0, 0, 0, 2]));
+            cmap.extend(u16s(&[0, 0x41, 0, 0x43, 0, 1, 0, 0x3042, 0, 0x3042, 0, 4]));
+        }
+
+        let tables: [(&[u8; 4], Vec<u8>); 4] = [
+            (b"cmap", cmap),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+        ];
+        let mut data = u16s(&[1, 0, tables.len() as u16, 0, 0, 0]);
+        let mut offset = 12 + tables.len() * 16;
+        for (tag, table) in &tables {
+            data.extend(*tag);
+            data.extend([0; 4]);
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += table.len();
+        }
+        for (_, table) in tables {
+            data.extend(table);
+        }
+        data
+    }
+
+    #[test]
+    fn test_monospace() {
+        let metrics = MonospaceFontMetrics;
+        assert_eq!(8, metrics.advance("a", &font(16)));
+        assert_eq!(16, metrics.advance("\u{3042}", &font(16)));
+        assert_eq!(16, metrics.advance("e\u{301}", &font(32)));
+        // Three narrow clusters and two wide ones.
+        assert_eq!(
+            56,
+            metrics.text_width("ae\u{301}b\u{65E5}\u{672C}", &font(16))
+        );
+        assert_eq!(16, metrics.ascent(&font(20)));
+        assert_eq!(4, metrics.descent(&font(20)));
+    }
+
+    #[test]
+    fn test_true_type() {
+        let metrics = TrueTypeFontMetrics::from_bytes(true_type_font(true)).expect("valid font");
+        assert_eq!(12, metrics.advance("A", &font(20)));
+        assert_eq!(14, metrics.advance("B", &font(20)));
+        assert_eq!(14, metrics.advance("B\u{301}", &font(20)));
+        assert_eq!(20, metrics.advance("\u{3042}", &font(20)));
+        // Characters that the font doesn't have get the missing glyph.
+        assert_eq!(10, metrics.advance("z", &font(20)));
+        assert_eq!(42, metrics.text_width("zAC", &font(20)));
+        assert_eq!(16, metrics.ascent(&font(20)));
+        assert_eq!(4, metrics.descent(&font(20)));
+        assert_eq!(10, metrics.x_height(&font(20)));
+    }
+
+    #[test]
+    fn test_true_type_bmp_only() {
+        // Without the format 12 subtable, the format 4 one is used.
+        let bmp_only = TrueTypeFontMetrics::from_bytes(true_type_font(false)).expect("valid font");
+        assert_eq!(14, bmp_only.advance("B", &font(20)));
+        assert_eq!(10, bmp_only.advance("\u{3042}", &font(20)));
+    }
+
+    #[test]
+    fn test_invalid_true_type() {
+        assert!(TrueTypeFontMetrics::from_bytes(Vec::new()).is_err());
+        let mut data = true_type_font(true);
+        data.truncate(40);
+        assert!(TrueTypeFontMetrics::from_bytes(data).is_err());
+    }
+}
@@ -0,0 +1,941 @@
+//! https://www.w3.org/TR/css-grid-1/
+//! Grid layout. The items of a grid container are placed in the cells of a grid of columns and
+//! rows. The sizes of the tracks are decided by their track sizing functions and the contents of
+//! the items in them, and the items are stretched or aligned in their grid areas.
+
+use crate::renderer::css::value::round;
+use crate::renderer::css::value::LengthPercentage;
+use crate::renderer::css::value::LengthPercentageOrAuto;
+use crate::renderer::layout::computed_style::AlignItems;
+use crate::renderer::layout::computed_style::AutoRepeat;
+use crate::renderer::layout::computed_style::ComputedStyle;
+use crate::renderer::layout::computed_style::GridLine;
+use crate::renderer::layout::computed_style::Side;
+use crate::renderer::layout::computed_style::TrackBreadth;
+use crate::renderer::layout::computed_style::TrackSize;
+use crate::renderer::layout::flex_layout::ItemContent;
+use crate::renderer::layout::font_metrics::FontMetrics;
+use crate::renderer::layout::layout_object::LayoutObject;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// The columns are in the axis 0, and the rows are in the axis 1.
+const COLUMN: usize = 0;
+const ROW: usize = 1;
+
+/// The tracks of the grid in one axis.
+#[derive(Debug, Clone)]
+struct Tracks {
+    /// The tracks of the explicit grid.
+    explicit: Vec<TrackSize>,
+    /// The names of the lines of the explicit grid, including the ones of the named areas.
+    line_names: Vec<Vec<String>>,
+    /// The sizes of the implicit tracks after the explicit grid, which are repeated.
+    implicit: Vec<TrackSize>,
+    /// The tracks repeated by auto-fit, which are collapsed when they are empty.
+    auto_fit: Range<usize>,
+    gap: i64,
+}
+
+impl Tracks {
+    fn size(&self, index: usize) -> TrackSize {
+        match self.explicit.get(index) {
+            Some(size) => size.clone(),
+            None if self.implicit.is_empty() => {
+                TrackSize::new(TrackBreadth::Auto, TrackBreadth::Auto)
+            }
+            None => self.implicit[(index - self.explicit.len()) % self.implicit.len()].clone(),
+        }
+    }
+
+    /// https://www.w3.org/TR/css-grid-1/#line-placement
+    /// Resolves a line of a placement property into a line number from 0. A name is looked up
+    /// as `<name>-start` or `<name>-end` first, which are the lines of a named area.
+    fn resolve(&self, line: &GridLine, start: bool) -> ResolvedLine {
+        match line {
+            GridLine::Auto => ResolvedLine::Auto,
+            GridLine::Span(span) => ResolvedLine::Span(*span),
+            GridLine::Line(n) if *n > 0 => ResolvedLine::Line(*n as usize - 1),
+            // Negative numbers count from the last line of the explicit grid. The implicit
+            // tracks before the explicit grid are not supported, so they are clamped to it.
+            GridLine::Line(n) => {
+                ResolvedLine::Line((self.explicit.len() as i64 + 1 + *n as i64).max(0) as usize)
+            }
+            GridLine::Name(name) => {
+                let suffixed = format!("{}-{}", name, if start { "start" } else { "end" });
+                let find = |name: &str| {
+                    self.line_names
+                        .iter()
+                        .position(|names| names.iter().any(|n| n == name))
+                };
+                match find(&suffixed).or_else(|| find(name)) {
+                    Some(line) => ResolvedLine::Line(line),
+                    None => ResolvedLine::Auto,
+                }
+            }
+        }
+    }
+
+    /// https://www.w3.org/TR/css-grid-1/#common-uses-numeric
+    /// Resolves the start and end lines of an item into the tracks it spans if they are
+    /// definite, or into the number of tracks it spans if it's placed automatically.
+    fn placement(&self, (start, end): (GridLine, GridLine)) -> AxisPlacement {
+        let start = self.resolve(&start, true);
+        let end = self.resolve(&end, false);
+        match (start, end) {
+            // "If the placement for a grid item contains two lines, and the start line is further
+            // end-ward than the end line, swap the two lines. If the start line is equal to the
+            // end line, remove the end line."
+            (ResolvedLine::Line(s), ResolvedLine::Line(e)) if s < e => {
+                AxisPlacement::Definite(s..e)
+            }
+            (ResolvedLine::Line(s), ResolvedLine::Line(e)) if e < s => {
+                AxisPlacement::Definite(e..s)
+            }
+            (ResolvedLine::Line(s), ResolvedLine::Span(n)) => AxisPlacement::Definite(s..s + n),
+            (ResolvedLine::Line(s), _) => AxisPlacement::Definite(s..s + 1),
+            (ResolvedLine::Span(n), ResolvedLine::Line(e)) => {
+                let e = e.max(n);
+                AxisPlacement::Definite(e - n..e)
+            }
+            (ResolvedLine::Auto, ResolvedLine::Line(e)) => {
+                let e = e.max(1);
+                AxisPlacement::Definite(e - 1..e)
+            }
+            (ResolvedLine::Span(n), _) | (ResolvedLine::Auto, ResolvedLine::Span(n)) => {
+                AxisPlacement::Auto(n)
+            }
+            (ResolvedLine::Auto, ResolvedLine::Auto) => AxisPlacement::Auto(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ResolvedLine {
+    Auto,
+    Line(usize),
+    Span(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum AxisPlacement {
+    Definite(Range<usize>),
+    /// The item is placed automatically, and spans this number of tracks.
+    Auto(usize),
+}
+
+impl AxisPlacement {
+    fn span(&self) -> usize {
+        match self {
+            Self::Definite(range) => range.len(),
+            Self::Auto(span) => *span,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct GridItem {
+    content: ItemContent,
+    /// The columns and the rows that the item spans.
+    area: [Range<usize>; 2],
+    align: AlignItems,
+}
+
+/// The cells of the grid that are occupied by the items. The major axis is the one that grows
+/// as the items are placed automatically, which is the rows unless grid-auto-flow is column.
+struct Occupancy {
+    minor_count: usize,
+    cells: Vec<Vec<bool>>,
+}
+
+impl Occupancy {
+    fn fits(&self, major: &Range<usize>, minor: &Range<usize>) -> bool {
+        minor.end <= self.minor_count
+            && major.clone().all(|i| {
+                self.cells
+                    .get(i)
+                    .is_none_or(|row| minor.clone().all(|j| !row[j]))
+            })
+    }
+
+    fn occupy(&mut self, major: &Range<usize>, minor: &Range<usize>) {
+        while self.cells.len() < major.end {
+            self.cells.push(vec![false; self.minor_count]);
+        }
+        for row in &mut self.cells[major.clone()] {
+            for j in minor.clone() {
+                if j < row.len() {
+                    row[j] = true;
+                }
+            }
+        }
+    }
+
+    /// Returns the first position at or after (`major`, `minor`) where an item of `span` fits.
+    fn find(&self, mut major: usize, mut minor: usize, span: [usize; 2]) -> (usize, usize) {
+        loop {
+            if minor + span[1] > self.minor_count {
+                major += 1;
+                minor = 0;
+                continue;
+            }
+            if self.fits(&(major..major + span[0]), &(minor..minor + span[1])) {
+                return (major, minor);
+            }
+            minor += 1;
+        }
+    }
+}
+
+/// The state of the layout of a grid container.
+struct GridLayout<'a> {
+    font_metrics: &'a dyn FontMetrics,
+    style: ComputedStyle,
+    /// The width of the content box of the grid container, which is None when the max-content
+    /// width is measured.
+    width: Option<i64>,
+    tracks: [Tracks; 2],
+}
+
+impl<'a> GridLayout<'a> {
+    /// https://www.w3.org/TR/css-grid-1/#explicit-grids
+    /// Sets up the explicit grid from grid-template-columns, grid-template-rows and
+    /// grid-template-areas. "the size of the explicit grid is determined by the larger of the
+    /// number of rows/columns defined by grid-template-areas and the number of rows/columns sized
+    /// by grid-template-rows/grid-template-columns."
+    fn new(
+        style: ComputedStyle,
+        width: Option<i64>,
+        definite_height: Option<i64>,
+        font_metrics: &'a dyn FontMetrics,
+    ) -> Self {
+        // https://www.w3.org/TR/css-align-3/#column-row-gap
+        // Percentages refer to the content box, and they are 0 when it's not definite.
+        let gap = |gap: LengthPercentage, basis: Option<i64>| match basis {
+            Some(basis) => gap.resolve(basis),
+            None if gap.has_percentage() => 0,
+            None => gap.resolve(0),
+        };
+        let areas = style.grid_template_areas();
+        let axes = [
+            (
+                style.grid_template_columns(),
+                style.grid_auto_columns(),
+                areas.columns(),
+                width,
+                gap(style.column_gap(), width),
+            ),
+            (
+                style.grid_template_rows(),
+                style.grid_auto_rows(),
+                areas.rows(),
+                definite_height,
+                gap(style.row_gap(), definite_height),
+            ),
+        ];
+        let tracks = axes.map(|(template, implicit, area_count, available, gap)| {
+            let repetitions = template.repetitions(available, gap);
+            let (mut explicit, mut line_names) = template.expand(repetitions);
+            let auto_fit = match template.auto_repeat(repetitions) {
+                Some((AutoRepeat::AutoFit, range)) => range,
+                _ => 0..0,
+            };
+            let implicit_size = |i: usize| implicit[i % implicit.len()].clone();
+            let mut i = 0;
+            while explicit.len() < area_count {
+                explicit.push(implicit_size(i));
+                line_names.push(Vec::new());
+                i += 1;
+            }
+            Tracks {
+                explicit,
+                line_names,
+                implicit,
+                auto_fit,
+                gap,
+            }
+        });
+        let mut layout = Self {
+            font_metrics,
+            style,
+            width,
+            tracks,
+        };
+        // https://www.w3.org/TR/css-grid-1/#implicit-named-lines
+        // "The grid-template-areas property generates implicit named lines from the named grid
+        // areas in the template. For each named grid area foo, four implicit named lines are
+        // created: two named foo-start, naming the row-start and column-start lines of the named
+        // grid area, and two named foo-end, naming the row-end and column-end lines."
+        for (name, rows, columns) in areas.areas() {
+            for (axis, range) in [(ROW, rows), (COLUMN, columns)] {
+                let names = &mut layout.tracks[axis].line_names;
+                names[range.start].push(format!("{}-start", name));
+                names[range.end].push(format!("{}-end", name));
+            }
+        }
+        layout
+    }
+
+    /// https://www.w3.org/TR/css-grid-1/#auto-placement-algo
+    /// Places the items in the grid, and returns them with the numbers of the columns and rows.
+    fn place_items(&self, container: &LayoutObject) -> (Vec<GridItem>, [usize; 2]) {
+        let mut contents: Vec<_> = ItemContent::collect(container, self.font_metrics)
+            .into_iter()
+            .map(|content| {
+                let (placement, order, align) = match content.style() {
+                    Some(style) => (
+                        [
+                            self.tracks[COLUMN].placement(style.grid_column()),
+                            self.tracks[ROW].placement(style.grid_row()),
+                        ],
+                        style.order(),
+                        style.align_self().resolve(self.style.align_items()),
+                    ),
+                    None => (
+                        [AxisPlacement::Auto(1), AxisPlacement::Auto(1)],
+                        0,
+                        self.style.align_items(),
+                    ),
+                };
+                (content, placement, order, align)
+            })
+            .collect();
+        // "Grid items are laid out in order-modified document order."
+        contents.sort_by_key(|(_, _, order, _)| *order);
+
+        let flow = self.style.grid_auto_flow();
+        let (major, minor) = if flow.is_column() {
+            (COLUMN, ROW)
+        } else {
+            (ROW, COLUMN)
+        };
+        // The grid has as many tracks in the minor axis as the explicit grid, the items placed
+        // in it and the widest auto-placed item need.
+        let minor_count = contents
+            .iter()
+            .map(|(_, placement, _, _)| match &placement[minor] {
+                AxisPlacement::Definite(range) => range.end,
+                AxisPlacement::Auto(span) => *span,
+            })
+            .fold(self.tracks[minor].explicit.len(), usize::max);
+        let mut occupancy = Occupancy {
+            minor_count,
+            cells: Vec::new(),
+        };
+        let mut areas: Vec<Option<[Range<usize>; 2]>> = vec![None; contents.len()];
+        let area = |major_range: Range<usize>, minor_range: Range<usize>| {
+            let mut area = [0..0, 0..0];
+            area[major] = major_range;
+            area[minor] = minor_range;
+            area
+        };
+
+        // "Process the items locked to a given row." The items with definite positions in both
+        // axes are placed first, and then the ones that are only definite in the major axis.
+        for (i, (_, placement, _, _)) in contents.iter().enumerate() {
+            if let [AxisPlacement::Definite(c), AxisPlacement::Definite(r)] = placement {
+                let a = [c.clone(), r.clone()];
+                occupancy.occupy(&a[major], &a[minor]);
+                areas[i] = Some(a);
+            }
+        }
+        let mut cursors: BTreeMap<usize, usize> = BTreeMap::new();
+        for (i, (_, placement, _, _)) in contents.iter().enumerate() {
+            if let (AxisPlacement::Definite(major_range), AxisPlacement::Auto(span)) =
+                (&placement[major], &placement[minor])
+            {
+                let start = if flow.is_dense() {
+                    0
+                } else {
+                    cursors.get(&major_range.start).copied().unwrap_or(0)
+                };
+                let position = (start..=minor_count.saturating_sub(*span))
+                    .find(|m| occupancy.fits(major_range, &(*m..*m + span)))
+                    .unwrap_or(start);
+                let minor_range = position..position + span;
+                occupancy.occupy(major_range, &minor_range);
+                cursors.insert(major_range.start, minor_range.end);
+                areas[i] = Some(area(major_range.clone(), minor_range));
+            }
+        }
+
+        // "Position the remaining grid items." The auto-placement cursor moves forward through
+        // the grid unless the packing is dense, which starts from the beginning every time.
+        let mut cursor = (0, 0);
+        for (i, (_, placement, _, _)) in contents.iter().enumerate() {
+            if areas[i].is_some() {
+                continue;
+            }
+            if flow.is_dense() {
+                cursor = (0, 0);
+            }
+            let major_span = placement[major].span();
+            let (major_start, minor_range) = match &placement[minor] {
+                AxisPlacement::Definite(minor_range) => {
+                    if minor_range.start < cursor.1 && !flow.is_dense() {
+                        cursor.0 += 1;
+                    }
+                    let mut m = cursor.0;
+                    while !occupancy.fits(&(m..m + major_span), minor_range) {
+                        m += 1;
+                    }
+                    cursor = (m, minor_range.start);
+                    (m, minor_range.clone())
+                }
+                AxisPlacement::Auto(span) => {
+                    let (m, n) = occupancy.find(cursor.0, cursor.1, [major_span, *span]);
+                    cursor = (m, n + span);
+                    (m, n..n + span)
+                }
+            };
+            let major_range = major_start..major_start + major_span;
+            occupancy.occupy(&major_range, &minor_range);
+            areas[i] = Some(area(major_range, minor_range));
+        }
+
+        let mut counts = [
+            self.tracks[COLUMN].explicit.len(),
+            self.tracks[ROW].explicit.len(),
+        ];
+        let items: Vec<GridItem> = contents
+            .into_iter()
+            .zip(areas)
+            .map(|((content, _, _, align), area)| {
+                let area = area.expect("every item should be placed");
+                counts[COLUMN] = counts[COLUMN].max(area[COLUMN].end);
+                counts[ROW] = counts[ROW].max(area[ROW].end);
+                GridItem {
+                    content,
+                    area,
+                    align,
+                }
+            })
+            .collect();
+        (items, counts)
+    }
+
+    /// Returns the track sizing functions of the tracks in `axis`, and whether each of them is
+    /// collapsed. "an empty repeated track from auto-fit is collapsed".
+    fn track_sizes(
+        &self,
+        axis: usize,
+        count: usize,
+        items: &[GridItem],
+    ) -> (Vec<TrackSize>, Vec<bool>) {
+        let tracks = &self.tracks[axis];
+        let sizes = (0..count).map(|i| tracks.size(i)).collect();
+        let collapsed = (0..count)
+            .map(|i| {
+                tracks.auto_fit.contains(&i)
+                    && !items.iter().any(|item| item.area[axis].contains(&i))
+            })
+            .collect();
+        (sizes, collapsed)
+    }
+
+    /// The margins of an item in `axis` whose containing block, the grid area, is `width` wide,
+    /// and the sum of its paddings and borders. Auto margins are 0.
+    fn edges(&self, item: &GridItem, axis: usize, width: i64) -> ([i64; 2], i64) {
+        let style = match item.content.style() {
+            Some(style) => style,
+            None => return ([0, 0], 0),
+        };
+        let sides = if axis == COLUMN {
+            [Side::Left, Side::Right]
+        } else {
+            [Side::Top, Side::Bottom]
+        };
+        let margins = sides.map(|side| style.margin(side).resolve_or_zero(width));
+        let edges = sides
+            .iter()
+            .map(|side| style.padding(*side).resolve(width) + style.border_width(*side))
+            .sum();
+        (margins, edges)
+    }
+
+    /// Lays out an item in a grid area of `width` with the height of the content box given,
+    /// and returns the size of its content box. An item with an auto width is stretched to the
+    /// grid area.
+    fn layout_item(&self, item: &GridItem, width: i64, height: Option<i64>) -> LayoutSize {
+        let auto_width = item
+            .content
+            .style()
+            .is_none_or(|style| style.width().is_auto());
+        let (margins, edges) = self.edges(item, COLUMN, width);
+        let content_width = auto_width.then(|| (width - margins[0] - margins[1] - edges).max(0));
+        item.content.layout(
+            &self.style,
+            LayoutSize::new(width, 0),
+            height.is_some(),
+            self.font_metrics,
+            content_width,
+            height,
+        )
+    }
+
+    /// The min-content and max-content contributions of an item to the columns, which are the
+    /// widths of its margin box.
+    fn column_contributions(&self, item: &GridItem) -> (i64, i64) {
+        match &item.content {
+            ItemContent::Box(object) => {
+                let o = object.borrow();
+                (
+                    o.outer_min_content_width(self.font_metrics),
+                    o.outer_max_content_width(self.font_metrics),
+                )
+            }
+            content => (
+                content.min_content_width(self.font_metrics),
+                content.max_content_width(self.font_metrics),
+            ),
+        }
+    }
+
+    fn size_columns(&self, items: &[GridItem], count: usize) -> Vec<i64> {
+        let (sizes, collapsed) = self.track_sizes(COLUMN, count, items);
+        let contributions: Vec<_> = items
+            .iter()
+            .map(|item| {
+                let (min, max) = self.column_contributions(item);
+                (item.area[COLUMN].clone(), min, max)
+            })
+            .collect();
+        size_tracks(
+            &sizes,
+            &collapsed,
+            &contributions,
+            self.width,
+            self.tracks[COLUMN].gap,
+        )
+    }
+}
+
+/// Returns the positions of the lines from the first one. The gaps are between the tracks that
+/// are not collapsed.
+fn line_positions(sizes: &[i64], collapsed: &[bool], gap: i64) -> Vec<i64> {
+    let last = collapsed.iter().rposition(|c| !c);
+    let mut positions = vec![0];
+    let mut position = 0;
+    for (i, size) in sizes.iter().enumerate() {
+        position += size;
+        if !collapsed[i] && last.is_some_and(|last| i < last) {
+            position += gap;
+        }
+        positions.push(position);
+    }
+    positions
+}
+
+/// The size of the grid area of `range` between the lines at `positions`, without the gap after
+/// it.
+fn area_size(positions: &[i64], sizes: &[i64], range: &Range<usize>) -> i64 {
+    positions[range.end - 1] + sizes[range.end - 1] - positions[range.start]
+}
+
+/// https://www.w3.org/TR/css-grid-1/#algo-track-sizing
+/// Sizes the tracks of `sizes` in a grid container whose content box is `available` in this
+/// axis, which is None when the tracks are sized to their max-content. Each item is given as
+/// the tracks it spans and its min-content and max-content contributions. The collapsed tracks
+/// are 0 and don't have gaps around them.
+fn size_tracks(
+    sizes: &[TrackSize],
+    collapsed: &[bool],
+    items: &[(Range<usize>, i64, i64)],
+    available: Option<i64>,
+    gap: i64,
+) -> Vec<i64> {
+    let count = sizes.len();
+    let flex = |i: usize| match sizes[i].max() {
+        TrackBreadth::Flex(f) if !collapsed[i] => Some(*f),
+        _ => None,
+    };
+    let intrinsic = |breadth: &TrackBreadth| {
+        matches!(
+            breadth,
+            TrackBreadth::MinContent | TrackBreadth::MaxContent | TrackBreadth::Auto
+        )
+    };
+    let gaps = gap * (collapsed.iter().filter(|c| !**c).count() as i64 - 1).max(0);
+
+    // "Initialize each track's base size and growth limit." A growth limit of None is infinite.
+    let mut base = vec![0; count];
+    let mut limit: Vec<Option<i64>> = vec![None; count];
+    for i in (0..count).filter(|i| !collapsed[*i]) {
+        base[i] = sizes[i].min().resolve(available).unwrap_or(0);
+        limit[i] = sizes[i].max().resolve(available).map(|l| l.max(base[i]));
+    }
+    if collapsed.iter().any(|c| *c) {
+        for i in (0..count).filter(|i| collapsed[*i]) {
+            limit[i] = Some(0);
+        }
+    }
+
+    // https://www.w3.org/TR/css-grid-1/#algo-content
+    // "Resolve Intrinsic Track Sizes." The items spanning one track are sized first, and then
+    // the ones spanning more tracks in the order of the numbers of the tracks they span. The
+    // items spanning flexible tracks are sized when the flexible tracks are expanded.
+    let mut sorted: Vec<_> = items.iter().collect();
+    sorted.sort_by_key(|(range, _, _)| range.len());
+    for (range, min, max) in sorted {
+        if range.len() == 1 {
+            let i = range.start;
+            match sizes[i].min() {
+                TrackBreadth::MinContent | TrackBreadth::Auto => base[i] = base[i].max(*min),
+                TrackBreadth::MaxContent => base[i] = base[i].max(*max),
+                _ => {}
+            }
+            if flex(i).is_none() {
+                match sizes[i].max() {
+                    TrackBreadth::MinContent => limit[i] = Some(limit[i].unwrap_or(0).max(*min)),
+                    TrackBreadth::MaxContent | TrackBreadth::Auto => {
+                        limit[i] = Some(limit[i].unwrap_or(0).max(*max))
+                    }
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        if range.clone().any(|i| flex(i).is_some()) {
+            continue;
+        }
+        let spanned_gaps = gap * (range.len() as i64 - 1);
+        // "Increase sizes to accommodate spanning items". The extra space is distributed
+        // equally to the tracks with intrinsic sizing functions.
+        let mut targets: Vec<usize> = range
+            .clone()
+            .filter(|i| intrinsic(sizes[*i].min()))
+            .collect();
+        if targets.is_empty() {
+            targets = range.clone().collect();
+        }
+        let used: i64 = range.clone().map(|i| base[i]).sum::<i64>() + spanned_gaps;
+        distribute(&mut base, &targets, min - used);
+        // The growth limits of min-content maximums accommodate the min-content contribution,
+        // and the ones of max-content and auto maximums accommodate the max-content one.
+        for (contribution, min_content) in [(*min, true), (*max, false)] {
+            let targets: Vec<usize> = range
+                .clone()
+                .filter(|i| match sizes[*i].max() {
+                    TrackBreadth::MinContent => min_content,
+                    TrackBreadth::MaxContent | TrackBreadth::Auto => !min_content,
+                    _ => false,
+                })
+                .collect();
+            let mut limits: Vec<i64> = (0..count).map(|i| limit[i].unwrap_or(base[i])).collect();
+            let used: i64 = range.clone().map(|i| limits[i]).sum::<i64>() + spanned_gaps;
+            if distribute(&mut limits, &targets, contribution - used) {
+                for i in &targets {
+                    limit[*i] = Some(limits[*i]);
+                }
+            }
+        }
+    }
+    // "If any track still has an infinite growth limit [...], set its growth limit to its base
+    // size."
+    let limit: Vec<i64> = (0..count)
+        .map(|i| limit[i].unwrap_or(base[i]).max(base[i]))
+        .collect();
+
+    // https://www.w3.org/TR/css-grid-1/#algo-grow-tracks
+    // "Maximize Tracks": the free space is distributed equally to the tracks up to their growth
+    // limits. Under a max-content constraint, every track is at its growth limit.
+    match available {
+        Some(available) => {
+            let mut free = available - gaps - base.iter().sum::<i64>();
+            while free > 0 {
+                let growable: Vec<usize> = (0..count)
+                    .filter(|i| flex(*i).is_none() && base[*i] < limit[*i])
+                    .collect();
+                if growable.is_empty() {
+                    break;
+                }
+                let share = (free / growable.len() as i64).max(1);
+                for i in growable {
+                    let grow = share.min(limit[i] - base[i]).min(free);
+                    base[i] += grow;
+                    free -= grow;
+                }
+            }
+        }
+        None => {
+            for i in (0..count).filter(|i| flex(*i).is_none()) {
+                base[i] = limit[i];
+            }
+        }
+    }
+
+    // https://www.w3.org/TR/css-grid-1/#algo-flex-tracks
+    // "Expand Flexible Tracks"
+    let flexible: Vec<usize> = (0..count).filter(|i| flex(*i).is_some()).collect();
+    let mut sizes_f: Vec<f64> = base.iter().map(|b| *b as f64).collect();
+    if !flexible.is_empty() {
+        let fr = match available {
+            Some(available) => {
+                // "Find the size of an fr": the leftover space is divided by the flex factors,
+                // and a track whose base size is larger than its share is treated as inflexible.
+                let mut inflexible: Vec<bool> = (0..count).map(|i| flex(i).is_none()).collect();
+                loop {
+                    let leftover = (available - gaps) as f64
+                        - (0..count)
+                            .filter(|i| inflexible[*i])
+                            .map(|i| base[i] as f64)
+                            .sum::<f64>();
+                    let factors: f64 = (0..count)
+                        .filter(|i| !inflexible[*i])
+                        .filter_map(flex)
+                        .sum::<f64>()
+                        .max(1.0);
+                    let fr = leftover.max(0.0) / factors;
+                    let too_small: Vec<usize> = (0..count)
+                        .filter(|i| !inflexible[*i])
+                        .filter(|i| base[*i] as f64 > fr * flex(*i).unwrap_or(0.0))
+                        .collect();
+                    if too_small.is_empty() {
+                        break fr;
+                    }
+                    for i in too_small {
+                        inflexible[i] = true;
+                    }
+                }
+            }
+            // Under a max-content constraint, an fr is as large as the largest of the flexible
+            // tracks and the max-content contributions of the items in them need.
+            None => {
+                let mut fr: f64 = 0.0;
+                for i in &flexible {
+                    let factor = flex(*i).unwrap_or(0.0);
+                    fr = fr.max(base[*i] as f64 / factor.max(1.0));
+                }
+                for (range, _, max) in items {
+                    let factors: f64 = range.clone().filter_map(flex).sum();
+                    if factors > 0.0 {
+                        let fixed: i64 = range
+                            .clone()
+                            .filter(|i| flex(*i).is_none())
+                            .map(|i| base[i])
+                            .sum::<i64>()
+                            + gap * (range.len() as i64 - 1);
+                        fr = fr.max((max - fixed) as f64 / factors.max(1.0));
+                    }
+                }
+                fr
+            }
+        };
+        for i in flexible.iter().copied() {
+            sizes_f[i] = sizes_f[i].max(fr * flex(i).unwrap_or(0.0));
+        }
+    } else if let Some(available) = available {
+        // https://www.w3.org/TR/css-grid-1/#algo-stretch
+        // "Stretch auto Tracks": the initial value of justify-content and align-content,
+        // normal, stretches the tracks with an auto max track sizing function.
+        let free = available - gaps - base.iter().sum::<i64>();
+        let auto: Vec<usize> = (0..count)
+            .filter(|i| !collapsed[*i] && matches!(sizes[*i].max(), TrackBreadth::Auto))
+            .collect();
+        if free > 0 && !auto.is_empty() {
+            for i in &auto {
+                sizes_f[*i] += free as f64 / auto.len() as f64;
+            }
+        }
+    }
+
+    // The sizes are rounded so that they add up to the rounded sum of them.
+    let mut sum = 0.0;
+    sizes_f
+        .iter()
+        .map(|size| {
+            let start = round(sum);
+            sum += size;
+            round(sum) - start
+        })
+        .collect()
+}
+
+/// Adds `extra` to the sizes of `targets` equally, and returns whether anything is added.
+fn distribute(sizes: &mut [i64], targets: &[usize], extra: i64) -> bool {
+    if extra <= 0 || targets.is_empty() {
+        return false;
+    }
+    let n = targets.len() as i64;
+    for (k, i) in targets.iter().enumerate() {
+        let k = k as i64;
+        sizes[*i] += extra * (k + 1) / n - extra * k / n;
+    }
+    true
+}
+
+/// https://www.w3.org/TR/css-grid-1/#layout-algorithm
+/// Lays out the grid items of `container`, whose content box is `content_size` and whose height
+/// is specified if `definite_height` is given. The offsets of the items are set, and the height
+/// of the content is returned.
+pub fn layout_grid_items(
+    container: &LayoutObject,
+    content_size: LayoutSize,
+    definite_height: Option<i64>,
+    font_metrics: &dyn FontMetrics,
+) -> i64 {
+    let grid = GridLayout::new(
+        container.style(),
+        Some(content_size.width()),
+        definite_height,
+        font_metrics,
+    );
+    let (items, counts) = grid.place_items(container);
+
+    let columns = grid.size_columns(&items, counts[COLUMN]);
+    let (_, column_collapsed) = grid.track_sizes(COLUMN, counts[COLUMN], &items);
+    let column_positions = line_positions(&columns, &column_collapsed, grid.tracks[COLUMN].gap);
+
+    // The heights of the items are their contributions to the rows when they are laid out in
+    // the widths of their grid areas.
+    let widths: Vec<i64> = items
+        .iter()
+        .map(|item| area_size(&column_positions, &columns, &item.area[COLUMN]))
+        .collect();
+    let contributions: Vec<_> = items
+        .iter()
+        .zip(&widths)
+        .map(|(item, width)| {
+            let (margins, edges) = grid.edges(item, ROW, *width);
+            let height =
+                grid.layout_item(item, *width, None).height() + edges + margins[0] + margins[1];
+            (item.area[ROW].clone(), height, height)
+        })
+        .collect();
+    let (row_sizes, row_collapsed) = grid.track_sizes(ROW, counts[ROW], &items);
+    let rows = size_tracks(
+        &row_sizes,
+        &row_collapsed,
+        &contributions,
+        definite_height,
+        grid.tracks[ROW].gap,
+    );
+    let row_positions = line_positions(&rows, &row_collapsed, grid.tracks[ROW].gap);
+
+    // https://www.w3.org/TR/css-grid-1/#grid-item-sizing
+    // An item with align-self: normal or stretch and an auto height is stretched to its grid
+    // area. The others are aligned in it.
+    for (item, width) in items.iter().zip(&widths) {
+        let height = area_size(&row_positions, &rows, &item.area[ROW]);
+        let (margins, edges) = grid.edges(item, ROW, *width);
+        let (column_margins, _) = grid.edges(item, COLUMN, *width);
+        let auto_height = item
+            .content
+            .style()
+            .is_none_or(|style| style.height() == LengthPercentageOrAuto::Auto);
+        let stretch = auto_height && matches!(item.align, AlignItems::Normal | AlignItems::Stretch);
+        let content_height = if stretch {
+            let content_height = (height - margins[0] - margins[1] - edges).max(0);
+            grid.layout_item(item, *width, Some(content_height));
+            content_height
+        } else {
+            grid.layout_item(item, *width, None).height()
+        };
+        let free = height - content_height - edges - margins[0] - margins[1];
+        let align = match item.align {
+            AlignItems::FlexEnd | AlignItems::End => free,
+            AlignItems::Center => free / 2,
+            _ => 0,
+        };
+        item.content.set_offset(LayoutPoint::new(
+            column_positions[item.area[COLUMN].start] + column_margins[0],
+            row_positions[item.area[ROW].start] + margins[0] + align,
+        ));
+    }
+
+    definite_height.unwrap_or_else(|| row_positions.last().copied().unwrap_or(0))
+}
+
+/// https://www.w3.org/TR/css-grid-1/#intrinsic-sizes
+/// The max-content width of a grid container, which is the sum of its columns sized under a
+/// max-content constraint.
+pub fn max_content_width(container: &LayoutObject, font_metrics: &dyn FontMetrics) -> i64 {
+    let grid = GridLayout::new(container.style(), None, None, font_metrics);
+    let (items, counts) = grid.place_items(container);
+    let columns = grid.size_columns(&items, counts[COLUMN]);
+    let (_, collapsed) = grid.track_sizes(COLUMN, counts[COLUMN], &items);
+    line_positions(&columns, &collapsed, grid.tracks[COLUMN].gap)
+        .last()
+        .copied()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::value::Length;
+
+    fn px(px: f64) -> TrackBreadth {
+        TrackBreadth::LengthPercentage(LengthPercentage::Length(Length::px(px)))
+    }
+
+    fn track(breadth: TrackBreadth) -> TrackSize {
+        match breadth {
+            TrackBreadth::Flex(_) => TrackSize::new(TrackBreadth::Auto, breadth),
+            breadth => TrackSize::new(breadth.clone(), breadth),
+        }
+    }
+
+    #[test]
+    fn test_size_tracks() {
+        // The leftover space is shared by the fr tracks.
+        let sizes = [
+            track(px(100.0)),
+            track(TrackBreadth::Flex(1.0)),
+            track(TrackBreadth::Flex(2.0)),
+        ];
+        assert_eq!(
+            vec![100, 100, 200],
+            size_tracks(&sizes, &[false; 3], &[], Some(420), 10)
+        );
+        // A flexible track isn't smaller than the min-content contribution of its item.
+        assert_eq!(
+            vec![100, 150, 150],
+            size_tracks(&sizes, &[false; 3], &[(1..2, 150, 200)], Some(420), 10)
+        );
+        // Auto tracks are sized to their contents, and stretched to fill the container.
+        let sizes = [track(TrackBreadth::Auto), track(TrackBreadth::MaxContent)];
+        assert_eq!(
+            vec![50, 70],
+            size_tracks(
+                &sizes,
+                &[false; 2],
+                &[(0..1, 20, 50), (1..2, 30, 70)],
+                None,
+                0
+            )
+        );
+        assert_eq!(
+            vec![130, 70],
+            size_tracks(
+                &sizes,
+                &[false; 2],
+                &[(0..1, 20, 50), (1..2, 30, 70)],
+                Some(200),
+                0
+            )
+        );
+        // A spanning item grows the intrinsic tracks it spans.
+        let sizes = [track(px(10.0)), track(TrackBreadth::MinContent)];
+        assert_eq!(
+            vec![10, 85],
+            size_tracks(&sizes, &[false; 2], &[(0..2, 100, 120)], None, 5)
+        );
+        // A collapsed track is 0, and the gaps around it collapse.
+        let sizes = [track(px(50.0)), track(px(50.0)), track(px(50.0))];
+        assert_eq!(
+            vec![0, 60, 110, 110],
+            line_positions(
+                &size_tracks(&sizes, &[false, false, true], &[], Some(200), 10),
+                &[false, false, true],
+                10
+            )
+        );
+    }
+}
@@ -0,0 +1,1104 @@
+//! https://www.w3.org/TR/css-inline-3/
+//! https://www.w3.org/TR/CSS2/visuren.html#inline-formatting
+//! The inline formatting context. A run of inline-level boxes and text in a block container is
+//! broken into line boxes, which wrap across the boundaries of elements. Boxes on a line are
+//! aligned on their baselines as vertical-align says, and every inline-level box and text gets a
+//! fragment for each line box it's on.
+
+use crate::renderer::dom::node::NodeKind;
+use crate::renderer::layout::computed_style::ComputedStyle;
+use crate::renderer::layout::computed_style::Float;
+use crate::renderer::layout::computed_style::LineBreak;
+use crate::renderer::layout::computed_style::OverflowWrap;
+use crate::renderer::layout::computed_style::TextAlign;
+use crate::renderer::layout::computed_style::VerticalAlign;
+use crate::renderer::layout::computed_style::WordBreak;
+use crate::renderer::layout::float_layout::FloatManager;
+use crate::renderer::layout::font_metrics::FontMetrics;
+use crate::renderer::layout::layout_object::Fragment;
+use crate::renderer::layout::layout_object::LayoutObject;
+use crate::renderer::layout::layout_object::LayoutObjectKind;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
+use crate::renderer::layout::line_break::is_break_opportunity;
+use crate::renderer::layout::line_break::is_break_opportunity_after_spaces;
+use crate::renderer::layout::line_break::resolve_class;
+use crate::renderer::layout::line_break::LineBreakClass;
+use crate::renderer::layout::unicode::graphemes;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// https://www.w3.org/TR/css-text-3/#tab-size-property
+/// Preserved tabs advance to the next tab stop, which is every 8 spaces.
+const TAB_SIZE: usize = 8;
+
+/// The boxes that are aligned to the line box with vertical-align: top or bottom form their own
+/// group, and the boxes inside them are aligned relative to them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum AlignmentGroup {
+    Baseline,
+    Top,
+    Bottom,
+}
+
+/// An inline-level box or text in the inline formatting context.
+struct InlineObject {
+    object: Rc<RefCell<LayoutObject>>,
+    kind: LayoutObjectKind,
+    style: ComputedStyle,
+    /// The inline box that contains this object, or None if the block container contains it
+    /// directly.
+    parent: Option<usize>,
+    /// The index of the alignment group.
+    group: usize,
+    /// How far the baseline of this object is raised from the baseline of its group.
+    shift: i64,
+    /// Fragments in the coordinate space of the content box of the block container.
+    fragments: Vec<Fragment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AtomKind {
+    /// A grapheme cluster.
+    Text(String),
+    /// A single space. Collapsible spaces are removed at the start and the end of a line.
+    Space { collapsible: bool },
+    /// The start of an inline box, which takes up its left margin, border and padding.
+    Start,
+    /// The end of an inline box, which takes up its right padding, border and margin.
+    End,
+    /// A block-level box inside an inline box. It's laid out as an atomic inline.
+    Atomic,
+    /// A float. It doesn't take space on the line, but shortens the lines next to it.
+    Float,
+    /// A forced line break.
+    Break,
+}
+
+/// The smallest piece of inline content that line breaking works with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Atom {
+    kind: AtomKind,
+    /// The index of the object that this atom belongs to.
+    owner: usize,
+    width: i64,
+    /// Whether there is a soft wrap opportunity after this atom.
+    break_after: bool,
+    /// Whether overflow-wrap allows an emergency break after this atom, which is only taken when
+    /// a line has no other soft wrap opportunity.
+    /// https://www.w3.org/TR/css-text-3/#overflow-wrap-property
+    emergency_break: bool,
+}
+
+impl Atom {
+    /// Returns true if the atom keeps a line box from being empty.
+    /// https://www.w3.org/TR/css-inline-3/#invisible-line-boxes
+    fn is_content(&self) -> bool {
+        match self.kind {
+            AtomKind::Text(_) | AtomKind::Atomic => true,
+            AtomKind::Space { collapsible } => !collapsible,
+            AtomKind::Start | AtomKind::End => self.width != 0,
+            AtomKind::Break | AtomKind::Float => false,
+        }
+    }
+
+    fn is_collapsible_space(&self) -> bool {
+        self.kind == AtomKind::Space { collapsible: true }
+    }
+
+    /// Returns true if the atom is a preserved space that hangs at the end of a line instead of
+    /// overflowing it.
+    /// https://www.w3.org/TR/css-text-3/#hanging
+    fn is_hanging_space(&self) -> bool {
+        self.kind == AtomKind::Space { collapsible: false } && self.break_after
+    }
+}
+
+struct Line {
+    /// Indices of atoms on the line.
+    atoms: Vec<usize>,
+    /// Whether the line ends with a forced line break.
+    forced: bool,
+}
+
+struct InlineFormattingContext<'a> {
+    font_metrics: &'a dyn FontMetrics,
+    container_style: ComputedStyle,
+    width: i64,
+    /// The floats of the block formatting context.
+    floats: &'a mut FloatManager,
+    /// The top-left corner of the content box of the block container in the block formatting
+    /// context.
+    origin: LayoutPoint,
+    /// The offsets of the floats that have been placed, by the index of their atoms.
+    placed_floats: Vec<(usize, LayoutPoint)>,
+    objects: Vec<InlineObject>,
+    groups: Vec<AlignmentGroup>,
+    atoms: Vec<Atom>,
+    /// Whether the last character was a collapsible space. A collapsible space that follows
+    /// another one is removed even if they are in different elements.
+    after_collapsible_space: bool,
+    /// The line breaking class of the last grapheme cluster or atomic inline before the spaces
+    /// that follow it, if any. Soft wrap opportunities are decided across elements.
+    line_break_class: Option<LineBreakClass>,
+    /// The ::first-line style of the block container if the run starts its first line.
+    first_line_style: Option<ComputedStyle>,
+    /// The indices of the first object and the first atom that are collected again with the
+    /// ::first-line styles, after the others.
+    first_line: Option<(usize, usize)>,
+    /// Whether objects are being collected with their ::first-line styles.
+    collecting_first_line: bool,
+}
+
+/// Lays out `children`, a run of consecutive inline-level children of a block container whose
+/// style is `container_style`, in line boxes of `width` starting at `y` in the content box of the
+/// block container. Text is measured with `font_metrics`. The line boxes are shortened next to
+/// `floats`, the floats of the block formatting context where the content box is at `origin`,
+/// and the floats in the run are added to them. If the run starts the first line of the block
+/// container, `first_line_style` is its ::first-line style. The offset, size and fragments of
+/// every object in the run are set, and the height from `y` to the bottom of the last line box is
+/// returned.
+#[allow(clippy::too_many_arguments)]
+pub fn layout_inline_content(
+    font_metrics: &dyn FontMetrics,
+    container_style: &ComputedStyle,
+    first_line_style: Option<&ComputedStyle>,
+    children: &[Rc<RefCell<LayoutObject>>],
+    width: i64,
+    y: i64,
+    floats: &mut FloatManager,
+    origin: LayoutPoint,
+) -> i64 {
+    let mut context = InlineFormattingContext {
+        font_metrics,
+        container_style: container_style.clone(),
+        width,
+        floats,
+        origin,
+        placed_floats: Vec::new(),
+        objects: Vec::new(),
+        groups: vec![AlignmentGroup::Baseline],
+        atoms: Vec::new(),
+        after_collapsible_space: true,
+        line_break_class: None,
+        first_line_style: None,
+        first_line: None,
+        collecting_first_line: false,
+    };
+    for child in children {
+        context.collect(child, None, 0, 0, container_style);
+    }
+    if let Some(first_line_style) = first_line_style {
+        context.collect_first_line(children, first_line_style);
+    }
+
+    let mut top = y;
+    // The first line is broken from the atoms with the ::first-line styles, and the next lines
+    // continue from the same position in the others.
+    let (mut start, mut end) = match context.first_line {
+        Some((_, atom)) => (atom, context.atoms.len()),
+        None => (0, context.atoms.len()),
+    };
+    let mut first_line = true;
+    while start < end {
+        let (left, right) = context.line_space(top);
+        let available = right - left - context.indent(first_line);
+        let (line, next) = context.break_line(start, end, available);
+        // A float that fits on the line is put at the top of it, and the line is broken again
+        // in the space that is left. The others go below the line.
+        if context.place_floats_at_top(&line, top, available) {
+            continue;
+        }
+        // https://www.w3.org/TR/CSS2/visuren.html#floats
+        // "If a shortened line box is too small to contain any content, then the line box is
+        // shifted downward (and its width recomputed) until either some content fits or there
+        // are no more floats present."
+        if context.line_width(&line) > available && (left, right) != (0, width) {
+            if let Some(bottom) = context.floats.next_band(origin.y() + top) {
+                top = bottom - origin.y();
+                continue;
+            }
+        }
+        top += context.place_line(&line, first_line, next == end, top, left, right - left);
+        context.place_floats_below(&line, top);
+        match context.first_line {
+            Some((_, atom)) if first_line => (start, end) = (next - atom, atom),
+            _ => start = next,
+        }
+        first_line = false;
+    }
+
+    for (atom, offset) in core::mem::take(&mut context.placed_floats) {
+        let object = &context.objects[context.atoms[atom].owner];
+        object.object.borrow_mut().set_offset(offset);
+    }
+    // The fragments on the first line go before the others of the same object.
+    if let Some((object, _)) = context.first_line {
+        let first_line_objects = context.objects.split_off(object);
+        for (o, first_line_object) in context.objects.iter_mut().zip(first_line_objects) {
+            o.fragments.splice(0..0, first_line_object.fragments);
+        }
+    }
+
+    for object in context
+        .objects
+        .iter()
+        .filter(|o| o.style.float() == Float::None)
+    {
+        let mut o = object.object.borrow_mut();
+        match object.fragments.split_first() {
+            Some((first, rest)) => {
+                // The object is placed at the top-left corner of the bounding box of its
+                // fragments.
+                let (mut left, mut top) = (first.point().x(), first.point().y());
+                let (mut right, mut bottom) =
+                    (left + first.size().width(), top + first.size().height());
+                for f in rest {
+                    left = left.min(f.point().x());
+                    top = top.min(f.point().y());
+                    right = right.max(f.point().x() + f.size().width());
+                    bottom = bottom.max(f.point().y() + f.size().height());
+                }
+                let fragments = object
+                    .fragments
+                    .iter()
+                    .map(|f| f.translated(-left, -top))
+                    .collect();
+                o.set_offset(LayoutPoint::new(left, top));
+                if !matches!(
+                    object.kind,
+                    LayoutObjectKind::Block | LayoutObjectKind::Grid
+                ) {
+                    o.set_size(LayoutSize::new(right - left, bottom - top));
+                }
+                o.set_fragments(fragments);
+            }
+            None => {
+                o.set_offset(LayoutPoint::new(0, y));
+                if !matches!(
+                    object.kind,
+                    LayoutObjectKind::Block | LayoutObjectKind::Grid
+                ) {
+                    o.set_size(LayoutSize::new(0, 0));
+                }
+                o.set_fragments(Vec::new());
+            }
+        }
+    }
+
+    top - y
+}
+
+impl InlineFormattingContext<'_> {
+    /// The distance from the top of the content area of text in `style` to its baseline.
+    fn ascent(&self, style: &ComputedStyle) -> i64 {
+        self.font_metrics.ascent(&style.font())
+    }
+
+    fn descent(&self, style: &ComputedStyle) -> i64 {
+        self.font_metrics.descent(&style.font())
+    }
+
+    /// https://www.w3.org/TR/css-inline-3/#line-height-property
+    /// The difference between the line height and the height of the content area is split
+    /// equally above and below the glyphs as half-leading.
+    fn half_leading(&self, style: &ComputedStyle) -> i64 {
+        (style.line_height() - self.ascent(style) - self.descent(style)) / 2
+    }
+
+    /// https://www.w3.org/TR/css-inline-3/#layout-bounds
+    /// The extents of the layout bounds of an inline box above and below its baseline. They're
+    /// the content area with the half-leading added on both sides, so they're as tall as
+    /// line-height.
+    fn layout_bounds(&self, style: &ComputedStyle) -> (i64, i64) {
+        let above = self.ascent(style) + self.half_leading(style);
+        (above, style.line_height() - above)
+    }
+
+    /// Turns `object` and its descendants into atoms. `shift` and `group` are those of the parent
+    /// inline box, and `parent_style` is its style.
+    fn collect(
+        &mut self,
+        object: &Rc<RefCell<LayoutObject>>,
+        parent: Option<usize>,
+        group: usize,
+        shift: i64,
+        parent_style: &ComputedStyle,
+    ) {
+        // Absolutely positioned boxes are laid out apart from the line boxes.
+        if object.borrow().is_out_of_flow() {
+            return;
+        }
+        let kind = object.borrow().kind();
+        let style = match object.borrow().first_line_style() {
+            Some(style) if self.collecting_first_line => style,
+            _ => object.borrow().style(),
+        };
+        let index = self.objects.len();
+
+        match kind {
+            LayoutObjectKind::Text => {
+                self.objects.push(InlineObject {
+                    object: object.clone(),
+                    kind,
+                    style,
+                    parent,
+                    group,
+                    // Text is aligned on the baseline of its parent box.
+                    shift,
+                    fragments: Vec::new(),
+                });
+                if let NodeKind::Text(text) = object.borrow().node_kind() {
+                    self.collect_text(index, &text);
+                }
+            }
+            LayoutObjectKind::Inline => {
+                let (group, shift) = self.vertical_alignment(&style, group, shift, parent_style);
+                let margin = object.borrow().margin();
+                let padding = object.borrow().padding();
+                let border = object.borrow().border();
+                self.objects.push(InlineObject {
+                    object: object.clone(),
+                    kind,
+                    style: style.clone(),
+                    parent,
+                    group,
+                    shift,
+                    fragments: Vec::new(),
+                });
+
+                self.push_atom(
+                    AtomKind::Start,
+                    index,
+                    margin.left + border.left + padding.left,
+                    false,
+                );
+                let mut child = object.borrow().first_child();
+                while let Some(c) = child {
+                    self.collect(&c, Some(index), group, shift, &style);
+                    child = c.borrow().next_sibling();
+                }
+                self.push_atom(
+                    AtomKind::End,
+                    index,
+                    padding.right + border.right + margin.right,
+                    false,
+                );
+            }
+            LayoutObjectKind::Block | LayoutObjectKind::Grid if style.float() != Float::None => {
+                self.objects.push(InlineObject {
+                    object: object.clone(),
+                    kind,
+                    style,
+                    parent,
+                    group,
+                    shift,
+                    fragments: Vec::new(),
+                });
+                self.push_atom(AtomKind::Float, index, 0, false);
+            }
+            LayoutObjectKind::Block | LayoutObjectKind::Grid => {
+                let width = object.borrow().size().width() + object.borrow().margin().horizontal();
+                self.objects.push(InlineObject {
+                    object: object.clone(),
+                    kind,
+                    style,
+                    parent,
+                    group,
+                    shift,
+                    fragments: Vec::new(),
+                });
+                // An atomic inline is treated as an ideographic character for line breaking.
+                // https://www.w3.org/TR/css-text-3/#line-break-details
+                self.break_before(LineBreakClass::Id, parent_style);
+                self.push_atom(AtomKind::Atomic, index, width, false);
+                self.after_collapsible_space = false;
+            }
+        }
+    }
+
+    /// https://www.w3.org/TR/css-pseudo-4/#first-line-styling
+    /// Collects `children` again with their ::first-line styles, which inherit from
+    /// `first_line_style`, so that the first line is broken and placed with them. The styles are
+    /// ignored if they split the content into different atoms, as text-transform can.
+    fn collect_first_line(
+        &mut self,
+        children: &[Rc<RefCell<LayoutObject>>],
+        first_line_style: &ComputedStyle,
+    ) {
+        let (objects, groups, atoms) = (self.objects.len(), self.groups.len(), self.atoms.len());
+        self.collecting_first_line = true;
+        self.after_collapsible_space = true;
+        self.line_break_class = None;
+        for child in children {
+            self.collect(child, None, 0, 0, first_line_style);
+        }
+        self.collecting_first_line = false;
+
+        let matches = self.atoms.len() == atoms * 2
+            && self.atoms[..atoms]
+                .iter()
+                .zip(&self.atoms[atoms..])
+                .all(|(a, b)| core::mem::discriminant(&a.kind) == core::mem::discriminant(&b.kind));
+        if matches {
+            self.first_line = Some((objects, atoms));
+            self.first_line_style = Some(first_line_style.clone());
+        } else {
+            self.objects.truncate(objects);
+            self.groups.truncate(groups);
+            self.atoms.truncate(atoms);
+        }
+    }
+
+    /// https://www.w3.org/TR/css-inline-3/#propdef-vertical-align
+    /// Returns the alignment group of an inline box and how far its baseline is raised from the
+    /// baseline of the group.
+    fn vertical_alignment(
+        &mut self,
+        style: &ComputedStyle,
+        group: usize,
+        parent_shift: i64,
+        parent_style: &ComputedStyle,
+    ) -> (usize, i64) {
+        let parent_size = parent_style.font().size();
+        let (above, below) = self.layout_bounds(style);
+        let raise = match style.vertical_align() {
+            VerticalAlign::Baseline => 0,
+            // "Lower the baseline of the box to the proper position for subscripts of the
+            // parent's box."
+            VerticalAlign::Sub => -parent_size / 5,
+            // "Raise the baseline of the box to the proper position for superscripts of the
+            // parent's box."
+            VerticalAlign::Super => parent_size / 3,
+            // "Align the top of the box with the top of the parent's content area."
+            VerticalAlign::TextTop => self.ascent(parent_style) - above,
+            // "Align the bottom of the box with the bottom of the parent's content area."
+            VerticalAlign::TextBottom => below - self.descent(parent_style),
+            // "Align the vertical midpoint of the box with the baseline of the parent box plus
+            // half the x-height of the parent."
+            VerticalAlign::Middle => {
+                self.font_metrics.x_height(&parent_style.font()) / 2 - (above - below) / 2
+            }
+            // "Percentages: refer to the line-height of the element itself."
+            VerticalAlign::LengthPercentage(l) => l.resolve(style.line_height()),
+            VerticalAlign::Top => {
+                self.groups.push(AlignmentGroup::Top);
+                return (self.groups.len() - 1, 0);
+            }
+            VerticalAlign::Bottom => {
+                self.groups.push(AlignmentGroup::Bottom);
+                return (self.groups.len() - 1, 0);
+            }
+        };
+        (group, parent_shift + raise)
+    }
+
+    fn push_atom(&mut self, kind: AtomKind, owner: usize, width: i64, break_after: bool) {
+        self.atoms.push(Atom {
+            kind,
+            owner,
+            width,
+            break_after,
+            emergency_break: false,
+        });
+    }
+
+    /// The index of the last atom that isn't the start or the end of an inline box, or a float.
+    fn last_content_atom(&self) -> Option<usize> {
+        self.atoms
+            .iter()
+            .rposition(|a| !matches!(a.kind, AtomKind::Start | AtomKind::End | AtomKind::Float))
+    }
+
+    /// https://www.w3.org/TR/css-text-3/#line-breaking
+    /// Decides whether there is a soft wrap opportunity before a grapheme cluster or an atomic
+    /// inline of `class` in an element of `style`, and sets it on the atoms before it.
+    fn break_before(&mut self, class: LineBreakClass, style: &ComputedStyle) {
+        let wraps = style.white_space().wraps();
+        // "anywhere: There is a soft wrap opportunity around every typographic character unit,
+        // including around any punctuation character or preserved white spaces, or in the
+        // middle of words, disregarding any prohibition against line breaks."
+        let anywhere = style.line_break() == LineBreak::Anywhere;
+        let before = self.line_break_class.replace(class);
+        let last = match self.last_content_atom() {
+            Some(last) => last,
+            None => return,
+        };
+        match self.atoms[last].kind {
+            AtomKind::Space { .. } => {
+                if anywhere || is_break_opportunity_after_spaces(before, class) {
+                    return;
+                }
+                for atom in self.atoms[..=last].iter_mut().rev() {
+                    match atom.kind {
+                        AtomKind::Space { .. } => atom.break_after = false,
+                        AtomKind::Start | AtomKind::End => {}
+                        _ => break,
+                    }
+                }
+            }
+            AtomKind::Text(_) | AtomKind::Atomic => {
+                let opportunity = anywhere
+                    || before.is_some_and(|b| is_break_opportunity(b, class, style.word_break()));
+                // "An otherwise unbreakable sequence of characters may be broken at an arbitrary
+                // point if there are no otherwise-acceptable break points in the line."
+                // word-break: break-word has the same effect as overflow-wrap: anywhere.
+                let emergency = style.overflow_wrap() != OverflowWrap::Normal
+                    || style.word_break() == WordBreak::BreakWord;
+                self.atoms[last].break_after = wraps && opportunity;
+                self.atoms[last].emergency_break = wraps && emergency;
+            }
+            _ => {}
+        }
+    }
+
+    /// https://www.w3.org/TR/css-text-3/#white-space-processing
+    /// Transforms the text, collapses or preserves its white space, and splits it into grapheme
+    /// clusters, spaces and forced line breaks.
+    fn collect_text(&mut self, owner: usize, text: &str) {
+        let style = self.objects[owner].style.clone();
+        let white_space = style.white_space();
+        let font = style.font();
+        let letter_spacing = style.letter_spacing();
+        let word_spacing = style.word_spacing();
+        // https://www.w3.org/TR/css-text-3/#letter-spacing-property
+        // https://www.w3.org/TR/css-text-3/#word-spacing-property
+        // Letter spacing is added after every typographic character unit, which is a grapheme
+        // cluster, and word spacing after every space.
+        let space_width = self.font_metrics.advance(" ", &font) + letter_spacing + word_spacing;
+
+        let text = style.text_transform().apply(text).replace('\r', "");
+        // The column in the line, which decides where preserved tabs stop.
+        let mut column = 0;
+
+        for g in graphemes(&text) {
+            match g {
+                "\n" if white_space.preserves_newlines() => {
+                    self.push_atom(AtomKind::Break, owner, 0, false);
+                    self.after_collapsible_space = true;
+                    self.line_break_class = None;
+                    column = 0;
+                }
+                " " | "\t" | "\n" if white_space.collapses_spaces() => {
+                    if !self.after_collapsible_space {
+                        self.break_before_space(&style);
+                        self.push_atom(
+                            AtomKind::Space { collapsible: true },
+                            owner,
+                            space_width,
+                            white_space.wraps(),
+                        );
+                        self.after_collapsible_space = true;
+                    }
+                }
+                " " | "\t" => {
+                    self.break_before_space(&style);
+                    let count = if g == "\t" {
+                        TAB_SIZE - column % TAB_SIZE
+                    } else {
+                        1
+                    };
+                    for _ in 0..count {
+                        self.push_atom(
+                            AtomKind::Space { collapsible: false },
+                            owner,
+                            space_width,
+                            white_space.wraps(),
+                        );
+                    }
+                    self.after_collapsible_space = false;
+                    column += count;
+                }
+                g => {
+                    let class = resolve_class(g, style.word_break(), style.line_break());
+                    self.break_before(class, &style);
+                    let width = self.font_metrics.advance(g, &font) + letter_spacing;
+                    self.push_atom(AtomKind::Text(g.to_string()), owner, width, false);
+                    self.after_collapsible_space = false;
+                    column += 1;
+                }
+            }
+        }
+    }
+
+    /// Spaces don't have a soft wrap opportunity before them, except with line-break: anywhere.
+    fn break_before_space(&mut self, style: &ComputedStyle) {
+        if style.line_break() != LineBreak::Anywhere {
+            return;
+        }
+        if let Some(last) = self.last_content_atom() {
+            if matches!(self.atoms[last].kind, AtomKind::Text(_) | AtomKind::Atomic) {
+                self.atoms[last].break_after = style.white_space().wraps();
+            }
+        }
+    }
+
+    /// The width available to a line. The first line is shortened by text-indent.
+    /// https://www.w3.org/TR/css-text-3/#text-indent-property
+    fn indent(&self, first_line: bool) -> i64 {
+        if first_line {
+            self.container_style.text_indent().resolve(self.width)
+        } else {
+            0
+        }
+    }
+
+    /// The left and right edges of the space for a line box at `top`, which floats don't take.
+    fn line_space(&self, top: i64) -> (i64, i64) {
+        let (left, right) = self.floats.available_space(
+            self.origin.y() + top,
+            self.container_style.line_height(),
+            self.origin.x(),
+            self.origin.x() + self.width,
+        );
+        (left - self.origin.x(), right - self.origin.x())
+    }
+
+    /// https://www.w3.org/TR/css-text-3/#line-breaking
+    /// Puts as many atoms from `start` on a line as fit in `available`, and breaks the line at
+    /// the last soft wrap opportunity when the next atom doesn't fit. If the line has no soft
+    /// wrap opportunity, it's broken at the last emergency break, and otherwise the atom
+    /// overflows it. Atoms from `end` are not put on the line. Returns the line and the index of
+    /// the atom that starts the next line.
+    fn break_line(&self, start: usize, end: usize, available: i64) -> (Line, usize) {
+        let mut line: Vec<usize> = Vec::new();
+        let mut width = 0;
+        // The position in `line` after the last soft wrap opportunity.
+        let mut last_break: Option<usize> = None;
+        let mut last_emergency_break: Option<usize> = None;
+        let mut has_content = false;
+
+        for (i, atom) in self.atoms[..end].iter().enumerate().skip(start) {
+            match atom.kind {
+                AtomKind::Break => {
+                    line.push(i);
+                    return (
+                        Line {
+                            atoms: line,
+                            forced: true,
+                        },
+                        i + 1,
+                    );
+                }
+                // Collapsible spaces at the start of a line are removed.
+                AtomKind::Space { collapsible: true } if !has_content => continue,
+                AtomKind::Text(_) | AtomKind::Atomic => {
+                    if let Some(at) = last_break
+                        .or(last_emergency_break)
+                        .filter(|_| width + atom.width > available)
+                    {
+                        // Ends of inline boxes just after the break stay on this line.
+                        let mut at = at;
+                        while at < line.len() && self.atoms[line[at]].kind == AtomKind::End {
+                            at += 1;
+                        }
+                        let next = line.get(at).copied().unwrap_or(i);
+                        line.truncate(at);
+                        return (
+                            Line {
+                                atoms: line,
+                                forced: false,
+                            },
+                            next,
+                        );
+                    }
+                }
+                _ => {}
+            }
+            line.push(i);
+            width += atom.width;
+            has_content |= atom.is_content();
+            if atom.break_after {
+                last_break = Some(line.len());
+            }
+            if atom.emergency_break {
+                last_emergency_break = Some(line.len());
+            }
+        }
+        (
+            Line {
+                atoms: line,
+                forced: false,
+            },
+            end,
+        )
+    }
+
+    /// The width of the atoms on a line without the collapsible spaces at its end.
+    fn line_width(&self, line: &Line) -> i64 {
+        let end = line
+            .atoms
+            .iter()
+            .rposition(|i| {
+                !matches!(
+                    self.atoms[*i].kind,
+                    AtomKind::Space { collapsible: true } | AtomKind::End
+                )
+            })
+            .map_or(0, |p| p + 1);
+        line.atoms[..end].iter().map(|i| self.atoms[*i].width).sum()
+    }
+
+    /// The size of the margin box of the float of an atom.
+    fn float_size(&self, atom: usize) -> LayoutSize {
+        let o = self.objects[self.atoms[atom].owner].object.borrow();
+        LayoutSize::new(
+            o.size().width() + o.margin().horizontal(),
+            o.size().height() + o.margin().vertical(),
+        )
+    }
+
+    fn is_placed(&self, atom: usize) -> bool {
+        self.placed_floats.iter().any(|(a, _)| *a == atom)
+    }
+
+    /// https://www.w3.org/TR/CSS2/visuren.html#float-position
+    /// Places a float at `top` in the content box, and keeps its offset.
+    fn place_float(&mut self, atom: usize, top: i64) {
+        let size = self.float_size(atom);
+        let object = &self.objects[self.atoms[atom].owner];
+        let side = object.style.float();
+        let margin = object.object.borrow().margin();
+        let point = self.floats.place(
+            side,
+            size,
+            self.origin.y() + top,
+            self.origin.x(),
+            self.origin.x() + self.width,
+        );
+        self.placed_floats.push((
+            atom,
+            LayoutPoint::new(
+                point.x() - self.origin.x() + margin.left,
+                point.y() - self.origin.y() + margin.top,
+            ),
+        ));
+    }
+
+    /// Places the floats on a line at the top of the line box if they fit next to the content
+    /// before them. Returns true if any float is placed.
+    fn place_floats_at_top(&mut self, line: &Line, top: i64, available: i64) -> bool {
+        let mut placed = false;
+        let mut width = 0;
+        for i in &line.atoms {
+            if self.atoms[*i].kind == AtomKind::Float && !self.is_placed(*i) {
+                if width + self.float_size(*i).width() > available {
+                    break;
+                }
+                self.place_float(*i, top);
+                placed = true;
+            }
+            width += self.atoms[*i].width;
+        }
+        placed
+    }
+
+    /// Places the floats on a line that didn't fit on it below the line box, whose bottom is at
+    /// `top`.
+    fn place_floats_below(&mut self, line: &Line, top: i64) {
+        for i in &line.atoms {
+            if self.atoms[*i].kind == AtomKind::Float && !self.is_placed(*i) {
+                self.place_float(*i, top);
+            }
+        }
+    }
+
+    /// Returns the objects whose boxes are on a line: the owners of its atoms and the inline
+    /// boxes that contain them.
+    fn objects_on_line(&self, atoms: &[usize]) -> Vec<usize> {
+        let mut on_line = Vec::new();
+        for i in atoms {
+            let mut object = Some(self.atoms[*i].owner);
+            while let Some(o) = object {
+                if !on_line.contains(&o) {
+                    on_line.push(o);
+                }
+                object = self.objects[o].parent;
+            }
+        }
+        on_line
+    }
+
+    /// The extents of an object above and below its baseline, which decide the height of the
+    /// line box.
+    fn extents(&self, index: usize) -> (i64, i64) {
+        let object = &self.objects[index];
+        match object.kind {
+            // An atomic inline has its baseline at its bottom margin edge.
+            LayoutObjectKind::Block | LayoutObjectKind::Grid => {
+                let o = object.object.borrow();
+                (o.size().height() + o.margin().vertical(), 0)
+            }
+            _ => self.layout_bounds(&object.style),
+        }
+    }
+
+    /// https://www.w3.org/TR/css-inline-3/#line-box
+    /// Aligns the atoms of a line horizontally and vertically, and adds the fragments of the
+    /// objects on it. Returns the height of the line box.
+    /// The line box is `width` wide, and its left edge is at `left` in the content box.
+    fn place_line(
+        &mut self,
+        line: &Line,
+        first_line: bool,
+        last_line: bool,
+        top: i64,
+        left: i64,
+        width: i64,
+    ) -> i64 {
+        // Floats are placed apart from the line box.
+        let mut atoms: Vec<usize> = line
+            .atoms
+            .iter()
+            .filter(|i| self.atoms[**i].kind != AtomKind::Float)
+            .copied()
+            .collect();
+        // Collapsible spaces at the end of a line are removed.
+        while let Some(p) = atoms
+            .iter()
+            .rposition(|i| self.atoms[*i].kind != AtomKind::End)
+        {
+            if !self.atoms[atoms[p]].is_collapsible_space() {
+                break;
+            }
+            atoms.remove(p);
+        }
+        // https://www.w3.org/TR/css-inline-3/#invisible-line-boxes
+        // Line boxes that contain nothing visible are treated as zero-height line boxes, except
+        // for the ones that end with a forced break, which only preserved newlines make.
+        if !line.forced && !atoms.iter().any(|i| self.atoms[*i].is_content()) {
+            return 0;
+        }
+
+        // Preserved spaces at the end of the line hang and are not aligned.
+        let mut content_width: i64 = atoms.iter().map(|i| self.atoms[*i].width).sum();
+        let mut hanging = 0;
+        for i in atoms.iter().rev() {
+            let atom = &self.atoms[*i];
+            if atom.is_hanging_space() {
+                content_width -= atom.width;
+                hanging += 1;
+            } else if atom.kind != AtomKind::End {
+                break;
+            }
+        }
+        let spaces: Vec<usize> = atoms
+            .iter()
+            .filter(|i| matches!(self.atoms[**i].kind, AtomKind::Space { .. }))
+            .copied()
+            .collect();
+        let spaces = &spaces[..spaces.len() - hanging];
+
+        let indent = self.indent(first_line);
+        let free = width - indent - content_width;
+        // https://www.w3.org/TR/css-text-3/#text-align-property
+        // The direction is always ltr, so start is left and end is right. Lines that overflow
+        // are aligned to the start edge.
+        let (offset, justification) = match self.container_style.text_align() {
+            TextAlign::Start | TextAlign::Left => (0, 0),
+            TextAlign::End | TextAlign::Right => (free.max(0), 0),
+            TextAlign::Center => (free.max(0) / 2, 0),
+            // "Text is justified according to the method specified by the text-justify
+            // property", whose auto expands the spaces between words. "The last line of a
+            // block, or a line ending in a forced break, is start-aligned."
+            TextAlign::Justify if !last_line && !line.forced && !spaces.is_empty() && free > 0 => {
+                (0, free)
+            }
+            TextAlign::Justify => (0, 0),
+        };
+
+        // The x position of every atom on the line. The justification space is distributed to
+        // the spaces as evenly as possible, and the remainder goes to the first spaces.
+        let mut x = left + indent + offset;
+        let mut positions = Vec::with_capacity(atoms.len());
+        for i in &atoms {
+            let mut width = self.atoms[*i].width;
+            if let Some(n) = spaces.iter().position(|s| s == i) {
+                let count = spaces.len() as i64;
+                width += justification / count
+                    + if (n as i64) < justification % count {
+                        1
+                    } else {
+                        0
+                    };
+            }
+            positions.push((x, width));
+            x += width;
+        }
+
+        // https://www.w3.org/TR/css-inline-3/#inline-height
+        // The line box is tall enough to contain the strut of the block container and the
+        // layout bounds of every box on it. Boxes aligned to the top or the bottom of the line
+        // box are measured separately and fit between them.
+        let on_line = self.objects_on_line(&atoms);
+        let mut above = vec![0; self.groups.len()];
+        let mut below = vec![0; self.groups.len()];
+        let strut = match &self.first_line_style {
+            Some(style) if first_line => style,
+            _ => &self.container_style,
+        };
+        let (strut_above, strut_below) = self.layout_bounds(strut);
+        above[0] = strut_above;
+        below[0] = strut_below;
+        for o in &on_line {
+            let (a, d) = self.extents(*o);
+            let object = &self.objects[*o];
+            above[object.group] = above[object.group].max(object.shift + a);
+            below[object.group] = below[object.group].max(d - object.shift);
+        }
+        let mut height = above[0] + below[0];
+        for g in 1..self.groups.len() {
+            height = height.max(above[g] + below[g]);
+        }
+        let baselines: Vec<i64> = self
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(g, group)| match group {
+                AlignmentGroup::Baseline => top + above[0],
+                AlignmentGroup::Top => top + above[g],
+                AlignmentGroup::Bottom => top + height - below[g],
+            })
+            .collect();
+
+        // The horizontal range of each object on the line. The owner of an atom covers its
+        // border box part, and the inline boxes that contain it cover the whole atom.
+        let mut ranges: Vec<Option<(i64, i64)>> = vec![None; self.objects.len()];
+        let mut extend = |o: usize, left: i64, right: i64| {
+            ranges[o] = Some(match ranges[o] {
+                Some((l, r)) => (l.min(left), r.max(right)),
+                None => (left, right),
+            });
+        };
+        for (i, (x, width)) in atoms.iter().zip(&positions) {
+            let atom = &self.atoms[*i];
+            let owner = &self.objects[atom.owner];
+            let o = owner.object.borrow();
+            let (left, right) = match atom.kind {
+                AtomKind::Start => (x + o.margin().left, x + width),
+                AtomKind::End => (*x, x + width - o.margin().right),
+                AtomKind::Atomic => (x + o.margin().left, x + width - o.margin().right),
+                _ => (*x, x + width),
+            };
+            extend(atom.owner, left, right);
+            let mut parent = owner.parent;
+            while let Some(p) = parent {
+                extend(p, *x, x + width);
+                parent = self.objects[p].parent;
+            }
+        }
+
+        for o in on_line {
+            let (left, right) = match ranges[o] {
+                Some(range) => range,
+                None => continue,
+            };
+            let object = &self.objects[o];
+            let baseline = baselines[object.group] - object.shift;
+            let fragment = match object.kind {
+                LayoutObjectKind::Text => {
+                    let (a, d) = self.layout_bounds(&object.style);
+                    let runs = self.text_runs(o, &atoms, &positions, left);
+                    Fragment::new(
+                        LayoutPoint::new(left, baseline - a),
+                        LayoutSize::new(right - left, a + d),
+                        runs,
+                    )
+                }
+                // https://www.w3.org/TR/css-inline-3/#inline-box-dimensions
+                // The borders and paddings of an inline box surround its content area, which is
+                // as tall as the ascent and the descent of its font.
+                LayoutObjectKind::Inline => {
+                    let b = object.object.borrow();
+                    let top =
+                        baseline - self.ascent(&object.style) - b.padding().top - b.border().top;
+                    let bottom = baseline
+                        + self.descent(&object.style)
+                        + b.padding().bottom
+                        + b.border().bottom;
+                    Fragment::new(
+                        LayoutPoint::new(left, top),
+                        LayoutSize::new(right - left, bottom - top),
+                        Vec::new(),
+                    )
+                }
+                LayoutObjectKind::Block | LayoutObjectKind::Grid => {
+                    let b = object.object.borrow();
+                    let (a, _) = self.extents(o);
+                    Fragment::new(
+                        LayoutPoint::new(left, baseline - a + b.margin().top),
+                        b.size(),
+                        Vec::new(),
+                    )
+                }
+            };
+            let fragment = match self.first_line {
+                Some((first, _))
+                    if o >= first
+                        && matches!(
+                            object.kind,
+                            LayoutObjectKind::Text | LayoutObjectKind::Inline
+                        ) =>
+                {
+                    fragment.with_style(object.style.clone())
+                }
+                _ => fragment,
+            };
+            self.objects[o].fragments.push(fragment);
+        }
+
+        height
+    }
+
+    /// Returns the runs of text of an object on a line, with their positions relative to the
+    /// fragment at `left`. Adjacent atoms are painted as one run unless letter-spacing,
+    /// word-spacing or justification moves them apart.
+    fn text_runs(
+        &self,
+        owner: usize,
+        atoms: &[usize],
+        positions: &[(i64, i64)],
+        left: i64,
+    ) -> Vec<(LayoutPoint, String)> {
+        let style = &self.objects[owner].style;
+        let font = style.font();
+        let letter_spacing = style.letter_spacing();
+        // The glyphs are drawn from the top of the content area, below the half-leading.
+        let y = self.half_leading(style);
+
+        // Runs with the x position where their next grapheme cluster would be drawn.
+        let mut runs: Vec<(LayoutPoint, String, i64)> = Vec::new();
+        for (i, (x, _)) in atoms.iter().zip(positions) {
+            let atom = &self.atoms[*i];
+            if atom.owner != owner {
+                continue;
+            }
+            let text = match &atom.kind {
+                AtomKind::Text(text) => text.clone(),
+                AtomKind::Space { .. } => " ".to_string(),
+                _ => continue,
+            };
+            let mut x = x - left;
+            for g in graphemes(&text) {
+                let advance = self.font_metrics.advance(g, &font);
+                match runs.last_mut() {
+                    Some((_, run, end)) if *end == x => {
+                        run.push_str(g);
+                        *end += advance;
+                    }
+                    _ => runs.push((LayoutPoint::new(x, y), g.to_string(), x + advance)),
+                }
+                x += advance + letter_spacing;
+            }
+        }
+        // Runs of spaces don't paint anything.
+        runs.into_iter()
+            .filter(|(_, run, _)| !run.trim().is_empty())
+            .map(|(point, run, _)| (point, run))
+            .collect()
+    }
+}
@@ -1,21 +1,47 @@
 use crate::alloc::string::ToString;
-use crate::constants::CHAR_HEIGHT;
-use crate::constants::CHAR_HEIGHT_WITH_PADDING;
-use crate::constants::CHAR_WIDTH;
-use crate::constants::CONTENT_AREA_WIDTH;
-use crate::constants::WINDOW_PADDING;
-use crate::constants::WINDOW_WIDTH;
+use crate::display_item::BorderSide;
+use crate::display_item::BorderSides;
+use crate::display_item::BulletShape;
+use crate::display_item::CornerRadii;
 use crate::display_item::DisplayItem;
-use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::cssom::CascadeOrigin;
 use crate::renderer::css::cssom::Declaration;
+use crate::renderer::css::cssom::PseudoElement;
 use crate::renderer::css::cssom::Selector;
 use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::media::Device;
+use crate::renderer::css::user_agent::presentational_hints;
+use crate::renderer::css::value::LengthPercentageOrAuto;
+use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
+use crate::renderer::image::Image;
+use crate::renderer::image::ImageStore;
+use crate::renderer::layout::computed_style::BoxSizing;
 use crate::renderer::layout::computed_style::Color;
+use crate::renderer::layout::computed_style::ColorValue;
 use crate::renderer::layout::computed_style::ComputedStyle;
+use crate::renderer::layout::computed_style::Content;
+use crate::renderer::layout::computed_style::Corner;
 use crate::renderer::layout::computed_style::DisplayType;
-use crate::renderer::layout::computed_style::FontSize;
+use crate::renderer::layout::computed_style::Float;
+use crate::renderer::layout::computed_style::ListStylePosition;
+use crate::renderer::layout::computed_style::ListStyleType;
+use crate::renderer::layout::computed_style::Overflow;
+use crate::renderer::layout::computed_style::Position;
+use crate::renderer::layout::computed_style::Side;
+use crate::renderer::layout::computed_style::StyleDifference;
+use crate::renderer::layout::counter::CounterScopes;
+use crate::renderer::layout::counter::CounterStyle;
+use crate::renderer::layout::flex_layout;
+use crate::renderer::layout::float_layout::FloatManager;
+use crate::renderer::layout::font_metrics::FontMetrics;
+use crate::renderer::layout::grid_layout;
+use crate::renderer::layout::inline_layout::layout_inline_content;
+use crate::renderer::layout::scroll::clamp_scroll_offset;
+use crate::renderer::layout::scroll::scrollbar;
+use crate::renderer::layout::scroll::Axis;
+use crate::renderer::layout::transform::Transform;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
@@ -23,30 +49,19 @@ use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
-/// This is used when { word-break: normal; } in CSS.
-/// https://drafts.csswg.org/css-text/#word-break-property
-fn find_index_for_line_break(line: String, max_index: usize) -> usize {
-    for i in (0..max_index).rev() {
-        if line.chars().collect::<Vec<char>>()[i] == ' ' {
-            return i;
-        }
-    }
-    max_index
-}
-/// https://drafts.csswg.org/css-text/#word-break-property
-fn split_text(line: String, char_width: i64) -> Vec<String> {
-    let mut result: Vec<String> = vec![];
-    if line.len() as i64 * char_width > (WINDOW_WIDTH + WINDOW_PADDING) {
-        let s = line.split_at(find_index_for_line_break(
-            line.clone(),
-            ((WINDOW_WIDTH + WINDOW_PADDING) / char_width) as usize,
-        ));
-        result.push(s.0.to_string());
-        result.extend(split_text(s.1.trim().to_string(), char_width))
+/// https://www.w3.org/TR/CSS2/box.html#collapsing-margins
+/// "When two or more margins collapse, the resulting margin width is the maximum of the
+/// collapsing margins' widths. In the case of negative margins, the maximum of the absolute
+/// values of the negative adjoining margins is deducted from the maximum of the positive adjoining
+/// margins."
+fn collapse_margins(a: i64, b: i64) -> i64 {
+    if a >= 0 && b >= 0 {
+        a.max(b)
+    } else if a < 0 && b < 0 {
+        a.min(b)
     } else {
-        result.push(line);
+        a + b
     }
-    result
 }
 
 #[derive(Debug, Clone)]
@@ -57,8 +72,66 @@ pub struct LayoutObject {
     next_sibling: Option<Rc<RefCell<LayoutObject>>>,
     parent: Weak<RefCell<LayoutObject>>,
     style: ComputedStyle,
+    /// The top-left corner of the border box.
     point: LayoutPoint,
+    /// The size of the border box.
     size: LayoutSize,
+    margin: EdgeSizes,
+    padding: EdgeSizes,
+    border: EdgeSizes,
+    /// The position of the border box relative to the content box of the containing block. It's
+    /// decided by the layout of the containing block.
+    offset: LayoutPoint,
+    /// The pieces of an inline-level box or text on each line box.
+    fragments: Vec<Fragment>,
+    /// https://www.w3.org/TR/css-overflow-3/#scrollable
+    /// The size of the scrollable overflow area of a block box, measured from the top-left corner
+    /// of its padding box.
+    scrollable_overflow: LayoutSize,
+    /// How far the content of a scroll container is scrolled from its padding box.
+    scroll_offset: LayoutPoint,
+    /// How far a relatively or sticky positioned box is moved from its position in flow.
+    relative_offset: LayoutPoint,
+    /// The width and the height of the content box of a flex item, which are decided by the
+    /// flex container instead of the width and height properties.
+    forced_size: (Option<i64>, Option<i64>),
+    /// https://www.w3.org/TR/css-lists-3/#list-style-position-property
+    /// The ::marker of a list item whose list-style-position is outside. It's a text box that is
+    /// laid out and painted with the list item, outside the principal box. An inside marker is
+    /// the first child instead.
+    marker: Option<Rc<RefCell<LayoutObject>>>,
+    /// The shape that a marker of a disc, circle or square counter style is drawn as.
+    bullet: Option<BulletShape>,
+    /// https://www.w3.org/TR/css-pseudo-4/#first-line-pseudo
+    /// The style on the first line of the block container. It's the ::first-line style for a
+    /// block container that has ::first-line rules, and the style that inherits from it for an
+    /// inline-level box or text in such a block container.
+    first_line_style: Option<ComputedStyle>,
+    /// https://html.spec.whatwg.org/multipage/rendering.html#images-3
+    /// The image of an <img> that is loaded. The element is a replaced element whose content is
+    /// the image instead of its children.
+    image: Option<Rc<Image>>,
+    /// https://www.w3.org/TR/css-backgrounds-3/#background-image
+    /// The loaded images of the background-image layers, from the top one.
+    background_images: Vec<Rc<Image>>,
+    /// True if this box or a descendant is changed since the box was last laid out.
+    needs_layout: bool,
+    /// The inputs of the last layout of this box, if it didn't depend on floats.
+    layout_input: Option<LayoutInput>,
+    /// True if the style of this box is changed in a way that only has to be painted again.
+    needs_paint: bool,
+    /// The visual rectangle of this box in the coordinates of the document, as it was when the
+    /// damage was last collected.
+    painted_rect: Option<(LayoutPoint, LayoutSize)>,
+}
+
+/// The inputs that the layout of a box depends on besides its style and its descendants. A box
+/// that doesn't need layout keeps its sizes when it's laid out again with the same inputs.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct LayoutInput {
+    parent_size: LayoutSize,
+    definite_height: bool,
+    forced_size: (Option<i64>, Option<i64>),
 }
 
 impl LayoutObject {
@@ -70,167 +143,1419 @@ impl LayoutObject {
         self.node.borrow().kind().clone()
     }
 
-    pub fn compute_position(
+    pub fn node(&self) -> Rc<RefCell<Node>> {
+        self.node.clone()
+    }
+
+    /// Returns true if this box isn't generated by a node in the DOM tree, such as the box of a
+    /// pseudo-element or a part of a text split by ::first-letter.
+    pub fn is_anonymous(&self) -> bool {
+        self.node.borrow().parent().upgrade().is_none()
+    }
+
+    /// Marks this box and its ancestors to be laid out again, since the sizes of the ancestors
+    /// may depend on this box.
+    pub fn set_needs_layout(&mut self) {
+        self.needs_layout = true;
+        let mut parent = self.parent.upgrade();
+        while let Some(p) = parent {
+            p.borrow_mut().needs_layout = true;
+            parent = p.borrow().parent.upgrade();
+        }
+    }
+
+    pub fn set_needs_paint(&mut self) {
+        self.needs_paint = true;
+    }
+
+    /// Returns the visual rectangle that this box was last painted in, and remembers `rect` as the
+    /// one that it's painted in next. The old rectangle is returned if it's painted again because
+    /// its style is changed, or if it's moved or resized.
+    pub fn update_painted_rect(
         &mut self,
-        parent_point: LayoutPoint,
-        previous_sibiling_kind: LayoutObjectKind,
-        previous_sibiling_point: Option<LayoutPoint>,
-        previous_sibiling_size: Option<LayoutSize>,
+        rect: (LayoutPoint, LayoutSize),
+    ) -> Option<(LayoutPoint, LayoutSize)> {
+        let old = self.painted_rect.replace(rect);
+        let needs_paint = core::mem::take(&mut self.needs_paint);
+        match old {
+            Some(old) if !needs_paint && old == rect => None,
+            _ => Some(old.unwrap_or(rect)),
+        }
+    }
+
+    /// The rectangle that this box paints in before transforms: the border box of a block-level
+    /// box, or the rectangle around the fragments of an inline box or text.
+    pub fn visual_rect(&self) -> (LayoutPoint, LayoutSize) {
+        match self.kind {
+            LayoutObjectKind::Block | LayoutObjectKind::Grid => (self.point, self.size),
+            LayoutObjectKind::Inline | LayoutObjectKind::Text => {
+                let mut rect: Option<(i64, i64, i64, i64)> = None;
+                for fragment in &self.fragments {
+                    let left = self.point.x() + fragment.point().x();
+                    let top = self.point.y() + fragment.point().y();
+                    let right = left + fragment.size().width();
+                    let bottom = top + fragment.size().height();
+                    rect = Some(match rect {
+                        Some((l, t, r, b)) => {
+                            (l.min(left), t.min(top), r.max(right), b.max(bottom))
+                        }
+                        None => (left, top, right, bottom),
+                    });
+                }
+                let (left, top, right, bottom) = rect.unwrap_or((0, 0, 0, 0));
+                (
+                    LayoutPoint::new(left, top),
+                    LayoutSize::new(right - left, bottom - top),
+                )
+            }
+        }
+    }
+
+    /// Places this object at its offset from the content box of its containing block, whose
+    /// top-left corner is `containing_block_point`.
+    pub fn compute_position(&mut self, containing_block_point: LayoutPoint) {
+        self.point = LayoutPoint::new(
+            containing_block_point.x() + self.offset.x() + self.relative_offset.x(),
+            containing_block_point.y() + self.offset.y() + self.relative_offset.y(),
+        );
+        self.place_marker();
+    }
+
+    /// https://www.w3.org/TR/css-position-3/#abspos-insets
+    /// Places an absolutely positioned box in its containing block. An axis whose insets are both
+    /// auto keeps the static position, which is where the box would be in flow, at
+    /// `static_point`.
+    pub fn compute_out_of_flow_position(
+        &mut self,
+        containing_block_point: LayoutPoint,
+        containing_block_size: LayoutSize,
+        static_point: LayoutPoint,
     ) {
-        let mut point = LayoutPoint::new(0, 0);
-        match (self.kind(), previous_sibiling_kind) {
-            (LayoutObjectKind::Block, _) | (_, LayoutObjectKind::Block) => {
-                if let (Some(size), Some(pos)) = (previous_sibiling_size, previous_sibiling_point) {
-                    point.set_y(pos.y() + size.height());
-                } else {
-                    point.set_y(parent_point.y());
+        let (cb_point, cb_size) = (containing_block_point, containing_block_size);
+        let inset = |side: Side, basis: i64| match self.style.inset(side) {
+            LengthPercentageOrAuto::LengthPercentage(l) => Some(l.resolve(basis)),
+            LengthPercentageOrAuto::Auto => None,
+        };
+        let x = match (
+            inset(Side::Left, cb_size.width()),
+            inset(Side::Right, cb_size.width()),
+        ) {
+            (Some(left), _) => cb_point.x() + left + self.margin.left,
+            (None, Some(right)) => {
+                cb_point.x() + cb_size.width() - right - self.margin.right - self.size.width()
+            }
+            (None, None) => static_point.x() + self.margin.left,
+        };
+        let y = match (
+            inset(Side::Top, cb_size.height()),
+            inset(Side::Bottom, cb_size.height()),
+        ) {
+            (Some(top), _) => cb_point.y() + top + self.margin.top,
+            (None, Some(bottom)) => {
+                cb_point.y() + cb_size.height() - bottom - self.margin.bottom - self.size.height()
+            }
+            (None, None) => static_point.y() + self.margin.top,
+        };
+        self.point = LayoutPoint::new(x, y);
+        self.place_marker();
+    }
+
+    /// Places the outside marker at its offset from the content box.
+    fn place_marker(&self) {
+        if let Some(marker) = &self.marker {
+            marker.borrow_mut().compute_position(self.content_point());
+        }
+    }
+
+    /// https://www.w3.org/TR/css-lists-3/#list-style-position-property
+    /// Attaches the ::marker of this list item as the first child or as an outside marker.
+    pub fn attach_marker(&mut self, marker: Rc<RefCell<LayoutObject>>) {
+        match self.style.list_style_position() {
+            ListStylePosition::Inside => {
+                marker
+                    .borrow_mut()
+                    .set_next_sibling(self.first_child.take());
+                self.first_child = Some(marker);
+            }
+            ListStylePosition::Outside => self.marker = Some(marker),
+        }
+    }
+
+    pub fn marker(&self) -> Option<Rc<RefCell<LayoutObject>>> {
+        self.marker.clone()
+    }
+
+    /// https://www.w3.org/TR/css-pseudo-4/#generated-content
+    /// Attaches ::before as the first child and ::after as the last child.
+    pub fn attach_generated_content(
+        &mut self,
+        before: Option<Rc<RefCell<LayoutObject>>>,
+        after: Option<Rc<RefCell<LayoutObject>>>,
+    ) {
+        if let Some(after) = after {
+            match self.last_child() {
+                Some(last) => last.borrow_mut().set_next_sibling(Some(after)),
+                None => self.first_child = Some(after),
+            }
+        }
+        if let Some(before) = before {
+            before
+                .borrow_mut()
+                .set_next_sibling(self.first_child.take());
+            self.first_child = Some(before);
+        }
+    }
+
+    /// Replaces `child` with `replacement`, which are given in order.
+    fn replace_child(
+        &mut self,
+        child: &Rc<RefCell<LayoutObject>>,
+        replacement: Vec<Rc<RefCell<LayoutObject>>>,
+    ) {
+        let mut next = child.borrow().next_sibling();
+        for r in replacement.into_iter().rev() {
+            r.borrow_mut().set_next_sibling(next);
+            next = Some(r);
+        }
+        if self
+            .first_child
+            .as_ref()
+            .is_some_and(|first| Rc::ptr_eq(first, child))
+        {
+            self.first_child = next;
+            return;
+        }
+        let mut previous = self.first_child();
+        while let Some(p) = previous {
+            let sibling = p.borrow().next_sibling();
+            if sibling.as_ref().is_some_and(|s| Rc::ptr_eq(s, child)) {
+                p.borrow_mut().set_next_sibling(next);
+                return;
+            }
+            previous = sibling;
+        }
+    }
+
+    fn last_child(&self) -> Option<Rc<RefCell<LayoutObject>>> {
+        let mut last = self.first_child();
+        while let Some(next) = last.as_ref().and_then(|l| l.borrow().next_sibling()) {
+            last = Some(next);
+        }
+        last
+    }
+
+    pub fn first_line_style(&self) -> Option<ComputedStyle> {
+        self.first_line_style.clone()
+    }
+
+    /// Whether this box is a block container, whose inline-level children are laid out in line
+    /// boxes, so that it has a first line and a first letter.
+    fn is_block_container(&self) -> bool {
+        matches!(
+            self.style.display(),
+            DisplayType::Block | DisplayType::FlowRoot | DisplayType::ListItem
+        )
+    }
+
+    /// Whether this box or text is put on the lines of its block container as a part of the
+    /// text, not as an atomic inline or a float.
+    fn is_inline_level(&self) -> bool {
+        matches!(self.kind, LayoutObjectKind::Inline | LayoutObjectKind::Text)
+            && !self.is_out_of_flow()
+            && !self.is_floating()
+    }
+
+    /// https://www.w3.org/TR/css-pseudo-4/#first-line-inheritance
+    /// Sets the style on the first line from `declarations`, which match this object. A block
+    /// container with ::first-line rules in `style_sheets` gets the ::first-line style, and an
+    /// inline-level box or text inherits from the first line style of `parent`.
+    fn cascade_first_line_style(
+        &mut self,
+        declarations: &[(CascadeOrigin, Declaration)],
+        parent: Option<&ComputedStyle>,
+        style_sheets: &[StyleSheet],
+        device: &Device,
+    ) {
+        if !self.is_block_container() {
+            self.inherit_first_line_style(declarations, parent, device);
+            return;
+        }
+        let declarations = matching_declarations(style_sheets, device, |selector| {
+            self.is_pseudo_element_selected(selector, PseudoElement::FirstLine)
+        });
+        if !declarations.is_empty() {
+            self.first_line_style = Some(ComputedStyle::compute(
+                &declarations,
+                Some(&self.style),
+                device,
+            ));
+        }
+    }
+
+    /// Sets the style on the first line of an inline-level box or text from `declarations`, which
+    /// inherits from the first line style of `parent`.
+    fn inherit_first_line_style(
+        &mut self,
+        declarations: &[(CascadeOrigin, Declaration)],
+        parent: Option<&ComputedStyle>,
+        device: &Device,
+    ) {
+        self.first_line_style = parent
+            .filter(|_| self.is_inline_level())
+            .map(|parent| ComputedStyle::compute(declarations, Some(parent), device));
+    }
+
+    /// https://www.w3.org/TR/css-align-3/#first-baseline
+    /// The baseline of the first line box in this block container, measured from the top of the
+    /// content box. Returns None if there is no line box.
+    fn first_baseline(&self, font_metrics: &dyn FontMetrics) -> Option<i64> {
+        let mut child = self.first_child();
+        while let Some(c) = child {
+            let c_ref = c.borrow();
+            if !c_ref.is_out_of_flow() && !c_ref.is_floating() {
+                let ascent = || font_metrics.ascent(&c_ref.style.font());
+                let baseline = match c_ref.kind() {
+                    // The glyphs of text runs start at the top of the content area.
+                    LayoutObjectKind::Text => c_ref.fragments.first().and_then(|f| {
+                        f.text_runs()
+                            .first()
+                            .map(|(point, _)| f.point().y() + point.y() + ascent())
+                    }),
+                    LayoutObjectKind::Inline => c_ref
+                        .fragments
+                        .first()
+                        .map(|f| f.point().y() + c_ref.border.top + c_ref.padding.top + ascent()),
+                    // An atomic inline has its baseline at its bottom margin edge.
+                    _ if c_ref.is_atomic_inline() => {
+                        Some(c_ref.size.height() + c_ref.margin.bottom)
+                    }
+                    _ => c_ref
+                        .first_baseline(font_metrics)
+                        .map(|b| c_ref.border.top + c_ref.padding.top + b),
+                };
+                if let Some(baseline) = baseline {
+                    return Some(c_ref.offset.y() + baseline);
                 }
-                point.set_x(parent_point.x());
             }
-            (LayoutObjectKind::Inline, LayoutObjectKind::Inline) => {
-                if let (Some(size), Some(pos)) = (previous_sibiling_size, previous_sibiling_point) {
-                    // TODO: consider padding of the previous sibiling.
-                    point.set_x(pos.x() + size.width());
-                    point.set_y(pos.y());
-                } else {
-                    point.set_x(parent_point.x());
-                    point.set_y(parent_point.y());
+            child = c_ref.next_sibling();
+        }
+        None
+    }
+
+    /// https://www.w3.org/TR/css-lists-3/#list-style-position-property
+    /// Lays out the outside marker on one line. The marker box ends at the start of the border
+    /// box of the list item, and its baseline is aligned with the baseline of the first line box
+    /// of the list item.
+    fn layout_marker(&self, font_metrics: &dyn FontMetrics) {
+        let marker = match &self.marker {
+            Some(marker) => marker,
+            None => return,
+        };
+        let mut m = marker.borrow_mut();
+        let text = match m.node_kind() {
+            NodeKind::Text(text) => text,
+            _ => return,
+        };
+        let font = m.style.font();
+        let ascent = font_metrics.ascent(&font);
+        let line_height = m.style.line_height();
+        let half_leading = (line_height - ascent - font_metrics.descent(&font)) / 2;
+        let width = font_metrics.text_width(&text, &font);
+        let baseline = self
+            .first_baseline(font_metrics)
+            .unwrap_or(ascent + half_leading);
+
+        m.set_offset(LayoutPoint::new(
+            -(self.padding.left + self.border.left) - width,
+            baseline - ascent - half_leading,
+        ));
+        m.set_size(LayoutSize::new(width, line_height));
+        m.set_fragments(vec![Fragment::new(
+            LayoutPoint::new(0, 0),
+            LayoutSize::new(width, line_height),
+            vec![(LayoutPoint::new(0, half_leading), text)],
+        )]);
+    }
+
+    /// https://www.w3.org/TR/css-position-3/#relpos-insets
+    /// Returns how far a relatively positioned box is moved. "If both top and bottom are auto,
+    /// their used values are both zero. If one of them is auto, it becomes the negative of the
+    /// other. If neither is auto, bottom is ignored", and the same goes for left and right.
+    fn compute_relative_offset(&self, containing_block_size: LayoutSize) -> LayoutPoint {
+        if self.style.position() != Position::Relative {
+            return LayoutPoint::new(0, 0);
+        }
+        let offset = |start: Side, end: Side, basis: i64| match (
+            self.style.inset(start),
+            self.style.inset(end),
+        ) {
+            (LengthPercentageOrAuto::LengthPercentage(l), _) => l.resolve(basis),
+            (LengthPercentageOrAuto::Auto, end) => -end.resolve_or_zero(basis),
+        };
+        LayoutPoint::new(
+            offset(Side::Left, Side::Right, containing_block_size.width()),
+            offset(Side::Top, Side::Bottom, containing_block_size.height()),
+        )
+    }
+
+    pub fn relative_offset(&self) -> LayoutPoint {
+        self.relative_offset
+    }
+
+    pub fn set_relative_offset(&mut self, relative_offset: LayoutPoint) {
+        self.relative_offset = relative_offset;
+    }
+
+    pub fn is_out_of_flow(&self) -> bool {
+        self.style.position().is_out_of_flow()
+    }
+
+    /// https://www.w3.org/TR/CSS2/visudet.html#abs-non-replaced-width
+    /// Computes the used margins, borders and paddings of an absolutely positioned box, and
+    /// returns the width of the content box. When both left and right are given, an auto width
+    /// fills the space between them. Otherwise the width is shrink-to-fit and auto margins are 0.
+    fn compute_out_of_flow_box_edges(
+        &mut self,
+        containing_block_width: i64,
+        font_metrics: &dyn FontMetrics,
+    ) -> i64 {
+        let left = self.style.inset(Side::Left);
+        let right = self.style.inset(Side::Right);
+        let width = self.style.width();
+        if !left.is_auto() && !right.is_auto() {
+            let available = containing_block_width
+                - left.resolve_or_zero(containing_block_width)
+                - right.resolve_or_zero(containing_block_width);
+            return match width {
+                LengthPercentageOrAuto::Auto => self.compute_box_edges(available, width),
+                width => self.compute_box_edges(containing_block_width, width),
+            };
+        }
+
+        self.compute_shrink_to_fit_box_edges(containing_block_width, font_metrics)
+    }
+
+    /// https://www.w3.org/TR/CSS2/visudet.html#float-width
+    /// Computes the used margins, borders and paddings of a box whose auto width is
+    /// shrink-to-fit, which floats and absolutely positioned boxes are, and returns the width of
+    /// the content box. Their auto margins are 0.
+    fn compute_shrink_to_fit_box_edges(
+        &mut self,
+        containing_block_width: i64,
+        font_metrics: &dyn FontMetrics,
+    ) -> i64 {
+        let width = self.style.width();
+        let content_width = match width {
+            LengthPercentageOrAuto::Auto => {
+                // "shrink-to-fit width is: min(max(preferred minimum width, available width),
+                // preferred width)". The preferred minimum width is not computed, so the
+                // content may overflow a narrow containing block instead.
+                let available = self.compute_box_edges(containing_block_width, width);
+                available.min(self.max_content_width(font_metrics))
+            }
+            width => self.compute_box_edges(containing_block_width, width),
+        };
+        self.margin.left = self
+            .style
+            .margin(Side::Left)
+            .resolve_or_zero(containing_block_width);
+        self.margin.right = self
+            .style
+            .margin(Side::Right)
+            .resolve_or_zero(containing_block_width);
+        content_width
+    }
+
+    /// https://www.w3.org/TR/css-sizing-3/#max-content-inline-size
+    /// Returns the width of the content box when no line is wrapped. Percentages are treated as
+    /// 0 because they depend on the width that is being computed.
+    pub fn max_content_width(&self, font_metrics: &dyn FontMetrics) -> i64 {
+        match self.kind {
+            LayoutObjectKind::Text => match self.node_kind() {
+                NodeKind::Text(text) => {
+                    // Collapsible white space is collapsed into a space.
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    font_metrics.text_width(&text, &self.style.font())
                 }
+                _ => 0,
+            },
+            LayoutObjectKind::Inline => {
+                let mut width = 0;
+                let mut child = self.first_child();
+                while let Some(c) = child {
+                    if !c.borrow().is_out_of_flow() {
+                        width += c.borrow().outer_max_content_width(font_metrics);
+                    }
+                    child = c.borrow().next_sibling();
+                }
+                width
             }
-            _ => {
-                point.set_x(parent_point.x());
-                point.set_y(parent_point.y());
+            LayoutObjectKind::Block | LayoutObjectKind::Grid => {
+                if let Some(image) = &self.image {
+                    return self.intrinsic_replaced_width(image);
+                }
+                if let Some(width) = self.fixed_content_width() {
+                    return width;
+                }
+                if self.kind == LayoutObjectKind::Grid {
+                    return grid_layout::max_content_width(self, font_metrics);
+                }
+                if self.style.display().is_flex_container() {
+                    return flex_layout::max_content_width(self, font_metrics);
+                }
+                // Block-level children are on lines of their own, and inline-level ones are put
+                // on one line.
+                let (mut width, mut line) = (0, 0);
+                let mut child = self.first_child();
+                while let Some(c) = child {
+                    let c_ref = c.borrow();
+                    if c_ref.is_out_of_flow() {
+                        // Absolutely positioned children don't take space.
+                    } else if c_ref.is_block_level() {
+                        width = width
+                            .max(line)
+                            .max(c_ref.outer_max_content_width(font_metrics));
+                        line = 0;
+                    } else {
+                        line += c_ref.outer_max_content_width(font_metrics);
+                    }
+                    child = c_ref.next_sibling();
+                }
+                width.max(line)
             }
         }
-        self.point = point;
     }
 
-    pub fn compute_size(&mut self, parent_size: LayoutSize) {
-        let mut size = LayoutSize::new(0, 0);
+    /// The max-content width of the margin box.
+    pub fn outer_max_content_width(&self, font_metrics: &dyn FontMetrics) -> i64 {
+        self.max_content_width(font_metrics) + self.horizontal_edges() + self.horizontal_margins()
+    }
 
-        match self.kind() {
-            LayoutObjectKind::Block => {
-                size.set_width(parent_size.width());
-                let mut height = 0;
+    /// https://www.w3.org/TR/css-sizing-3/#min-content-inline-size
+    /// Returns the width of the content box when every line is wrapped where it can be, which is
+    /// the width of the widest word or box in it.
+    pub fn min_content_width(&self, font_metrics: &dyn FontMetrics) -> i64 {
+        match self.kind {
+            LayoutObjectKind::Text => match self.node_kind() {
+                NodeKind::Text(text) => text
+                    .split_whitespace()
+                    .map(|word| font_metrics.text_width(word, &self.style.font()))
+                    .max()
+                    .unwrap_or(0),
+                _ => 0,
+            },
+            LayoutObjectKind::Inline | LayoutObjectKind::Block | LayoutObjectKind::Grid => {
+                if let Some(image) = &self.image {
+                    return self.intrinsic_replaced_width(image);
+                }
+                if let Some(width) = self.fixed_content_width() {
+                    return width;
+                }
+                let mut width = 0;
                 let mut child = self.first_child();
-                let mut previous_child_kind = LayoutObjectKind::Block;
-                while child.is_some() {
-                    let c = match child {
-                        Some(c) => c,
-                        None => panic!("first child should exist"),
-                    };
+                while let Some(c) = child {
+                    if !c.borrow().is_out_of_flow() {
+                        width = width.max(c.borrow().outer_min_content_width(font_metrics));
+                    }
+                    child = c.borrow().next_sibling();
+                }
+                width
+            }
+        }
+    }
 
-                    if previous_child_kind == LayoutObjectKind::Block
-                        || c.borrow().kind() == LayoutObjectKind::Block
-                    {
-                        height += c.borrow().size().height();
+    /// The min-content width of the margin box.
+    pub fn outer_min_content_width(&self, font_metrics: &dyn FontMetrics) -> i64 {
+        self.min_content_width(font_metrics) + self.horizontal_edges() + self.horizontal_margins()
+    }
+
+    /// The width of the content box of a block box whose width is a length.
+    fn fixed_content_width(&self) -> Option<i64> {
+        match (self.kind, self.style.width()) {
+            (
+                LayoutObjectKind::Block | LayoutObjectKind::Grid,
+                LengthPercentageOrAuto::LengthPercentage(width),
+            ) if !width.has_percentage() => {
+                let width = width.resolve(0);
+                Some(match self.style.box_sizing() {
+                    BoxSizing::ContentBox => width.max(0),
+                    BoxSizing::BorderBox => (width - self.horizontal_edges()).max(0),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The width of the content box of a replaced element of `image` when it doesn't depend on
+    /// the containing block. Percentages are treated as auto.
+    fn intrinsic_replaced_width(&self, image: &Image) -> i64 {
+        let height = match self.style.height() {
+            LengthPercentageOrAuto::LengthPercentage(h) if !h.has_percentage() => {
+                let h = h.resolve(0);
+                Some(match self.style.box_sizing() {
+                    BoxSizing::ContentBox => h.max(0),
+                    BoxSizing::BorderBox => {
+                        let edges: i64 = [Side::Top, Side::Bottom]
+                            .iter()
+                            .map(|side| {
+                                self.style.padding(*side).resolve(0)
+                                    + self.style.border_width(*side)
+                            })
+                            .sum();
+                        (h - edges).max(0)
                     }
+                })
+            }
+            _ => None,
+        };
+        replaced_size(image, self.fixed_content_width(), height).width()
+    }
+
+    /// The sum of the left and right margins, with percentages and auto treated as 0.
+    fn horizontal_margins(&self) -> i64 {
+        self.style.margin(Side::Left).resolve_or_zero(0)
+            + self.style.margin(Side::Right).resolve_or_zero(0)
+    }
 
-                    previous_child_kind = c.borrow().kind();
+    /// The sum of the left and right paddings and borders, with percentages treated as 0.
+    fn horizontal_edges(&self) -> i64 {
+        [Side::Left, Side::Right]
+            .iter()
+            .map(|side| self.style.padding(*side).resolve(0) + self.style.border_width(*side))
+            .sum()
+    }
+
+    /// https://www.w3.org/TR/CSS2/visudet.html#blockwidth
+    /// Computes the used margins, borders and paddings, and returns the width of the content box.
+    /// Percentages of margins and paddings, including vertical ones, refer to the width of the
+    /// containing block.
+    fn compute_box_edges(
+        &mut self,
+        containing_block_width: i64,
+        width: LengthPercentageOrAuto,
+    ) -> i64 {
+        let style = &self.style;
+        self.padding = EdgeSizes::new(
+            style.padding(Side::Top).resolve(containing_block_width),
+            style.padding(Side::Right).resolve(containing_block_width),
+            style.padding(Side::Bottom).resolve(containing_block_width),
+            style.padding(Side::Left).resolve(containing_block_width),
+        );
+        self.border = EdgeSizes::new(
+            style.border_width(Side::Top),
+            style.border_width(Side::Right),
+            style.border_width(Side::Bottom),
+            style.border_width(Side::Left),
+        );
+
+        let margin_left = style.margin(Side::Left);
+        let margin_right = style.margin(Side::Right);
+        let edges = self.padding.horizontal() + self.border.horizontal();
+
+        let (content_width, used_margin_left, used_margin_right) = match width {
+            LengthPercentageOrAuto::Auto => {
+                let left = margin_left.resolve_or_zero(containing_block_width);
+                let right = margin_right.resolve_or_zero(containing_block_width);
+                (
+                    (containing_block_width - left - right - edges).max(0),
+                    left,
+                    right,
+                )
+            }
+            LengthPercentageOrAuto::LengthPercentage(width) => {
+                let width = width.resolve(containing_block_width);
+                let content_width = match style.box_sizing() {
+                    BoxSizing::ContentBox => width.max(0),
+                    BoxSizing::BorderBox => (width - edges).max(0),
+                };
+                // "If both margin-left and margin-right are auto, their used values are equal.
+                // This horizontally centers the element with respect to the edges of the
+                // containing block."
+                let remaining = (containing_block_width - content_width - edges).max(0);
+                match (margin_left.is_auto(), margin_right.is_auto()) {
+                    (true, true) => (content_width, remaining / 2, remaining - remaining / 2),
+                    (true, false) => {
+                        let right = margin_right.resolve_or_zero(containing_block_width);
+                        (content_width, remaining - right, right)
+                    }
+                    (false, true) => {
+                        let left = margin_left.resolve_or_zero(containing_block_width);
+                        (content_width, left, remaining - left)
+                    }
+                    (false, false) => (
+                        content_width,
+                        margin_left.resolve_or_zero(containing_block_width),
+                        margin_right.resolve_or_zero(containing_block_width),
+                    ),
+                }
+            }
+        };
+
+        self.margin = EdgeSizes::new(
+            style
+                .margin(Side::Top)
+                .resolve_or_zero(containing_block_width),
+            used_margin_right,
+            style
+                .margin(Side::Bottom)
+                .resolve_or_zero(containing_block_width),
+            used_margin_left,
+        );
+
+        content_width
+    }
+
+    /// https://www.w3.org/TR/CSS2/visudet.html#the-height-property
+    /// "If the height of the containing block is not specified explicitly (i.e., it depends on
+    /// content height), and this element is not absolutely positioned, the value computes to
+    /// 'auto'." The root box is sized against the viewport, whose height is always known.
+    fn has_definite_containing_block_height(&self) -> bool {
+        match self.parent.upgrade() {
+            Some(parent) => !parent.borrow().style().height().is_auto(),
+            None => true,
+        }
+    }
+
+    pub fn is_floating(&self) -> bool {
+        self.style.float() != Float::None
+    }
+
+    /// Whether this box is stacked vertically in its block container. Floats and atomic inlines
+    /// are put on lines instead.
+    fn is_block_level(&self) -> bool {
+        matches!(self.kind, LayoutObjectKind::Block | LayoutObjectKind::Grid)
+            && !self.is_floating()
+            && !self.is_atomic_inline()
+    }
+
+    /// https://www.w3.org/TR/css-display-3/#atomic-inline
+    /// Whether this box is put on a line as a single unit. An inline replaced element is one, as
+    /// well as an inline flex or grid container.
+    pub fn is_atomic_inline(&self) -> bool {
+        let display = self.style.display();
+        display.is_atomic_inline() || (self.image.is_some() && display == DisplayType::Inline)
+    }
+
+    /// Whether this box establishes a new block formatting context, in which the floats inside
+    /// it are laid out. The root box always does.
+    fn establishes_block_formatting_context(&self) -> bool {
+        self.parent.upgrade().is_none() || self.style.establishes_block_formatting_context()
+    }
+
+    /// https://www.w3.org/TR/CSS2/visuren.html#block-formatting
+    /// Lays out the children in the content box and returns the height of the content.
+    /// Block-level children are stacked vertically, and each run of consecutive inline-level
+    /// children forms an inline formatting context, as if it were wrapped in an anonymous block
+    /// box. Floats are put on the lines they are in, and `floats` has the floats of the block
+    /// formatting context, where the top-left corner of the content box is at `origin`.
+    fn layout_children(
+        &self,
+        content_size: LayoutSize,
+        font_metrics: &dyn FontMetrics,
+        floats: &mut FloatManager,
+        origin: LayoutPoint,
+    ) -> i64 {
+        let content_width = content_size.width();
+        // The percentage heights of the children refer to the height of this box if it's
+        // specified.
+        let definite_height = !self.style.height().is_auto() || self.forced_size.1.is_some();
+        let mut height = 0;
+        // The bottom margin of the previous block-level child. It collapses with the top margin
+        // of the next block-level child.
+        let mut pending_margin = 0;
+        // https://www.w3.org/TR/css-pseudo-4/#first-formatted-line
+        // Whether no line box or block-level child has been laid out, so that the next inline
+        // run starts the first formatted line. The first line inside a block-level child isn't
+        // styled by ::first-line of this box.
+        let mut before_first_line = true;
+        let mut inline_run = Vec::new();
+        let mut child = self.first_child();
+        loop {
+            // An absolutely positioned child doesn't take space. It's put where the next box
+            // would be, which is its static position.
+            if let Some(c) = child.clone() {
+                if c.borrow().is_out_of_flow() {
+                    c.borrow_mut()
+                        .set_offset(LayoutPoint::new(0, height + pending_margin));
                     child = c.borrow().next_sibling();
+                    continue;
+                }
+            }
+            let is_block = child.as_ref().is_none_or(|c| c.borrow().is_block_level());
+            if is_block && !inline_run.is_empty() {
+                for c in &inline_run {
+                    Self::layout_inline_level(c, content_size, definite_height, font_metrics);
+                }
+                let lines_height = layout_inline_content(
+                    font_metrics,
+                    &self.style,
+                    self.first_line_style.as_ref().filter(|_| before_first_line),
+                    &inline_run,
+                    content_width,
+                    height + pending_margin,
+                    floats,
+                    origin,
+                );
+                // A run that only has collapsible white space has no line boxes, and doesn't
+                // separate the margins around it.
+                if lines_height > 0 {
+                    height += pending_margin + lines_height;
+                    pending_margin = 0;
+                    before_first_line = false;
+                }
+                inline_run.clear();
+            }
+
+            let c = match child {
+                Some(c) => c,
+                None => break,
+            };
+            if is_block {
+                let style = c.borrow().style();
+                let margin_top = style.margin(Side::Top).resolve_or_zero(content_width);
+                let mut y = height + collapse_margins(pending_margin, margin_top);
+                // https://www.w3.org/TR/CSS2/visuren.html#clearance
+                // A box that clears floats is put below them, and the margins above it don't
+                // collapse through the clearance.
+                if let Some(bottom) = floats.clearance(style.clear()) {
+                    y = y.max(bottom - origin.y());
+                }
+                if c.borrow().establishes_block_formatting_context() {
+                    Self::layout_beside_floats(
+                        &c,
+                        content_size,
+                        definite_height,
+                        font_metrics,
+                        floats,
+                        origin,
+                        y,
+                    );
+                } else {
+                    c.borrow_mut().set_offset(LayoutPoint::new(0, y));
+                    c.borrow_mut().layout(
+                        content_size,
+                        definite_height,
+                        font_metrics,
+                        Some((floats, LayoutPoint::new(origin.x(), origin.y() + y))),
+                    );
+                    let margin_left = c.borrow().margin().left;
+                    c.borrow_mut().set_offset(LayoutPoint::new(margin_left, y));
                 }
-                size.set_height(height);
+                height = y + c.borrow().size().height();
+                pending_margin = c.borrow().margin().bottom;
+                before_first_line = false;
+            } else {
+                inline_run.push(c.clone());
+            }
+            child = c.borrow().next_sibling();
+        }
+        height + pending_margin
+    }
+
+    /// https://www.w3.org/TR/CSS2/visuren.html#bfc-next-to-float
+    /// "The border box of [...] an element in the normal flow that establishes a new block
+    /// formatting context [...] must not overlap the margin box of any floats in the same block
+    /// formatting context as the element itself." The box is made narrower to fit next to the
+    /// floats at `y`, and it's moved down below them if it still doesn't fit.
+    fn layout_beside_floats(
+        object: &Rc<RefCell<LayoutObject>>,
+        content_size: LayoutSize,
+        definite_height: bool,
+        font_metrics: &dyn FontMetrics,
+        floats: &FloatManager,
+        origin: LayoutPoint,
+        y: i64,
+    ) {
+        let (left, right) = (origin.x(), origin.x() + content_size.width());
+        let mut top = y;
+        loop {
+            let (l, r) = floats.available_space(origin.y() + top, 0, left, right);
+            let mut o = object.borrow_mut();
+            o.layout(
+                LayoutSize::new(r - l, content_size.height()),
+                definite_height,
+                font_metrics,
+                None,
+            );
+            let outer_width = o.size().width() + o.margin().horizontal();
+            let space = floats.available_space(origin.y() + top, o.size().height(), left, right);
+            let next = floats.next_band(origin.y() + top);
+            if (space == (l, r) && outer_width <= r - l) || next.is_none() {
+                let margin_left = o.margin().left;
+                o.set_offset(LayoutPoint::new(l - origin.x() + margin_left, top));
+                return;
+            }
+            top = next.unwrap_or(origin.y() + top) - origin.y();
+        }
+    }
+
+    /// Lays out an inline-level child and its descendants before they are put on lines. A
+    /// block-level box among them is an atomic inline or a float, which is laid out in a block
+    /// formatting context of its own.
+    fn layout_inline_level(
+        object: &Rc<RefCell<LayoutObject>>,
+        containing_block_size: LayoutSize,
+        definite_height: bool,
+        font_metrics: &dyn FontMetrics,
+    ) {
+        if object.borrow().is_out_of_flow() {
+            return;
+        }
+        object
+            .borrow_mut()
+            .layout(containing_block_size, definite_height, font_metrics, None);
+        if object.borrow().kind() == LayoutObjectKind::Inline {
+            let mut child = object.borrow().first_child();
+            while let Some(c) = child {
+                Self::layout_inline_level(&c, containing_block_size, definite_height, font_metrics);
+                child = c.borrow().next_sibling();
             }
+        }
+    }
+
+    /// Lays out this object and its descendants in a containing block of `parent_size`. The
+    /// containing block must not be borrowed.
+    pub fn compute_size(&mut self, parent_size: LayoutSize, font_metrics: &dyn FontMetrics) {
+        let definite_height = self.has_definite_containing_block_height();
+        self.layout(parent_size, definite_height, font_metrics, None);
+    }
+
+    /// Lays out this object as a flex item whose content box is `width` wide and `height` tall
+    /// when they are given. The other sizes are computed as usual in a containing block of
+    /// `parent_size`.
+    pub fn layout_with_content_size(
+        &mut self,
+        parent_size: LayoutSize,
+        definite_height: bool,
+        font_metrics: &dyn FontMetrics,
+        width: Option<i64>,
+        height: Option<i64>,
+    ) {
+        self.forced_size = (width, height);
+        self.layout(parent_size, definite_height, font_metrics, None);
+    }
+
+    /// The height of the content box if it doesn't depend on the content. Percentages refer to
+    /// the height of the containing block if `definite_height` says it's specified.
+    fn definite_content_height(
+        &self,
+        parent_size: LayoutSize,
+        definite_height: bool,
+    ) -> Option<i64> {
+        if let Some(height) = self.forced_size.1 {
+            return Some(height);
+        }
+        let edges = self.padding.vertical() + self.border.vertical();
+        // The containing block of an absolutely positioned box is laid out before it.
+        let definite_height = definite_height || self.is_out_of_flow();
+        match self.style.height() {
+            LengthPercentageOrAuto::LengthPercentage(h)
+                if !h.has_percentage() || definite_height =>
+            {
+                let h = h.resolve(parent_size.height());
+                Some(match self.style.box_sizing() {
+                    BoxSizing::ContentBox => h.max(0),
+                    BoxSizing::BorderBox => (h - edges).max(0),
+                })
+            }
+            // https://www.w3.org/TR/CSS2/visudet.html#abs-non-replaced-height
+            // An absolutely positioned box with top and bottom fills the space between.
+            LengthPercentageOrAuto::Auto
+                if self.is_out_of_flow()
+                    && !self.style.inset(Side::Top).is_auto()
+                    && !self.style.inset(Side::Bottom).is_auto() =>
+            {
+                let basis = parent_size.height();
+                Some(
+                    (basis
+                        - self.style.inset(Side::Top).resolve_or_zero(basis)
+                        - self.style.inset(Side::Bottom).resolve_or_zero(basis)
+                        - self.margin.vertical()
+                        - edges)
+                        .max(0),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Lays out this object in a containing block of `parent_size`, whose height is specified if
+    /// `definite_height` is true. A block box that doesn't
+    /// establish a block formatting context is in the one of its containing block, which is
+    /// given as `floats` with the position of the top-left corner of the content box of the
+    /// containing block moved down to the top border edge of this box.
+    fn layout(
+        &mut self,
+        parent_size: LayoutSize,
+        definite_height: bool,
+        font_metrics: &dyn FontMetrics,
+        mut floats: Option<(&mut FloatManager, LayoutPoint)>,
+    ) {
+        // The layout of a box that is placed around floats or that places floats depends on the
+        // other boxes in the block formatting context, so it's only reused without floats.
+        let input = LayoutInput {
+            parent_size,
+            definite_height,
+            forced_size: self.forced_size,
+        };
+        let without_floats = |floats: &Option<(&mut FloatManager, LayoutPoint)>| {
+            floats.as_ref().is_none_or(|(floats, _)| floats.is_empty())
+        };
+        if !self.needs_layout && without_floats(&floats) && self.layout_input == Some(input) {
+            return;
+        }
+
+        let mut size = LayoutSize::new(0, 0);
+
+        match self.kind() {
+            LayoutObjectKind::Block if self.image.is_some() => {
+                self.layout_replaced(parent_size, definite_height);
+            }
+            LayoutObjectKind::Block | LayoutObjectKind::Grid => {
+                let content_width = if let Some(width) = self.forced_size.0 {
+                    self.compute_box_edges(parent_size.width(), LengthPercentageOrAuto::Auto);
+                    width
+                } else if self.is_out_of_flow() {
+                    self.compute_out_of_flow_box_edges(parent_size.width(), font_metrics)
+                } else if self.is_floating() || self.is_atomic_inline() {
+                    self.compute_shrink_to_fit_box_edges(parent_size.width(), font_metrics)
+                } else {
+                    self.compute_box_edges(parent_size.width(), self.style.width())
+                };
+                size.set_width(
+                    content_width + self.padding.horizontal() + self.border.horizontal(),
+                );
+                let definite_height = self.definite_content_height(parent_size, definite_height);
+                let content_size = LayoutSize::new(content_width, definite_height.unwrap_or(0));
+
+                let mut own_floats = FloatManager::new();
+                let height = match floats.as_mut() {
+                    Some((floats, position)) if !self.establishes_block_formatting_context() => {
+                        let origin = LayoutPoint::new(
+                            position.x() + self.margin.left + self.border.left + self.padding.left,
+                            position.y() + self.border.top + self.padding.top,
+                        );
+                        self.layout_children(content_size, font_metrics, floats, origin)
+                    }
+                    _ if self.kind == LayoutObjectKind::Grid => grid_layout::layout_grid_items(
+                        self,
+                        content_size,
+                        definite_height,
+                        font_metrics,
+                    ),
+                    _ if self.style.display().is_flex_container() => {
+                        flex_layout::layout_flex_items(
+                            self,
+                            content_size,
+                            definite_height,
+                            font_metrics,
+                        )
+                    }
+                    _ => {
+                        let height = self.layout_children(
+                            content_size,
+                            font_metrics,
+                            &mut own_floats,
+                            LayoutPoint::new(0, 0),
+                        );
+                        height.max(own_floats.bottom().unwrap_or(0))
+                    }
+                };
+                let children_extent = self.children_extent(height);
+
+                let edges = self.padding.vertical() + self.border.vertical();
+                size.set_height(definite_height.unwrap_or(height) + edges);
+                self.size = size;
+                self.update_scrollable_overflow(children_extent);
+                self.layout_marker(font_metrics);
+            }
+            // The size and the position of inline-level boxes and text are decided by the
+            // inline formatting context of the containing block.
             LayoutObjectKind::Inline => {
-                let mut width = 0;
-                let mut height = 0;
-                let mut child = self.first_child();
-                while child.is_some() {
-                    let c = match child {
-                        Some(c) => c,
-                        None => panic!("first child should exist"),
-                    };
-                    width += c.borrow().size().width();
-                    height = height.max(c.borrow().size().height());
-                    child = c.borrow().next_sibling();
+                // The width and height properties don't apply to non-replaced inline boxes.
+                self.compute_box_edges(parent_size.width(), LengthPercentageOrAuto::Auto);
+            }
+            LayoutObjectKind::Text => {}
+        }
+        self.relative_offset = self.compute_relative_offset(parent_size);
+        self.needs_layout = false;
+        self.layout_input = without_floats(&floats).then_some(input);
+    }
+
+    /// https://www.w3.org/TR/CSS2/visudet.html#inline-replaced-width
+    /// Lays out a replaced element. The content box is the size of the image, or the width and
+    /// height properties if they are given. "if 'height' has a computed value of 'auto', and the
+    /// element has an intrinsic ratio then the used value of 'height' is: (used width) / (intrinsic
+    /// ratio)", and likewise for the width.
+    fn layout_replaced(&mut self, parent_size: LayoutSize, definite_height: bool) {
+        let image = match &self.image {
+            Some(image) => image.clone(),
+            None => return,
+        };
+        // A block-level replaced element with a width is centered by auto margins as a
+        // non-replaced one is, and the auto margins of an atomic inline are 0.
+        let margin_width = if self.is_block_level() {
+            self.style.width()
+        } else {
+            LengthPercentageOrAuto::Auto
+        };
+        self.compute_box_edges(parent_size.width(), margin_width);
+        let edges = self.padding.horizontal() + self.border.horizontal();
+        let width = self.forced_size.0.or(match self.style.width() {
+            LengthPercentageOrAuto::LengthPercentage(w) => {
+                let w = w.resolve(parent_size.width());
+                Some(match self.style.box_sizing() {
+                    BoxSizing::ContentBox => w.max(0),
+                    BoxSizing::BorderBox => (w - edges).max(0),
+                })
+            }
+            LengthPercentageOrAuto::Auto => None,
+        });
+        let height = self.definite_content_height(parent_size, definite_height);
+        let content_size = replaced_size(&image, width, height);
+        self.size = LayoutSize::new(
+            content_size.width() + self.padding.horizontal() + self.border.horizontal(),
+            content_size.height() + self.padding.vertical() + self.border.vertical(),
+        );
+        self.update_scrollable_overflow(LayoutSize::new(0, 0));
+    }
+
+    /// Updates the scrollable overflow area with the extent of the children, and keeps the scroll
+    /// offset in it. The content is measured from the padding box, which the content box is inset
+    /// from by the padding.
+    fn update_scrollable_overflow(&mut self, children_extent: LayoutSize) {
+        let client = self.client_size();
+        self.scrollable_overflow = LayoutSize::new(
+            client
+                .width()
+                .max(self.padding.horizontal() + children_extent.width()),
+            client
+                .height()
+                .max(self.padding.vertical() + children_extent.height()),
+        );
+        self.scroll_offset =
+            clamp_scroll_offset(self.scroll_offset, 0, 0, client, self.scrollable_overflow);
+    }
+
+    /// Returns the size of the area from the top-left corner of the content box to the far edges
+    /// of the margin boxes of the children. `height` is the height of the laid out content.
+    fn children_extent(&self, height: i64) -> LayoutSize {
+        let mut extent = LayoutSize::new(0, height);
+        let mut child = self.first_child();
+        while let Some(c) = child {
+            let c_ref = c.borrow();
+            if c_ref.is_out_of_flow() {
+                child = c_ref.next_sibling();
+                continue;
+            }
+            let (margin_right, margin_bottom) = match c_ref.kind() {
+                LayoutObjectKind::Block | LayoutObjectKind::Grid => {
+                    (c_ref.margin().right, c_ref.margin().bottom)
                 }
-                size.set_width(width);
-                size.set_height(height);
+                LayoutObjectKind::Inline | LayoutObjectKind::Text => (0, 0),
+            };
+            let offset = c_ref.offset;
+            extent.set_width(
+                extent
+                    .width()
+                    .max(offset.x() + c_ref.size().width() + margin_right),
+            );
+            extent.set_height(
+                extent
+                    .height()
+                    .max(offset.y() + c_ref.size().height() + margin_bottom),
+            );
+            child = c_ref.next_sibling();
+        }
+        extent
+    }
+
+    /// The color behind this box: the backgrounds of the ancestors composited over the white
+    /// canvas.
+    fn backdrop_color(&self) -> Color {
+        let mut backgrounds = Vec::new();
+        let mut parent = self.parent.upgrade();
+        while let Some(p) = parent {
+            backgrounds.push(p.borrow().style().background_color());
+            parent = p.borrow().parent().upgrade();
+        }
+        backgrounds
+            .iter()
+            .rev()
+            .fold(Color::white(), |backdrop, background| {
+                background.blend_over(&backdrop)
+            })
+    }
+
+    /// https://www.w3.org/TR/css-color-4/#alpha-syntax
+    /// Returns the style with translucent colors blended over what is painted behind them, so that
+    /// the display items only carry opaque colors. `computed` is the style of this object or of
+    /// one of its fragments.
+    fn paint_style(&self, computed: &ComputedStyle) -> ComputedStyle {
+        let mut style = computed.clone();
+        let backdrop = self.backdrop_color();
+        // Text doesn't paint a background of its own.
+        let background = match self.kind {
+            LayoutObjectKind::Text => backdrop,
+            _ => computed.background_color().blend_over(&backdrop),
+        };
+        style.set_color(ColorValue::Color(computed.color().blend_over(&background)));
+        for side in Side::ALL {
+            style.set_border_color(
+                side,
+                ColorValue::Color(computed.border_color(side).blend_over(&background)),
+            );
+        }
+        style.set_background_color(ColorValue::Color(background));
+        style
+    }
+
+    /// Returns the used radii of the corners of a box of `size` with `style`, reduced so that
+    /// adjacent corners don't overlap.
+    fn corner_radii(style: &ComputedStyle, size: LayoutSize) -> CornerRadii {
+        let radius = |corner| {
+            style
+                .border_radius(corner)
+                .resolve(size.width(), size.height())
+        };
+        CornerRadii {
+            top_left: radius(Corner::TopLeft),
+            top_right: radius(Corner::TopRight),
+            bottom_right: radius(Corner::BottomRight),
+            bottom_left: radius(Corner::BottomLeft),
+        }
+        .fit(size)
+    }
+
+    /// Returns the background of a box of `size` at `layout_point`. It's rounded if any corner
+    /// of the box is.
+    fn background_item(
+        style: &ComputedStyle,
+        radii: CornerRadii,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    ) -> DisplayItem {
+        let color = style.background_color();
+        if radii.is_zero() {
+            DisplayItem::Rect {
+                color,
+                layout_point,
+                layout_size,
             }
-            LayoutObjectKind::Text => {
-                if let NodeKind::Text(t) = self.node_kind() {
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
+        } else {
+            DisplayItem::RoundedRect {
+                color,
+                radii,
+                layout_point,
+                layout_size,
+            }
+        }
+    }
+
+    /// https://www.w3.org/TR/CSS2/zindex.html#painting-order
+    /// Paints the background color, the background images and the border of the box. A block
+    /// box paints them over its border box, and an inline box over each of its fragments.
+    pub fn paint_decorations(&self) -> Vec<DisplayItem> {
+        if self.style.display() == DisplayType::DisplayNone {
+            return vec![];
+        }
+
+        let style = self.paint_style(&self.style);
+        let mut v = vec![];
+        match self.kind {
+            LayoutObjectKind::Block | LayoutObjectKind::Grid => {
+                if let NodeKind::Element(_e) = self.node_kind() {
+                    let radii = Self::corner_radii(&self.style, self.size());
+                    if !self.style.background_color().is_transparent() {
+                        v.push(Self::background_item(
+                            &style,
+                            radii,
+                            self.point(),
+                            self.size(),
+                        ));
+                    }
+                    // https://www.w3.org/TR/css-backgrounds-3/#background-layers
+                    // "The first image in the list is the layer closest to the user", so it's
+                    // painted last. The images are tiled from the top-left corner of the padding
+                    // box over the border box.
+                    for image in self.background_images.iter().rev() {
+                        v.push(DisplayItem::BackgroundImage {
+                            image: image.clone(),
+                            origin: self.padding_point(),
+                            layout_point: self.point(),
+                            layout_size: self.size(),
+                        });
+                    }
+                    if self.style.has_border() {
+                        v.push(DisplayItem::Border {
+                            sides: border_sides(&style),
+                            radii,
+                            layout_point: self.point(),
+                            layout_size: self.size(),
+                        });
+                    }
+                }
+            }
+            LayoutObjectKind::Inline => {
+                // https://www.w3.org/TR/css-break-3/#break-decoration
+                // The box is sliced at line breaks, so the left border and the left corners are
+                // only on the first fragment, and the right ones are only on the last one.
+                let last = self.fragments.len().saturating_sub(1);
+                for (i, fragment) in self.fragments.iter().enumerate() {
+                    let layout_point = LayoutPoint::new(
+                        self.point.x() + fragment.point().x(),
+                        self.point.y() + fragment.point().y(),
+                    );
+                    let (computed, style) = match fragment.style() {
+                        Some(computed) => (computed, self.paint_style(computed)),
+                        None => (&self.style, style.clone()),
                     };
-                    let width = CHAR_WIDTH * ratio * t.len() as i64;
-                    if width > CONTENT_AREA_WIDTH {
-                        size.set_width(CONTENT_AREA_WIDTH);
-                        let line_num = if width.wrapping_rem(CONTENT_AREA_WIDTH) == 0 {
-                            width.wrapping_div(CONTENT_AREA_WIDTH)
-                        } else {
-                            width.wrapping_div(CONTENT_AREA_WIDTH) + 1
-                        };
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio * line_num);
-                    } else {
-                        size.set_width(width);
-                        size.set_height(CHAR_HEIGHT);
+                    let mut radii = Self::corner_radii(computed, fragment.size());
+                    if i != 0 {
+                        radii.top_left = (0, 0);
+                        radii.bottom_left = (0, 0);
+                    }
+                    if i != last {
+                        radii.top_right = (0, 0);
+                        radii.bottom_right = (0, 0);
+                    }
+                    if !computed.background_color().is_transparent() {
+                        v.push(Self::background_item(
+                            &style,
+                            radii,
+                            layout_point,
+                            fragment.size(),
+                        ));
+                    }
+                    if computed.has_border() {
+                        let mut sides = border_sides(&style);
+                        if i != 0 {
+                            sides.left.width = 0;
+                        }
+                        if i != last {
+                            sides.right.width = 0;
+                        }
+                        v.push(DisplayItem::Border {
+                            sides,
+                            radii,
+                            layout_point,
+                            layout_size: fragment.size(),
+                        });
                     }
                 }
             }
+            LayoutObjectKind::Text => {}
         }
-        self.size = size;
+        v
     }
 
-    pub fn paint(&mut self) -> Vec<DisplayItem> {
+    /// Paints the content of the box itself: the glyphs of text, or the image of a replaced
+    /// element. The content of the descendants is painted by them.
+    pub fn paint_foreground(&self) -> Vec<DisplayItem> {
         if self.style.display() == DisplayType::DisplayNone {
             return vec![];
         }
 
+        let mut v = vec![];
         match self.kind {
-            LayoutObjectKind::Block => {
-                if let NodeKind::Element(_e) = self.node_kind() {
-                    return vec![DisplayItem::Rect {
-                        style: self.style(),
-                        layout_point: self.point(),
-                        layout_size: self.size(),
-                    }];
+            LayoutObjectKind::Block | LayoutObjectKind::Grid => {
+                // The image of a replaced element is scaled to the content box.
+                if let Some(image) = &self.image {
+                    v.push(DisplayItem::Img {
+                        image: image.clone(),
+                        layout_point: self.content_point(),
+                        layout_size: self.content_size(),
+                    });
                 }
             }
             LayoutObjectKind::Inline => {}
             LayoutObjectKind::Text => {
-                if let NodeKind::Text(t) = self.node_kind() {
-                    let mut v = vec![];
-
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
+                let style = self.paint_style(&self.style);
+                for fragment in &self.fragments {
+                    let style = match fragment.style() {
+                        Some(computed) => self.paint_style(computed),
+                        None => style.clone(),
                     };
-                    let plain_text = t
-                        .replace('\n', " ")
-                        .split(' ')
-                        .filter(|s| !s.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let lines = split_text(plain_text, CHAR_WIDTH * ratio);
-                    for (i, line) in lines.into_iter().enumerate() {
-                        let item = DisplayItem::Text {
-                            text: line,
-                            style: self.style(),
-                            layout_point: LayoutPoint::new(
-                                self.point().x(),
-                                self.point().y() + CHAR_HEIGHT_WITH_PADDING * i as i64,
-                            ),
-                        };
-                        v.push(item);
+                    let font = style.font();
+                    for (point, text) in fragment.text_runs() {
+                        let layout_point = LayoutPoint::new(
+                            self.point.x() + fragment.point().x() + point.x(),
+                            self.point.y() + fragment.point().y() + point.y(),
+                        );
+                        match self.bullet {
+                            // The bullet is a third of the font size, in the middle of the em
+                            // box at the start of the text.
+                            Some(shape) => {
+                                let font_size = self.style.font_size() as i64;
+                                let side = (font_size / 3).max(1);
+                                v.push(DisplayItem::Bullet {
+                                    color: style.color(),
+                                    shape,
+                                    layout_point: LayoutPoint::new(
+                                        layout_point.x() + side / 2,
+                                        layout_point.y() + (font_size - side) / 2,
+                                    ),
+                                    layout_size: LayoutSize::new(side, side),
+                                });
+                            }
+                            None => v.push(DisplayItem::Text {
+                                text: text.clone(),
+                                font: font.clone(),
+                                color: style.color(),
+                                decoration: style.text_decoration(),
+                                layout_point,
+                            }),
+                        }
                     }
-
-                    return v;
                 }
             }
         }
+        v
+    }
+
+    /// https://www.w3.org/TR/css-transforms-1/#transform-rendering
+    /// Returns the transform of the box and its origin, which is the center of the border box as
+    /// the initial value of transform-origin is `50% 50%`. Returns None if the box isn't
+    /// transformed.
+    pub fn transform(&self) -> Option<(Transform, LayoutPoint)> {
+        let functions = self.style.transform();
+        if functions.is_empty() {
+            return None;
+        }
+        let origin = LayoutPoint::new(
+            self.point.x() + self.size.width() / 2,
+            self.point.y() + self.size.height() / 2,
+        );
+        Some((Transform::from_functions(&functions, self.size), origin))
+    }
 
-        vec![]
+    /// Paints the outside marker of a list item. It's painted right after the list item box.
+    pub fn paint_marker(&self) -> Vec<DisplayItem> {
+        match &self.marker {
+            Some(marker) => {
+                let marker = marker.borrow();
+                let mut v = marker.paint_decorations();
+                v.extend(marker.paint_foreground());
+                v
+            }
+            None => vec![],
+        }
     }
+
     pub fn update_kind(&mut self) {
         match self.node_kind() {
             NodeKind::Document => panic!("should not craete a layout object for a Document node"),
             NodeKind::Element(_) => {
                 let display = self.style.display();
                 match display {
-                    DisplayType::Block => self.kind = LayoutObjectKind::Block,
+                    DisplayType::Block
+                    | DisplayType::FlowRoot
+                    | DisplayType::ListItem
+                    | DisplayType::Flex
+                    | DisplayType::InlineFlex => self.kind = LayoutObjectKind::Block,
+                    DisplayType::Grid | DisplayType::InlineGrid => {
+                        self.kind = LayoutObjectKind::Grid
+                    }
                     DisplayType::Inline => self.kind = LayoutObjectKind::Inline,
                     DisplayType::DisplayNone => {
                         panic!("should not createa a layout object for display::none")
@@ -265,65 +1590,37 @@ impl LayoutObject {
                     }
                     false
                 }
-                Selector::UnknownSelector => false,
+                Selector::PseudoElement(_, _) | Selector::UnknownSelector => false,
             },
             _ => false,
         }
     }
 
-    pub fn cascading_style(&mut self, declarations: Vec<Declaration>) {
-        for declaration in declarations {
-            match declaration.property.as_str() {
-                "background-color" => {
-                    if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(value) {
-                            Ok(color) => color,
-                            Err(_) => Color::white(),
-                        };
-                        self.style.set_background_color(color);
-                        continue;
-                    }
-
-                    if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_code(color_code) {
-                            Ok(color) => color,
-                            Err(_) => Color::white(),
-                        };
-                        self.style.set_background_color(color);
-                        continue;
-                    }
-                }
-
-                "color" => {
-                    if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(value) {
-                            Ok(color) => color,
-                            Err(_) => Color::black(),
-                        };
-                        self.style.set_color(color);
-                    }
-
-                    if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_code(color_code) {
-                            Ok(color) => color,
-                            Err(_) => Color::black(),
-                        };
-                        self.style.set_color(color);
-                    }
-                }
-                "display" => {
-                    if let ComponentValue::Ident(value) = &declaration.value {
-                        let display_type = match DisplayType::from_str_display(value) {
-                            Ok(display_type) => display_type,
-                            Err(_) => DisplayType::DisplayNone,
-                        };
-                        self.style.set_display(display_type);
-                    }
-                }
-                _ => {}
+    /// Returns true if `selector` selects the `pseudo_element` of this object.
+    pub fn is_pseudo_element_selected(
+        &self,
+        selector: &Selector,
+        pseudo_element: PseudoElement,
+    ) -> bool {
+        match selector {
+            Selector::PseudoElement(originating, p) => {
+                *p == pseudo_element && self.is_node_selected(originating)
             }
+            _ => false,
         }
     }
+
+    /// https://www.w3.org/TR/css-cascade-4/#cascading
+    /// `declarations` are the declarations of the rules that match this object, in cascade order.
+    pub fn cascading_style(
+        &mut self,
+        declarations: Vec<(CascadeOrigin, Declaration)>,
+        parent_style: Option<ComputedStyle>,
+        device: &Device,
+    ) {
+        self.style = ComputedStyle::compute(&declarations, parent_style.as_ref(), device);
+    }
+
     pub fn set_first_child(&mut self, first_child: Option<Rc<RefCell<LayoutObject>>>) {
         self.first_child = first_child;
     }
@@ -356,12 +1653,208 @@ impl LayoutObject {
         self.size
     }
 
-    pub fn defaulting_style(
-        &mut self,
-        node: &Rc<RefCell<Node>>,
-        parent_style: Option<ComputedStyle>,
-    ) {
-        self.style.defaulting(node, parent_style);
+    pub fn margin(&self) -> EdgeSizes {
+        self.margin
+    }
+
+    pub fn padding(&self) -> EdgeSizes {
+        self.padding
+    }
+
+    pub fn border(&self) -> EdgeSizes {
+        self.border
+    }
+
+    pub fn offset(&self) -> LayoutPoint {
+        self.offset
+    }
+
+    pub fn set_offset(&mut self, offset: LayoutPoint) {
+        self.offset = offset;
+    }
+
+    pub fn set_size(&mut self, size: LayoutSize) {
+        self.size = size;
+    }
+
+    pub fn set_fragments(&mut self, fragments: Vec<Fragment>) {
+        self.fragments = fragments;
+    }
+
+    pub fn fragments(&self) -> &[Fragment] {
+        &self.fragments
+    }
+
+    /// Returns true if `position` is in this object. A block-level box is hit in its border box,
+    /// and an inline-level box or text is hit in one of its fragments, so that the space between
+    /// the fragments on different lines doesn't belong to it.
+    pub fn contains_point(&self, position: (i64, i64)) -> bool {
+        let contains = |point: LayoutPoint, size: LayoutSize| {
+            point.x() <= position.0
+                && position.0 <= point.x() + size.width()
+                && point.y() <= position.1
+                && position.1 <= point.y() + size.height()
+        };
+        match self.kind {
+            LayoutObjectKind::Block | LayoutObjectKind::Grid => contains(self.point, self.size),
+            LayoutObjectKind::Inline | LayoutObjectKind::Text => {
+                self.fragments.iter().any(|fragment| {
+                    contains(
+                        LayoutPoint::new(
+                            self.point.x() + fragment.point().x(),
+                            self.point.y() + fragment.point().y(),
+                        ),
+                        fragment.size(),
+                    )
+                })
+            }
+        }
+    }
+
+    /// The top-left corner of the content box. Children are laid out from this point.
+    pub fn content_point(&self) -> LayoutPoint {
+        LayoutPoint::new(
+            self.point.x() + self.border.left + self.padding.left,
+            self.point.y() + self.border.top + self.padding.top,
+        )
+    }
+
+    /// https://www.w3.org/TR/css-box-3/#padding-box
+    /// The padding box is the scrollport of a scroll container.
+    pub fn padding_point(&self) -> LayoutPoint {
+        LayoutPoint::new(
+            self.point.x() + self.border.left,
+            self.point.y() + self.border.top,
+        )
+    }
+
+    /// The size of the padding box.
+    /// https://www.w3.org/TR/cssom-view-1/#dom-element-clientwidth
+    pub fn client_size(&self) -> LayoutSize {
+        LayoutSize::new(
+            (self.size.width() - self.border.horizontal()).max(0),
+            (self.size.height() - self.border.vertical()).max(0),
+        )
+    }
+
+    pub fn scrollable_overflow(&self) -> LayoutSize {
+        self.scrollable_overflow
+    }
+
+    pub fn scroll_offset(&self) -> LayoutPoint {
+        self.scroll_offset
+    }
+
+    /// The top-left corner that children are laid out from, moved by the scroll offset.
+    pub fn scrolled_content_point(&self) -> LayoutPoint {
+        let point = self.content_point();
+        LayoutPoint::new(
+            point.x() - self.scroll_offset.x(),
+            point.y() - self.scroll_offset.y(),
+        )
+    }
+
+    /// Returns the rectangle that the content is clipped to, if the box clips it.
+    /// https://www.w3.org/TR/css-overflow-3/#overflow-control
+    pub fn clip_rect(&self) -> Option<(LayoutPoint, LayoutSize)> {
+        if !matches!(self.kind, LayoutObjectKind::Block | LayoutObjectKind::Grid)
+            || self.style.display() == DisplayType::DisplayNone
+            || !self.style.clips_content()
+        {
+            return None;
+        }
+        Some((self.padding_point(), self.client_size()))
+    }
+
+    /// Returns true if `position` is in the padding box, or if this box doesn't clip its content.
+    /// The content outside of the clip can't be hit.
+    pub fn clip_contains(&self, position: (i64, i64)) -> bool {
+        match self.clip_rect() {
+            Some((point, size)) => {
+                point.x() <= position.0
+                    && position.0 <= point.x() + size.width()
+                    && point.y() <= position.1
+                    && position.1 <= point.y() + size.height()
+            }
+            None => true,
+        }
+    }
+
+    /// Scrolls the content by (`dx`, `dy`) along the axes that the user can scroll, and returns
+    /// true if the scroll offset is changed. The positions of the descendants should be computed
+    /// again after that.
+    pub fn scroll_by(&mut self, dx: i64, dy: i64) -> bool {
+        if !matches!(self.kind, LayoutObjectKind::Block | LayoutObjectKind::Grid) {
+            return false;
+        }
+        let dx = if self.style.overflow_x().is_user_scrollable() {
+            dx
+        } else {
+            0
+        };
+        let dy = if self.style.overflow_y().is_user_scrollable() {
+            dy
+        } else {
+            0
+        };
+        let offset = clamp_scroll_offset(
+            self.scroll_offset,
+            dx,
+            dy,
+            self.client_size(),
+            self.scrollable_overflow,
+        );
+        let changed = offset != self.scroll_offset;
+        self.scroll_offset = offset;
+        changed
+    }
+
+    /// https://www.w3.org/TR/css-overflow-3/#valdef-overflow-scroll
+    /// Returns the scrollbars of a scroll container. "scroll" always shows a scrollbar, and "auto"
+    /// shows one only when the content overflows.
+    pub fn paint_scrollbars(&self) -> Vec<DisplayItem> {
+        if self.clip_rect().is_none() {
+            return Vec::new();
+        }
+        let client = self.client_size();
+        let extent = self.scrollable_overflow;
+        let mut v = Vec::new();
+        for (axis, overflow, overflows) in [
+            (
+                Axis::Vertical,
+                self.style.overflow_y(),
+                extent.height() > client.height(),
+            ),
+            (
+                Axis::Horizontal,
+                self.style.overflow_x(),
+                extent.width() > client.width(),
+            ),
+        ] {
+            let visible = match overflow {
+                Overflow::Scroll => true,
+                Overflow::Auto => overflows,
+                _ => false,
+            };
+            if visible {
+                v.push(scrollbar(
+                    axis,
+                    self.padding_point(),
+                    client,
+                    extent,
+                    self.scroll_offset,
+                ));
+            }
+        }
+        v
+    }
+
+    /// https://www.w3.org/TR/css-box-3/#content-box
+    pub fn content_size(&self) -> LayoutSize {
+        LayoutSize::new(
+            (self.size.width() - self.border.horizontal() - self.padding.horizontal()).max(0),
+            (self.size.height() - self.border.vertical() - self.padding.vertical()).max(0),
+        )
     }
 
     pub fn new(node: Rc<RefCell<Node>>, parent_obj: &Option<Rc<RefCell<LayoutObject>>>) -> Self {
@@ -379,6 +1872,24 @@ impl LayoutObject {
             style: ComputedStyle::new(),
             point: LayoutPoint::new(0, 0),
             size: LayoutSize::new(0, 0),
+            margin: EdgeSizes::default(),
+            padding: EdgeSizes::default(),
+            border: EdgeSizes::default(),
+            offset: LayoutPoint::new(0, 0),
+            fragments: Vec::new(),
+            scrollable_overflow: LayoutSize::new(0, 0),
+            scroll_offset: LayoutPoint::new(0, 0),
+            relative_offset: LayoutPoint::new(0, 0),
+            forced_size: (None, None),
+            marker: None,
+            bullet: None,
+            first_line_style: None,
+            image: None,
+            background_images: Vec::new(),
+            needs_layout: true,
+            layout_input: None,
+            needs_paint: false,
+            painted_rect: None,
         }
     }
 }
@@ -392,10 +1903,42 @@ impl PartialEq for LayoutObject {
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LayoutObjectKind {
     Block,
+    /// https://www.w3.org/TR/css-grid-1/#grid-containers
+    /// A grid container, which lays out its children in a grid formatting context.
+    Grid,
     Inline,
     Text,
 }
 
+/// https://www.w3.org/TR/css-box-3/#box-model
+/// The used sizes of margins, borders or paddings on the four sides of a box.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct EdgeSizes {
+    pub top: i64,
+    pub right: i64,
+    pub bottom: i64,
+    pub left: i64,
+}
+
+impl EdgeSizes {
+    pub fn new(top: i64, right: i64, bottom: i64, left: i64) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    pub fn horizontal(&self) -> i64 {
+        self.left + self.right
+    }
+
+    pub fn vertical(&self) -> i64 {
+        self.top + self.bottom
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct LayoutPoint {
     pub x: i64,
@@ -420,6 +1963,72 @@ impl LayoutPoint {
     }
 }
 
+/// https://www.w3.org/TR/css-break-3/#box-fragment
+/// The part of an inline-level box or text that is on one line box. The point is relative to the
+/// point of the layout object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+    point: LayoutPoint,
+    size: LayoutSize,
+    /// Runs of text with the top-left corners of their glyphs relative to the fragment. A line is
+    /// split into several runs when letter-spacing, word-spacing or justification moves glyphs
+    /// apart. It's empty for inline boxes.
+    text_runs: Vec<(LayoutPoint, String)>,
+    /// https://www.w3.org/TR/css-pseudo-4/#first-line-styling
+    /// The style of the fragment on the first line of a block container with ::first-line rules,
+    /// which is used instead of the style of the layout object.
+    style: Option<ComputedStyle>,
+}
+
+impl Fragment {
+    pub fn new(
+        point: LayoutPoint,
+        size: LayoutSize,
+        text_runs: Vec<(LayoutPoint, String)>,
+    ) -> Self {
+        Self {
+            point,
+            size,
+            text_runs,
+            style: None,
+        }
+    }
+
+    /// Returns the fragment with its own `style`.
+    pub fn with_style(self, style: ComputedStyle) -> Self {
+        Self {
+            style: Some(style),
+            ..self
+        }
+    }
+
+    pub fn style(&self) -> Option<&ComputedStyle> {
+        self.style.as_ref()
+    }
+
+    pub fn point(&self) -> LayoutPoint {
+        self.point
+    }
+
+    pub fn size(&self) -> LayoutSize {
+        self.size
+    }
+
+    pub fn text_runs(&self) -> &[(LayoutPoint, String)] {
+        &self.text_runs
+    }
+
+    /// Returns the fragment moved by (`dx`, `dy`).
+    pub fn translated(&self, dx: i64, dy: i64) -> Self {
+        Self {
+            point: LayoutPoint::new(self.point.x() + dx, self.point.y() + dy),
+            size: self.size,
+            text_runs: self.text_runs.clone(),
+            style: self.style.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct LayoutSize {
     pub width: i64,
@@ -444,27 +2053,30 @@ impl LayoutSize {
     }
 }
 
+/// Returns the used widths, styles and colors of the border of `style`.
+fn border_sides(style: &ComputedStyle) -> BorderSides {
+    let side = |side| BorderSide {
+        width: style.border_width(side),
+        style: style.border_style(side),
+        color: style.border_color(side),
+    };
+    BorderSides {
+        top: side(Side::Top),
+        right: side(Side::Right),
+        bottom: side(Side::Bottom),
+        left: side(Side::Left),
+    }
+}
+
 pub fn create_layout_object(
     node: &Option<Rc<RefCell<Node>>>,
     parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
-    cssom: &StyleSheet,
+    style_sheets: &[StyleSheet],
+    device: &Device,
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     if let Some(n) = node {
         let layout_object = Rc::new(RefCell::new(LayoutObject::new(n.clone(), parent_obj)));
-
-        // Apply CSS rules to LayoutObject.
-        for rule in &cssom.rules {
-            if layout_object.borrow().is_node_selected(&rule.selector) {
-                layout_object
-                    .borrow_mut()
-                    .cascading_style(rule.declarations.clone());
-            }
-        }
-
-        // Defaulting a parent CSS style.
-        let parent_style = parent_obj.as_ref().map(|parent| parent.borrow().style());
-
-        layout_object.borrow_mut().defaulting_style(n, parent_style);
+        let declarations = cascade(&layout_object, parent_obj, style_sheets, device);
 
         if layout_object.borrow().style().display() == DisplayType::DisplayNone {
             return None;
@@ -472,7 +2084,445 @@ pub fn create_layout_object(
 
         // Set a correct LayoutObjectKind.
         layout_object.borrow_mut().update_kind();
+        let parent_first_line_style = parent_obj
+            .as_ref()
+            .and_then(|parent| parent.borrow().first_line_style());
+        layout_object.borrow_mut().cascade_first_line_style(
+            &declarations,
+            parent_first_line_style.as_ref(),
+            style_sheets,
+            device,
+        );
         return Some(layout_object);
     }
     None
 }
+
+/// Computes the style of `object` from the rules that match its node and the style of
+/// `parent_obj`, and returns the declarations of the rules in cascade order.
+fn cascade(
+    object: &Rc<RefCell<LayoutObject>>,
+    parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
+    style_sheets: &[StyleSheet],
+    device: &Device,
+) -> Vec<(CascadeOrigin, Declaration)> {
+    let mut declarations = matching_declarations(style_sheets, device, |selector| {
+        object.borrow().is_node_selected(selector)
+    });
+    // https://html.spec.whatwg.org/multipage/rendering.html#presentational-hints
+    // "the user agent must then treat the presentational hints as if they were ... at the
+    // start of the author style sheet".
+    let hints_index = declarations
+        .iter()
+        .position(|(origin, _)| *origin == CascadeOrigin::Author)
+        .unwrap_or(declarations.len());
+    let node = object.borrow().node();
+    for (i, hint) in presentational_hints(&node).into_iter().enumerate() {
+        declarations.insert(hints_index + i, (CascadeOrigin::Author, hint));
+    }
+
+    let parent_style = parent_obj.as_ref().map(|parent| parent.borrow().style());
+    object
+        .borrow_mut()
+        .cascading_style(declarations.clone(), parent_style, device);
+    declarations
+}
+
+/// Computes the style and the ::first-line style of `object` again, after its node or the style
+/// of its parent is changed, and returns how much of its rendering is out of date. The boxes
+/// have to be built again if the style decides other boxes, and then the style isn't updated
+/// further.
+pub fn restyle(
+    object: &Rc<RefCell<LayoutObject>>,
+    style_sheets: &[StyleSheet],
+    device: &Device,
+) -> StyleDifference {
+    let parent_obj = object.borrow().parent().upgrade();
+    let old_style = object.borrow().style();
+    let old_first_line_style = object.borrow().first_line_style();
+    let declarations = cascade(object, &parent_obj, style_sheets, device);
+    let difference = old_style.difference(&object.borrow().style);
+    if difference == StyleDifference::Rebuild {
+        return difference;
+    }
+
+    let parent_first_line_style = parent_obj
+        .as_ref()
+        .and_then(|parent| parent.borrow().first_line_style());
+    let mut o = object.borrow_mut();
+    o.first_line_style = None;
+    o.cascade_first_line_style(
+        &declarations,
+        parent_first_line_style.as_ref(),
+        style_sheets,
+        device,
+    );
+    if o.first_line_style != old_first_line_style {
+        return difference.max(StyleDifference::Layout);
+    }
+    difference
+}
+
+/// https://www.w3.org/TR/CSS2/visudet.html#inline-replaced-height
+/// Returns the size of the content box of a replaced element of `image`, whose width and height
+/// are given if they aren't auto. An auto size keeps the intrinsic ratio of the image, and both
+/// are the intrinsic size of the image if they are auto.
+fn replaced_size(image: &Image, width: Option<i64>, height: Option<i64>) -> LayoutSize {
+    let (intrinsic_width, intrinsic_height) = (image.width().max(1), image.height().max(1));
+    match (width, height) {
+        (Some(w), Some(h)) => LayoutSize::new(w, h),
+        (Some(w), None) => LayoutSize::new(w, w * intrinsic_height / intrinsic_width),
+        (None, Some(h)) => LayoutSize::new(h * intrinsic_width / intrinsic_height, h),
+        (None, None) => LayoutSize::new(image.width(), image.height()),
+    }
+}
+
+/// Collects the declarations of the rules whose selectors `matches`. Style sheets are given in
+/// cascade order, so later declarations win.
+fn matching_declarations(
+    style_sheets: &[StyleSheet],
+    device: &Device,
+    matches: impl Fn(&Selector) -> bool,
+) -> Vec<(CascadeOrigin, Declaration)> {
+    let mut declarations = Vec::new();
+    for style_sheet in style_sheets {
+        for rule in style_sheet.style_rules(device) {
+            if matches(&rule.selector) {
+                for declaration in &rule.declarations {
+                    declarations.push((style_sheet.origin, declaration.clone()));
+                }
+            }
+        }
+    }
+    declarations
+}
+
+/// https://www.w3.org/TR/css-lists-3/#marker-pseudo
+/// Creates the ::marker of a list item. It's a text box styled by the ::marker rules, which
+/// inherits from the list item. The text is the content property of the ::marker if it's given,
+/// or else the list-style-type with the value of the list-item counter in `counters`. Returns
+/// None if the list item has no marker.
+pub fn create_marker(
+    list_item: &Rc<RefCell<LayoutObject>>,
+    style_sheets: &[StyleSheet],
+    device: &Device,
+    counters: &mut CounterScopes,
+) -> Option<Rc<RefCell<LayoutObject>>> {
+    let parent_style = list_item.borrow().style();
+    if parent_style.display() != DisplayType::ListItem {
+        return None;
+    }
+
+    let declarations = matching_declarations(style_sheets, device, |selector| {
+        list_item
+            .borrow()
+            .is_pseudo_element_selected(selector, PseudoElement::Marker)
+    });
+    let style = ComputedStyle::compute(&declarations, Some(&parent_style), device);
+    let (text, bullet) = match style.content() {
+        Content::Normal => {
+            let list_style_type = style.list_style_type();
+            let bullet = match list_style_type {
+                ListStyleType::CounterStyle(CounterStyle::Disc) => Some(BulletShape::Disc),
+                ListStyleType::CounterStyle(CounterStyle::Circle) => Some(BulletShape::Circle),
+                ListStyleType::CounterStyle(CounterStyle::Square) => Some(BulletShape::Square),
+                _ => None,
+            };
+            (list_style_type.marker(counters.value("list-item")), bullet)
+        }
+        Content::None => (None, None),
+        Content::Items(items) => {
+            let element = match list_item.borrow().node_kind() {
+                NodeKind::Element(e) => Some(e),
+                _ => None,
+            };
+            let text = counters.resolve(&items, &style.quotes(), element.as_ref());
+            (Some(text), None)
+        }
+    };
+    let text = text.filter(|text| !text.is_empty())?;
+
+    let node = Rc::new(RefCell::new(Node::new(NodeKind::Text(text))));
+    let mut marker = LayoutObject::new(node, &Some(list_item.clone()));
+    marker.style = style;
+    marker.bullet = bullet;
+    marker.update_kind();
+    Some(Rc::new(RefCell::new(marker)))
+}
+
+/// Computes the style of the outside ::marker of `list_item` again, after the style of the list
+/// item is computed again. Returns how the style of the marker changed. The style is kept if the
+/// marker has to be built again.
+pub fn restyle_marker(
+    list_item: &Rc<RefCell<LayoutObject>>,
+    style_sheets: &[StyleSheet],
+    device: &Device,
+) -> StyleDifference {
+    let marker = match list_item.borrow().marker() {
+        Some(marker) => marker,
+        None => return StyleDifference::Equal,
+    };
+    let declarations = matching_declarations(style_sheets, device, |selector| {
+        list_item
+            .borrow()
+            .is_pseudo_element_selected(selector, PseudoElement::Marker)
+    });
+    let style = ComputedStyle::compute(&declarations, Some(&list_item.borrow().style()), device);
+    let difference = marker.borrow().style().difference(&style);
+    if difference != StyleDifference::Rebuild {
+        marker.borrow_mut().style = style;
+    }
+    difference
+}
+
+/// https://www.w3.org/TR/css-pseudo-4/#generated-content
+/// Creates the ::before or ::after of `element`. It's a box of the display of the pseudo-element,
+/// which inherits from `element`, with a text box of its content property inside. The counters
+/// are updated with the style of the pseudo-element. Returns None if the content is normal or
+/// none, which generate no box.
+pub fn create_generated_content(
+    element: &Rc<RefCell<LayoutObject>>,
+    pseudo_element: PseudoElement,
+    style_sheets: &[StyleSheet],
+    device: &Device,
+    counters: &mut CounterScopes,
+) -> Option<Rc<RefCell<LayoutObject>>> {
+    let e = match element.borrow().node_kind() {
+        NodeKind::Element(e) => e,
+        _ => return None,
+    };
+    let declarations = matching_declarations(style_sheets, device, |selector| {
+        element
+            .borrow()
+            .is_pseudo_element_selected(selector, pseudo_element)
+    });
+    // "On elements, [normal] always computes to normal. On ::before and ::after, normal
+    // computes to none."
+    if declarations.is_empty() {
+        return None;
+    }
+    let parent_style = element.borrow().style();
+    let style = ComputedStyle::compute(&declarations, Some(&parent_style), device);
+    let items = match style.content() {
+        Content::Items(items) if style.display() != DisplayType::DisplayNone => items,
+        _ => return None,
+    };
+    counters.update(&style);
+    let text = counters.resolve(&items, &style.quotes(), Some(&e));
+
+    // The box is over a copy of the element without its children, so that it's hit and handled
+    // as a part of the element.
+    let node = Rc::new(RefCell::new(Node::new(NodeKind::Element(e))));
+    let pseudo = Rc::new(RefCell::new(LayoutObject::new(
+        node,
+        &Some(element.clone()),
+    )));
+    pseudo.borrow_mut().style = style;
+    pseudo.borrow_mut().update_kind();
+    let parent_first_line_style = element.borrow().first_line_style();
+    pseudo.borrow_mut().inherit_first_line_style(
+        &declarations,
+        parent_first_line_style.as_ref(),
+        device,
+    );
+    if !text.is_empty() {
+        let text = create_anonymous_text(&pseudo, text, device);
+        pseudo.borrow_mut().first_child = Some(text);
+    }
+    Some(pseudo)
+}
+
+/// https://html.spec.whatwg.org/multipage/rendering.html#images-3
+/// Sets the images of `object` that are loaded in `images`. An <img> whose image is loaded is a
+/// replaced element, which is laid out as a block box that is put on a line if it's inline-level.
+/// "When an img element represents some text and the user agent does not expect this to change,
+/// the user agent is expected to treat the element as a non-replaced phrasing element whose
+/// content is the text", which is the alt attribute of an image that isn't loaded.
+pub fn attach_images(object: &Rc<RefCell<LayoutObject>>, images: &ImageStore, device: &Device) {
+    let background_images = object
+        .borrow()
+        .style
+        .background_image()
+        .iter()
+        .filter_map(|layer| layer.url().and_then(|url| images.get(url)))
+        .collect();
+    object.borrow_mut().background_images = background_images;
+
+    let element = match object.borrow().node_kind() {
+        NodeKind::Element(e) if e.kind() == ElementKind::Img => e,
+        _ => return,
+    };
+    match element
+        .get_attribute("src")
+        .and_then(|src| images.get(&src))
+    {
+        Some(image) => {
+            let mut o = object.borrow_mut();
+            o.image = Some(image);
+            o.kind = LayoutObjectKind::Block;
+        }
+        None => {
+            let alt = element.get_attribute("alt").unwrap_or_default();
+            if !alt.is_empty() {
+                let text = create_anonymous_text(object, alt, device);
+                object.borrow_mut().first_child = Some(text);
+            }
+        }
+    }
+}
+
+/// Creates a text box of `text` in `parent`, which isn't in the DOM tree.
+fn create_anonymous_text(
+    parent: &Rc<RefCell<LayoutObject>>,
+    text: String,
+    device: &Device,
+) -> Rc<RefCell<LayoutObject>> {
+    let node = Rc::new(RefCell::new(Node::new(NodeKind::Text(text))));
+    let mut object = LayoutObject::new(node, &Some(parent.clone()));
+    let p = parent.borrow();
+    object.style = ComputedStyle::compute(&[], Some(&p.style), device);
+    object.update_kind();
+    object.inherit_first_line_style(&[], p.first_line_style.as_ref(), device);
+    Rc::new(RefCell::new(object))
+}
+
+/// https://www.w3.org/TR/css-pseudo-4/#first-letter-pseudo
+/// Wraps the first letter of `block` in an inline box styled by the ::first-letter rules. "The
+/// ::first-letter pseudo-element represents the first typographic letter unit on the first
+/// formatted line of its originating element", together with the punctuation around it. The
+/// letter is looked for in the first text, which may be inside inline boxes, a ::before or the
+/// first block-level child. The text box that has it is split, and the new box inherits from
+/// the parent of the text.
+pub fn create_first_letter(
+    block: &Rc<RefCell<LayoutObject>>,
+    style_sheets: &[StyleSheet],
+    device: &Device,
+) {
+    if !block.borrow().is_block_container() {
+        return;
+    }
+    let declarations = matching_declarations(style_sheets, device, |selector| {
+        block
+            .borrow()
+            .is_pseudo_element_selected(selector, PseudoElement::FirstLetter)
+    });
+    if declarations.is_empty() {
+        return;
+    }
+    let text_object = match first_text(block) {
+        Some(text_object) => text_object,
+        None => return,
+    };
+    let text = match text_object.borrow().node_kind() {
+        NodeKind::Text(text) => text,
+        _ => return,
+    };
+    let (before, letter, after) = match split_first_letter(&text) {
+        Some(split) => split,
+        None => return,
+    };
+    let parent = match text_object.borrow().parent().upgrade() {
+        Some(parent) => parent,
+        None => return,
+    };
+
+    let node = Rc::new(RefCell::new(Node::new(block.borrow().node_kind())));
+    let first_letter = Rc::new(RefCell::new(LayoutObject::new(node, &Some(parent.clone()))));
+    {
+        let p = parent.borrow();
+        let mut f = first_letter.borrow_mut();
+        f.style = ComputedStyle::compute(&declarations, Some(&p.style), device);
+        f.update_kind();
+        f.inherit_first_line_style(&declarations, p.first_line_style.as_ref(), device);
+    }
+    let letter = create_anonymous_text(&first_letter, letter.to_string(), device);
+    first_letter.borrow_mut().first_child = Some(letter);
+
+    // The white space before the letter and the rest of the text stay in text boxes of the same
+    // style.
+    let split_text = |text: &str| {
+        let node = Rc::new(RefCell::new(Node::new(NodeKind::Text(text.to_string()))));
+        let mut object = LayoutObject::new(node, &Some(parent.clone()));
+        object.style = text_object.borrow().style();
+        object.first_line_style = text_object.borrow().first_line_style();
+        object.update_kind();
+        Rc::new(RefCell::new(object))
+    };
+    let mut replacement = Vec::new();
+    if !before.is_empty() {
+        replacement.push(split_text(before));
+    }
+    replacement.push(first_letter);
+    if !after.is_empty() {
+        replacement.push(split_text(after));
+    }
+    parent.borrow_mut().replace_child(&text_object, replacement);
+}
+
+/// Returns the first text in flow in `object` that isn't only white space. The search ends at an
+/// atomic inline, and goes into the first block-level child if it's a block container.
+fn first_text(object: &Rc<RefCell<LayoutObject>>) -> Option<Rc<RefCell<LayoutObject>>> {
+    let mut child = object.borrow().first_child();
+    while let Some(c) = child {
+        let c_ref = c.borrow();
+        if !c_ref.is_out_of_flow() && !c_ref.is_floating() {
+            match c_ref.kind() {
+                LayoutObjectKind::Text => match c_ref.node_kind() {
+                    NodeKind::Text(text) if !text.trim().is_empty() => return Some(c.clone()),
+                    _ => {}
+                },
+                LayoutObjectKind::Inline => {
+                    if let Some(text) = first_text(&c) {
+                        return Some(text);
+                    }
+                }
+                _ if c_ref.is_block_container() && c_ref.is_block_level() => {
+                    return first_text(&c);
+                }
+                _ => return None,
+            }
+        }
+        child = c_ref.next_sibling();
+    }
+    None
+}
+
+/// Splits `text` into the white space before the first letter, the first letter with the
+/// punctuation around it, and the rest. Returns None if the text has no letter.
+fn split_first_letter(text: &str) -> Option<(&str, &str, &str)> {
+    let start = text.len() - text.trim_start().len();
+    let mut chars = text[start..].char_indices();
+    let letter_end = loop {
+        match chars.next() {
+            Some((_, c)) if is_punctuation(c) => continue,
+            Some((_, c)) if c.is_whitespace() => return None,
+            Some((i, c)) => break start + i + c.len_utf8(),
+            None => return None,
+        }
+    };
+    let end = text[letter_end..]
+        .char_indices()
+        .find(|(_, c)| !is_punctuation(*c))
+        .map_or(text.len(), |(i, _)| letter_end + i);
+    Some((&text[..start], &text[start..end], &text[end..]))
+}
+
+/// https://www.w3.org/TR/css-pseudo-4/#first-letter-pattern
+/// Whether `c` is punctuation that goes with the first letter. The Unicode punctuation classes
+/// are approximated by ASCII punctuation and common quotation marks.
+fn is_punctuation(c: char) -> bool {
+    (c.is_ascii_punctuation() && !matches!(c, '$' | '+' | '<' | '=' | '>' | '^' | '`' | '|' | '~'))
+        || matches!(
+            c,
+            '\u{2018}'
+                | '\u{2019}'
+                | '\u{201C}'
+                | '\u{201D}'
+                | '\u{00AB}'
+                | '\u{00BB}'
+                | '\u{00A1}'
+                | '\u{00BF}'
+                | '\u{300C}'
+                | '\u{300D}'
+        )
+}
@@ -1,116 +1,832 @@
-use crate::constants::CONTENT_AREA_WIDTH;
 use crate::display_item::DisplayItem;
+use crate::renderer::css::cssom::PseudoElement;
 use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::media::Device;
+use crate::renderer::css::user_agent::user_agent_style_sheet;
+use crate::renderer::css::value::LengthPercentageOrAuto;
 use crate::renderer::dom::api::get_target_element_node;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeKind;
+use crate::renderer::image::ImageStore;
+use crate::renderer::layout::computed_style::Position;
+use crate::renderer::layout::computed_style::Side;
+use crate::renderer::layout::computed_style::StyleDifference;
+use crate::renderer::layout::counter::CounterScopes;
+use crate::renderer::layout::font_metrics::FontMetrics;
+use crate::renderer::layout::layout_object::attach_images;
+use crate::renderer::layout::layout_object::create_first_letter;
+use crate::renderer::layout::layout_object::create_generated_content;
 use crate::renderer::layout::layout_object::create_layout_object;
+use crate::renderer::layout::layout_object::create_marker;
+use crate::renderer::layout::layout_object::restyle;
+use crate::renderer::layout::layout_object::restyle_marker;
 use crate::renderer::layout::layout_object::LayoutObject;
 use crate::renderer::layout::layout_object::LayoutObjectKind;
 use crate::renderer::layout::layout_object::LayoutPoint;
 use crate::renderer::layout::layout_object::LayoutSize;
+use crate::renderer::layout::transform::Transform;
+use alloc::collections::BTreeMap;
 use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+/// https://www.w3.org/TR/CSS2/zindex.html#painting-order
+/// The passes over the descendants in flow of a box, which paint their parts in the order of the
+/// steps of the painting algorithm.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PaintPhase {
+    /// Step 4: the backgrounds and borders of the block-level descendants.
+    BlockBackgrounds,
+    /// Step 5: the floats, each painted atomically.
+    Floats,
+    /// Step 7: the replaced content of block-level boxes, and the inline boxes, text and atomic
+    /// inlines on the lines.
+    Inline,
+}
+
 #[derive(Debug, Clone)]
 pub struct LayoutView {
+    /// The document whose boxes are laid out. Its changes are found by the dirty flags of the
+    /// nodes.
+    document: Rc<RefCell<Node>>,
+    /// The user-agent and author style sheets in cascade order.
+    style_sheets: Vec<StyleSheet>,
     root: Option<Rc<RefCell<LayoutObject>>>,
+    /// The viewport that the page is laid out in. Media queries are evaluated against it.
+    viewport: Device,
+    /// Measures text in the fonts that paint it.
+    font_metrics: Rc<dyn FontMetrics>,
+    /// The scroll position of the viewport. Fixed boxes are placed in the viewport.
+    scroll_position: LayoutPoint,
 }
 
 impl LayoutView {
-    pub fn new(root: Rc<RefCell<Node>>, cssom: &StyleSheet) -> Self {
-        let body_root = get_target_element_node(Some(root), ElementKind::Body);
+    pub fn new(
+        root: Rc<RefCell<Node>>,
+        cssom: &StyleSheet,
+        viewport: Device,
+        font_metrics: Rc<dyn FontMetrics>,
+        images: &ImageStore,
+    ) -> Self {
+        // The user-agent style sheet comes first so that author rules override it.
+        // https://www.w3.org/TR/css-cascade-4/#cascade-origin
+        let style_sheets = vec![user_agent_style_sheet(), cssom.clone()];
 
         let mut tree = Self {
-            root: build_layout_tree(&body_root, &None, cssom),
+            document: root,
+            style_sheets,
+            root: None,
+            viewport,
+            font_metrics,
+            scroll_position: LayoutPoint::new(0, 0),
         };
-        tree.update_layout();
+        tree.build(images);
         tree
     }
 
-    fn paint_node(node: &Option<Rc<RefCell<LayoutObject>>>, display_items: &mut Vec<DisplayItem>) {
-        // match node {
-        if let Some(n) = node {
-            display_items.extend(n.borrow_mut().paint());
-            let first_child = n.borrow().first_child();
-            Self::paint_node(&first_child, display_items);
-            let next_sibling = n.borrow().next_sibling();
-            Self::paint_node(&next_sibling, display_items);
+    /// Builds the boxes of the body of the document, and lays them out.
+    fn build(&mut self, images: &ImageStore) {
+        let body_root = get_target_element_node(Some(self.document.clone()), ElementKind::Body);
+        self.root = build_layout_tree(
+            &body_root,
+            &None,
+            &self.style_sheets,
+            &self.viewport,
+            images,
+            &mut CounterScopes::new(),
+        );
+        self.update_layout();
+        Self::collect_damage(&self.root, Transform::identity(), &mut Vec::new());
+    }
+
+    /// https://html.spec.whatwg.org/multipage/webappapis.html#update-the-rendering
+    /// Updates the boxes for the changes of the DOM tree since the last update, which are marked
+    /// by the dirty flags of the nodes. Only the boxes of the changed nodes and their descendants
+    /// are styled again, and only the changed boxes and their ancestors are laid out again. All
+    /// boxes are built again if the changes decide other boxes. Returns the rectangles of the
+    /// document that have to be painted again.
+    pub fn update(&mut self, images: &ImageStore) -> Vec<(LayoutPoint, LayoutSize)> {
+        let mut objects = BTreeMap::new();
+        Self::collect_objects(&self.root, &mut objects);
+        let mut rebuild = false;
+        let document = self.document.clone();
+        self.invalidate(&document, false, &objects, &mut rebuild);
+
+        if rebuild {
+            let old_size = self.document_size();
+            self.build(images);
+            let new_size = self.document_size();
+            return vec![(
+                LayoutPoint::new(0, 0),
+                LayoutSize::new(
+                    old_size.width().max(new_size.width()),
+                    old_size.height().max(new_size.height()),
+                ),
+            )];
+        }
+        self.update_layout();
+        let mut damage = Vec::new();
+        Self::collect_damage(&self.root, Transform::identity(), &mut damage);
+        damage
+    }
+
+    /// Maps the nodes in the DOM tree to their boxes by the addresses of the nodes.
+    fn collect_objects(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        objects: &mut BTreeMap<usize, Rc<RefCell<LayoutObject>>>,
+    ) {
+        let mut child = node.clone();
+        while let Some(c) = child {
+            if !c.borrow().is_anonymous() {
+                objects.insert(Rc::as_ptr(&c.borrow().node()) as usize, c.clone());
+            }
+            Self::collect_objects(&c.borrow().first_child(), objects);
+            child = c.borrow().next_sibling();
+        }
+    }
+
+    /// Invalidates the boxes of `node` and its descendants by their dirty flags, and clears the
+    /// flags. The style of a node in a subtree that is `restyled` is already computed again.
+    /// Sets `rebuild` if the boxes have to be built again.
+    fn invalidate(
+        &self,
+        node: &Rc<RefCell<Node>>,
+        restyled: bool,
+        objects: &BTreeMap<usize, Rc<RefCell<LayoutObject>>>,
+        rebuild: &mut bool,
+    ) {
+        let dirty = node.borrow().dirty();
+        node.borrow_mut().clear_dirty();
+        let mut restyled = restyled;
+        if dirty.is_dirty() && !*rebuild {
+            match objects.get(&(Rc::as_ptr(node) as usize)) {
+                Some(object) => {
+                    if dirty.tree {
+                        *rebuild = true;
+                    }
+                    if dirty.style && !restyled {
+                        restyled = true;
+                        *rebuild |= !self.restyle_subtree(object);
+                    }
+                    if dirty.layout {
+                        object.borrow_mut().set_needs_layout();
+                    }
+                    if dirty.paint {
+                        object.borrow_mut().set_needs_paint();
+                    }
+                }
+                // A node without boxes may generate boxes now, unless it's in an element without
+                // boxes.
+                None => {
+                    let parent = node.borrow().parent().upgrade();
+                    *rebuild |=
+                        parent.is_some_and(|p| objects.contains_key(&(Rc::as_ptr(&p) as usize)));
+                }
+            }
+        }
+        if dirty.descendants {
+            let mut child = node.borrow().first_child();
+            while let Some(c) = child {
+                self.invalidate(&c, restyled, objects, rebuild);
+                child = c.borrow().next_sibling();
+            }
+        }
+    }
+
+    /// Computes the styles of `object` and its descendants again, and marks the boxes whose
+    /// rendering is out of date. Returns false if the boxes have to be built again. The boxes of
+    /// pseudo-elements are styled when they are built, so they are built again too, except for
+    /// an outside marker, which is styled with its list item.
+    fn restyle_subtree(&self, object: &Rc<RefCell<LayoutObject>>) -> bool {
+        if object.borrow().is_anonymous() {
+            return false;
+        }
+        match restyle(object, &self.style_sheets, &self.viewport) {
+            StyleDifference::Equal => {}
+            StyleDifference::Paint => object.borrow_mut().set_needs_paint(),
+            StyleDifference::Layout => object.borrow_mut().set_needs_layout(),
+            StyleDifference::Rebuild => return false,
+        }
+        // The marker is laid out by its list item.
+        let marker = object.borrow().marker();
+        match restyle_marker(object, &self.style_sheets, &self.viewport) {
+            StyleDifference::Equal => {}
+            StyleDifference::Paint => {
+                if let Some(marker) = marker {
+                    marker.borrow_mut().set_needs_paint();
+                }
+            }
+            StyleDifference::Layout => object.borrow_mut().set_needs_layout(),
+            StyleDifference::Rebuild => return false,
+        }
+        let mut child = object.borrow().first_child();
+        while let Some(c) = child {
+            if !self.restyle_subtree(&c) {
+                return false;
+            }
+            child = c.borrow().next_sibling();
+        }
+        true
+    }
+
+    /// Collects the visual rectangles of the boxes that have to be painted again, both where they
+    /// were last painted and where they are painted now. A box is painted again if its style is
+    /// changed, or if it's moved or resized. The rectangles are in the coordinates of the
+    /// document after `transform` and the transforms of the boxes.
+    fn collect_damage(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        transform: Transform,
+        damage: &mut Vec<(LayoutPoint, LayoutSize)>,
+    ) {
+        let mut child = node.clone();
+        while let Some(c) = child {
+            let transform = match c.borrow().transform() {
+                Some((t, origin)) => transform.multiply(&t.about(origin)),
+                None => transform,
+            };
+            let marker = c.borrow().marker();
+            for object in core::iter::once(c.clone()).chain(marker) {
+                let (point, size) = object.borrow().visual_rect();
+                let rect = transform.bounding_box(point, size);
+                let old = object.borrow_mut().update_painted_rect(rect);
+                for r in old.into_iter().chain(old.map(|_| rect)) {
+                    if r.1.width() > 0 && r.1.height() > 0 && !damage.contains(&r) {
+                        damage.push(r);
+                    }
+                }
+            }
+            Self::collect_damage(&c.borrow().first_child(), transform, damage);
+            child = c.borrow().next_sibling();
         }
-        //     None => (),
-        // }
     }
+
+    /// https://www.w3.org/TR/CSS2/zindex.html
+    /// Returns the display items in painting order. The root box forms the root stacking context.
     pub fn paint(&self) -> Vec<DisplayItem> {
         let mut display_items = Vec::new();
-        Self::paint_node(&self.root, &mut display_items);
+        if let Some(root) = &self.root {
+            Self::paint_stacking_context(root, &mut display_items);
+        }
         display_items
     }
 
+    /// https://www.w3.org/TR/CSS2/zindex.html#painting-order
+    /// Paints the root box of a stacking context, the layers with negative stack levels, the
+    /// descendants in flow, and then the layers with zero or positive stack levels. Layers at the
+    /// same stack level are painted in tree order. The whole stacking context is painted in the
+    /// opacity and the transform of its root box.
+    fn paint_stacking_context(
+        root: &Rc<RefCell<LayoutObject>>,
+        display_items: &mut Vec<DisplayItem>,
+    ) {
+        let mut layers = Vec::new();
+        Self::collect_layers(&root.borrow().first_child(), &mut layers);
+        // The sort is stable, so the tree order is kept in each level.
+        layers.sort_by_key(|layer| layer.borrow().style().stack_level());
+        let (negative, rest): (Vec<_>, Vec<_>) = layers
+            .into_iter()
+            .partition(|layer| layer.borrow().style().stack_level() < 0);
+
+        let transform = root.borrow().transform();
+        if let Some((transform, origin)) = transform {
+            display_items.push(DisplayItem::PushTransform { transform, origin });
+        }
+        let opacity = root.borrow().style().opacity();
+        if opacity < 1.0 {
+            display_items.push(DisplayItem::PushOpacity { opacity });
+        }
+
+        display_items.extend(root.borrow().paint_decorations());
+        for layer in &negative {
+            Self::paint_layer(root, layer, display_items);
+        }
+        Self::paint_flow(root, display_items);
+        for layer in &rest {
+            Self::paint_layer(root, layer, display_items);
+        }
+
+        if opacity < 1.0 {
+            display_items.push(DisplayItem::PopOpacity);
+        }
+        if transform.is_some() {
+            display_items.push(DisplayItem::PopTransform);
+        }
+    }
+
+    /// Collects the boxes that are painted as layers by the stacking context that `node` is in:
+    /// the positioned boxes and the boxes that create stacking contexts. A positioned box that
+    /// doesn't create a stacking context is painted as if it did, but its layers belong to the
+    /// parent stacking context.
+    fn collect_layers(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        layers: &mut Vec<Rc<RefCell<LayoutObject>>>,
+    ) {
+        let mut child = node.clone();
+        while let Some(c) = child {
+            let style = c.borrow().style();
+            if style.is_layer() {
+                layers.push(c.clone());
+            }
+            if !style.creates_stacking_context() {
+                Self::collect_layers(&c.borrow().first_child(), layers);
+            }
+            child = c.borrow().next_sibling();
+        }
+    }
+
+    /// Paints a layer of the stacking context of `root` in the clips of its ancestors.
+    fn paint_layer(
+        root: &Rc<RefCell<LayoutObject>>,
+        layer: &Rc<RefCell<LayoutObject>>,
+        display_items: &mut Vec<DisplayItem>,
+    ) {
+        let clips = Self::ancestor_clips(root, layer);
+        for (layout_point, layout_size) in &clips {
+            display_items.push(DisplayItem::PushClip {
+                layout_point: *layout_point,
+                layout_size: *layout_size,
+            });
+        }
+        if layer.borrow().style().creates_stacking_context() {
+            Self::paint_stacking_context(layer, display_items);
+        } else {
+            Self::paint_atomically(layer, display_items);
+        }
+        for _ in &clips {
+            display_items.push(DisplayItem::PopClip);
+        }
+    }
+
+    /// Returns the clips of the ancestors of `layer` up to `root`, from the outermost one. A box
+    /// isn't clipped by the ancestors between it and its containing block, so a fixed box isn't
+    /// clipped at all and an absolutely positioned box is clipped from its containing block.
+    fn ancestor_clips(
+        root: &Rc<RefCell<LayoutObject>>,
+        layer: &Rc<RefCell<LayoutObject>>,
+    ) -> Vec<(LayoutPoint, LayoutSize)> {
+        let position = layer.borrow().style().position();
+        let mut in_containing_block = !position.is_out_of_flow();
+        let mut clips = Vec::new();
+        if position == Position::Fixed {
+            return clips;
+        }
+        let mut ancestor = layer.borrow().parent().upgrade();
+        while let Some(a) = ancestor {
+            if a.borrow().style().position().is_positioned() {
+                in_containing_block = true;
+            }
+            if in_containing_block {
+                if let Some(clip) = a.borrow().clip_rect() {
+                    clips.push(clip);
+                }
+            }
+            if Rc::ptr_eq(&a, root) {
+                break;
+            }
+            ancestor = a.borrow().parent().upgrade();
+        }
+        clips.reverse();
+        clips
+    }
+
+    /// https://www.w3.org/TR/CSS2/zindex.html#painting-order
+    /// Paints `node` "as if it created a new stacking context, but any positioned descendants and
+    /// descendants which actually create a new stacking context should be considered part of the
+    /// parent stacking context". Floats, inline blocks and flex and grid items are painted in
+    /// this way.
+    fn paint_atomically(node: &Rc<RefCell<LayoutObject>>, display_items: &mut Vec<DisplayItem>) {
+        display_items.extend(node.borrow().paint_decorations());
+        Self::paint_flow(node, display_items);
+    }
+
+    /// https://www.w3.org/TR/CSS2/zindex.html#painting-order
+    /// Paints the descendants in flow of `node` in the phases of steps 4 to 7, in the clip of
+    /// `node`: the backgrounds and borders of the block-level descendants, then the floats, and
+    /// then the content of `node` itself and the inline-level content of all of them. The
+    /// scrollbars of `node` are painted over its content.
+    fn paint_flow(node: &Rc<RefCell<LayoutObject>>, display_items: &mut Vec<DisplayItem>) {
+        let clipped = Self::push_clip(node, display_items);
+        Self::paint_descendants(node, PaintPhase::BlockBackgrounds, display_items);
+        Self::paint_descendants(node, PaintPhase::Floats, display_items);
+        display_items.extend(node.borrow().paint_foreground());
+        display_items.extend(node.borrow().paint_marker());
+        Self::paint_descendants(node, PaintPhase::Inline, display_items);
+        if clipped {
+            display_items.push(DisplayItem::PopClip);
+        }
+        display_items.extend(node.borrow().paint_scrollbars());
+    }
+
+    /// Pushes the clip of `node` if it clips its content, and returns true if it does.
+    fn push_clip(node: &Rc<RefCell<LayoutObject>>, display_items: &mut Vec<DisplayItem>) -> bool {
+        match node.borrow().clip_rect() {
+            Some((layout_point, layout_size)) => {
+                display_items.push(DisplayItem::PushClip {
+                    layout_point,
+                    layout_size,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Paints the part of the descendants of `node` that belongs to `phase`, in tree order. The
+    /// descendants of a box that clips its content are painted in its clip. Floats and atomic
+    /// inlines are painted as a whole in their phases, and layers are painted by the stacking
+    /// context.
+    fn paint_descendants(
+        node: &Rc<RefCell<LayoutObject>>,
+        phase: PaintPhase,
+        display_items: &mut Vec<DisplayItem>,
+    ) {
+        // https://www.w3.org/TR/css-flexbox-1/#painting
+        // "Flex items paint exactly the same as inline blocks", and so do grid items.
+        let display = node.borrow().style().display();
+        let items_are_atomic = display.is_flex_container() || display.is_grid_container();
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            let c_ref = c.borrow();
+            if c_ref.style().is_layer() {
+                // Painted by the stacking context.
+            } else if c_ref.is_floating() {
+                if phase == PaintPhase::Floats {
+                    Self::paint_atomically(&c, display_items);
+                }
+            } else if c_ref.is_atomic_inline() || items_are_atomic {
+                if phase == PaintPhase::Inline {
+                    Self::paint_atomically(&c, display_items);
+                }
+            } else {
+                match (c_ref.kind(), phase) {
+                    (
+                        LayoutObjectKind::Block | LayoutObjectKind::Grid,
+                        PaintPhase::BlockBackgrounds,
+                    ) => display_items.extend(c_ref.paint_decorations()),
+                    (LayoutObjectKind::Block | LayoutObjectKind::Grid, PaintPhase::Inline) => {
+                        display_items.extend(c_ref.paint_foreground());
+                        display_items.extend(c_ref.paint_marker());
+                    }
+                    (LayoutObjectKind::Inline, PaintPhase::Inline) => {
+                        display_items.extend(c_ref.paint_decorations())
+                    }
+                    (LayoutObjectKind::Text, PaintPhase::Inline) => {
+                        display_items.extend(c_ref.paint_foreground())
+                    }
+                    _ => {}
+                }
+                let clipped = Self::push_clip(&c, display_items);
+                Self::paint_descendants(&c, phase, display_items);
+                if clipped {
+                    display_items.push(DisplayItem::PopClip);
+                }
+                // The scrollbars of a scroll container are over its content.
+                if phase == PaintPhase::Inline {
+                    display_items.extend(c_ref.paint_scrollbars());
+                }
+            }
+            child = c_ref.next_sibling();
+        }
+    }
+
+    /// The viewport in the coordinates of the document. It's moved by scrolling.
+    fn viewport_rect(&self) -> (LayoutPoint, LayoutSize) {
+        (
+            self.scroll_position,
+            LayoutSize::new(self.viewport.width(), self.viewport.height()),
+        )
+    }
+
+    /// https://www.w3.org/TR/css-position-3/#def-cb
+    /// Returns the padding box of the containing block of an absolutely positioned box. It's the
+    /// nearest positioned ancestor, or the initial containing block if there is none. A fixed box
+    /// is placed in the viewport.
+    fn containing_block(
+        node: &Rc<RefCell<LayoutObject>>,
+        viewport: (LayoutPoint, LayoutSize),
+    ) -> (LayoutPoint, LayoutSize) {
+        if node.borrow().style().position() == Position::Fixed {
+            return viewport;
+        }
+        let mut ancestor = node.borrow().parent().upgrade();
+        while let Some(a) = ancestor {
+            let a_ref = a.borrow();
+            if a_ref.style().position().is_positioned() {
+                return match a_ref.kind() {
+                    // The content of a scroll container moves with its scroll offset.
+                    LayoutObjectKind::Block | LayoutObjectKind::Grid => {
+                        let point = a_ref.padding_point();
+                        let offset = a_ref.scroll_offset();
+                        (
+                            LayoutPoint::new(point.x() - offset.x(), point.y() - offset.y()),
+                            a_ref.client_size(),
+                        )
+                    }
+                    // An inline box contains its fragments.
+                    LayoutObjectKind::Inline | LayoutObjectKind::Text => {
+                        (a_ref.point(), a_ref.size())
+                    }
+                };
+            }
+            ancestor = a_ref.parent().upgrade();
+        }
+        (LayoutPoint::new(0, 0), viewport.1)
+    }
+
+    /// https://www.w3.org/TR/css-position-3/#stickypos-insets
+    /// Returns how far a sticky positioned box at its position in flow is moved to stay in the
+    /// scrollport of its nearest scroll container, or the viewport. It's not moved out of its
+    /// containing block.
+    fn sticky_offset(
+        node: &Rc<RefCell<LayoutObject>>,
+        viewport: (LayoutPoint, LayoutSize),
+    ) -> LayoutPoint {
+        let n = node.borrow();
+        let parent = match n.parent().upgrade() {
+            Some(p) => p,
+            None => return LayoutPoint::new(0, 0),
+        };
+
+        let mut scrollport = viewport;
+        let mut ancestor = Some(parent.clone());
+        while let Some(a) = ancestor {
+            if a.borrow().clip_rect().is_some() && a.borrow().style().is_scroll_container() {
+                scrollport = (a.borrow().padding_point(), a.borrow().client_size());
+                break;
+            }
+            ancestor = a.borrow().parent().upgrade();
+        }
+
+        // The containing block of a child of a scroll container spans its scrollable overflow.
+        let p = parent.borrow();
+        let cb_point = p.scrolled_content_point();
+        let cb_size = if p.clip_rect().is_some() {
+            let padding = p.padding();
+            LayoutSize::new(
+                p.scrollable_overflow().width() - padding.horizontal(),
+                p.scrollable_overflow().height() - padding.vertical(),
+            )
+        } else {
+            p.content_size()
+        };
+
+        let style = n.style();
+        let point = n.point();
+        let margin = n.margin();
+        // (the start of the box, its size, the start of the scrollport, its size, the start of
+        // the containing block, its size, the start margin and the end margin) along each axis.
+        let axis =
+            |start: Side,
+             end: Side,
+             (p, s, sp, ss, cp, cs, ms, me): (i64, i64, i64, i64, i64, i64, i64, i64)| {
+                let mut d = 0;
+                if let LengthPercentageOrAuto::LengthPercentage(inset) = style.inset(start) {
+                    let limit = sp + inset.resolve(ss);
+                    if p < limit {
+                        d = (limit - p).min(cp + cs - me - s - p).max(0);
+                    }
+                }
+                if let LengthPercentageOrAuto::LengthPercentage(inset) = style.inset(end) {
+                    let limit = sp + ss - inset.resolve(ss) - s;
+                    if p + d > limit {
+                        d = (limit - p).max(cp + ms - p).min(d);
+                    }
+                }
+                d
+            };
+        let size = n.size();
+        LayoutPoint::new(
+            axis(
+                Side::Left,
+                Side::Right,
+                (
+                    point.x(),
+                    size.width(),
+                    scrollport.0.x(),
+                    scrollport.1.width(),
+                    cb_point.x(),
+                    cb_size.width(),
+                    margin.left,
+                    margin.right,
+                ),
+            ),
+            axis(
+                Side::Top,
+                Side::Bottom,
+                (
+                    point.y(),
+                    size.height(),
+                    scrollport.0.y(),
+                    scrollport.1.height(),
+                    cb_point.y(),
+                    cb_size.height(),
+                    margin.top,
+                    margin.bottom,
+                ),
+            ),
+        )
+    }
+
+    /// Sets the absolute positions of objects. Objects are placed relative to the content box of
+    /// their containing block, which is the nearest block-level ancestor. Absolutely positioned
+    /// boxes are placed in their own containing block, and sticky boxes are moved to stay in the
+    /// scrollport.
     fn calculate_node_position(
         node: &Option<Rc<RefCell<LayoutObject>>>,
-        parent_point: LayoutPoint,
-        previous_sibling_kind: LayoutObjectKind,
-        previous_sibling_point: Option<LayoutPoint>,
-        previous_sibling_size: Option<LayoutSize>,
+        containing_block_point: LayoutPoint,
+        viewport: (LayoutPoint, LayoutSize),
     ) {
         if let Some(n) = node {
-            n.borrow_mut().compute_position(
-                parent_point,
-                previous_sibling_kind,
-                previous_sibling_point,
-                previous_sibling_size,
-            );
+            let position = n.borrow().style().position();
+            match position {
+                Position::Absolute | Position::Fixed => {
+                    let (cb_point, cb_size) = Self::containing_block(n, viewport);
+                    let offset = n.borrow().offset();
+                    let static_point = LayoutPoint::new(
+                        containing_block_point.x() + offset.x(),
+                        containing_block_point.y() + offset.y(),
+                    );
+                    n.borrow_mut()
+                        .compute_out_of_flow_position(cb_point, cb_size, static_point);
+                }
+                Position::Sticky => {
+                    n.borrow_mut().set_relative_offset(LayoutPoint::new(0, 0));
+                    n.borrow_mut().compute_position(containing_block_point);
+                    let offset = Self::sticky_offset(n, viewport);
+                    n.borrow_mut().set_relative_offset(offset);
+                    n.borrow_mut().compute_position(containing_block_point);
+                }
+                Position::Static | Position::Relative => {
+                    n.borrow_mut().compute_position(containing_block_point)
+                }
+            }
 
             let first_child = n.borrow().first_child();
-            Self::calculate_node_position(
-                &first_child,
-                n.borrow().point(),
-                LayoutObjectKind::Block,
-                None,
-                None,
-            );
-            let next_sibling = n.borrow().next_sibling();
-            Self::calculate_node_position(
-                &next_sibling,
-                parent_point,
+            // The children of an inline box are placed in the containing block, and move with a
+            // relatively positioned inline box.
+            let child_containing_block_point = if matches!(
                 n.borrow().kind(),
-                Some(n.borrow().point()),
-                Some(n.borrow().size()),
-            );
+                LayoutObjectKind::Block | LayoutObjectKind::Grid
+            ) {
+                n.borrow().scrolled_content_point()
+            } else {
+                let offset = n.borrow().relative_offset();
+                LayoutPoint::new(
+                    containing_block_point.x() + offset.x(),
+                    containing_block_point.y() + offset.y(),
+                )
+            };
+            Self::calculate_node_position(&first_child, child_containing_block_point, viewport);
+            let next_sibling = n.borrow().next_sibling();
+            Self::calculate_node_position(&next_sibling, containing_block_point, viewport);
         }
     }
+
     pub fn root(&self) -> Option<Rc<RefCell<LayoutObject>>> {
         self.root.clone()
     }
+
+    pub fn viewport(&self) -> Device {
+        self.viewport
+    }
+
+    /// The size of the scrollable overflow area of the viewport. It contains the initial
+    /// containing block and the margin box of the root box.
+    /// https://www.w3.org/TR/css-overflow-3/#scrollable
+    pub fn document_size(&self) -> LayoutSize {
+        let mut size = LayoutSize::new(self.viewport.width(), self.viewport.height());
+        if let Some(root) = &self.root {
+            let root = root.borrow();
+            let margin = root.margin();
+            size.set_width(
+                size.width()
+                    .max(root.point().x() + root.size().width() + margin.right),
+            );
+            size.set_height(
+                size.height()
+                    .max(root.point().y() + root.size().height() + margin.bottom),
+            );
+        }
+        size
+    }
+
+    /// Scrolls the innermost scroll container at `position` that can be scrolled by (`dx`, `dy`),
+    /// and returns true if one is scrolled. The viewport is scrolled by the page instead.
+    pub fn scroll_by(&mut self, position: (i64, i64), dx: i64, dy: i64) -> bool {
+        let scrolled = Self::scroll_node_by(&self.root, position, dx, dy);
+        if scrolled {
+            Self::calculate_node_position(&self.root, LayoutPoint::new(0, 0), self.viewport_rect());
+        }
+        scrolled
+    }
+
+    pub fn scroll_position(&self) -> LayoutPoint {
+        self.scroll_position
+    }
+
+    /// Sets the scroll position of the viewport, and moves the fixed and sticky boxes with it.
+    pub fn set_scroll_position(&mut self, scroll_position: LayoutPoint) {
+        self.scroll_position = scroll_position;
+        Self::calculate_node_position(&self.root, LayoutPoint::new(0, 0), self.viewport_rect());
+    }
+
+    fn scroll_node_by(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        position: (i64, i64),
+        dx: i64,
+        dy: i64,
+    ) -> bool {
+        match node {
+            Some(n) => {
+                if n.borrow().clip_contains(position) {
+                    let first_child = n.borrow().first_child();
+                    if Self::scroll_node_by(&first_child, position, dx, dy) {
+                        return true;
+                    }
+                }
+                let next_sibling = n.borrow().next_sibling();
+                if Self::scroll_node_by(&next_sibling, position, dx, dy) {
+                    return true;
+                }
+                n.borrow().clip_rect().is_some()
+                    && n.borrow().contains_point(position)
+                    && n.borrow_mut().scroll_by(dx, dy)
+            }
+            None => false,
+        }
+    }
+
     fn update_layout(&mut self) {
-        Self::calculate_node_size(&self.root, LayoutSize::new(CONTENT_AREA_WIDTH, 0));
-        Self::calculate_node_position(
-            &self.root,
-            LayoutPoint::new(0, 0),
-            LayoutObjectKind::Block,
-            None,
-            None,
-        );
+        // The initial containing block has the dimensions of the viewport.
+        // https://www.w3.org/TR/CSS2/visudet.html#containing-block-details
+        // Boxes in flow are laid out from the root, each block box after the ones before it.
+        if let Some(root) = &self.root {
+            root.borrow_mut().compute_size(
+                LayoutSize::new(self.viewport.width(), self.viewport.height()),
+                self.font_metrics.as_ref(),
+            );
+            let margin = root.borrow().margin();
+            root.borrow_mut()
+                .set_offset(LayoutPoint::new(margin.left, margin.top));
+        }
+        // Out-of-flow boxes are sized against their containing blocks, which are sized in flow.
+        Self::layout_out_of_flow(&self.root, self.viewport_rect(), self.font_metrics.as_ref());
+        Self::calculate_node_position(&self.root, LayoutPoint::new(0, 0), self.viewport_rect());
     }
-    fn calculate_node_size(node: &Option<Rc<RefCell<LayoutObject>>>, parent_size: LayoutSize) {
+
+    /// https://www.w3.org/TR/css-position-3/#abspos-layout
+    /// Sizes the absolutely positioned boxes against their containing blocks, after the boxes in
+    /// flow are sized.
+    fn layout_out_of_flow(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        viewport: (LayoutPoint, LayoutSize),
+        font_metrics: &dyn FontMetrics,
+    ) {
         if let Some(n) = node {
-            // For block elements, we should layout the size before calling children.
-            if n.borrow().kind() == LayoutObjectKind::Block {
-                n.borrow_mut().compute_size(parent_size);
+            if n.borrow().is_out_of_flow() {
+                let (_, cb_size) = Self::containing_block(n, viewport);
+                n.borrow_mut().compute_size(cb_size, font_metrics);
             }
-
             let first_child = n.borrow().first_child();
-            Self::calculate_node_size(&first_child, n.borrow().size());
-
+            Self::layout_out_of_flow(&first_child, viewport, font_metrics);
             let next_sibling = n.borrow().next_sibling();
-            Self::calculate_node_size(&next_sibling, parent_size);
+            Self::layout_out_of_flow(&next_sibling, viewport, font_metrics);
+        }
+    }
+
+    /// Returns the URLs of the images that the page uses, as written in the document: the
+    /// sources of <img> elements and the background images, in tree order.
+    pub fn image_urls(&self) -> Vec<String> {
+        let mut urls = Vec::new();
+        Self::collect_image_urls(&self.root, &mut urls);
+        urls
+    }
 
-            // TODO: optimize this code because we call compute_size() twice.
-            // For inline, text elements and the height of block elements, we should layout the size after calling children.
-            n.borrow_mut().compute_size(parent_size);
+    fn collect_image_urls(node: &Option<Rc<RefCell<LayoutObject>>>, urls: &mut Vec<String>) {
+        let mut child = node.clone();
+        while let Some(c) = child {
+            if let NodeKind::Element(e) = c.borrow().node_kind() {
+                if let Some(src) = e
+                    .get_attribute("src")
+                    .filter(|_| e.kind() == ElementKind::Img)
+                {
+                    urls.push(src);
+                }
+            }
+            for layer in c.borrow().style().background_image() {
+                if let Some(url) = layer.url() {
+                    urls.push(url.to_string());
+                }
+            }
+            Self::collect_image_urls(&c.borrow().first_child(), urls);
+            child = c.borrow().next_sibling();
         }
     }
+
     pub fn find_node_by_position(&self, position: (i64, i64)) -> Option<Rc<RefCell<LayoutObject>>> {
         Self::find_node_by_position_internal(&self.root(), position)
     }
@@ -121,10 +837,12 @@ impl LayoutView {
     ) -> Option<Rc<RefCell<LayoutObject>>> {
         match node {
             Some(n) => {
-                let first_child = n.borrow().first_child();
-                let result1 = Self::find_node_by_position_internal(&first_child, position);
-                if result1.is_some() {
-                    return result1;
+                if n.borrow().clip_contains(position) {
+                    let first_child = n.borrow().first_child();
+                    let result1 = Self::find_node_by_position_internal(&first_child, position);
+                    if result1.is_some() {
+                        return result1;
+                    }
                 }
 
                 let next_sibling = n.borrow().next_sibling();
@@ -133,11 +851,7 @@ impl LayoutView {
                     return result2;
                 }
 
-                if n.borrow().point().x() <= position.0
-                    && position.0 <= (n.borrow().point().x() + n.borrow().size().width())
-                    && n.borrow().point().y() <= position.1
-                    && position.1 <= (n.borrow().point().y() + n.borrow().size().height())
-                {
+                if n.borrow().contains_point(position) {
                     return Some(n.clone());
                 }
                 None
@@ -147,27 +861,54 @@ impl LayoutView {
     }
 }
 
+/// Builds the layout objects of `node` and its following siblings, with the ::marker, ::before,
+/// ::after and ::first-letter boxes of the elements and the images loaded in `images`. The counters and quotes are updated in tree
+/// order, so the children of a node are built before its next sibling.
 fn build_layout_tree(
     node: &Option<Rc<RefCell<Node>>>,
     parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
-    cssom: &StyleSheet,
+    style_sheets: &[StyleSheet],
+    device: &Device,
+    images: &ImageStore,
+    counters: &mut CounterScopes,
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     let mut target_node = node.clone();
-    let mut layout_object = create_layout_object(node, parent_obj, cssom);
+    let mut layout_object = create_layout_object(node, parent_obj, style_sheets, device);
 
     while layout_object.is_none() {
         if let Some(n) = target_node {
             target_node = n.borrow().next_sibling().clone();
-            layout_object = create_layout_object(&target_node, parent_obj, cssom);
+            layout_object = create_layout_object(&target_node, parent_obj, style_sheets, device);
         } else {
             return layout_object;
         }
     }
     if let Some(n) = target_node {
+        let obj = match layout_object {
+            Some(ref obj) => obj,
+            None => panic!("render object should exist here"),
+        };
+        // https://www.w3.org/TR/css-lists-3/#counters-without-boxes
+        // "An element that does not generate a box (for example, an element with display set
+        // to none, or a pseudo-element with content set to none) cannot set, reset, or
+        // increment a counter."
+        let style = obj.borrow().style();
+        counters.update(&style);
+        let marker = create_marker(obj, style_sheets, device, counters);
+
         let original_first_child = n.borrow().first_child();
         let original_next_sibling = n.borrow().next_sibling();
-        let mut first_child = build_layout_tree(&original_first_child, &layout_object, cssom);
-        let mut next_sibling = build_layout_tree(&original_next_sibling, &None, cssom);
+        counters.enter();
+        let before =
+            create_generated_content(obj, PseudoElement::Before, style_sheets, device, counters);
+        let mut first_child = build_layout_tree(
+            &original_first_child,
+            &layout_object,
+            style_sheets,
+            device,
+            images,
+            counters,
+        );
 
         if first_child.is_none() && original_first_child.is_some() {
             let mut original_dom_node = original_first_child
@@ -176,7 +917,14 @@ fn build_layout_tree(
                 .next_sibling();
 
             loop {
-                first_child = build_layout_tree(&original_dom_node, &layout_object, cssom);
+                first_child = build_layout_tree(
+                    &original_dom_node,
+                    &layout_object,
+                    style_sheets,
+                    device,
+                    images,
+                    counters,
+                );
 
                 if first_child.is_none() && original_dom_node.is_some() {
                     original_dom_node = original_dom_node
@@ -188,6 +936,18 @@ fn build_layout_tree(
                 break;
             }
         }
+        let after =
+            create_generated_content(obj, PseudoElement::After, style_sheets, device, counters);
+        counters.leave();
+
+        let mut next_sibling = build_layout_tree(
+            &original_next_sibling,
+            parent_obj,
+            style_sheets,
+            device,
+            images,
+            counters,
+        );
 
         if next_sibling.is_none() && original_next_sibling.is_some() {
             let mut original_dom_node = original_next_sibling
@@ -196,7 +956,14 @@ fn build_layout_tree(
                 .next_sibling();
 
             loop {
-                next_sibling = build_layout_tree(&original_dom_node, &None, cssom);
+                next_sibling = build_layout_tree(
+                    &original_dom_node,
+                    parent_obj,
+                    style_sheets,
+                    device,
+                    images,
+                    counters,
+                );
 
                 if next_sibling.is_none() && original_dom_node.is_some() {
                     original_dom_node = original_dom_node
@@ -209,12 +976,14 @@ fn build_layout_tree(
             }
         }
 
-        let obj = match layout_object {
-            Some(ref obj) => obj,
-            None => panic!("render object should exist here"),
-        };
         obj.borrow_mut().set_first_child(first_child);
         obj.borrow_mut().set_next_sibling(next_sibling);
+        attach_images(obj, images, device);
+        obj.borrow_mut().attach_generated_content(before, after);
+        create_first_letter(obj, style_sheets, device);
+        if let Some(marker) = marker {
+            obj.borrow_mut().attach_marker(marker);
+        }
     }
     layout_object
 }
@@ -225,23 +994,72 @@ mod tests {
     use super::*;
     use crate::alloc::string::String;
     use crate::alloc::string::ToString;
+    use crate::display_item::BulletShape;
+    use crate::display_item::CornerRadii;
     use crate::renderer::css::cssom::CssParser;
     use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::dom::api::get_element_by_id;
     use crate::renderer::dom::api::get_style_content;
+    use crate::renderer::dom::api::set_attribute;
+    use crate::renderer::dom::api::set_text_content;
     use crate::renderer::dom::node::Element;
     use crate::renderer::dom::node::NodeKind;
     use crate::renderer::html::parser::HtmlParser;
     use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::image::Image;
+    use crate::renderer::layout::computed_style::BorderStyle;
+    use crate::renderer::layout::computed_style::Color;
+    use crate::renderer::layout::computed_style::Font;
+    use crate::renderer::layout::computed_style::TextDecoration;
+    use crate::renderer::layout::font_metrics::MonospaceFontMetrics;
+    use crate::renderer::layout::layout_object::EdgeSizes;
+    use crate::renderer::layout::transform::Transform;
+    use alloc::vec;
     use alloc::vec::Vec;
 
+    const VIEWPORT_WIDTH: i64 = 600;
+    const VIEWPORT_HEIGHT: i64 = 400;
+
     fn create_layout_view(html: String) -> LayoutView {
+        create_layout_view_with_viewport(html, Device::new(VIEWPORT_WIDTH, VIEWPORT_HEIGHT))
+    }
+
+    fn create_layout_view_with_viewport(html: String, viewport: Device) -> LayoutView {
+        create_layout_view_with(html, viewport, Rc::new(MonospaceFontMetrics))
+    }
+
+    fn create_layout_view_with_font_metrics(
+        html: String,
+        font_metrics: Rc<dyn FontMetrics>,
+    ) -> LayoutView {
+        create_layout_view_with(
+            html,
+            Device::new(VIEWPORT_WIDTH, VIEWPORT_HEIGHT),
+            font_metrics,
+        )
+    }
+
+    fn create_layout_view_with(
+        html: String,
+        viewport: Device,
+        font_metrics: Rc<dyn FontMetrics>,
+    ) -> LayoutView {
+        create_layout_view_with_images(html, viewport, font_metrics, &ImageStore::new())
+    }
+
+    fn create_layout_view_with_images(
+        html: String,
+        viewport: Device,
+        font_metrics: Rc<dyn FontMetrics>,
+        images: &ImageStore,
+    ) -> LayoutView {
         let t = HtmlTokenizer::new(html);
         let windows = HtmlParser::new(t).construct_tree();
         let dom = windows.borrow().document();
         let style = get_style_content(dom.clone());
         let css_tokenizer = CssTokenizer::new(style);
         let cssom = CssParser::new(css_tokenizer).parse_stylesheet();
-        LayoutView::new(dom, &cssom)
+        LayoutView::new(dom, &cssom, viewport, font_metrics, images)
     }
 
     #[test]
@@ -354,4 +1172,1775 @@ mod tests {
             .next_sibling()
             .is_none());
     }
+
+    #[test]
+    fn test_media_query() {
+        let html = r#"<html><head><style>
+        @media (max-width: 300px) { .narrow { display: none; } }
+        @media (min-width: 300px) { .wide { display: none; } }
+        </style></head><body><p class="narrow"></p><p class="wide"></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root();
+        assert!(root.is_some());
+
+        let p = root.expect("root should exist").borrow().first_child();
+        assert!(p.is_some());
+        assert_eq!(
+            NodeKind::Element(Element::new("p", Vec::new())),
+            p.clone().expect("p node should exist").borrow().node_kind()
+        );
+        assert!(p
+            .expect("p node should exist")
+            .borrow()
+            .next_sibling()
+            .is_none());
+    }
+
+    #[test]
+    fn test_media_query_at_viewport() {
+        // The viewport is narrower than the default device of 800px.
+        let html = r#"<html><head><style>
+        @media (min-width: 700px) { p { display: none; } }
+        </style></head><body><p></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+        assert!(root.borrow().first_child().is_some());
+    }
+
+    #[test]
+    fn test_user_agent_style() {
+        let html = "<html><head></head><body><h1>title</h1><p>text <a>link</a></p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+
+        let h1 = root.borrow().first_child().expect("h1 should exist");
+        assert_eq!(LayoutObjectKind::Block, h1.borrow().kind());
+        assert_eq!(32.0, h1.borrow().style().font_size());
+
+        let p = h1.borrow().next_sibling().expect("p should exist");
+        assert_eq!(LayoutObjectKind::Block, p.borrow().kind());
+        assert_eq!(16.0, p.borrow().style().font_size());
+        assert_eq!(TextDecoration::None, p.borrow().style().text_decoration());
+    }
+
+    #[test]
+    fn test_author_style_overrides_user_agent_style() {
+        let html = r#"<html><head><style>h1 { display: inline; font-size: medium; }
+        a { text-decoration: none; }</style></head><body><h1><a>link</a></h1></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+
+        let h1 = root.borrow().first_child().expect("h1 should exist");
+        assert_eq!(LayoutObjectKind::Inline, h1.borrow().kind());
+        assert_eq!(16.0, h1.borrow().style().font_size());
+
+        let a = h1.borrow().first_child().expect("a should exist");
+        assert_eq!(TextDecoration::None, a.borrow().style().text_decoration());
+    }
+
+    #[test]
+    fn test_box_model() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 10px; padding: 5px; border: 2px solid red; width: 100px; height: 20px; }
+        </style></head><body><p></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+        assert_eq!(LayoutPoint::new(0, 0), root.borrow().point());
+
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(EdgeSizes::new(10, 10, 10, 10), p.borrow().margin());
+        assert_eq!(EdgeSizes::new(5, 5, 5, 5), p.borrow().padding());
+        assert_eq!(EdgeSizes::new(2, 2, 2, 2), p.borrow().border());
+        assert_eq!(LayoutPoint::new(10, 10), p.borrow().point());
+        assert_eq!(LayoutSize::new(114, 34), p.borrow().size());
+        assert_eq!(LayoutPoint::new(17, 17), p.borrow().content_point());
+        assert_eq!(LayoutSize::new(100, 20), p.borrow().content_size());
+
+        // The body contains the margin box of p.
+        assert_eq!(54, root.borrow().size().height());
+    }
+
+    #[test]
+    fn test_margin_longhands_and_body_margin() {
+        let html = r#"<html><head><style>
+        p { margin: 0; margin-left: 20px; padding-top: 3px; }
+        </style></head><body><p></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+        // The user-agent style sheet gives body a margin of 8px.
+        assert_eq!(LayoutPoint::new(8, 8), root.borrow().point());
+        assert_eq!(VIEWPORT_WIDTH - 16, root.borrow().size().width());
+
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(EdgeSizes::new(0, 0, 0, 20), p.borrow().margin());
+        assert_eq!(EdgeSizes::new(3, 0, 0, 0), p.borrow().padding());
+        assert_eq!(LayoutPoint::new(28, 8), p.borrow().point());
+        assert_eq!(VIEWPORT_WIDTH - 36, p.borrow().size().width());
+    }
+
+    #[test]
+    fn test_margin_collapsing() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin-top: 20px; margin-bottom: 30px; height: 10px; }
+        </style></head><body><p></p><p></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+
+        let p1 = root.borrow().first_child().expect("p should exist");
+        assert_eq!(20, p1.borrow().point().y());
+        let p2 = p1.borrow().next_sibling().expect("p should exist");
+        // The bottom margin of the first p (30px) and the top margin of the second p (20px)
+        // collapse into 30px.
+        assert_eq!(60, p2.borrow().point().y());
+        assert_eq!(100, root.borrow().size().height());
+    }
+
+    #[test]
+    fn test_auto_margins_and_box_sizing() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { width: 100px; margin: 0 auto; padding: 10px; border-width: 5px; border-style: solid;
+            box-sizing: border-box; }
+        </style></head><body><p></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(100, p.borrow().size().width());
+        assert_eq!(70, p.borrow().content_size().width());
+        let margin = (VIEWPORT_WIDTH - 100) / 2;
+        assert_eq!(margin, p.borrow().margin().left);
+        assert_eq!(margin, p.borrow().point().x());
+    }
+
+    #[test]
+    fn test_relative_lengths_and_percentages() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        h2 { width: 50%; padding: 10% 0; height: calc(10vh + 6px); }
+        h1 { margin: 0; margin-left: 0.5em; }
+        p { margin: 0; height: 50%; }
+        </style></head><body><h2><p></p></h2><h1></h1></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+
+        let h2 = root.borrow().first_child().expect("h2 should exist");
+        // Vertical paddings also refer to the width of the containing block.
+        let padding = VIEWPORT_WIDTH / 10;
+        assert_eq!(
+            EdgeSizes::new(padding, 0, padding, 0),
+            h2.borrow().padding()
+        );
+        assert_eq!(
+            LayoutSize::new(VIEWPORT_WIDTH / 2, VIEWPORT_HEIGHT / 10 + 6),
+            h2.borrow().content_size()
+        );
+
+        // The height of h2 is specified, so the percentage height of p resolves against it.
+        let p = h2.borrow().first_child().expect("p should exist");
+        assert_eq!((VIEWPORT_HEIGHT / 10 + 6) / 2, p.borrow().size().height());
+
+        // em is relative to the font size of h1 (xx-large, 32px).
+        let h1 = h2.borrow().next_sibling().expect("h1 should exist");
+        assert_eq!(16, h1.borrow().margin().left);
+    }
+
+    #[test]
+    fn test_percentage_height_with_auto_parent() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; height: 50%; }
+        </style></head><body><h2><p></p></h2></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+        let h2 = root.borrow().first_child().expect("h2 should exist");
+        let p = h2.borrow().first_child().expect("p should exist");
+        // The height of h2 depends on its content, so the percentage computes to auto.
+        assert_eq!(0, p.borrow().size().height());
+    }
+
+    #[test]
+    fn test_paint_border() {
+        let html = r#"<html><head><style>
+        p { border-bottom: 1px dashed blue; }
+        </style></head><body><p></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        assert_eq!(EdgeSizes::new(0, 0, 1, 0), p.borrow().border());
+
+        let borders: Vec<DisplayItem> = layout_view
+            .paint()
+            .into_iter()
+            .filter(|item| matches!(item, DisplayItem::Border { .. }))
+            .collect();
+        assert_eq!(1, borders.len());
+        if let DisplayItem::Border { sides, .. } = &borders[0] {
+            assert_eq!(BorderStyle::Dashed, sides.side(Side::Bottom).style);
+            assert_eq!(
+                Color::from_name("blue").expect("blue should be a color"),
+                sides.side(Side::Bottom).color
+            );
+            assert_eq!(0, sides.side(Side::Top).width);
+        }
+    }
+
+    #[test]
+    fn test_colors_in_paint() {
+        let html = r#"<html><head><style>
+        body { background-color: #00f; }
+        p { color: rgba(255, 255, 255, 0.5); border: 1px solid; background-color: transparent; }
+        </style></head><body><p>text</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let display_items = layout_view.paint();
+
+        // Only body paints a background because p is transparent.
+        let rects: Vec<&DisplayItem> = display_items
+            .iter()
+            .filter(|item| matches!(item, DisplayItem::Rect { .. }))
+            .collect();
+        assert_eq!(1, rects.len());
+
+        for item in display_items {
+            match item {
+                // The border color defaults to currentColor and is blended over the blue body.
+                DisplayItem::Border { sides, .. } => {
+                    assert_eq!(Color::rgb(128, 128, 255), sides.top.color);
+                }
+                DisplayItem::Text { color, .. } => {
+                    assert_eq!(Color::rgb(128, 128, 255), color);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_inheritance() {
+        let html = r#"<html><head><style>
+        body { color: red; background-color: blue; }
+        h2 { color: black; }
+        a { color: inherit; }
+        </style></head><body><p>text</p><h2><a>link</a></h2></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+
+        // Every child inherits color, not only the first one, and background-color is not
+        // inherited.
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(Color::rgb(255, 0, 0), p.borrow().style().color());
+        assert!(p.borrow().style().background_color().is_transparent());
+
+        // An explicit value that equals the initial value still overrides the inherited value.
+        let h2 = p.borrow().next_sibling().expect("h2 should exist");
+        assert_eq!(Color::black(), h2.borrow().style().color());
+        assert_eq!(24.0, h2.borrow().style().font_size());
+
+        let a = h2.borrow().first_child().expect("a should exist");
+        assert_eq!(Color::black(), a.borrow().style().color());
+        assert_eq!(24.0, a.borrow().style().font_size());
+    }
+
+    #[test]
+    fn test_revert() {
+        let html = r#"<html><head><style>
+        p { margin: 0; margin-top: revert; }
+        </style></head><body><p></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+
+        // margin-top rolls back to 1em in the user-agent style sheet.
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(EdgeSizes::new(16, 0, 0, 0), p.borrow().margin());
+    }
+
+    #[test]
+    fn test_custom_properties() {
+        let html = r#"<html><head><style>
+        body { --brand: rgb(0, 0, 255); --gap: 6px; --loop: var(--loop); margin: var(--gap); }
+        h2 { --wide: calc(var(--gap) * 2); color: var(--brand); margin: var(--wide) 0; }
+        p { color: red; color: var(--loop); padding: var(--missing, var(--gap)); }
+        </style></head><body><h2></h2><p></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+        assert_eq!(LayoutPoint::new(6, 6), root.borrow().point());
+
+        // Custom properties are inherited and can refer to other custom properties.
+        let h2 = root.borrow().first_child().expect("h2 should exist");
+        assert_eq!(Color::rgb(0, 0, 255), h2.borrow().style().color());
+        assert_eq!(EdgeSizes::new(12, 0, 12, 0), h2.borrow().margin());
+
+        // A var() that refers to a cycle makes the declaration unset, so color is inherited.
+        let p = h2.borrow().next_sibling().expect("p should exist");
+        assert_eq!(Color::black(), p.borrow().style().color());
+        assert_eq!(EdgeSizes::new(6, 6, 6, 6), p.borrow().padding());
+    }
+
+    #[test]
+    fn test_shorthands() {
+        let html = r#"<html><head><style>
+        body { --border: 2px solid; }
+        p { background: url("a.png") rgb(0, 0, 255); font: bold x-large serif; border: var(--border); }
+        a { text-decoration: underline wavy red; }
+        h2 { background: red; background: blue blue; margin: 0; margin: 1px 2px 3px 4px 5px; }
+        </style></head><body><p><a>link</a></p><h2></h2></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(Color::rgb(0, 0, 255), p.borrow().style().background_color());
+        assert_eq!(24.0, p.borrow().style().font_size());
+        assert_eq!(EdgeSizes::new(2, 2, 2, 2), p.borrow().border());
+
+        let a = p.borrow().first_child().expect("a should exist");
+        assert_eq!(
+            TextDecoration::Underline,
+            a.borrow().style().text_decoration()
+        );
+
+        // Invalid shorthand declarations are ignored as a whole.
+        let h2 = p.borrow().next_sibling().expect("h2 should exist");
+        assert_eq!(
+            Color::rgb(255, 0, 0),
+            h2.borrow().style().background_color()
+        );
+        assert_eq!(EdgeSizes::new(0, 0, 0, 0), h2.borrow().margin());
+    }
+
+    #[test]
+    fn test_line_height() {
+        let html = r#"<html><head><style>
+        body { margin: 0; font-size: 20px; line-height: 2; }
+        p { margin: 0; font-weight: bold; }
+        </style></head><body><p>text</p><p>text</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(LayoutSize::new(VIEWPORT_WIDTH, 40), p.borrow().size());
+        let text = p.borrow().first_child().expect("text should exist");
+        assert_eq!(LayoutSize::new(40, 40), text.borrow().size());
+        let p = p.borrow().next_sibling().expect("p should exist");
+        assert_eq!(LayoutPoint::new(0, 40), p.borrow().point());
+
+        let texts: Vec<DisplayItem> = layout_view
+            .paint()
+            .into_iter()
+            .filter(|item| item.is_text())
+            .collect();
+        assert_eq!(2, texts.len());
+        match &texts[0] {
+            DisplayItem::Text {
+                font, layout_point, ..
+            } => {
+                assert_eq!(20, font.size());
+                assert!(font.is_bold());
+                // Half of the leading is above the glyphs.
+                assert_eq!(LayoutPoint::new(0, 10), *layout_point);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn text_items(layout_view: &LayoutView) -> Vec<(String, LayoutPoint)> {
+        layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } => Some((text, layout_point)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_text_align_and_indent() {
+        // Glyphs are 8px wide and lines are 20px high at the medium font size.
+        let html = r#"<html><head><style>
+        body { margin: 0; width: 100px; }
+        p { margin: 0; }
+        .right { text-align: right; }
+        .center { text-align: center; text-transform: uppercase; }
+        .indent { text-indent: 16px; }
+        .justify { text-align: justify; }
+        </style></head><body>
+        <p class="right">abc</p>
+        <p class="center">abcd</p>
+        <p class="indent">aaaa bbbb cccc</p>
+        <p class="justify">aa bb cc dd ee</p>
+        </body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        assert_eq!(
+            vec![
+                ("abc".to_string(), LayoutPoint::new(76, 2)),
+                ("ABCD".to_string(), LayoutPoint::new(34, 22)),
+                // The first line is indented, so "cccc" doesn't fit on it.
+                ("aaaa bbbb".to_string(), LayoutPoint::new(16, 42)),
+                ("cccc".to_string(), LayoutPoint::new(0, 62)),
+                // The remaining 12px of the line are distributed to the spaces. The last line is
+                // not justified.
+                ("aa ".to_string(), LayoutPoint::new(0, 82)),
+                ("bb ".to_string(), LayoutPoint::new(28, 82)),
+                ("cc ".to_string(), LayoutPoint::new(56, 82)),
+                ("dd".to_string(), LayoutPoint::new(84, 82)),
+                ("ee".to_string(), LayoutPoint::new(0, 102)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_white_space() {
+        let html = "<html><head><style>
+        body { margin: 0; width: 100px; }
+        pre { margin: 0; }
+        p { margin: 0; }
+        .nowrap { white-space: nowrap; }
+        .pre-line { white-space: pre-line; }
+        .spacing { letter-spacing: 2px; word-spacing: 4px; }
+        </style></head><body><pre>
+a  b
+\tc</pre><p class=\"nowrap\">aaaa bbbb cccc</p><p class=\"pre-line\">a   b
+  c</p><p class=\"spacing\">ab cd</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let root = layout_view.root().expect("root should exist");
+        let pre = root.borrow().first_child().expect("pre should exist");
+        assert_eq!(LayoutSize::new(100, 40), pre.borrow().size());
+        let nowrap = pre.borrow().next_sibling().expect("p should exist");
+        let text = nowrap.borrow().first_child().expect("text should exist");
+        // The text overflows instead of wrapping.
+        assert_eq!(LayoutSize::new(112, 20), text.borrow().size());
+
+        assert_eq!(
+            vec![
+                ("a  b".to_string(), LayoutPoint::new(0, 2)),
+                ("        c".to_string(), LayoutPoint::new(0, 22)),
+                ("aaaa bbbb cccc".to_string(), LayoutPoint::new(0, 42)),
+                ("a b".to_string(), LayoutPoint::new(0, 62)),
+                ("c".to_string(), LayoutPoint::new(0, 82)),
+                ("a".to_string(), LayoutPoint::new(0, 102)),
+                ("b".to_string(), LayoutPoint::new(10, 102)),
+                ("c".to_string(), LayoutPoint::new(34, 102)),
+                ("d".to_string(), LayoutPoint::new(44, 102)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_line_boxes_across_elements() {
+        let html = r#"<html><head><style>
+        body { margin: 0; width: 100px; }
+        p { margin: 0; }
+        a { border-width: 1px; border-style: solid; }
+        </style></head><body><p>aaaa <a>bbbb cccc</a> dddd</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        // 12 characters fit on a line, so the line is broken inside the link.
+        assert_eq!(
+            vec![
+                ("aaaa ".to_string(), LayoutPoint::new(0, 2)),
+                ("bbbb".to_string(), LayoutPoint::new(41, 2)),
+                ("cccc".to_string(), LayoutPoint::new(0, 22)),
+                (" dddd".to_string(), LayoutPoint::new(33, 22)),
+            ],
+            text_items(&layout_view)
+        );
+
+        let root = layout_view.root().expect("root should exist");
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(LayoutSize::new(100, 40), p.borrow().size());
+        let a = p
+            .borrow()
+            .first_child()
+            .expect("text should exist")
+            .borrow()
+            .next_sibling()
+            .expect("a should exist");
+        // The link has a fragment on each line. Its left border is on the first line and its
+        // right border is on the second one.
+        let point = a.borrow().point();
+        let fragments: Vec<(LayoutPoint, LayoutSize)> = a
+            .borrow()
+            .fragments()
+            .iter()
+            .map(|f| {
+                (
+                    LayoutPoint::new(point.x() + f.point().x(), point.y() + f.point().y()),
+                    f.size(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (LayoutPoint::new(40, 1), LayoutSize::new(33, 18)),
+                (LayoutPoint::new(0, 21), LayoutSize::new(33, 18)),
+            ],
+            fragments
+        );
+        let borders: Vec<DisplayItem> = layout_view
+            .paint()
+            .into_iter()
+            .filter(|item| matches!(item, DisplayItem::Border { .. }))
+            .collect();
+        match &borders[..] {
+            [DisplayItem::Border { sides: first, .. }, DisplayItem::Border { sides: last, .. }] => {
+                assert_eq!((1, 0), (first.left.width, first.right.width));
+                assert_eq!((0, 1), (last.left.width, last.right.width));
+            }
+            _ => panic!("expected two borders but got {:?}", borders),
+        }
+
+        // Fragments are hit-tested, so the space in the bounding box of the link that is not on
+        // its fragments belongs to the text after it.
+        let hit = layout_view
+            .find_node_by_position((60, 25))
+            .expect("text should be hit");
+        assert_eq!(
+            NodeKind::Text(" dddd".to_string()),
+            hit.borrow().node_kind()
+        );
+        assert!(
+            hit.borrow()
+                .parent()
+                .upgrade()
+                .expect("parent should exist")
+                == p
+        );
+        let hit = layout_view
+            .find_node_by_position((50, 5))
+            .expect("text should be hit");
+        assert!(
+            hit.borrow()
+                .parent()
+                .upgrade()
+                .expect("parent should exist")
+                == a
+        );
+    }
+
+    #[test]
+    fn test_vertical_align() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .big { font-size: 32px; }
+        .sup { vertical-align: super; }
+        .raised { vertical-align: 10px; }
+        </style></head><body><p>x<a class="big">y</a></p><p>x<a class="sup">y</a></p><p>x<a class="raised">y</a></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        // The baselines are aligned, and the line box grows to contain the larger text.
+        // Glyphs have their baseline at 4/5 of the font size.
+        assert_eq!(
+            vec![
+                ("x".to_string(), LayoutPoint::new(0, 17)),
+                ("y".to_string(), LayoutPoint::new(8, 4)),
+                ("x".to_string(), LayoutPoint::new(0, 47)),
+                ("y".to_string(), LayoutPoint::new(8, 42)),
+                ("x".to_string(), LayoutPoint::new(0, 77)),
+                ("y".to_string(), LayoutPoint::new(8, 67)),
+            ],
+            text_items(&layout_view)
+        );
+
+        let root = layout_view.root().expect("root should exist");
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(40, p.borrow().size().height());
+        let p = p.borrow().next_sibling().expect("p should exist");
+        assert_eq!(25, p.borrow().size().height());
+        let p = p.borrow().next_sibling().expect("p should exist");
+        assert_eq!(30, p.borrow().size().height());
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        let html = "<html><head><style>
+        body { margin: 0; width: 60px; }
+        p { margin: 0; }
+        </style></head><body><p>\u{65E5}\u{672C}\u{8A9E} cafe\u{301}s</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        // The kanji are twice as wide as Latin letters, and the accent is combined with the "e",
+        // so the line breaks after the kanji.
+        assert_eq!(
+            vec![
+                (
+                    "\u{65E5}\u{672C}\u{8A9E}".to_string(),
+                    LayoutPoint::new(0, 2)
+                ),
+                ("cafe\u{301}s".to_string(), LayoutPoint::new(0, 22)),
+            ],
+            text_items(&layout_view)
+        );
+        let root = layout_view.root().expect("root should exist");
+        let text = root
+            .borrow()
+            .first_child()
+            .expect("p should exist")
+            .borrow()
+            .first_child()
+            .expect("text should exist");
+        let sizes: Vec<LayoutSize> = text.borrow().fragments().iter().map(|f| f.size()).collect();
+        assert_eq!(
+            vec![LayoutSize::new(48, 20), LayoutSize::new(40, 20)],
+            sizes
+        );
+    }
+
+    #[test]
+    fn test_font_metrics() {
+        /// A proportional font whose "i" is half as wide as other glyphs, and whose glyphs are
+        /// as tall as the line.
+        #[derive(Debug)]
+        struct ProportionalFontMetrics;
+
+        impl FontMetrics for ProportionalFontMetrics {
+            fn advance(&self, cluster: &str, font: &Font) -> i64 {
+                if cluster == "i" {
+                    font.size() / 4
+                } else {
+                    font.size() / 2
+                }
+            }
+
+            fn ascent(&self, font: &Font) -> i64 {
+                font.size()
+            }
+
+            fn descent(&self, font: &Font) -> i64 {
+                font.size() / 4
+            }
+        }
+
+        let html = r#"<html><head><style>
+        body { margin: 0; width: 100px; }
+        p { margin: 0; text-align: right; }
+        </style></head><body><p>iii mm</p></body></html>"#
+            .to_string();
+        let layout_view =
+            create_layout_view_with_font_metrics(html, Rc::new(ProportionalFontMetrics));
+
+        // "iii mm" is 4 * 3 + 8 + 8 * 2 px wide, and there is no half-leading.
+        assert_eq!(
+            vec![("iii mm".to_string(), LayoutPoint::new(64, 0))],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_cjk_line_breaking() {
+        let html = "<html><head><style>
+        body { margin: 0; width: 64px; }
+        p { margin: 0; }
+        </style></head><body><p>\u{65E5}\u{672C}\u{8A9E}\u{306E}\u{6587}\u{7AE0}\u{3067}\u{3059}\u{3002}\u{6B21}</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        // Four ideographs fit on a line. The ideographic full stop doesn't start a line, so the
+        // character before it is carried over with it.
+        assert_eq!(
+            vec![
+                (
+                    "\u{65E5}\u{672C}\u{8A9E}\u{306E}".to_string(),
+                    LayoutPoint::new(0, 2)
+                ),
+                (
+                    "\u{6587}\u{7AE0}\u{3067}".to_string(),
+                    LayoutPoint::new(0, 22)
+                ),
+                (
+                    "\u{3059}\u{3002}\u{6B21}".to_string(),
+                    LayoutPoint::new(0, 42)
+                ),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_overflow_wrap() {
+        let html = r#"<html><head><style>
+        body { margin: 0; width: 64px; }
+        p { margin: 0; }
+        .anywhere { overflow-wrap: anywhere; }
+        </style></head><body><p class="anywhere">aa bbbbbbbbbbbb</p><p>cccccccccccc</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        // The long word is broken only after it's moved to a line of its own. Without
+        // overflow-wrap, it overflows the line.
+        assert_eq!(
+            vec![
+                ("aa".to_string(), LayoutPoint::new(0, 2)),
+                ("bbbbbbbb".to_string(), LayoutPoint::new(0, 22)),
+                ("bbbb".to_string(), LayoutPoint::new(0, 42)),
+                ("cccccccccccc".to_string(), LayoutPoint::new(0, 62)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_viewport() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { width: 50%; height: 10vh; }
+        @media (max-width: 400px) { p { width: 100%; } }
+        @media (min-resolution: 2dppx) { p { height: 20vh; } }
+        </style></head><body><p></p></body></html>"#
+            .to_string();
+
+        for (width, height, expected) in [
+            (800, 600, LayoutSize::new(400, 60)),
+            (600, 400, LayoutSize::new(300, 40)),
+            (320, 480, LayoutSize::new(320, 48)),
+        ] {
+            let layout_view =
+                create_layout_view_with_viewport(html.clone(), Device::new(width, height));
+            assert_eq!(Device::new(width, height), layout_view.viewport());
+            let root = layout_view.root().expect("root should exist");
+            assert_eq!(width, root.borrow().size().width());
+            let p = root.borrow().first_child().expect("p should exist");
+            assert_eq!(expected, p.borrow().size());
+        }
+
+        let mut device = Device::new(800, 600);
+        device.set_device_pixel_ratio(2.0);
+        let layout_view = create_layout_view_with_viewport(html, device);
+        let root = layout_view.root().expect("root should exist");
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(LayoutSize::new(400, 120), p.borrow().size());
+    }
+
+    #[test]
+    fn test_scroll_container() {
+        let html = r#"<html><head><style>
+        body { margin: 0; height: 100px; padding: 10px; border: solid 2px; overflow: scroll; }
+        p { margin: 0; height: 300px; }
+        </style></head><body><p>a</p></body></html>"#
+            .to_string();
+        let mut layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+        assert_eq!(
+            LayoutSize::new(VIEWPORT_WIDTH - 4, 120),
+            root.borrow().client_size()
+        );
+        // The padding is a part of the scrollable overflow area.
+        assert_eq!(
+            LayoutSize::new(VIEWPORT_WIDTH - 4, 320),
+            root.borrow().scrollable_overflow()
+        );
+
+        let items = layout_view.paint();
+        let clip = DisplayItem::PushClip {
+            layout_point: LayoutPoint::new(2, 2),
+            layout_size: LayoutSize::new(VIEWPORT_WIDTH - 4, 120),
+        };
+        let push = items.iter().position(|item| *item == clip);
+        let pop = items.iter().position(|item| *item == DisplayItem::PopClip);
+        let text = items.iter().position(|item| item.is_text());
+        assert!(push < text && text < pop);
+        // overflow: scroll always has scrollbars on both axes.
+        assert_eq!(
+            2,
+            items
+                .iter()
+                .filter(|item| matches!(item, DisplayItem::Scrollbar { .. }))
+                .count()
+        );
+
+        assert!(layout_view.scroll_by((10, 10), 0, 500));
+        assert_eq!(LayoutPoint::new(0, 200), root.borrow().scroll_offset());
+        let p = root.borrow().first_child().expect("p should exist");
+        assert_eq!(LayoutPoint::new(12, -188), p.borrow().point());
+        // The end of the content is reached.
+        assert!(!layout_view.scroll_by((10, 10), 0, 10));
+        // Nothing is scrolled outside of the scroll container.
+        assert!(!layout_view.scroll_by((10, 200), 0, -10));
+    }
+
+    #[test]
+    fn test_relative_and_absolute_position() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .relative { position: relative; top: 10px; left: 20px; }
+        .container { position: relative; padding: 10px; }
+        .absolute { position: absolute; top: 0; right: 0; }
+        .stretch { position: absolute; top: 5px; bottom: 5px; left: 5px; right: 5px; }
+        </style></head><body><p class="relative">aa</p><p class="container"><a class="absolute">bb</a>cc</p><p class="stretch"></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // A relatively positioned box doesn't move the following boxes, and an absolutely
+        // positioned box is shrunk to fit in its containing block.
+        assert_eq!(
+            vec![
+                ("aa".to_string(), LayoutPoint::new(20, 12)),
+                ("cc".to_string(), LayoutPoint::new(10, 32)),
+                ("bb".to_string(), LayoutPoint::new(VIEWPORT_WIDTH - 16, 22)),
+            ],
+            text_items(&layout_view)
+        );
+
+        // An absolutely positioned box without a positioned ancestor is placed in the initial
+        // containing block.
+        let root = layout_view.root().expect("root should exist");
+        let stretch = root
+            .borrow()
+            .first_child()
+            .and_then(|p| p.borrow().next_sibling())
+            .and_then(|p| p.borrow().next_sibling())
+            .expect("p should exist");
+        assert_eq!(LayoutPoint::new(5, 5), stretch.borrow().point());
+        assert_eq!(
+            LayoutSize::new(VIEWPORT_WIDTH - 10, VIEWPORT_HEIGHT - 10),
+            stretch.borrow().size()
+        );
+        // Out-of-flow boxes don't make the body taller.
+        assert_eq!(60, root.borrow().size().height());
+    }
+
+    #[test]
+    fn test_fixed_and_sticky_position() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .sticky { position: sticky; top: 0; }
+        .fixed { position: fixed; bottom: 0; left: 0; }
+        .tall { height: 1000px; }
+        </style></head><body><p class="sticky">s</p><p class="fixed">f</p><p class="tall"></p></body></html>"#
+            .to_string();
+        let mut layout_view = create_layout_view(html);
+        assert_eq!(
+            vec![
+                ("s".to_string(), LayoutPoint::new(0, 2)),
+                ("f".to_string(), LayoutPoint::new(0, VIEWPORT_HEIGHT - 18)),
+            ],
+            text_items(&layout_view)
+        );
+
+        layout_view.set_scroll_position(LayoutPoint::new(0, 100));
+        assert_eq!(
+            vec![
+                ("s".to_string(), LayoutPoint::new(0, 102)),
+                ("f".to_string(), LayoutPoint::new(0, VIEWPORT_HEIGHT + 82)),
+            ],
+            text_items(&layout_view)
+        );
+
+        // A sticky box stays in its containing block.
+        layout_view.set_scroll_position(LayoutPoint::new(0, 1010));
+        assert_eq!(
+            ("s".to_string(), LayoutPoint::new(0, 1002)),
+            text_items(&layout_view)[0]
+        );
+    }
+
+    #[test]
+    fn test_z_index() {
+        let html = r#"<html><head><style>
+        .front { position: relative; z-index: 2; }
+        .middle { position: relative; }
+        .back { position: relative; z-index: -1; }
+        </style></head><body><p class="front">a</p><p class="middle">b</p><p class="back">c</p><p>d</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let texts: Vec<String> = text_items(&layout_view)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect();
+        // Negative z-index, in flow, z-index: auto and positive z-index, in that order.
+        assert_eq!(vec!["c", "d", "b", "a"], texts);
+    }
+
+    #[test]
+    fn test_painting_order() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; height: 20px; }
+        .float { float: left; width: 8px; background-color: blue; }
+        .later { background-color: red; }
+        </style></head><body><p>a</p><p class="float">f</p><p class="later">b</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let red = Color::from_name("red").unwrap();
+        let items: Vec<String> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Rect { color, .. } if color == red => Some("red".to_string()),
+                DisplayItem::Rect { .. } => Some("blue".to_string()),
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        // The backgrounds of blocks, then floats, and then the text of all blocks. So the
+        // background of a later block is below the text of an earlier one.
+        assert_eq!(vec!["red", "blue", "f", "a", "b"], items);
+    }
+
+    #[test]
+    fn test_paint_effects() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; height: 20px; }
+        .fade { opacity: 0.5; }
+        .turn { transform: translateX(10px) rotate(90deg); }
+        .round { border-radius: 4px 50%; background-color: red; }
+        </style></head><body>
+        <p class="fade">a</p><p class="turn">b</p><p class="round"></p>
+        </body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let items = layout_view.paint();
+        let labels: Vec<String> = items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::RoundedRect { .. } => Some("rounded".to_string()),
+                DisplayItem::PushOpacity { .. } => Some("opacity".to_string()),
+                DisplayItem::PopOpacity => Some("/opacity".to_string()),
+                DisplayItem::PushTransform { .. } => Some("transform".to_string()),
+                DisplayItem::PopTransform => Some("/transform".to_string()),
+                DisplayItem::Text { text, .. } => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        // The rounded box is in flow, and the boxes with effects are painted over it as layers.
+        assert_eq!(
+            vec![
+                "rounded",
+                "opacity",
+                "a",
+                "/opacity",
+                "transform",
+                "b",
+                "/transform"
+            ],
+            labels
+        );
+        assert!(items.contains(&DisplayItem::PushOpacity { opacity: 0.5 }));
+        // The transform is about the center of the box.
+        assert!(items.contains(&DisplayItem::PushTransform {
+            transform: Transform::translate(10.0, 0.0).multiply(&Transform::rotate(90.0)),
+            origin: LayoutPoint::new(VIEWPORT_WIDTH / 2, 30),
+        }));
+        // Percentages of radii refer to the width and the height of the box.
+        assert!(items.contains(&DisplayItem::RoundedRect {
+            color: Color::from_name("red").unwrap(),
+            radii: CornerRadii {
+                top_left: (4, 4),
+                top_right: (300, 10),
+                bottom_right: (4, 4),
+                bottom_left: (300, 10),
+            },
+            layout_point: LayoutPoint::new(0, 40),
+            layout_size: LayoutSize::new(VIEWPORT_WIDTH, 20),
+        }));
+    }
+
+    #[test]
+    fn test_float_beside_text() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; width: 200px; }
+        .image { float: left; width: 100px; height: 50px; margin-right: 8px; }
+        </style></head><body><p><a class="image"></a>aaaa bbbb cccc dddd eeee ffff gggg hhhh</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // The lines next to the float are shortened, and the ones below it are not.
+        assert_eq!(
+            vec![
+                ("aaaa bbbb".to_string(), LayoutPoint::new(108, 2)),
+                ("cccc dddd".to_string(), LayoutPoint::new(108, 22)),
+                ("eeee ffff".to_string(), LayoutPoint::new(108, 42)),
+                ("gggg hhhh".to_string(), LayoutPoint::new(0, 62)),
+            ],
+            text_items(&layout_view)
+        );
+        let root = layout_view.root().expect("root should exist");
+        let p = root.borrow().first_child().expect("p should exist");
+        let image = p.borrow().first_child().expect("a should exist");
+        assert_eq!(LayoutPoint::new(0, 0), image.borrow().point());
+        assert_eq!(LayoutSize::new(100, 50), image.borrow().size());
+    }
+
+    #[test]
+    fn test_float_and_clear() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .sidebar { float: right; height: 100px; }
+        .footer { clear: both; }
+        </style></head><body><p class="sidebar">side</p><p>main</p><p class="footer">foot</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // A float without a width shrinks to fit its content.
+        assert_eq!(
+            vec![
+                ("side".to_string(), LayoutPoint::new(VIEWPORT_WIDTH - 32, 2)),
+                ("main".to_string(), LayoutPoint::new(0, 2)),
+                ("foot".to_string(), LayoutPoint::new(0, 102)),
+            ],
+            text_items(&layout_view)
+        );
+        let root = layout_view.root().expect("root should exist");
+        assert_eq!(120, root.borrow().size().height());
+    }
+
+    #[test]
+    fn test_block_formatting_context() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .float { float: left; width: 100px; height: 50px; }
+        .root { display: flow-root; }
+        .hidden { overflow: hidden; }
+        </style></head><body><p class="root"><a class="float"></a></p><p><a class="float"></a></p><p class="hidden">text</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+        let flow_root = root.borrow().first_child().expect("p should exist");
+        let p = flow_root.borrow().next_sibling().expect("p should exist");
+        let hidden = p.borrow().next_sibling().expect("p should exist");
+
+        // A block formatting context root contains its floats, and other boxes don't.
+        assert_eq!(50, flow_root.borrow().size().height());
+        assert_eq!(0, p.borrow().size().height());
+        // A block formatting context root is made narrower next to the floats outside of it.
+        assert_eq!(LayoutPoint::new(100, 50), hidden.borrow().point());
+        assert_eq!(
+            LayoutSize::new(VIEWPORT_WIDTH - 100, 20),
+            hidden.borrow().size()
+        );
+        assert_eq!(
+            vec![("text".to_string(), LayoutPoint::new(100, 52))],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_flex_row() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .nav { display: flex; justify-content: space-between; }
+        .anonymous { display: flex; column-gap: 8px; }
+        </style></head><body><p class="nav"><a>home</a><a>about</a><a>blog</a></p><p class="anonymous">text <a>link</a></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // The free space is put between the items. A run of text is an anonymous flex item.
+        assert_eq!(
+            vec![
+                ("home".to_string(), LayoutPoint::new(0, 2)),
+                ("about".to_string(), LayoutPoint::new(280, 2)),
+                ("blog".to_string(), LayoutPoint::new(568, 2)),
+                ("text".to_string(), LayoutPoint::new(0, 22)),
+                ("link".to_string(), LayoutPoint::new(40, 22)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_flex_grow_and_shrink() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; display: flex; height: 50px; }
+        .one { flex: 1; }
+        .two { flex: 2; }
+        .wide { width: 400px; }
+        .rigid { width: 400px; flex-shrink: 0; }
+        </style></head><body><p><a class="one">a</a><a class="two">b</a></p><p><a class="wide">c</a><a class="wide">d</a></p><p><a class="rigid">e</a><a class="wide">f</a></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let root = layout_view.root().expect("root should exist");
+        let sizes = |p: &Rc<RefCell<LayoutObject>>| {
+            let first = p.borrow().first_child().expect("a should exist");
+            let second = first.borrow().next_sibling().expect("a should exist");
+            let sizes = [first, second].map(|a| (a.borrow().point().x(), a.borrow().size()));
+            sizes
+        };
+        let grow = root.borrow().first_child().expect("p should exist");
+        let shrink = grow.borrow().next_sibling().expect("p should exist");
+        let rigid = shrink.borrow().next_sibling().expect("p should exist");
+
+        // The free space is distributed by the flex grow factors, and the items are stretched
+        // to the height of the line.
+        assert_eq!(
+            [
+                (0, LayoutSize::new(200, 50)),
+                (200, LayoutSize::new(400, 50))
+            ],
+            sizes(&grow)
+        );
+        // The overflow is taken from the items by their flex shrink factors.
+        assert_eq!(
+            [
+                (0, LayoutSize::new(300, 50)),
+                (300, LayoutSize::new(300, 50))
+            ],
+            sizes(&shrink)
+        );
+        assert_eq!(
+            [
+                (0, LayoutSize::new(400, 50)),
+                (400, LayoutSize::new(200, 50))
+            ],
+            sizes(&rigid)
+        );
+    }
+
+    #[test]
+    fn test_flex_wrap_gap_and_order() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; display: flex; flex-wrap: wrap; gap: 10px 20px; width: 200px; }
+        a { width: 80px; }
+        .first { order: -1; }
+        .reverse { flex-flow: row-reverse wrap-reverse; }
+        </style></head><body><p><a>a</a><a>b</a><a class="first">c</a></p><p class="reverse"><a>d</a><a>e</a><a>f</a></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // The items that don't fit go on the next line, and the lines are separated by the row
+        // gap. A reversed container is laid out from the bottom right.
+        assert_eq!(
+            vec![
+                ("a".to_string(), LayoutPoint::new(100, 2)),
+                ("b".to_string(), LayoutPoint::new(0, 32)),
+                ("c".to_string(), LayoutPoint::new(0, 2)),
+                ("d".to_string(), LayoutPoint::new(120, 82)),
+                ("e".to_string(), LayoutPoint::new(20, 82)),
+                ("f".to_string(), LayoutPoint::new(120, 52)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_flex_column_and_alignment() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .column { display: flex; flex-direction: column; height: 100px; justify-content: flex-end; align-items: center; }
+        .end { align-self: flex-end; }
+        .auto { display: flex; height: 60px; }
+        .centered { margin: auto; }
+        .inline { display: inline-flex; }
+        </style></head><body><p class="column"><a>one</a><a class="end">two</a></p><p class="auto"><a class="centered">mid</a></p><p>x<a class="inline">flex</a>y</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // Auto margins take the free space before justify-content and align-items do. An inline
+        // flex container shrinks to fit on the line.
+        assert_eq!(
+            vec![
+                ("one".to_string(), LayoutPoint::new(288, 62)),
+                ("two".to_string(), LayoutPoint::new(576, 82)),
+                ("mid".to_string(), LayoutPoint::new(288, 122)),
+                ("x".to_string(), LayoutPoint::new(0, 168)),
+                ("flex".to_string(), LayoutPoint::new(8, 162)),
+                ("y".to_string(), LayoutPoint::new(40, 168)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_grid_tracks_and_placement() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .grid { display: grid; grid-template-columns: 100px 1fr 2fr; column-gap: 10px; row-gap: 5px; }
+        .wide { grid-column: 2 / span 2; }
+        .last { grid-row: 3; grid-column: -2; }
+        </style></head><body><p class="grid"><a>a</a><a>b</a><a>c</a><a class="wide">d</a><a class="last">e</a></p><p>after</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // The leftover space is shared by the fr columns. An item with a column is put in the
+        // next row that has room for it, and the implicit rows are sized to their items.
+        assert_eq!(
+            vec![
+                ("a".to_string(), LayoutPoint::new(0, 2)),
+                ("b".to_string(), LayoutPoint::new(110, 2)),
+                ("c".to_string(), LayoutPoint::new(280, 2)),
+                ("d".to_string(), LayoutPoint::new(110, 27)),
+                ("e".to_string(), LayoutPoint::new(280, 52)),
+                ("after".to_string(), LayoutPoint::new(0, 72)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_grid_named_areas() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .dashboard { display: grid; height: 200px; grid-template: "head head" 40px "nav main" 1fr / 100px 1fr; }
+        .head { grid-area: head; }
+        .nav { grid-area: nav; align-self: end; }
+        .main { grid-area: main; }
+        </style></head><body><p class="dashboard"><a class="main">main</a><a class="nav">nav</a><a class="head">head</a></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // The items are placed in their areas regardless of the document order, and an item
+        // that is not stretched is aligned in its area.
+        assert_eq!(
+            vec![
+                ("main".to_string(), LayoutPoint::new(100, 42)),
+                ("nav".to_string(), LayoutPoint::new(0, 182)),
+                ("head".to_string(), LayoutPoint::new(0, 2)),
+            ],
+            text_items(&layout_view)
+        );
+        let root = layout_view.root().expect("root should exist");
+        let dashboard = root.borrow().first_child().expect("p should exist");
+        let main = dashboard.borrow().first_child().expect("a should exist");
+        assert_eq!(LayoutSize::new(600, 200), dashboard.borrow().size());
+        // The item is stretched to its area.
+        assert_eq!(LayoutSize::new(500, 160), main.borrow().size());
+    }
+
+    #[test]
+    fn test_grid_auto_repeat_and_dense() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .fill { display: grid; gap: 10px; grid-template-columns: repeat(auto-fill, minmax(120px, 1fr)); }
+        .fit { display: grid; gap: 10px; grid-template-columns: repeat(auto-fit, minmax(120px, 1fr)); }
+        .sparse { display: grid; grid-template-columns: repeat(3, 50px); }
+        .dense { display: grid; grid-template-columns: repeat(3, 50px); grid-auto-flow: dense; }
+        .span { grid-column: span 2; }
+        </style></head><body><p class="fill"><a>a</a><a>b</a><a>c</a><a>d</a><a>e</a></p><p class="fit"><a>f</a><a>g</a></p><p class="sparse"><a class="span">h</a><a class="span">i</a><a>j</a></p><p class="dense"><a class="span">k</a><a class="span">l</a><a>m</a></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // auto-fill repeats the columns as many times as they fit, and auto-fit collapses the
+        // empty ones. Dense packing fills the hole left by an item that spans two columns.
+        assert_eq!(
+            vec![
+                ("a".to_string(), LayoutPoint::new(0, 2)),
+                ("b".to_string(), LayoutPoint::new(153, 2)),
+                ("c".to_string(), LayoutPoint::new(305, 2)),
+                ("d".to_string(), LayoutPoint::new(458, 2)),
+                ("e".to_string(), LayoutPoint::new(0, 32)),
+                ("f".to_string(), LayoutPoint::new(0, 52)),
+                ("g".to_string(), LayoutPoint::new(305, 52)),
+                ("h".to_string(), LayoutPoint::new(0, 72)),
+                ("i".to_string(), LayoutPoint::new(0, 92)),
+                ("j".to_string(), LayoutPoint::new(100, 92)),
+                ("k".to_string(), LayoutPoint::new(0, 112)),
+                ("l".to_string(), LayoutPoint::new(0, 132)),
+                ("m".to_string(), LayoutPoint::new(100, 112)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_inline_grid() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        .grid { display: inline-grid; grid-template-columns: 30px auto; column-gap: 4px; }
+        </style></head><body><p>ab<a class="grid">xyz</a>cd</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // An inline grid is as wide as its columns under a max-content constraint, and it sits
+        // on the baseline with the bottom of its margin box.
+        assert_eq!(
+            vec![
+                ("ab".to_string(), LayoutPoint::new(0, 8)),
+                ("xyz".to_string(), LayoutPoint::new(16, 2)),
+                ("cd".to_string(), LayoutPoint::new(50, 8)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_list_markers() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        ul { margin: 0; }
+        ol { margin: 0; }
+        </style></head><body><ul><li>a</li><li>b</li></ul><ol start="3"><li>x</li><li value="10">y</li><li>z</li></ol></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        // Outside markers end at the start of the list item and sit on its first baseline.
+        assert_eq!(
+            vec![
+                ("a".to_string(), LayoutPoint::new(40, 2)),
+                ("b".to_string(), LayoutPoint::new(40, 22)),
+                ("3. ".to_string(), LayoutPoint::new(16, 42)),
+                ("x".to_string(), LayoutPoint::new(40, 42)),
+                ("10. ".to_string(), LayoutPoint::new(8, 62)),
+                ("y".to_string(), LayoutPoint::new(40, 62)),
+                ("11. ".to_string(), LayoutPoint::new(8, 82)),
+                ("z".to_string(), LayoutPoint::new(40, 82)),
+            ],
+            text_items(&layout_view)
+        );
+        let bullets: Vec<(BulletShape, LayoutPoint, LayoutSize)> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Bullet {
+                    shape,
+                    layout_point,
+                    layout_size,
+                    ..
+                } => Some((shape, layout_point, layout_size)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    BulletShape::Disc,
+                    LayoutPoint::new(26, 7),
+                    LayoutSize::new(5, 5)
+                ),
+                (
+                    BulletShape::Disc,
+                    LayoutPoint::new(26, 27),
+                    LayoutSize::new(5, 5)
+                ),
+            ],
+            bullets
+        );
+    }
+
+    #[test]
+    fn test_list_style_and_attributes() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        ul { margin: 0; }
+        ol { margin: 0; }
+        .inside { list-style: inside upper-roman; }
+        .none { list-style-type: none; }
+        .hidden { display: none; }
+        </style></head><body><ol reversed><li>a</li><li>b</li></ol><ol type="a"><li>c</li></ol><ul class="inside"><li>d</li></ul><ul class="none"><li>e</li></ul><ol><li>f</li><li class="hidden">g</li><li>h</li></ol></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        assert_eq!(
+            vec![
+                ("2. ".to_string(), LayoutPoint::new(16, 2)),
+                ("a".to_string(), LayoutPoint::new(40, 2)),
+                ("1. ".to_string(), LayoutPoint::new(16, 22)),
+                ("b".to_string(), LayoutPoint::new(40, 22)),
+                ("a. ".to_string(), LayoutPoint::new(16, 42)),
+                ("c".to_string(), LayoutPoint::new(40, 42)),
+                // An inside marker is the first inline box of the list item.
+                ("I. ".to_string(), LayoutPoint::new(40, 62)),
+                ("d".to_string(), LayoutPoint::new(64, 62)),
+                ("e".to_string(), LayoutPoint::new(40, 82)),
+                ("1. ".to_string(), LayoutPoint::new(16, 102)),
+                ("f".to_string(), LayoutPoint::new(40, 102)),
+                ("2. ".to_string(), LayoutPoint::new(16, 122)),
+                ("h".to_string(), LayoutPoint::new(40, 122)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_counters() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        ol { margin: 0; counter-reset: item; }
+        li { counter-increment: item; }
+        li::marker { content: counters(item, ".") " "; }
+        </style></head><body><ol><li>a<ol><li>b</li><li>c</li></ol></li><li>d</li></ol></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        assert_eq!(
+            vec![
+                ("1 ".to_string(), LayoutPoint::new(24, 2)),
+                ("a".to_string(), LayoutPoint::new(40, 2)),
+                ("1.1 ".to_string(), LayoutPoint::new(48, 22)),
+                ("b".to_string(), LayoutPoint::new(80, 22)),
+                ("1.2 ".to_string(), LayoutPoint::new(48, 42)),
+                ("c".to_string(), LayoutPoint::new(80, 42)),
+                ("2 ".to_string(), LayoutPoint::new(24, 62)),
+                ("d".to_string(), LayoutPoint::new(40, 62)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_before_and_after() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        a::before { content: "[" attr(href) "]"; }
+        a::after { content: "\2192"; }
+        .q::before { content: open-quote; }
+        .q::after { content: close-quote; }
+        </style></head><body><p><a href="x">link</a> <a class="q">hi</a></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        assert_eq!(
+            vec![
+                ("[x]".to_string(), LayoutPoint::new(0, 2)),
+                ("link".to_string(), LayoutPoint::new(24, 2)),
+                ("\u{2192}".to_string(), LayoutPoint::new(56, 2)),
+                ("\u{201C}".to_string(), LayoutPoint::new(72, 2)),
+                ("hi".to_string(), LayoutPoint::new(80, 2)),
+                ("\u{201D}".to_string(), LayoutPoint::new(96, 2)),
+            ],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_generated_content_boxes_quotes_and_counters() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; quotes: "[" "]" "(" ")"; }
+        .s { counter-increment: section; }
+        .s::before { content: counter(section, upper-roman) ". "; }
+        .n::before { content: open-quote; }
+        .n::after { content: close-quote; display: block; }
+        a::before { content: open-quote; }
+        a::after { content: close-quote; }
+        .none::before { content: none; }
+        </style></head><body><p class="s">A</p><p class="s">B</p><p class="n">x<a>y</a>z</p><p><a class="none">e</a></p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        assert_eq!(
+            vec![
+                ("I. ".to_string(), LayoutPoint::new(0, 2)),
+                ("A".to_string(), LayoutPoint::new(24, 2)),
+                ("II. ".to_string(), LayoutPoint::new(0, 22)),
+                ("B".to_string(), LayoutPoint::new(32, 22)),
+                ("[".to_string(), LayoutPoint::new(0, 42)),
+                ("x".to_string(), LayoutPoint::new(8, 42)),
+                ("(".to_string(), LayoutPoint::new(16, 42)),
+                ("y".to_string(), LayoutPoint::new(24, 42)),
+                (")".to_string(), LayoutPoint::new(32, 42)),
+                ("z".to_string(), LayoutPoint::new(40, 42)),
+                // The quote is closed in a block box.
+                ("]".to_string(), LayoutPoint::new(0, 62)),
+                ("e".to_string(), LayoutPoint::new(0, 82)),
+            ],
+            text_items(&layout_view)
+        );
+
+        // The ::before with content: none generates no box. The close quote of the ::after has
+        // no open quote, so its box is empty.
+        let p = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .and_then(|p| {
+                let mut p = Some(p);
+                for _ in 0..3 {
+                    p = p.and_then(|p| p.borrow().next_sibling());
+                }
+                p
+            })
+            .expect("the last p should exist");
+        let a = p.borrow().first_child().expect("a should exist");
+        let text = a.borrow().first_child().expect("text should exist");
+        assert_eq!(LayoutObjectKind::Text, text.borrow().kind());
+        let after = text.borrow().next_sibling().expect("::after should exist");
+        assert_eq!(LayoutObjectKind::Inline, after.borrow().kind());
+        assert!(after.borrow().first_child().is_none());
+    }
+
+    #[test]
+    fn test_first_letter() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; }
+        p::first-letter { color: red; }
+        .drop::first-letter { float: left; font-size: 32px; line-height: 40px; }
+        </style></head><body><p> "The rest</p><p class="drop">Dropped cap</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let items = text_items(&layout_view);
+        assert_eq!(
+            vec![
+                // The floated letter is painted before the lines, and is next to two of them.
+                ("D".to_string(), LayoutPoint::new(0, 24)),
+                ("\"T".to_string(), LayoutPoint::new(0, 2)),
+                ("he rest".to_string(), LayoutPoint::new(16, 2)),
+                ("ropped cap".to_string(), LayoutPoint::new(16, 22)),
+            ],
+            items
+        );
+        let colors: Vec<Color> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { color, .. } => Some(color),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(Color::from_name("red").unwrap(), colors[1]);
+        assert_eq!(Color::black(), colors[2]);
+    }
+
+    #[test]
+    fn test_first_line() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        p { margin: 0; width: 80px; }
+        p::first-line { color: red; font-size: 32px; line-height: 40px; }
+        </style></head><body><p>aa <a>bb</a> cc</p><p>dd</p></body></html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let items: Vec<(String, LayoutPoint, i64, Color)> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text,
+                    font,
+                    color,
+                    layout_point,
+                    ..
+                } => Some((text, layout_point, font.size(), color)),
+                _ => None,
+            })
+            .collect();
+        let red = Color::from_name("red").unwrap();
+        assert_eq!(
+            vec![
+                // The first line is laid out in the ::first-line style, so only two words fit.
+                ("aa ".to_string(), LayoutPoint::new(0, 4), 32, red.clone()),
+                ("bb".to_string(), LayoutPoint::new(48, 4), 32, red.clone()),
+                (
+                    "cc".to_string(),
+                    LayoutPoint::new(0, 42),
+                    16,
+                    Color::black()
+                ),
+                ("dd".to_string(), LayoutPoint::new(0, 64), 32, red),
+            ],
+            items
+        );
+    }
+
+    #[test]
+    fn test_img() {
+        let html = r#"<html><head><style>
+        body { margin: 0; }
+        .tall { height: 20px; }
+        .block { display: block; width: 50%; margin: 0 auto; }
+        .float { float: left; padding: 1px; }
+        .bg { width: 40px; height: 40px; border: 2px solid black; background-image: url(a.png), url(none.png); }
+        </style></head><body><img src="a.png"><img class="tall" src="a.png"><img src="a.png" class="block"><p><img class="float" src="a.png" width="2">x</p><p class="bg"></p><img src="none.png" alt="alt text"></body></html>"#
+            .to_string();
+        let mut images = ImageStore::new();
+        images.insert(
+            "a.png".to_string(),
+            Some(Image::new(8, 4, vec![0xff00ff00; 32]).unwrap()),
+        );
+        images.insert("none.png".to_string(), None);
+        let layout_view = create_layout_view_with_images(
+            html,
+            Device::new(VIEWPORT_WIDTH, VIEWPORT_HEIGHT),
+            Rc::new(MonospaceFontMetrics),
+            &images,
+        );
+        assert_eq!(
+            vec!["a.png", "a.png", "a.png", "a.png", "a.png", "none.png", "none.png"],
+            layout_view.image_urls()
+        );
+
+        let items = layout_view.paint();
+        let imgs: Vec<(LayoutPoint, LayoutSize)> = items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Img {
+                    layout_point,
+                    layout_size,
+                    ..
+                } => Some((*layout_point, *layout_size)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                // A float shrinks to the image. Floats are painted before the content of lines.
+                (LayoutPoint::new(1, 193), LayoutSize::new(2, 1)),
+                // Inline images are on the line at their intrinsic size, and an image with only
+                // a height keeps its ratio.
+                (LayoutPoint::new(0, 16), LayoutSize::new(8, 4)),
+                (LayoutPoint::new(8, 0), LayoutSize::new(40, 20)),
+                // A block-level image fills half of the body and is centered.
+                (LayoutPoint::new(150, 26), LayoutSize::new(300, 150)),
+            ],
+            imgs
+        );
+        // Only the loaded layer of the background is painted, tiled from the padding box.
+        assert!(items.contains(&DisplayItem::BackgroundImage {
+            image: images.get("a.png").unwrap(),
+            origin: LayoutPoint::new(2, 230),
+            layout_point: LayoutPoint::new(0, 228),
+            layout_size: LayoutSize::new(44, 44),
+        }));
+        // The image that isn't loaded is its alt text.
+        assert!(text_items(&layout_view)
+            .iter()
+            .any(|(text, _)| text == "alt text"));
+    }
+
+    fn rect(x: i64, y: i64, width: i64, height: i64) -> (LayoutPoint, LayoutSize) {
+        (LayoutPoint::new(x, y), LayoutSize::new(width, height))
+    }
+
+    #[test]
+    fn test_incremental_update() {
+        let mut layout_view = create_layout_view(
+            r#"<html><head><style>
+            body { margin: 0; }
+            p { margin: 0; }
+            .red { color: red; }
+            .narrow { width: 100px; }
+            .hidden { display: none; }
+            </style></head><body><p id="a">aaa</p><p id="b">bbb</p></body></html>"#
+                .to_string(),
+        );
+        let images = ImageStore::new();
+        let document = layout_view.document.clone();
+        let a = get_element_by_id(Some(document.clone()), &"a".to_string()).unwrap();
+        let b = get_element_by_id(Some(document.clone()), &"b".to_string()).unwrap();
+        let first = layout_view.root().unwrap().borrow().first_child().unwrap();
+        assert!(layout_view.update(&images).is_empty());
+
+        // A color is only painted again, in the paragraph and its text.
+        set_attribute(&a, "class", "red".to_string());
+        assert_eq!(
+            vec![rect(0, 0, 600, 20), rect(0, 0, 24, 20)],
+            layout_view.update(&images)
+        );
+        assert_eq!(
+            Color::from_name("red").unwrap(),
+            first
+                .borrow()
+                .first_child()
+                .unwrap()
+                .borrow()
+                .style()
+                .color()
+        );
+
+        // Longer text is laid out again, and the paragraph keeps its size.
+        set_text_content(&b, "bbb bbb".to_string());
+        assert_eq!(
+            vec![rect(0, 20, 24, 20), rect(0, 20, 56, 20)],
+            layout_view.update(&images)
+        );
+
+        // A narrower paragraph is laid out again, and its text stays.
+        set_attribute(&b, "class", "narrow".to_string());
+        assert_eq!(
+            vec![rect(0, 20, 600, 20), rect(0, 20, 100, 20)],
+            layout_view.update(&images)
+        );
+
+        // The text of the first paragraph wraps, and the body, the first paragraph and its text
+        // grow to two lines. The second paragraph moves down.
+        set_text_content(&a, "aaa ".repeat(25));
+        assert_eq!(
+            vec![
+                rect(0, 0, 600, 40),
+                rect(0, 0, 600, 60),
+                rect(0, 0, 600, 20),
+                rect(0, 0, 24, 20),
+                rect(0, 20, 100, 20),
+                rect(0, 40, 100, 20),
+                rect(0, 20, 56, 20),
+                rect(0, 40, 56, 20),
+            ],
+            layout_view.update(&images)
+        );
+        // The boxes are kept.
+        assert!(Rc::ptr_eq(
+            &first,
+            &layout_view.root().unwrap().borrow().first_child().unwrap()
+        ));
+
+        // A box that disappears makes the boxes built again, and the whole document is painted.
+        set_attribute(&a, "class", "hidden".to_string());
+        assert_eq!(vec![rect(0, 0, 600, 400)], layout_view.update(&images));
+        assert_eq!(
+            vec![("bbb bbb".to_string(), LayoutPoint::new(0, 2))],
+            text_items(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_incremental_update_list_item() {
+        let mut layout_view = create_layout_view(
+            r#"<html><head><style>
+            body { margin: 0; }
+            ul { margin: 0; }
+            .red { color: red; }
+            </style></head><body><ul><li id="item">item</li></ul></body></html>"#
+                .to_string(),
+        );
+        let images = ImageStore::new();
+        let document = layout_view.document.clone();
+        let item = get_element_by_id(Some(document), &"item".to_string()).unwrap();
+        let list_item = layout_view
+            .root()
+            .unwrap()
+            .borrow()
+            .first_child()
+            .unwrap()
+            .borrow()
+            .first_child()
+            .unwrap();
+        let marker = list_item.borrow().marker().expect("marker should exist");
+        let marker_rect = marker.borrow().visual_rect();
+
+        // The list item and its marker are styled again and only painted again.
+        set_attribute(&item, "class", "red".to_string());
+        let damage = layout_view.update(&images);
+        assert!(damage.contains(&marker_rect));
+        assert!(!damage.contains(&(LayoutPoint::new(0, 0), layout_view.document_size())));
+        assert_eq!(
+            Color::from_name("red").unwrap(),
+            marker.borrow().style().color()
+        );
+        // The boxes are kept.
+        let root = layout_view.root().unwrap();
+        let ul = root.borrow().first_child().unwrap();
+        assert!(Rc::ptr_eq(&list_item, &ul.borrow().first_child().unwrap()));
+    }
 }
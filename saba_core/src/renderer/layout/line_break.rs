@@ -0,0 +1,422 @@
+//! https://www.unicode.org/reports/tr14/
+//! https://www.w3.org/TR/css-text-3/#line-breaking
+//! Soft wrap opportunities between grapheme clusters. The line breaking classes of UAX #14 are
+//! looked up for the characters that are common in Latin and CJK text, and the pair rules are
+//! applied with the tailoring that word-break and line-break ask for.
+
+use crate::renderer::layout::computed_style::LineBreak;
+use crate::renderer::layout::computed_style::WordBreak;
+use crate::renderer::layout::unicode::is_wide;
+
+/// https://www.unicode.org/reports/tr14/#Table1
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineBreakClass {
+    /// Ordinary alphabetic and symbol characters.
+    Al,
+    /// Break opportunity after, such as hyphens other than the hyphen-minus.
+    Ba,
+    /// Break opportunity before.
+    Bb,
+    /// Break opportunity before and after, the em dash.
+    B2,
+    /// Closing punctuation, which includes the ideographic comma and full stop.
+    Cl,
+    /// Conditional Japanese starters, the small kana and the prolonged sound mark.
+    Cj,
+    /// Closing parentheses.
+    Cp,
+    /// Exclamation and interrogation.
+    Ex,
+    /// Non-breaking ("glue").
+    Gl,
+    /// Hyphen-minus.
+    Hy,
+    /// Ideographic characters.
+    Id,
+    /// Inseparable characters, such as ellipses.
+    In,
+    /// Infix numeric separators.
+    Is,
+    /// Nonstarters, such as iteration marks.
+    Ns,
+    /// Numeric digits.
+    Nu,
+    /// Opening punctuation.
+    Op,
+    /// Postfix numeric, such as %.
+    Po,
+    /// Prefix numeric, such as currency symbols.
+    Pr,
+    /// Quotation marks.
+    Qu,
+    /// Symbols allowing break after, the solidus.
+    Sy,
+    /// Word joiner.
+    Wj,
+    /// Zero width space.
+    Zw,
+}
+
+/// Returns the line breaking class of `c`. Combining marks and joiners are parts of grapheme
+/// clusters, which take the class of their base character, so they only get here at the start of
+/// a cluster, where they are treated as AL as LB10 says.
+pub fn line_break_class(c: char) -> LineBreakClass {
+    use LineBreakClass::*;
+    match c as u32 {
+        0x200B => Zw,
+        0x2060 | 0xFEFF => Wj,
+        0x00A0 | 0x034F | 0x2007 | 0x2011 | 0x202F | 0x0F0C => Gl,
+        0x002D => Hy,
+        0x00AD | 0x007C | 0x058A | 0x05BE | 0x2010 | 0x2012 | 0x2013 | 0x2027 => Ba,
+        0x00B4 | 0x02C8 | 0x02CC | 0x02DF => Bb,
+        0x2014 | 0x2E3A | 0x2E3B => B2,
+        0x0029 | 0x005D | 0xFF09 | 0xFF3D => Cp,
+        0x007D | 0x2046 | 0x3001 | 0x3002 | 0x3009 | 0x300B | 0x300D | 0x300F | 0x3011 | 0x3015
+        | 0x3017 | 0x3019 | 0x301B | 0x301E | 0x301F | 0xFE11 | 0xFE12 | 0xFE50 | 0xFE52
+        | 0xFE5A | 0xFE5C | 0xFE5E | 0xFF0C | 0xFF0E | 0xFF5D | 0xFF60 | 0xFF61 | 0xFF63
+        | 0xFF64 => Cl,
+        0x0028 | 0x005B | 0x007B | 0x00A1 | 0x00BF | 0x2045 | 0x3008 | 0x300A | 0x300C | 0x300E
+        | 0x3010 | 0x3014 | 0x3016 | 0x3018 | 0x301A | 0x301D | 0xFE59 | 0xFE5B | 0xFE5D
+        | 0xFF08 | 0xFF3B | 0xFF5B | 0xFF5F | 0xFF62 => Op,
+        0x0022 | 0x0027 | 0x00AB | 0x00BB | 0x2018..=0x201F | 0x2039 | 0x203A | 0x275B..=0x275E => {
+            Qu
+        }
+        0x0021 | 0x003F | 0x05C6 | 0x061F | 0xFE15 | 0xFE16 | 0xFE56 | 0xFE57 | 0xFF01 | 0xFF1F => {
+            Ex
+        }
+        0x002C | 0x002E | 0x003A | 0x003B | 0x037E | 0x0589 | 0x060C | 0x060D | 0x2044 | 0xFE10
+        | 0xFE13 | 0xFE14 => Is,
+        0x002F => Sy,
+        0x2024..=0x2026 | 0x22EF | 0xFE19 => In,
+        0x0030..=0x0039 | 0x0660..=0x0669 | 0x06F0..=0x06F9 | 0x0966..=0x096F => Nu,
+        0x0024
+        | 0x002B
+        | 0x005C
+        | 0x00A3
+        | 0x00A5
+        | 0x00B1
+        | 0x20A0..=0x20A6
+        | 0x20A8..=0x20B5
+        | 0x20B7..=0x20BA
+        | 0x2116
+        | 0xFE69
+        | 0xFF04
+        | 0xFFE1
+        | 0xFFE5
+        | 0xFFE6 => Pr,
+        0x0025
+        | 0x00A2
+        | 0x00B0
+        | 0x2030..=0x2037
+        | 0x20A7
+        | 0x20B6
+        | 0x20BB
+        | 0x2103
+        | 0x2109
+        | 0xFE6A
+        | 0xFF05
+        | 0xFFE0 => Po,
+        0x203C
+        | 0x203D
+        | 0x2047..=0x2049
+        | 0x3005
+        | 0x301C
+        | 0x303B
+        | 0x303C
+        | 0x309B..=0x309E
+        | 0x30A0
+        | 0x30FB
+        | 0x30FD
+        | 0x30FE
+        | 0xFE54
+        | 0xFE55
+        | 0xFF1A
+        | 0xFF1B
+        | 0xFF65
+        | 0xFF9E
+        | 0xFF9F => Ns,
+        0x3041
+        | 0x3043
+        | 0x3045
+        | 0x3047
+        | 0x3049
+        | 0x3063
+        | 0x3083
+        | 0x3085
+        | 0x3087
+        | 0x308E
+        | 0x3095
+        | 0x3096
+        | 0x30A1
+        | 0x30A3
+        | 0x30A5
+        | 0x30A7
+        | 0x30A9
+        | 0x30C3
+        | 0x30E3
+        | 0x30E5
+        | 0x30E7
+        | 0x30EE
+        | 0x30F5
+        | 0x30F6
+        | 0x30FC
+        | 0x31F0..=0x31FF
+        | 0xFF67..=0xFF70 => Cj,
+        // Ideographs, kana, Hangul, fullwidth forms and emoji that aren't listed above.
+        _ if is_wide(c) => Id,
+        _ => Al,
+    }
+}
+
+/// Returns the line breaking class of a grapheme cluster, tailored by the word-break and
+/// line-break properties.
+pub fn resolve_class(
+    cluster: &str,
+    word_break: WordBreak,
+    line_break: LineBreak,
+) -> LineBreakClass {
+    use LineBreakClass::*;
+    let first = cluster.chars().next().unwrap_or(' ');
+    let class = line_break_class(first);
+    match (class, line_break) {
+        // https://www.w3.org/TR/css-text-3/#line-break-property
+        // "strict: Breaks text using the most stringent set of line-breaking rules." The small
+        // kana and the prolonged sound mark don't start a line. Otherwise, they are ideographs
+        // as LB1 suggests for CJ.
+        (Cj, LineBreak::Strict) => Ns,
+        (Cj, _) => Id,
+        // "loose: Breaks text using the least restrictive set of line-breaking rules." Iteration
+        // marks and the wave dash may start a line.
+        (Ns, LineBreak::Loose)
+            if matches!(
+                first,
+                '\u{3005}'
+                    | '\u{303B}'
+                    | '\u{301C}'
+                    | '\u{30A0}'
+                    | '\u{309D}'
+                    | '\u{309E}'
+                    | '\u{30FD}'
+                    | '\u{30FE}'
+            ) =>
+        {
+            Id
+        }
+        // https://www.w3.org/TR/css-text-3/#word-break-property
+        // "break-all: Breaking is allowed within 'words': specifically, in addition to soft wrap
+        // opportunities allowed for normal, any typographic letter units ... shall be treated as
+        // ID."
+        (Al | Nu, _) if word_break == WordBreak::BreakAll => Id,
+        _ => class,
+    }
+}
+
+/// Returns true if the class is a letter or a digit, which word-break: keep-all keeps together.
+fn is_letter(class: LineBreakClass) -> bool {
+    matches!(
+        class,
+        LineBreakClass::Al | LineBreakClass::Nu | LineBreakClass::Id
+    )
+}
+
+/// https://www.unicode.org/reports/tr14/#Algorithm
+/// Returns true if there is a soft wrap opportunity between two adjacent grapheme clusters of
+/// classes `before` and `after`. Rules that only apply to spaces, mandatory breaks and scripts
+/// that need a dictionary are left to the caller or not supported.
+pub fn is_break_opportunity(
+    before: LineBreakClass,
+    after: LineBreakClass,
+    word_break: WordBreak,
+) -> bool {
+    use LineBreakClass::*;
+    // "keep-all: Breaking is forbidden within 'words'."
+    if word_break == WordBreak::KeepAll && is_letter(before) && is_letter(after) {
+        return false;
+    }
+    match (before, after) {
+        // LB7: "Do not break before spaces or zero width space."
+        (_, Zw) => false,
+        // LB8: "Break before any character following a zero-width space."
+        (Zw, _) => true,
+        // LB11: "Do not break before or after Word joiner and related characters."
+        (_, Wj) | (Wj, _) => false,
+        // LB12: "Do not break after NBSP and related characters."
+        (Gl, _) => false,
+        // LB12a: "Do not break before NBSP and related characters, except after spaces and
+        // hyphens."
+        (_, Gl) => matches!(before, Ba | Hy),
+        // LB13: "Do not break before ']' or '!' or '/', even after spaces."
+        (_, Cl | Cp | Ex | Is | Sy) => false,
+        // LB14: "Do not break after '[', even after spaces."
+        (Op, _) => false,
+        // LB15: "Do not break within '"[', even with intervening spaces."
+        (Qu, Op) => false,
+        // LB16: "Do not break between closing punctuation and a nonstarter, even with
+        // intervening spaces."
+        (Cl | Cp, Ns) => false,
+        // LB17: "Do not break within '——', even with intervening spaces."
+        (B2, B2) => false,
+        // LB19: "Do not break before or after quotation marks."
+        (_, Qu) | (Qu, _) => false,
+        // LB21: "Do not break before hyphen-minus, other hyphens, fixed-width spaces, small
+        // kana, and other non-starters, or after acute accents."
+        (_, Ba | Hy | Ns) | (Bb, _) => false,
+        // LB22: "Do not break before ellipses."
+        (_, In) => false,
+        // LB23: "Do not break between digits and letters."
+        (Al, Nu) | (Nu, Al) => false,
+        // LB23a: "Do not break between numeric prefixes and ideographs, or between ideographs
+        // and numeric postfixes."
+        (Pr, Id) | (Id, Po) => false,
+        // LB24: "Do not break between numeric prefix/postfix and letters."
+        (Pr | Po, Al) | (Al, Pr | Po) => false,
+        // LB25: "Do not break between the following pairs of classes relevant to numbers."
+        (Cl | Cp | Nu, Po | Pr) | (Po | Pr, Op) | (Po | Pr | Hy | Is | Nu | Sy, Nu) => false,
+        // LB28: "Do not break between alphabetics."
+        (Al, Al) => false,
+        // LB29: "Do not break between numeric punctuation and alphabetics."
+        (Is, Al) => false,
+        // LB30: "Do not break between letters, numbers, or ordinary symbols and opening or
+        // closing parentheses."
+        (Al | Nu, Op) | (Cp, Al | Nu) => false,
+        // LB31: "Break everywhere else."
+        _ => true,
+    }
+}
+
+/// Returns true if there is a soft wrap opportunity after the spaces between a grapheme cluster
+/// of class `before` and one of class `after`. `before` is None if nothing is before the spaces.
+/// LB18 allows a break after spaces, but some pairs don't break "even with intervening spaces".
+pub fn is_break_opportunity_after_spaces(
+    before: Option<LineBreakClass>,
+    after: LineBreakClass,
+) -> bool {
+    use LineBreakClass::*;
+    match (before, after) {
+        // LB11, LB13
+        (_, Wj | Cl | Cp | Ex | Is | Sy) => false,
+        // LB14, LB15, LB16, LB17
+        (Some(Op), _) | (Some(Qu), Op) | (Some(Cl | Cp), Ns) | (Some(B2), B2) => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::layout::unicode::graphemes;
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Splits `text`, which has no spaces, at its soft wrap opportunities.
+    fn split(text: &str, word_break: WordBreak, line_break: LineBreak) -> Vec<String> {
+        let mut parts: Vec<String> = Vec::new();
+        let mut previous = None;
+        for g in graphemes(text) {
+            let class = resolve_class(g, word_break, line_break);
+            match (previous, parts.last_mut()) {
+                (Some(p), Some(part)) if !is_break_opportunity(p, class, word_break) => {
+                    part.push_str(g)
+                }
+                _ => parts.push(g.into()),
+            }
+            previous = Some(class);
+        }
+        parts
+    }
+
+    fn normal(text: &str) -> Vec<String> {
+        split(text, WordBreak::Normal, LineBreak::Auto)
+    }
+
+    #[test]
+    fn test_latin() {
+        assert_eq!(vec!["hello"], normal("hello"));
+        assert_eq!(vec!["well-", "known"], normal("well-known"));
+        assert_eq!(vec!["and/", "or"], normal("and/or"));
+        assert_eq!(vec!["(a)."], normal("(a)."));
+        assert_eq!(vec!["$100%"], normal("$100%"));
+        assert_eq!(vec!["3.14"], normal("3.14"));
+        assert_eq!(vec!["a\u{A0}b"], normal("a\u{A0}b"));
+        assert_eq!(vec!["a\u{200B}", "b"], normal("a\u{200B}b"));
+    }
+
+    #[test]
+    fn test_cjk() {
+        // Ideographs and kana break everywhere.
+        assert_eq!(
+            vec!["\u{65E5}", "\u{672C}", "\u{8A9E}", "\u{3067}", "\u{3059}"],
+            normal("\u{65E5}\u{672C}\u{8A9E}\u{3067}\u{3059}")
+        );
+        // Kinsoku: the ideographic comma and full stop and closing brackets don't start a line,
+        // and opening brackets don't end one.
+        assert_eq!(
+            vec![
+                "\u{3042}\u{3001}",
+                "\u{300C}\u{3044}\u{300D}\u{3002}",
+                "\u{3046}"
+            ],
+            normal("\u{3042}\u{3001}\u{300C}\u{3044}\u{300D}\u{3002}\u{3046}")
+        );
+        // Letters and ideographs break between each other, but not inside a word.
+        assert_eq!(vec!["Rust", "\u{306E}"], normal("Rust\u{306E}"));
+    }
+
+    #[test]
+    fn test_line_break() {
+        // "chotto": chi, small yo, small tsu, to.
+        let text = "\u{3061}\u{3087}\u{3063}\u{3068}";
+        assert_eq!(
+            vec!["\u{3061}", "\u{3087}", "\u{3063}", "\u{3068}"],
+            split(text, WordBreak::Normal, LineBreak::Normal)
+        );
+        assert_eq!(
+            vec!["\u{3061}\u{3087}\u{3063}", "\u{3068}"],
+            split(text, WordBreak::Normal, LineBreak::Strict)
+        );
+        // An iteration mark starts a line only with loose.
+        let text = "\u{6642}\u{3005}";
+        assert_eq!(
+            vec![text],
+            split(text, WordBreak::Normal, LineBreak::Normal)
+        );
+        assert_eq!(
+            vec!["\u{6642}", "\u{3005}"],
+            split(text, WordBreak::Normal, LineBreak::Loose)
+        );
+    }
+
+    #[test]
+    fn test_word_break() {
+        assert_eq!(
+            vec!["a", "b", "c."],
+            split("abc.", WordBreak::BreakAll, LineBreak::Auto)
+        );
+        assert_eq!(
+            vec!["\u{65E5}\u{672C}\u{8A9E}\u{3002}", "\u{3042}"],
+            split(
+                "\u{65E5}\u{672C}\u{8A9E}\u{3002}\u{3042}",
+                WordBreak::KeepAll,
+                LineBreak::Auto
+            )
+        );
+    }
+
+    #[test]
+    fn test_after_spaces() {
+        assert!(is_break_opportunity_after_spaces(
+            Some(LineBreakClass::Al),
+            LineBreakClass::Al
+        ));
+        assert!(!is_break_opportunity_after_spaces(
+            Some(LineBreakClass::Al),
+            LineBreakClass::Cl
+        ));
+        assert!(!is_break_opportunity_after_spaces(
+            Some(LineBreakClass::Op),
+            LineBreakClass::Al
+        ));
+        assert!(is_break_opportunity_after_spaces(None, LineBreakClass::Id));
+    }
+}
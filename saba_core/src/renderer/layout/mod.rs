@@ -1,3 +1,13 @@
 pub mod computed_style;
+pub mod counter;
+pub mod flex_layout;
+pub mod float_layout;
+pub mod font_metrics;
+pub mod grid_layout;
+pub mod inline_layout;
 pub mod layout_object;
 pub mod layout_view;
+pub mod line_break;
+pub mod scroll;
+pub mod transform;
+pub mod unicode;
@@ -0,0 +1,182 @@
+//! https://www.w3.org/TR/css-overflow-3/#scroll-container
+//! Scroll offsets of scroll containers and the scrollbars that show them.
+
+use crate::display_item::DisplayItem;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
+
+/// The thickness of a scrollbar in px. Scrollbars are drawn over the content near the edges of
+/// the padding box, so they don't take space from the content.
+pub const SCROLLBAR_WIDTH: i64 = 8;
+
+/// The shortest length of a scrollbar thumb in px, so that the thumb of a very long scroller can
+/// still be seen.
+const MIN_THUMB_LENGTH: i64 = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// Returns the largest scroll offset at which the end of the scrollable overflow area of
+/// `extent` is at the end of the scrollport of `client`.
+/// https://www.w3.org/TR/cssom-view-1/#scrolling
+pub fn max_scroll_offset(client: LayoutSize, extent: LayoutSize) -> LayoutPoint {
+    LayoutPoint::new(
+        (extent.width() - client.width()).max(0),
+        (extent.height() - client.height()).max(0),
+    )
+}
+
+/// Moves `offset` by (`dx`, `dy`) and keeps it between 0 and the max scroll offset.
+pub fn clamp_scroll_offset(
+    offset: LayoutPoint,
+    dx: i64,
+    dy: i64,
+    client: LayoutSize,
+    extent: LayoutSize,
+) -> LayoutPoint {
+    let max = max_scroll_offset(client, extent);
+    LayoutPoint::new(
+        (offset.x() + dx).clamp(0, max.x()),
+        (offset.y() + dy).clamp(0, max.y()),
+    )
+}
+
+/// Returns the scrollbar of a scrollport at `point` whose size is `client`. The thumb shows which
+/// part of the scrollable overflow area of `extent` is seen at `offset`.
+pub fn scrollbar(
+    axis: Axis,
+    point: LayoutPoint,
+    client: LayoutSize,
+    extent: LayoutSize,
+    offset: LayoutPoint,
+) -> DisplayItem {
+    let max = max_scroll_offset(client, extent);
+    // (the position of the scrollport, its length, the length of the content, the offset and
+    // the max offset) along the axis.
+    let (start, length, content_length, offset, max) = match axis {
+        Axis::Horizontal => (
+            point.x(),
+            client.width(),
+            extent.width(),
+            offset.x(),
+            max.x(),
+        ),
+        Axis::Vertical => (
+            point.y(),
+            client.height(),
+            extent.height(),
+            offset.y(),
+            max.y(),
+        ),
+    };
+    let thumb_length = if content_length <= 0 {
+        length
+    } else {
+        (length * length / content_length).clamp(MIN_THUMB_LENGTH.min(length), length)
+    };
+    let thumb_start = if max == 0 {
+        start
+    } else {
+        start + (length - thumb_length) * offset / max
+    };
+
+    match axis {
+        Axis::Horizontal => {
+            let y = point.y() + client.height() - SCROLLBAR_WIDTH;
+            DisplayItem::Scrollbar {
+                track_point: LayoutPoint::new(start, y),
+                track_size: LayoutSize::new(length, SCROLLBAR_WIDTH),
+                thumb_point: LayoutPoint::new(thumb_start, y),
+                thumb_size: LayoutSize::new(thumb_length, SCROLLBAR_WIDTH),
+            }
+        }
+        Axis::Vertical => {
+            let x = point.x() + client.width() - SCROLLBAR_WIDTH;
+            DisplayItem::Scrollbar {
+                track_point: LayoutPoint::new(x, start),
+                track_size: LayoutSize::new(SCROLLBAR_WIDTH, length),
+                thumb_point: LayoutPoint::new(x, thumb_start),
+                thumb_size: LayoutSize::new(SCROLLBAR_WIDTH, thumb_length),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamp_scroll_offset() {
+        let client = LayoutSize::new(100, 100);
+        let extent = LayoutSize::new(100, 250);
+        assert_eq!(
+            LayoutPoint::new(0, 50),
+            clamp_scroll_offset(LayoutPoint::new(0, 0), 0, 50, client, extent)
+        );
+        assert_eq!(
+            LayoutPoint::new(0, 150),
+            clamp_scroll_offset(LayoutPoint::new(0, 100), 20, 100, client, extent)
+        );
+        assert_eq!(
+            LayoutPoint::new(0, 0),
+            clamp_scroll_offset(LayoutPoint::new(0, 100), 0, -200, client, extent)
+        );
+    }
+
+    #[test]
+    fn test_scrollbar() {
+        let point = LayoutPoint::new(10, 20);
+        let client = LayoutSize::new(100, 100);
+        let extent = LayoutSize::new(100, 400);
+        assert_eq!(
+            DisplayItem::Scrollbar {
+                track_point: LayoutPoint::new(102, 20),
+                track_size: LayoutSize::new(SCROLLBAR_WIDTH, 100),
+                thumb_point: LayoutPoint::new(102, 20),
+                thumb_size: LayoutSize::new(SCROLLBAR_WIDTH, 25),
+            },
+            scrollbar(
+                Axis::Vertical,
+                point,
+                client,
+                extent,
+                LayoutPoint::new(0, 0)
+            )
+        );
+        // At the max offset, the thumb is at the end of the track.
+        assert_eq!(
+            DisplayItem::Scrollbar {
+                track_point: LayoutPoint::new(102, 20),
+                track_size: LayoutSize::new(SCROLLBAR_WIDTH, 100),
+                thumb_point: LayoutPoint::new(102, 95),
+                thumb_size: LayoutSize::new(SCROLLBAR_WIDTH, 25),
+            },
+            scrollbar(
+                Axis::Vertical,
+                point,
+                client,
+                extent,
+                LayoutPoint::new(0, 300)
+            )
+        );
+        assert_eq!(
+            DisplayItem::Scrollbar {
+                track_point: LayoutPoint::new(10, 112),
+                track_size: LayoutSize::new(100, SCROLLBAR_WIDTH),
+                thumb_point: LayoutPoint::new(10, 112),
+                thumb_size: LayoutSize::new(100, SCROLLBAR_WIDTH),
+            },
+            scrollbar(
+                Axis::Horizontal,
+                point,
+                client,
+                extent,
+                LayoutPoint::new(0, 0)
+            )
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! https://www.w3.org/TR/css-transforms-1/#transform-rendering
+//! 2D transformations of boxes. The transform property of a box is turned into a matrix that maps
+//! the coordinates of the box and its descendants to the coordinates of its stacking context.
+
+use crate::renderer::css::value::round;
+use crate::renderer::layout::computed_style::TransformFunction;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
+use core::f64::consts::PI;
+
+/// https://www.w3.org/TR/css-transforms-1/#mathematical-description
+/// A 2D transformation matrix `[a c e; b d f; 0 0 1]`, which maps (x, y) to
+/// (a * x + c * y + e, b * x + d * y + f).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+}
+
+impl Transform {
+    /// `matrix` is `[a, b, c, d, e, f]`, in the order of the arguments of matrix().
+    pub fn new(matrix: [f64; 6]) -> Self {
+        let [a, b, c, d, e, f] = matrix;
+        Self { a, b, c, d, e, f }
+    }
+
+    pub fn identity() -> Self {
+        Self::new([1.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Self {
+        Self::new([1.0, 0.0, 0.0, 1.0, tx, ty])
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Self::new([sx, 0.0, 0.0, sy, 0.0, 0.0])
+    }
+
+    /// https://www.w3.org/TR/css-transforms-1/#funcdef-transform-rotate
+    /// "rotate(a) specifies a 2D rotation by the angle specified in the parameter about the origin
+    /// of the element", which is `[cos(a) sin(a) -sin(a) cos(a) 0 0]`. Positive angles rotate
+    /// clockwise because the y axis points down.
+    pub fn rotate(degrees: f64) -> Self {
+        let (sin, cos) = sin_cos(degrees);
+        Self::new([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    pub fn matrix(&self) -> [f64; 6] {
+        [self.a, self.b, self.c, self.d, self.e, self.f]
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::identity()
+    }
+
+    /// Returns the product `self * other`, which applies `other` first and then `self`.
+    pub fn multiply(&self, other: &Transform) -> Self {
+        Self::new([
+            self.a * other.a + self.c * other.b,
+            self.b * other.a + self.d * other.b,
+            self.a * other.c + self.c * other.d,
+            self.b * other.c + self.d * other.d,
+            self.a * other.e + self.c * other.f + self.e,
+            self.b * other.e + self.d * other.f + self.f,
+        ])
+    }
+
+    /// https://www.w3.org/TR/css-transforms-1/#transform-rendering
+    /// Returns the matrix of a transform list on a border box of `size`. "Post-multiply all
+    /// <transform-function>s in the list from left to right", so the last function is applied
+    /// first. Percentages in translations refer to the size of the border box.
+    pub fn from_functions(functions: &[TransformFunction], size: LayoutSize) -> Self {
+        functions
+            .iter()
+            .fold(Self::identity(), |transform, function| {
+                let matrix = match function {
+                    TransformFunction::Translate(x, y) => Self::translate(
+                        x.resolve(size.width()) as f64,
+                        y.resolve(size.height()) as f64,
+                    ),
+                    TransformFunction::Scale(sx, sy) => Self::scale(*sx, *sy),
+                    TransformFunction::Rotate(degrees) => Self::rotate(*degrees),
+                    TransformFunction::Matrix(matrix) => Self::new(*matrix),
+                };
+                transform.multiply(&matrix)
+            })
+    }
+
+    /// https://www.w3.org/TR/css-transforms-1/#transform-origin-property
+    /// Returns the transform about `origin` instead of (0, 0): "translate by the computed X, Y
+    /// and Z values of transform-origin", apply the transform, and translate back.
+    pub fn about(&self, origin: LayoutPoint) -> Self {
+        let (x, y) = (origin.x() as f64, origin.y() as f64);
+        Self::translate(x, y)
+            .multiply(self)
+            .multiply(&Self::translate(-x, -y))
+    }
+
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// Returns the smallest rectangle on the pixel grid that contains the rectangle transformed.
+    pub fn bounding_box(
+        &self,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    ) -> (LayoutPoint, LayoutSize) {
+        let (x0, y0) = (layout_point.x() as f64, layout_point.y() as f64);
+        let (x1, y1) = (
+            x0 + layout_size.width() as f64,
+            y0 + layout_size.height() as f64,
+        );
+        let corners = [
+            self.apply(x0, y0),
+            self.apply(x1, y0),
+            self.apply(x1, y1),
+            self.apply(x0, y1),
+        ];
+        let (mut left, mut top) = corners[0];
+        let (mut right, mut bottom) = corners[0];
+        for (x, y) in &corners[1..] {
+            left = left.min(*x);
+            top = top.min(*y);
+            right = right.max(*x);
+            bottom = bottom.max(*y);
+        }
+        let (left, top) = (round(left), round(top));
+        (
+            LayoutPoint::new(left, top),
+            // The corners saturate at the range of i64 for huge transforms.
+            LayoutSize::new(
+                round(right).saturating_sub(left),
+                round(bottom).saturating_sub(top),
+            ),
+        )
+    }
+}
+
+/// Returns the sine and the cosine of an angle in degrees. core has no trigonometric functions,
+/// so they are computed with Taylor series after reducing the angle to [-45, 45] degrees.
+/// Multiples of 90 degrees are exact.
+fn sin_cos(degrees: f64) -> (f64, f64) {
+    let turns = degrees / 90.0;
+    let quadrant = round(turns);
+    let x = (turns - quadrant as f64) * PI / 2.0;
+
+    // sin(x) = x - x^3/3! + x^5/5! - ... and cos(x) = 1 - x^2/2! + x^4/4! - ...
+    let mut sin = 0.0;
+    let mut cos = 0.0;
+    let mut power = 1.0;
+    for n in 0..16 {
+        let term = if (n / 2) % 2 == 0 { power } else { -power };
+        if n % 2 == 0 {
+            cos += term;
+        } else {
+            sin += term;
+        }
+        power = power * x / (n + 1) as f64;
+    }
+    match quadrant.rem_euclid(4) {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::value::Length;
+    use crate::renderer::css::value::LengthPercentage;
+
+    #[test]
+    fn test_sin_cos() {
+        assert_eq!((1.0, 0.0), sin_cos(90.0));
+        assert_eq!((0.0, -1.0), sin_cos(-180.0));
+        let (sin, cos) = sin_cos(30.0);
+        assert!((sin - 0.5).abs() < 1e-12);
+        assert!((cos - 0.866_025_403_784_438_6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_from_functions() {
+        // The scale is applied first, and then the translation.
+        let transform = Transform::from_functions(
+            &[
+                TransformFunction::Translate(
+                    LengthPercentage::Percentage(50.0),
+                    LengthPercentage::Length(Length::px(3.0)),
+                ),
+                TransformFunction::Scale(2.0, 1.0),
+            ],
+            LayoutSize::new(40, 10),
+        );
+        assert_eq!((24.0, 4.0), transform.apply(2.0, 1.0));
+
+        // A quarter turn about the center of a 20x10 box at (10, 10).
+        let transform =
+            Transform::from_functions(&[TransformFunction::Rotate(90.0)], LayoutSize::new(20, 10))
+                .about(LayoutPoint::new(20, 15));
+        assert_eq!(
+            (LayoutPoint::new(15, 5), LayoutSize::new(10, 20)),
+            transform.bounding_box(LayoutPoint::new(10, 10), LayoutSize::new(20, 10))
+        );
+    }
+
+    #[test]
+    fn test_huge_bounding_box() {
+        let transform = Transform::translate(1e23, -1e23);
+        assert_eq!(
+            (LayoutPoint::new(i64::MAX, i64::MIN), LayoutSize::new(0, 0)),
+            transform.bounding_box(LayoutPoint::new(0, 0), LayoutSize::new(20, 10))
+        );
+
+        // A box stretched past both ends of i64 doesn't overflow its size.
+        let transform = Transform::translate(-1e23, 0.0).multiply(&Transform::scale(2e23, 1.0));
+        assert_eq!(
+            (LayoutPoint::new(i64::MIN, 0), LayoutSize::new(i64::MAX, 10)),
+            transform.bounding_box(LayoutPoint::new(0, 0), LayoutSize::new(1, 10))
+        );
+    }
+}
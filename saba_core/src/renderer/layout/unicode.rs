@@ -0,0 +1,327 @@
+//! Unicode text properties that text layout needs: grapheme clusters and East Asian Width.
+//! Only the ranges of the Unicode Character Database that matter for text the browser is likely
+//! to see are covered.
+
+/// https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Break_Property_Values
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum GraphemeClusterBreak {
+    Cr,
+    Lf,
+    Control,
+    Extend,
+    Zwj,
+    RegionalIndicator,
+    SpacingMark,
+    L,
+    V,
+    T,
+    Lv,
+    Lvt,
+    Other,
+}
+
+fn grapheme_cluster_break(c: char) -> GraphemeClusterBreak {
+    let cp = c as u32;
+    match cp {
+        0x0D => GraphemeClusterBreak::Cr,
+        0x0A => GraphemeClusterBreak::Lf,
+        0x00..=0x1F | 0x7F..=0x9F | 0x200B | 0x2028 | 0x2029 => GraphemeClusterBreak::Control,
+        0x200D => GraphemeClusterBreak::Zwj,
+        0x1F1E6..=0x1F1FF => GraphemeClusterBreak::RegionalIndicator,
+        0x1100..=0x115F | 0xA960..=0xA97C => GraphemeClusterBreak::L,
+        0x1160..=0x11A7 | 0xD7B0..=0xD7C6 => GraphemeClusterBreak::V,
+        0x11A8..=0x11FF | 0xD7CB..=0xD7FB => GraphemeClusterBreak::T,
+        // Precomposed Hangul syllables are LV if they have no final consonant.
+        0xAC00..=0xD7A3 if (cp - 0xAC00).is_multiple_of(28) => GraphemeClusterBreak::Lv,
+        0xAC00..=0xD7A3 => GraphemeClusterBreak::Lvt,
+        0x0903 | 0x093B | 0x093E..=0x0940 | 0x0949..=0x094C | 0x094E..=0x094F | 0x0E33 | 0x0EB3 => {
+            GraphemeClusterBreak::SpacingMark
+        }
+        _ if is_extend(cp) => GraphemeClusterBreak::Extend,
+        _ => GraphemeClusterBreak::Other,
+    }
+}
+
+/// Combining marks, variation selectors, emoji modifiers and tags, which extend the grapheme
+/// cluster before them.
+fn is_extend(cp: u32) -> bool {
+    matches!(
+        cp,
+        0x0300..=0x036F
+            | 0x0483..=0x0489
+            | 0x0591..=0x05BD
+            | 0x0610..=0x061A
+            | 0x064B..=0x065F
+            | 0x0900..=0x0902
+            | 0x093A
+            | 0x093C
+            | 0x0941..=0x0948
+            | 0x094D
+            | 0x0951..=0x0957
+            | 0x0E31
+            | 0x0E34..=0x0E3A
+            | 0x0E47..=0x0E4E
+            | 0x1AB0..=0x1AFF
+            | 0x1DC0..=0x1DFF
+            | 0x20D0..=0x20FF
+            | 0x302A..=0x302F
+            | 0x3099..=0x309A
+            | 0xFE00..=0xFE0F
+            | 0xFE20..=0xFE2F
+            | 0x1F3FB..=0x1F3FF
+            | 0xE0020..=0xE007F
+            | 0xE0100..=0xE01EF
+    )
+}
+
+/// The Extended_Pictographic property of https://www.unicode.org/reports/tr51/, which emoji
+/// sequences start with.
+fn is_extended_pictographic(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x00A9 | 0x00AE | 0x203C | 0x2049 | 0x2122 | 0x2139
+            | 0x2194..=0x21AA
+            | 0x231A..=0x23FF
+            | 0x24C2
+            | 0x25AA..=0x27BF
+            | 0x2934..=0x2935
+            | 0x2B05..=0x2B55
+            | 0x3030
+            | 0x303D
+            | 0x3297
+            | 0x3299
+            | 0x1F000..=0x1F0FF
+            | 0x1F10D..=0x1F1AD
+            | 0x1F200..=0x1F3FA
+            | 0x1F400..=0x1FAFF
+    )
+}
+
+/// https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundary_Rules
+/// Returns true if there is no extended grapheme cluster boundary between `prev` and `next`.
+/// `pictographic_zwj` says if the cluster so far is an Extended_Pictographic character followed by
+/// Extend characters and a ZWJ, and `regional_indicators` is the number of regional indicators in
+/// a row before `next`.
+fn continues_cluster(
+    prev: char,
+    next: char,
+    pictographic_zwj: bool,
+    regional_indicators: usize,
+) -> bool {
+    use GraphemeClusterBreak::*;
+    match (grapheme_cluster_break(prev), grapheme_cluster_break(next)) {
+        // GB3: "Do not break between a CR and LF."
+        (Cr, Lf) => true,
+        // GB4, GB5: "Otherwise, break before and after controls."
+        (Cr | Lf | Control, _) | (_, Cr | Lf | Control) => false,
+        // GB6, GB7, GB8: "Do not break Hangul syllable or other conjoining sequences."
+        (L, L | V | Lv | Lvt) | (Lv | V, V | T) | (Lvt | T, T) => true,
+        // GB9, GB9a: "Do not break before extending characters or ZWJ", "Do not break before
+        // SpacingMarks".
+        (_, Extend | Zwj | SpacingMark) => true,
+        // GB12, GB13: "Do not break within emoji flag sequences."
+        (RegionalIndicator, RegionalIndicator) => regional_indicators % 2 == 1,
+        // GB11: "Do not break within emoji modifier sequences or emoji zwj sequences."
+        (Zwj, _) => pictographic_zwj && is_extended_pictographic(next),
+        // GB999: "Otherwise, break everywhere."
+        _ => false,
+    }
+}
+
+/// An iterator over the extended grapheme clusters of a string.
+/// https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries
+#[derive(Debug, Clone)]
+pub struct Graphemes<'a> {
+    text: &'a str,
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chars = self.text.char_indices();
+        let (_, first) = chars.next()?;
+        let mut prev = first;
+        let mut pictographic = is_extended_pictographic(first);
+        let mut pictographic_zwj = false;
+        let mut regional_indicators =
+            usize::from(grapheme_cluster_break(first) == GraphemeClusterBreak::RegionalIndicator);
+        let mut end = self.text.len();
+        for (i, c) in chars {
+            if !continues_cluster(prev, c, pictographic_zwj, regional_indicators) {
+                end = i;
+                break;
+            }
+            let gcb = grapheme_cluster_break(c);
+            pictographic_zwj = pictographic && gcb == GraphemeClusterBreak::Zwj;
+            pictographic = (pictographic && gcb == GraphemeClusterBreak::Extend)
+                || is_extended_pictographic(c);
+            if gcb == GraphemeClusterBreak::RegionalIndicator {
+                regional_indicators += 1;
+            }
+            prev = c;
+        }
+        let (cluster, rest) = self.text.split_at(end);
+        self.text = rest;
+        Some(cluster)
+    }
+}
+
+/// Splits `text` into extended grapheme clusters, which are what users perceive as characters.
+pub fn graphemes(text: &str) -> Graphemes<'_> {
+    Graphemes { text }
+}
+
+/// https://www.unicode.org/reports/tr11/
+/// Returns true if `c` is wide (W) or fullwidth (F), so it takes up two columns of a monospace
+/// font. Ambiguous characters are narrow, as they are outside of East Asian contexts.
+pub fn is_wide(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x115F
+            | 0x231A..=0x231B
+            | 0x2329..=0x232A
+            | 0x23E9..=0x23EC
+            | 0x2614..=0x2615
+            | 0x2648..=0x2653
+            | 0x26AA..=0x26AB
+            | 0x26BD..=0x26BE
+            | 0x26F5
+            | 0x26FA
+            | 0x26FD
+            | 0x2705
+            | 0x270A..=0x270B
+            | 0x2728
+            | 0x274C
+            | 0x2753..=0x2755
+            | 0x2757
+            | 0x2795..=0x2797
+            | 0x27B0
+            | 0x2B1B..=0x2B1C
+            | 0x2B50
+            | 0x2B55
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xA960..=0xA97F
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE10..=0xFE19
+            | 0xFE30..=0xFE6F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F004
+            | 0x1F0CF
+            | 0x1F18E
+            | 0x1F191..=0x1F19A
+            | 0x1F200..=0x1F251
+            | 0x1F300..=0x1F320
+            | 0x1F32D..=0x1F335
+            | 0x1F337..=0x1F37C
+            | 0x1F37E..=0x1F393
+            | 0x1F3A0..=0x1F3CA
+            | 0x1F3CF..=0x1F3D3
+            | 0x1F3E0..=0x1F3F0
+            | 0x1F3F4
+            | 0x1F3F8..=0x1F43E
+            | 0x1F440
+            | 0x1F442..=0x1F4FC
+            | 0x1F4FF..=0x1F53D
+            | 0x1F54B..=0x1F54E
+            | 0x1F550..=0x1F567
+            | 0x1F57A
+            | 0x1F595..=0x1F596
+            | 0x1F5A4
+            | 0x1F5FB..=0x1F64F
+            | 0x1F680..=0x1F6C5
+            | 0x1F6CC
+            | 0x1F6D0..=0x1F6D2
+            | 0x1F6D5..=0x1F6D7
+            | 0x1F6EB..=0x1F6EC
+            | 0x1F6F4..=0x1F6FC
+            | 0x1F7E0..=0x1F7EB
+            | 0x1F90C..=0x1F93A
+            | 0x1F93C..=0x1F945
+            | 0x1F947..=0x1F9FF
+            | 0x1FA70..=0x1FAFF
+            | 0x20000..=0x2FFFD
+            | 0x30000..=0x3FFFD
+    )
+}
+
+/// Returns true if a grapheme cluster takes up two columns. A cluster is as wide as its base
+/// character, except that an emoji presentation selector makes it an emoji, which is wide.
+pub fn is_wide_cluster(cluster: &str) -> bool {
+    cluster.chars().next().is_some_and(is_wide) || cluster.contains('\u{FE0F}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_ascii() {
+        assert_eq!(
+            vec!["a", "b", " ", "c", "\r\n", "d"],
+            graphemes("ab c\r\nd").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_combining_marks() {
+        // "e" with a combining acute accent, and "ka" with a combining voiced sound mark.
+        assert_eq!(
+            vec!["e\u{301}", "x", "\u{304B}\u{3099}"],
+            graphemes("e\u{301}x\u{304B}\u{3099}").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_hangul() {
+        // A conjoining jamo sequence and a precomposed syllable with a trailing jamo.
+        assert_eq!(
+            vec!["\u{1100}\u{1161}\u{11A8}", "\u{AC00}\u{11A8}", "\u{AC01}"],
+            graphemes("\u{1100}\u{1161}\u{11A8}\u{AC00}\u{11A8}\u{AC01}").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_emoji() {
+        // Family: man, woman, girl joined with ZWJ, a thumbs up with a skin tone modifier, and
+        // the flags of Japan and the US.
+        let text = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{1F44D}\u{1F3FD}\u{1F1EF}\u{1F1F5}\u{1F1FA}\u{1F1F8}";
+        assert_eq!(
+            vec![
+                "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}",
+                "\u{1F44D}\u{1F3FD}",
+                "\u{1F1EF}\u{1F1F5}",
+                "\u{1F1FA}\u{1F1F8}"
+            ],
+            graphemes(text).collect::<Vec<_>>()
+        );
+        // ZWJ only joins pictographs.
+        assert_eq!(
+            vec!["a\u{200D}", "b"],
+            graphemes("a\u{200D}b").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_east_asian_width() {
+        assert!(!is_wide('a'));
+        assert!(!is_wide('\u{00E9}'));
+        assert!(is_wide('\u{3042}'));
+        assert!(is_wide('\u{6F22}'));
+        assert!(is_wide('\u{FF21}'));
+        assert!(!is_wide('\u{FF71}'));
+        assert!(is_wide('\u{D55C}'));
+        assert!(is_wide_cluster("\u{1F600}"));
+        assert!(is_wide_cluster("\u{2764}\u{FE0F}"));
+        assert!(!is_wide_cluster("\u{2764}"));
+        assert!(!is_wide_cluster("e\u{301}"));
+    }
+}
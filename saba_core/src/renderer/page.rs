@@ -1,27 +1,46 @@
 use crate::browser::Browser;
+use crate::error::Error;
 use crate::http::HttpResponse;
+use crate::http::IMAGE_ACCEPT;
 use crate::renderer::dom::node::Window;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use core::cell::RefCell;
 
+use crate::display_item::damage;
 use crate::display_item::DisplayItem;
 use crate::renderer::css::cssom::CssParser;
 use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::media::Device;
 use crate::renderer::css::token::CssTokenizer;
 use crate::renderer::dom::api::get_js_content;
 use crate::renderer::dom::api::get_style_content;
 use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
 use crate::renderer::html::parser::HtmlParser;
 use crate::renderer::html::token::HtmlTokenizer;
+use crate::renderer::image::decode;
+use crate::renderer::image::ImageStore;
 use crate::renderer::js::ast::JsParser;
 use crate::renderer::js::runtime::JsRuntime;
 use crate::renderer::js::token::JsLexer;
+use crate::renderer::layout::font_metrics::FontMetrics;
+use crate::renderer::layout::font_metrics::MonospaceFontMetrics;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
 use crate::renderer::layout::layout_view::LayoutView;
+use crate::renderer::layout::scroll::clamp_scroll_offset;
+use crate::renderer::layout::scroll::scrollbar;
+use crate::renderer::layout::scroll::Axis;
+use crate::url::Url;
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
 
+/// Fetches a URL with the value of the Accept header of the request.
+pub type ImageLoader = fn(String, &str) -> Result<HttpResponse, Error>;
+
 #[derive(Debug, Clone)]
 pub struct Page {
     browser: Weak<RefCell<Browser>>,
@@ -29,6 +48,19 @@ pub struct Page {
     style: Option<StyleSheet>,
     layout_view: Option<LayoutView>,
     display_items: Vec<DisplayItem>,
+    font_metrics: Rc<dyn FontMetrics>,
+    viewport: Device,
+    /// The scroll position of the viewport, which is the root scroller of the page.
+    /// https://www.w3.org/TR/cssom-view-1/#dom-window-scrollx
+    scroll_position: LayoutPoint,
+    /// The URL of the document, which the URLs in it are resolved against.
+    url: Option<String>,
+    /// Fetches the images that the document uses. Images aren't loaded without it.
+    image_loader: Option<ImageLoader>,
+    images: ImageStore,
+    /// The rectangles of the viewport whose display items are changed since the page was last
+    /// painted.
+    damage: Vec<(LayoutPoint, LayoutSize)>,
 }
 
 impl Page {
@@ -39,6 +71,13 @@ impl Page {
             style: None,
             layout_view: None,
             display_items: Vec::new(),
+            font_metrics: Rc::new(MonospaceFontMetrics),
+            viewport: Device::default(),
+            scroll_position: LayoutPoint::new(0, 0),
+            url: None,
+            image_loader: None,
+            images: ImageStore::new(),
+            damage: Vec::new(),
         }
     }
 
@@ -54,10 +93,157 @@ impl Page {
         self.style = Some(style);
     }
 
+    /// Sets the metrics of the font that the UI draws text with, so that layout measures text as
+    /// it is painted. The built-in monospace font is used by default.
+    pub fn set_font_metrics(&mut self, font_metrics: Rc<dyn FontMetrics>) {
+        self.font_metrics = font_metrics;
+    }
+
+    /// Sets the URL of the document that is loaded next.
+    pub fn set_url(&mut self, url: String) {
+        self.url = Some(url);
+    }
+
+    /// Sets the function that fetches images, which is the one that fetches documents in the UI.
+    /// Images are requested with IMAGE_ACCEPT as the Accept header.
+    pub fn set_image_loader(&mut self, image_loader: ImageLoader) {
+        self.image_loader = Some(image_loader);
+    }
+
+    pub fn viewport(&self) -> Device {
+        self.viewport
+    }
+
+    /// Changes the size of the viewport in CSS pixels and lays out the page again.
+    pub fn resize(&mut self, width: i64, height: i64) {
+        self.viewport.set_size(width, height);
+        self.relayout();
+    }
+
+    /// Changes the number of device pixels per CSS pixel and lays out the page again, since media
+    /// queries may depend on it.
+    pub fn set_device_pixel_ratio(&mut self, device_pixel_ratio: f64) {
+        self.viewport.set_device_pixel_ratio(device_pixel_ratio);
+        self.relayout();
+    }
+
+    pub fn scroll_position(&self) -> LayoutPoint {
+        self.scroll_position
+    }
+
+    /// Scrolls the viewport to (`x`, `y`) in the document.
+    /// https://www.w3.org/TR/cssom-view-1/#dom-window-scroll
+    pub fn scroll_to(&mut self, x: i64, y: i64) {
+        self.scroll_by(x - self.scroll_position.x(), y - self.scroll_position.y());
+    }
+
+    /// Scrolls the viewport by (`dx`, `dy`). The scroll position is kept in the document.
+    /// https://www.w3.org/TR/cssom-view-1/#dom-window-scrollby
+    pub fn scroll_by(&mut self, dx: i64, dy: i64) {
+        let viewport_size = self.viewport_size();
+        let layout_view = match &mut self.layout_view {
+            Some(v) => v,
+            None => return,
+        };
+        let position = clamp_scroll_offset(
+            self.scroll_position,
+            dx,
+            dy,
+            viewport_size,
+            layout_view.document_size(),
+        );
+        if position != self.scroll_position {
+            self.scroll_position = position;
+            // Fixed and sticky boxes move with the viewport.
+            layout_view.set_scroll_position(position);
+            self.paint_tree();
+        }
+    }
+
+    /// Scrolls the innermost scroll container at `position` in the viewport, as a mouse wheel
+    /// does. The viewport is scrolled if there is no scroll container to scroll.
+    pub fn scroll_at(&mut self, position: (i64, i64), dx: i64, dy: i64) {
+        let position = self.to_document_position(position);
+        let scrolled = match &mut self.layout_view {
+            Some(v) => v.scroll_by(position, dx, dy),
+            None => return,
+        };
+        if scrolled {
+            self.paint_tree();
+        } else {
+            self.scroll_by(dx, dy);
+        }
+    }
+
+    fn viewport_size(&self) -> LayoutSize {
+        LayoutSize::new(self.viewport.width(), self.viewport.height())
+    }
+
+    fn to_document_position(&self, position: (i64, i64)) -> (i64, i64) {
+        (
+            position.0 + self.scroll_position.x(),
+            position.1 + self.scroll_position.y(),
+        )
+    }
+
+    fn relayout(&mut self) {
+        if self.layout_view.is_none() {
+            return;
+        }
+        self.set_layout_view();
+        self.paint_tree();
+    }
+
     pub fn display_items(&self) -> Vec<DisplayItem> {
         self.display_items.clone()
     }
 
+    /// Returns the rectangles of the viewport that have to be drawn again since the page was last
+    /// painted.
+    pub fn damage(&self) -> Vec<(LayoutPoint, LayoutSize)> {
+        self.damage.clone()
+    }
+
+    pub fn document(&self) -> Option<Rc<RefCell<Node>>> {
+        self.frame.as_ref().map(|frame| frame.borrow().document())
+    }
+
+    /// https://html.spec.whatwg.org/multipage/webappapis.html#update-the-rendering
+    /// Updates the rendering for the changes of the DOM tree since the last update. Only the
+    /// changed parts are styled and laid out again, and the page is painted only if any box is
+    /// damaged.
+    pub fn update_rendering(&mut self) {
+        let viewport = self.viewport_size();
+        let damage = match &mut self.layout_view {
+            Some(v) => v.update(&self.images),
+            None => return,
+        };
+        if damage.is_empty() {
+            self.damage = Vec::new();
+            return;
+        }
+        // The boxes may use images that aren't loaded yet, and then the page is laid out again.
+        if self.load_images() {
+            self.relayout();
+            return;
+        }
+        // The document may become shorter, and then the viewport is scrolled to be in it.
+        if let Some(layout_view) = &mut self.layout_view {
+            let position = clamp_scroll_offset(
+                self.scroll_position,
+                0,
+                0,
+                viewport,
+                layout_view.document_size(),
+            );
+            if position != self.scroll_position {
+                self.scroll_position = position;
+                layout_view.set_scroll_position(position);
+            }
+        }
+        self.paint_tree();
+    }
+
     pub fn clear_display_items(&mut self) {
         self.display_items = Vec::new();
     }
@@ -72,7 +258,22 @@ impl Page {
             None => return,
         };
 
-        let layout_view = LayoutView::new(dom, &style);
+        let mut layout_view = LayoutView::new(
+            dom,
+            &style,
+            self.viewport,
+            self.font_metrics.clone(),
+            &self.images,
+        );
+        // The document may become shorter, so the scroll position is kept in it.
+        self.scroll_position = clamp_scroll_offset(
+            self.scroll_position,
+            0,
+            0,
+            self.viewport_size(),
+            layout_view.document_size(),
+        );
+        layout_view.set_scroll_position(self.scroll_position);
         self.layout_view = Some(layout_view);
     }
 
@@ -81,9 +282,44 @@ impl Page {
     }
 
     pub fn receive_response(&mut self, response: HttpResponse) {
+        self.scroll_position = LayoutPoint::new(0, 0);
+        self.images = ImageStore::new();
         self.create_frame(response.body());
         self.set_layout_view();
+        // The page is laid out again with the images, which change the sizes of the boxes.
+        if self.load_images() {
+            self.set_layout_view();
+        }
         self.paint_tree();
+        // Nothing of the previous page may be left in the viewport.
+        self.damage = vec![(LayoutPoint::new(0, 0), self.viewport_size())];
+    }
+
+    /// https://html.spec.whatwg.org/multipage/images.html#update-the-image-data
+    /// Fetches and decodes the images that the page uses and that haven't been loaded yet.
+    /// Returns true if any image is loaded, successfully or not.
+    fn load_images(&mut self) -> bool {
+        let (image_loader, layout_view) = match (self.image_loader, &self.layout_view) {
+            (Some(loader), Some(view)) => (loader, view),
+            _ => return false,
+        };
+        let mut loaded = false;
+        for src in layout_view.image_urls() {
+            if self.images.contains(&src) {
+                continue;
+            }
+            let url = match &self.url {
+                Some(base) => Url::new(base.clone()).join(&src),
+                None => src.clone(),
+            };
+            let image = image_loader(url, IMAGE_ACCEPT)
+                .ok()
+                .filter(|response| response.status_code() == 200)
+                .and_then(|response| decode(&response.body_bytes()).ok());
+            self.images.insert(src, image);
+            loaded = true;
+        }
+        loaded
     }
 
     fn create_frame(&mut self, html: String) {
@@ -98,10 +334,58 @@ impl Page {
     }
 
     /// https://source.chromium.org/chromium/chromium/src/+/main:third_party/blink/renderer/core/frame/local_frame_view.h;drc=0e9a0b6e9bb6ec59521977eec805f5d0bca833e0;bpv=1;bpt=1;l=907
+    /// The display items are in the coordinates of the viewport. They are moved by the scroll
+    /// position, and the items outside of the viewport are dropped. Items in a transform are
+    /// kept because the transform can move them into the viewport. The damage is where the new
+    /// display items differ from the previous ones.
     fn paint_tree(&mut self) {
-        if let Some(layout_view) = &self.layout_view {
-            self.display_items = layout_view.paint();
+        let layout_view = match &self.layout_view {
+            Some(v) => v,
+            None => return,
+        };
+        let viewport = self.viewport_size();
+        let (dx, dy) = (-self.scroll_position.x(), -self.scroll_position.y());
+
+        let mut display_items = vec![DisplayItem::PushClip {
+            layout_point: LayoutPoint::new(0, 0),
+            layout_size: viewport,
+        }];
+        let mut transforms = 0;
+        for item in layout_view.paint() {
+            match item {
+                DisplayItem::PushTransform { .. } => transforms += 1,
+                DisplayItem::PopTransform => transforms -= 1,
+                _ => {}
+            }
+            let item = item.translated(dx, dy);
+            if transforms > 0 || !is_outside(&item, viewport) {
+                display_items.push(item);
+            }
         }
+        display_items.push(DisplayItem::PopClip);
+
+        // The viewport always has a vertical scrollbar when the document overflows it.
+        let document_size = layout_view.document_size();
+        for (axis, overflows) in [
+            (Axis::Vertical, document_size.height() > viewport.height()),
+            (Axis::Horizontal, document_size.width() > viewport.width()),
+        ] {
+            if overflows {
+                display_items.push(scrollbar(
+                    axis,
+                    LayoutPoint::new(0, 0),
+                    viewport,
+                    document_size,
+                    self.scroll_position,
+                ));
+            }
+        }
+        self.damage = damage(
+            &self.display_items,
+            &display_items,
+            self.font_metrics.as_ref(),
+        );
+        self.display_items = display_items;
     }
 
     #[allow(dead_code)]
@@ -119,13 +403,16 @@ impl Page {
 
         let mut runtime = JsRuntime::new(dom);
         runtime.execute(&ast);
+        self.update_rendering();
     }
 
+    /// Returns the link at `position` in the viewport.
     pub fn clicked(&self, position: (i64, i64)) -> Option<String> {
         let view = match &self.layout_view {
             Some(v) => v,
             None => return None,
         };
+        let position = self.to_document_position(position);
 
         if let Some(n) = view.find_node_by_position(position) {
             if let Some(parent) = n.borrow().parent().upgrade() {
@@ -141,8 +428,332 @@ impl Page {
     }
 }
 
+/// Returns true if `item` is entirely outside of the viewport of `viewport` and can't be seen.
+/// Clips, opacity groups and transforms are kept so that they stay balanced.
+fn is_outside(item: &DisplayItem, viewport: LayoutSize) -> bool {
+    let (point, size) = match item {
+        DisplayItem::Rect {
+            layout_point,
+            layout_size,
+            ..
+        }
+        | DisplayItem::RoundedRect {
+            layout_point,
+            layout_size,
+            ..
+        }
+        | DisplayItem::Bullet {
+            layout_point,
+            layout_size,
+            ..
+        }
+        | DisplayItem::Border {
+            layout_point,
+            layout_size,
+            ..
+        }
+        | DisplayItem::Img {
+            layout_point,
+            layout_size,
+            ..
+        }
+        | DisplayItem::BackgroundImage {
+            layout_point,
+            layout_size,
+            ..
+        } => (*layout_point, *layout_size),
+        // Text is measured by the font when it's drawn, so only the line is checked.
+        DisplayItem::Text {
+            layout_point, font, ..
+        } => (
+            *layout_point,
+            LayoutSize::new(viewport.width(), font.size()),
+        ),
+        _ => return false,
+    };
+    point.x() + size.width() < 0
+        || point.y() + size.height() < 0
+        || point.x() > viewport.width()
+        || point.y() > viewport.height()
+}
+
 impl Default for Page {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::api::get_element_by_id;
+    use crate::renderer::dom::api::set_attribute;
+    use crate::renderer::layout::computed_style::Color;
+    use alloc::format;
+    use alloc::string::ToString;
+
+    fn text_positions(page: &Page) -> Vec<(String, i64)> {
+        page.display_items()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } => Some((text.clone(), layout_point.y())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn load(page: &mut Page, html: &str) {
+        let response = HttpResponse::new(format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("failed to parse http response");
+        page.receive_response(response);
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut page = Page::new();
+        page.resize(600, 400);
+        load(
+            &mut page,
+            "<html><body><p>aaaa bbbb cccc dddd</p></body></html>",
+        );
+        assert_eq!(1, text_positions(&page).len());
+
+        // The text wraps at a narrower viewport.
+        page.resize(100, 400);
+        assert_eq!(100, page.viewport().width());
+        assert_eq!(2, text_positions(&page).len());
+
+        page.resize(600, 400);
+        assert_eq!(1, text_positions(&page).len());
+    }
+
+    #[test]
+    fn test_media_query_after_resize() {
+        let mut page = Page::new();
+        page.resize(300, 400);
+        load(
+            &mut page,
+            r#"<html><head><style>
+            @media (max-width: 400px) { p { display: none; } }
+            </style></head><body><p>text</p></body></html>"#,
+        );
+        // Media queries are evaluated against the viewport of the page.
+        assert!(text_positions(&page).is_empty());
+
+        page.resize(600, 400);
+        assert_eq!(1, text_positions(&page).len());
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut page = Page::new();
+        page.resize(600, 400);
+        load(
+            &mut page,
+            r#"<html><head><style>
+            body { margin: 0; }
+            p { margin: 0; }
+            .tall { height: 1000px; }
+            </style></head><body><p>top</p><p class="tall"></p><p>bottom</p></body></html>"#,
+        );
+        assert_eq!(vec![("top".to_string(), 2)], text_positions(&page));
+        assert!(page.display_items().contains(&DisplayItem::Scrollbar {
+            track_point: LayoutPoint::new(592, 0),
+            track_size: LayoutSize::new(8, 400),
+            thumb_point: LayoutPoint::new(592, 0),
+            thumb_size: LayoutSize::new(8, 153),
+        }));
+
+        page.scroll_by(0, 100);
+        assert_eq!(LayoutPoint::new(0, 100), page.scroll_position());
+        assert!(text_positions(&page).is_empty());
+
+        // The scroll position is kept in the document of 1040px.
+        page.scroll_by(0, 10000);
+        assert_eq!(LayoutPoint::new(0, 640), page.scroll_position());
+        assert_eq!(vec![("bottom".to_string(), 382)], text_positions(&page));
+        page.scroll_to(0, -10);
+        assert_eq!(LayoutPoint::new(0, 0), page.scroll_position());
+
+        // The document is shorter at a wider viewport.
+        page.scroll_to(0, 640);
+        page.resize(600, 1000);
+        assert_eq!(LayoutPoint::new(0, 40), page.scroll_position());
+    }
+
+    #[test]
+    fn test_scroll_container() {
+        let mut page = Page::new();
+        page.resize(600, 400);
+        load(
+            &mut page,
+            r#"<html><head><style>
+            body { margin: 0 0 1000px 0; height: 100px; overflow: auto; }
+            p { margin: 0; }
+            .tall { height: 300px; }
+            </style></head><body><p class="tall"></p><p><a href="link">link</a></p></body></html>"#,
+        );
+        // The link is clipped by the body.
+        assert!(page.display_items().contains(&DisplayItem::PushClip {
+            layout_point: LayoutPoint::new(0, 0),
+            layout_size: LayoutSize::new(600, 100),
+        }));
+        assert_eq!(vec![("link".to_string(), 302)], text_positions(&page));
+        assert_eq!(None, page.clicked((10, 305)));
+
+        // The wheel scrolls the body until its end, and then the viewport.
+        page.scroll_at((10, 10), 0, 150);
+        assert_eq!(LayoutPoint::new(0, 0), page.scroll_position());
+        assert_eq!(vec![("link".to_string(), 152)], text_positions(&page));
+        page.scroll_at((10, 10), 0, 150);
+        assert_eq!(vec![("link".to_string(), 82)], text_positions(&page));
+        assert_eq!(Some("link".to_string()), page.clicked((10, 85)));
+        page.scroll_at((10, 10), 0, 150);
+        assert_eq!(LayoutPoint::new(0, 150), page.scroll_position());
+        assert!(text_positions(&page).is_empty());
+
+        // The wheel outside of the body scrolls the viewport.
+        page.scroll_at((10, 300), 0, -150);
+        assert_eq!(LayoutPoint::new(0, 0), page.scroll_position());
+    }
+
+    #[test]
+    fn test_fixed_position() {
+        let mut page = Page::new();
+        page.resize(600, 400);
+        load(
+            &mut page,
+            r#"<html><head><style>
+            body { margin: 0 0 1000px 0; }
+            p { margin: 0; }
+            .fixed { position: fixed; top: 0; right: 0; }
+            </style></head><body><p class="fixed">fixed</p><p>text</p></body></html>"#,
+        );
+        assert_eq!(
+            vec![("text".to_string(), 2), ("fixed".to_string(), 2)],
+            text_positions(&page)
+        );
+
+        // A fixed box stays in the viewport while the document is scrolled.
+        page.scroll_by(0, 10);
+        assert_eq!(
+            vec![("text".to_string(), -8), ("fixed".to_string(), 2)],
+            text_positions(&page)
+        );
+    }
+
+    #[test]
+    fn test_update_rendering() {
+        let mut page = Page::new();
+        page.resize(600, 400);
+        load(
+            &mut page,
+            r#"<html><head><style>
+            body { margin: 0 0 1000px 0; }
+            p { margin: 0; }
+            .red { color: red; }
+            </style></head><body><p>top</p><p id="target">text</p></body></html>"#,
+        );
+        assert_eq!(
+            vec![(LayoutPoint::new(0, 0), LayoutSize::new(600, 400))],
+            page.damage()
+        );
+        page.update_rendering();
+        assert!(page.damage().is_empty());
+
+        // Only the visible part of the changed text is damaged, in the coordinates of the
+        // viewport.
+        page.scroll_by(0, 30);
+        let target = get_element_by_id(page.document(), &"target".to_string()).unwrap();
+        set_attribute(&target, "class", "red".to_string());
+        page.update_rendering();
+        assert_eq!(
+            vec![(LayoutPoint::new(0, 0), LayoutSize::new(33, 8))],
+            page.damage()
+        );
+        assert!(page.display_items().iter().any(|item| matches!(
+            item,
+            DisplayItem::Text { text, color, .. }
+                if text == "text" && *color == Color::from_name("red").unwrap()
+        )));
+    }
+
+    /// Serves a 2x1 BMP of red pixels at /images/red.bmp, and nothing else.
+    fn load_image(url: String, accept: &str) -> Result<HttpResponse, Error> {
+        assert_eq!(IMAGE_ACCEPT, accept);
+        if url != "http://example.com/images/red.bmp" {
+            return HttpResponse::new("HTTP/1.1 404 Not Found\n\n".to_string());
+        }
+        let mut bytes = b"HTTP/1.1 200 OK\r\n\r\nBM".to_vec();
+        bytes.extend_from_slice(&[
+            62, 0, 0, 0, 0, 0, 0, 0, 54, 0, 0, 0, 40, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 1, 0, 24, 0,
+        ]);
+        bytes.extend_from_slice(&[0; 24]);
+        bytes.extend_from_slice(&[0, 0, 255, 0, 0, 255, 0, 0]);
+        HttpResponse::from_bytes(bytes)
+    }
+
+    #[test]
+    fn test_load_images() {
+        let mut page = Page::new();
+        page.resize(600, 400);
+        page.set_url("http://example.com/index.html".to_string());
+        page.set_image_loader(load_image);
+        load(
+            &mut page,
+            r#"<html><head><style>body { margin: 0; }</style></head><body>
+            <img src="images/red.bmp" width="20"><img src="missing.png" alt="missing">
+            </body></html>"#,
+        );
+        let images: Vec<(u32, LayoutPoint, LayoutSize)> = page
+            .display_items()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Img {
+                    image,
+                    layout_point,
+                    layout_size,
+                } => Some((image.pixel(1, 0), *layout_point, *layout_size)),
+                _ => None,
+            })
+            .collect();
+        // The height keeps the ratio of the image, and the image sits on the baseline.
+        assert_eq!(
+            vec![(0xffff0000, LayoutPoint::new(0, 4), LayoutSize::new(20, 10))],
+            images
+        );
+        // The broken image shows its alt text.
+        assert!(text_positions(&page)
+            .iter()
+            .any(|(text, _)| text == "missing"));
+    }
+
+    #[test]
+    fn test_change_image_source() {
+        let mut page = Page::new();
+        page.resize(600, 400);
+        page.set_url("http://example.com/index.html".to_string());
+        page.set_image_loader(load_image);
+        load(
+            &mut page,
+            r#"<html><head><style>body { margin: 0; }</style></head><body>
+            <img id="image" src="missing.png" width="20">
+            </body></html>"#,
+        );
+        assert!(!page
+            .display_items()
+            .iter()
+            .any(|item| matches!(item, DisplayItem::Img { .. })));
+
+        // The new image is fetched and laid out.
+        let image = get_element_by_id(page.document(), &"image".to_string()).unwrap();
+        set_attribute(&image, "src", "images/red.bmp".to_string());
+        page.update_rendering();
+        assert!(page.display_items().iter().any(|item| matches!(
+            item,
+            DisplayItem::Img { layout_size, .. } if *layout_size == LayoutSize::new(20, 10)
+        )));
+    }
+}
@@ -1,3 +1,4 @@
+use alloc::format;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
@@ -54,6 +55,29 @@ impl Url {
         self.searchpart = self.extract_searchpart();
         Ok(self.clone())
     }
+    /// https://url.spec.whatwg.org/#concept-basic-url-parser
+    /// Resolves `reference`, a URL written in a document, against this URL. An absolute path
+    /// replaces the path, and a relative path replaces the last segment of it. "." and ".."
+    /// segments are kept as they are.
+    pub fn join(&self, reference: &str) -> String {
+        if reference.contains("://") {
+            return reference.to_string();
+        }
+        if let Some(rest) = reference.strip_prefix("//") {
+            return format!("http://{}", rest);
+        }
+        let rest = self.url.trim_start_matches("http://");
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        if let Some(path) = reference.strip_prefix('/') {
+            return format!("http://{}/{}", authority, path);
+        }
+        let path = path.split('?').next().unwrap_or("");
+        let directory = match path.rfind('/') {
+            Some(i) => &path[..i + 1],
+            None => "",
+        };
+        format!("http://{}/{}{}", authority, directory, reference)
+    }
     fn is_http(&mut self) -> bool {
         if self.url.contains("http://") {
             return true;
@@ -196,4 +220,16 @@ mod tests {
         let expected = Err("Only HTTP scheme is supported.".to_string());
         assert_eq!(expected, Url::new(url_str).parse());
     }
+    #[test]
+    fn test_join() {
+        let url = Url::new("http://example.com:8000/dir/page.html?q=1".to_string());
+        assert_eq!("http://example.com:8000/dir/a.png", url.join("a.png"));
+        assert_eq!("http://example.com:8000/img/a.png", url.join("/img/a.png"));
+        assert_eq!("http://other.com/a.png", url.join("//other.com/a.png"));
+        assert_eq!("http://other.com/a.png", url.join("http://other.com/a.png"));
+        assert_eq!(
+            "http://example.com/a.png",
+            Url::new("http://example.com".to_string()).join("a.png")
+        );
+    }
 }
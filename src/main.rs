@@ -16,7 +16,7 @@ use saba_core::http::HttpResponse;
 use saba_core::url::Url;
 use ui_wasabi::app::WasabiUI;
 
-fn handle_url(url: String) -> Result<HttpResponse, Error> {
+fn handle_url(url: String, accept: &str) -> Result<HttpResponse, Error> {
     // URLを解釈する
     let parsed_url = match Url::new(url.to_string()).parse() {
         Ok(url) => url,
@@ -37,6 +37,7 @@ fn handle_url(url: String) -> Result<HttpResponse, Error> {
             parsed_url.port()
         )),
         parsed_url.path(),
+        accept,
     ) {
         Ok(res) => {
             // HTTPレスポンスのステータスコードが302のとき、転送する（リダイレクト）
@@ -54,6 +55,7 @@ fn handle_url(url: String) -> Result<HttpResponse, Error> {
                         parsed_url.port()
                     )),
                     redirect_parsed_url.path(),
+                    accept,
                 ) {
                     Ok(res) => res,
                     Err(e) => return Err(Error::Network(format!("{:?}", e))),
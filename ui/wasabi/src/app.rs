@@ -1,8 +1,25 @@
 use crate::alloc::string::ToString;
+use crate::constants::ADDRESS_BAR_HEIGHT;
+use crate::constants::BLACK;
+use crate::constants::CONTENT_AREA_HEIGHT;
+use crate::constants::CONTENT_AREA_WIDTH;
+use crate::constants::DARK_GRAY;
+use crate::constants::GRAY;
+use crate::constants::LIGHT_GRAY;
+use crate::constants::TITLE_BAR_HEIGHT;
+use crate::constants::TOOLBAR_HEIGHT;
+use crate::constants::WHITE;
+use crate::constants::WINDOW_HEIGHT;
+use crate::constants::WINDOW_INIT_X_POS;
+use crate::constants::WINDOW_INIT_Y_POS;
+use crate::constants::WINDOW_PADDING;
+use crate::constants::WINDOW_WIDTH;
 use crate::cursor::Cursor;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 use core::cell::Ref;
 use core::cell::RefCell;
 use noli::error::Result as OsResult;
@@ -14,26 +31,24 @@ use noli::sys::wasabi::Api;
 use noli::window::StringSize;
 use noli::window::Window;
 use saba_core::browser::Browser;
-use saba_core::constants::ADDRESS_BAR_HEIGHT;
-use saba_core::constants::BLACK;
-use saba_core::constants::CONTENT_AREA_HEIGHT;
-use saba_core::constants::CONTENT_AREA_WIDTH;
-use saba_core::constants::DARK_GRAY;
-use saba_core::constants::GRAY;
-use saba_core::constants::LIGHT_GRAY;
-use saba_core::constants::TITLE_BAR_HEIGHT;
-use saba_core::constants::TOOLBAR_HEIGHT;
-use saba_core::constants::WHITE;
-use saba_core::constants::WINDOW_HEIGHT;
-use saba_core::constants::WINDOW_INIT_X_POS;
-use saba_core::constants::WINDOW_INIT_Y_POS;
-use saba_core::constants::WINDOW_PADDING;
-use saba_core::constants::WINDOW_WIDTH;
+use saba_core::display_item::BorderSides;
+use saba_core::display_item::BulletShape;
+use saba_core::display_item::CornerRadii;
 use saba_core::display_item::DisplayItem;
 use saba_core::error::Error;
 use saba_core::http::HttpResponse;
-use saba_core::renderer::layout::computed_style::FontSize;
+use saba_core::http::DOCUMENT_ACCEPT;
+use saba_core::renderer::image::Image;
+use saba_core::renderer::layout::computed_style::BorderStyle;
+use saba_core::renderer::layout::computed_style::Side;
 use saba_core::renderer::layout::computed_style::TextDecoration;
+use saba_core::renderer::layout::layout_object::LayoutPoint;
+use saba_core::renderer::layout::layout_object::LayoutSize;
+use saba_core::renderer::layout::scroll::SCROLLBAR_WIDTH;
+use saba_core::renderer::layout::transform::Transform;
+
+/// How far a key press scrolls the page in px.
+const SCROLL_STEP: i64 = 40;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum InputMode {
@@ -48,11 +63,29 @@ pub struct WasabiUI {
     input_mode: InputMode,
     window: Window,
     cursor: Cursor,
+    /// The position of the mouse cursor in the content area. Scroll keys scroll the scroll
+    /// container under it, as a mouse wheel does.
+    mouse_position: (i64, i64),
+    /// The clip rectangles of the display items being drawn, in the coordinates of the content
+    /// area. The last one is the intersection of all of them.
+    clips: Vec<(i64, i64, i64, i64)>,
+    /// The transforms of the display items being drawn, each combined with the ones around it.
+    /// The last one maps the coordinates of the items to the content area.
+    transforms: Vec<Transform>,
+    /// The opacities of the groups being drawn. The last one is the product of all of them.
+    opacities: Vec<f64>,
 }
 
 #[allow(dead_code)]
 impl WasabiUI {
     pub fn new(browser: Rc<RefCell<Browser>>) -> Self {
+        // Pages are laid out in the content area below the toolbar.
+        browser
+            .borrow()
+            .current_page()
+            .borrow_mut()
+            .resize(CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT);
+
         Self {
             browser,
             input_url: String::new(),
@@ -67,15 +100,28 @@ impl WasabiUI {
             )
             .unwrap(),
             cursor: Cursor::new(),
+            mouse_position: (0, 0),
+            clips: Vec::new(),
+            transforms: Vec::new(),
+            opacities: Vec::new(),
         }
     }
     fn handle_key_input(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: fn(String, &str) -> Result<HttpResponse, Error>,
     ) -> Result<(), Error> {
         match self.input_mode {
             InputMode::Normal => {
-                let _ = Api::read_key();
+                // j and k scroll by a line, and the space key scrolls by a screen.
+                let dy = match Api::read_key() {
+                    Some('j') => SCROLL_STEP,
+                    Some('k') => -SCROLL_STEP,
+                    Some(' ') => CONTENT_AREA_HEIGHT - SCROLL_STEP,
+                    _ => return Ok(()),
+                };
+                let page = self.browser.borrow().current_page();
+                page.borrow_mut().scroll_at(self.mouse_position, 0, dy);
+                self.update_ui()?;
             }
             InputMode::Editing => {
                 if let Some(c) = Api::read_key() {
@@ -98,17 +144,18 @@ impl WasabiUI {
 
     fn start_navigation(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: fn(String, &str) -> Result<HttpResponse, Error>,
         destination: String,
     ) -> Result<(), Error> {
-        self.clear_content_area()?;
-
-        match handle_url(destination) {
+        match handle_url(destination.clone(), DOCUMENT_ACCEPT) {
             Ok(response) => {
                 // Borrow トレイトを使わず RefCell::borrow() を呼びたいので (*self.browser)
                 // を経由して RefCell にアクセスする
                 let browser_ref: Ref<Browser> = (*self.browser).borrow();
                 let page = browser_ref.current_page();
+                // Images in the page are fetched in the same way, relative to the page.
+                page.borrow_mut().set_url(destination);
+                page.borrow_mut().set_image_loader(handle_url);
                 page.borrow_mut().receive_response(response);
             }
             Err(e) => {
@@ -120,25 +167,6 @@ impl WasabiUI {
         Ok(())
     }
 
-    fn clear_content_area(&mut self) -> Result<(), Error> {
-        if self
-            .window
-            .fill_rect(
-                WHITE,
-                0,
-                TOOLBAR_HEIGHT + 2,
-                CONTENT_AREA_WIDTH,
-                CONTENT_AREA_HEIGHT - 2,
-            )
-            .is_err()
-        {
-            return Err(Error::InvalidUI(
-                "failed to clear a content area".to_string(),
-            ));
-        }
-        self.window.flush();
-        Ok(())
-    }
     fn update_address_bar(&mut self) -> Result<(), Error> {
         if self
             .window
@@ -201,13 +229,17 @@ impl WasabiUI {
     }
     fn handle_mouse_input(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: fn(String, &str) -> Result<HttpResponse, Error>,
     ) -> Result<(), Error> {
         if let Some(MouseEvent { button, position }) = Api::get_mouse_cursor_info() {
             self.window.flush_area(self.cursor.rect());
             self.cursor.set_position(position.x, position.y);
             self.window.flush_area(self.cursor.rect());
             self.cursor.flush();
+            self.mouse_position = (
+                position.x - WINDOW_INIT_X_POS,
+                position.y - WINDOW_INIT_Y_POS - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT,
+            );
 
             if button.l() || button.c() || button.r() {
                 println!("mouse clicked {:?}", button);
@@ -246,6 +278,37 @@ impl WasabiUI {
                     relative_pos.1 - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT,
                 );
                 let page = self.browser.borrow().current_page();
+
+                // A click on the scrollbar of the viewport scrolls by a screen toward the click.
+                if position_in_content_area.0 >= CONTENT_AREA_WIDTH - SCROLLBAR_WIDTH {
+                    let thumb =
+                        page.borrow()
+                            .display_items()
+                            .into_iter()
+                            .find_map(|item| match item {
+                                DisplayItem::Scrollbar {
+                                    track_point,
+                                    thumb_point,
+                                    thumb_size,
+                                    ..
+                                } if track_point.x() == CONTENT_AREA_WIDTH - SCROLLBAR_WIDTH => {
+                                    Some((thumb_point.y(), thumb_point.y() + thumb_size.height()))
+                                }
+                                _ => None,
+                            });
+                    if let Some((top, bottom)) = thumb {
+                        let y = position_in_content_area.1;
+                        let page_step = CONTENT_AREA_HEIGHT - SCROLL_STEP;
+                        if y < top {
+                            page.borrow_mut().scroll_by(0, -page_step);
+                        } else if y > bottom {
+                            page.borrow_mut().scroll_by(0, page_step);
+                        }
+                        self.update_ui()?;
+                        return Ok(());
+                    }
+                }
+
                 let next_destination = page.borrow_mut().clicked(position_in_content_area);
 
                 if let Some(url) = next_destination {
@@ -272,7 +335,7 @@ impl WasabiUI {
 
     pub fn start(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: fn(String, &str) -> Result<HttpResponse, Error>,
     ) -> Result<(), Error> {
         self.setup()?;
         self.run_app(handle_url)?;
@@ -281,7 +344,7 @@ impl WasabiUI {
 
     fn run_app(
         &mut self,
-        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: fn(String, &str) -> Result<HttpResponse, Error>,
     ) -> Result<(), Error> {
         loop {
             self.handle_key_input(handle_url)?;
@@ -289,64 +352,563 @@ impl WasabiUI {
         }
     }
 
+    /// Draws the damaged rectangles of the page again and flushes only them to the screen, so the
+    /// rest of the content area doesn't flicker.
     fn update_ui(&mut self) -> Result<(), Error> {
-        let display_items = self
-            .browser
-            .borrow()
-            .current_page()
-            .borrow()
-            .display_items();
+        let (display_items, damage) = {
+            let browser = self.browser.borrow();
+            let page = browser.current_page();
+            let page = page.borrow();
+            (page.display_items(), page.damage())
+        };
+
+        for (point, size) in damage {
+            let (x, y) = (point.x().max(0), point.y().max(0));
+            let width = (point.x() + size.width()).min(CONTENT_AREA_WIDTH) - x;
+            let height = (point.y() + size.height()).min(CONTENT_AREA_HEIGHT) - y;
+            if width <= 0 || height <= 0 {
+                continue;
+            }
+            // Every item is drawn in the clip of the damaged rectangle, over a white background.
+            self.clips = vec![(x, y, width, height)];
+            self.transforms = Vec::new();
+            self.opacities = Vec::new();
+            self.fill_content_rect(WHITE, x, y, width, height)?;
+            self.draw_display_items(&display_items)?;
+
+            self.window.flush_area(
+                Rect::new(
+                    x + WINDOW_INIT_X_POS + WINDOW_PADDING,
+                    y + WINDOW_INIT_Y_POS + TITLE_BAR_HEIGHT + TOOLBAR_HEIGHT + WINDOW_PADDING,
+                    width,
+                    height,
+                )
+                .expect("failed to create a rect for the damaged area"),
+            );
+        }
 
-        for item in display_items {
+        Ok(())
+    }
+
+    fn draw_display_items(&mut self, display_items: &[DisplayItem]) -> Result<(), Error> {
+        for item in display_items.iter().cloned() {
             match item {
                 DisplayItem::Text {
                     text,
-                    style,
+                    font,
+                    color,
+                    decoration,
                     layout_point,
                 } => {
-                    if self
-                        .window
-                        .draw_string(
-                            style.color().code_u32(),
-                            layout_point.x() + WINDOW_PADDING,
-                            layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
-                            &text,
-                            convert_font_size(style.font_size()),
-                            style.text_decoration() == TextDecoration::Underline,
-                        )
-                        .is_err()
-                    {
-                        return Err(Error::InvalidUI("failed to draw a string".to_string()));
+                    // The built-in font has no bold face, so bold text is drawn twice with an
+                    // offset of 1px.
+                    // The built-in font can't be drawn partially, so text that is cut by a clip
+                    // isn't drawn. Glyphs can't be scaled or rotated either, so transformed text
+                    // is only moved.
+                    let (x, y) = self
+                        .transform()
+                        .apply(layout_point.x() as f64, layout_point.y() as f64);
+                    let layout_point = LayoutPoint::new(x as i64, y as i64);
+                    if !self.is_line_in_clip(layout_point, font.size()) {
+                        continue;
+                    }
+                    let offsets: &[i64] = if font.is_bold() { &[0, 1] } else { &[0] };
+                    for offset in offsets {
+                        if self
+                            .window
+                            .draw_string(
+                                self.fade(color.code_u32()),
+                                layout_point.x() + WINDOW_PADDING + offset,
+                                layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                                &text,
+                                convert_font_size(font.size()),
+                                decoration == TextDecoration::Underline,
+                            )
+                            .is_err()
+                        {
+                            return Err(Error::InvalidUI("failed to draw a string".to_string()));
+                        }
                     }
                 }
                 DisplayItem::Rect {
-                    style,
+                    color,
                     layout_point,
                     layout_size,
                 } => {
-                    if self
-                        .window
-                        .fill_rect(
-                            style.background_color().code_u32(),
-                            layout_point.x() + WINDOW_PADDING,
-                            layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
-                            layout_size.width(),
-                            layout_size.height(),
-                        )
-                        .is_err()
-                    {
-                        return Err(Error::InvalidUI("failed to draw a string".to_string()));
-                    }
+                    self.fill_content_rect(
+                        color.code_u32(),
+                        layout_point.x(),
+                        layout_point.y(),
+                        layout_size.width(),
+                        layout_size.height(),
+                    )?;
+                }
+                DisplayItem::RoundedRect {
+                    color,
+                    radii,
+                    layout_point,
+                    layout_size,
+                } => {
+                    self.fill_rounded_rect(color.code_u32(), &radii, layout_point, layout_size)?;
+                }
+                DisplayItem::Border {
+                    sides,
+                    radii,
+                    layout_point,
+                    layout_size,
+                } => {
+                    self.draw_border(&sides, &radii, layout_point, layout_size)?;
+                }
+                DisplayItem::Bullet {
+                    color,
+                    shape,
+                    layout_point,
+                    layout_size,
+                } => {
+                    self.draw_bullet(color.code_u32(), shape, layout_point, layout_size)?;
+                }
+                DisplayItem::Img {
+                    image,
+                    layout_point,
+                    layout_size,
+                } => {
+                    self.draw_image(&image, layout_point, layout_size)?;
+                }
+                DisplayItem::BackgroundImage {
+                    image,
+                    origin,
+                    layout_point,
+                    layout_size,
+                } => {
+                    self.draw_background_image(&image, origin, layout_point, layout_size)?;
+                }
+                DisplayItem::PushClip {
+                    layout_point,
+                    layout_size,
+                } => {
+                    let (x, y, width, height) = self.to_content_rect(
+                        layout_point.x(),
+                        layout_point.y(),
+                        layout_size.width(),
+                        layout_size.height(),
+                    );
+                    let clip = self.clip_rect(x, y, width, height);
+                    self.clips.push(clip);
+                }
+                DisplayItem::PopClip => {
+                    self.clips.pop();
+                }
+                DisplayItem::PushOpacity { opacity } => {
+                    let opacity = self.opacity() * opacity;
+                    self.opacities.push(opacity);
+                }
+                DisplayItem::PopOpacity => {
+                    self.opacities.pop();
+                }
+                DisplayItem::PushTransform { transform, origin } => {
+                    let transform = self.transform().multiply(&transform.about(origin));
+                    self.transforms.push(transform);
+                }
+                DisplayItem::PopTransform => {
+                    self.transforms.pop();
+                }
+                DisplayItem::Scrollbar {
+                    track_point,
+                    track_size,
+                    thumb_point,
+                    thumb_size,
+                } => {
+                    self.fill_content_rect(
+                        LIGHT_GRAY,
+                        track_point.x(),
+                        track_point.y(),
+                        track_size.width(),
+                        track_size.height(),
+                    )?;
+                    self.fill_content_rect(
+                        GRAY,
+                        thumb_point.x(),
+                        thumb_point.y(),
+                        thumb_size.width(),
+                        thumb_size.height(),
+                    )?;
                 }
                 _ => {}
             }
         }
 
-        self.window.flush();
+        Ok(())
+    }
+
+    /// Returns the intersection of (`x`, `y`, `width`, `height`) and the current clip.
+    fn clip_rect(&self, x: i64, y: i64, width: i64, height: i64) -> (i64, i64, i64, i64) {
+        match self.clips.last() {
+            Some(&(cx, cy, cw, ch)) => {
+                let left = x.max(cx);
+                let top = y.max(cy);
+                let right = (x + width).min(cx + cw);
+                let bottom = (y + height).min(cy + ch);
+                (left, top, right - left, bottom - top)
+            }
+            None => (x, y, width, height),
+        }
+    }
+
+    /// Returns true if a line of text of `size` px at `point` is in the current clip vertically and
+    /// starts in it horizontally.
+    fn is_line_in_clip(&self, point: LayoutPoint, size: i64) -> bool {
+        match self.clips.last() {
+            Some(&(cx, cy, cw, ch)) => {
+                cx <= point.x()
+                    && point.x() < cx + cw
+                    && cy <= point.y()
+                    && point.y() + size <= cy + ch
+            }
+            None => true,
+        }
+    }
+
+    /// The current transform from the coordinates of the display items to the content area.
+    fn transform(&self) -> Transform {
+        self.transforms
+            .last()
+            .copied()
+            .unwrap_or_else(Transform::identity)
+    }
 
+    /// The current opacity, which is the product of the opacities of all groups being drawn.
+    fn opacity(&self) -> f64 {
+        self.opacities.last().copied().unwrap_or(1.0)
+    }
+
+    /// https://www.w3.org/TR/compositing-1/#simplealphacompositing
+    /// Returns `color` faded by the current opacity. The window can't read back its pixels, so
+    /// the color is blended over the white background of the content area instead of over what
+    /// is drawn behind it.
+    fn fade(&self, color: u32) -> u32 {
+        let opacity = self.opacity();
+        if opacity >= 1.0 {
+            return color;
+        }
+        let channel = |shift: u32| {
+            let c = ((color >> shift) & 0xff) as f64;
+            (c * opacity + 255.0 * (1.0 - opacity) + 0.5) as u32
+        };
+        (channel(16) << 16) | (channel(8) << 8) | channel(0)
+    }
+
+    /// Maps a rectangle in the coordinates of the display items to the content area by the
+    /// current transform. A rotated rectangle is approximated by its bounding box.
+    fn to_content_rect(&self, x: i64, y: i64, width: i64, height: i64) -> (i64, i64, i64, i64) {
+        let transform = self.transform();
+        if transform.is_identity() {
+            return (x, y, width, height);
+        }
+        let (point, size) =
+            transform.bounding_box(LayoutPoint::new(x, y), LayoutSize::new(width, height));
+        (point.x(), point.y(), size.width(), size.height())
+    }
+
+    /// Fills a rectangle of the display items in the content area. It's transformed by the
+    /// current transform, faded by the current opacity and clipped to the current clip.
+    fn fill_content_rect(
+        &mut self,
+        color: u32,
+        x: i64,
+        y: i64,
+        width: i64,
+        height: i64,
+    ) -> Result<(), Error> {
+        let color = self.fade(color);
+        let (x, y, width, height) = self.to_content_rect(x, y, width, height);
+        let (x, y, width, height) = self.clip_rect(x, y, width, height);
+        if width <= 0 || height <= 0 {
+            return Ok(());
+        }
+        if self
+            .window
+            .fill_rect(
+                color,
+                x + WINDOW_PADDING,
+                y + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                width,
+                height,
+            )
+            .is_err()
+        {
+            return Err(Error::InvalidUI("failed to draw a rect".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Fills a rectangle with rounded corners row by row. Each row is shortened by the curves of
+    /// the corners it crosses, and the rows of the same length are filled at once.
+    fn fill_rounded_rect(
+        &mut self,
+        color: u32,
+        radii: &CornerRadii,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    ) -> Result<(), Error> {
+        let (x, y) = (layout_point.x(), layout_point.y());
+        let (w, h) = (layout_size.width(), layout_size.height());
+        let mut row = 0;
+        while row < h {
+            let (left, right) = radii.insets_at(row, layout_size);
+            let mut end = row + 1;
+            while end < h && radii.insets_at(end, layout_size) == (left, right) {
+                end += 1;
+            }
+            self.fill_content_rect(color, x + left, y + row, w - left - right, end - row)?;
+            row = end;
+        }
         Ok(())
     }
 
+    /// Draws the four sides of a border inside the border box. Dashed and dotted borders are
+    /// drawn as a sequence of segments and double borders as two lines.
+    fn draw_border(
+        &mut self,
+        sides: &BorderSides,
+        radii: &CornerRadii,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    ) -> Result<(), Error> {
+        if !radii.is_zero() {
+            return self.draw_rounded_border(sides, radii, layout_point, layout_size);
+        }
+        let (x, y) = (layout_point.x(), layout_point.y());
+        let (w, h) = (layout_size.width(), layout_size.height());
+
+        for side in Side::ALL {
+            let border = sides.side(side);
+            let bw = border.width;
+            if bw <= 0 {
+                continue;
+            }
+            let color = border.color.code_u32();
+            // (x, y, width, height) of the side and whether it runs horizontally.
+            let (sx, sy, sw, sh, horizontal) = match side {
+                Side::Top => (x, y, w, bw, true),
+                Side::Bottom => (x, y + h - bw, w, bw, true),
+                Side::Left => (x, y, bw, h, false),
+                Side::Right => (x + w - bw, y, bw, h, false),
+            };
+
+            match border.style {
+                BorderStyle::Dashed | BorderStyle::Dotted => {
+                    let segment = if border.style == BorderStyle::Dashed {
+                        bw * 3
+                    } else {
+                        bw
+                    };
+                    let length = if horizontal { sw } else { sh };
+                    let mut offset = 0;
+                    while offset < length {
+                        let l = segment.min(length - offset);
+                        if horizontal {
+                            self.fill_content_rect(color, sx + offset, sy, l, sh)?;
+                        } else {
+                            self.fill_content_rect(color, sx, sy + offset, sw, l)?;
+                        }
+                        offset += segment * 2;
+                    }
+                }
+                BorderStyle::Double if bw >= 3 => {
+                    let line = bw / 3;
+                    if horizontal {
+                        self.fill_content_rect(color, sx, sy, sw, line)?;
+                        self.fill_content_rect(color, sx, sy + bw - line, sw, line)?;
+                    } else {
+                        self.fill_content_rect(color, sx, sy, line, sh)?;
+                        self.fill_content_rect(color, sx + bw - line, sy, line, sh)?;
+                    }
+                }
+                _ => self.fill_content_rect(color, sx, sy, sw, sh)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// https://www.w3.org/TR/css-backgrounds-3/#corner-shaping
+    /// Draws a border with rounded corners row by row. Each row is drawn between the outer edge
+    /// and the padding edge, whose radii are "the outer border radius minus the corresponding
+    /// border thickness". The parts are drawn in the colors of the sides they are on, and all
+    /// styles are drawn as solid.
+    fn draw_rounded_border(
+        &mut self,
+        sides: &BorderSides,
+        radii: &CornerRadii,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    ) -> Result<(), Error> {
+        let (x, y) = (layout_point.x(), layout_point.y());
+        let (w, h) = (layout_size.width(), layout_size.height());
+        let (top, right, bottom, left) = (
+            sides.top.width,
+            sides.right.width,
+            sides.bottom.width,
+            sides.left.width,
+        );
+        let inner_size = LayoutSize::new(w - left - right, h - top - bottom);
+        let shrink = |(rx, ry): (i64, i64), bx: i64, by: i64| ((rx - bx).max(0), (ry - by).max(0));
+        let inner = CornerRadii {
+            top_left: shrink(radii.top_left, left, top),
+            top_right: shrink(radii.top_right, right, top),
+            bottom_right: shrink(radii.bottom_right, right, bottom),
+            bottom_left: shrink(radii.bottom_left, left, bottom),
+        };
+
+        for row in 0..h {
+            let (outer_left, outer_right) = radii.insets_at(row, layout_size);
+            let (start, end) = (x + outer_left, x + w - outer_right);
+            let inner_row = row - top;
+            if inner_row < 0 || inner_row >= inner_size.height() || inner_size.width() <= 0 {
+                let color = if inner_row < 0 {
+                    sides.top.color.code_u32()
+                } else {
+                    sides.bottom.color.code_u32()
+                };
+                self.fill_content_rect(color, start, y + row, end - start, 1)?;
+                continue;
+            }
+            let (inner_left, inner_right) = inner.insets_at(inner_row, inner_size);
+            let inner_start = x + left + inner_left;
+            let inner_end = x + w - right - inner_right;
+            self.fill_content_rect(
+                sides.left.color.code_u32(),
+                start,
+                y + row,
+                inner_start - start,
+                1,
+            )?;
+            self.fill_content_rect(
+                sides.right.color.code_u32(),
+                inner_end,
+                y + row,
+                end - inner_end,
+                1,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draws the bullet of a list marker. A disc and a circle are drawn row by row, where each
+    /// row spans the chord of the circle at its middle.
+    fn draw_bullet(
+        &mut self,
+        color: u32,
+        shape: BulletShape,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    ) -> Result<(), Error> {
+        let (x, y) = (layout_point.x(), layout_point.y());
+        let size = layout_size.width().min(layout_size.height());
+        if shape == BulletShape::Square {
+            return self.fill_content_rect(color, x, y, size, size);
+        }
+
+        // In doubled coordinates, where the center can be between pixels, the radius is `size`
+        // and half of a chord is as long as the whole chord in pixels.
+        let chord = |row: i64| {
+            let d = 2 * row + 1 - size;
+            let mut half = 0;
+            while (half + 1) * (half + 1) + d * d <= size * size {
+                half += 1;
+            }
+            half
+        };
+        for row in 0..size {
+            let width = chord(row).max(1);
+            let left = x + (size - width) / 2;
+            let edge = row == 0 || row == size - 1;
+            if shape == BulletShape::Disc || edge {
+                self.fill_content_rect(color, left, y + row, width, 1)?;
+            } else {
+                self.fill_content_rect(color, left, y + row, 1, 1)?;
+                self.fill_content_rect(color, left + width - 1, y + row, 1, 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws `image` scaled to the rectangle, taking the nearest pixel of the image for each
+    /// pixel. Pixels that are more than half transparent aren't drawn, and each run of pixels of
+    /// the same color in a row is filled at once. Only the part in the clip is drawn.
+    fn draw_image(
+        &mut self,
+        image: &Image,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    ) -> Result<(), Error> {
+        let (x0, y0) = (layout_point.x(), layout_point.y());
+        let (w, h) = (layout_size.width(), layout_size.height());
+        if w <= 0 || h <= 0 || image.width() <= 0 || image.height() <= 0 {
+            return Ok(());
+        }
+        // The pixels outside of the clip are skipped, unless the image is transformed and the
+        // clip is in other coordinates.
+        let (cx, cy, cw, ch) = if self.transform().is_identity() {
+            self.clip_rect(x0, y0, w, h)
+        } else {
+            (x0, y0, w, h)
+        };
+        let source_x = |x: i64| (x - x0) * image.width() / w;
+        for y in cy..cy + ch {
+            let source_y = (y - y0) * image.height() / h;
+            let mut x = cx;
+            while x < cx + cw {
+                let pixel = image.pixel(source_x(x), source_y);
+                let mut end = x + 1;
+                while end < cx + cw && image.pixel(source_x(end), source_y) == pixel {
+                    end += 1;
+                }
+                if pixel >> 24 >= 0x80 {
+                    self.fill_content_rect(pixel & 0xffffff, x, y, end - x, 1)?;
+                }
+                x = end;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws `image` at its size repeatedly over the rectangle, with a tile at `origin`.
+    fn draw_background_image(
+        &mut self,
+        image: &Image,
+        origin: LayoutPoint,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    ) -> Result<(), Error> {
+        let (iw, ih) = (image.width(), image.height());
+        if iw <= 0 || ih <= 0 {
+            return Ok(());
+        }
+        let (x, y, width, height) = self.to_content_rect(
+            layout_point.x(),
+            layout_point.y(),
+            layout_size.width(),
+            layout_size.height(),
+        );
+        let clip = self.clip_rect(x, y, width, height);
+        self.clips.push(clip);
+        // The first tile is the one that covers the top-left corner of the rectangle.
+        let start_x = origin.x() - (origin.x() - layout_point.x() + iw - 1).div_euclid(iw) * iw;
+        let start_y = origin.y() - (origin.y() - layout_point.y() + ih - 1).div_euclid(ih) * ih;
+        let mut result = Ok(());
+        let mut y = start_y;
+        while y < layout_point.y() + layout_size.height() && result.is_ok() {
+            let mut x = start_x;
+            while x < layout_point.x() + layout_size.width() && result.is_ok() {
+                result = self.draw_image(image, LayoutPoint::new(x, y), LayoutSize::new(iw, ih));
+                x += iw;
+            }
+            y += ih;
+        }
+        self.clips.pop();
+        result
+    }
+
     fn setup_toolbar(&mut self) -> OsResult<()> {
         self.window
             .fill_rect(LIGHT_GRAY, 0, 0, WINDOW_WIDTH, TOOLBAR_HEIGHT)?;
@@ -382,10 +944,12 @@ impl WasabiUI {
     }
 }
 
-fn convert_font_size(size: FontSize) -> StringSize {
+/// Picks the largest glyph size of the built-in font that fits in `size` px. The glyphs are 16px,
+/// 32px and 48px high.
+fn convert_font_size(size: i64) -> StringSize {
     match size {
-        FontSize::Medium => StringSize::Medium,
-        FontSize::XLarge => StringSize::Large,
-        FontSize::XXLarge => StringSize::XLarge,
+        s if s < 32 => StringSize::Medium,
+        s if s < 48 => StringSize::Large,
+        _ => StringSize::XLarge,
     }
 }
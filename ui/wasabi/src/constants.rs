@@ -0,0 +1,20 @@
+pub static WINDOW_WIDTH: i64 = 600;
+pub static WINDOW_HEIGHT: i64 = 400;
+pub static WINDOW_PADDING: i64 = 5;
+pub static WINDOW_INIT_X_POS: i64 = 30;
+pub static WINDOW_INIT_Y_POS: i64 = 50;
+
+pub static TITLE_BAR_HEIGHT: i64 = 24;
+pub static TOOLBAR_HEIGHT: i64 = 26;
+
+pub static CONTENT_AREA_WIDTH: i64 = WINDOW_WIDTH - WINDOW_PADDING * 2;
+pub static CONTENT_AREA_HEIGHT: i64 =
+    WINDOW_HEIGHT - WINDOW_PADDING * 2 - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT;
+
+pub static WHITE: u32 = 0xFFFFFF;
+pub static BLACK: u32 = 0x000000;
+pub static GRAY: u32 = 0x808080;
+pub static DARK_GRAY: u32 = 0x404040;
+pub static LIGHT_GRAY: u32 = 0xC0C0C0;
+
+pub static ADDRESS_BAR_HEIGHT: i64 = 24;
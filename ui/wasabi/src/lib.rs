@@ -3,4 +3,5 @@
 extern crate alloc;
 
 pub mod app;
+mod constants;
 mod cursor;